@@ -3,15 +3,47 @@
 //! 本文件包含了 LRA 计算器各个组件的性能基准测试，
 //! 用于监控和优化程序的性能表现。
 
-use criterion::{black_box, criterion_group, criterion_main, Criterion, BenchmarkId};
+use criterion::{black_box, criterion_group, criterion_main, BatchSize, Criterion, BenchmarkId, Throughput};
+use std::alloc::{GlobalAlloc, Layout, System};
+use std::collections::HashMap;
 use std::fs::File;
+use std::hash::Hash;
+use std::io::Write;
 use std::path::Path;
+use std::sync::atomic::{AtomicUsize, Ordering};
 use tempfile::TempDir;
 
-use lra_calculator_rust::audio::{scan_audio_files, extract_file_extension, is_supported_audio_format};
-use lra_calculator_rust::processor::{analyze_results, ProcessingStats};
-use lra_calculator_rust::utils::{parse_result_line, sort_entries_by_lra};
+use lra_calculator_rust::audio::{
+    scan_audio_files, scan_audio_files_with_options, extract_file_extension, is_supported_audio_format,
+    ScanOptions,
+};
+use lra_calculator_rust::processor::{
+    analyze_results, process_files_parallel_with_options, FileMeasurement, ProcessingStats, ResultOrder, RunOptions,
+};
+use lra_calculator_rust::utils::{parse_result_line, read_and_parse_results_file, sort_entries_by_lra, sort_lra_results_file, RESULTS_HEADER_LINE};
+use lra_calculator_rust::utils::stream::{StreamingConfig, StreamingResultsWriter};
 use lra_calculator_rust::error::{ProcessFileError, FileErrorType};
+use lra_calculator_rust::hash::{hash_file, HashAlgorithm};
+use lra_calculator_rust::{CalculationOptions, FileKey, RunEvent};
+
+/// 统计累计分配字节数的分配器，用于内存相关的基准测试
+struct CountingAllocator;
+
+static ALLOCATED_BYTES: AtomicUsize = AtomicUsize::new(0);
+
+unsafe impl GlobalAlloc for CountingAllocator {
+    unsafe fn alloc(&self, layout: Layout) -> *mut u8 {
+        ALLOCATED_BYTES.fetch_add(layout.size(), Ordering::Relaxed);
+        System.alloc(layout)
+    }
+
+    unsafe fn dealloc(&self, ptr: *mut u8, layout: Layout) {
+        System.dealloc(ptr, layout)
+    }
+}
+
+#[global_allocator]
+static GLOBAL: CountingAllocator = CountingAllocator;
 
 /// 基准测试：文件扫描性能
 /// 
@@ -47,6 +79,42 @@ fn benchmark_file_scanning(c: &mut Criterion) {
     group.finish();
 }
 
+/// 基准测试：目录剪枝对扫描性能的影响
+/// 
+/// 合成目录树中包含一个很大的 `node_modules` 子树，对比剪枝与不剪枝的遍历耗时。
+fn benchmark_pruned_scanning(c: &mut Criterion) {
+    let mut group = c.benchmark_group("pruned_scanning");
+    
+    // 设置测试环境：100 个音频文件 + 200 个目录共 4000 个无关文件
+    let temp_dir = TempDir::new().expect("无法创建临时目录");
+    let temp_path = temp_dir.path();
+    for i in 0..100 {
+        File::create(temp_path.join(format!("test_{:04}.mp3", i))).expect("无法创建测试文件");
+    }
+    for i in 0..200 {
+        let package_dir = temp_path.join("node_modules").join(format!("pkg_{:03}", i));
+        std::fs::create_dir_all(&package_dir).expect("无法创建测试目录");
+        for j in 0..20 {
+            File::create(package_dir.join(format!("module_{:02}.js", j))).expect("无法创建测试文件");
+        }
+    }
+    
+    let unpruned = ScanOptions::default();
+    let mut pruned = ScanOptions::default();
+    pruned.exclude.add_default_pruned_dirs();
+    
+    for (name, options) in [("without_pruning", &unpruned), ("with_pruning", &pruned)] {
+        group.bench_function(name, |b| {
+            b.iter(|| {
+                let report = scan_audio_files_with_options(black_box(temp_path), options);
+                black_box(report)
+            });
+        });
+    }
+    
+    group.finish();
+}
+
 /// 基准测试：文件扩展名提取性能
 /// 
 /// 测试文件扩展名提取函数的性能。
@@ -112,7 +180,7 @@ fn benchmark_result_analysis(c: &mut Criterion) {
                             FileErrorType::Other,
                         )));
                     } else {
-                        test_results.push(Ok((
+                        test_results.push(Ok(FileMeasurement::new(
                             format!("file_{}.mp3", i),
                             (i as f64) * 0.1 + 5.0, // 模拟 LRA 值
                         )));
@@ -147,7 +215,23 @@ fn benchmark_result_line_parsing(c: &mut Criterion) {
         b.iter(|| {
             for line in &test_lines {
                 let result = parse_result_line(black_box(line));
-                black_box(result);
+                let _ = black_box(result);
+            }
+        });
+    });
+
+    // 三种错误各一行，衡量错误路径的开销
+    let invalid_lines = vec![
+        "notes without separator",
+        "album/track.flac - n/a",
+        "album/track.flac - -3.0",
+    ];
+
+    c.bench_function("parse_result_line_invalid", |b| {
+        b.iter(|| {
+            for line in &invalid_lines {
+                let result = parse_result_line(black_box(line));
+                let _ = black_box(result);
             }
         });
     });
@@ -155,11 +239,15 @@ fn benchmark_result_line_parsing(c: &mut Criterion) {
 
 /// 基准测试：条目排序性能
 /// 
-/// 测试结果条目排序功能在不同数据量下的性能。
+/// 测试结果条目排序功能在不同数据量下的性能，
+/// 10 万和 100 万条目用于观察超过并行排序阈值后的表现。
 fn benchmark_entry_sorting(c: &mut Criterion) {
     let mut group = c.benchmark_group("entry_sorting");
     
-    for entry_count in [100, 500, 1000, 5000, 10000].iter() {
+    for entry_count in [100, 500, 1000, 5000, 10000, 100_000, 1_000_000].iter() {
+        if *entry_count >= 100_000 {
+            group.sample_size(20);
+        }
         group.bench_with_input(
             BenchmarkId::new("sort_entries_by_lra", entry_count),
             entry_count,
@@ -168,7 +256,7 @@ fn benchmark_entry_sorting(c: &mut Criterion) {
                 let mut entries = Vec::new();
                 for i in 0..entry_count {
                     let lra = (i as f64 * 7.0) % 25.0; // 生成 0-25 范围的 LRA 值
-                    entries.push((format!("file_{:04}.mp3", i), lra));
+                    entries.push((format!("file_{:04}.mp3", i).into(), lra));
                 }
                 
                 // 基准测试
@@ -183,12 +271,195 @@ fn benchmark_entry_sorting(c: &mut Criterion) {
     group.finish();
 }
 
+/// 基准测试：结果文件读取性能
+/// 
+/// 测试 `read_and_parse_results_file` 读取 10 万和 100 万行结果文件的耗时。
+fn benchmark_results_file_reading(c: &mut Criterion) {
+    let mut group = c.benchmark_group("results_file_reading");
+    group.sample_size(20);
+    let temp_dir = TempDir::new().expect("无法创建临时目录");
+
+    for entry_count in [100_000, 1_000_000].iter() {
+        let file_path = temp_dir.path().join(format!("lra_results_{}.txt", entry_count));
+        let mut writer = std::io::BufWriter::new(File::create(&file_path).expect("无法创建测试文件"));
+        writeln!(writer, "{}", RESULTS_HEADER_LINE).unwrap();
+        for i in 0..*entry_count {
+            writeln!(writer, "Artist_{:03}/Album_{:02}/{:06}.flac - {:.1}", i / 1000, i % 50, i, (i as f64 * 7.0) % 25.0).unwrap();
+        }
+        writer.flush().unwrap();
+        drop(writer);
+
+        group.bench_with_input(BenchmarkId::new("read_and_parse_results_file", entry_count), &file_path, |b, file_path| {
+            b.iter(|| black_box(read_and_parse_results_file(file_path).unwrap()));
+        });
+    }
+
+    group.finish();
+}
+
+/// 基准测试：结果排序方式
+///
+/// 比较 10 万个条目的两种得到有序结果文件的方式：
+/// - `end_of_run_sort`：按完成顺序追加写入，结束时 `sort_lra_results_file` 读回、排序并重写
+/// - `maintained_order`：`--stream-sorted`，条目保存在有序集合中，文件随条目增多退避地整体重写
+fn benchmark_results_ordering(c: &mut Criterion) {
+    let mut group = c.benchmark_group("results_ordering");
+    group.sample_size(10);
+    let temp_dir = TempDir::new().expect("无法创建临时目录");
+    let entry_count = 100_000;
+    let entries: Vec<(FileKey, f64)> = (0..entry_count)
+        .map(|i| (format!("Artist_{:03}/Album_{:02}/{:06}.flac", i / 1000, i % 50, i).into(), (i as f64 * 7.0) % 25.0))
+        .collect();
+    let config = StreamingConfig::default();
+
+    group.bench_with_input(BenchmarkId::new("end_of_run_sort", entry_count), &entries, |b, entries| {
+        let path = temp_dir.path().join("appended.txt");
+        b.iter(|| {
+            let writer = StreamingResultsWriter::create(&path, RESULTS_HEADER_LINE, config).unwrap();
+            for (path, lra) in entries {
+                writer.append(path, *lra).unwrap();
+            }
+            drop(writer);
+            sort_lra_results_file(&path, RESULTS_HEADER_LINE).unwrap();
+        });
+    });
+    group.bench_with_input(BenchmarkId::new("maintained_order", entry_count), &entries, |b, entries| {
+        let path = temp_dir.path().join("maintained.txt");
+        let sorted = StreamingConfig { sorted: true, ..config };
+        b.iter(|| {
+            let writer = StreamingResultsWriter::create(&path, RESULTS_HEADER_LINE, sorted).unwrap();
+            for (path, lra) in entries {
+                writer.append(path, *lra).unwrap();
+            }
+            drop(writer);
+        });
+    });
+
+    group.finish();
+}
+
+/// 基准测试：内容哈希吞吐量
+///
+/// 测试 `--hash` 对 64 MiB 文件（约一张 CD 音轨的 FLAC）计算 xxh3 和 sha1 的耗时。
+/// 哈希与 FFmpeg 同时进行，只要吞吐量远高于 FFmpeg 解码的速度，就不会延长总耗时；
+/// 文件第二次读取时已在页缓存中，这里测的是哈希本身而不是磁盘。
+fn benchmark_content_hashing(c: &mut Criterion) {
+    const FILE_SIZE: usize = 64 * 1024 * 1024;
+
+    let mut group = c.benchmark_group("content_hashing");
+    group.sample_size(10);
+    group.throughput(Throughput::Bytes(FILE_SIZE as u64));
+    let temp_dir = TempDir::new().expect("无法创建临时目录");
+    let file_path = temp_dir.path().join("track.flac");
+    let content: Vec<u8> = (0..FILE_SIZE).map(|i| (i.wrapping_mul(2_654_435_761) >> 13) as u8).collect();
+    std::fs::write(&file_path, content).expect("无法创建测试文件");
+
+    for algorithm in [HashAlgorithm::Xxh3, HashAlgorithm::Sha1] {
+        group.bench_with_input(BenchmarkId::new("hash_file", algorithm), &algorithm, |b, algorithm| {
+            b.iter(|| black_box(hash_file(&file_path, *algorithm).unwrap()));
+        });
+    }
+
+    group.finish();
+}
+
+/// 模拟 FFmpeg 的脚本：忽略输入，立即输出一份固定的 ebur128 汇总后退出
+#[cfg(unix)]
+const FAKE_FFMPEG_SCRIPT: &str = r#"#!/bin/sh
+printf '%s\n' \
+  '[Parsed_ebur128_0 @ 0x1] Summary:' \
+  '' \
+  '  Integrated loudness:' \
+  '    I:         -14.2 LUFS' \
+  '    Threshold: -24.5 LUFS' \
+  '' \
+  '  Loudness range:' \
+  '    LRA:         7.3 LU' \
+  '    Threshold: -34.6 LUFS' \
+  '    LRA low:   -19.8 LUFS' \
+  '    LRA high:  -12.5 LUFS' >&2
+"#;
+
+/// 基准测试：调度开销 (Orchestration Overhead)
+///
+/// 用一个立即输出固定汇总的脚本代替 FFmpeg，测量并行处理中与音频解码无关的部分：
+/// 构建参数、启动子进程、收集并解析 stderr、汇总结果。脚本放在临时目录中并加到 `PATH` 最前面，
+/// 与命令行程序查找 FFmpeg 的方式相同。进度通过空的事件接收者丢弃，避免控制台输出影响计时。
+/// 只在 Unix 上运行（脚本需要 `/bin/sh`）。
+#[cfg(unix)]
+fn benchmark_orchestration(c: &mut Criterion) {
+    use std::os::unix::fs::PermissionsExt;
+
+    let bin_dir = TempDir::new().expect("无法创建临时目录");
+    let fake_ffmpeg = bin_dir.path().join("ffmpeg");
+    std::fs::write(&fake_ffmpeg, FAKE_FFMPEG_SCRIPT).expect("无法写入模拟 FFmpeg");
+    std::fs::set_permissions(&fake_ffmpeg, std::fs::Permissions::from_mode(0o755)).expect("无法设置执行权限");
+    let original_path = std::env::var_os("PATH").unwrap_or_default();
+    let search_path = std::env::join_paths(
+        std::iter::once(bin_dir.path().to_path_buf()).chain(std::env::split_paths(&original_path)),
+    )
+    .expect("无法构建 PATH");
+    std::env::set_var("PATH", &search_path);
+
+    // 文件只需要存在：分析前会检查文件是否已消失
+    let music_dir = TempDir::new().expect("无法创建临时目录");
+    let files: Vec<(std::path::PathBuf, String)> = (0..10_000)
+        .map(|i| {
+            let name = format!("track_{:05}.flac", i);
+            let path = music_dir.path().join(&name);
+            File::create(&path).expect("无法创建测试文件");
+            (path, name)
+        })
+        .collect();
+    let options = CalculationOptions::default();
+    let silent = |_: &RunEvent| {};
+    let run = RunOptions::default();
+
+    let mut group = c.benchmark_group("orchestration");
+    group.sample_size(10);
+    for file_count in [100, 1_000, 10_000] {
+        group.throughput(Throughput::Elements(file_count as u64));
+        for threads in [1, 4, 8] {
+            let pool = rayon::ThreadPoolBuilder::new().num_threads(threads).build().expect("无法创建线程池");
+            let batch = &files[..file_count];
+            // 确认模拟的 FFmpeg 可用，否则测的是失败路径
+            let probe =
+                process_files_parallel_with_options(batch[..1].to_vec(), ResultOrder::InputOrder, &options, &run, &silent);
+            assert!(probe[0].is_success(), "模拟 FFmpeg 分析失败: {:?}", probe[0]);
+
+            group.bench_with_input(
+                BenchmarkId::new(format!("process_files_parallel/{}_threads", threads), file_count),
+                &batch,
+                |b, batch| {
+                    b.iter_batched(
+                        || batch.to_vec(),
+                        |batch| {
+                            let results = pool.install(|| {
+                                process_files_parallel_with_options(batch, ResultOrder::InputOrder, &options, &run, &silent)
+                            });
+                            black_box(analyze_results(results))
+                        },
+                        BatchSize::LargeInput,
+                    );
+                },
+            );
+        }
+    }
+    group.finish();
+
+    std::env::set_var("PATH", original_path);
+}
+
+/// 非 Unix 平台没有 `/bin/sh`，不运行调度开销测试
+#[cfg(not(unix))]
+fn benchmark_orchestration(_c: &mut Criterion) {}
+
 /// 基准测试：ProcessingStats 创建和方法调用
 /// 
 /// 测试 ProcessingStats 结构体的性能。
 fn benchmark_processing_stats(c: &mut Criterion) {
-    let error_messages: Vec<String> = (0..1000)
-        .map(|i| format!("错误信息 {}", i))
+    let errors: Vec<ProcessFileError> = (0..1000)
+        .map(|i| ProcessFileError::new(format!("file_{}.mp3", i), format!("错误信息 {}", i), FileErrorType::Other))
         .collect();
     
     c.bench_function("processing_stats_creation", |b| {
@@ -196,13 +467,13 @@ fn benchmark_processing_stats(c: &mut Criterion) {
             let stats = ProcessingStats::new(
                 black_box(800),
                 black_box(200),
-                black_box(error_messages.clone()),
+                black_box(errors.clone()),
             );
             black_box(stats)
         });
     });
     
-    let stats = ProcessingStats::new(800, 200, error_messages);
+    let stats = ProcessingStats::new(800, 200, errors);
     
     c.bench_function("processing_stats_methods", |b| {
         b.iter(|| {
@@ -218,53 +489,117 @@ fn benchmark_processing_stats(c: &mut Criterion) {
 /// 
 /// 测试程序在处理大量数据时的内存分配模式。
 fn benchmark_memory_usage(c: &mut Criterion) {
-    c.bench_function("large_data_processing", |b| {
-        b.iter(|| {
-            // 模拟处理大量文件的内存使用模式
-            let file_count = 10000;
-            let mut file_paths = Vec::with_capacity(file_count);
-            
-            // 创建文件路径列表
-            for i in 0..file_count {
-                file_paths.push((
-                    std::path::PathBuf::from(format!("/path/to/file_{:05}.mp3", i)),
-                    format!("file_{:05}.mp3", i),
-                ));
-            }
-            
-            // 模拟处理结果
-            let mut results = Vec::with_capacity(file_count);
-            for (_, display_path) in &file_paths {
-                if display_path.ends_with("0.mp3") {
+    // 模拟处理大量文件的内存使用模式；只计时结果分析，构造输入的开销不计入
+    let file_count = 10000;
+    let build_results = || {
+        (0..file_count)
+            .map(|i| {
+                let display_path = format!("file_{:05}.mp3", i);
+                if i % 10 == 0 {
                     // 模拟 10% 的失败率
-                    results.push(Err(ProcessFileError::new(
-                        display_path.clone(),
-                        "模拟错误".to_string(),
-                        FileErrorType::Other,
-                    )));
+                    Err(ProcessFileError::new(display_path, "模拟错误".to_string(), FileErrorType::Other))
                 } else {
-                    results.push(Ok((display_path.clone(), 12.5)));
+                    Ok(FileMeasurement::new(display_path, 12.5))
                 }
-            }
-            
-            // 分析结果
-            let (stats, successful) = analyze_results(black_box(results));
-            black_box((stats, successful, file_paths))
-        });
+            })
+            .collect::<Vec<_>>()
+    };
+
+    c.bench_function("large_data_processing", |b| {
+        b.iter_batched(
+            build_results,
+            |results| {
+                // 分析结果
+                let (stats, successful) = analyze_results(black_box(results));
+                black_box((stats, successful))
+            },
+            BatchSize::LargeInput,
+        );
     });
 }
 
+/// 各阶段持有的路径：(处理结果, 缓存指纹, 排序后的条目, 阈值违规)
+type HandOff<K> = (Vec<(K, f64)>, HashMap<K, usize>, Vec<(K, f64)>, Vec<K>);
+
+/// 模拟一个路径从处理结果依次交给缓存指纹、排序和阈值检查的过程
+///
+/// 每个阶段都持有自己的路径副本，全部副本同时存活，与主流程写出结果前的状态一致。
+fn hand_off_paths<K: Clone + Eq + Hash + Ord>(paths: &[K]) -> HandOff<K> {
+    let results: Vec<(K, f64)> = paths
+        .iter()
+        .enumerate()
+        .map(|(i, path)| (path.clone(), (i % 250) as f64 * 0.1))
+        .collect();
+    let fingerprints: HashMap<K, usize> = results
+        .iter()
+        .enumerate()
+        .map(|(i, (path, _))| (path.clone(), i))
+        .collect();
+    let mut sorted = results.clone();
+    sorted.sort_by(|a, b| b.1.total_cmp(&a.1).then_with(|| a.0.cmp(&b.0)));
+    let violations: Vec<K> = sorted
+        .iter()
+        .filter(|(_, lra)| *lra > 20.0)
+        .map(|(path, _)| path.clone())
+        .collect();
+    (results, fingerprints, sorted, violations)
+}
+
+/// 基准测试：在结果、缓存和输出之间传递路径的开销
+/// 
+/// 对比 10 万个以上条目时 `String` 逐阶段复制与 `FileKey` 共享的耗时，
+/// 并输出每轮的累计分配字节数。
+fn benchmark_path_sharing(c: &mut Criterion) {
+    let mut group = c.benchmark_group("path_sharing");
+    group.sample_size(20);
+
+    for entry_count in [100_000, 200_000].iter() {
+        let strings: Vec<String> = (0..*entry_count)
+            .map(|i| format!("Artist_{:03}/Album_{:02}/{:02} - Track Title.flac", i / 1000, i / 20 % 50, i % 20))
+            .collect();
+        let keys: Vec<FileKey> = strings.iter().map(FileKey::from).collect();
+
+        let before = ALLOCATED_BYTES.load(Ordering::Relaxed);
+        black_box(hand_off_paths(&strings));
+        let string_bytes = ALLOCATED_BYTES.load(Ordering::Relaxed) - before;
+        let before = ALLOCATED_BYTES.load(Ordering::Relaxed);
+        black_box(hand_off_paths(&keys));
+        let key_bytes = ALLOCATED_BYTES.load(Ordering::Relaxed) - before;
+        println!(
+            "path_sharing/{}: String 每轮分配 {} KiB，FileKey 每轮分配 {} KiB",
+            entry_count,
+            string_bytes / 1024,
+            key_bytes / 1024
+        );
+
+        group.bench_with_input(BenchmarkId::new("string", entry_count), &strings, |b, strings| {
+            b.iter(|| black_box(hand_off_paths(strings)));
+        });
+        group.bench_with_input(BenchmarkId::new("file_key", entry_count), &keys, |b, keys| {
+            b.iter(|| black_box(hand_off_paths(keys)));
+        });
+    }
+
+    group.finish();
+}
+
 // 定义基准测试组
 criterion_group!(
     benches,
     benchmark_file_scanning,
+    benchmark_pruned_scanning,
     benchmark_file_extension_extraction,
     benchmark_format_support_check,
     benchmark_result_analysis,
     benchmark_result_line_parsing,
     benchmark_entry_sorting,
+    benchmark_results_file_reading,
+    benchmark_results_ordering,
+    benchmark_content_hashing,
+    benchmark_orchestration,
     benchmark_processing_stats,
-    benchmark_memory_usage
+    benchmark_memory_usage,
+    benchmark_path_sharing
 );
 
 criterion_main!(benches);
@@ -0,0 +1,189 @@
+//! 提前中止模块 (Early Abort Module)
+//!
+//! FFmpeg 配置错误（缺少解码器、被替换成了别的程序等）时每个文件都会失败。对一个 20 万文件的音乐库，
+//! 逐个分析完再报告毫无意义。[`EarlyAbort`] 观察最先完成的 [`EarlyAbort::threshold`] 个文件：
+//! 如果它们全部以同一种错误类型失败，判定为环境问题并中止，剩下的文件以
+//! [`SkipReason::Aborted`](crate::processor::SkipReason::Aborted) 跳过，命令行程序以
+//! `AppError::Ffmpeg` 退出。只要其中有一个文件成功或以不同的类型失败，本次运行就不再中止。
+//!
+//! 跳过的文件（云端占位文件等）不计入观察的文件；启用断路器时，断连类失败交给断路器处理，也不计入。
+//!
+//! 见 [`crate::processor::RunOptions::early_abort`]。
+
+use std::fmt;
+use std::sync::{Arc, Mutex, MutexGuard};
+
+use crate::error::{FileErrorType, ProcessFileError};
+
+/// 默认观察的文件数量（`--abort-after`）
+pub const DEFAULT_ABORT_AFTER: usize = 20;
+
+/// 中止的原因 (Abort Reason)
+#[derive(Debug, Clone, PartialEq)]
+pub struct AbortReason {
+    /// 全部失败的文件数量
+    pub failures: usize,
+    /// 这些文件共同的错误类型
+    pub error_type: FileErrorType,
+    /// 第一个失败的文件的错误
+    pub first_error: ProcessFileError,
+}
+
+impl fmt::Display for AbortReason {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "最先完成的 {} 个文件都以「{}」失败，很可能是环境问题（如 FFmpeg 配置错误）而不是文件本身；第一个错误: {}",
+            self.failures,
+            self.first_error.error_type_description(),
+            self.first_error
+        )
+    }
+}
+
+/// 提前中止判断 (Early Abort)
+///
+/// 克隆得到的实例共享同一个状态。工作线程在每个文件完成后调用 [`record`](Self::record)，
+/// 开始新的文件之前检查 [`is_tripped`](Self::is_tripped)。
+///
+/// # 示例
+/// ```
+/// use lra_calculator_rust::abort::EarlyAbort;
+/// use lra_calculator_rust::error::{FileErrorType, ProcessFileError};
+///
+/// let abort = EarlyAbort::new(3);
+/// for index in 0..3 {
+///     let error = ProcessFileError::new(format!("{}.flac", index), "无法启动".to_string(), FileErrorType::FfmpegExecution);
+///     abort.record(Err(&error));
+/// }
+/// assert!(abort.is_tripped());
+/// assert_eq!(abort.reason().unwrap().failures, 3);
+/// ```
+#[derive(Debug, Clone)]
+pub struct EarlyAbort {
+    threshold: usize,
+    state: Arc<Mutex<AbortState>>,
+}
+
+/// 提前中止的内部状态
+#[derive(Debug, Default)]
+struct AbortState {
+    /// 已观察的失败数量
+    failures: usize,
+    /// 第一个失败的文件的错误
+    first_error: Option<ProcessFileError>,
+    /// 出现了成功或不同类型的失败，不再中止
+    disarmed: bool,
+    /// 已经中止
+    tripped: bool,
+}
+
+impl EarlyAbort {
+    /// 创建观察最先完成的 `threshold` 个文件的判断；`threshold` 为 0 时从不中止
+    pub fn new(threshold: usize) -> Self {
+        Self { threshold, state: Arc::new(Mutex::new(AbortState { disarmed: threshold == 0, ..AbortState::default() })) }
+    }
+
+    /// 观察的文件数量
+    pub fn threshold(&self) -> usize {
+        self.threshold
+    }
+
+    /// 记录一个完成的文件 (Record)
+    ///
+    /// # 参数
+    /// - `result` - `Ok(())` 表示成功，`Err` 为失败的错误
+    ///
+    /// # 返回值
+    /// - `true` - 这个文件触发了中止
+    pub fn record(&self, result: Result<(), &ProcessFileError>) -> bool {
+        let mut state = self.lock();
+        if state.disarmed || state.tripped {
+            return false;
+        }
+        let error = match result {
+            Ok(()) => {
+                state.disarmed = true;
+                return false;
+            }
+            Err(error) => error,
+        };
+        if state.first_error.as_ref().is_some_and(|first| first.error_type != error.error_type) {
+            state.disarmed = true;
+            return false;
+        }
+        state.first_error.get_or_insert_with(|| error.clone());
+        state.failures += 1;
+        state.tripped = state.failures >= self.threshold;
+        state.tripped
+    }
+
+    /// 是否已经中止
+    pub fn is_tripped(&self) -> bool {
+        self.lock().tripped
+    }
+
+    /// 中止的原因；没有中止时为 `None`
+    pub fn reason(&self) -> Option<AbortReason> {
+        let state = self.lock();
+        let first_error = state.first_error.clone().filter(|_| state.tripped)?;
+        Some(AbortReason { failures: state.failures, error_type: first_error.error_type, first_error })
+    }
+
+    fn lock(&self) -> MutexGuard<'_, AbortState> {
+        self.state.lock().unwrap_or_else(|poisoned| poisoned.into_inner())
+    }
+}
+
+/// 同一个判断的克隆视为相等
+impl PartialEq for EarlyAbort {
+    fn eq(&self, other: &Self) -> bool {
+        Arc::ptr_eq(&self.state, &other.state)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn failure(path: &str, error_type: FileErrorType) -> ProcessFileError {
+        ProcessFileError::new(path, "失败".to_string(), error_type)
+    }
+
+    /// 测试前几个文件全部以同一类型失败时中止，之后的记录不再改变结果
+    #[test]
+    fn test_trips_on_uniform_failures() {
+        let abort = EarlyAbort::new(3);
+        let error = failure("a.flac", FileErrorType::FfmpegExecution);
+        assert!(!abort.record(Err(&error)));
+        assert!(!abort.record(Err(&failure("b.flac", FileErrorType::FfmpegExecution))));
+        assert!(abort.record(Err(&failure("c.flac", FileErrorType::FfmpegExecution))));
+        assert!(!abort.record(Ok(())));
+
+        let reason = abort.reason().unwrap();
+        assert_eq!(reason, AbortReason { failures: 3, error_type: FileErrorType::FfmpegExecution, first_error: error });
+        assert!(reason.to_string().starts_with("最先完成的 3 个文件都以「FFmpeg 执行失败」失败"));
+    }
+
+    /// 测试成功或不同类型的失败之后不再中止
+    #[test]
+    fn test_disarmed_by_success_or_mixed_types() {
+        let mixed = EarlyAbort::new(2);
+        mixed.record(Err(&failure("a.flac", FileErrorType::FfmpegExecution)));
+        mixed.record(Err(&failure("b.flac", FileErrorType::FileAccess)));
+        mixed.record(Err(&failure("c.flac", FileErrorType::FileAccess)));
+        assert!(!mixed.is_tripped());
+
+        let success = EarlyAbort::new(2);
+        success.record(Ok(()));
+        for index in 0..5 {
+            success.record(Err(&failure(&format!("{}.flac", index), FileErrorType::FfmpegExecution)));
+        }
+        assert!(!success.is_tripped());
+        assert_eq!(success.reason(), None);
+
+        let disabled = EarlyAbort::new(0);
+        assert!(!disabled.record(Err(&failure("a.flac", FileErrorType::Other))));
+        assert!(!disabled.is_tripped());
+    }
+}
@@ -0,0 +1,116 @@
+//! 专辑模块 (Album Module)
+//!
+//! 同一目录下的音轨视为一张专辑。`--album-lra` 对每个至少有 [`MIN_ALBUM_TRACKS`] 首
+//! 成功音轨的目录额外做一次合并分析（见 [`crate::audio::measure_album_loudness`]），
+//! 结果以 `ALBUM:<目录>` 条目写入结果文件，与音轨条目区分开：
+//!
+//! ```text
+//! ALBUM:Artist/Album - 14.2
+//! Artist/Album/01.flac - 9.8
+//! ```
+//!
+//! 专辑条目不参与音轨级的统计和阈值检查。
+
+use std::collections::BTreeMap;
+
+use crate::file_key::FileKey;
+
+/// 结果文件中专辑条目的路径前缀
+pub const ALBUM_ENTRY_PREFIX: &str = "ALBUM:";
+
+/// 做合并分析所需的最少音轨数
+pub const MIN_ALBUM_TRACKS: usize = 2;
+
+/// 扫描根目录本身作为专辑时使用的目录名
+const ROOT_DIRECTORY_NAME: &str = ".";
+
+/// 音轨所属的专辑（显示路径的父目录，根目录下的文件为空字符串）
+pub fn album_key(display_path: &str) -> &str {
+    display_path
+        .rsplit_once(['/', '\\'])
+        .map_or("", |(directory, _)| directory)
+}
+
+/// 专辑条目的显示路径 (Album Entry Key)
+///
+/// # 示例
+/// ```
+/// use lra_calculator_rust::album::{album_directory, album_entry_key};
+///
+/// assert_eq!(album_entry_key("Artist/Album"), "ALBUM:Artist/Album");
+/// assert_eq!(album_entry_key(""), "ALBUM:.");
+/// assert_eq!(album_directory("ALBUM:."), Some(""));
+/// assert_eq!(album_directory("Artist/Album/01.flac"), None);
+/// ```
+pub fn album_entry_key(directory: &str) -> FileKey {
+    let directory = if directory.is_empty() { ROOT_DIRECTORY_NAME } else { directory };
+    FileKey::from(format!("{}{}", ALBUM_ENTRY_PREFIX, directory))
+}
+
+/// 专辑条目对应的目录（相对于扫描根目录，根目录为空字符串）
+///
+/// # 返回值
+/// - `Some(目录)` - 是专辑条目
+/// - `None` - 是音轨条目
+pub fn album_directory(display_path: &str) -> Option<&str> {
+    display_path
+        .strip_prefix(ALBUM_ENTRY_PREFIX)
+        .map(|directory| if directory == ROOT_DIRECTORY_NAME { "" } else { directory })
+}
+
+/// 是否为专辑条目
+pub fn is_album_entry(display_path: &str) -> bool {
+    display_path.starts_with(ALBUM_ENTRY_PREFIX)
+}
+
+/// 按目录把音轨分组为专辑 (Group Albums)
+///
+/// 专辑条目本身会被忽略；音轨少于 [`MIN_ALBUM_TRACKS`] 首的目录不算专辑。
+/// 每张专辑的音轨按显示路径排序，即合并分析时的播放顺序。
+///
+/// # 参数
+/// - `track_paths` - 成功分析的音轨显示路径
+///
+/// # 返回值
+/// - 目录到音轨列表的映射
+pub fn group_albums<'a, I>(track_paths: I) -> BTreeMap<&'a str, Vec<&'a str>>
+where
+    I: IntoIterator<Item = &'a str>,
+{
+    let mut albums: BTreeMap<&str, Vec<&str>> = BTreeMap::new();
+    for path in track_paths.into_iter().filter(|path| !is_album_entry(path)) {
+        albums.entry(album_key(path)).or_default().push(path);
+    }
+    albums.retain(|_, tracks| tracks.len() >= MIN_ALBUM_TRACKS);
+    for tracks in albums.values_mut() {
+        tracks.sort_unstable();
+    }
+    albums
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// 测试按目录分组专辑
+    #[test]
+    fn test_group_albums() {
+        assert_eq!(album_key("Artist/Album/01.flac"), "Artist/Album");
+        assert_eq!(album_key("Album\\01.flac"), "Album");
+        assert_eq!(album_key("single.flac"), "");
+
+        let albums = group_albums([
+            "A/02.flac",
+            "A/01.flac",
+            "B/only.flac",
+            "ALBUM:A",
+            "root1.wav",
+            "root2.wav",
+        ]);
+        assert_eq!(albums.len(), 2);
+        assert_eq!(albums["A"], vec!["A/01.flac", "A/02.flac"]);
+        assert_eq!(albums[""], vec!["root1.wav", "root2.wav"]);
+        assert!(is_album_entry(&album_entry_key("A")));
+        assert_eq!(album_directory(&album_entry_key("A")), Some("A"));
+    }
+}
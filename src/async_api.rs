@@ -0,0 +1,346 @@
+//! 异步接口模块 (Async API Module)
+//!
+//! 需要 `async` 特性。同步接口在 Rayon 线程池中阻塞等待 `Command::output()`，
+//! 不适合已经完全异步化的服务。这里用 `tokio::process::Command` 执行同样的 FFmpeg
+//! 命令，并与同步路径共用命令构建、输出解析和错误类型：
+//!
+//! - [`calculate_lra_async`] - 分析单个文件
+//! - [`process_files_concurrent`] - 以有限并发分析一批文件（`buffer_unordered`）
+//!
+//! ## 超时与取消
+//!
+//! - 超时由 `tokio::time::timeout` 实现，超时返回 [`LraCalculationError::TimedOut`]
+//! - FFmpeg 子进程以 `kill_on_drop` 启动：超时或调用方丢弃 future（例如在
+//!   `tokio::select!` 中被取消）时，正在运行的进程都会被终止，不会遗留孤儿进程
+//!
+//! 文件可用性检查、内容哈希、并发名额的等待以及缩混和逐声道的附加测量是阻塞操作，
+//! 通过 `spawn_blocking` 在 tokio 的阻塞线程池中执行，不占用运行时的工作线程。
+//! 测量结果与同步路径由同一个构建函数组装，包含相同的字段。
+//!
+//! 本模块不创建运行时，需要在调用方的 tokio 运行时中使用。
+
+use std::path::{Path, PathBuf};
+use std::process::{Command, Stdio};
+use std::time::{Duration, Instant};
+
+use futures::stream::{self, StreamExt};
+
+use crate::audio::{announce_command, build_ebur128_command, summarize_ebur128_output, CalculationOptions, LoudnessSummary};
+use crate::error::{LraCalculationError, ProcessFileError};
+use crate::file_key::FileKey;
+use crate::processor::{
+    check_file_available, classify_lra_error, content_hash, FileAnalysis, FileMeasurement, FileOutcome, SkipReason,
+};
+
+/// 异步计算音频文件的 LRA 值 (Calculate LRA Asynchronously)
+///
+/// 与 [`crate::audio::calculate_lra_direct`] 相同的分析，不阻塞运行时线程。
+///
+/// # 参数
+/// - `audio_file_path` - 要分析的音频文件路径
+///
+/// # 返回值
+/// - 与 [`crate::audio::calculate_lra_direct`] 相同
+///
+/// # 示例
+/// ```rust,no_run
+/// use lra_calculator_rust::async_api::calculate_lra_async;
+/// use std::path::Path;
+///
+/// # async fn run() {
+/// match calculate_lra_async(Path::new("song.flac")).await {
+///     Ok(lra) => println!("LRA: {:.1} LU", lra),
+///     Err(e) => eprintln!("分析失败: {}", e),
+/// }
+/// # }
+/// ```
+pub async fn calculate_lra_async(audio_file_path: &Path) -> Result<f64, LraCalculationError> {
+    measure_loudness_async(audio_file_path, "", &CalculationOptions::default(), None)
+        .await
+        .map(|summary| summary.lra)
+}
+
+/// 异步测量音频文件的响度 (Measure Loudness Asynchronously)
+///
+/// 与 [`crate::audio::measure_loudness`] 相同，额外支持超时。
+///
+/// # 参数
+/// - `audio_file_path` - 要分析的音频文件路径
+/// - `display_path` - 显示路径，决定调试输出的文件名
+/// - `options` - 计算选项
+/// - `timeout` - 单个文件的时间限制；`None` 表示不限制
+///
+/// # 返回值
+/// - `Ok(LoudnessSummary)` - LRA 和综合响度
+/// - `Err(LraCalculationError::TimedOut)` - 超时，FFmpeg 进程已被终止
+/// - `Err(LraCalculationError)` - 其他错误与同步接口相同
+pub async fn measure_loudness_async(
+    audio_file_path: &Path,
+    display_path: &str,
+    options: &CalculationOptions,
+    timeout: Option<Duration>,
+) -> Result<LoudnessSummary, LraCalculationError> {
+    run_ebur128_async(build_ebur128_command(audio_file_path, options), display_path, options, timeout).await
+}
+
+/// 并发分析一批音频文件 (Process Files Concurrently)
+///
+/// 最多同时运行 `max_concurrency` 个 FFmpeg 进程（为 0 时按 1 处理）。
+/// 结果按输入顺序排列，与 [`crate::processor::process_files_parallel`] 一致。
+///
+/// # 参数
+/// - `files_to_process` - 要处理的文件列表：(完整路径, 显示路径)
+/// - `max_concurrency` - 最大并发数
+///
+/// # 返回值
+/// - 与 [`crate::processor::process_files_parallel`] 相同的处理结果
+pub async fn process_files_concurrent<K>(
+    files_to_process: Vec<(PathBuf, K)>,
+    max_concurrency: usize,
+) -> Vec<Result<FileMeasurement, ProcessFileError>>
+where
+    K: Into<FileKey>,
+{
+    process_files_concurrent_with_options(files_to_process, max_concurrency, &CalculationOptions::default(), None)
+        .await
+}
+
+/// 按选项并发分析一批音频文件 (Process Files Concurrently with Options)
+///
+/// 与 [`process_files_concurrent`] 相同，额外接受计算选项和单个文件的时间限制。
+/// 超时的文件记为 FFmpeg 执行失败，不影响其他文件。云端占位文件、缩混和逐声道测量
+/// 以及分析用时与同步接口相同；`max_concurrency` 限制同时进行的文件数。
+///
+/// # 参数
+/// - `files_to_process` - 要处理的文件列表：(完整路径, 显示路径)
+/// - `max_concurrency` - 最大并发数
+/// - `options` - 计算选项
+/// - `timeout` - 单个文件的时间限制；`None` 表示不限制
+pub async fn process_files_concurrent_with_options<K>(
+    files_to_process: Vec<(PathBuf, K)>,
+    max_concurrency: usize,
+    options: &CalculationOptions,
+    timeout: Option<Duration>,
+) -> Vec<Result<FileMeasurement, ProcessFileError>>
+where
+    K: Into<FileKey>,
+{
+    let mut indexed: Vec<(usize, Result<FileMeasurement, ProcessFileError>)> =
+        stream::iter(files_to_process.into_iter().enumerate())
+            .map(|(index, (file_path, display_path))| async move {
+                let outcome = process_file_async(file_path, display_path.into(), options, timeout).await;
+                (index, outcome.into_result())
+            })
+            .buffer_unordered(max_concurrency.max(1))
+            .collect()
+            .await;
+    indexed.sort_unstable_by_key(|(index, _)| *index);
+    indexed.into_iter().map(|(_, result)| result).collect()
+}
+
+/// 异步分析单个文件 (Process File Asynchronously)
+///
+/// 与同步路径的 `process_single_file` 步骤相同，只有主测量使用异步的 FFmpeg 进程。
+/// 运行时关闭导致阻塞任务被取消时，文件以 [`SkipReason::Cancelled`] 结束。
+async fn process_file_async(
+    file_path: PathBuf,
+    display_path: FileKey,
+    options: &CalculationOptions,
+    timeout: Option<Duration>,
+) -> FileOutcome {
+    let cancelled = || FileOutcome::Skipped { path: display_path.clone(), reason: SkipReason::Cancelled };
+
+    // 可用性检查（元数据、云端占位、可选的下载）是阻塞的文件系统操作
+    let availability = {
+        let (file_path, display_path, options) = (file_path.clone(), display_path.clone(), options.clone());
+        run_blocking(move || check_file_available(&file_path, &display_path, &options)).await
+    };
+    match availability {
+        Some(None) => {}
+        Some(Some(outcome)) => return outcome,
+        None => return cancelled(),
+    }
+
+    // 哈希在阻塞线程池中进行，与 FFmpeg 的分析同时读取文件
+    let hasher = options.hash.map(|algorithm| {
+        let file_path = file_path.clone();
+        tokio::task::spawn_blocking(move || content_hash(&file_path, algorithm))
+    });
+    let started = Instant::now();
+    let analysis = match measure_loudness_async(&file_path, &display_path, options, timeout).await {
+        Ok(summary) => {
+            let (file_path, display_path, options) = (file_path.clone(), display_path.clone(), options.clone());
+            match run_blocking(move || FileAnalysis::complete(summary, &file_path, &display_path, &options, started)).await {
+                Some(analysis) => Ok(analysis),
+                None => return cancelled(),
+            }
+        }
+        Err(e) => Err(e),
+    };
+    let content_hash = match hasher {
+        Some(handle) => join_blocking(handle).await,
+        None => None,
+    };
+    analysis
+        .map(|analysis| analysis.into_measurement(&display_path, content_hash, None))
+        .map_err(|e| classify_lra_error(&display_path, e))
+        .into()
+}
+
+/// 在 tokio 的阻塞线程池中执行同步操作，不占用运行时的工作线程
+///
+/// # 返回值
+/// - 与 [`join_blocking`] 相同
+async fn run_blocking<T, F>(f: F) -> Option<T>
+where
+    F: FnOnce() -> T + Send + 'static,
+    T: Send + 'static,
+{
+    join_blocking(tokio::task::spawn_blocking(f)).await
+}
+
+/// 等待阻塞任务完成
+///
+/// 任务中的 panic 原样传播给调用方，与同步路径一致。
+///
+/// # 返回值
+/// - `Some(T)` - 任务的结果
+/// - `None` - 任务在开始之前被取消（运行时正在关闭）
+async fn join_blocking<T>(handle: tokio::task::JoinHandle<T>) -> Option<T> {
+    match handle.await {
+        Ok(value) => Some(value),
+        Err(e) if e.is_panic() => std::panic::resume_unwind(e.into_panic()),
+        Err(_) => None,
+    }
+}
+
+/// 异步执行 ebur128 命令并解析汇总结果
+async fn run_ebur128_async(
+    command: Command,
+    display_path: &str,
+    options: &CalculationOptions,
+    timeout: Option<Duration>,
+) -> Result<LoudnessSummary, LraCalculationError> {
+    let command_line = announce_command(&command, options);
+    let mut command = tokio::process::Command::from(command);
+    command.stdin(Stdio::null()).kill_on_drop(true);
+
+    let output = match timeout {
+        Some(limit) => tokio::time::timeout(limit, command.output())
+            .await
+            .map_err(|_| LraCalculationError::TimedOut { timeout: limit, command: command_line.clone() })?,
+        None => command.output().await,
+    }
+    .map_err(LraCalculationError::Spawn)?;
+
+    summarize_ebur128_output(&output, &command_line, display_path, options)
+}
+
+#[cfg(all(test, unix))]
+mod tests {
+    use super::*;
+    use std::time::Instant;
+
+    /// 用 `sh -c` 模拟 FFmpeg：脚本的 stderr 即 ebur128 输出
+    fn fake_ffmpeg(script: &str) -> Command {
+        let mut command = Command::new("sh");
+        command.arg("-c").arg(script);
+        command
+    }
+
+    /// 测试异步路径与同步路径共用输出解析和错误类型
+    #[tokio::test]
+    async fn test_run_ebur128_async() {
+        let options = CalculationOptions::default();
+        let summary = run_ebur128_async(
+            fake_ffmpeg("printf '  Integrated loudness:\\n    I:  -14.2 LUFS\\n  Loudness range:\\n    LRA:  7.3 LU\\n' >&2"),
+            "a.flac",
+            &options,
+            None,
+        )
+        .await
+        .expect("应该解析出 LRA");
+        assert_eq!(
+            summary,
+            LoudnessSummary {
+                lra: 7.3,
+                integrated_lufs: Some(-14.2),
+                duration: None,
+                true_peak_dbtp: None,
+                lra_low_lufs: None,
+                lra_high_lufs: None,
+                threshold_lufs: None,
+                warnings: Vec::new(),
+            }
+        );
+
+        let warned = run_ebur128_async(
+            fake_ffmpeg("printf '[mp3 @ 0x55d0] Estimating duration from bitrate, this may be inaccurate\\n  LRA:  5.0 LU\\n' >&2"),
+            "w.mp3",
+            &options,
+            None,
+        )
+        .await
+        .expect("有警告时仍应解析出 LRA");
+        assert_eq!(warned.warnings, vec!["[mp3] Estimating duration from bitrate, this may be inaccurate"]);
+
+        let failed = run_ebur128_async(fake_ffmpeg("echo broken >&2; exit 3"), "b.flac", &options, None).await;
+        assert!(matches!(failed, Err(LraCalculationError::NonZeroExit { code: Some(3), .. })));
+
+        let unparsable = run_ebur128_async(fake_ffmpeg("echo nothing >&2"), "c.flac", &options, None).await;
+        assert!(matches!(unparsable, Err(LraCalculationError::ParseFailed { .. })));
+    }
+
+    /// 测试超时后立即返回并终止进程
+    #[tokio::test]
+    async fn test_run_ebur128_async_timeout() {
+        let started = Instant::now();
+        let result = run_ebur128_async(
+            fake_ffmpeg("sleep 10"),
+            "slow.flac",
+            &CalculationOptions::default(),
+            Some(Duration::from_millis(100)),
+        )
+        .await;
+        assert!(matches!(result, Err(LraCalculationError::TimedOut { .. })));
+        assert!(started.elapsed() < Duration::from_secs(5));
+    }
+
+    /// 测试阻塞任务中的 panic 原样传播
+    #[tokio::test]
+    #[should_panic(expected = "阻塞任务失败")]
+    async fn test_join_blocking_resumes_panic() {
+        join_blocking(tokio::task::spawn_blocking(|| panic!("阻塞任务失败"))).await;
+    }
+
+    /// 测试运行时关闭后阻塞任务被取消，返回 `None` 而不是 panic
+    #[test]
+    fn test_join_blocking_maps_cancellation_to_none() {
+        let runtime = tokio::runtime::Builder::new_current_thread().build().unwrap();
+        let handle = runtime.handle().clone();
+        runtime.shutdown_background();
+        let cancelled = handle.spawn_blocking(|| 42);
+
+        let other = tokio::runtime::Builder::new_current_thread().build().unwrap();
+        assert_eq!(other.block_on(join_blocking(cancelled)), None);
+    }
+
+    /// 测试并发处理的结果按输入顺序排列
+    #[tokio::test]
+    async fn test_process_files_concurrent_keeps_input_order() {
+        let files: Vec<(PathBuf, String)> = ["c.flac", "a.flac", "b.flac"]
+            .iter()
+            .map(|name| (PathBuf::from("/nonexistent/lra-async-test").join(name), name.to_string()))
+            .collect();
+        let results = process_files_concurrent(files, 2).await;
+
+        let paths: Vec<String> = results
+            .iter()
+            .map(|result| match result {
+                Ok(measurement) => measurement.path.to_string(),
+                Err(error) => error.file_path.to_string(),
+            })
+            .collect();
+        assert_eq!(paths, vec!["c.flac", "a.flac", "b.flac"]);
+    }
+}
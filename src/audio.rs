@@ -17,12 +17,39 @@
 //! - **标准兼容**: 严格遵循 EBU R128 标准进行 LRA 计算
 //! - **扩展性**: 易于添加新的音频格式支持
 
+use std::borrow::Cow;
+use std::collections::hash_map::DefaultHasher;
+use std::ffi::OsStr;
+use std::fs;
+use std::hash::{Hash, Hasher};
 use std::path::{Path, PathBuf};
-use std::process::Command;
+use std::io::{BufRead, BufReader, Read};
+use std::process::{Command, Output, Stdio};
+use std::sync::LazyLock;
+use std::thread;
+use std::time::{Duration, Instant, SystemTime};
 use regex::Regex;
 use walkdir::WalkDir;
 
-use crate::error::AppError;
+use crate::case_alias::{CaseAlias, CaseAliasDetector};
+use crate::cloud::icloud_stub_target;
+use crate::environment::{probe_ffmpeg, resolved_program};
+use crate::exclude::{matches_pattern, ExcludeSet};
+use crate::file_key::{DisplayPathCollision, DisplayPathDeduplicator};
+use crate::formats::{self, FormatRegistry};
+use crate::hash::HashAlgorithm;
+use crate::priority::tool_command;
+use crate::run_id::RunId;
+use crate::shell::ShellSyntax;
+use crate::probe::{probe_channels, probe_duration};
+use crate::warnings::extract_ffmpeg_warnings;
+use crate::progress::{
+    percent_complete, AnalysisProgress, ProgressParser, LONG_FILE_THRESHOLD, PROGRESS_REPORT_INTERVAL,
+};
+use crate::error::{
+    truncate_stderr_excerpt, AppError, EmptyScanReason, LraCalculationError, SelfTestError,
+    SelfTestStage,
+};
 
 /// 支持的音频文件扩展名列表 (Supported Audio File Extensions)
 ///
@@ -46,6 +73,8 @@ use crate::error::AppError;
 /// - 所有扩展名都使用小写形式进行匹配
 /// - 文件扫描时会自动转换为小写进行比较
 /// - 添加新格式时需要确保 FFmpeg 支持该格式的 LRA 分析
+/// - 这只是默认列表：运行时以 [`FormatRegistry`] 为准，
+///   可通过 `--add-ext` / `--remove-ext` 调整
 pub const SUPPORTED_EXTENSIONS: [&str; 10] = [
     "wav", "mp3", "m4a", "flac", "aac", "ogg", "opus", "wma", "aiff", "alac",
 ];
@@ -59,7 +88,8 @@ pub const SUPPORTED_EXTENSIONS: [&str; 10] = [
 /// - **递归遍历**: 使用 `walkdir` 库进行深度优先遍历
 /// - **格式过滤**: 只保留扩展名在支持列表中的文件
 /// - **路径处理**: 生成相对路径用于显示，保留绝对路径用于处理
-/// - **排除机制**: 可以排除特定文件（如结果文件）避免重复处理
+/// - **排除机制**: 可以排除特定文件（如结果文件）避免重复处理；
+///   需要按文件名模式排除时使用 [`scan_audio_files_with_report`] 和 [`ExcludeSet`]
 ///
 /// ## 性能考虑
 /// - 使用迭代器链式操作，避免中间集合的创建
@@ -78,6 +108,8 @@ pub const SUPPORTED_EXTENSIONS: [&str; 10] = [
 /// # 示例
 /// ```rust
 /// use std::path::Path;
+/// use lra_calculator_rust::audio::scan_audio_files;
+///
 /// let files = scan_audio_files(Path::new("/music"), None);
 /// for (full_path, display_path) in files {
 ///     println!("发现文件: {} -> {}", display_path, full_path.display());
@@ -87,36 +119,416 @@ pub fn scan_audio_files(
     base_path: &Path,
     exclude_file: Option<&Path>,
 ) -> Vec<(PathBuf, String)> {
-    let mut files_to_process = Vec::new();
+    scan_audio_files_with_report(base_path, &ExcludeSet::from_optional_path(exclude_file)).files
+}
 
-    // 使用 WalkDir 进行递归目录遍历
-    // 这里使用函数式编程风格，通过链式调用提高代码可读性
-    for entry_result in WalkDir::new(base_path)
-        .into_iter()
-        .filter_map(Result::ok)  // 忽略无法访问的目录项（权限问题等）
-        .filter(|e| e.file_type().is_file())  // 只处理文件，跳过目录和符号链接
-    {
-        let current_file_path = entry_result.path().to_path_buf();
-
-        // 排除指定文件（通常是结果文件，避免处理自己生成的文件）
-        if let Some(exclude) = exclude_file {
-            if current_file_path == exclude {
-                continue;
-            }
+/// 扫描选项 (Scan Options)
+///
+/// 控制扫描时排除哪些文件。所有过滤条件默认关闭。
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct ScanOptions {
+    /// 要排除的路径和文件名模式
+    pub exclude: ExcludeSet,
+    /// 最小文件大小（字节），更小的音频文件被跳过
+    pub min_size: Option<u64>,
+    /// 最大文件大小（字节），更大的音频文件被跳过
+    pub max_size: Option<u64>,
+    /// 只保留修改时间晚于该时刻的音频文件（增量更新）
+    pub newer_than: Option<SystemTime>,
+    /// 只保留文件名匹配其中任一模式的音频文件，为空时不限制（见 [`crate::exclude::validate_pattern`]）
+    pub only: Vec<String>,
+    /// 跳过与先找到的文件只有大小写不同且是同一个文件的音频文件（见 [`crate::case_alias`]）
+    pub dedup_case_aliases: bool,
+}
+
+impl ScanOptions {
+    /// 文件大小是否超出范围
+    ///
+    /// 只在设置了大小过滤时读取元数据；元数据读取失败时不过滤，
+    /// 让真正损坏的文件在分析阶段报告具体错误。
+    fn rejects_size(&self, entry: &walkdir::DirEntry) -> bool {
+        if self.min_size.is_none() && self.max_size.is_none() {
+            return false;
+        }
+        let Ok(size) = entry.metadata().map(|metadata| metadata.len()) else {
+            return false;
+        };
+        self.min_size.is_some_and(|min| size < min) || self.max_size.is_some_and(|max| size > max)
+    }
+
+    /// 文件是否早于 `newer_than`
+    ///
+    /// 修改时间无法读取时不过滤：宁可多处理，也不要漏掉文件。
+    fn rejects_mtime(&self, entry: &walkdir::DirEntry) -> bool {
+        let Some(newer_than) = self.newer_than else {
+            return false;
+        };
+        entry
+            .metadata()
+            .ok()
+            .and_then(|metadata| metadata.modified().ok())
+            .is_some_and(|modified| modified <= newer_than)
+    }
+}
+
+/// 扫描报告 (Scan Report)
+///
+/// 除了找到的音频文件之外，还记录扫描过程中遇到的其他条目和
+/// 无法读取的路径，用于在扫描结果为空时判断具体原因。
+#[derive(Debug, Clone, Default)]
+pub struct ScanReport {
+    /// 找到的音频文件：(完整路径, 显示路径)
+    pub files: Vec<(PathBuf, String)>,
+    /// 找到的音频文件的总大小（字节），用于估算用时；无法读取元数据的文件不计入
+    pub total_bytes: u64,
+    /// 根目录下可读取的条目总数（文件和目录，不含根目录本身）
+    pub entries_seen: usize,
+    /// 跳过的非音频文件数量
+    pub skipped_files: usize,
+    /// 作为本程序生成的文件（结果文件、缓存、生成文件清单中的路径等）而被排除的文件和目录数量，
+    /// 不计入 `skipped_files`，也不含本次运行的临时文件（见 [`ExcludeSet::add_transient_path`]）
+    pub excluded_artifacts: usize,
+    /// 没有扩展名的文件（已计入 `skipped_files`），供 `--probe-unknown` 按内容识别
+    pub extensionless_files: Vec<(PathBuf, String)>,
+    /// 无法读取的路径及错误原因（通常是权限问题）
+    pub unreadable_paths: Vec<(PathBuf, String)>,
+    /// 因文件大小超出 `min_size` / `max_size` 而跳过的音频文件数量
+    pub filtered_by_size: usize,
+    /// 因修改时间早于 `newer_than` 而跳过的音频文件数量
+    pub filtered_by_mtime: usize,
+    /// 因文件名不匹配 `only` 中任何模式而跳过的音频文件数量
+    pub filtered_by_pattern: usize,
+    /// 因文件名匹配用户的排除模式（`--exclude`，见 [`ExcludeSet::add_user_pattern`]）而跳过的音频文件数量
+    pub filtered_by_exclude: usize,
+    /// `only` 中每个模式匹配到的音频文件数量，顺序与 `only` 相同
+    pub pattern_matches: Vec<(String, usize)>,
+    /// 被剪枝（整体跳过）的目录数量
+    pub pruned_dirs: usize,
+    /// 显示路径与先发现的文件相同而被改名的文件（通常是替换后相同的非 UTF-8 文件名）
+    pub path_collisions: Vec<DisplayPathCollision>,
+    /// `dedup_case_aliases` 时跳过的大小写别名，不计入任何其他统计
+    pub case_aliases: Vec<CaseAlias>,
+}
+
+impl ScanReport {
+    /// 判断扫描结果为空的原因
+    ///
+    /// # 返回值
+    /// - `Some(EmptyScanReason)` - 没有找到音频文件时的具体原因
+    /// - `None` - 找到了至少一个音频文件
+    pub fn empty_reason(&self) -> Option<EmptyScanReason> {
+        if !self.files.is_empty() {
+            None
+        } else if !self.unreadable_paths.is_empty() {
+            Some(EmptyScanReason::Unreadable {
+                unreadable_paths: self.unreadable_paths.len(),
+            })
+        } else if self.filtered_files() > 0 {
+            Some(EmptyScanReason::AllFiltered {
+                filtered_files: self.filtered_files(),
+            })
+        } else if self.skipped_files > 0 {
+            Some(EmptyScanReason::NoSupportedAudio {
+                other_files: self.skipped_files,
+            })
+        } else if self.excluded_artifacts > 0 {
+            Some(EmptyScanReason::OnlyGeneratedFiles {
+                excluded_files: self.excluded_artifacts,
+            })
+        } else {
+            Some(EmptyScanReason::EmptyDirectory)
+        }
+    }
+
+    /// 被过滤条件（大小、修改时间、文件名模式、用户的排除模式）跳过的音频文件总数
+    pub fn filtered_files(&self) -> usize {
+        self.filtered_by_size + self.filtered_by_mtime + self.filtered_by_pattern + self.filtered_by_exclude
+    }
+
+    /// 没有匹配到任何音频文件的 `only` 模式
+    ///
+    /// 通常是模式写错了（比如漏了扩展名前的 `*`），调用方应当提示用户。
+    pub fn unmatched_patterns(&self) -> impl Iterator<Item = &str> {
+        self.pattern_matches
+            .iter()
+            .filter(|(_, matched)| *matched == 0)
+            .map(|(pattern, _)| pattern.as_str())
+    }
+}
+
+/// 扫描音频文件并生成扫描报告 (Scan Audio Files with Report)
+///
+/// 与 [`scan_audio_files`] 相同，但不会静默丢弃遍历错误，
+/// 而是将无法读取的路径收集到 [`ScanReport::unreadable_paths`] 中。
+///
+/// # 参数
+/// - `base_path` - 要扫描的根目录路径
+/// - `exclude` - 要排除的路径和文件名模式
+///
+/// # 返回值
+/// - 包含音频文件列表和遍历统计的 [`ScanReport`]
+pub fn scan_audio_files_with_report(
+    base_path: &Path,
+    exclude: &ExcludeSet,
+) -> ScanReport {
+    let options = ScanOptions {
+        exclude: exclude.clone(),
+        ..ScanOptions::default()
+    };
+    scan_audio_files_with_options(base_path, &options)
+}
+
+/// 按扫描选项扫描音频文件 (Scan Audio Files with Options)
+///
+/// # 参数
+/// - `base_path` - 要扫描的根目录路径
+/// - `options` - 排除规则和过滤条件
+///
+/// # 返回值
+/// - 包含音频文件列表和遍历统计的 [`ScanReport`]
+pub fn scan_audio_files_with_options(base_path: &Path, options: &ScanOptions) -> ScanReport {
+    scan_audio_files_with_formats(base_path, options, &FormatRegistry::global())
+}
+
+/// 按指定格式注册表扫描音频文件 (Scan Audio Files with Formats)
+///
+/// 与 [`scan_audio_files_with_options`] 相同，但使用给定的注册表而不是全局注册表。
+///
+/// # 参数
+/// - `base_path` - 要扫描的根目录路径
+/// - `options` - 排除规则和过滤条件
+/// - `registry` - 判断音频格式的注册表
+///
+/// # 返回值
+/// - 包含音频文件列表和遍历统计的 [`ScanReport`]
+pub fn scan_audio_files_with_formats(
+    base_path: &Path,
+    options: &ScanOptions,
+    registry: &FormatRegistry,
+) -> ScanReport {
+    let mut iter = ScanIter::new(base_path, options, Cow::Borrowed(registry));
+    let files = iter.by_ref().collect();
+    ScanReport { files, ..iter.into_report() }
+}
+
+/// 惰性扫描音频文件 (Scan Audio Files Lazily)
+///
+/// 返回边遍历边产出文件的迭代器，不会先把整个文件列表放进内存。
+/// 适合超大目录树：调用方可以在扫描进行时显示进度，或者直接开始处理。
+/// 遍历统计在迭代结束后通过 [`ScanIter::into_report`] 获取。
+///
+/// # 参数
+/// - `base_path` - 要扫描的根目录路径
+/// - `options` - 排除规则和过滤条件
+///
+/// # 返回值
+/// - 产出 (完整路径, 显示路径) 的 [`ScanIter`]
+///
+/// # 示例
+/// ```rust
+/// use std::path::Path;
+/// use lra_calculator_rust::audio::{scan_audio_files_iter, ScanOptions};
+///
+/// let options = ScanOptions::default();
+/// let mut scan = scan_audio_files_iter(Path::new("/music"), &options);
+/// for (discovered, (_, display_path)) in scan.by_ref().enumerate() {
+///     println!("已发现 {} 个文件: {}", discovered + 1, display_path);
+/// }
+/// println!("跳过了 {} 个其他文件", scan.into_report().skipped_files);
+/// ```
+pub fn scan_audio_files_iter<'a>(base_path: &Path, options: &'a ScanOptions) -> ScanIter<'a> {
+    ScanIter::new(base_path, options, Cow::Owned(FormatRegistry::global()))
+}
+
+/// 惰性扫描迭代器 (Scan Iterator)
+///
+/// 由 [`scan_audio_files_iter`] 创建。每次调用 `next` 只推进目录遍历到下一个
+/// 音频文件为止；无关条目只计入统计，不会被保存。
+pub struct ScanIter<'a> {
+    /// 扫描根目录，用于生成显示路径
+    base_path: PathBuf,
+    /// 排除规则和过滤条件
+    options: &'a ScanOptions,
+    /// 扫描根目录下生成文件清单记录的路径（见 [`crate::exclude::record_artifacts`]）
+    artifacts: ExcludeSet,
+    /// 判断音频格式的注册表
+    registry: Cow<'a, FormatRegistry>,
+    /// 底层目录遍历
+    walker: walkdir::IntoIter,
+    /// 保证显示路径唯一
+    display_paths: DisplayPathDeduplicator,
+    /// 大小写别名检测，`dedup_case_aliases` 时启用
+    case_aliases: Option<CaseAliasDetector>,
+    /// 遍历统计（`files` 始终为空）
+    report: ScanReport,
+}
+
+impl<'a> ScanIter<'a> {
+    /// 创建扫描迭代器
+    fn new(base_path: &Path, options: &'a ScanOptions, registry: Cow<'a, FormatRegistry>) -> Self {
+        let mut artifacts = ExcludeSet::new();
+        artifacts.add_artifact_manifest(base_path);
+        Self {
+            base_path: base_path.to_path_buf(),
+            options,
+            artifacts,
+            registry,
+            walker: WalkDir::new(base_path).into_iter(),
+            display_paths: DisplayPathDeduplicator::new(),
+            case_aliases: options.dedup_case_aliases.then(CaseAliasDetector::new),
+            report: ScanReport {
+                pattern_matches: options.only.iter().map(|pattern| (pattern.clone(), 0)).collect(),
+                ..ScanReport::default()
+            },
         }
+    }
 
-        // 检查文件扩展名是否在支持列表中
-        // 使用 Option 链式调用优雅地处理可能的 None 值
-        if let Some(extension) = extract_file_extension(&current_file_path) {
-            if is_supported_audio_format(&extension) {
-                // 生成用户友好的相对路径显示
-                let display_path_str = generate_display_path(&current_file_path, base_path);
-                files_to_process.push((current_file_path, display_path_str));
+    /// 文件名是否不匹配任何 `only` 模式，同时累计每个模式的匹配数
+    fn rejects_name(&mut self, file_name: &str) -> bool {
+        if self.report.pattern_matches.is_empty() {
+            return false;
+        }
+        let mut matched = false;
+        for (pattern, count) in &mut self.report.pattern_matches {
+            if matches_pattern(pattern, file_name) {
+                *count += 1;
+                matched = true;
             }
         }
+        !matched
+    }
+
+    /// 生成唯一的显示路径，冲突记录到扫描报告中
+    fn display_path_for(&mut self, file_path: &Path) -> String {
+        let display_path = self.display_paths.assign(file_path, generate_display_path(file_path, &self.base_path));
+        self.report.path_collisions.append(&mut self.display_paths.take_collisions());
+        display_path
+    }
+
+    /// 到目前为止的遍历统计（`files` 为空）
+    pub fn report(&self) -> &ScanReport {
+        &self.report
     }
 
-    files_to_process
+    /// 结束扫描并取得遍历统计（`files` 为空）
+    ///
+    /// 迭代器未耗尽时调用，统计只包含已遍历的部分。
+    pub fn into_report(self) -> ScanReport {
+        self.report
+    }
+}
+
+impl Iterator for ScanIter<'_> {
+    type Item = (PathBuf, String);
+
+    fn next(&mut self) -> Option<Self::Item> {
+        // 使用 WalkDir 进行递归目录遍历
+        while let Some(entry_result) = self.walker.next() {
+            let entry = match entry_result {
+                Ok(entry) => entry,
+                Err(e) => {
+                    // 记录无法访问的目录项（权限问题等），而不是静默忽略
+                    let path = e.path().unwrap_or(&self.base_path).to_path_buf();
+                    self.report.unreadable_paths.push((path, e.to_string()));
+                    continue;
+                }
+            };
+
+            if entry.depth() == 0 {
+                continue;
+            }
+
+            // 被剪枝的目录不会进入，其子树完全不被访问
+            if entry.file_type().is_dir() {
+                let is_artifact = self.artifacts.is_excluded(entry.path());
+                if is_artifact || self.options.exclude.is_pruned_dir(&entry.file_name().to_string_lossy()) {
+                    self.walker.skip_current_dir();
+                    self.report.pruned_dirs += 1;
+                    self.report.excluded_artifacts += usize::from(is_artifact);
+                    continue;
+                }
+            }
+
+            self.report.entries_seen += 1;
+
+            // 只处理文件，跳过目录和符号链接
+            if !entry.file_type().is_file() {
+                continue;
+            }
+
+            let current_file_path = entry.path().to_path_buf();
+
+            // 排除程序自己生成的文件（结果文件、缓存等），不计入跳过的文件
+            let exclude = &self.options.exclude;
+            if exclude.is_transient(&current_file_path) {
+                continue;
+            }
+            if exclude.is_generated(&current_file_path) || self.artifacts.is_excluded(&current_file_path) {
+                self.report.excluded_artifacts += 1;
+                continue;
+            }
+            // 用户的排除模式：音频文件计为被过滤，其他文件计为跳过的非音频文件
+            if exclude.is_user_excluded(&current_file_path) {
+                match extract_file_extension(&current_file_path) {
+                    Some(extension) if self.registry.contains(&extension) => self.report.filtered_by_exclude += 1,
+                    _ => self.report.skipped_files += 1,
+                }
+                continue;
+            }
+
+            // 检查文件扩展名是否在支持列表中
+            match extract_file_extension(&current_file_path) {
+                Some(extension) if self.registry.contains(&extension) => {
+                    // 同一个文件的大小写别名不占用显示路径
+                    if let Some(alias) = self.case_aliases.as_mut().and_then(|detector| detector.check(&current_file_path)) {
+                        self.report.case_aliases.push(alias);
+                        continue;
+                    }
+                    // 生成用户友好的相对路径显示。被过滤的文件同样占用显示路径，
+                    // 增量更新时冲突文件分到的路径与完整扫描时相同
+                    let display_path_str = self.display_path_for(&current_file_path);
+                    if self.rejects_name(&entry.file_name().to_string_lossy()) {
+                        self.report.filtered_by_pattern += 1;
+                        continue;
+                    }
+                    if self.options.rejects_size(&entry) {
+                        self.report.filtered_by_size += 1;
+                        continue;
+                    }
+                    if self.options.rejects_mtime(&entry) {
+                        self.report.filtered_by_mtime += 1;
+                        continue;
+                    }
+                    self.report.total_bytes += entry.metadata().map_or(0, |metadata| metadata.len());
+                    return Some((current_file_path, display_path_str));
+                }
+                // 原文件是音频的 iCloud 存根照常产出，分析阶段报告为云端占位文件
+                _ if icloud_stub_target(&entry.file_name().to_string_lossy())
+                    .and_then(|target| extract_file_extension(Path::new(target)))
+                    .is_some_and(|extension| self.registry.contains(&extension)) =>
+                {
+                    let display_path_str = self.display_path_for(&current_file_path);
+                    // 文件名模式按原文件名匹配
+                    let file_name = entry.file_name().to_string_lossy();
+                    if icloud_stub_target(&file_name).is_some_and(|target| self.rejects_name(target)) {
+                        self.report.filtered_by_pattern += 1;
+                        continue;
+                    }
+                    return Some((current_file_path, display_path_str));
+                }
+                _ => {
+                    self.report.skipped_files += 1;
+                    // 隐藏文件（如 .DS_Store）不参与内容探测
+                    let is_hidden = entry.file_name().to_string_lossy().starts_with('.');
+                    if current_file_path.extension().is_none() && !is_hidden {
+                        let display_path_str = self.display_path_for(&current_file_path);
+                        self.report.extensionless_files.push((current_file_path, display_path_str));
+                    }
+                }
+            }
+        }
+        None
+    }
 }
 
 /// 提取文件扩展名并转换为小写 (Extract File Extension in Lowercase)
@@ -139,7 +551,7 @@ pub fn extract_file_extension(file_path: &Path) -> Option<String> {
 
 /// 检查是否为支持的音频格式 (Check if Supported Audio Format)
 ///
-/// 检查给定的扩展名是否在支持的音频格式列表中。
+/// 检查给定的扩展名是否在全局 [`FormatRegistry`] 中。
 /// 这个函数封装了格式检查逻辑，便于未来扩展和修改。
 ///
 /// # 参数
@@ -149,7 +561,7 @@ pub fn extract_file_extension(file_path: &Path) -> Option<String> {
 /// - `true` - 支持的音频格式
 /// - `false` - 不支持的格式
 pub fn is_supported_audio_format(extension: &str) -> bool {
-    SUPPORTED_EXTENSIONS.contains(&extension)
+    formats::is_registered(extension)
 }
 
 /// 生成用于显示的相对路径 (Generate Display Path)
@@ -181,12 +593,13 @@ fn generate_display_path(file_path: &Path, base_path: &Path) -> String {
 /// ### FFmpeg 命令构建
 /// 使用以下 FFmpeg 命令进行分析：
 /// ```bash
-/// ffmpeg -i <input_file> -filter_complex ebur128 -f null -hide_banner -loglevel info -
+/// ffmpeg -i <input_file> -filter_complex [0:a:0]ebur128 -vn -sn -dn -f null -hide_banner -loglevel info -
 /// ```
 ///
 /// ### 参数说明
 /// - `-i <input_file>`: 指定输入音频文件
-/// - `-filter_complex ebur128`: 使用 EBU R128 标准的响度分析滤波器
+/// - `-filter_complex [0:a:0]ebur128`: 把第一条音频流送入 EBU R128 标准的响度分析滤波器
+/// - `-vn -sn -dn`: 不输出视频、字幕和数据流，封面图片和视频轨不会被解码
 /// - `-f null`: 输出格式为 null（不生成实际输出文件）
 /// - `-hide_banner`: 隐藏 FFmpeg 版本信息，减少输出噪音
 /// - `-loglevel info`: 设置日志级别为 info，确保 ebur128 输出可见
@@ -194,133 +607,725 @@ fn generate_display_path(file_path: &Path, base_path: &Path) -> String {
 ///
 /// ### LRA 值解析
 /// ebur128 滤波器会在 stderr 中输出分析结果，格式类似：
-/// ```
+/// ```text
 /// [Parsed_ebur128_0 @ 0x...] Summary:
 /// [Parsed_ebur128_0 @ 0x...] Integrated loudness: -23.0 LUFS
 /// [Parsed_ebur128_0 @ 0x...] LRA: 12.3 LU
 /// ```
 ///
-/// 我们使用正则表达式提取 "LRA: X.X LU" 中的数值部分。
+/// 具体的匹配规则和版本差异见 `parse_lra_from_ffmpeg_output`。
 ///
 /// # 参数
 /// - `audio_file_path` - 要分析的音频文件路径
 ///
 /// # 返回值
 /// - `Ok(f64)` - 计算得到的 LRA 值（单位：LU，Loudness Units）
-/// - `Err(Box<dyn std::error::Error + Send + Sync>)` - 分析过程中的错误
+/// - `Err(LraCalculationError)` - 分析过程中的类型化错误
 ///
 /// # 错误情况
-/// - FFmpeg 执行失败（文件不存在、格式不支持、权限问题等）
-/// - 音频文件损坏或格式异常
-/// - FFmpeg 输出中无法找到 LRA 值
-/// - LRA 值解析失败（非数字格式）
+/// - `Spawn`: 无法启动 FFmpeg 进程
+/// - `NonZeroExit`: FFmpeg 执行失败（文件不存在、格式不支持、文件损坏等）
+/// - `ParseFailed`: FFmpeg 输出中无法找到 LRA 值
+/// - `InvalidValue`: LRA 值解析失败（非数字格式）
 ///
 /// # 性能注意事项
 /// - 这个函数会阻塞直到 FFmpeg 分析完成
 /// - 分析时间取决于音频文件的长度和复杂度
 /// - 内存使用量相对较小，因为使用流式处理
-pub fn calculate_lra_direct(
+pub fn calculate_lra_direct(audio_file_path: &Path) -> Result<f64, LraCalculationError> {
+    calculate_lra_with_options(audio_file_path, "", &CalculationOptions::default())
+}
+
+/// 计算选项 (Calculation Options)
+///
+/// 控制单个文件分析时的附加行为，默认全部关闭。并行处理整体的控制（调度顺序、取消、
+/// 并发名额等）在 [`crate::processor::RunOptions`] 中。
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct CalculationOptions {
+    /// 调试输出目录（`--debug-dump <dir>`）：设置后每个文件的完整 FFmpeg stderr
+    /// 和命令行会写入 `<dir>/<显示路径>.log`，见 [`debug_dump_path`]
+    pub dump_sink: Option<PathBuf>,
+    /// 执行前打印可直接复制运行的 FFmpeg 命令行（`--verbose`）
+    pub verbose: bool,
+    /// 不限定分析的流（`--keep-all-streams`）：去掉 `[0:a:0]` 和 `-vn -sn -dn`，
+    /// 恢复 FFmpeg 的自动流选择
+    pub keep_all_streams: bool,
+    /// 额外测量真峰值（`--true-peak`）：ebur128 以 `peak=true` 运行，
+    /// 结果保存在 [`LoudnessSummary::true_peak_dbtp`]
+    pub true_peak: bool,
+    /// 照常分析云端占位文件，允许读取时触发下载（`--hydrate`）；
+    /// 默认跳过，见 [`crate::cloud`]
+    pub hydrate: bool,
+    /// 同时计算文件内容的摘要（`--hash xxh3|sha1`），结果保存在
+    /// [`crate::processor::FileMeasurement::content_hash`]；只影响并行处理和异步接口
+    pub hash: Option<HashAlgorithm>,
+    /// 送入 ebur128 之前先缩混（`--downmix stereo`）；`None` 时按原始声道测量
+    pub downmix: Option<Downmix>,
+    /// 多声道文件额外测量一次缩混后的 LRA（`--also-downmixed`），结果保存在
+    /// [`crate::processor::FileMeasurement::downmixed_lra`]；只影响并行处理
+    pub also_downmixed: bool,
+    /// 额外逐声道测量综合响度和 LRA（`--per-channel`），结果保存在
+    /// [`crate::processor::FileMeasurement::channels`]；只影响并行处理
+    pub per_channel: bool,
+    /// 本次运行的 ID：设置后调试输出的文件名中带有该 ID，多次运行的输出不会相互覆盖
+    /// （[`crate::plan::execute`] 自动设置为计划的 ID）
+    pub run_id: Option<RunId>,
+}
+
+/// `--range-details` 在结果文件中追加的列名：LRA 下限、LRA 上限和综合响度门限
+pub const RANGE_DETAIL_COLUMNS: [&str; 3] = ["LRA 下限 (LUFS)", "LRA 上限 (LUFS)", "门限 (LUFS)"];
+
+/// `--also-downmixed` 在结果文件中追加的列名
+pub const DOWNMIXED_LRA_COLUMN: &str = "立体声缩混 LRA";
+
+/// 缩混方式 (Downmix)
+///
+/// 环绕声文件（如 5.1 FLAC）大多在立体声设备上收听，缩混后的 LRA 更接近实际听感。
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Downmix {
+    /// 立体声：由 FFmpeg 的重采样器按默认矩阵缩混（中置和环绕声道 -3 dB，LFE 丢弃），
+    /// 声道布局不是 5.1 的文件同样适用
+    Stereo,
+}
+
+impl Downmix {
+    /// 从命令行取值解析（目前只有 `stereo`）
+    pub fn parse(value: &str) -> Option<Self> {
+        match value {
+            "stereo" => Some(Self::Stereo),
+            _ => None,
+        }
+    }
+
+    /// 放在 ebur128 之前的滤镜
+    fn filter(self) -> &'static str {
+        match self {
+            Self::Stereo => "aformat=channel_layouts=stereo",
+        }
+    }
+
+    /// 需要缩混的最少声道数：声道数不超过输出声道数的文件缩混前后相同
+    fn min_source_channels(self) -> u32 {
+        match self {
+            Self::Stereo => 3,
+        }
+    }
+}
+
+impl std::fmt::Display for Downmix {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::Stereo => write!(f, "stereo"),
+        }
+    }
+}
+
+/// 按选项计算音频文件的 LRA 值 (Calculate LRA Value with Options)
+///
+/// 与 [`calculate_lra_direct`] 相同，额外按 [`CalculationOptions`] 处理调试输出。
+/// 调试输出在 FFmpeg 结束后写入（无论成功与否），写入失败只打印警告，
+/// 不影响分析结果。
+///
+/// # 参数
+/// - `audio_file_path` - 要分析的音频文件路径
+/// - `display_path` - 显示路径（相对于扫描根目录），决定调试输出的文件名
+/// - `options` - 计算选项
+///
+/// # 返回值
+/// - 与 [`calculate_lra_direct`] 相同
+pub fn calculate_lra_with_options(
+    audio_file_path: &Path,
+    display_path: &str,
+    options: &CalculationOptions,
+) -> Result<f64, LraCalculationError> {
+    measure_loudness(audio_file_path, display_path, options).map(|summary| summary.lra)
+}
+
+/// ebur128 汇总中的测量值 (Loudness Summary)
+#[derive(Debug, Clone, PartialEq)]
+pub struct LoudnessSummary {
+    /// 响度范围（LU）
+    pub lra: f64,
+    /// 综合响度（LUFS）；输出中找不到时为 `None`
+    pub integrated_lufs: Option<f64>,
+    /// 输入的音频总时长；FFmpeg 没有输出时长（`Duration: N/A`）时为 `None`
+    pub duration: Option<Duration>,
+    /// 真峰值（dBTP），各声道中的最大值；未启用 [`CalculationOptions::true_peak`]
+    /// 或输出中找不到时为 `None`
+    pub true_peak_dbtp: Option<f64>,
+    /// LRA 下限（LUFS，短期响度分布的第 10 百分位）
+    pub lra_low_lufs: Option<f64>,
+    /// LRA 上限（LUFS，短期响度分布的第 95 百分位）
+    pub lra_high_lufs: Option<f64>,
+    /// 综合响度的相对门限（LUFS）
+    pub threshold_lufs: Option<f64>,
+    /// 汇总之前输出的 FFmpeg 警告，见 [`crate::warnings`]
+    pub warnings: Vec<String>,
+}
+
+/// 测量音频文件的响度 (Measure Loudness)
+///
+/// 与 [`calculate_lra_with_options`] 执行相同的分析，额外返回汇总中的综合响度。
+/// 综合响度解析失败不影响结果，只有 LRA 是必需的。
+///
+/// # 参数
+/// - `audio_file_path` - 要分析的音频文件路径
+/// - `display_path` - 显示路径（相对于扫描根目录），决定调试输出的文件名
+/// - `options` - 计算选项
+///
+/// # 返回值
+/// - `Ok(LoudnessSummary)` - LRA 和综合响度
+/// - `Err(LraCalculationError)` - 与 [`calculate_lra_direct`] 相同
+pub fn measure_loudness(
+    audio_file_path: &Path,
+    display_path: &str,
+    options: &CalculationOptions,
+) -> Result<LoudnessSummary, LraCalculationError> {
+    run_ebur128(build_ebur128_command(audio_file_path, options), display_path, options)
+}
+
+/// 测量音频文件的响度并报告长文件的进度 (Measure Loudness with Progress)
+///
+/// 与 [`measure_loudness`] 相同，FFmpeg 额外以 `-progress pipe:1` 运行。
+/// 运行超过 [`LONG_FILE_THRESHOLD`] 后每隔 [`PROGRESS_REPORT_INTERVAL`] 调用一次
+/// `on_progress`；第一次报告时用 ffprobe 读取文件总时长（见 [`probe_duration`]），
+/// 读取失败时只报告已用时间。运行不到阈值的文件不会调用 ffprobe。
+///
+/// # 参数
+/// - `audio_file_path` - 要分析的音频文件路径
+/// - `display_path` - 显示路径（相对于扫描根目录），决定调试输出的文件名
+/// - `options` - 计算选项
+/// - `on_progress` - 进度回调，在调用线程上执行
+///
+/// # 返回值
+/// - 与 [`measure_loudness`] 相同
+pub fn measure_loudness_with_progress(
+    audio_file_path: &Path,
+    display_path: &str,
+    options: &CalculationOptions,
+    on_progress: &dyn Fn(AnalysisProgress),
+) -> Result<LoudnessSummary, LraCalculationError> {
+    let mut command = ebur128_command(audio_file_path, options, true);
+    let command_line = announce_command(&command, options);
+    let mut child = command
+        .stdin(Stdio::null())
+        .stdout(Stdio::piped())
+        .stderr(Stdio::piped())
+        .spawn()
+        .map_err(LraCalculationError::Spawn)?;
+    let (progress_output, stderr_pipe) = (child.stdout.take(), child.stderr.take());
+
+    let started = Instant::now();
+    // stderr 在单独的线程中读取，避免两个管道互相阻塞
+    let stderr = thread::scope(|scope| {
+        let stderr_reader = scope.spawn(move || {
+            let mut stderr = Vec::new();
+            if let Some(mut pipe) = stderr_pipe {
+                let _ = pipe.read_to_end(&mut stderr);
+            }
+            stderr
+        });
+
+        let mut parser = ProgressParser::default();
+        let mut next_report = started + LONG_FILE_THRESHOLD;
+        let mut total_duration = None;
+        for line in progress_output.into_iter().flat_map(|pipe| BufReader::new(pipe).lines().map_while(Result::ok)) {
+            let Some(block) = parser.feed(&line) else { continue };
+            let now = Instant::now();
+            if block.finished || now < next_report {
+                continue;
+            }
+            next_report = now + PROGRESS_REPORT_INTERVAL;
+            let total = *total_duration.get_or_insert_with(|| probe_duration(audio_file_path));
+            on_progress(AnalysisProgress {
+                elapsed: now - started,
+                percent: percent_complete(block.out_time, total),
+            });
+        }
+        stderr_reader.join().unwrap_or_default()
+    });
+
+    let status = child.wait().map_err(LraCalculationError::Spawn)?;
+    let output = Output { status, stdout: Vec::new(), stderr };
+    summarize_ebur128_output(&output, &command_line, display_path, options)
+}
+
+/// 合并测量多个文件的响度 (Measure Album Loudness)
+///
+/// 把多个文件首尾相接作为一条音频送入 ebur128，得到整张专辑的 LRA 和综合响度。
+/// 每个文件的第一条音频流先统一重采样为 48 kHz 立体声浮点格式，再用 `concat` 滤波器拼接，
+/// 因此同一目录中混合不同采样率、声道数或编码的文件也能一起分析。
+/// 单声道文件被复制到两个声道，综合响度比单独测量时高约 3 LU，LRA 不受影响。
+///
+/// # 参数
+/// - `audio_file_paths` - 按播放顺序排列的文件完整路径，不能为空
+/// - `display_path` - 专辑的显示名称，用于调试输出的文件名
+/// - `options` - 分析选项（`keep_all_streams` 不适用，总是只取第一条音频流）
+///
+/// # 返回值
+/// - `Ok(LoudnessSummary)` - 整张专辑的 LRA 和综合响度
+/// - `Err(LraCalculationError)` - FFmpeg 执行失败或输出无法解析
+pub fn measure_album_loudness(
+    audio_file_paths: &[PathBuf],
+    display_path: &str,
+    options: &CalculationOptions,
+) -> Result<LoudnessSummary, LraCalculationError> {
+    run_ebur128(build_album_command(audio_file_paths), display_path, options)
+}
+
+/// 测量多声道文件缩混后的 LRA (Measure Downmixed LRA)
+///
+/// `--also-downmixed` 使用：先用 ffprobe 读取第一条音频流的声道数，只有多于两个声道的文件
+/// 才再运行一次立体声缩混后的测量。读不到声道数时只保留原始声道的测量值；
+/// 缩混测量失败由调用方决定如何报告，不影响原始测量的结果。调试输出只保留原始测量的那一次。
+///
+/// # 参数
+/// - `audio_file_path` - 音频文件路径
+/// - `display_path` - 显示路径，用于错误信息
+/// - `options` - 原始测量使用的计算选项
+///
+/// # 返回值
+/// - `Ok(Some(LRA))` - 多声道文件缩混后的 LRA
+/// - `Ok(None)` - 不是多声道文件或声道数未知
+/// - `Err(LraCalculationError)` - 缩混测量失败
+pub fn measure_downmixed_lra(
     audio_file_path: &Path,
-) -> Result<f64, Box<dyn std::error::Error + Send + Sync>> {
-    // 构建并执行 FFmpeg 命令
-    // 使用 Command::new 创建子进程，避免 shell 注入攻击
-    let output = Command::new("ffmpeg")
+    display_path: &str,
+    options: &CalculationOptions,
+) -> Result<Option<f64>, LraCalculationError> {
+    let downmix = Downmix::Stereo;
+    if probe_channels(audio_file_path).is_none_or(|channels| channels < downmix.min_source_channels()) {
+        return Ok(None);
+    }
+    let downmixed_options = CalculationOptions { downmix: Some(downmix), dump_sink: None, ..options.clone() };
+    measure_loudness(audio_file_path, display_path, &downmixed_options).map(|summary| Some(summary.lra))
+}
+
+/// 执行 ebur128 命令并解析汇总结果
+fn run_ebur128(
+    mut command: Command,
+    display_path: &str,
+    options: &CalculationOptions,
+) -> Result<LoudnessSummary, LraCalculationError> {
+    let command_line = announce_command(&command, options);
+    let output = command.output().map_err(LraCalculationError::Spawn)?;
+    summarize_ebur128_output(&output, &command_line, display_path, options)
+}
+
+/// 渲染命令行，`--verbose` 时打印出来（只在 `cli` 特性下打印）
+pub(crate) fn announce_command(command: &Command, options: &CalculationOptions) -> String {
+    let command_line = render_command_line(&command.get_program().to_string_lossy(), command.get_args());
+    #[cfg(feature = "cli")]
+    if options.verbose {
+        human_println!("    🔧 FFmpeg 命令: {}", command_line);
+    }
+    #[cfg(not(feature = "cli"))]
+    let _ = options;
+    command_line
+}
+
+/// 处理 ebur128 命令的输出 (Summarize ebur128 Output)
+///
+/// 同步和异步两条执行路径共用：写入调试输出、检查退出状态，再从 stderr 中解析汇总和警告。
+///
+/// # 参数
+/// - `output` - FFmpeg 进程的输出
+/// - `command_line` - 实际执行的命令行，见 [`announce_command`]
+/// - `display_path` - 显示路径，决定调试输出的文件名
+/// - `options` - 计算选项
+pub(crate) fn summarize_ebur128_output(
+    output: &Output,
+    command_line: &str,
+    display_path: &str,
+    options: &CalculationOptions,
+) -> Result<LoudnessSummary, LraCalculationError> {
+    // FFmpeg 的 ebur128 滤波器将分析结果输出到 stderr
+    let stderr_output = String::from_utf8_lossy(&output.stderr);
+    let warnings = extract_ffmpeg_warnings(&stderr_output);
+
+    if let Some(dump_dir) = &options.dump_sink {
+        let dump_path = debug_dump_path(dump_dir, display_path, options.run_id.as_ref());
+        // 调试输出只是辅助信息，写入失败不影响测量；没有 `cli` 特性时不报告
+        let written = write_debug_dump(&dump_path, command_line, output, &warnings);
+        #[cfg(feature = "cli")]
+        if let Err(e) = written {
+            human_eprintln!("⚠️  无法写入调试输出 {}: {}", dump_path.display(), e);
+        }
+        #[cfg(not(feature = "cli"))]
+        let _ = written;
+    }
+
+    ensure_success(output, command_line)?;
+
+    // 解析 LRA 值和综合响度
+    Ok(LoudnessSummary {
+        lra: parse_lra_from_ffmpeg_output(&stderr_output)?,
+        integrated_lufs: parse_integrated_loudness(&stderr_output),
+        duration: parse_input_duration(&stderr_output),
+        true_peak_dbtp: parse_true_peak(&stderr_output),
+        lra_low_lufs: parse_summary_lufs(&stderr_output, &*LRA_LOW_PATTERNS),
+        lra_high_lufs: parse_summary_lufs(&stderr_output, &*LRA_HIGH_PATTERNS),
+        threshold_lufs: parse_summary_lufs(&stderr_output, &*THRESHOLD_PATTERNS),
+        warnings,
+    })
+}
+
+/// 检查 FFmpeg 命令是否成功执行
+///
+/// 失败时保留退出码和 stderr 摘录，便于调用方进行结构化调试。
+pub(crate) fn ensure_success(output: &Output, command_line: &str) -> Result<(), LraCalculationError> {
+    if output.status.success() {
+        return Ok(());
+    }
+    let stderr_preview = String::from_utf8_lossy(&output.stderr);
+    Err(LraCalculationError::NonZeroExit {
+        code: output.status.code(),
+        stderr: truncate_stderr_excerpt(&stderr_preview),
+        command: command_line.to_string(),
+    })
+}
+
+/// 构建 ebur128 分析命令
+///
+/// 默认用 `[0:a:0]` 只把第一条音频流送入滤波器，并用 `-vn -sn -dn` 关闭其他流的输出：
+/// 带封面的 m4a/mp3 不再解码封面图片，视频容器不再解码整条视频轨。
+/// 只有封面这一条"视频流"的文件照常分析其音频流。
+pub(crate) fn build_ebur128_command(audio_file_path: &Path, options: &CalculationOptions) -> Command {
+    ebur128_command(audio_file_path, options, false)
+}
+
+/// 构建 ebur128 分析命令，`report_progress` 时在 stdout 输出 `-progress` 块
+fn ebur128_command(audio_file_path: &Path, options: &CalculationOptions, report_progress: bool) -> Command {
+    // 直接传参创建子进程（不经过 shell），避免 shell 注入攻击
+    let mut command = tool_command("ffmpeg");
+    if report_progress {
+        command.args(["-progress", "pipe:1"]);
+    }
+    let ebur128 = if options.true_peak { "ebur128=peak=true" } else { "ebur128" };
+    // 需要缩混时在 ebur128 之前插入缩混滤镜
+    let analysis = match options.downmix {
+        Some(downmix) => format!("{},{}", downmix.filter(), ebur128),
+        None => ebur128.to_string(),
+    };
+    command
         .arg("-i")
         .arg(audio_file_path)           // 输入文件路径
-        .arg("-filter_complex")
-        .arg("ebur128")                 // EBU R128 响度分析滤波器
+        .arg("-filter_complex");
+    if options.keep_all_streams {
+        command.arg(analysis);          // 由 FFmpeg 自动选择输入流
+    } else {
+        command
+            .arg(format!("[0:a:0]{}", analysis)) // 只分析第一条音频流
+            .args(["-vn", "-sn", "-dn"]); // 跳过视频（含封面）、字幕和数据流
+    }
+    command
         .arg("-f")
         .arg("null")                    // 输出格式为 null，不生成文件
         .arg("-hide_banner")            // 隐藏版本信息，减少输出噪音
         .arg("-loglevel")
         .arg("info")                    // ebur128 的输出在 info 级别
-        .arg("-")                       // 输出到标准输出（被丢弃）
-        .output()                       // 执行命令并等待完成
-        .map_err(|e| {
-            format!(
-                "执行 FFmpeg 命令失败 (文件: {}): {}. 请确保 FFmpeg 已正确安装。",
-                audio_file_path.display(),
-                e
-            )
-        })?;
+        .arg("-");                      // 输出到标准输出（被丢弃）
+    command
+}
 
-    // 检查 FFmpeg 命令是否成功执行
-    if !output.status.success() {
-        let stderr_preview = String::from_utf8_lossy(&output.stderr);
-        return Err(format!(
-            "FFmpeg 分析文件 {} 失败 (退出码: {}). 错误信息: {}",
-            audio_file_path.display(),
-            output.status.code().unwrap_or(-1),
-            stderr_preview.lines().take(3).collect::<Vec<_>>().join("; ")
-        ).into());
+/// 构建专辑合并分析命令，见 [`measure_album_loudness`]
+fn build_album_command(audio_file_paths: &[PathBuf]) -> Command {
+    let mut command = tool_command("ffmpeg");
+    let mut filter_graph = String::new();
+    for (index, path) in audio_file_paths.iter().enumerate() {
+        command.arg("-i").arg(path);
+        filter_graph.push_str(&format!(
+            "[{index}:a:0]aresample=48000,aformat=sample_fmts=fltp:channel_layouts=stereo[a{index}];"
+        ));
     }
+    for index in 0..audio_file_paths.len() {
+        filter_graph.push_str(&format!("[a{index}]"));
+    }
+    filter_graph.push_str(&format!("concat=n={}:v=0:a=1,ebur128", audio_file_paths.len()));
 
-    // 从 stderr 中提取 LRA 值
-    // FFmpeg 的 ebur128 滤波器将分析结果输出到 stderr
-    let stderr_output = String::from_utf8_lossy(&output.stderr);
+    command
+        .arg("-filter_complex")
+        .arg(filter_graph)
+        .args(["-vn", "-sn", "-dn"])
+        .args(["-f", "null", "-hide_banner", "-loglevel", "info", "-"]);
+    command
+}
+
+/// 渲染可复制运行的命令行 (Render Command Line)
+///
+/// 按 POSIX shell 的规则给参数加引号（见 [`ShellSyntax::quote`]）：只含安全字符（字母、数字和 `-_./=:,+@%`）
+/// 的参数原样输出，其他参数（空格、引号、`$`、非 ASCII 字符、空字符串等）
+/// 用单引号包裹，内部的单引号写成 `'\''`。
+///
+/// # 参数
+/// - `program` - 程序名
+/// - `args` - 参数列表；不是有效 UTF-8 的部分按 `to_string_lossy` 替换
+///
+/// # 返回值
+/// - 可以直接粘贴到 sh/bash/zsh 中执行的命令行
+///
+/// # 示例
+/// ```
+/// use lra_calculator_rust::audio::render_command_line;
+///
+/// let line = render_command_line("ffmpeg", ["-i", "My Song's.flac", "-f", "null", "-"]);
+/// assert_eq!(line, r"ffmpeg -i 'My Song'\''s.flac' -f null -");
+/// ```
+pub fn render_command_line<I, S>(program: &str, args: I) -> String
+where
+    I: IntoIterator<Item = S>,
+    S: AsRef<OsStr>,
+{
+    ShellSyntax::Posix.render_command_line(program, args)
+}
+
+/// 调试输出文件名中单个路径组成部分的最大字节数
+///
+/// 常见文件系统的文件名上限是 255 字节，这里给 `.log` 后缀和截断时追加的哈希留出余量。
+const MAX_DUMP_COMPONENT_BYTES: usize = 200;
+
+/// 调试输出文件路径 (Debug Dump Path)
+///
+/// 显示路径按 `/` 和 `\` 拆分，在 `dump_dir` 下重建相同的子目录结构，
+/// 最后一级追加 `.log` 后缀（给出 `run_id` 时为 `.<运行 ID>.log`）。每一级都会被清理：
+/// - Windows 不允许的字符（`<>:"|?*` 和控制字符）替换为 `_`
+/// - 末尾的 `.` 和空格被去掉，`..` 因此不会跳出 `dump_dir`
+/// - 超过 [`MAX_DUMP_COMPONENT_BYTES`] 的部分被截断，并追加原名的哈希以免重名
+///
+/// # 示例
+/// ```
+/// use std::path::Path;
+/// use lra_calculator_rust::audio::debug_dump_path;
+/// use lra_calculator_rust::run_id::RunId;
+///
+/// let path = debug_dump_path(Path::new("dump"), "Live/../take: 1?.wav", None);
+/// assert_eq!(path, Path::new("dump/Live/_/take_ 1_.wav.log"));
+///
+/// let run_id = RunId::parse("20240601-153012-a3f9").unwrap();
+/// let path = debug_dump_path(Path::new("dump"), "01.flac", Some(&run_id));
+/// assert_eq!(path, Path::new("dump/01.flac.20240601-153012-a3f9.log"));
+/// ```
+pub fn debug_dump_path(dump_dir: &Path, display_path: &str, run_id: Option<&RunId>) -> PathBuf {
+    let mut components: Vec<String> = display_path
+        .split(['/', '\\'])
+        .filter(|component| !component.is_empty() && *component != ".")
+        .map(sanitize_dump_component)
+        .collect();
+    if components.is_empty() {
+        components.push("unnamed".to_string());
+    }
+    if let Some(file_name) = components.last_mut() {
+        if let Some(run_id) = run_id {
+            file_name.push('.');
+            file_name.push_str(run_id.as_str());
+        }
+        file_name.push_str(".log");
+    }
+
+    let mut path = dump_dir.to_path_buf();
+    path.extend(components);
+    path
+}
+
+/// 清理调试输出路径中的一级
+fn sanitize_dump_component(component: &str) -> String {
+    let cleaned: String = component
+        .chars()
+        .map(|c| if c.is_control() || "<>:\"|?*".contains(c) { '_' } else { c })
+        .collect();
+    let cleaned = match cleaned.trim_end_matches(['.', ' ']) {
+        "" => "_".to_string(),
+        trimmed => trimmed.to_string(),
+    };
+    if cleaned.len() <= MAX_DUMP_COMPONENT_BYTES {
+        return cleaned;
+    }
+
+    let mut hasher = DefaultHasher::new();
+    component.hash(&mut hasher);
+    let suffix = format!("~{:08x}", hasher.finish() as u32);
+    let mut end = MAX_DUMP_COMPONENT_BYTES - suffix.len();
+    while !cleaned.is_char_boundary(end) {
+        end -= 1;
+    }
+    format!("{}{}", &cleaned[..end], suffix)
+}
 
-    // 解析 LRA 值
-    parse_lra_from_ffmpeg_output(&stderr_output, audio_file_path)
+/// 写入单个文件的调试输出：命令行、退出码、识别出的警告和完整的 stderr
+fn write_debug_dump(dump_path: &Path, command_line: &str, output: &Output, warnings: &[String]) -> std::io::Result<()> {
+    if let Some(parent) = dump_path.parent() {
+        fs::create_dir_all(parent)?;
+    }
+    let mut header = format!(
+        "# 命令: {}\n# 退出码: {}\n",
+        command_line,
+        output.status.code().map_or_else(|| "无 (被信号终止)".to_string(), |code| code.to_string())
+    );
+    for warning in warnings {
+        header.push_str(&format!("# 警告: {}\n", warning));
+    }
+    header.push('\n');
+    let mut contents = header.into_bytes();
+    contents.extend_from_slice(&output.stderr);
+    fs::write(dump_path, contents)
 }
 
+/// ebur128 汇总中 LRA 值的候选模式，按顺序尝试
+///
+/// 不同 FFmpeg 版本的汇总块对齐方式不同（`LRA:         6.4 LU`、`LRA: 6 LU`），
+/// 个别构建还会把整段写成一行 `Loudness range: 6.4 LU`。
+/// 末尾的 `\b` 保证不会把 `LUFS` 当成 `LU`。
+static LRA_PATTERNS: LazyLock<[Regex; 2]> = LazyLock::new(|| {
+    [
+        Regex::new(r"LRA:[ \t]*([\d\.-]+)[ \t]*LU\b").expect("LRA 正则表达式无效"),
+        Regex::new(r"Loudness range:[ \t]*([\d\.-]+)[ \t]*LU\b").expect("LRA 正则表达式无效"),
+    ]
+});
+
 /// 从 FFmpeg 输出中解析 LRA 值 (Parse LRA Value from FFmpeg Output)
 ///
 /// 使用正则表达式从 FFmpeg 的 ebur128 滤波器输出中提取 LRA 值。
 /// 分离这个逻辑可以提高代码的可测试性和可维护性。
 ///
 /// ## 解析策略
-/// - 使用正则表达式匹配 "LRA: X.X LU" 模式
-/// - 支持整数和浮点数格式
-/// - 支持负数（虽然 LRA 通常为正数）
-/// - 使用 `captures_iter().last()` 获取最后一个匹配（最终结果）
+/// - 优先在最后一个 `Summary:` 之后的汇总块中查找，避免取到逐帧日志中的瞬时值
+/// - 没有汇总块（或汇总块中找不到）时在整个输出中查找
+/// - 依次尝试 [`LRA_PATTERNS`] 中的模式，取第一个命中模式的最后一个匹配
+/// - 支持整数和浮点数格式，以及数值前后任意数量的空格
+/// - 正则表达式只编译一次，在所有线程间共享
 ///
 /// # 参数
 /// - `ffmpeg_output` - FFmpeg 的 stderr 输出
-/// - `file_path` - 文件路径（用于错误信息）
 ///
 /// # 返回值
 /// - `Ok(f64)` - 解析得到的 LRA 值
-/// - `Err(...)` - 解析失败的错误
-fn parse_lra_from_ffmpeg_output(
-    ffmpeg_output: &str,
-    file_path: &Path
-) -> Result<f64, Box<dyn std::error::Error + Send + Sync>> {
-    // 编译正则表达式匹配 LRA 值
-    // 模式说明: LRA: 后跟可选空白，然后是数字（可能包含小数点和负号），最后是 LU
-    let re = Regex::new(r"LRA:\s*([\d\.-]+)\s*LU")
-        .map_err(|e| format!("正则表达式编译失败: {}", e))?;
-
-    // 查找所有匹配项，取最后一个（通常是最终的汇总结果）
-    if let Some(caps) = re.captures_iter(ffmpeg_output).last() {
-        if let Some(lra_match) = caps.get(1) {
-            let lra_str = lra_match.as_str();
-            return lra_str.parse::<f64>().map_err(|e| {
-                format!(
-                    "解析 LRA 值 '{}' 失败 (来自文件 {}): {}",
-                    lra_str,
-                    file_path.display(),
-                    e
-                ).into()
-            });
-        }
+/// - `Err(LraCalculationError::ParseFailed)` - 输出中没有 LRA 值
+/// - `Err(LraCalculationError::InvalidValue)` - LRA 值不是有效数字
+pub(crate) fn parse_lra_from_ffmpeg_output(ffmpeg_output: &str) -> Result<f64, LraCalculationError> {
+    if let Some(lra_str) = find_summary_value(ffmpeg_output, &*LRA_PATTERNS) {
+        return lra_str.parse::<f64>().map_err(|source| LraCalculationError::InvalidValue {
+            value: lra_str.to_string(),
+            source,
+        });
     }
 
-    // 如果没有找到 LRA 值，提供详细的错误信息
-    Err(format!(
-        "无法从 FFmpeg 输出中解析文件 {} 的 LRA 值。\n\
-         这可能是因为：\n\
-         1. 音频文件格式不支持或已损坏\n\
-         2. 音频文件时长过短（需要至少几秒钟）\n\
-         3. FFmpeg 版本不兼容\n\
-         \n\
-         FFmpeg 输出摘要: {}",
-        file_path.display(),
-        ffmpeg_output.lines()
+    // 如果没有找到 LRA 值，保留输出摘要用于诊断
+    Err(LraCalculationError::ParseFailed {
+        output_excerpt: ffmpeg_output
+            .lines()
             .filter(|line| !line.trim().is_empty())
             .take(5)
             .collect::<Vec<_>>()
-            .join("; ")
-    ).into())
+            .join("; "),
+    })
+}
+
+/// ebur128 汇总中综合响度的候选模式，按顺序尝试
+///
+/// 多行汇总块中是 `Integrated loudness:` 标题下的 `I:         -19.5 LUFS`，
+/// 单行格式是 `Integrated loudness: -23.0 LUFS`。完全静音的文件可能输出 `-inf`。
+static INTEGRATED_PATTERNS: LazyLock<[Regex; 2]> = LazyLock::new(|| {
+    [
+        Regex::new(r"\bI:[ \t]*(-?[\d\.]+|-inf)[ \t]*LUFS").expect("综合响度正则表达式无效"),
+        Regex::new(r"Integrated loudness:[ \t]*(-?[\d\.]+|-inf)[ \t]*LUFS").expect("综合响度正则表达式无效"),
+    ]
+});
+
+/// 从 FFmpeg 输出中解析综合响度 (Parse Integrated Loudness)
+///
+/// 与 LRA 使用相同的查找顺序（先汇总块，再整个输出）。综合响度只用于
+/// ReplayGain 等附加信息，找不到或无法解析时返回 `None`，不视为分析失败。
+///
+/// # 返回值
+/// - `Some(f64)` - 综合响度（LUFS），静音文件可能为负无穷
+/// - `None` - 输出中没有综合响度
+pub(crate) fn parse_integrated_loudness(ffmpeg_output: &str) -> Option<f64> {
+    find_summary_value(ffmpeg_output, &*INTEGRATED_PATTERNS)?.parse().ok()
+}
+
+/// ebur128 汇总中的真峰值，`peak=true` 时输出，如
+/// `True peak:` 标题下的 `Peak:         -0.4 dBFS`
+static TRUE_PEAK_PATTERNS: LazyLock<[Regex; 1]> = LazyLock::new(|| {
+    [Regex::new(r"True peak:\s*Peak:[ \t]*(-?[\d\.]+|-inf)[ \t]*dB(?:FS|TP)").expect("真峰值正则表达式无效")]
+});
+
+/// 从 FFmpeg 输出中解析真峰值 (Parse True Peak)
+///
+/// 与综合响度一样只是附加信息，找不到时返回 `None`。
+///
+/// # 返回值
+/// - `Some(f64)` - 真峰值（dBTP），静音文件可能为负无穷
+/// - `None` - 输出中没有真峰值（没有以 `peak=true` 运行）
+fn parse_true_peak(ffmpeg_output: &str) -> Option<f64> {
+    find_summary_value(ffmpeg_output, &*TRUE_PEAK_PATTERNS)?.parse().ok()
+}
+
+/// ebur128 汇总中的 LRA 下限，如 `LRA low:   -23.5 LUFS`
+static LRA_LOW_PATTERNS: LazyLock<[Regex; 1]> =
+    LazyLock::new(|| [Regex::new(r"LRA low:[ \t]*(-?[\d\.]+|-inf)[ \t]*LUFS").expect("LRA 下限正则表达式无效")]);
+
+/// ebur128 汇总中的 LRA 上限，如 `LRA high:  -17.1 LUFS`
+static LRA_HIGH_PATTERNS: LazyLock<[Regex; 1]> =
+    LazyLock::new(|| [Regex::new(r"LRA high:[ \t]*(-?[\d\.]+|-inf)[ \t]*LUFS").expect("LRA 上限正则表达式无效")]);
+
+/// 综合响度的门限：紧跟在 `I:` 行之后的 `Threshold:` 行。
+/// `Loudness range:` 下还有一个 LRA 计算用的门限，不在这里解析
+static THRESHOLD_PATTERNS: LazyLock<[Regex; 1]> = LazyLock::new(|| {
+    [Regex::new(r"\bI:[ \t]*(?:-?[\d\.]+|-inf)[ \t]*LUFS[ \t]*\r?\n[ \t]*Threshold:[ \t]*(-?[\d\.]+|-inf)[ \t]*LUFS")
+        .expect("门限正则表达式无效")]
+});
+
+/// 从 FFmpeg 输出中解析汇总块中的一个 LUFS 值 (Parse Summary LUFS)
+///
+/// 用于 LRA 上下限和门限。较旧的 FFmpeg 构建不输出这些行，此时返回 `None`；
+/// 静音文件的 `-inf` 同样视为没有值。
+fn parse_summary_lufs(ffmpeg_output: &str, patterns: &[Regex]) -> Option<f64> {
+    find_summary_value(ffmpeg_output, patterns)?.parse().ok().filter(|lufs: &f64| lufs.is_finite())
+}
+
+/// FFmpeg 输入信息中的时长行，如 `  Duration: 01:02:03.45, start: 0.000000, bitrate: 1411 kb/s`
+static DURATION_PATTERN: LazyLock<Regex> = LazyLock::new(|| {
+    Regex::new(r"(?m)^\s*Duration: (\d+):(\d{2}):(\d{2}(?:\.\d+)?)").expect("时长正则表达式无效")
+});
+
+/// 从 FFmpeg 输出中解析输入的总时长 (Parse Input Duration)
+///
+/// FFmpeg 打开每个输入时输出 `Duration:` 行（与 ffprobe 的 `format=duration` 相同），
+/// 多个输入（专辑合并分析）时取各输入之和。只用于统计，找不到时返回 `None`。
+///
+/// # 返回值
+/// - `Some(Duration)` - 输入的总时长
+/// - `None` - 输出中没有可解析的时长（如 `Duration: N/A`）
+fn parse_input_duration(ffmpeg_output: &str) -> Option<Duration> {
+    DURATION_PATTERN
+        .captures_iter(ffmpeg_output)
+        .filter_map(|caps| {
+            let hours: f64 = caps[1].parse().ok()?;
+            let minutes: f64 = caps[2].parse().ok()?;
+            let seconds: f64 = caps[3].parse().ok()?;
+            Some(Duration::from_secs_f64(hours * 3600.0 + minutes * 60.0 + seconds))
+        })
+        .reduce(|total, duration| total + duration)
+}
+
+/// 在 ebur128 输出中查找第一个命中模式的最后一个捕获值
+///
+/// 先在最后一个 `Summary:` 之后的汇总块中查找，找不到时再查找整个输出。
+fn find_summary_value<'a>(ffmpeg_output: &'a str, patterns: &[Regex]) -> Option<&'a str> {
+    let summary = ffmpeg_output
+        .rfind("Summary:")
+        .map(|start| &ffmpeg_output[start..]);
+
+    summary
+        .into_iter()
+        .chain(std::iter::once(ffmpeg_output))
+        .find_map(|scope| {
+            patterns
+                .iter()
+                .find_map(|re| re.captures_iter(scope).last())
+                .and_then(|caps| caps.get(1))
+        })
+        .map(|value| value.as_str())
 }
 
 /// 验证 FFmpeg 是否可用 (Verify FFmpeg Availability)
@@ -329,75 +1334,92 @@ fn parse_lra_from_ffmpeg_output(
 /// 这是一个关键的环境检查，因为整个 LRA 计算依赖于 FFmpeg。
 ///
 /// ## 检查策略
-/// 1. **存在性检查**: 尝试执行 `ffmpeg -version` 命令
-/// 2. **功能性检查**: 验证命令是否成功执行
-/// 3. **版本信息**: 可选地提取版本信息用于兼容性检查
+/// 1. **定位**: 在 PATH 中找到 FFmpeg 的绝对路径，之后的分析都使用这个文件
+///    （见 [`crate::environment::resolve_program`]）
+/// 2. **存在性检查**: 尝试执行 `ffmpeg -version` 命令
+/// 3. **功能性检查**: 验证命令是否成功执行
 ///
 /// ## 错误处理
 /// - 如果 FFmpeg 不存在，返回安装指导信息
 /// - 如果 FFmpeg 存在但无法运行，返回权限或损坏提示
-/// - 成功时显示确认信息并返回 Ok
+/// - 成功时不输出任何内容；版本等详细信息见 [`crate::environment::check_environment_report`]
 ///
 /// # 返回值
-/// - `Ok(())` - FFmpeg 可用且功能正常
+/// - `Ok(PathBuf)` - FFmpeg 可用且功能正常，值为实际使用的可执行文件；不在 PATH 中
+///   （如 Windows 上与程序位于同一目录）时只有程序名 `ffmpeg`
 /// - `Err(AppError::Ffmpeg)` - FFmpeg 不可用或存在问题
 ///
 /// # 使用场景
 /// 通常在 main 函数开始时调用，如果失败则终止程序执行
-pub fn check_ffmpeg_availability() -> Result<(), AppError> {
-    match Command::new("ffmpeg").arg("-version").output() {
-        Ok(output) => {
-            if output.status.success() {
-                // 可选：提取版本信息进行更详细的检查
-                let version_info = extract_ffmpeg_version(&output.stdout);
-                println!("✓ FFmpeg 检测成功{}", version_info);
-                Ok(())
-            } else {
-                Err(AppError::Ffmpeg(
-                    "FFmpeg 存在但无法正常运行。可能的原因：\n\
-                     1. FFmpeg 文件损坏或不完整\n\
-                     2. 缺少必要的系统依赖库\n\
-                     3. 权限不足\n\
-                     请尝试重新安装 FFmpeg。".to_string(),
-                ))
-            }
-        }
-        Err(_) => Err(AppError::Ffmpeg(
-            "未找到 FFmpeg，请确保已安装并添加到 PATH 环境变量中。\n\
-             \n\
-             安装方法：\n\
-             • macOS: brew install ffmpeg\n\
-             • Ubuntu/Debian: sudo apt install ffmpeg\n\
-             • Windows: choco install ffmpeg 或从官网下载\n\
-             • 其他系统: 请访问 https://ffmpeg.org/download.html".to_string(),
-        )),
-    }
+pub fn check_ffmpeg_availability() -> Result<PathBuf, AppError> {
+    probe_ffmpeg().0.ensure_available()?;
+    Ok(resolved_program("ffmpeg").unwrap_or_else(|| PathBuf::from("ffmpeg")))
 }
 
-/// 从 FFmpeg 输出中提取版本信息 (Extract FFmpeg Version Information)
-///
-/// 解析 FFmpeg 版本输出，提取有用的版本信息用于显示。
-/// 这有助于调试兼容性问题。
+/// 自检测试音频的时长（秒）
+pub const SELF_TEST_DURATION_SECS: u32 = 5;
+
+/// 安装自检 (Installation Self-Test)
 ///
-/// # 参数
-/// - `version_output` - FFmpeg -version 命令的输出
+/// "FFmpeg 已安装" 并不保证整个分析流程可用（例如某些构建的 ebur128
+/// 输出格式不同）。自检会在临时目录中用 `ffmpeg -f lavfi -i sine` 生成
+/// 一段测试音频，再走一遍 [`calculate_lra_direct`]，确认能得到有限的 LRA 值。
+/// 临时目录在结束后总会被清理。
 ///
 /// # 返回值
-/// - 格式化的版本信息字符串，如果解析失败则返回空字符串
-fn extract_ffmpeg_version(version_output: &[u8]) -> String {
-    let output_str = String::from_utf8_lossy(version_output);
-
-    // 查找版本行，通常格式为 "ffmpeg version X.X.X ..."
-    if let Some(first_line) = output_str.lines().next() {
-        if first_line.starts_with("ffmpeg version") {
-            // 提取版本号部分
-            if let Some(version_part) = first_line.split_whitespace().nth(2) {
-                return format!(" (版本: {})", version_part);
-            }
-        }
+/// - `Ok(f64)` - 测试音频的 LRA 值
+/// - `Err(SelfTestError)` - 失败的阶段（生成、分析、解析）和原因
+pub fn self_test() -> Result<f64, SelfTestError> {
+    // 目录名唯一，离开作用域时（包括 panic 展开）自动删除
+    let work_dir = tempfile::Builder::new().prefix("lra_self_test_").tempdir().map_err(|e| {
+        SelfTestError::new(SelfTestStage::Generation, format!("无法创建临时目录: {}", e))
+    })?;
+
+    run_self_test_in(work_dir.path())
+}
+
+/// 在指定目录中执行自检的各个阶段
+fn run_self_test_in(work_dir: &Path) -> Result<f64, SelfTestError> {
+    let tone_path = work_dir.join("self_test_tone.wav");
+
+    // 阶段 1: 生成测试音频
+    let output = tool_command("ffmpeg")
+        .args(["-hide_banner", "-nostdin", "-loglevel", "error", "-f", "lavfi", "-i"])
+        .arg(format!("sine=frequency=1000:duration={}", SELF_TEST_DURATION_SECS))
+        .arg("-y")
+        .arg(&tone_path)
+        .output()
+        .map_err(|e| SelfTestError::new(SelfTestStage::Generation, format!("无法启动 FFmpeg: {}", e)))?;
+
+    if !output.status.success() {
+        return Err(SelfTestError::new(
+            SelfTestStage::Generation,
+            format!(
+                "FFmpeg 退出码 {:?}: {}",
+                output.status.code(),
+                truncate_stderr_excerpt(&String::from_utf8_lossy(&output.stderr))
+            ),
+        ));
+    }
+    if !tone_path.is_file() {
+        return Err(SelfTestError::new(
+            SelfTestStage::Generation,
+            "FFmpeg 报告成功但没有生成测试文件".to_string(),
+        ));
     }
 
-    String::new()  // 如果无法解析版本信息，返回空字符串
+    // 阶段 2 和 3: 分析并解析 LRA 值
+    let lra = calculate_lra_direct(&tone_path)
+        .map_err(|e| SelfTestError::new(SelfTestStage::from(&e), e.to_string()))?;
+
+    if !lra.is_finite() {
+        return Err(SelfTestError::new(
+            SelfTestStage::Parsing,
+            format!("解析得到的 LRA 值不是有限数: {}", lra),
+        ));
+    }
+
+    Ok(lra)
 }
 
 #[cfg(test)]
@@ -405,6 +1427,8 @@ mod tests {
     use super::*;
     use std::fs::{self, File};
     use tempfile::TempDir;
+    use crate::case_alias::fold_case;
+    use crate::exclude::record_artifacts;
 
     /// 测试支持的音频格式常量
     #[test]
@@ -566,30 +1590,313 @@ mod tests {
         assert!(found_paths.iter().any(|p| p.contains("audio2.wav")));
     }
 
-    /// 测试 FFmpeg 版本信息提取
+    /// 测试按文件名模式排除历史结果文件
+    #[test]
+    fn test_scan_with_exclude_patterns() {
+        let temp_dir = TempDir::new().expect("无法创建临时目录");
+        let temp_path = temp_dir.path();
+        File::create(temp_path.join("song.mp3")).expect("无法创建测试文件");
+        File::create(temp_path.join("lra_results_2023.mp3")).expect("无法创建测试文件");
+        File::create(temp_path.join("backup.wav")).expect("无法创建测试文件");
+
+        let mut exclude = ExcludeSet::with_default_patterns();
+        exclude.add_path(temp_path.join("backup.wav"));
+        let report = scan_audio_files_with_report(temp_path, &exclude);
+
+        assert_eq!(report.files.len(), 1);
+        assert!(report.files[0].1.contains("song.mp3"));
+        assert_eq!(report.skipped_files, 0);
+
+        // 用户的排除模式排除了全部音频时报告为被过滤，而不是只有生成文件
+        let mut user_exclude = ExcludeSet::with_default_patterns();
+        user_exclude.add_user_pattern("*.mp3").add_user_pattern("*.wav");
+        let report = scan_audio_files_with_report(temp_path, &user_exclude);
+        assert_eq!(report.filtered_by_exclude, 2);
+        assert_eq!(report.excluded_artifacts, 1);
+        assert_eq!(report.empty_reason(), Some(EmptyScanReason::AllFiltered { filtered_files: 2 }));
+    }
+
+    /// 测试 `only` 文件名模式：不匹配的音频文件被过滤，没有匹配的模式会被报告
+    #[test]
+    fn test_scan_with_only_patterns() {
+        let temp_dir = TempDir::new().expect("无法创建临时目录");
+        let temp_path = temp_dir.path();
+        for name in ["take1.WAV", "take2.wav", "mix.flac", "notes.txt"] {
+            File::create(temp_path.join(name)).expect("无法创建测试文件");
+        }
+
+        let options = ScanOptions {
+            only: vec!["take[0-9].wav".to_string(), "*.mp3".to_string()],
+            ..ScanOptions::default()
+        };
+        let report = scan_audio_files_with_options(temp_path, &options);
+        assert_eq!(report.files.len(), 2);
+        assert_eq!(report.filtered_by_pattern, 1);
+        assert_eq!(report.skipped_files, 1);
+        assert_eq!(report.pattern_matches[0], ("take[0-9].wav".to_string(), 2));
+        assert_eq!(report.unmatched_patterns().collect::<Vec<_>>(), vec!["*.mp3"]);
+
+        let options = ScanOptions { only: vec!["*.ogg".to_string()], ..ScanOptions::default() };
+        let report = scan_audio_files_with_options(temp_path, &options);
+        assert_eq!(report.empty_reason(), Some(EmptyScanReason::AllFiltered { filtered_files: 3 }));
+    }
+
+    /// 测试按文件大小过滤
+    #[test]
+    fn test_scan_with_size_filters() {
+        let temp_dir = TempDir::new().expect("无法创建临时目录");
+        let temp_path = temp_dir.path();
+        fs::write(temp_path.join("click.wav"), vec![0u8; 10]).expect("无法创建测试文件");
+        fs::write(temp_path.join("song.flac"), vec![0u8; 100]).expect("无法创建测试文件");
+        fs::write(temp_path.join("master.wav"), vec![0u8; 1000]).expect("无法创建测试文件");
+
+        let options = ScanOptions {
+            min_size: Some(50),
+            max_size: Some(500),
+            ..ScanOptions::default()
+        };
+        let report = scan_audio_files_with_options(temp_path, &options);
+        assert_eq!(report.files.len(), 1);
+        assert!(report.files[0].1.contains("song.flac"));
+        assert_eq!(report.filtered_by_size, 2);
+        // 总大小只计入保留下来的文件
+        assert_eq!(report.total_bytes, 100);
+
+        // 全部被过滤时给出独立的原因
+        let options = ScanOptions { min_size: Some(5000), ..ScanOptions::default() };
+        let report = scan_audio_files_with_options(temp_path, &options);
+        assert_eq!(report.empty_reason(), Some(EmptyScanReason::AllFiltered { filtered_files: 3 }));
+    }
+
+    /// 测试按修改时间过滤
+    #[test]
+    fn test_scan_with_newer_than() {
+        use std::time::Duration;
+
+        let temp_dir = TempDir::new().expect("无法创建临时目录");
+        let temp_path = temp_dir.path();
+        let old = temp_path.join("old.mp3");
+        let fresh = temp_path.join("fresh.mp3");
+        File::create(&old).expect("无法创建测试文件");
+        File::create(&fresh).expect("无法创建测试文件");
+
+        let cutoff = SystemTime::now() - Duration::from_secs(3600);
+        File::options()
+            .write(true)
+            .open(&old)
+            .and_then(|file| file.set_modified(cutoff - Duration::from_secs(86400)))
+            .expect("无法设置修改时间");
+
+        let options = ScanOptions { newer_than: Some(cutoff), ..ScanOptions::default() };
+        let report = scan_audio_files_with_options(temp_path, &options);
+        assert_eq!(report.files.len(), 1);
+        assert!(report.files[0].1.contains("fresh.mp3"));
+        assert_eq!(report.filtered_by_mtime, 1);
+    }
+
+    /// 测试替换后相同的非 UTF-8 文件名得到不同的显示路径
+    #[cfg(target_os = "linux")]
+    #[test]
+    fn test_scan_disambiguates_lossy_display_paths() {
+        use std::os::unix::ffi::OsStrExt;
+
+        let temp_dir = TempDir::new().expect("无法创建临时目录");
+        for name in [&b"a\xff.flac"[..], &b"a\xfe.flac"[..]] {
+            File::create(temp_dir.path().join(OsStr::from_bytes(name))).expect("无法创建测试文件");
+        }
+
+        let report = scan_audio_files_with_options(temp_dir.path(), &ScanOptions::default());
+        let mut display_paths: Vec<&str> = report.files.iter().map(|(_, display)| display.as_str()).collect();
+        display_paths.sort_unstable();
+        assert_eq!(display_paths, ["a\u{FFFD}.flac", "a\u{FFFD}.flac [2]"]);
+        assert_eq!(report.path_collisions.len(), 1);
+        assert_eq!(report.path_collisions[0].display_path, "a\u{FFFD}.flac");
+    }
+
+    /// 测试 `dedup_case_aliases`：同一个文件的大小写别名被跳过，只有大小写不同的不同文件都保留
+    #[test]
+    #[cfg(unix)]
+    fn test_scan_dedups_case_aliases() {
+        let temp_dir = TempDir::new().expect("无法创建临时目录");
+        let temp_path = temp_dir.path();
+        fs::write(temp_path.join("Song.mp3"), b"song").expect("无法创建测试文件");
+        fs::write(temp_path.join("Take.flac"), b"upper").expect("无法创建测试文件");
+        fs::write(temp_path.join("take.flac"), b"lower").expect("无法创建测试文件");
+        if fs::read(temp_path.join("Take.flac")).unwrap() != b"upper" {
+            // 不区分大小写的文件系统中无法构造两个只有大小写不同的路径
+            return;
+        }
+        // 同一个 inode 的两个只有大小写不同的路径，相当于经链接访问同一个文件
+        fs::hard_link(temp_path.join("Song.mp3"), temp_path.join("song.mp3")).expect("无法创建硬链接");
+
+        let report = scan_audio_files_with_options(temp_path, &ScanOptions::default());
+        assert_eq!(report.files.len(), 4);
+        assert!(report.case_aliases.is_empty());
+
+        let options = ScanOptions { dedup_case_aliases: true, ..ScanOptions::default() };
+        let report = scan_audio_files_with_options(temp_path, &options);
+        // 内容不同的 Take.flac 和 take.flac 都保留
+        let mut names: Vec<_> = report.files.iter().map(|(_, display)| display.to_lowercase()).collect();
+        names.sort();
+        assert_eq!(names, ["song.mp3", "take.flac", "take.flac"]);
+        assert_eq!(report.case_aliases.len(), 1);
+        let alias = &report.case_aliases[0];
+        assert_eq!(fold_case(&alias.alias), fold_case(&alias.kept));
+        assert_ne!(alias.alias, alias.kept);
+        assert!(report.files.iter().any(|(path, _)| *path == alias.kept));
+    }
+
+    /// 测试惰性扫描与一次性扫描结果一致，且迭代器只推进到需要的位置
+    #[test]
+    fn test_scan_iter_is_lazy() {
+        let temp_dir = TempDir::new().expect("无法创建临时目录");
+        let temp_path = temp_dir.path();
+        for i in 0..5 {
+            File::create(temp_path.join(format!("track{}.flac", i))).expect("无法创建测试文件");
+        }
+        File::create(temp_path.join("notes.txt")).expect("无法创建测试文件");
+
+        let options = ScanOptions::default();
+        let mut scan = scan_audio_files_iter(temp_path, &options);
+        assert!(scan.next().is_some());
+        assert!(scan.report().entries_seen < 6);
+
+        let mut rest: Vec<_> = scan.by_ref().collect();
+        assert_eq!(rest.len(), 4);
+        let report = scan.into_report();
+        assert_eq!(report.entries_seen, 6);
+        assert_eq!(report.skipped_files, 1);
+        assert!(report.files.is_empty());
+
+        let mut all = scan_audio_files_with_options(temp_path, &options).files;
+        all.sort();
+        rest.sort();
+        assert!(rest.iter().all(|file| all.contains(file)));
+        assert_eq!(all.len(), 5);
+    }
+
+    /// 测试按目录名剪枝
+    #[test]
+    fn test_scan_prunes_directories() {
+        let temp_dir = TempDir::new().expect("无法创建临时目录");
+        let temp_path = temp_dir.path();
+        fs::create_dir_all(temp_path.join("node_modules/pkg")).unwrap();
+        fs::create_dir_all(temp_path.join("Album/.git")).unwrap();
+        File::create(temp_path.join("node_modules/pkg/ding.wav")).expect("无法创建测试文件");
+        File::create(temp_path.join("Album/.git/blob.mp3")).expect("无法创建测试文件");
+        File::create(temp_path.join("Album/song.mp3")).expect("无法创建测试文件");
+
+        let mut options = ScanOptions::default();
+        options.exclude.add_default_pruned_dirs();
+        let report = scan_audio_files_with_options(temp_path, &options);
+        assert_eq!(report.files.len(), 1);
+        assert_eq!(report.pruned_dirs, 2);
+        // 被剪枝的子树不计入已访问条目：Album 和 song.mp3
+        assert_eq!(report.entries_seen, 2);
+
+        // 扫描根目录本身从不剪枝
+        let report = scan_audio_files_with_options(&temp_path.join("node_modules"), &options);
+        assert_eq!(report.files.len(), 1);
+    }
+
+    /// 测试按自定义格式注册表扫描
+    #[test]
+    fn test_scan_with_custom_formats() {
+        let temp_dir = TempDir::new().expect("无法创建临时目录");
+        let temp_path = temp_dir.path();
+        File::create(temp_path.join("live.APE")).expect("无法创建测试文件");
+        File::create(temp_path.join("old.wma")).expect("无法创建测试文件");
+
+        let default_report = scan_audio_files_with_formats(temp_path, &ScanOptions::default(), &FormatRegistry::default());
+        assert_eq!(default_report.files.len(), 1);
+
+        let mut registry = FormatRegistry::default();
+        registry.add("ape").unwrap();
+        registry.remove("wma").unwrap();
+        let report = scan_audio_files_with_formats(temp_path, &ScanOptions::default(), &registry);
+        assert_eq!(report.files.len(), 1);
+        assert!(report.files[0].1.contains("live.APE"));
+        assert_eq!(report.skipped_files, 1);
+        assert!(report.extensionless_files.is_empty());
+    }
+
+    /// 测试没有扩展名的文件会被单独记录
+    #[test]
+    fn test_scan_collects_extensionless_files() {
+        let temp_dir = TempDir::new().expect("无法创建临时目录");
+        let temp_path = temp_dir.path();
+        File::create(temp_path.join("take1")).expect("无法创建测试文件");
+        File::create(temp_path.join(".DS_Store")).expect("无法创建测试文件");
+        File::create(temp_path.join("notes.txt")).expect("无法创建测试文件");
+
+        let report = scan_audio_files_with_report(temp_path, &ExcludeSet::new());
+        assert_eq!(report.skipped_files, 3);
+        assert_eq!(report.extensionless_files.len(), 1);
+        assert!(report.extensionless_files[0].1.contains("take1"));
+    }
+
+    /// 测试原文件是音频的 iCloud 存根会被扫描到，交给分析阶段报告为占位文件
+    #[test]
+    fn test_scan_includes_icloud_stubs() {
+        let temp_dir = TempDir::new().expect("无法创建临时目录");
+        let temp_path = temp_dir.path();
+        File::create(temp_path.join(".Song.flac.icloud")).expect("无法创建测试文件");
+        File::create(temp_path.join(".cover.jpg.icloud")).expect("无法创建测试文件");
+
+        let report = scan_audio_files_with_report(temp_path, &ExcludeSet::new());
+        assert_eq!(report.files.len(), 1);
+        assert_eq!(report.files[0].1, ".Song.flac.icloud");
+        assert_eq!(report.skipped_files, 1);
+    }
+
+    /// 测试扫描报告对空结果原因的区分
     #[test]
-    fn test_extract_ffmpeg_version() {
-        // 测试正常的 FFmpeg 版本输出
-        let version_output = b"ffmpeg version 4.4.2 Copyright (c) 2000-2021 the FFmpeg developers\n";
-        let version_info = extract_ffmpeg_version(version_output);
-        assert_eq!(version_info, " (版本: 4.4.2)");
+    fn test_scan_report_empty_reason() {
+        // 目录确实为空
+        let empty_dir = TempDir::new().expect("无法创建临时目录");
+        let report = scan_audio_files_with_report(empty_dir.path(), &ExcludeSet::new());
+        assert_eq!(report.empty_reason(), Some(EmptyScanReason::EmptyDirectory));
+
+        // 只有本程序生成的文件（结果文件、清单中记录的文件）时不是"目录为空"
+        fs::write(empty_dir.path().join("lra_results.txt"), "").expect("无法创建测试文件");
+        fs::write(empty_dir.path().join("mix_153012.wav"), "").expect("无法创建测试文件");
+        record_artifacts(empty_dir.path(), &[empty_dir.path().join("mix_153012.wav")]).expect("无法写入清单");
+        let report = scan_audio_files_with_report(empty_dir.path(), &ExcludeSet::with_default_patterns());
+        assert_eq!(report.skipped_files, 0);
+        assert_eq!(
+            report.empty_reason(),
+            Some(EmptyScanReason::OnlyGeneratedFiles { excluded_files: 3 })
+        );
+
+        // 有文件但没有支持的音频
+        let no_audio_dir = TempDir::new().expect("无法创建临时目录");
+        File::create(no_audio_dir.path().join("notes.txt")).expect("无法创建测试文件");
+        File::create(no_audio_dir.path().join("cover.jpg")).expect("无法创建测试文件");
+        let report = scan_audio_files_with_report(no_audio_dir.path(), &ExcludeSet::new());
+        assert_eq!(
+            report.empty_reason(),
+            Some(EmptyScanReason::NoSupportedAudio { other_files: 2 })
+        );
 
-        // 测试异常的版本输出
-        let invalid_output = b"some other output\n";
-        let version_info2 = extract_ffmpeg_version(invalid_output);
-        assert_eq!(version_info2, "");
+        // 找到音频文件时没有空结果原因
+        File::create(no_audio_dir.path().join("song.mp3")).expect("无法创建测试文件");
+        let report = scan_audio_files_with_report(no_audio_dir.path(), &ExcludeSet::new());
+        assert_eq!(report.empty_reason(), None);
+        assert_eq!(report.entries_seen, 3);
+        assert_eq!(report.skipped_files, 2);
 
-        // 测试空输出
-        let empty_output = b"";
-        let version_info3 = extract_ffmpeg_version(empty_output);
-        assert_eq!(version_info3, "");
+        // 根目录本身无法读取
+        let missing = no_audio_dir.path().join("missing");
+        let report = scan_audio_files_with_report(&missing, &ExcludeSet::new());
+        assert_eq!(
+            report.empty_reason(),
+            Some(EmptyScanReason::Unreadable { unreadable_paths: 1 })
+        );
     }
 
     /// 测试 LRA 值解析功能
     #[test]
     fn test_parse_lra_from_ffmpeg_output() {
-        let test_path = Path::new("test.mp3");
-
         // 测试正常的 FFmpeg 输出
         let normal_output = r#"
 [Parsed_ebur128_0 @ 0x7f8b8c000000] Summary:
@@ -598,7 +1905,7 @@ mod tests {
 [Parsed_ebur128_0 @ 0x7f8b8c000000] LRA low: -33.2 LUFS
 "#;
 
-        let result = parse_lra_from_ffmpeg_output(normal_output, test_path);
+        let result = parse_lra_from_ffmpeg_output(normal_output);
         assert!(result.is_ok());
         assert_eq!(result.unwrap(), 12.3);
 
@@ -608,8 +1915,8 @@ mod tests {
 [Parsed_ebur128_0 @ 0x7f8b8c000000] Integrated loudness: -23.0 LUFS
 "#;
 
-        let result2 = parse_lra_from_ffmpeg_output(no_lra_output, test_path);
-        assert!(result2.is_err());
+        let result2 = parse_lra_from_ffmpeg_output(no_lra_output);
+        assert!(matches!(result2, Err(LraCalculationError::ParseFailed { .. })));
 
         // 测试多个 LRA 值（应该取最后一个）
         let multiple_lra_output = r#"
@@ -618,8 +1925,164 @@ mod tests {
 [Parsed_ebur128_0 @ 0x7f8b8c000000] LRA: 15.7 LU
 "#;
 
-        let result3 = parse_lra_from_ffmpeg_output(multiple_lra_output, test_path);
+        let result3 = parse_lra_from_ffmpeg_output(multiple_lra_output);
         assert!(result3.is_ok());
         assert_eq!(result3.unwrap(), 15.7);
+
+        // 测试无效的数值（匹配到了 LRA 字段，但不是数字）
+        let invalid_value_output = "[Parsed_ebur128_0 @ 0x7f8b8c000000] LRA: 1.2.3 LU";
+        let result4 = parse_lra_from_ffmpeg_output(invalid_value_output);
+        match result4 {
+            Err(LraCalculationError::InvalidValue { value, .. }) => assert_eq!(value, "1.2.3"),
+            other => panic!("期望得到 InvalidValue 错误, 实际为 {:?}", other),
+        }
+    }
+
+    /// 测试命令行渲染的引号规则
+    #[test]
+    fn test_render_command_line() {
+        assert_eq!(
+            render_command_line("ffmpeg", ["-i", "/music/a.flac", "-filter_complex", "ebur128", "-"]),
+            "ffmpeg -i /music/a.flac -filter_complex ebur128 -"
+        );
+        assert_eq!(render_command_line("ffmpeg", ["My Song.flac"]), "ffmpeg 'My Song.flac'");
+        assert_eq!(render_command_line("ffmpeg", ["it's"]), r"ffmpeg 'it'\''s'");
+        assert_eq!(render_command_line("ffmpeg", ["\"quoted\" $HOME"]), r#"ffmpeg '"quoted" $HOME'"#);
+        assert_eq!(render_command_line("ffmpeg", ["专辑/01 晴天.flac"]), "ffmpeg '专辑/01 晴天.flac'");
+        assert_eq!(render_command_line("ffmpeg", [""]), "ffmpeg ''");
+        assert_eq!(render_command_line("ffmpeg", Vec::<&str>::new()), "ffmpeg");
+
+        // 渲染结果交给 sh 执行后，每个参数都应原样还原
+        #[cfg(unix)]
+        {
+            let args = ["%s|", "a b", "it's", "\"$HOME\"", "专辑 `x`", "", "back\\slash", "*.flac"];
+            let line = render_command_line("printf", args);
+            let output = Command::new("sh").arg("-c").arg(&line).output().expect("无法执行 sh");
+            let expected: String = args.iter().skip(1).map(|arg| format!("{}|", arg)).collect();
+            assert_eq!(String::from_utf8_lossy(&output.stdout), expected);
+        }
+    }
+
+    /// 测试 ebur128 命令默认只分析第一条音频流
+    #[test]
+    fn test_build_ebur128_command() {
+        let args_of = |options: &CalculationOptions| {
+            build_ebur128_command(Path::new("cover.m4a"), options)
+                .get_args()
+                .map(|arg| arg.to_string_lossy().into_owned())
+                .collect::<Vec<_>>()
+        };
+
+        let default_args = args_of(&CalculationOptions::default());
+        assert_eq!(
+            default_args,
+            [
+                "-i", "cover.m4a", "-filter_complex", "[0:a:0]ebur128", "-vn", "-sn", "-dn",
+                "-f", "null", "-hide_banner", "-loglevel", "info", "-",
+            ]
+        );
+
+        let all_streams = args_of(&CalculationOptions { keep_all_streams: true, ..Default::default() });
+        assert!(all_streams.contains(&"ebur128".to_string()));
+        assert!(!all_streams.iter().any(|arg| arg == "-vn" || arg.contains("0:a:0")));
+
+        let true_peak = CalculationOptions { true_peak: true, ..Default::default() };
+        assert_eq!(args_of(&true_peak)[3], "[0:a:0]ebur128=peak=true");
+
+        // 缩混滤镜插在 ebur128 之前
+        let downmix = CalculationOptions { downmix: Downmix::parse("stereo"), ..Default::default() };
+        assert_eq!(args_of(&downmix)[3], "[0:a:0]aformat=channel_layouts=stereo,ebur128");
+        let downmix_all = CalculationOptions { keep_all_streams: true, ..downmix };
+        assert_eq!(args_of(&downmix_all)[3], "aformat=channel_layouts=stereo,ebur128");
+        assert_eq!(Downmix::parse("5.1"), None);
+
+        // 专辑合并分析：每个输入统一格式后拼接
+        let album_args: Vec<String> = build_album_command(&[PathBuf::from("01.flac"), PathBuf::from("02.mp3")])
+            .get_args()
+            .map(|arg| arg.to_string_lossy().into_owned())
+            .collect();
+        assert_eq!(&album_args[..4], ["-i", "01.flac", "-i", "02.mp3"]);
+        assert_eq!(
+            album_args[5],
+            "[0:a:0]aresample=48000,aformat=sample_fmts=fltp:channel_layouts=stereo[a0];\
+             [1:a:0]aresample=48000,aformat=sample_fmts=fltp:channel_layouts=stereo[a1];\
+             [a0][a1]concat=n=2:v=0:a=1,ebur128"
+        );
+    }
+
+    /// 测试调试输出文件名的清理
+    #[test]
+    fn test_debug_dump_path() {
+        let dir = Path::new("dump");
+        assert_eq!(debug_dump_path(dir, "Album/01.flac", None), dir.join("Album").join("01.flac.log"));
+        assert_eq!(debug_dump_path(dir, "Album\\CD1\\01.flac", None), dir.join("Album/CD1/01.flac.log"));
+        assert_eq!(debug_dump_path(dir, "./../a|b. ", None), dir.join("_").join("a_b.log"));
+        assert_eq!(debug_dump_path(dir, "", None), dir.join("unnamed.log"));
+        let run_id = RunId::parse("20240601-153012-a3f9").unwrap();
+        assert_eq!(debug_dump_path(dir, "", Some(&run_id)), dir.join("unnamed.20240601-153012-a3f9.log"));
+
+        // 超长文件名被截断到上限内，不同的原名得到不同的结果
+        let long_a = format!("{}a.flac", "长".repeat(100));
+        let long_b = format!("{}b.flac", "长".repeat(100));
+        let dumped_a = debug_dump_path(dir, &long_a, None);
+        let name_a = dumped_a.file_name().unwrap().to_str().unwrap();
+        assert!(name_a.len() <= MAX_DUMP_COMPONENT_BYTES + ".log".len());
+        assert!(name_a.ends_with(".log"));
+        assert_ne!(dumped_a, debug_dump_path(dir, &long_b, None));
+    }
+
+    /// 测试不同 FFmpeg 版本的 ebur128 汇总输出
+    #[test]
+    fn test_parse_lra_from_ffmpeg_fixtures() {
+        let cases = [
+            ("FFmpeg 4.x", include_str!("../tests/fixtures/ebur128_ffmpeg4.txt"), 6.4, -19.5, 3000),
+            ("FFmpeg 5.x", include_str!("../tests/fixtures/ebur128_ffmpeg5.txt"), 11.8, -16.3, 4000),
+            ("FFmpeg 6.x", include_str!("../tests/fixtures/ebur128_ffmpeg6.txt"), 6.0, -22.9, 5020),
+            ("FFmpeg 7.x", include_str!("../tests/fixtures/ebur128_ffmpeg7.txt"), 7.9, -14.1, 6000),
+            ("旧版构建", include_str!("../tests/fixtures/ebur128_no_range_bounds.txt"), 5.2, -20.9, 4000),
+        ];
+        for (version, output, expected, integrated, duration_ms) in cases {
+            match parse_lra_from_ffmpeg_output(output) {
+                Ok(lra) => assert_eq!(lra, expected, "{}", version),
+                Err(e) => panic!("{} 的输出解析失败: {}", version, e),
+            }
+            // 综合响度取汇总块中的值，而不是逐帧日志中的瞬时值
+            assert_eq!(parse_integrated_loudness(output), Some(integrated), "{}", version);
+            assert_eq!(parse_input_duration(output), Some(Duration::from_millis(duration_ms)), "{}", version);
+        }
+        let album = "  Duration: 01:00:00.50, bitrate: 1411 kb/s\n  Duration: 00:30:00.00, bitrate: 1411 kb/s\n";
+        assert_eq!(parse_input_duration(album), Some(Duration::from_millis(5_400_500)));
+        assert_eq!(parse_input_duration("  Duration: N/A, bitrate: N/A"), None);
+        assert_eq!(
+            parse_integrated_loudness("[Parsed_ebur128_0 @ 0x1] Integrated loudness: -23.0 LUFS"),
+            Some(-23.0)
+        );
+        assert_eq!(parse_integrated_loudness("Summary:\n    I:         -inf LUFS"), Some(f64::NEG_INFINITY));
+        let true_peak = "Summary:\n  Integrated loudness:\n    I: -9.1 LUFS\n\n  True peak:\n    Peak:        0.4 dBFS\n";
+        assert_eq!(parse_true_peak(true_peak), Some(0.4));
+        assert_eq!(parse_true_peak("[Parsed_ebur128_0 @ 0x1] t: 0.4 TPK: -1.0 -0.8 dBFS"), None);
+
+        // LRA 上下限和综合响度门限（不是 LRA 的门限）；旧版构建没有这些行
+        let bounds = |output: &str| {
+            [&*LRA_LOW_PATTERNS, &*LRA_HIGH_PATTERNS, &*THRESHOLD_PATTERNS].map(|patterns| parse_summary_lufs(output, patterns))
+        };
+        assert_eq!(
+            bounds(include_str!("../tests/fixtures/ebur128_ffmpeg4.txt")),
+            [Some(-23.5), Some(-17.1), Some(-29.8)]
+        );
+        assert_eq!(
+            bounds(include_str!("../tests/fixtures/ebur128_ffmpeg7.txt")),
+            [Some(-18.6), Some(-10.7), Some(-24.2)]
+        );
+        assert_eq!(bounds(include_str!("../tests/fixtures/ebur128_no_range_bounds.txt")), [None; 3]);
+
+        // 汇总块中没有 LRA 时仍然返回带输出摘录的解析错误
+        let truncated = include_str!("../tests/fixtures/ebur128_truncated.txt");
+        match parse_lra_from_ffmpeg_output(truncated) {
+            Err(LraCalculationError::ParseFailed { output_excerpt }) => {
+                assert!(output_excerpt.starts_with("Input #0, wav, from 'broken.wav':"));
+            }
+            other => panic!("期望得到 ParseFailed 错误, 实际为 {:?}", other),
+        }
     }
 }
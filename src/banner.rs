@@ -0,0 +1,246 @@
+//! 横幅和使用建议模块 (Banner Module)
+//!
+//! 交互运行时开始和结束时显示的欢迎横幅、完成横幅和 LRA 使用建议。写进日志文件或作为服务运行时
+//! 这些装饰只是噪音：[`show_banners`] 在 `-q` / `--quiet` 或人类可读输出不是终端时返回 `false`，
+//! 此时只输出结果文件位置这样的必要信息。
+//!
+//! 使用建议中的分界值取自 [`LraDistribution`] 的分类阈值，与分布统计的分类始终一致；
+//! `--advice-file` 可以用自定义文本替换整段建议（文件为空时不显示建议）。
+//!
+//! 横幅函数只返回字符串，由调用方输出，便于测试；`--ascii` 时按 [`Charset::Ascii`] 去掉模板中的 emoji，
+//! 传入的结果位置和 `--advice-file` 的内容原样输出。
+
+use std::fmt;
+
+use chrono::{DateTime, TimeZone};
+
+use crate::console::{Charset, ColorMode, Style};
+use crate::processor::LraDistribution;
+use crate::run_id::RunId;
+
+/// 横幅中时间的显示格式
+const TIME_FORMAT: &str = "%Y-%m-%d %H:%M:%S";
+
+/// 是否显示横幅和使用建议 (Show Banners)
+///
+/// # 参数
+/// - `quiet` - 是否指定了 `-q` / `--quiet`
+/// - `is_terminal` - 人类可读输出的目标是否为终端
+///
+/// # 示例
+/// ```
+/// use lra_calculator_rust::banner::show_banners;
+///
+/// assert!(show_banners(false, true));
+/// assert!(!show_banners(true, true));
+/// assert!(!show_banners(false, false));
+/// ```
+pub fn show_banners(quiet: bool, is_terminal: bool) -> bool {
+    !quiet && is_terminal
+}
+
+/// 欢迎横幅 (Welcome Banner)
+///
+/// # 参数
+/// - `run_id` - 本次运行的 ID，与结果文件等产物中记录的相同
+/// - `started` - 启动时间
+/// - `charset` - 输出字符集
+///
+/// # 返回值
+/// - 多行文本，以空行结尾
+pub fn welcome_banner<Tz: TimeZone>(run_id: &RunId, started: DateTime<Tz>, charset: Charset) -> String
+where
+    Tz::Offset: fmt::Display,
+{
+    [
+        format_with_charset!(charset, "🎵 =========================================="),
+        format_with_charset!(charset, "🎵   LRA 音频响度范围计算器"),
+        format_with_charset!(charset, "🎵   高性能版 - 基于 FFmpeg 直接分析"),
+        format_with_charset!(charset, "🎵 =========================================="),
+        format_with_charset!(charset, "📅 启动时间: {}", started.format(TIME_FORMAT)),
+        format_with_charset!(charset, "🆔 运行 ID: {}", run_id),
+        format_with_charset!(charset, "🔧 基于 EBU R128 标准进行精确 LRA 计算"),
+        format_with_charset!(charset, "⚡ 支持多线程并行处理，充分利用 CPU 资源"),
+        String::new(),
+    ]
+    .join("\n")
+}
+
+/// 完成横幅 (Completion Banner)
+///
+/// # 参数
+/// - `results_location` - 结果位置的说明，如 `📄 结果文件位置: ...`；已按字符集格式化，原样输出
+/// - `sort_description` - 结果排序方式的说明
+/// - `run_id` - 本次运行的 ID
+/// - `finished` - 完成时间
+/// - `advice` - 使用建议，见 [`lra_advice`]；为空时不显示，原样输出
+/// - `mode` - 颜色模式
+/// - `charset` - 输出字符集
+///
+/// # 返回值
+/// - 多行文本，以空行开头
+pub fn completion_banner<Tz: TimeZone>(
+    results_location: &str,
+    sort_description: &str,
+    run_id: &RunId,
+    finished: DateTime<Tz>,
+    advice: &str,
+    mode: ColorMode,
+    charset: Charset,
+) -> String
+where
+    Tz::Offset: fmt::Display,
+{
+    let mut lines = vec![
+        format_with_charset!(charset, "\n🎉 =========================================="),
+        mode.paint(Style::Success, format_with_charset!(charset, "🎉   所有操作已成功完成！")),
+        format_with_charset!(charset, "🎉 =========================================="),
+        results_location.to_string(),
+        format_with_charset!(charset, "📊 文件已按{}排序", sort_description),
+        format_with_charset!(charset, "🆔 运行 ID: {}", run_id),
+    ];
+    if !advice.is_empty() {
+        lines.push(advice.to_string());
+    }
+    lines.push(format_with_charset!(charset, "⏰ 完成时间: {}", finished.format(TIME_FORMAT)));
+    lines.push(format_with_charset!(charset, "🎵 感谢使用 LRA 计算器！"));
+    lines.join("\n")
+}
+
+/// LRA 使用建议 (LRA Advice)
+///
+/// 分界值与 [`LraDistribution::HIGH_THRESHOLD`] 和 [`LraDistribution::LOW_THRESHOLD`] 一致。
+///
+/// # 参数
+/// - `custom` - `--advice-file` 的内容；指定时原样替换默认建议（去掉末尾的空白，不按字符集转换）
+/// - `mode` - 颜色模式
+/// - `charset` - 输出字符集
+///
+/// # 示例
+/// ```
+/// use lra_calculator_rust::banner::lra_advice;
+/// use lra_calculator_rust::console::{Charset, ColorMode};
+///
+/// assert!(lra_advice(None, ColorMode::Plain, Charset::Unicode).contains("LRA > 15 LU: 动态范围丰富"));
+/// assert!(lra_advice(None, ColorMode::Plain, Charset::Ascii).starts_with("[HINT] 使用建议:\n   * LRA > 15 LU"));
+/// assert_eq!(lra_advice(Some("母带请控制在 6–10 LU\n"), ColorMode::Plain, Charset::Ascii), "母带请控制在 6–10 LU");
+/// ```
+pub fn lra_advice(custom: Option<&str>, mode: ColorMode, charset: Charset) -> String {
+    if let Some(text) = custom {
+        return text.trim_end().to_string();
+    }
+    let (high, low) = (LraDistribution::HIGH_THRESHOLD, LraDistribution::LOW_THRESHOLD);
+    [
+        format_with_charset!(charset, "💡 使用建议:"),
+        format_with_charset!(charset, "   • {}", mode.paint(Style::LraHigh, format!("LRA > {} LU: 动态范围丰富（古典、爵士）", high))),
+        format_with_charset!(charset, "   • {}", mode.paint(Style::LraMedium, format!("LRA {}-{} LU: 适中动态范围（摇滚、民谣）", low, high))),
+        format_with_charset!(charset, "   • {}", mode.paint(Style::LraLow, format!("LRA < {} LU: 动态范围较小（流行、播客）", low))),
+    ]
+    .join("\n")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::Utc;
+
+    fn time() -> DateTime<Utc> {
+        Utc.with_ymd_and_hms(2024, 3, 1, 9, 30, 0).unwrap()
+    }
+
+    /// 测试欢迎横幅包含运行 ID 和启动时间
+    #[test]
+    fn test_welcome_banner() {
+        let run_id = RunId::parse("20240301-093000-abcd").unwrap();
+        let banner = welcome_banner(&run_id, time(), Charset::Unicode);
+        assert!(banner.starts_with("🎵 ====="));
+        assert!(banner.contains("📅 启动时间: 2024-03-01 09:30:00\n"));
+        assert!(banner.contains("🆔 运行 ID: 20240301-093000-abcd\n"));
+        assert!(banner.ends_with('\n'));
+    }
+
+    /// 测试两种字符集下的横幅与黄金文件一致，ASCII 版本不含任何非 ASCII 的符号
+    #[test]
+    fn test_golden_banners() {
+        let run_id = RunId::parse("20240301-093000-abcd").unwrap();
+        let render = |charset| {
+            let advice = lra_advice(None, ColorMode::Plain, charset);
+            let completion = completion_banner(
+                &format_with_charset!(charset, "📄 结果文件位置: {}", "/music/lra_results.txt"),
+                "LRA 值从高到低",
+                &run_id,
+                time(),
+                &advice,
+                ColorMode::Plain,
+                charset,
+            );
+            format!("{}{}\n", welcome_banner(&run_id, time(), charset), completion)
+        };
+        assert_eq!(render(Charset::Unicode), include_str!("../tests/fixtures/golden_banners.txt"));
+        let ascii = render(Charset::Ascii);
+        assert_eq!(ascii, include_str!("../tests/fixtures/golden_banners_ascii.txt"));
+        assert!(ascii.chars().all(|ch| ch.is_ascii() || ('\u{4e00}'..='\u{9fff}').contains(&ch) || "，（）！、".contains(ch)));
+    }
+
+    /// 测试 ASCII 模式只转换横幅的模板，结果位置中的路径和自定义建议原样输出
+    #[test]
+    fn test_ascii_banner_keeps_user_text() {
+        let run_id = RunId::parse("20240301-093000-abcd").unwrap();
+        let location = format_with_charset!(Charset::Ascii, "📄 结果文件位置: {}", "/music/Mozart · Requiem — Live 🎵/lra_results.txt");
+        let banner = completion_banner(
+            &location,
+            "LRA 值从高到低",
+            &run_id,
+            time(),
+            &lra_advice(Some("📐 母带标准: 6–10 LU"), ColorMode::Plain, Charset::Ascii),
+            ColorMode::Plain,
+            Charset::Ascii,
+        );
+        assert!(banner.contains("\n结果文件位置: /music/Mozart · Requiem — Live 🎵/lra_results.txt\n"));
+        assert!(banner.contains("\n📐 母带标准: 6–10 LU\n"));
+        assert!(banner.ends_with("\n感谢使用 LRA 计算器！"));
+    }
+
+    /// 测试完成横幅中的建议可以替换或省略
+    #[test]
+    fn test_completion_banner_advice() {
+        let run_id = RunId::parse("20240301-093000-abcd").unwrap();
+        let banner = |advice: &str| {
+            completion_banner(
+                "📄 结果文件位置: /music/lra_results.txt",
+                "LRA 值从高到低",
+                &run_id,
+                time(),
+                advice,
+                ColorMode::Plain,
+                Charset::Unicode,
+            )
+        };
+
+        let default = banner(&lra_advice(None, ColorMode::Plain, Charset::Unicode));
+        assert!(default.contains("📄 结果文件位置: /music/lra_results.txt\n📊 文件已按LRA 值从高到低排序\n"));
+        assert!(default.contains(
+            "💡 使用建议:\n   • LRA > 15 LU: 动态范围丰富（古典、爵士）\n   • LRA 8-15 LU: 适中动态范围（摇滚、民谣）\n   • LRA < 8 LU: 动态范围较小（流行、播客）\n⏰ 完成时间: 2024-03-01 09:30:00"
+        ));
+
+        let custom = banner(&lra_advice(Some("📐 本工作室母带标准: 6-10 LU\n\n"), ColorMode::Plain, Charset::Unicode));
+        assert!(custom.contains("🆔 运行 ID: 20240301-093000-abcd\n📐 本工作室母带标准: 6-10 LU\n⏰ 完成时间"));
+        assert!(!custom.contains("使用建议"));
+
+        let silent = banner(&lra_advice(Some("\n"), ColorMode::Plain, Charset::Unicode));
+        assert!(silent.contains("🆔 运行 ID: 20240301-093000-abcd\n⏰ 完成时间"));
+    }
+
+    /// 测试默认建议的分界值与分布统计的分类阈值一致
+    #[test]
+    fn test_advice_matches_distribution_thresholds() {
+        let advice = lra_advice(None, ColorMode::Plain, Charset::Unicode);
+        let high = LraDistribution::HIGH_THRESHOLD;
+        let low = LraDistribution::LOW_THRESHOLD;
+        assert!(advice.contains(&format!("LRA > {} LU", high)));
+        assert!(advice.contains(&format!("LRA {}-{} LU", low, high)));
+        assert!(advice.contains(&format!("LRA < {} LU", low)));
+        assert_eq!(LraDistribution::from_values(&[high + 0.1]).unwrap().high_count, 1);
+        assert_eq!(LraDistribution::from_values(&[low - 0.1]).unwrap().low_count, 1);
+    }
+}
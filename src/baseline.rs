@@ -0,0 +1,173 @@
+//! 基线比较模块 (Baseline Comparison Module)
+//!
+//! 在 CI 中用于回归检测：处理完成后把本次结果与提交到仓库的基线结果文件按路径比较
+//! （`--baseline <文件>`），LRA 变化超过 `--regression-threshold` 的条目视为回归，
+//! 程序以独立的退出码失败（见 [`crate::error::AppError::RegressionDetected`]）。
+//!
+//! 比较逻辑是纯函数 [`compare_with_baseline`]，不涉及文件或输出，便于测试。
+
+use std::collections::{HashMap, HashSet};
+use std::fmt;
+
+use crate::console::Glyph;
+use crate::file_key::FileKey;
+
+/// 默认的回归阈值（LU）
+pub const DEFAULT_REGRESSION_THRESHOLD: f64 = 1.0;
+
+/// 小于该差值的变化视为相同（结果文件只保留一位小数）
+const CHANGE_EPSILON: f64 = 0.05;
+
+/// LRA 发生变化的条目 (Baseline Change)
+#[derive(Debug, Clone, PartialEq)]
+pub struct BaselineChange {
+    /// 文件显示路径
+    pub path: FileKey,
+    /// 基线中的 LRA 值
+    pub old: f64,
+    /// 本次的 LRA 值
+    pub new: f64,
+    /// 变化量是否超过回归阈值
+    pub regression: bool,
+}
+
+impl BaselineChange {
+    /// 变化量（本次减去基线）
+    pub fn delta(&self) -> f64 {
+        self.new - self.old
+    }
+}
+
+impl fmt::Display for BaselineChange {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}: {:.1} {} {:.1} LU ({:+.1})", self.path, self.old, Glyph::Arrow, self.new, self.delta())
+    }
+}
+
+/// 与基线比较的结果 (Baseline Comparison)
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct BaselineComparison {
+    /// LRA 发生变化的条目，按本次结果的顺序
+    pub changed: Vec<BaselineChange>,
+    /// 基线中没有的新条目
+    pub new: Vec<(FileKey, f64)>,
+    /// 基线中有、本次结果中没有的条目
+    pub missing: Vec<FileKey>,
+}
+
+impl BaselineComparison {
+    /// 变化超过回归阈值的条目
+    pub fn regressions(&self) -> impl Iterator<Item = &BaselineChange> {
+        self.changed.iter().filter(|change| change.regression)
+    }
+
+    /// 本次结果与基线完全一致
+    pub fn is_unchanged(&self) -> bool {
+        self.changed.is_empty() && self.new.is_empty() && self.missing.is_empty()
+    }
+}
+
+/// 与基线结果比较 (Compare with Baseline)
+///
+/// 按路径匹配条目。变化量的绝对值小于 0.05 LU（结果文件的精度以下）时视为未变化；
+/// 大于 `threshold` 时视为回归，等于阈值不算回归。
+///
+/// # 参数
+/// - `baseline` - 基线结果：(显示路径, LRA 值)
+/// - `current` - 本次结果
+/// - `threshold` - 回归阈值（LU）
+///
+/// # 返回值
+/// - 变化、新增和缺失的条目；变化和新增按 `current` 的顺序，缺失按 `baseline` 的顺序
+///
+/// # 示例
+/// ```
+/// use lra_calculator_rust::baseline::compare_with_baseline;
+///
+/// let baseline = vec![("ep1.mp3".into(), 6.0), ("ep2.mp3".into(), 7.0)];
+/// let current = vec![("ep1.mp3".into(), 8.5), ("ep2.mp3".into(), 7.0), ("ep3.mp3".into(), 5.0)];
+/// let comparison = compare_with_baseline(&baseline, &current, 1.0);
+/// assert_eq!(comparison.regressions().count(), 1);
+/// assert_eq!(comparison.new, vec![("ep3.mp3".into(), 5.0)]);
+/// ```
+pub fn compare_with_baseline(
+    baseline: &[(FileKey, f64)],
+    current: &[(FileKey, f64)],
+    threshold: f64,
+) -> BaselineComparison {
+    let baseline_by_path: HashMap<&FileKey, f64> = baseline.iter().map(|(path, lra)| (path, *lra)).collect();
+    let mut comparison = BaselineComparison::default();
+
+    for (path, lra) in current {
+        match baseline_by_path.get(path) {
+            Some(&old) if (lra - old).abs() >= CHANGE_EPSILON => comparison.changed.push(BaselineChange {
+                path: path.clone(),
+                old,
+                new: *lra,
+                regression: (lra - old).abs() > threshold,
+            }),
+            Some(_) => {}
+            None => comparison.new.push((path.clone(), *lra)),
+        }
+    }
+
+    let current_paths: HashSet<&FileKey> = current.iter().map(|(path, _)| path).collect();
+    comparison.missing = baseline
+        .iter()
+        .filter(|(path, _)| !current_paths.contains(path))
+        .map(|(path, _)| path.clone())
+        .collect();
+
+    comparison
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn entries(items: &[(&str, f64)]) -> Vec<(FileKey, f64)> {
+        items.iter().map(|(path, lra)| ((*path).into(), *lra)).collect()
+    }
+
+    /// 测试各种变化量与阈值的组合
+    #[test]
+    fn test_compare_with_baseline_table() {
+        // (基线值, 本次值, 阈值, 是否变化, 是否回归)
+        let cases = [
+            (6.0, 6.0, 1.0, false, false),
+            (6.0, 6.04, 1.0, false, false),
+            (6.0, 6.1, 1.0, true, false),
+            (6.0, 7.0, 1.0, true, false),
+            (6.0, 7.1, 1.0, true, true),
+            (6.0, 4.9, 1.0, true, true),
+            (6.0, 6.5, 0.0, true, true),
+            (6.0, 9.0, 5.0, true, false),
+        ];
+
+        for (old, new, threshold, changed, regression) in cases {
+            let comparison = compare_with_baseline(&entries(&[("a.mp3", old)]), &entries(&[("a.mp3", new)]), threshold);
+            let case = format!("{} → {} (阈值 {})", old, new, threshold);
+            assert_eq!(comparison.changed.len(), usize::from(changed), "{}", case);
+            assert_eq!(comparison.regressions().count(), usize::from(regression), "{}", case);
+            assert!(comparison.new.is_empty() && comparison.missing.is_empty(), "{}", case);
+        }
+    }
+
+    /// 测试新增、缺失条目和输出顺序
+    #[test]
+    fn test_new_and_missing_entries() {
+        let baseline = entries(&[("ep1.mp3", 6.0), ("ep2.mp3", 7.0), ("ep3.mp3", 8.0)]);
+        let current = entries(&[("ep4.mp3", 5.0), ("ep3.mp3", 10.0), ("ep1.mp3", 6.0), ("ep0.mp3", 4.0)]);
+        let comparison = compare_with_baseline(&baseline, &current, DEFAULT_REGRESSION_THRESHOLD);
+
+        assert_eq!(
+            comparison.changed,
+            vec![BaselineChange { path: "ep3.mp3".into(), old: 8.0, new: 10.0, regression: true }]
+        );
+        assert_eq!(comparison.changed[0].to_string(), "ep3.mp3: 8.0 → 10.0 LU (+2.0)");
+        assert_eq!(comparison.new, entries(&[("ep4.mp3", 5.0), ("ep0.mp3", 4.0)]));
+        assert_eq!(comparison.missing, vec![FileKey::from("ep2.mp3")]);
+        assert!(!comparison.is_unchanged());
+        assert!(compare_with_baseline(&baseline, &baseline, 0.0).is_unchanged());
+    }
+}
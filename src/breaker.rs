@@ -0,0 +1,397 @@
+//! 断路器模块 (Circuit Breaker Module)
+//!
+//! 音乐库放在 NAS 上时，网络共享短暂断开会让几十个文件接连以文件访问错误失败，整次运行基本作废。
+//! `--outage-threshold` / `--outage-budget` 时并行处理通过 [`CircuitBreaker`] 应对断连：
+//!
+//! 1. 连续超过 [`OutageConfig::threshold`] 个文件因断连类错误（[`is_outage_error`]）失败时断开，
+//!    发出 [`RunEvent::OutagePaused`]，不再开始新的文件
+//! 2. 断开期间每隔 [`OutageConfig::probe_interval`] 检查一次扫描根目录
+//!    （[`crate::utils::validate_folder_path`]），可以访问时恢复分派，发出 [`RunEvent::OutageResumed`]
+//! 3. 断连期间失败的文件（包括触发断开的那一串）在本轮处理结束后重新分析一次（[`RunEvent::Requeued`]）
+//! 4. 累计暂停时间超过 [`OutageConfig::pause_budget`] 时放弃，发出 [`RunEvent::OutageGaveUp`]；
+//!    剩下的文件不再分析，以 [`SkipReason::Outage`](crate::processor::SkipReason::Outage) 跳过，命令行程序以 `AppError::Io` 退出
+//!
+//! 见 [`crate::processor::RunOptions::outage`]。
+
+use std::collections::HashSet;
+use std::fmt;
+use std::mem;
+use std::path::{Path, PathBuf};
+use std::sync::{Arc, Condvar, Mutex, MutexGuard};
+use std::thread;
+use std::time::{Duration, Instant};
+
+use crate::cancel::CancellationToken;
+use crate::error::{FileErrorType, ProcessFileError};
+use crate::events::{EventSink, RunEvent};
+use crate::file_key::FileKey;
+use crate::utils::validate_folder_path;
+
+/// 默认的连续失败阈值
+pub const DEFAULT_OUTAGE_THRESHOLD: usize = 10;
+
+/// 默认的累计暂停时间上限
+pub const DEFAULT_PAUSE_BUDGET: Duration = Duration::from_secs(600);
+
+/// 断开期间检查扫描根目录的间隔
+pub const PROBE_INTERVAL: Duration = Duration::from_secs(5);
+
+/// 断路器配置 (Outage Configuration)
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct OutageConfig {
+    /// 连续失败超过该数量时断开（`--outage-threshold <N>`）
+    pub threshold: usize,
+    /// 累计暂停时间上限，超过后放弃（`--outage-budget <时长>`）
+    pub pause_budget: Duration,
+    /// 断开期间检查扫描根目录的间隔
+    pub probe_interval: Duration,
+}
+
+impl Default for OutageConfig {
+    fn default() -> Self {
+        Self { threshold: DEFAULT_OUTAGE_THRESHOLD, pause_budget: DEFAULT_PAUSE_BUDGET, probe_interval: PROBE_INTERVAL }
+    }
+}
+
+/// 是否是断连类错误 (Is Outage Error)
+///
+/// 文件访问失败（包括扫描之后消失的文件），以及无法启动 FFmpeg
+/// （执行失败但没有退出码和 stderr，见 [`crate::error::LraCalculationError::Spawn`]）。
+/// 文件损坏等 FFmpeg 正常运行后报告的错误不算。
+pub fn is_outage_error(error: &ProcessFileError) -> bool {
+    match error.error_type {
+        FileErrorType::FileAccess => true,
+        FileErrorType::FfmpegExecution => error.exit_code.is_none() && error.stderr_excerpt.is_none(),
+        _ => false,
+    }
+}
+
+/// 需要重新分析的文件：(输入序号, 完整路径, 显示路径)
+pub type RequeuedFile = (usize, PathBuf, FileKey);
+
+/// 断路器 (Circuit Breaker)
+///
+/// 克隆得到的断路器共享同一个状态。工作线程在开始每个文件之前调用
+/// [`wait_until_closed`](Self::wait_until_closed)，分析结束后调用 [`record`](Self::record)。
+///
+/// # 示例
+/// ```
+/// use std::time::Duration;
+/// use lra_calculator_rust::breaker::{CircuitBreaker, OutageConfig};
+/// use lra_calculator_rust::error::ProcessFileError;
+/// use lra_calculator_rust::events::RunEvent;
+///
+/// let config = OutageConfig { threshold: 1, probe_interval: Duration::from_millis(1), ..OutageConfig::default() };
+/// let breaker = CircuitBreaker::with_probe(config, || true);
+/// let sink = |_: &RunEvent| {};
+/// for index in 0..2 {
+///     let failure: Result<(), _> = Err(ProcessFileError::vanished(format!("{}.flac", index)));
+///     breaker.record(index, format!("/nas/{}.flac", index).as_ref(), &failure, &sink);
+/// }
+/// assert!(breaker.is_open());
+/// assert!(breaker.wait_until_closed(None, &sink));
+/// assert_eq!(breaker.take_requeue().len(), 2);
+/// ```
+#[derive(Clone)]
+pub struct CircuitBreaker {
+    shared: Arc<Shared>,
+}
+
+struct Shared {
+    config: OutageConfig,
+    /// 检查扫描根目录是否可以访问
+    probe: Box<dyn Fn() -> bool + Send + Sync>,
+    state: Mutex<BreakerState>,
+    /// 断路器闭合或放弃时通知等待中的工作线程
+    changed: Condvar,
+}
+
+/// 断路器的内部状态
+#[derive(Debug, Default)]
+struct BreakerState {
+    /// 连续的断连类失败数量
+    consecutive: usize,
+    /// 这一串连续失败的文件；断开时移入 `requeue`
+    streak: Vec<RequeuedFile>,
+    /// 等待重新分析的文件
+    requeue: Vec<RequeuedFile>,
+    /// 已经重新分析过的输入序号，每个文件只重新分析一次
+    retried: HashSet<usize>,
+    /// 断开的时刻；闭合时为 `None`
+    open_since: Option<Instant>,
+    /// 之前各次断开的累计暂停时间
+    paused: Duration,
+    /// 有工作线程正在检查扫描根目录
+    probing: bool,
+    /// 累计暂停时间超过上限，已经放弃
+    gave_up: bool,
+    /// 断开的次数
+    pauses: usize,
+}
+
+impl CircuitBreaker {
+    /// 创建检查 `root` 的断路器
+    ///
+    /// # 参数
+    /// - `root` - 扫描根目录，断开期间用 [`validate_folder_path`] 检查
+    /// - `config` - 阈值、暂停时间上限和检查间隔
+    pub fn new(root: impl Into<PathBuf>, config: OutageConfig) -> Self {
+        let root = root.into();
+        Self::with_probe(config, move || validate_folder_path(&root).is_ok())
+    }
+
+    /// 创建使用自定义检查的断路器（测试或非本地存储）
+    pub fn with_probe(config: OutageConfig, probe: impl Fn() -> bool + Send + Sync + 'static) -> Self {
+        Self {
+            shared: Arc::new(Shared {
+                config,
+                probe: Box::new(probe),
+                state: Mutex::new(BreakerState::default()),
+                changed: Condvar::new(),
+            }),
+        }
+    }
+
+    /// 记录一个文件的结果 (Record)
+    ///
+    /// 断连类失败计入连续失败；超过阈值时断开并发出 [`RunEvent::OutagePaused`]，
+    /// 这一串失败的文件等待重新分析。断开期间的断连类失败直接等待重新分析。
+    /// 成功和其他失败清零连续失败的计数。
+    ///
+    /// # 参数
+    /// - `index` - 文件的输入序号
+    /// - `path` - 文件的完整路径，重新分析时使用
+    /// - `result` - 分析结果
+    /// - `sink` - 事件接收者
+    pub fn record<T, S>(&self, index: usize, path: &Path, result: &Result<T, ProcessFileError>, sink: &S)
+    where
+        S: EventSink + ?Sized,
+    {
+        let mut state = self.lock();
+        let error = match result {
+            Err(error) if is_outage_error(error) => error,
+            _ => {
+                if state.open_since.is_none() {
+                    state.consecutive = 0;
+                    state.streak.clear();
+                }
+                return;
+            }
+        };
+        if state.gave_up {
+            return;
+        }
+        let file = (index, path.to_path_buf(), error.file_path.clone());
+        if state.open_since.is_some() {
+            state.requeue.push(file);
+            return;
+        }
+        state.consecutive += 1;
+        state.streak.push(file);
+        if state.consecutive <= self.shared.config.threshold {
+            return;
+        }
+        let streak = mem::take(&mut state.streak);
+        state.requeue.extend(streak);
+        state.open_since = Some(Instant::now());
+        state.pauses += 1;
+        let consecutive_failures = state.consecutive;
+        drop(state);
+        sink.emit(&RunEvent::OutagePaused { consecutive_failures });
+    }
+
+    /// 等待断路器闭合 (Wait Until Closed)
+    ///
+    /// 闭合时立即返回。断开时由一个工作线程每隔 [`OutageConfig::probe_interval`] 检查一次，
+    /// 其他线程等待结果；可以访问时闭合并发出 [`RunEvent::OutageResumed`]，
+    /// 累计暂停时间超过上限时放弃并发出 [`RunEvent::OutageGaveUp`]。
+    ///
+    /// # 参数
+    /// - `cancellation` - 运行取消后不再等待（最多延迟一个检查间隔）
+    /// - `sink` - 事件接收者
+    ///
+    /// # 返回值
+    /// - `true` - 可以开始新的文件
+    /// - `false` - 已经放弃或运行已取消，不再开始新的文件
+    pub fn wait_until_closed<S: EventSink + ?Sized>(&self, cancellation: Option<&CancellationToken>, sink: &S) -> bool {
+        let config = &self.shared.config;
+        let mut state = self.lock();
+        loop {
+            if state.gave_up {
+                return false;
+            }
+            if state.open_since.is_some() && cancellation.is_some_and(CancellationToken::is_cancelled) {
+                return false;
+            }
+            let Some(open_since) = state.open_since else {
+                return true;
+            };
+            if state.probing {
+                state = self
+                    .shared
+                    .changed
+                    .wait_timeout(state, config.probe_interval)
+                    .unwrap_or_else(|poisoned| poisoned.into_inner())
+                    .0;
+                continue;
+            }
+
+            state.probing = true;
+            drop(state);
+            thread::sleep(config.probe_interval);
+            let reachable = (self.shared.probe)();
+            state = self.lock();
+            state.probing = false;
+            let paused_secs = open_since.elapsed().as_secs_f64();
+            if reachable {
+                state.paused += open_since.elapsed();
+                state.open_since = None;
+                state.consecutive = 0;
+                drop(state);
+                self.shared.changed.notify_all();
+                sink.emit(&RunEvent::OutageResumed { paused_secs });
+                return true;
+            }
+            if state.paused + open_since.elapsed() >= config.pause_budget {
+                state.paused += open_since.elapsed();
+                state.gave_up = true;
+                drop(state);
+                self.shared.changed.notify_all();
+                sink.emit(&RunEvent::OutageGaveUp { paused_secs });
+                return false;
+            }
+        }
+    }
+
+    /// 取出等待重新分析的文件 (Take Requeue)
+    ///
+    /// 每个文件只重新分析一次，已经重新分析过的文件不再返回；已经放弃时返回空列表。
+    pub fn take_requeue(&self) -> Vec<RequeuedFile> {
+        let mut state = self.lock();
+        if state.gave_up {
+            return Vec::new();
+        }
+        let requeue = mem::take(&mut state.requeue);
+        let BreakerState { retried, .. } = &mut *state;
+        requeue.into_iter().filter(|(index, ..)| retried.insert(*index)).collect()
+    }
+
+    /// 当前是否断开
+    pub fn is_open(&self) -> bool {
+        self.lock().open_since.is_some()
+    }
+
+    /// 是否已经放弃
+    pub fn gave_up(&self) -> bool {
+        self.lock().gave_up
+    }
+
+    /// 断开的次数
+    pub fn pauses(&self) -> usize {
+        self.lock().pauses
+    }
+
+    /// 累计暂停时间（不含仍在进行的断开）
+    pub fn paused(&self) -> Duration {
+        self.lock().paused
+    }
+
+    /// 配置
+    pub fn config(&self) -> OutageConfig {
+        self.shared.config
+    }
+
+    /// 工作线程不会在持有锁时 panic，中毒时照常使用内部状态
+    fn lock(&self) -> MutexGuard<'_, BreakerState> {
+        self.shared.state.lock().unwrap_or_else(|poisoned| poisoned.into_inner())
+    }
+}
+
+impl fmt::Debug for CircuitBreaker {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("CircuitBreaker").field("config", &self.shared.config).field("state", &*self.lock()).finish()
+    }
+}
+
+/// 同一个断路器的克隆视为相等
+impl PartialEq for CircuitBreaker {
+    fn eq(&self, other: &Self) -> bool {
+        Arc::ptr_eq(&self.shared, &other.shared)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::events::BufferSink;
+    use std::sync::atomic::{AtomicUsize, Ordering};
+
+    fn config(threshold: usize, pause_budget: Duration) -> OutageConfig {
+        OutageConfig { threshold, pause_budget, probe_interval: Duration::from_millis(1) }
+    }
+
+    fn outage(index: usize) -> Result<(), ProcessFileError> {
+        Err(ProcessFileError::file_access_error(format!("{}.flac", index), "Input/output error".to_string()))
+    }
+
+    /// 测试断连类错误的判断
+    #[test]
+    fn test_is_outage_error() {
+        assert!(is_outage_error(&ProcessFileError::vanished("a.flac")));
+        let spawn = ProcessFileError::ffmpeg_error("a.flac", "无法启动".to_string());
+        assert!(is_outage_error(&spawn));
+        assert!(!is_outage_error(&spawn.with_ffmpeg_context(Some(1), "Invalid data found")));
+        assert!(!is_outage_error(&ProcessFileError::lra_parsing_error("a.flac", "无 LRA".to_string())));
+    }
+
+    /// 测试超过阈值时断开，恢复后重新分析这一串失败的文件，且每个文件只重新分析一次
+    #[test]
+    fn test_trip_resume_and_requeue() {
+        let probes = Arc::new(AtomicUsize::new(0));
+        let counter = probes.clone();
+        let breaker = CircuitBreaker::with_probe(config(2, Duration::from_secs(60)), move || {
+            counter.fetch_add(1, Ordering::SeqCst) >= 2
+        });
+        let sink = BufferSink::new();
+
+        // 其他失败和成功打断连续失败
+        breaker.record(0, Path::new("/nas/0.flac"), &outage(0), &sink);
+        breaker.record(1, Path::new("/nas/1.flac"), &Ok::<(), ProcessFileError>(()), &sink);
+        for index in 2..5 {
+            breaker.record(index, Path::new("/nas/x.flac"), &outage(index), &sink);
+        }
+        assert!(breaker.is_open());
+        // 断开期间失败的文件同样重新分析
+        breaker.record(5, Path::new("/nas/5.flac"), &outage(5), &sink);
+
+        assert!(breaker.wait_until_closed(None, &sink));
+        assert_eq!(probes.load(Ordering::SeqCst), 3);
+        assert!(!breaker.is_open());
+        assert_eq!(breaker.pauses(), 1);
+        let requeued: Vec<usize> = breaker.take_requeue().into_iter().map(|(index, ..)| index).collect();
+        assert_eq!(requeued, [2, 3, 4, 5]);
+
+        for index in 2..5 {
+            breaker.record(index, Path::new("/nas/x.flac"), &outage(index), &sink);
+        }
+        assert!(breaker.wait_until_closed(None, &sink));
+        assert!(breaker.take_requeue().is_empty());
+
+        let events = sink.into_events();
+        assert!(matches!(events[0], RunEvent::OutagePaused { consecutive_failures: 3 }));
+        assert!(matches!(events[1], RunEvent::OutageResumed { .. }));
+    }
+
+    /// 测试累计暂停时间超过上限时放弃，之后不再开始新的文件
+    #[test]
+    fn test_give_up() {
+        let breaker = CircuitBreaker::with_probe(config(0, Duration::ZERO), || false);
+        let sink = BufferSink::new();
+        breaker.record(0, Path::new("/nas/0.flac"), &outage(0), &sink);
+        assert!(!breaker.wait_until_closed(None, &sink));
+        assert!(breaker.gave_up());
+        assert!(!breaker.wait_until_closed(None, &sink));
+        assert!(breaker.take_requeue().is_empty());
+        assert!(matches!(sink.into_events()[..], [RunEvent::OutagePaused { .. }, RunEvent::OutageGaveUp { .. }]));
+    }
+}
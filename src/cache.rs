@@ -0,0 +1,621 @@
+//! 结果缓存模块 (Results Cache Module)
+//!
+//! 在结果文件旁保存一个 JSON 缓存，记录每个文件上次分析时的大小、
+//! 修改时间和 LRA 值。下次运行时，只有大小和修改时间都与缓存一致的文件
+//! 才会直接复用缓存结果；原地重新编码过的文件会被重新分析。
+//!
+//! ## 缓存格式
+//!
+//! ```text
+//! {
+//!   "ffmpeg_version": "6.1.1",
+//!   "entries": {
+//!     "Album/01.flac": { "size": 31457280, "mtime_ms": 1700000000000, "lra": 12.3, "integrated_lufs": -14.2 },
+//!     "Album/02.flac": { "size": 28311552, "mtime_ms": 1700000000000, "lra": 9.8, "content_hash": "xxh3:2d06800538d394c2" }
+//!   }
+//! }
+//! ```
+//!
+//! ## 容错策略
+//!
+//! 缓存只是加速手段：文件缺失时视为空缓存，文件损坏时输出警告后视为空缓存，
+//! 绝不会中断运行。未启用 `serde` 特性时缓存不会被读写。
+//!
+//! ## FFmpeg 版本
+//!
+//! ebur128 滤波器的行为可能随 FFmpeg 大版本变化，缓存头部记录了生成缓存时的
+//! FFmpeg 版本。检测到版本变化时按 [`CacheVersionPolicy`] 处理旧条目。
+
+use std::collections::{BTreeMap, HashMap};
+use std::fmt;
+use std::fs;
+use std::io;
+use std::path::{Path, PathBuf};
+use std::time::UNIX_EPOCH;
+
+use crate::file_key::FileKey;
+use crate::hash::ContentHash;
+use crate::processor::FileMeasurement;
+
+/// 文件指纹 (File Fingerprint)
+///
+/// 用于判断文件自上次分析以来是否发生变化。
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct FileFingerprint {
+    /// 文件大小（字节）
+    pub size: u64,
+    /// 修改时间（Unix 毫秒时间戳）
+    pub mtime_ms: u64,
+}
+
+impl FileFingerprint {
+    /// 读取文件的当前指纹
+    ///
+    /// # 参数
+    /// - `path` - 文件路径
+    ///
+    /// # 返回值
+    /// - `Ok(FileFingerprint)` - 文件指纹
+    /// - `Err(io::Error)` - 无法读取文件元数据
+    pub fn of(path: &Path) -> io::Result<Self> {
+        let metadata = fs::metadata(path)?;
+        let mtime_ms = metadata
+            .modified()?
+            .duration_since(UNIX_EPOCH)
+            .map(|duration| duration.as_millis() as u64)
+            .unwrap_or(0);
+
+        Ok(Self {
+            size: metadata.len(),
+            mtime_ms,
+        })
+    }
+}
+
+/// 缓存条目 (Cache Entry)
+#[derive(Debug, Clone, Copy, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct CacheEntry {
+    /// 分析时的文件大小（字节）
+    pub size: u64,
+    /// 分析时的修改时间（Unix 毫秒时间戳）
+    pub mtime_ms: u64,
+    /// 分析得到的 LRA 值
+    pub lra: f64,
+    /// 是否由其他 FFmpeg 版本计算（见 [`CacheVersionPolicy::MarkStale`]）
+    #[cfg_attr(feature = "serde", serde(default, skip_serializing_if = "std::ops::Not::not"))]
+    pub stale: bool,
+    /// 分析得到的综合响度（LUFS）；旧版本写入的缓存没有此字段
+    #[cfg_attr(feature = "serde", serde(default, skip_serializing_if = "Option::is_none"))]
+    pub integrated_lufs: Option<f64>,
+    /// 分析得到的真峰值（dBTP）；未启用 `--true-peak` 时没有此字段
+    #[cfg_attr(feature = "serde", serde(default, skip_serializing_if = "Option::is_none"))]
+    pub true_peak_dbtp: Option<f64>,
+    /// LRA 下限（LUFS）；旧版本写入的缓存或不输出该值的 FFmpeg 构建没有此字段
+    #[cfg_attr(feature = "serde", serde(default, skip_serializing_if = "Option::is_none"))]
+    pub lra_low_lufs: Option<f64>,
+    /// LRA 上限（LUFS）；同上
+    #[cfg_attr(feature = "serde", serde(default, skip_serializing_if = "Option::is_none"))]
+    pub lra_high_lufs: Option<f64>,
+    /// 综合响度的相对门限（LUFS）；同上
+    #[cfg_attr(feature = "serde", serde(default, skip_serializing_if = "Option::is_none"))]
+    pub threshold_lufs: Option<f64>,
+    /// 分析时文件内容的摘要（`--hash`），形如 `sha1:…`；未启用时没有此字段
+    #[cfg_attr(feature = "serde", serde(default, skip_serializing_if = "Option::is_none"))]
+    pub content_hash: Option<ContentHash>,
+}
+
+impl CacheEntry {
+    /// 根据文件指纹和 LRA 值创建缓存条目
+    pub fn new(fingerprint: FileFingerprint, lra: f64) -> Self {
+        Self {
+            size: fingerprint.size,
+            mtime_ms: fingerprint.mtime_ms,
+            lra,
+            stale: false,
+            integrated_lufs: None,
+            true_peak_dbtp: None,
+            lra_low_lufs: None,
+            lra_high_lufs: None,
+            threshold_lufs: None,
+            content_hash: None,
+        }
+    }
+
+    /// 缓存条目是否与文件的当前指纹一致
+    pub fn matches(&self, fingerprint: &FileFingerprint) -> bool {
+        self.size == fingerprint.size && self.mtime_ms == fingerprint.mtime_ms
+    }
+
+    /// 分析时的文件指纹
+    pub fn fingerprint(&self) -> FileFingerprint {
+        FileFingerprint { size: self.size, mtime_ms: self.mtime_ms }
+    }
+
+    /// 复用该条目时的测量结果
+    pub fn measurement(&self, path: impl Into<FileKey>) -> FileMeasurement {
+        FileMeasurement::new(path, self.lra)
+            .with_integrated_lufs(self.integrated_lufs)
+            .with_true_peak(self.true_peak_dbtp)
+            .with_range_bounds(self.lra_low_lufs, self.lra_high_lufs, self.threshold_lufs)
+            .with_content_hash(self.content_hash)
+    }
+}
+
+/// 内容探测缓存条目 (Probe Cache Entry)
+///
+/// 记录 `--probe-unknown` 对没有扩展名的文件的判断结果（见 [`crate::probe`]）。
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct ProbeCacheEntry {
+    /// 探测时的文件大小（字节）
+    pub size: u64,
+    /// 探测时的修改时间（Unix 毫秒时间戳）
+    pub mtime_ms: u64,
+    /// 是否识别为音频
+    pub is_audio: bool,
+}
+
+impl ProbeCacheEntry {
+    /// 根据文件指纹和探测结果创建缓存条目
+    pub fn new(fingerprint: FileFingerprint, is_audio: bool) -> Self {
+        Self {
+            size: fingerprint.size,
+            mtime_ms: fingerprint.mtime_ms,
+            is_audio,
+        }
+    }
+
+    /// 缓存条目是否与文件的当前指纹一致
+    pub fn matches(&self, fingerprint: &FileFingerprint) -> bool {
+        self.size == fingerprint.size && self.mtime_ms == fingerprint.mtime_ms
+    }
+}
+
+/// FFmpeg 版本变化时的缓存处理策略 (Cache Version Policy)
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum CacheVersionPolicy {
+    /// 丢弃所有旧条目，全部重新分析（默认）
+    #[default]
+    Invalidate,
+    /// 继续复用旧条目，但将其标记为过期，直到文件被重新分析
+    MarkStale,
+}
+
+impl CacheVersionPolicy {
+    /// 从命令行取值解析（`invalidate` 或 `mark-stale`）
+    pub fn parse(value: &str) -> Option<Self> {
+        match value {
+            "invalidate" => Some(Self::Invalidate),
+            "mark-stale" => Some(Self::MarkStale),
+            _ => None,
+        }
+    }
+}
+
+impl fmt::Display for CacheVersionPolicy {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Invalidate => write!(f, "invalidate"),
+            Self::MarkStale => write!(f, "mark-stale"),
+        }
+    }
+}
+
+/// FFmpeg 版本变化的处理结果 (Version Change)
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct VersionChange {
+    /// 缓存中记录的旧版本
+    pub previous: String,
+    /// 当前检测到的版本
+    pub current: String,
+    /// 采用的策略
+    pub policy: CacheVersionPolicy,
+    /// 被丢弃或标记为过期的条目数量
+    pub affected: usize,
+}
+
+/// 结果缓存 (Results Cache)
+#[derive(Debug, Clone, Default, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct ResultsCache {
+    /// 生成缓存时的 FFmpeg 版本
+    #[cfg_attr(feature = "serde", serde(default))]
+    pub ffmpeg_version: Option<String>,
+    /// 显示路径（相对于扫描根目录）到缓存条目的映射
+    pub entries: BTreeMap<FileKey, CacheEntry>,
+    /// 没有扩展名的文件的内容探测结果（与 FFmpeg 版本无关，版本变化时保留）
+    #[cfg_attr(feature = "serde", serde(default, skip_serializing_if = "BTreeMap::is_empty"))]
+    pub probes: BTreeMap<String, ProbeCacheEntry>,
+}
+
+impl ResultsCache {
+    /// 根据当前 FFmpeg 版本处理旧条目 (Apply Version Policy)
+    ///
+    /// 只有缓存记录的版本和当前版本都已知且不同时才视为版本变化。
+    /// 处理后缓存记录的版本更新为当前版本。
+    ///
+    /// # 参数
+    /// - `current_version` - 当前检测到的 FFmpeg 版本
+    /// - `policy` - 版本变化时的处理策略
+    ///
+    /// # 返回值
+    /// - `Some(VersionChange)` - 版本发生变化，包含受影响的条目数量
+    /// - `None` - 版本未变化或无法比较
+    pub fn apply_version_policy(
+        &mut self,
+        current_version: Option<&str>,
+        policy: CacheVersionPolicy,
+    ) -> Option<VersionChange> {
+        let current = current_version?;
+        let previous = self.ffmpeg_version.replace(current.to_string())?;
+        if previous == current {
+            return None;
+        }
+
+        let affected = match policy {
+            CacheVersionPolicy::Invalidate => {
+                let count = self.entries.len();
+                self.entries.clear();
+                count
+            }
+            CacheVersionPolicy::MarkStale => {
+                let mut count = 0;
+                for entry in self.entries.values_mut().filter(|entry| !entry.stale) {
+                    entry.stale = true;
+                    count += 1;
+                }
+                count
+            }
+        };
+
+        Some(VersionChange {
+            previous,
+            current: current.to_string(),
+            policy,
+            affected,
+        })
+    }
+
+    /// 获取文件可复用的缓存 LRA 值
+    ///
+    /// # 参数
+    /// - `display_path` - 文件的显示路径
+    /// - `fingerprint` - 文件的当前指纹
+    ///
+    /// # 返回值
+    /// - `Some(f64)` - 缓存命中且文件未变化
+    /// - `None` - 需要重新分析
+    pub fn cached_lra(&self, display_path: &str, fingerprint: &FileFingerprint) -> Option<f64> {
+        self.cached_entry(display_path, fingerprint).map(|entry| entry.lra)
+    }
+
+    /// 获取文件可复用的缓存条目（与 [`ResultsCache::cached_lra`] 相同，但包含综合响度等全部字段）
+    pub fn cached_entry(&self, display_path: &str, fingerprint: &FileFingerprint) -> Option<&CacheEntry> {
+        self.entries
+            .get(display_path)
+            .filter(|entry| entry.matches(fingerprint))
+    }
+}
+
+/// 判断文件是否需要重新分析 (Needs Reprocessing)
+///
+/// 文件不在缓存中，或大小、修改时间与缓存记录不一致时需要重新分析。
+///
+/// # 参数
+/// - `cache` - 结果缓存
+/// - `display_path` - 文件的显示路径
+/// - `fingerprint` - 文件的当前指纹
+pub fn needs_reprocessing(
+    cache: &ResultsCache,
+    display_path: &str,
+    fingerprint: &FileFingerprint,
+) -> bool {
+    cache.cached_lra(display_path, fingerprint).is_none()
+}
+
+/// 缓存检查结果 (Cache Plan)
+#[derive(Debug, Default)]
+pub struct CachePlan {
+    /// 需要分析的文件：(完整路径, 显示路径)
+    pub to_process: Vec<(PathBuf, FileKey)>,
+    /// 直接复用缓存的结果
+    pub cached: Vec<FileMeasurement>,
+    /// 所有能读取到元数据的文件的当前指纹，用于更新缓存
+    pub fingerprints: HashMap<FileKey, FileFingerprint>,
+}
+
+impl CachePlan {
+    /// 检查单个文件是否可以复用缓存
+    ///
+    /// 命中缓存时结果记入 `cached`；能读取到元数据时指纹记入 `fingerprints`。
+    /// 边扫描边处理时逐个文件调用，不需要先得到完整的文件列表。
+    ///
+    /// # 返回值
+    /// - `Some((完整路径, 显示路径))` - 文件需要分析（不会加入 `to_process`）
+    /// - `None` - 复用缓存结果
+    pub fn admit(
+        &mut self,
+        full_path: PathBuf,
+        display_path: impl Into<FileKey>,
+        cache: &ResultsCache,
+    ) -> Option<(PathBuf, FileKey)> {
+        let display_path = display_path.into();
+        let Ok(fingerprint) = FileFingerprint::of(&full_path) else {
+            return Some((full_path, display_path));
+        };
+
+        let cached = cache.cached_entry(&display_path, &fingerprint).copied();
+        self.fingerprints.insert(display_path.clone(), fingerprint);
+        match cached {
+            Some(entry) => {
+                self.cached.push(entry.measurement(display_path));
+                None
+            }
+            None => Some((full_path, display_path)),
+        }
+    }
+}
+
+/// 根据缓存划分待处理文件 (Plan with Cache)
+///
+/// 无法读取元数据的文件总是会被重新分析（分析阶段会给出具体错误）。
+///
+/// # 参数
+/// - `files` - 扫描得到的文件列表：(完整路径, 显示路径)
+/// - `cache` - 上次运行保存的缓存
+///
+/// # 返回值
+/// - 需要分析的文件、可复用的结果和文件指纹
+pub fn plan_with_cache<K: Into<FileKey>>(files: Vec<(PathBuf, K)>, cache: &ResultsCache) -> CachePlan {
+    let mut plan = CachePlan::default();
+    for (full_path, display_path) in files {
+        if let Some(file) = plan.admit(full_path, display_path, cache) {
+            plan.to_process.push(file);
+        }
+    }
+    plan
+}
+
+/// 根据本次运行的结果重建缓存 (Rebuild Cache)
+///
+/// 新缓存只包含本次成功得到结果的文件，已删除或分析失败的文件不会保留。
+/// 复用的条目保留过期标记，重新分析过的条目不再过期。探测记录原样保留。
+///
+/// # 参数
+/// - `previous` - 本次运行开始时使用的缓存（已应用版本策略）
+/// - `fingerprints` - 文件的当前指纹
+/// - `successful_results` - 本次运行的所有成功结果（包括复用的缓存结果）
+///
+/// # 返回值
+/// - 新的结果缓存
+pub fn rebuild_cache(
+    previous: &ResultsCache,
+    fingerprints: &HashMap<FileKey, FileFingerprint>,
+    successful_results: &[FileMeasurement],
+) -> ResultsCache {
+    let entries = successful_results
+        .iter()
+        .filter_map(|measurement| {
+            let path = &measurement.path;
+            let fingerprint = fingerprints.get(path)?;
+            let reused_stale = previous
+                .entries
+                .get(path)
+                .is_some_and(|entry| entry.stale && entry.matches(fingerprint));
+
+            let mut entry = CacheEntry::new(*fingerprint, measurement.lra);
+            entry.stale = reused_stale;
+            entry.integrated_lufs = measurement.integrated_lufs;
+            entry.true_peak_dbtp = measurement.true_peak_dbtp;
+            entry.lra_low_lufs = measurement.lra_low_lufs;
+            entry.lra_high_lufs = measurement.lra_high_lufs;
+            entry.threshold_lufs = measurement.threshold_lufs;
+            entry.content_hash = measurement.content_hash;
+            Some((path.clone(), entry))
+        })
+        .collect();
+
+    ResultsCache {
+        ffmpeg_version: previous.ffmpeg_version.clone(),
+        entries,
+        probes: previous.probes.clone(),
+    }
+}
+
+/// 获取结果文件对应的缓存文件路径
+///
+/// 例如 `lra_results.txt` 对应 `lra_results_cache.json`。
+pub fn cache_path_for(results_file_path: &Path) -> PathBuf {
+    let stem = results_file_path
+        .file_stem()
+        .map(|stem| stem.to_string_lossy().into_owned())
+        .unwrap_or_else(|| "lra_results".to_string());
+    results_file_path.with_file_name(format!("{}_cache.json", stem))
+}
+
+/// 读取缓存文件 (Load Cache)
+///
+/// # 参数
+/// - `path` - 缓存文件路径
+///
+/// # 返回值
+/// - `Ok(ResultsCache)` - 读取成功；文件不存在时返回空缓存
+/// - `Err(String)` - 文件无法读取或内容损坏（调用方应警告后使用空缓存）
+#[cfg(feature = "serde")]
+pub fn load_cache(path: &Path) -> Result<ResultsCache, String> {
+    match fs::read_to_string(path) {
+        Ok(content) => serde_json::from_str(&content)
+            .map_err(|e| format!("缓存文件 {} 已损坏: {}", path.display(), e)),
+        Err(e) if e.kind() == io::ErrorKind::NotFound => Ok(ResultsCache::default()),
+        Err(e) => Err(format!("无法读取缓存文件 {}: {}", path.display(), e)),
+    }
+}
+
+/// 读取缓存文件（未启用 `serde` 特性时总是返回空缓存）
+#[cfg(not(feature = "serde"))]
+pub fn load_cache(_path: &Path) -> Result<ResultsCache, String> {
+    Ok(ResultsCache::default())
+}
+
+/// 写入缓存文件 (Save Cache)
+///
+/// # 参数
+/// - `path` - 缓存文件路径
+/// - `cache` - 要保存的缓存
+///
+/// # 返回值
+/// - `Ok(())` - 写入成功
+/// - `Err(io::Error)` - 写入失败
+#[cfg(feature = "serde")]
+pub fn save_cache(path: &Path, cache: &ResultsCache) -> io::Result<()> {
+    let json = serde_json::to_string_pretty(cache).map_err(io::Error::from)?;
+    fs::write(path, json)
+}
+
+/// 写入缓存文件（未启用 `serde` 特性时不执行任何操作）
+#[cfg(not(feature = "serde"))]
+pub fn save_cache(_path: &Path, _cache: &ResultsCache) -> io::Result<()> {
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempfile::TempDir;
+
+    fn fingerprint(size: u64, mtime_ms: u64) -> FileFingerprint {
+        FileFingerprint { size, mtime_ms }
+    }
+
+    /// 测试是否需要重新分析的判断
+    #[test]
+    fn test_needs_reprocessing() {
+        let mut cache = ResultsCache::default();
+        cache.entries.insert("a.flac".into(), CacheEntry::new(fingerprint(100, 5000), 9.1));
+
+        // 大小和修改时间都一致时复用缓存
+        assert!(!needs_reprocessing(&cache, "a.flac", &fingerprint(100, 5000)));
+        assert_eq!(cache.cached_lra("a.flac", &fingerprint(100, 5000)), Some(9.1));
+
+        // 原地重新编码：大小或修改时间变化
+        assert!(needs_reprocessing(&cache, "a.flac", &fingerprint(101, 5000)));
+        assert!(needs_reprocessing(&cache, "a.flac", &fingerprint(100, 6000)));
+
+        // 新文件
+        assert!(needs_reprocessing(&cache, "b.flac", &fingerprint(100, 5000)));
+    }
+
+    /// 测试按缓存划分文件并重建缓存
+    #[test]
+    fn test_plan_and_rebuild() {
+        let temp_dir = TempDir::new().expect("无法创建临时目录");
+        let unchanged = temp_dir.path().join("unchanged.flac");
+        let changed = temp_dir.path().join("changed.flac");
+        fs::write(&unchanged, b"same").unwrap();
+        fs::write(&changed, b"new content").unwrap();
+
+        let mut cache = ResultsCache::default();
+        cache.entries.insert(
+            "unchanged.flac".into(),
+            CacheEntry::new(FileFingerprint::of(&unchanged).unwrap(), 7.0),
+        );
+        cache.entries.insert("changed.flac".into(), CacheEntry::new(fingerprint(1, 1), 3.0));
+        cache.entries.insert("deleted.flac".into(), CacheEntry::new(fingerprint(1, 1), 5.0));
+
+        let files = vec![
+            (unchanged.clone(), "unchanged.flac".to_string()),
+            (changed.clone(), "changed.flac".to_string()),
+        ];
+        let plan = plan_with_cache(files, &cache);
+
+        assert_eq!(plan.cached, vec![FileMeasurement::new("unchanged.flac", 7.0)]);
+        assert_eq!(plan.to_process, vec![(changed.clone(), "changed.flac".into())]);
+
+        // 重建后只保留本次成功的文件，已删除的文件被移除
+        let results = vec![
+            FileMeasurement::new("unchanged.flac", 7.0),
+            FileMeasurement::new("changed.flac", 11.0).with_integrated_lufs(Some(-14.2)),
+        ];
+        let rebuilt = rebuild_cache(&cache, &plan.fingerprints, &results);
+        assert_eq!(rebuilt.entries.len(), 2);
+        assert_eq!(
+            rebuilt.cached_lra("changed.flac", &FileFingerprint::of(&changed).unwrap()),
+            Some(11.0)
+        );
+
+        // 综合响度随缓存保存，复用时一并恢复
+        let replan = plan_with_cache(vec![(changed.clone(), "changed.flac")], &rebuilt);
+        assert_eq!(replan.cached[0].integrated_lufs, Some(-14.2));
+        assert!(!rebuilt.entries.contains_key("deleted.flac"));
+    }
+
+    /// 测试 FFmpeg 版本变化时的两种策略
+    #[test]
+    fn test_version_policy() {
+        let mut cache = ResultsCache { ffmpeg_version: Some("5.1".to_string()), ..Default::default() };
+        cache.entries.insert("a.flac".into(), CacheEntry::new(fingerprint(1, 1), 6.0));
+        cache.entries.insert("b.flac".into(), CacheEntry::new(fingerprint(2, 2), 8.0));
+
+        // 版本未变化或未知时不处理
+        assert_eq!(cache.clone().apply_version_policy(Some("5.1"), CacheVersionPolicy::Invalidate), None);
+        assert_eq!(cache.clone().apply_version_policy(None, CacheVersionPolicy::Invalidate), None);
+
+        let mut invalidated = cache.clone();
+        let change = invalidated
+            .apply_version_policy(Some("6.1"), CacheVersionPolicy::Invalidate)
+            .expect("版本变化应被检测到");
+        assert_eq!(change.affected, 2);
+        assert_eq!(change.previous, "5.1");
+        assert!(invalidated.entries.is_empty());
+        assert_eq!(invalidated.ffmpeg_version.as_deref(), Some("6.1"));
+
+        let mut stale = cache.clone();
+        let change = stale.apply_version_policy(Some("6.1"), CacheVersionPolicy::MarkStale).unwrap();
+        assert_eq!(change.affected, 2);
+        assert_eq!(stale.cached_lra("a.flac", &fingerprint(1, 1)), Some(6.0));
+
+        // 复用的条目保持过期，重新分析的条目不再过期
+        let fingerprints: HashMap<_, _> = [
+            ("a.flac".into(), fingerprint(1, 1)),
+            ("b.flac".into(), fingerprint(3, 3)),
+        ]
+        .into_iter()
+        .collect();
+        let results = vec![FileMeasurement::new("a.flac", 6.0), FileMeasurement::new("b.flac", 9.0)];
+        let rebuilt = rebuild_cache(&stale, &fingerprints, &results);
+        assert!(rebuilt.entries["a.flac"].stale);
+        assert!(!rebuilt.entries["b.flac"].stale);
+        assert_eq!(rebuilt.ffmpeg_version.as_deref(), Some("6.1"));
+
+        assert_eq!(CacheVersionPolicy::parse("mark-stale"), Some(CacheVersionPolicy::MarkStale));
+        assert_eq!(CacheVersionPolicy::parse("bogus"), None);
+    }
+
+    /// 测试缓存文件的读写和损坏处理
+    #[cfg(feature = "serde")]
+    #[test]
+    fn test_load_and_save_cache() {
+        let temp_dir = TempDir::new().expect("无法创建临时目录");
+        let path = cache_path_for(&temp_dir.path().join("lra_results.txt"));
+        assert_eq!(path, temp_dir.path().join("lra_results_cache.json"));
+
+        // 文件不存在时为空缓存
+        assert_eq!(load_cache(&path), Ok(ResultsCache::default()));
+
+        let mut cache = ResultsCache::default();
+        cache.entries.insert("a.flac".into(), CacheEntry::new(fingerprint(10, 20), 4.5));
+        let mut with_loudness = CacheEntry::new(fingerprint(30, 40), 8.0);
+        with_loudness.integrated_lufs = Some(-16.3);
+        with_loudness.content_hash = Some(ContentHash::Sha1([0xab; 20]));
+        cache.entries.insert("b.flac".into(), with_loudness);
+        save_cache(&path, &cache).expect("保存缓存失败");
+        assert!(fs::read_to_string(&path).unwrap().contains(&format!("\"sha1:{}\"", "ab".repeat(20))));
+        assert_eq!(load_cache(&path), Ok(cache));
+
+        // 损坏的缓存返回错误，由调用方警告后忽略
+        fs::write(&path, "{ not json").unwrap();
+        assert!(load_cache(&path).is_err());
+    }
+}
@@ -0,0 +1,120 @@
+//! 取消模块 (Cancellation Module)
+//!
+//! 并行处理在分派每个文件之前检查 [`CancellationToken`]，取消后不再开始新的文件，
+//! 已经在分析的文件照常完成，未开始的文件以 [`crate::processor::SkipReason::Cancelled`]
+//! 跳过，见 [`crate::processor::RunOptions::cancellation`]。
+//!
+//! `--max-duration` 用 [`CancellationToken::cancel_after`] 启动计时线程，到时自动取消。
+
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{mpsc, Arc};
+use std::thread::{self, JoinHandle};
+use std::time::Duration;
+
+/// 取消令牌 (Cancellation Token)
+///
+/// 克隆得到的令牌共享同一个状态，任何一个取消后所有克隆都能看到。
+///
+/// # 示例
+/// ```
+/// use lra_calculator_rust::cancel::CancellationToken;
+///
+/// let token = CancellationToken::new();
+/// let worker_view = token.clone();
+/// assert!(!worker_view.is_cancelled());
+/// token.cancel();
+/// assert!(worker_view.is_cancelled());
+/// ```
+#[derive(Debug, Clone, Default)]
+pub struct CancellationToken {
+    cancelled: Arc<AtomicBool>,
+}
+
+impl CancellationToken {
+    /// 创建未取消的令牌
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// 取消（可以重复调用）
+    pub fn cancel(&self) {
+        self.cancelled.store(true, Ordering::SeqCst);
+    }
+
+    /// 是否已经取消
+    pub fn is_cancelled(&self) -> bool {
+        self.cancelled.load(Ordering::SeqCst)
+    }
+
+    /// 在 `duration` 之后自动取消 (Cancel After)
+    ///
+    /// 启动一个计时线程。返回的 [`DeadlineTimer`] 被丢弃时计时线程立即结束，
+    /// 如果此时还没到期，令牌不会被取消。
+    ///
+    /// # 参数
+    /// - `duration` - 距离取消的时间
+    ///
+    /// # 返回值
+    /// - 计时器，需要在处理期间一直持有
+    pub fn cancel_after(&self, duration: Duration) -> DeadlineTimer {
+        let (stop, stopped) = mpsc::channel::<()>();
+        let token = self.clone();
+        let handle = thread::spawn(move || {
+            // 发送端被丢弃时 recv_timeout 立即返回 Disconnected，不会等到期
+            if let Err(mpsc::RecvTimeoutError::Timeout) = stopped.recv_timeout(duration) {
+                token.cancel();
+            }
+        });
+        DeadlineTimer { stop: Some(stop), handle: Some(handle) }
+    }
+}
+
+/// 同一个令牌的克隆视为相等
+impl PartialEq for CancellationToken {
+    fn eq(&self, other: &Self) -> bool {
+        Arc::ptr_eq(&self.cancelled, &other.cancelled)
+    }
+}
+
+/// 到期取消的计时器 (Deadline Timer)
+///
+/// 由 [`CancellationToken::cancel_after`] 创建，丢弃时停止计时线程。
+#[derive(Debug)]
+pub struct DeadlineTimer {
+    stop: Option<mpsc::Sender<()>>,
+    handle: Option<JoinHandle<()>>,
+}
+
+impl Drop for DeadlineTimer {
+    fn drop(&mut self) {
+        drop(self.stop.take());
+        if let Some(handle) = self.handle.take() {
+            let _ = handle.join();
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// 测试计时器到期后取消，提前丢弃时不取消
+    #[test]
+    fn test_cancel_after() {
+        let token = CancellationToken::new();
+        let _timer = token.cancel_after(Duration::from_millis(10));
+        let deadline = std::time::Instant::now() + Duration::from_secs(5);
+        while !token.is_cancelled() && std::time::Instant::now() < deadline {
+            thread::sleep(Duration::from_millis(5));
+        }
+        assert!(token.is_cancelled());
+
+        let token = CancellationToken::new();
+        let started = std::time::Instant::now();
+        drop(token.cancel_after(Duration::from_secs(3600)));
+        assert!(started.elapsed() < Duration::from_secs(5), "丢弃计时器时应立即结束计时线程");
+        assert!(!token.is_cancelled());
+        assert_ne!(token, CancellationToken::new());
+        assert_eq!(token, token.clone());
+    }
+}
@@ -0,0 +1,150 @@
+//! 大小写别名模块 (Case Alias Module)
+//!
+//! macOS（APFS 默认不区分大小写）和 Windows 上，经过目录链接或别名访问时，扫描可能同时
+//! 找到 `Song.MP3` 和 `song.mp3`：它们是同一个文件，会被分析两次，结果文件中出现只有大小写
+//! 不同的重复条目。`--dedup-case`（[`ScanOptions::dedup_case_aliases`](crate::audio::ScanOptions::dedup_case_aliases)）
+//! 时扫描用 [`CaseAliasDetector`] 检查每个音频文件：路径按 [`fold_case`] 折叠后与先找到的文件相同，
+//! 且确实是同一个文件（[`is_same_file`]）时跳过，记录到
+//! [`ScanReport::case_aliases`](crate::audio::ScanReport::case_aliases)。
+//!
+//! 区分大小写的文件系统（Linux 上常见）中只有大小写不同的两个文件可以是不同的文件，
+//! 这时两个都保留。只有折叠后相同的路径才会读取元数据比较，其余文件没有额外开销。
+
+use std::collections::HashMap;
+use std::path::{Path, PathBuf};
+
+/// 只有大小写不同的同一个文件 (Case Alias)
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct CaseAlias {
+    /// 跳过的路径
+    pub alias: PathBuf,
+    /// 先找到并保留的路径
+    pub kept: PathBuf,
+}
+
+/// 按大小写折叠路径 (Fold Case)
+///
+/// 使用 Unicode 小写映射，非 UTF-8 的部分按替换字符处理（是否为同一个文件另由元数据判断）。
+///
+/// # 示例
+/// ```
+/// use std::path::Path;
+/// use lra_calculator_rust::case_alias::fold_case;
+///
+/// assert_eq!(fold_case(Path::new("/Music/Song.MP3")), fold_case(Path::new("/music/song.mp3")));
+/// assert_eq!(fold_case(Path::new("Älbum/Ω.flac")), "älbum/ω.flac");
+/// ```
+pub fn fold_case(path: &Path) -> String {
+    path.to_string_lossy().to_lowercase()
+}
+
+/// 两个路径是否指向同一个文件 (Is Same File)
+///
+/// Unix 上比较设备号和 inode；其他平台比较规范化后的路径（Windows 上规范化会解析目录链接，
+/// 并按磁盘上的大小写返回）。任一路径的元数据无法读取时返回 `false`，两个文件都保留。
+pub fn is_same_file(a: &Path, b: &Path) -> bool {
+    #[cfg(unix)]
+    {
+        use std::os::unix::fs::MetadataExt;
+        match (std::fs::metadata(a), std::fs::metadata(b)) {
+            (Ok(a), Ok(b)) => a.dev() == b.dev() && a.ino() == b.ino(),
+            _ => false,
+        }
+    }
+    #[cfg(not(unix))]
+    {
+        match (std::fs::canonicalize(a), std::fs::canonicalize(b)) {
+            (Ok(a), Ok(b)) => a == b,
+            _ => false,
+        }
+    }
+}
+
+/// 大小写别名检测 (Case Alias Detector)
+///
+/// 按扫描顺序检查文件，先找到的路径被保留。
+#[derive(Debug, Default)]
+pub struct CaseAliasDetector {
+    /// 折叠后的路径 → 已找到的路径（折叠后相同但确实不同的文件有多个）
+    seen: HashMap<String, Vec<PathBuf>>,
+}
+
+impl CaseAliasDetector {
+    /// 创建空的检测器
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// 检查一个新找到的文件 (Check)
+    ///
+    /// # 返回值
+    /// - `Some(CaseAlias)` - 该文件与先找到的某个文件只有大小写不同且是同一个文件，应当跳过
+    /// - `None` - 新的文件，已记录
+    pub fn check(&mut self, path: &Path) -> Option<CaseAlias> {
+        self.check_with(path, is_same_file)
+    }
+
+    /// 用指定的判断检查文件，供测试模拟不同的文件系统
+    fn check_with(&mut self, path: &Path, same_file: impl Fn(&Path, &Path) -> bool) -> Option<CaseAlias> {
+        let found = self.seen.entry(fold_case(path)).or_default();
+        if let Some(kept) = found.iter().find(|kept| same_file(kept, path)) {
+            return Some(CaseAlias { alias: path.to_path_buf(), kept: kept.clone() });
+        }
+        found.push(path.to_path_buf());
+        None
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempfile::TempDir;
+
+    /// 测试只有折叠后相同且判断为同一个文件的路径被视为别名
+    #[test]
+    fn test_detector_with_simulated_filesystems() {
+        // 不区分大小写的文件系统：折叠后相同的路径都是同一个文件
+        let mut insensitive = CaseAliasDetector::new();
+        assert_eq!(insensitive.check_with(Path::new("/m/Song.MP3"), |_, _| true), None);
+        assert_eq!(insensitive.check_with(Path::new("/m/other.mp3"), |_, _| true), None);
+        assert_eq!(
+            insensitive.check_with(Path::new("/M/song.mp3"), |_, _| true),
+            Some(CaseAlias { alias: "/M/song.mp3".into(), kept: "/m/Song.MP3".into() })
+        );
+
+        // 区分大小写的文件系统：只有大小写不同的文件是不同的文件，都保留
+        let mut sensitive = CaseAliasDetector::new();
+        assert_eq!(sensitive.check_with(Path::new("/m/Song.mp3"), |a, b| a == b), None);
+        assert_eq!(sensitive.check_with(Path::new("/m/song.mp3"), |a, b| a == b), None);
+        assert_eq!(sensitive.seen[&fold_case(Path::new("/m/song.mp3"))].len(), 2);
+    }
+
+    /// 测试实际文件系统上的判断：不同的文件都保留，硬链接（同一个 inode）视为别名
+    #[test]
+    fn test_detector_on_real_filesystem() {
+        let temp_dir = TempDir::new().expect("无法创建临时目录");
+        let upper = temp_dir.path().join("Song.mp3");
+        let lower = temp_dir.path().join("song.mp3");
+        std::fs::write(&upper, b"upper").unwrap();
+        std::fs::write(&lower, b"lower").unwrap();
+
+        let mut detector = CaseAliasDetector::new();
+        assert_eq!(detector.check(&upper), None);
+        let case_sensitive = std::fs::read(&upper).unwrap() == b"upper";
+        if case_sensitive {
+            // Linux 等区分大小写的文件系统：两个不同的文件
+            assert_eq!(detector.check(&lower), None);
+
+            #[cfg(unix)]
+            {
+                let linked = temp_dir.path().join("SONG.mp3");
+                std::fs::hard_link(&upper, &linked).unwrap();
+                assert_eq!(detector.check(&linked), Some(CaseAlias { alias: linked.clone(), kept: upper.clone() }));
+            }
+        } else {
+            // macOS、Windows 默认的文件系统：第二次写入覆盖了同一个文件
+            assert_eq!(detector.check(&lower), Some(CaseAlias { alias: lower.clone(), kept: upper.clone() }));
+        }
+    }
+}
@@ -0,0 +1,306 @@
+//! 逐声道分析模块 (Per-Channel Analysis Module)
+//!
+//! 外录素材常见的问题是一个声道明显偏小或完全没有信号（话筒线没插好、左右声道接反后被
+//! 单独压低等），混合在一起测量时很难发现。`--per-channel` 用一条滤镜图把第一条音频流的
+//! 每个声道分别送入一个 ebur128 实例（见 [`per_channel_filtergraph`]），得到各声道的
+//! 综合响度和 LRA；声道间综合响度之差超过阈值（默认 [`DEFAULT_CHANNEL_IMBALANCE`]，
+//! `--channel-imbalance`）的文件在统计中标记出来。单声道文件不需要比较，直接跳过。
+//!
+//! 声道数由 ffprobe 读取（见 [`crate::probe::probe_channels`]）；读不到声道数或逐声道
+//! 测量失败时只警告，不影响整体测量的结果。
+
+use std::fmt;
+use std::path::Path;
+use std::sync::LazyLock;
+
+use regex::Regex;
+
+use crate::audio::{announce_command, ensure_success, parse_integrated_loudness, parse_lra_from_ffmpeg_output, CalculationOptions};
+use crate::error::LraCalculationError;
+use crate::file_key::FileKey;
+use crate::precision::LraValue;
+use crate::priority::tool_command;
+use crate::probe::probe_channels;
+use crate::processor::FileMeasurement;
+
+/// 默认的声道间综合响度差异阈值（LU）
+pub const DEFAULT_CHANNEL_IMBALANCE: f64 = 6.0;
+
+/// `--per-channel` 在结果文件中追加的列名：各声道的综合响度和 LRA，以 `/` 分隔
+pub const CHANNEL_COLUMNS: [&str; 2] = ["声道综合响度", "声道 LRA"];
+
+/// 单个声道的测量值 (Channel Loudness)
+#[derive(Debug, Clone, Copy, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
+pub struct ChannelLoudness {
+    /// 综合响度（LUFS）；完全静音的声道为负无穷
+    pub integrated_lufs: f64,
+    /// 响度范围（LU）
+    pub lra: f64,
+}
+
+/// 声道间综合响度差异过大的文件 (Channel Imbalance)
+#[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct ChannelImbalance {
+    /// 显示路径
+    pub path: FileKey,
+    /// 各声道的综合响度（LUFS），按声道顺序排列；完全静音的声道为 `None`
+    pub integrated_lufs: Vec<Option<f64>>,
+}
+
+impl ChannelImbalance {
+    /// 声道间综合响度之差（LU）；有声道完全静音时为正无穷
+    pub fn difference(&self) -> f64 {
+        channel_difference(self.integrated_lufs.iter().map(|lufs| lufs.unwrap_or(f64::NEG_INFINITY)))
+    }
+}
+
+impl fmt::Display for ChannelImbalance {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let levels: Vec<String> = self
+            .integrated_lufs
+            .iter()
+            .map(|lufs| lufs.map_or_else(|| "静音".to_string(), |lufs| format!("{:.1}", lufs)))
+            .collect();
+        write!(f, "{}: {} LUFS", self.path, levels.join(" / "))?;
+        match self.difference() {
+            difference if difference.is_finite() => write!(f, "（相差 {} LU）", LraValue(difference)),
+            _ => write!(f, "（有静音声道）"),
+        }
+    }
+}
+
+/// 构建逐声道分析的滤镜图 (Per-Channel Filtergraph)
+///
+/// 第一条音频流用 `asplit` 复制成 `channels` 份，每份用 `pan` 取出一个声道，送入命名为
+/// `ebur128@ch<N>` 的 ebur128 实例；实例名出现在日志前缀中，用于区分各声道的汇总。
+/// 最后用 `amerge` 把各路合并成一个输出，交给 null 输出格式丢弃。
+///
+/// # 参数
+/// - `channels` - 声道数，至少为 2
+///
+/// # 示例
+/// ```
+/// use lra_calculator_rust::channels::per_channel_filtergraph;
+///
+/// assert_eq!(
+///     per_channel_filtergraph(2),
+///     "[0:a:0]asplit=2[s0][s1];\
+///      [s0]pan=mono|c0=c0,ebur128@ch0[m0];\
+///      [s1]pan=mono|c0=c1,ebur128@ch1[m1];\
+///      [m0][m1]amerge=inputs=2"
+/// );
+/// ```
+pub fn per_channel_filtergraph(channels: u32) -> String {
+    let split_labels: String = (0..channels).map(|channel| format!("[s{channel}]")).collect();
+    let merge_labels: String = (0..channels).map(|channel| format!("[m{channel}]")).collect();
+    let mut graph = format!("[0:a:0]asplit={channels}{split_labels};");
+    for channel in 0..channels {
+        graph.push_str(&format!("[s{channel}]pan=mono|c0=c{channel},ebur128@ch{channel}[m{channel}];"));
+    }
+    graph.push_str(&format!("{merge_labels}amerge=inputs={channels}"));
+    graph
+}
+
+/// 各 ebur128 实例汇总块的开头，如 `[ebur128@ch1 @ 0x55d0c8a0] Summary:`
+static CHANNEL_SUMMARY: LazyLock<Regex> =
+    LazyLock::new(|| Regex::new(r"\[ebur128@ch(\d+) @ [^\]]*\] Summary:").expect("声道汇总正则表达式无效"));
+
+/// 从 FFmpeg 输出中解析各声道的汇总 (Parse Channel Summaries)
+///
+/// 每个汇总块从带实例名的 `Summary:` 行开始，到下一个汇总块或输出末尾结束，
+/// 块内的 LRA 和综合响度按整体测量的规则解析。
+///
+/// # 参数
+/// - `ffmpeg_output` - FFmpeg 的 stderr 输出
+/// - `channels` - 声道数
+///
+/// # 返回值
+/// - `Ok(Vec<ChannelLoudness>)` - 按声道顺序排列的测量值
+/// - `Err(LraCalculationError)` - 有声道找不到汇总块，或块中没有 LRA 或综合响度
+pub fn parse_channel_summaries(ffmpeg_output: &str, channels: u32) -> Result<Vec<ChannelLoudness>, LraCalculationError> {
+    let headers: Vec<(u32, usize, usize)> = CHANNEL_SUMMARY
+        .captures_iter(ffmpeg_output)
+        .filter_map(|caps| {
+            let header = caps.get(0)?;
+            Some((caps[1].parse().ok()?, header.start(), header.end()))
+        })
+        .collect();
+
+    (0..channels)
+        .map(|channel| {
+            let missing = || LraCalculationError::ParseFailed {
+                output_excerpt: format!("找不到声道 {} 的 ebur128 汇总", channel),
+            };
+            // 同一声道出现多次时取最后一次
+            let position = headers.iter().rposition(|(index, _, _)| *index == channel).ok_or_else(missing)?;
+            let block_end = headers.get(position + 1).map_or(ffmpeg_output.len(), |(_, start, _)| *start);
+            let block = &ffmpeg_output[headers[position].2..block_end];
+            Ok(ChannelLoudness {
+                integrated_lufs: parse_integrated_loudness(block).ok_or_else(missing)?,
+                lra: parse_lra_from_ffmpeg_output(block)?,
+            })
+        })
+        .collect()
+}
+
+/// 逐声道测量音频文件 (Measure Channels)
+///
+/// 先用 ffprobe 读取第一条音频流的声道数；单声道文件和读不到声道数的文件不测量。
+/// 测量失败由调用方决定如何报告，不影响整体测量的结果。调试输出只保留整体测量的那一次。
+///
+/// # 参数
+/// - `audio_file_path` - 音频文件路径
+/// - `options` - 计算选项（只使用 `verbose`）
+///
+/// # 返回值
+/// - `Ok(Some(Vec<ChannelLoudness>))` - 各声道的测量值，至少两个
+/// - `Ok(None)` - 单声道或声道数未知
+/// - `Err(LraCalculationError)` - 测量失败
+pub fn measure_channels(
+    audio_file_path: &Path,
+    options: &CalculationOptions,
+) -> Result<Option<Vec<ChannelLoudness>>, LraCalculationError> {
+    let Some(channels) = probe_channels(audio_file_path).filter(|channels| *channels >= 2) else {
+        return Ok(None);
+    };
+    let mut command = tool_command("ffmpeg");
+    command
+        .arg("-i")
+        .arg(audio_file_path)
+        .arg("-filter_complex")
+        .arg(per_channel_filtergraph(channels))
+        .args(["-vn", "-sn", "-dn"])
+        .args(["-f", "null", "-hide_banner", "-loglevel", "info", "-"]);
+    let command_line = announce_command(&command, options);
+
+    let output = command.output().map_err(LraCalculationError::Spawn)?;
+    ensure_success(&output, &command_line)?;
+    parse_channel_summaries(&String::from_utf8_lossy(&output.stderr), channels).map(Some)
+}
+
+/// 声道间综合响度之差：静音声道与有信号的声道之间为正无穷，全部静音时为 0
+fn channel_difference(levels: impl IntoIterator<Item = f64>) -> f64 {
+    let (min, max) = levels
+        .into_iter()
+        .fold((f64::INFINITY, f64::NEG_INFINITY), |(min, max), lufs| (min.min(lufs), max.max(lufs)));
+    if max == f64::NEG_INFINITY || min > max {
+        return 0.0;
+    }
+    max - min
+}
+
+/// 找出声道间综合响度差异超过阈值的文件 (Find Channel Imbalances)
+///
+/// # 参数
+/// - `measurements` - 成功的测量结果；没有逐声道测量值的文件被忽略
+/// - `threshold` - 差异阈值（LU）
+///
+/// # 返回值
+/// - 差异大于阈值的文件，按测量结果的顺序排列
+pub fn find_channel_imbalances(measurements: &[FileMeasurement], threshold: f64) -> Vec<ChannelImbalance> {
+    measurements
+        .iter()
+        .filter_map(|measurement| {
+            let channels = measurement.channels.as_deref()?;
+            (channel_difference(channels.iter().map(|channel| channel.integrated_lufs)) > threshold).then(|| ChannelImbalance {
+                path: measurement.path.clone(),
+                integrated_lufs: channels
+                    .iter()
+                    .map(|channel| Some(channel.integrated_lufs).filter(|lufs| lufs.is_finite()))
+                    .collect(),
+            })
+        })
+        .collect()
+}
+
+/// 结果文件中的逐声道列值：各声道的综合响度和 LRA，以 `/` 分隔
+///
+/// # 参数
+/// - `channels` - 各声道的测量值
+/// - `precision` - LRA 值保留的小数位数（与结果文件相同）
+///
+/// # 示例
+/// ```
+/// use lra_calculator_rust::channels::{channel_column_values, ChannelLoudness};
+///
+/// let channels = [
+///     ChannelLoudness { integrated_lufs: -20.04, lra: 7.3 },
+///     ChannelLoudness { integrated_lufs: f64::NEG_INFINITY, lra: 0.0 },
+/// ];
+/// assert_eq!(channel_column_values(&channels, 1), ["-20.0/-inf".to_string(), "7.3/0.0".to_string()]);
+/// assert_eq!(channel_column_values(&channels, 2)[1], "7.30/0.00");
+/// ```
+pub fn channel_column_values(channels: &[ChannelLoudness], precision: u8) -> [String; 2] {
+    let join = |values: Vec<String>| values.join("/");
+    [
+        join(channels.iter().map(|channel| format!("{:.1}", channel.integrated_lufs)).collect()),
+        join(channels.iter().map(|channel| LraValue(channel.lra).with_precision(precision).to_string()).collect()),
+    ]
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// 测试四声道滤镜图的标签和实例名
+    #[test]
+    fn test_per_channel_filtergraph() {
+        let graph = per_channel_filtergraph(4);
+        assert!(graph.starts_with("[0:a:0]asplit=4[s0][s1][s2][s3];"));
+        assert!(graph.contains(";[s3]pan=mono|c0=c3,ebur128@ch3[m3];"));
+        assert!(graph.ends_with(";[m0][m1][m2][m3]amerge=inputs=4"));
+        assert_eq!(graph.matches("ebur128@ch").count(), 4);
+    }
+
+    /// 测试按实例名拆分各声道的汇总块，缺少声道时报错
+    #[test]
+    fn test_parse_channel_summaries() {
+        let output = "\
+[ebur128@ch0 @ 0x1] t: 0.1 TARGET:-23 LUFS M: -20.0 S:-120.7 I: -19.0 LUFS LRA: 0.0 LU
+[ebur128@ch1 @ 0x2] Summary:
+
+  Integrated loudness:
+    I:         -inf LUFS
+    Threshold: -70.0 LUFS
+
+  Loudness range:
+    LRA:         0.0 LU
+[ebur128@ch0 @ 0x1] Summary:
+
+  Integrated loudness:
+    I:         -20.5 LUFS
+    Threshold: -30.6 LUFS
+
+  Loudness range:
+    LRA:         7.4 LU
+";
+        let channels = parse_channel_summaries(output, 2).unwrap();
+        assert_eq!(channels[0], ChannelLoudness { integrated_lufs: -20.5, lra: 7.4 });
+        assert_eq!(channels[1], ChannelLoudness { integrated_lufs: f64::NEG_INFINITY, lra: 0.0 });
+        assert!(matches!(parse_channel_summaries(output, 3), Err(LraCalculationError::ParseFailed { .. })));
+    }
+
+    /// 测试差异阈值、静音声道和单声道文件
+    #[test]
+    fn test_find_channel_imbalances() {
+        let loudness = |levels: &[f64]| {
+            levels.iter().map(|&integrated_lufs| ChannelLoudness { integrated_lufs, lra: 5.0 }).collect::<Vec<_>>()
+        };
+        let measurements = [
+            FileMeasurement::new("balanced.wav", 5.0).with_channels(Some(loudness(&[-20.0, -22.0]))),
+            FileMeasurement::new("quiet_right.wav", 5.0).with_channels(Some(loudness(&[-20.0, -30.0]))),
+            FileMeasurement::new("dead_left.wav", 5.0).with_channels(Some(loudness(&[f64::NEG_INFINITY, -20.0]))),
+            FileMeasurement::new("silent.wav", 0.0).with_channels(Some(loudness(&[f64::NEG_INFINITY; 2]))),
+            FileMeasurement::new("mono.wav", 5.0),
+        ];
+        let imbalances = find_channel_imbalances(&measurements, DEFAULT_CHANNEL_IMBALANCE);
+        assert_eq!(imbalances.len(), 2);
+        assert_eq!(imbalances[0].difference(), 10.0);
+        assert_eq!(imbalances[0].to_string(), "quiet_right.wav: -20.0 / -30.0 LUFS（相差 10.0 LU）");
+        assert_eq!(imbalances[1].integrated_lufs, [None, Some(-20.0)]);
+        assert_eq!(imbalances[1].to_string(), "dead_left.wav: 静音 / -20.0 LUFS（有静音声道）");
+        assert!(find_channel_imbalances(&measurements, 1.0).iter().any(|imbalance| imbalance.path == "balanced.wav"));
+    }
+}
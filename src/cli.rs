@@ -0,0 +1,1697 @@
+//! 命令行参数模块 (Command-Line Arguments Module)
+//!
+//! 本模块负责解析程序的命令行参数。程序默认以交互模式运行，
+//! 命令行参数用于在不修改交互流程的前提下开启可选功能。
+//!
+//! ## 子命令
+//!
+//! - `analyze [文件夹]` - 分析文件夹中的音频文件（默认）
+//! - `sort <结果文件>` - 重新排序已有的结果文件
+//! - `merge <结果文件...> -o <输出>` - 合并多个结果文件
+//! - `stats <结果文件>` - 显示已有结果文件的 LRA 分布统计
+//! - `history <历史文件>` - 以表格列出 `--run-history` 记录的历次运行
+//! - `check [--full]` - 检查运行环境并输出诊断信息，`--full` 额外执行分析自检
+//!
+//! 为保持向后兼容，不带子命令时第一个位置参数被视为要分析的文件夹。
+//!
+//! ## 设计原则
+//!
+//! - **零依赖**: 使用标准库手动解析，保持依赖精简
+//! - **可测试性**: 解析函数接受任意字符串迭代器，不直接读取 `std::env`
+//! - **友好报错**: 未知参数或缺少参数值时返回 `AppError::Configuration`
+
+use std::path::{Path, PathBuf};
+use std::time::{Duration, SystemTime};
+
+use chrono::{DateTime, Local, NaiveDate, NaiveDateTime, TimeZone};
+
+use crate::audio::Downmix;
+use crate::cache::CacheVersionPolicy;
+use crate::concurrency::ConcurrencyBand;
+use crate::console::{CharsetChoice, ColorChoice};
+use crate::error::AppError;
+use crate::exclude::validate_pattern;
+use crate::formats::normalize_extension;
+use crate::grouping::GroupKey;
+use crate::hash::HashAlgorithm;
+use crate::precision::{LraRange, DEFAULT_LRA_PRECISION, MAX_LRA_PRECISION};
+use crate::utils::SortOrder;
+use crate::versions::NameNormalization;
+use crate::thresholds::{LraThresholds, LufsRange};
+use crate::utils::stream::StreamingConfig;
+use crate::breaker::OutageConfig;
+use crate::utils::template::FileNameTemplate;
+use crate::utils::{expand_path, TextFormat};
+
+/// `--output` 取该值时结果输出到 stdout
+pub const STDOUT_OUTPUT: &str = "-";
+
+/// 子命令 (Subcommand)
+#[derive(Debug, Clone, Default, PartialEq)]
+pub enum Command {
+    /// 分析文件夹（文件夹来自 [`CliOptions::folder`]，未指定时交互式询问）
+    #[default]
+    Analyze,
+    /// 重新排序已有的结果文件
+    Sort {
+        /// 结果文件路径
+        results_file: PathBuf,
+    },
+    /// 合并多个结果文件，输出路径来自 [`CliOptions::output`]
+    Merge {
+        /// 要合并的结果文件
+        inputs: Vec<PathBuf>,
+    },
+    /// 显示已有结果文件的 LRA 分布统计
+    Stats {
+        /// 结果文件路径
+        results_file: PathBuf,
+        /// 额外以表格列出 LRA 最高和最低的各 N 个条目（`--top <N>`）
+        top: Option<usize>,
+    },
+    /// 以表格列出运行历史文件中的历次运行
+    History {
+        /// 运行历史文件路径（`--run-history` 写入的文件）
+        history_file: PathBuf,
+    },
+    /// 比较不同目录中同名音轨的 LRA（规范化规则来自 [`CliOptions::name_normalization`]）
+    CompareVersions {
+        /// 结果文件路径
+        results_file: PathBuf,
+    },
+    /// 直接分析一个或多个音频文件，不扫描、不写结果文件
+    File {
+        /// 要分析的音频文件
+        paths: Vec<PathBuf>,
+        /// 每个文件输出一行测量结果的 JSON（`--json`）
+        json: bool,
+    },
+    /// 检查运行环境
+    Check {
+        /// 是否额外生成测试音频并完整分析一遍（`--full`）
+        full: bool,
+    },
+}
+
+/// 机器可读输出格式 (Porcelain Format)
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum PorcelainFormat {
+    /// 每行一个 JSON 事件（见 [`crate::events::ProgressEvent`]）
+    Ndjson,
+    /// 空格分隔的文本记录，如 `PROGRESS 3 10`（见 [`crate::events::ProgressLineFormatter`]）
+    Progress,
+}
+
+/// 命令行选项 (Command-Line Options)
+///
+/// 保存解析后的命令行参数。所有字段都有合理的默认值，
+/// 不传任何参数时程序的行为与交互模式完全一致。
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct CliOptions {
+    /// 是否只显示帮助信息
+    pub show_help: bool,
+    /// 要执行的子命令
+    pub command: Command,
+    /// 要处理的文件夹（位置参数）；未提供时交互式询问
+    pub folder: Option<PathBuf>,
+    /// 运行结束时写入 JSON 统计信息的路径（`--stats-json <path>`）
+    pub stats_json: Option<PathBuf>,
+    /// 运行结束时向该文件追加一条运行记录（`--run-history <路径>`）
+    pub run_history: Option<PathBuf>,
+    /// 结果文件路径（`--output <path>`），默认写入扫描根目录下的 `lra_results.txt`；
+    /// [`STDOUT_OUTPUT`] 表示输出到 stdout（见 [`CliOptions::output_to_stdout`]）
+    pub output: Option<PathBuf>,
+    /// 结果文件名模板（`--output-name <模板>`），展开后的文件名放在扫描根目录下
+    pub output_name: Option<FileNameTemplate>,
+    /// 没有找到需要处理的文件时不创建只有表头的结果文件（`--no-empty-results`）
+    pub no_empty_results: bool,
+    /// 不读取也不保存最近使用的文件夹（`--no-history`）
+    pub no_history: bool,
+    /// 即使结果文件被其他实例锁定也继续运行（`--force`）
+    pub force: bool,
+    /// 不读取也不更新结果缓存，所有文件都重新分析（`--no-cache`）
+    pub no_cache: bool,
+    /// FFmpeg 版本变化时如何处理缓存（`--cache-version-policy <策略>`）
+    pub cache_version_policy: CacheVersionPolicy,
+    /// 机器可读输出格式（`--porcelain <格式>`）；启用时人类可读输出改写到 stderr
+    pub porcelain: Option<PorcelainFormat>,
+    /// 额外支持的扩展名（`--add-ext ape,wv`，已规范化为小写、无前导点）
+    pub add_extensions: Vec<String>,
+    /// 不再支持的扩展名（`--remove-ext wma`，已规范化）
+    pub remove_extensions: Vec<String>,
+    /// 跳过小于该大小的音频文件（`--min-size <大小>`，字节）
+    pub min_size: Option<u64>,
+    /// 跳过大于该大小的音频文件（`--max-size <大小>`，字节）
+    pub max_size: Option<u64>,
+    /// 只处理修改时间晚于该时刻的文件（`--newer-than <时间>`）
+    pub newer_than: Option<SystemTime>,
+    /// 只处理修改时间晚于现有结果文件的文件（`--newer-than-results`）
+    pub newer_than_results: bool,
+    /// 按内容识别没有扩展名的文件（`--probe-unknown`）
+    pub probe_unknown: bool,
+    /// 跳过只有大小写不同的同一个文件（`--dedup-case`）
+    pub dedup_case: bool,
+    /// 边扫描边分析，不等待扫描完成（`--pipeline`）
+    pub pipeline: bool,
+    /// 只输出处理计划，不分析也不写入结果（`--dry-run`）
+    pub dry_run: bool,
+    /// 不跳过默认的无关目录（`--no-default-excludes`）
+    pub no_default_excludes: bool,
+    /// 额外跳过的目录名（`--exclude-dir <目录名>`，可重复）
+    pub exclude_dirs: Vec<String>,
+    /// 额外排除的文件名模式（`--exclude <模式>`，可重复，已校验）
+    pub exclude_patterns: Vec<String>,
+    /// 只分析文件名匹配这些模式的音频文件（`--only <模式>`，可重复，已校验）
+    pub only_patterns: Vec<String>,
+    /// CI 门禁阈值（`--fail-if-lra-above` / `--fail-if-lra-below` / `--fail-if-lufs-outside`）
+    pub thresholds: LraThresholds,
+    /// 处理阶段的时间预算（`--max-duration <时长>`），到期后不再开始新的文件
+    pub max_duration: Option<Duration>,
+    /// 按系统负载和可用内存在该区间内调整同时运行的 FFmpeg 数量（`--adaptive-jobs MIN-MAX`）
+    pub adaptive_jobs: Option<ConcurrencyBand>,
+    /// 网络共享断连时暂停、等待恢复并重新分析失败的文件（`--outage-threshold`、`--outage-budget`
+    /// 任一隐含启用，见 [`crate::breaker`]）
+    pub outage: Option<OutageConfig>,
+    /// 最先完成的 N 个文件全部以同一种错误失败时中止（`--abort-after <N>`，0 表示不中止）；未设置时使用
+    /// [`DEFAULT_ABORT_AFTER`](crate::abort::DEFAULT_ABORT_AFTER)
+    pub abort_after: Option<usize>,
+    /// 统计和控制台中最前和最后各保留的失败信息数量（`--max-error-messages <K>`）；未设置时使用
+    /// [`DEFAULT_RETAINED_ERRORS`](crate::processor::DEFAULT_RETAINED_ERRORS)。失败记录文件总是完整的
+    pub max_error_messages: Option<usize>,
+    /// 处理期间把完成的结果追加到部分结果文件（`--stream-results`，`--stream-sorted`、`--flush-every` 和
+    /// `--flush-interval` 隐含启用）；文件格式在写入时取 `text_format`
+    pub stream_results: Option<StreamingConfig>,
+    /// 只处理最先发现的 N 个文件（`--limit N`）
+    pub limit: Option<usize>,
+    /// 从发现的文件中随机抽取 N 个处理（`--sample N`）
+    pub sample: Option<usize>,
+    /// 打乱文件的处理顺序（`--shuffle`）
+    pub shuffle: bool,
+    /// 抽样和打乱顺序使用的随机种子（`--seed S`）；未设置时每次运行随机生成并显示
+    pub seed: Option<u64>,
+    /// 与该基线结果文件比较，检测 LRA 回归（`--baseline <文件>`）
+    pub baseline: Option<PathBuf>,
+    /// 视为回归的 LRA 变化量（`--regression-threshold <LU>`）；未设置时使用
+    /// [`DEFAULT_REGRESSION_THRESHOLD`](crate::baseline::DEFAULT_REGRESSION_THRESHOLD)
+    pub regression_threshold: Option<f64>,
+    /// 把基线中没有的新条目追加到基线文件（`--update-baseline`）
+    pub update_baseline: bool,
+    /// 将每个文件的完整 FFmpeg 输出写入该目录（`--debug-dump <dir>`）
+    pub debug_dump: Option<PathBuf>,
+    /// 分析每个文件前打印实际执行的 FFmpeg 命令行（`-v` / `--verbose`）
+    pub verbose: bool,
+    /// 不显示欢迎、完成横幅和 LRA 使用建议（`-q` / `--quiet`）；输出不是终端时也不显示
+    pub quiet: bool,
+    /// 用该文件的内容替换完成信息中的 LRA 使用建议（`--advice-file <path>`）
+    pub advice_file: Option<PathBuf>,
+    /// 不跳过视频、字幕和数据流，由 FFmpeg 自动选择输入流（`--keep-all-streams`）
+    pub keep_all_streams: bool,
+    /// 在结果文件中追加 ReplayGain 2.0 音轨增益和专辑增益列（`--replaygain`）
+    pub replaygain: bool,
+    /// 对每个目录的音轨做一次合并分析，写入 `ALBUM:<目录>` 条目（`--album-lra`）
+    pub album_lra: bool,
+    /// 在结果文件中追加艺术家、专辑、标题列（`--with-tags`）
+    pub with_tags: bool,
+    /// 按该键分组统计 LRA 并写入 `lra_by_<键>.txt`（`--group-by <键>`）
+    pub group_by: Option<GroupKey>,
+    /// 在结果文件中追加每个文件内容的摘要列（`--hash xxh3|sha1`）
+    pub hash: Option<HashAlgorithm>,
+    /// 是否使用颜色（`--color always|auto|never`）
+    pub color: ColorChoice,
+    /// 输出字符集（`--ascii` / `--no-emoji` 只用 ASCII，`--unicode` 关闭自动判断）
+    pub charset: CharsetChoice,
+    /// 照常分析云端占位文件，允许读取时触发下载（`--hydrate`）
+    pub hydrate: bool,
+    /// 以低优先级启动 FFmpeg 和 ffprobe（`--nice`）
+    pub nice: bool,
+    /// 在结果文件末尾追加汇总页脚（`--summary-footer`）
+    pub summary_footer: bool,
+    /// 有失败文件时在结果文件旁生成诊断命令脚本（`--inspect-script`）
+    pub inspect_script: bool,
+    /// 写入结果文件后不重新读取校验（`--no-verify`）
+    pub no_verify: bool,
+    /// 结果文件和分组统计文件的 BOM 和换行格式（`--bom`、`--crlf`；Windows 上默认都启用）
+    pub text_format: TextFormat,
+    /// LRA 值保留的小数位数（`--precision N`）；未指定时为一位
+    pub precision: Option<u8>,
+    /// LRA 合理范围（`--lra-range MIN-MAX`）；未指定时为 0–40 LU
+    pub lra_range: Option<LraRange>,
+    /// 结果文件的排序方式（`--sort lra|deviation:N`）；未指定时按 LRA 值从高到低，
+    /// `sort` 子命令未指定时沿用文件中记录的排序方式
+    pub sort_order: Option<SortOrder>,
+    /// 分析结束后比较同名音轨的 LRA（`--compare-versions`）
+    pub compare_versions: bool,
+    /// 比较同名音轨时只列出 LRA 差异不小于该值的组（`--min-spread <LU>`）；未指定时为
+    /// [`crate::versions::DEFAULT_MIN_SPREAD`]
+    pub min_spread: Option<f64>,
+    /// 比较同名音轨时保留文件名开头的音轨编号（`--keep-track-numbers`）
+    pub keep_track_numbers: bool,
+    /// 送入 ebur128 之前先缩混（`--downmix stereo`）
+    pub downmix: Option<Downmix>,
+    /// 多声道文件额外测量缩混后的 LRA，写入单独的列（`--also-downmixed`）
+    pub also_downmixed: bool,
+    /// 额外逐声道测量综合响度和 LRA，写入单独的列（`--per-channel`）
+    pub per_channel: bool,
+    /// 声道间综合响度差异超过该值（LU）的文件在统计中标记（`--channel-imbalance <LU>`）；
+    /// 未指定时为 [`crate::channels::DEFAULT_CHANNEL_IMBALANCE`]
+    pub channel_imbalance: Option<f64>,
+    /// 额外测量真峰值，列出超过上限的文件（`--true-peak`）
+    pub true_peak: bool,
+    /// 真峰值上限（dBTP，`--true-peak-ceiling <dBTP>`）；未指定时为
+    /// [`crate::peak::DEFAULT_TRUE_PEAK_CEILING`]
+    pub true_peak_ceiling: Option<f64>,
+    /// 把真峰值超过上限的文件写入该报告文件（`--clipping-report <path>`）
+    pub clipping_report: Option<PathBuf>,
+    /// 在结果文件中追加 LRA 下限、上限和综合响度门限列（`--range-details`）
+    pub range_details: bool,
+}
+
+/// 解析命令行参数 (Parse Command-Line Arguments)
+///
+/// 支持 `--flag value` 和 `--flag=value` 两种写法，选项可以出现在子命令前后。
+/// 路径类选项值中的 `~` 和环境变量会被展开（见 [`expand_path`]），位置参数按字面处理。
+///
+/// # 参数
+/// - `args` - 参数列表（不包含程序名本身）
+///
+/// # 返回值
+/// - `Ok(CliOptions)` - 解析成功
+/// - `Err(AppError::Configuration)` - 未知参数、缺少参数值或位置参数数量不对
+///
+/// # 示例
+/// ```
+/// use lra_calculator_rust::cli::parse_args;
+///
+/// let options = parse_args(["--stats-json", "stats.json"]).unwrap();
+/// assert_eq!(options.stats_json.unwrap().to_str(), Some("stats.json"));
+/// ```
+pub fn parse_args<I, S>(args: I) -> Result<CliOptions, AppError>
+where
+    I: IntoIterator<Item = S>,
+    S: Into<String>,
+{
+    let mut options = CliOptions::default();
+    let mut positionals = Vec::new();
+    // `--` 之后的参数都是位置参数；`--` 出现在第一个位置参数之前时，第一个位置参数不按子命令解释
+    let mut subcommand_allowed = true;
+    let mut full_check = false;
+    let mut top = None;
+    let mut json = false;
+    let mut args = args.into_iter().map(Into::into);
+
+    while let Some(arg) = args.next() {
+        // 拆分 `--flag=value` 形式的参数
+        let (flag, inline_value) = match arg.split_once('=') {
+            Some((flag, value)) if flag.starts_with("--") => (flag.to_string(), Some(value.to_string())),
+            _ => (arg, None),
+        };
+
+        match flag.as_str() {
+            "--" => {
+                subcommand_allowed = !positionals.is_empty();
+                positionals.extend(args.by_ref());
+            }
+            "-h" | "--help" => options.show_help = true,
+            "--no-history" => options.no_history = true,
+            "--full" => full_check = true,
+            "--json" => json = true,
+            "--force" => options.force = true,
+            "--no-cache" => options.no_cache = true,
+            "--no-empty-results" => options.no_empty_results = true,
+            "--probe-unknown" => options.probe_unknown = true,
+            "--dedup-case" => options.dedup_case = true,
+            "--newer-than-results" => options.newer_than_results = true,
+            "--no-default-excludes" => options.no_default_excludes = true,
+            "--pipeline" => options.pipeline = true,
+            "--dry-run" => options.dry_run = true,
+            "-v" | "--verbose" => options.verbose = true,
+            "-q" | "--quiet" => options.quiet = true,
+            "--ascii" | "--no-emoji" => options.charset = CharsetChoice::Ascii,
+            "--unicode" => options.charset = CharsetChoice::Unicode,
+            "--keep-all-streams" => options.keep_all_streams = true,
+            "--replaygain" => options.replaygain = true,
+            "--album-lra" => options.album_lra = true,
+            "--with-tags" => options.with_tags = true,
+            "--summary-footer" => options.summary_footer = true,
+            "--inspect-script" => options.inspect_script = true,
+            "--compare-versions" => options.compare_versions = true,
+            "--keep-track-numbers" => options.keep_track_numbers = true,
+            "--min-spread" => {
+                let value = take_value(&flag, inline_value, &mut args)?;
+                options.min_spread = Some(value.parse::<f64>().ok().filter(|spread| spread.is_finite() && *spread >= 0.0).ok_or_else(|| {
+                    AppError::Configuration(format!("无效的 --min-spread 取值 '{}'，需要一个非负数", value))
+                })?);
+            }
+            "--update-baseline" => options.update_baseline = true,
+            "--shuffle" => options.shuffle = true,
+            "--hydrate" => options.hydrate = true,
+            "--nice" => options.nice = true,
+            "--no-verify" => options.no_verify = true,
+            "--bom" => options.text_format.bom = true,
+            "--crlf" => options.text_format.crlf = true,
+            "--exclude-dir" => {
+                let value = take_value(&flag, inline_value, &mut args)?;
+                options.exclude_dirs.push(parse_dir_name(&value)?);
+            }
+            "--exclude" => {
+                let value = take_value(&flag, inline_value, &mut args)?;
+                options.exclude_patterns.push(validate_pattern(&value)?);
+            }
+            "--only" => {
+                let value = take_value(&flag, inline_value, &mut args)?;
+                options.only_patterns.push(validate_pattern(&value)?);
+            }
+            "--newer-than" => {
+                let value = take_value(&flag, inline_value, &mut args)?;
+                options.newer_than = Some(parse_timestamp(&value)?);
+            }
+            "--stats-json" => {
+                let value = take_value(&flag, inline_value, &mut args)?;
+                options.stats_json = Some(expand_path(&value));
+            }
+            "--run-history" => {
+                let value = take_value(&flag, inline_value, &mut args)?;
+                options.run_history = Some(expand_path(&value));
+            }
+            "--debug-dump" => {
+                let value = take_value(&flag, inline_value, &mut args)?;
+                options.debug_dump = Some(expand_path(&value));
+            }
+            "--porcelain" => {
+                let value = take_value(&flag, inline_value, &mut args)?;
+                options.porcelain = Some(match value.as_str() {
+                    "ndjson" => PorcelainFormat::Ndjson,
+                    "progress" => PorcelainFormat::Progress,
+                    other => {
+                        return Err(AppError::Configuration(format!(
+                            "不支持的 --porcelain 格式 '{}'，可用格式: ndjson、progress",
+                            other
+                        )));
+                    }
+                });
+            }
+            "--top" => {
+                let value = take_value(&flag, inline_value, &mut args)?;
+                top = Some(value.parse::<usize>().ok().filter(|count| *count > 0).ok_or_else(|| {
+                    AppError::Configuration(format!("无效的 --top 取值 '{}'，需要一个正整数", value))
+                })?);
+            }
+            "--group-by" => {
+                let value = take_value(&flag, inline_value, &mut args)?;
+                options.group_by = Some(GroupKey::parse(&value).ok_or_else(|| {
+                    AppError::Configuration(format!(
+                        "不支持的 --group-by 分组键 '{}'，可用分组键: artist, album, directory",
+                        value
+                    ))
+                })?);
+            }
+            "--hash" => {
+                let value = take_value(&flag, inline_value, &mut args)?;
+                options.hash = Some(HashAlgorithm::parse(&value).ok_or_else(|| {
+                    AppError::Configuration(format!("不支持的 --hash 算法 '{}'，可用算法: xxh3, sha1", value))
+                })?);
+            }
+            "--color" => {
+                let value = take_value(&flag, inline_value, &mut args)?;
+                options.color = ColorChoice::parse(&value).ok_or_else(|| {
+                    AppError::Configuration(format!("不支持的 --color 取值 '{}'，可用取值: always, auto, never", value))
+                })?;
+            }
+            "--cache-version-policy" => {
+                let value = take_value(&flag, inline_value, &mut args)?;
+                options.cache_version_policy = CacheVersionPolicy::parse(&value).ok_or_else(|| {
+                    AppError::Configuration(format!(
+                        "不支持的 --cache-version-policy 策略 '{}'，可用策略: invalidate, mark-stale",
+                        value
+                    ))
+                })?;
+            }
+            "--add-ext" => {
+                let value = take_value(&flag, inline_value, &mut args)?;
+                options.add_extensions.extend(parse_extension_list(&value)?);
+            }
+            "--remove-ext" => {
+                let value = take_value(&flag, inline_value, &mut args)?;
+                options.remove_extensions.extend(parse_extension_list(&value)?);
+            }
+            "--precision" => {
+                let value = take_value(&flag, inline_value, &mut args)?;
+                options.precision = Some(value.parse::<u8>().ok().filter(|digits| *digits <= MAX_LRA_PRECISION).ok_or_else(|| {
+                    AppError::Configuration(format!(
+                        "无效的 --precision 取值 '{}'，需要 0 到 {} 之间的整数",
+                        value, MAX_LRA_PRECISION
+                    ))
+                })?);
+            }
+            "--lra-range" => {
+                let value = take_value(&flag, inline_value, &mut args)?;
+                options.lra_range = Some(LraRange::parse(&value).ok_or_else(|| {
+                    AppError::Configuration(format!(
+                        "无效的 --lra-range 取值 '{}'，格式为 最小值-最大值，如 0-40",
+                        value
+                    ))
+                })?);
+            }
+            "--downmix" => {
+                let value = take_value(&flag, inline_value, &mut args)?;
+                options.downmix = Some(Downmix::parse(&value).ok_or_else(|| {
+                    AppError::Configuration(format!("不支持的 --downmix 取值 '{}'，可用取值: stereo", value))
+                })?);
+            }
+            "--also-downmixed" => options.also_downmixed = true,
+            "--per-channel" => options.per_channel = true,
+            "--true-peak" => options.true_peak = true,
+            "--range-details" => options.range_details = true,
+            "--true-peak-ceiling" => {
+                let value = take_value(&flag, inline_value, &mut args)?;
+                options.true_peak_ceiling = Some(value.parse::<f64>().ok().filter(|ceiling| ceiling.is_finite()).ok_or_else(|| {
+                    AppError::Configuration(format!("参数 '{}' 需要一个数值 (dBTP)，实际为 '{}'", flag, value))
+                })?);
+            }
+            "--advice-file" => {
+                let value = take_value(&flag, inline_value, &mut args)?;
+                options.advice_file = Some(expand_path(&value));
+            }
+            "--clipping-report" => {
+                let value = take_value(&flag, inline_value, &mut args)?;
+                options.clipping_report = Some(expand_path(&value));
+            }
+            "--channel-imbalance" => {
+                let value = take_value(&flag, inline_value, &mut args)?;
+                options.channel_imbalance = Some(parse_lra_value(&flag, &value)?);
+            }
+            "--sort" => {
+                let value = take_value(&flag, inline_value, &mut args)?;
+                options.sort_order = Some(SortOrder::parse(&value).ok_or_else(|| {
+                    AppError::Configuration(format!(
+                        "无效的 --sort 取值 '{}'，可选 lra 或 deviation:<目标 LRA>，如 deviation:9.0",
+                        value
+                    ))
+                })?);
+            }
+            "--min-size" => {
+                let value = take_value(&flag, inline_value, &mut args)?;
+                options.min_size = Some(parse_byte_size(&value)?);
+            }
+            "--max-size" => {
+                let value = take_value(&flag, inline_value, &mut args)?;
+                options.max_size = Some(parse_byte_size(&value)?);
+            }
+            "--fail-if-lra-above" => {
+                let value = take_value(&flag, inline_value, &mut args)?;
+                options.thresholds.max_lra = Some(parse_lra_value(&flag, &value)?);
+            }
+            "--fail-if-lra-below" => {
+                let value = take_value(&flag, inline_value, &mut args)?;
+                options.thresholds.min_lra = Some(parse_lra_value(&flag, &value)?);
+            }
+            "--fail-if-lufs-outside" => {
+                let value = take_value(&flag, inline_value, &mut args)?;
+                options.thresholds.lufs_range = Some(LufsRange::parse(&value).ok_or_else(|| {
+                    AppError::Configuration(format!(
+                        "无效的 --fail-if-lufs-outside 取值 '{}'，需要 A:B 形式的综合响度范围且 A 不大于 B (如 -24:-14)",
+                        value
+                    ))
+                })?);
+            }
+            "--max-duration" => {
+                let value = take_value(&flag, inline_value, &mut args)?;
+                options.max_duration = Some(parse_duration(&value)?);
+            }
+            "--adaptive-jobs" => {
+                let value = take_value(&flag, inline_value, &mut args)?;
+                options.adaptive_jobs = Some(parse_concurrency_band(&value)?);
+            }
+            "--outage-threshold" => {
+                let value = take_value(&flag, inline_value, &mut args)?;
+                options.outage.get_or_insert_with(OutageConfig::default).threshold = parse_file_count(&flag, &value)?;
+            }
+            "--outage-budget" => {
+                let value = take_value(&flag, inline_value, &mut args)?;
+                options.outage.get_or_insert_with(OutageConfig::default).pause_budget = parse_duration(&value)?;
+            }
+            "--abort-after" => {
+                let value = take_value(&flag, inline_value, &mut args)?;
+                options.abort_after = Some(value.parse::<usize>().map_err(|_| {
+                    AppError::Configuration(format!("无效的 --abort-after 取值 '{}'，需要一个非负整数", value))
+                })?);
+            }
+            "--max-error-messages" => {
+                let value = take_value(&flag, inline_value, &mut args)?;
+                options.max_error_messages = Some(parse_file_count(&flag, &value)?);
+            }
+            "--stream-results" => {
+                options.stream_results.get_or_insert_with(StreamingConfig::default);
+            }
+            "--stream-sorted" => options.stream_results.get_or_insert_with(StreamingConfig::default).sorted = true,
+            "--flush-every" => {
+                let value = take_value(&flag, inline_value, &mut args)?;
+                options.stream_results.get_or_insert_with(StreamingConfig::default).flush_every =
+                    parse_file_count(&flag, &value)?;
+            }
+            "--flush-interval" => {
+                let value = take_value(&flag, inline_value, &mut args)?;
+                options.stream_results.get_or_insert_with(StreamingConfig::default).flush_interval =
+                    parse_duration(&value)?;
+            }
+            "--limit" => {
+                let value = take_value(&flag, inline_value, &mut args)?;
+                options.limit = Some(parse_file_count(&flag, &value)?);
+            }
+            "--sample" => {
+                let value = take_value(&flag, inline_value, &mut args)?;
+                options.sample = Some(parse_file_count(&flag, &value)?);
+            }
+            "--seed" => {
+                let value = take_value(&flag, inline_value, &mut args)?;
+                options.seed = Some(value.parse::<u64>().map_err(|_| {
+                    AppError::Configuration(format!("无效的 --seed 取值 '{}'，需要一个非负整数", value))
+                })?);
+            }
+            "--baseline" => {
+                let value = take_value(&flag, inline_value, &mut args)?;
+                options.baseline = Some(expand_path(&value));
+            }
+            "--regression-threshold" => {
+                let value = take_value(&flag, inline_value, &mut args)?;
+                options.regression_threshold = Some(parse_lra_value(&flag, &value)?);
+            }
+            "-o" | "--output" => {
+                let value = take_value(&flag, inline_value, &mut args)?;
+                options.output = Some(expand_path(&value));
+            }
+            "--output-name" => {
+                let value = take_value(&flag, inline_value, &mut args)?;
+                options.output_name = Some(FileNameTemplate::parse(&value)?);
+            }
+            _ if !flag.starts_with('-') => positionals.push(flag),
+            _ => {
+                return Err(AppError::Configuration(format!(
+                    "未知参数 '{}'。使用 --help 查看可用参数。",
+                    flag
+                )));
+            }
+        }
+    }
+
+    apply_positionals(&mut options, positionals, subcommand_allowed)?;
+
+    if let (Some(min), Some(max)) = (options.thresholds.min_lra, options.thresholds.max_lra) {
+        if min > max {
+            return Err(AppError::Configuration(format!(
+                "--fail-if-lra-below ({}) 不能大于 --fail-if-lra-above ({})",
+                min, max
+            )));
+        }
+    }
+
+    if options.limit.is_some() && options.sample.is_some() {
+        return Err(AppError::Configuration("--limit 和 --sample 不能同时使用".to_string()));
+    }
+
+    if options.seed.is_some() && options.sample.is_none() && !options.shuffle {
+        return Err(AppError::Configuration("--seed 需要与 --sample 或 --shuffle 一起使用".to_string()));
+    }
+
+    if options.pipeline && (options.limit.is_some() || options.sample.is_some() || options.shuffle) {
+        return Err(AppError::Configuration(
+            "--pipeline 不能与 --limit、--sample 或 --shuffle 同时使用（需要先完成扫描才能选择或打乱文件）".to_string(),
+        ));
+    }
+
+    if options.baseline.is_none() && (options.regression_threshold.is_some() || options.update_baseline) {
+        return Err(AppError::Configuration(
+            "--regression-threshold 和 --update-baseline 需要与 --baseline 一起使用".to_string(),
+        ));
+    }
+
+    if options.newer_than.is_some() && options.newer_than_results {
+        return Err(AppError::Configuration(
+            "--newer-than 和 --newer-than-results 不能同时使用".to_string(),
+        ));
+    }
+
+    // 结果文件不记录综合响度，增量更新时无法用目录中全部音轨重新计算专辑增益
+    if options.replaygain && (options.newer_than.is_some() || options.newer_than_results) {
+        return Err(AppError::Configuration(
+            "--replaygain 不能与 --newer-than 或 --newer-than-results 同时使用\
+             （专辑增益需要目录中所有音轨的综合响度，增量更新只分析其中变化的文件）"
+                .to_string(),
+        ));
+    }
+
+    if options.output_name.is_some() {
+        if options.output.is_some() {
+            return Err(AppError::Configuration(
+                "--output-name 和 -o/--output 不能同时使用（--output 已经指定了完整路径）".to_string(),
+            ));
+        }
+        if !matches!(options.command, Command::Analyze) {
+            return Err(AppError::Configuration("--output-name 只能在分析时使用".to_string()));
+        }
+    }
+
+    if options.output_to_stdout() {
+        if !matches!(options.command, Command::Analyze) {
+            return Err(AppError::Configuration("只有分析时可以用 --output - 输出到 stdout".to_string()));
+        }
+        let conflicts = [
+            ("--porcelain", options.porcelain.is_some()),
+            ("--group-by", options.group_by.is_some()),
+            ("--stream-results", options.stream_results.is_some()),
+            ("--inspect-script", options.inspect_script),
+            ("--newer-than", options.newer_than.is_some()),
+            ("--newer-than-results", options.newer_than_results),
+        ];
+        if let Some((flag, _)) = conflicts.iter().find(|(_, set)| *set) {
+            return Err(AppError::Configuration(format!(
+                "--output - 不能与 {} 同时使用（结果输出到 stdout 时磁盘上没有结果文件）",
+                flag
+            )));
+        }
+    }
+
+    let custom_sort = options.sort_order.is_some_and(|order| order != SortOrder::default());
+    if custom_sort && options.stream_results.as_ref().is_some_and(|config| config.sorted) {
+        return Err(AppError::Configuration(
+            "--stream-sorted 只能按 LRA 值排序，不能与 --sort deviation:<目标> 同时使用".to_string(),
+        ));
+    }
+
+    if options.downmix.is_some() && options.also_downmixed {
+        return Err(AppError::Configuration(
+            "--downmix 和 --also-downmixed 不能同时使用（--downmix 已经只测量缩混后的值）".to_string(),
+        ));
+    }
+
+    if options.channel_imbalance.is_some() && !options.per_channel {
+        return Err(AppError::Configuration("--channel-imbalance 需要与 --per-channel 一起使用".to_string()));
+    }
+
+    if !options.true_peak && (options.true_peak_ceiling.is_some() || options.clipping_report.is_some()) {
+        return Err(AppError::Configuration(
+            "--true-peak-ceiling 和 --clipping-report 需要与 --true-peak 一起使用".to_string(),
+        ));
+    }
+
+    if options.pipeline && options.probe_unknown {
+        return Err(AppError::Configuration(
+            "--pipeline 和 --probe-unknown 不能同时使用".to_string(),
+        ));
+    }
+
+    if options.pipeline && options.dry_run {
+        return Err(AppError::Configuration(
+            "--pipeline 和 --dry-run 不能同时使用（--pipeline 不等扫描完成就开始分析，没有完整的计划）".to_string(),
+        ));
+    }
+
+    if let (Some(min), Some(max)) = (options.min_size, options.max_size) {
+        if min > max {
+            return Err(AppError::Configuration(format!(
+                "--min-size ({} 字节) 不能大于 --max-size ({} 字节)",
+                min, max
+            )));
+        }
+    }
+
+    match &mut options.command {
+        Command::Check { full } => *full = full_check,
+        _ if full_check => {
+            return Err(AppError::Configuration(
+                "参数 '--full' 只能与子命令 'check' 一起使用".to_string(),
+            ));
+        }
+        _ => {}
+    }
+    match &mut options.command {
+        Command::Stats { top: stats_top, .. } => *stats_top = top,
+        _ if top.is_some() => {
+            return Err(AppError::Configuration(
+                "参数 '--top' 只能与子命令 'stats' 一起使用".to_string(),
+            ));
+        }
+        _ => {}
+    }
+    match &mut options.command {
+        Command::File { json: file_json, .. } => *file_json = json,
+        _ if json => {
+            return Err(AppError::Configuration(
+                "参数 '--json' 只能与子命令 'file' 一起使用".to_string(),
+            ));
+        }
+        _ => {}
+    }
+
+    let compares_versions = options.compare_versions || matches!(options.command, Command::CompareVersions { .. });
+    if options.compare_versions && !matches!(options.command, Command::Analyze) {
+        return Err(AppError::Configuration(
+            "--compare-versions 只能在分析时使用；比较已有结果文件请用子命令 'compare-versions'".to_string(),
+        ));
+    }
+    if !compares_versions && (options.min_spread.is_some() || options.keep_track_numbers) {
+        return Err(AppError::Configuration(
+            "--min-spread 和 --keep-track-numbers 需要与 --compare-versions 或子命令 'compare-versions' 一起使用".to_string(),
+        ));
+    }
+    Ok(options)
+}
+
+impl CliOptions {
+    /// 结果是否输出到 stdout（`--output -`）
+    ///
+    /// 此时数据行写到 stdout，其他输出都改写到 stderr，磁盘上不创建结果文件、锁文件和失败记录。
+    pub fn output_to_stdout(&self) -> bool {
+        self.output.as_deref() == Some(Path::new(STDOUT_OUTPUT))
+    }
+
+    /// LRA 值保留的小数位数（`--precision`，默认 [`DEFAULT_LRA_PRECISION`]）
+    pub fn lra_precision(&self) -> u8 {
+        self.precision.unwrap_or(DEFAULT_LRA_PRECISION)
+    }
+
+    /// 标记可疑测量值使用的合理范围（`--lra-range`，默认 0–40 LU）
+    pub fn sanity_range(&self) -> LraRange {
+        self.lra_range.unwrap_or_default()
+    }
+
+    /// 比较同名音轨时使用的文件名规范化规则
+    pub fn name_normalization(&self) -> NameNormalization {
+        NameNormalization { strip_track_numbers: !self.keep_track_numbers, ..NameNormalization::default() }
+    }
+}
+
+/// 解释位置参数 (Apply Positional Arguments)
+///
+/// 第一个位置参数是子命令名时按子命令解释其余参数，
+/// 否则整体视为 `analyze` 的参数（向后兼容）。`subcommand_allowed` 为 `false`
+/// （位置参数写在 `--` 之后）时，与子命令同名的文件夹也按 `analyze` 的参数处理。
+fn apply_positionals(
+    options: &mut CliOptions,
+    positionals: Vec<String>,
+    subcommand_allowed: bool,
+) -> Result<(), AppError> {
+    let mut positionals = positionals.into_iter();
+    let first = positionals.next();
+
+    let (name, rest): (&str, Vec<String>) = match first.as_deref() {
+        Some(name @ ("analyze" | "sort" | "merge" | "stats" | "compare-versions" | "history" | "check" | "file"))
+            if subcommand_allowed =>
+        {
+            (name, positionals.collect())
+        }
+        Some(_) => ("analyze", first.into_iter().chain(positionals).collect()),
+        None => return Ok(()),
+    };
+
+    // 位置参数已经由 shell 展开过，这里按字面处理
+    let paths: Vec<PathBuf> = rest.iter().map(PathBuf::from).collect();
+
+    options.command = match (name, paths.len()) {
+        ("analyze", 0 | 1) => {
+            options.folder = paths.into_iter().next();
+            Command::Analyze
+        }
+        ("analyze", _) => {
+            return Err(AppError::Configuration(format!(
+                "多余的参数 '{}'。一次只能处理一个文件夹。",
+                rest[1]
+            )));
+        }
+        ("sort", 1) => Command::Sort {
+            results_file: paths.into_iter().next().unwrap_or_default(),
+        },
+        ("stats", 1) => Command::Stats {
+            results_file: paths.into_iter().next().unwrap_or_default(),
+            top: None,
+        },
+        ("compare-versions", 1) => Command::CompareVersions {
+            results_file: paths.into_iter().next().unwrap_or_default(),
+        },
+        ("history", 1) => Command::History {
+            history_file: paths.into_iter().next().unwrap_or_default(),
+        },
+        ("history", _) => {
+            return Err(AppError::Configuration(
+                "子命令 'history' 需要且只需要一个运行历史文件路径".to_string(),
+            ));
+        }
+        ("sort" | "stats" | "compare-versions", _) => {
+            return Err(AppError::Configuration(format!(
+                "子命令 '{}' 需要且只需要一个结果文件路径",
+                name
+            )));
+        }
+        ("merge", 0) => {
+            return Err(AppError::Configuration(
+                "子命令 'merge' 至少需要一个结果文件路径".to_string(),
+            ));
+        }
+        ("merge", _) if options.output.is_none() => {
+            return Err(AppError::Configuration(
+                "子命令 'merge' 需要用 -o/--output 指定输出文件".to_string(),
+            ));
+        }
+        ("merge", _) => Command::Merge { inputs: paths },
+        ("file", 0) => {
+            return Err(AppError::Configuration(
+                "子命令 'file' 至少需要一个音频文件路径".to_string(),
+            ));
+        }
+        ("file", _) => Command::File { paths, json: false },
+        ("check", 0) => Command::Check { full: false },
+        _ => {
+            return Err(AppError::Configuration(format!(
+                "子命令 '{}' 不接受参数 '{}'",
+                name, rest[0]
+            )));
+        }
+    };
+
+    Ok(())
+}
+
+/// 获取参数值 (Take Flag Value)
+///
+/// 优先使用 `--flag=value` 中的内联值，否则读取下一个参数。
+fn take_value(
+    flag: &str,
+    inline_value: Option<String>,
+    args: &mut impl Iterator<Item = String>,
+) -> Result<String, AppError> {
+    inline_value
+        .or_else(|| args.next())
+        .filter(|value| !value.is_empty())
+        .ok_or_else(|| AppError::Configuration(format!("参数 '{}' 需要一个值", flag)))
+}
+
+/// 解析逗号分隔的扩展名列表
+///
+/// 每个扩展名都会被规范化（见 [`normalize_extension`]）。
+fn parse_extension_list(value: &str) -> Result<Vec<String>, AppError> {
+    value.split(',').map(normalize_extension).collect()
+}
+
+/// 校验 `--exclude-dir` 的目录名
+///
+/// 目录名按精确匹配比较，不能为空，也不能包含路径分隔符。
+fn parse_dir_name(value: &str) -> Result<String, AppError> {
+    let name = value.trim();
+    if name.is_empty() || name == "." || name == ".." || name.contains(['/', '\\']) {
+        return Err(AppError::Configuration(format!(
+            "无效的目录名 '{}'，--exclude-dir 只接受单个目录名",
+            value
+        )));
+    }
+    Ok(name.to_string())
+}
+
+/// 解析文件大小 (Parse Byte Size)
+///
+/// 接受纯数字（字节）或带单位的写法，单位按 1024 进制、不区分大小写：
+/// `k`/`kb`/`kib`、`m`/`mb`/`mib`、`g`/`gb`/`gib`、`t`/`tb`/`tib`。
+/// 数字部分可以是小数（如 `1.5G`）。
+///
+/// # 参数
+/// - `value` - 用户输入的大小
+///
+/// # 返回值
+/// - `Ok(u64)` - 字节数
+/// - `Err(AppError::Configuration)` - 格式无效
+///
+/// # 示例
+/// ```
+/// use lra_calculator_rust::cli::parse_byte_size;
+///
+/// assert_eq!(parse_byte_size("500k").unwrap(), 500 * 1024);
+/// assert_eq!(parse_byte_size("2G").unwrap(), 2 * 1024 * 1024 * 1024);
+/// assert_eq!(parse_byte_size("4096").unwrap(), 4096);
+/// ```
+pub fn parse_byte_size(value: &str) -> Result<u64, AppError> {
+    let invalid = || {
+        AppError::Configuration(format!(
+            "无效的文件大小 '{}'，示例: 4096、500k、20M、2G",
+            value
+        ))
+    };
+
+    let trimmed = value.trim();
+    let split = trimmed
+        .find(|c: char| !(c.is_ascii_digit() || c == '.'))
+        .unwrap_or(trimmed.len());
+    let (number, unit) = trimmed.split_at(split);
+
+    let multiplier: u64 = match unit.trim().to_ascii_lowercase().as_str() {
+        "" | "b" => 1,
+        "k" | "kb" | "kib" => 1 << 10,
+        "m" | "mb" | "mib" => 1 << 20,
+        "g" | "gb" | "gib" => 1 << 30,
+        "t" | "tb" | "tib" => 1 << 40,
+        _ => return Err(invalid()),
+    };
+
+    let number: f64 = number.parse().map_err(|_| invalid())?;
+    let bytes = number * multiplier as f64;
+    if !bytes.is_finite() || bytes > u64::MAX as f64 {
+        return Err(invalid());
+    }
+    Ok(bytes.round() as u64)
+}
+
+/// 解析时长参数 (Parse Duration)
+///
+/// 由若干 `数值+单位` 组成，单位为 `h`、`m`（或 `min`）、`s`，没有单位的数值按秒计算；
+/// 数值可以带小数。结果必须大于零。
+///
+/// # 参数
+/// - `value` - 用户输入的时长，如 `2h`、`1h30m`、`90m`、`45`
+///
+/// # 返回值
+/// - `Ok(Duration)` - 解析得到的时长
+/// - `Err(AppError::Configuration)` - 格式无效或时长为零
+///
+/// # 示例
+/// ```
+/// use lra_calculator_rust::cli::parse_duration;
+/// use std::time::Duration;
+///
+/// assert_eq!(parse_duration("2h").unwrap(), Duration::from_secs(7200));
+/// assert_eq!(parse_duration("1h30m").unwrap(), Duration::from_secs(5400));
+/// assert_eq!(parse_duration("1.5m").unwrap(), Duration::from_secs(90));
+/// assert_eq!(parse_duration("45").unwrap(), Duration::from_secs(45));
+/// ```
+pub fn parse_duration(value: &str) -> Result<Duration, AppError> {
+    let invalid = || {
+        AppError::Configuration(format!(
+            "无效的时长 '{}'，示例: 2h、1h30m、90m、45s",
+            value
+        ))
+    };
+
+    let mut rest = value.trim();
+    let mut seconds = 0.0;
+    while !rest.is_empty() {
+        let number_end = rest.find(|c: char| !(c.is_ascii_digit() || c == '.')).unwrap_or(rest.len());
+        let (number, tail) = rest.split_at(number_end);
+        let unit_end = tail.find(|c: char| c.is_ascii_digit() || c == '.').unwrap_or(tail.len());
+        let (unit, tail) = tail.split_at(unit_end);
+
+        let multiplier = match unit.trim().to_ascii_lowercase().as_str() {
+            "" | "s" | "sec" => 1.0,
+            "m" | "min" => 60.0,
+            "h" | "hr" => 3600.0,
+            _ => return Err(invalid()),
+        };
+        let number: f64 = number.parse().map_err(|_| invalid())?;
+        seconds += number * multiplier;
+        rest = tail.trim_start();
+    }
+
+    Duration::try_from_secs_f64(seconds).ok().filter(|duration| !duration.is_zero()).ok_or_else(invalid)
+}
+
+/// 解析时间参数 (Parse Timestamp)
+///
+/// 支持 RFC 3339（如 `2024-01-01T12:00:00+08:00`）、本地日期时间
+/// （`2024-01-01 12:00:00`、`2024-01-01T12:00`）和仅日期（`2024-01-01`，
+/// 表示当地时间当天零点）。
+///
+/// # 参数
+/// - `value` - 用户输入的时间
+///
+/// # 返回值
+/// - `Ok(SystemTime)` - 解析得到的时刻
+/// - `Err(AppError::Configuration)` - 无法识别的格式
+pub fn parse_timestamp(value: &str) -> Result<SystemTime, AppError> {
+    const LOCAL_FORMATS: [&str; 4] = [
+        "%Y-%m-%dT%H:%M:%S",
+        "%Y-%m-%d %H:%M:%S",
+        "%Y-%m-%dT%H:%M",
+        "%Y-%m-%d %H:%M",
+    ];
+
+    let value = value.trim();
+    if let Ok(datetime) = DateTime::parse_from_rfc3339(value) {
+        return Ok(datetime.into());
+    }
+
+    let naive = LOCAL_FORMATS
+        .iter()
+        .find_map(|format| NaiveDateTime::parse_from_str(value, format).ok())
+        .or_else(|| {
+            NaiveDate::parse_from_str(value, "%Y-%m-%d")
+                .ok()
+                .and_then(|date| date.and_hms_opt(0, 0, 0))
+        });
+
+    naive
+        .and_then(|naive| Local.from_local_datetime(&naive).earliest())
+        .map(SystemTime::from)
+        .ok_or_else(|| {
+            AppError::Configuration(format!(
+                "无法识别的时间 '{}'，示例: 2024-01-01、2024-01-01 12:00、2024-01-01T12:00:00Z",
+                value
+            ))
+        })
+}
+
+/// 解析 LRA 数值参数
+///
+/// 只接受非负的有限数。
+fn parse_lra_value(flag: &str, value: &str) -> Result<f64, AppError> {
+    value
+        .parse::<f64>()
+        .ok()
+        .filter(|lra| lra.is_finite() && *lra >= 0.0)
+        .ok_or_else(|| {
+            AppError::Configuration(format!(
+                "参数 '{}' 需要一个非负数值 (LU)，实际为 '{}'",
+                flag, value
+            ))
+        })
+}
+
+/// 解析文件数量参数（正整数）
+fn parse_file_count(flag: &str, value: &str) -> Result<usize, AppError> {
+    value.parse::<usize>().ok().filter(|count| *count > 0).ok_or_else(|| {
+        AppError::Configuration(format!("无效的 {} 取值 '{}'，需要一个正整数", flag, value))
+    })
+}
+
+/// 解析并发数区间 (Parse Concurrency Band)
+///
+/// 格式为 `MIN-MAX`（如 `2-8`），两端都是正整数且 `MIN <= MAX`；
+/// 单个数字 `N` 等价于 `N-N`，即固定的并发数。
+///
+/// # 参数
+/// - `value` - 参数值
+///
+/// # 返回值
+/// - `Ok(ConcurrencyBand)` - 解析后的区间
+/// - `Err(AppError::Configuration)` - 格式无效
+pub fn parse_concurrency_band(value: &str) -> Result<ConcurrencyBand, AppError> {
+    let (min, max) = value.split_once('-').unwrap_or((value, value));
+    let parse = |part: &str| part.trim().parse::<usize>().ok().filter(|count| *count > 0);
+    match (parse(min), parse(max)) {
+        (Some(min), Some(max)) if min <= max => Ok(ConcurrencyBand { min, max }),
+        _ => Err(AppError::Configuration(format!(
+            "无效的 --adaptive-jobs 取值 '{}'，需要 MIN-MAX 形式的正整数区间 (如 2-8)",
+            value
+        ))),
+    }
+}
+
+/// 生成帮助信息 (Usage Text)
+///
+/// # 返回值
+/// - 命令行帮助文本
+pub fn usage() -> String {
+    format!(
+        "用法: {0} [选项] [文件夹]\n\
+         \x20      {0} <子命令> [参数] [选项]\n\
+         \n\
+         未指定文件夹时，程序会交互式地询问要处理的文件夹。\n\
+         文件夹与子命令同名时写成 ./check 或写在 -- 之后 (如 {0} -- check)。\n\
+         \n\
+         子命令:\n\
+         \x20 analyze [文件夹]                 分析文件夹中的音频文件 (默认)\n\
+         \x20 sort <结果文件>                  按 LRA 值重新排序已有的结果文件\n\
+         \x20 merge <结果文件...> -o <输出>    合并多个结果文件并排序\n\
+         \x20 stats <结果文件> [--top <N>]     显示已有结果文件的 LRA 分布统计；--top 额外列出最高和最低的 N 个文件\n\
+         \x20 compare-versions <结果文件>      找出不同目录中的同名音轨 (如原版与重制版)，按 LRA 差异从大到小列出\n\
+         \x20 history <历史文件>               以表格列出 --run-history 记录的历次运行\n\
+         \x20 check [--full]                   检查运行环境；--full 额外生成测试音频完整分析一遍\n\
+         \x20 file <音频文件...> [--json]      直接分析这些文件并输出测量结果，不扫描也不写结果文件；--json 每个文件输出一行 JSON\n\
+         \n\
+         选项:\n\
+         \x20 -o, --output <路径>   结果文件路径 (默认: <扫描目录>/lra_results.txt)；- 表示输出到 stdout\n\
+         \x20 --output-name <模板> 扫描目录下的结果文件名模板，可用 {{date}}、{{time}}、{{root}}、{{format}}，如 lra_results_{{date}}.txt\n\
+         \x20 --no-empty-results    没有需要处理的文件时不创建只有表头的结果文件\n\
+         \x20 --stats-json <路径>   运行结束时将处理统计写入 JSON 文件\n\
+         \x20 --run-history <路径>  运行结束时向该文件追加一条记录 (文件数、失败数、平均/中位数/标准差)\n\
+         \x20 --no-history          不读取也不保存最近使用的文件夹\n\
+         \x20 --force               忽略其他实例留下的结果文件锁\n\
+         \x20 --no-cache            不使用结果缓存，重新分析所有文件\n\
+         \x20 --cache-version-policy <策略>  FFmpeg 版本变化时: invalidate (默认，全部重新分析) 或 mark-stale\n\
+         \x20 --porcelain ndjson    在 stdout 逐行输出 JSON 事件，其他输出改写到 stderr\n\
+         \x20 --porcelain progress  在 stdout 输出 PROGRESS/OK/FAIL/DONE 文本记录，其他输出改写到 stderr\n\
+         \x20 --add-ext <扩展名,...>     额外扫描这些扩展名的文件 (如 ape,wv,dsf)\n\
+         \x20 --remove-ext <扩展名,...>  不再扫描这些扩展名的文件\n\
+         \x20 --min-size <大小>     跳过小于该大小的音频文件 (如 100k)\n\
+         \x20 --max-size <大小>     跳过大于该大小的音频文件 (如 2G)\n\
+         \x20 --newer-than <时间>   只分析该时间之后修改的文件，并与已有结果合并\n\
+         \x20 --newer-than-results  只分析比现有结果文件更新的文件，并与已有结果合并\n\
+         \x20 --probe-unknown       按文件内容识别没有扩展名的音频文件\n\
+         \x20 --pipeline            边扫描边分析，适合超大目录树\n\
+         \x20 --dry-run             扫描并列出需要分析和复用缓存的文件，不分析也不写入结果文件\n\
+         \x20 --max-duration <时长>  处理阶段的时间预算 (如 2h、1h30m)，到期后不再开始新的文件\n\
+         \x20 --adaptive-jobs <MIN-MAX>  按系统负载和可用内存在该区间内调整同时运行的 FFmpeg 数量\n\
+         \x20 --outage-threshold <N>  连续 N 个以上文件无法访问时视为网络共享断开，暂停等待恢复 (默认 10)\n\
+         \x20 --outage-budget <时长>  断开时累计最多暂停多久 (默认 10m)，恢复后重新分析断开期间失败的文件\n\
+         \x20 --abort-after <N>     最先完成的 N 个文件全部以同一种错误失败时中止 (默认 20，0 表示不中止)\n\
+         \x20 --max-error-messages <K>  统计中只保留最前和最后各 K 条失败信息 (默认 50)，失败记录文件总是完整的\n\
+         \x20 --stream-results      处理期间把完成的结果写入 <结果文件名>_partial.txt，中途被杀也不丢失\n\
+         \x20 --stream-sorted       流式写入时保持部分结果文件有序，结束时不再单独排序结果文件\n\
+         \x20 --flush-every <N>     流式写入每 N 个结果刷新一次 (默认 50，隐含 --stream-results)\n\
+         \x20 --flush-interval <时长>  流式写入最长刷新间隔 (默认 10s，隐含 --stream-results)\n\
+         \x20 --limit <N>           只分析最先发现的 N 个文件\n\
+         \x20 --sample <N>          从发现的文件中随机抽取 N 个分析，快速了解 LRA 分布\n\
+         \x20 --shuffle             打乱处理顺序，把同一目录的文件分散开，减轻单块硬盘的压力\n\
+         \x20 --seed <S>            抽样和打乱顺序使用的随机种子，相同种子得到相同的结果\n\
+         \x20 --exclude-dir <目录名>  跳过该名称的目录及其全部内容 (可重复)\n\
+         \x20 --exclude <模式>      跳过文件名匹配该模式的文件，支持 * ? [a-z] (可重复)\n\
+         \x20 --only <模式>         只分析文件名匹配该模式的音频文件 (可重复，匹配任一即可)\n\
+         \x20 --no-default-excludes  不跳过 .git、node_modules 等默认无关目录\n\
+         \x20 --dedup-case         跳过与已找到的文件只有大小写不同且是同一个文件的路径 (经链接访问不区分大小写的文件系统时)\n\
+         \x20 --fail-if-lra-above <LU>  有文件的 LRA 高于该值时以退出码 11 失败\n\
+         \x20 --fail-if-lra-below <LU>  有文件的 LRA 低于该值时以退出码 11 失败\n\
+         \x20 --fail-if-lufs-outside <A:B>  有文件的综合响度 (LUFS) 不在 A 到 B 之间时以退出码 11 失败 (如 --fail-if-lufs-outside=-24:-14)\n\
+         \x20 --baseline <文件>     与基线结果文件比较，列出 LRA 变化和新文件\n\
+         \x20 --regression-threshold <LU>  LRA 变化超过该值 (默认 1.0) 时以退出码 13 失败\n\
+         \x20 --update-baseline     把基线中没有的新文件追加到基线文件\n\
+         \x20 --debug-dump <目录>  将每个文件的完整 FFmpeg 输出和命令行写入该目录\n\
+         \x20 -v, --verbose         分析每个文件前打印可直接复制运行的 FFmpeg 命令\n\
+         \x20 -q, --quiet           不显示欢迎、完成横幅和 LRA 使用建议 (输出不是终端时自动隐藏)\n\
+         \x20 --advice-file <文件>  用该文件的内容替换完成信息中的 LRA 使用建议 (空文件则不显示)\n\
+         \x20 --keep-all-streams    不跳过视频、字幕和数据流，由 FFmpeg 自动选择分析的音频流\n\
+         \x20 --replaygain          在结果文件中追加 ReplayGain 2.0 音轨增益和专辑增益列\n\
+         \x20 --album-lra           把每个目录的音轨拼接后再分析一次，写入 ALBUM:<目录> 条目\n\
+         \x20 --with-tags           用 ffprobe 读取艺术家、专辑、标题标签，追加到结果文件中\n\
+         \x20 --group-by <键>        按 artist、album 或 directory 分组统计 LRA，写入 lra_by_<键>.txt\n\
+         \x20 --hash <算法>           在结果文件中追加每个文件内容的 xxh3 或 sha1 摘要列，便于存档核对\n\
+         \x20 --color <模式>        always、auto (默认，终端中且未设置 NO_COLOR 时) 或 never\n\
+         \x20 --ascii, --no-emoji  只输出 ASCII 标记和符号 (区域设置不是 UTF-8 或旧 Windows 控制台时自动启用)\n\
+         \x20 --unicode            总是输出 emoji 和 Unicode 符号，不自动切换到 ASCII\n\
+         \x20 --hydrate             照常分析 OneDrive/iCloud 云端占位文件 (会触发下载)；默认跳过\n\
+         \x20 --nice                以低 CPU 和 I/O 优先级运行 FFmpeg，分析期间不影响前台程序\n\
+         \x20 --summary-footer      在结果文件末尾追加以 ## 开头的汇总行 (文件数、平均 LRA、失败数、用时)\n\
+         \x20 --inspect-script      有失败文件时生成 lra_inspect_failures.sh (Windows 上为 .ps1)，每个文件一条 ffprobe/FFmpeg 诊断命令\n\
+         \x20 --precision <位数>    LRA 值保留的小数位数 (0-6，默认 1)；排序和合并时保留文件原有的位数\n\
+         \x20 --lra-range <最小-最大>  超出该范围的 LRA 值在统计中标记为可疑 (默认 0-40)，结果照常写入\n\
+         \x20 --compare-versions    分析结束后比较不同目录中同名音轨的 LRA (同 compare-versions 子命令)\n\
+         \x20 --min-spread <LU>     比较同名音轨时只列出 LRA 差异不小于该值的组 (默认 1.0)\n\
+         \x20 --keep-track-numbers  比较同名音轨时不去掉文件名开头的音轨编号 (如 \"01 - \")\n\
+         \x20 --downmix stereo      先缩混为立体声再测量 (环绕声文件按立体声设备上的听感测量)；不使用缓存\n\
+         \x20 --also-downmixed      多声道文件额外测量缩混为立体声后的 LRA，写入单独的列\n\
+         \x20 --per-channel         额外逐声道测量综合响度和 LRA，写入单独的列；标记声道间响度差异过大的文件\n\
+         \x20 --channel-imbalance <LU>  逐声道测量时声道间综合响度差异的标记阈值 (默认 6.0)\n\
+         \x20 --true-peak           额外测量真峰值，统计中列出超过上限、有损编码后可能削波的文件\n\
+         \x20 --true-peak-ceiling <dBTP>  真峰值上限 (默认 -1.0)\n\
+         \x20 --clipping-report <路径>  把真峰值超过上限的文件写入该报告文件 (制表符分隔)\n\
+         \x20 --range-details       在结果文件中追加 LRA 下限、LRA 上限和综合响度门限列 (LUFS)\n\
+         \x20 --sort <方式>         结果排序方式：lra (默认，从高到低) 或 deviation:<LU> (与目标 LRA 的偏差从大到小)\n\
+         \x20 --bom                 结果文件以 UTF-8 BOM 开头，便于 Windows 上的 Excel 识别中文 (Windows 默认)\n\
+         \x20 --crlf                结果文件使用 CRLF 换行 (Windows 默认)\n\
+         \x20 --no-verify           写入结果文件后不重新读取校验条目 (校验失败时保留上一次的文件)\n\
+         \x20 -h, --help            显示此帮助信息",
+        env!("CARGO_PKG_NAME")
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// 测试无参数时的默认选项
+    #[test]
+    fn test_parse_no_args() {
+        let options = parse_args(Vec::<String>::new()).unwrap();
+        assert_eq!(options, CliOptions::default());
+    }
+
+    /// 测试 --stats-json 的两种写法
+    #[test]
+    fn test_parse_stats_json() {
+        let separate = parse_args(["--stats-json", "out.json"]).unwrap();
+        assert_eq!(separate.stats_json, Some(PathBuf::from("out.json")));
+
+        let inline = parse_args(["--stats-json=out.json"]).unwrap();
+        assert_eq!(inline.stats_json, Some(PathBuf::from("out.json")));
+    }
+
+    /// 测试 --debug-dump 参数
+    #[test]
+    fn test_parse_debug_dump() {
+        let dump = parse_args(["--debug-dump", "dump"]).unwrap();
+        assert_eq!(dump.debug_dump, Some(PathBuf::from("dump")));
+        assert!(parse_args(["--debug-dump"]).is_err());
+    }
+
+    /// 测试 --precision、--lra-range 和 --sort 参数
+    #[test]
+    fn test_parse_precision_and_range() {
+        let options = parse_args(["--precision", "2", "--lra-range=1-25.5"]).unwrap();
+        assert_eq!((options.precision, options.lra_precision()), (Some(2), 2));
+        assert_eq!(options.lra_range, Some(LraRange { min: 1.0, max: 25.5 }));
+        assert_eq!(options.sanity_range(), LraRange { min: 1.0, max: 25.5 });
+        let defaults = parse_args(Vec::<String>::new()).unwrap();
+        assert_eq!((defaults.precision, defaults.lra_precision()), (None, DEFAULT_LRA_PRECISION));
+        assert_eq!(defaults.sanity_range(), LraRange::default());
+        assert!(parse_args(["--precision", "7"]).is_err());
+        assert!(parse_args(["--precision", "-1"]).is_err());
+        assert_eq!(parse_args(["--sort", "deviation:9"]).unwrap().sort_order, Some(SortOrder::DeviationFrom(9.0)));
+        assert_eq!(parse_args(["--sort=lra"]).unwrap().sort_order, Some(SortOrder::LraDescending));
+        assert!(parse_args(["--sort", "deviation:abc"]).is_err());
+        assert!(parse_args(["--sort", "deviation:9", "--stream-sorted"]).is_err());
+        assert!(parse_args(["--lra-range", "40-0"]).is_err());
+        assert_eq!(parse_args(["--downmix=stereo"]).unwrap().downmix, Some(Downmix::Stereo));
+        assert!(parse_args(["--also-downmixed"]).unwrap().also_downmixed);
+        assert!(parse_args(["--downmix", "mono"]).is_err());
+        assert!(parse_args(["--downmix", "stereo", "--also-downmixed"]).is_err());
+        let per_channel = parse_args(["--per-channel", "--channel-imbalance=3"]).unwrap();
+        assert!(per_channel.per_channel);
+        assert_eq!(per_channel.channel_imbalance, Some(3.0));
+        assert!(parse_args(["--channel-imbalance", "3"]).is_err());
+        let true_peak = parse_args(["--true-peak", "--true-peak-ceiling=-2", "--clipping-report", "clip.txt"]).unwrap();
+        assert!(true_peak.true_peak);
+        assert_eq!(true_peak.true_peak_ceiling, Some(-2.0));
+        assert_eq!(true_peak.clipping_report, Some(PathBuf::from("clip.txt")));
+        assert!(parse_args(["--clipping-report", "clip.txt"]).is_err());
+        assert!(parse_args(["--range-details"]).unwrap().range_details);
+        assert!(parse_args(["--true-peak", "--true-peak-ceiling", "loud"]).is_err());
+        assert!(parse_args(["--per-channel", "--channel-imbalance", "-1"]).is_err());
+        assert!(parse_args(["--lra-range", "40"]).is_err());
+    }
+
+    /// 测试 --output 参数
+    #[test]
+    fn test_parse_output() {
+        let long = parse_args(["--output", "/tmp/out.txt"]).unwrap();
+        assert_eq!(long.output, Some(PathBuf::from("/tmp/out.txt")));
+        assert!(!long.output_to_stdout());
+
+        let short = parse_args(["-o", "out.txt", "--stats-json", "s.json"]).unwrap();
+        assert_eq!(short.output, Some(PathBuf::from("out.txt")));
+        assert_eq!(short.stats_json, Some(PathBuf::from("s.json")));
+
+        // 文件名模板在解析时检查，不能与完整路径同时指定
+        let named = parse_args(["--output-name", "lra_{date}.txt"]).unwrap();
+        assert_eq!(named.output_name, Some(FileNameTemplate::parse("lra_{date}.txt").unwrap()));
+        assert!(parse_args(["--output-name", "../lra.txt"]).is_err());
+        assert!(parse_args(["--output-name", "lra_{date}.txt", "-o", "out.txt"]).is_err());
+        assert!(parse_args(["stats", "a.txt", "--output-name", "lra.txt"]).is_err());
+
+        // `-` 表示输出到 stdout，不能与需要结果文件或占用 stdout 的参数同时使用
+        assert!(parse_args(["analyze", "/music", "--output", "-"]).unwrap().output_to_stdout());
+        assert!(parse_args(["-o", "-"]).unwrap().output_to_stdout());
+        for conflict in [&["--porcelain", "ndjson"][..], &["--group-by", "artist"], &["--stream-results"], &["--newer-than-results"]] {
+            let args = ["-o", "-"].iter().chain(conflict).copied();
+            assert!(parse_args(args).is_err(), "{:?} 应该与 --output - 冲突", conflict);
+        }
+        assert!(parse_args(["merge", "a.txt", "-o", "-"]).is_err());
+    }
+
+    /// 测试文件夹位置参数
+    #[test]
+    fn test_parse_folder_argument() {
+        let options = parse_args(["/music", "--no-history"]).unwrap();
+        assert_eq!(options.folder, Some(PathBuf::from("/music")));
+        assert!(options.no_history);
+
+        // 只接受一个文件夹
+        let extra = parse_args(["/music", "/other"]);
+        assert!(matches!(extra, Err(AppError::Configuration(_))));
+    }
+
+    /// 测试与子命令同名的文件夹可以写在 `--` 之后或写成 `./check`
+    #[test]
+    fn test_folder_named_like_subcommand() {
+        for name in ["check", "sort", "merge", "stats", "file", "history", "compare-versions"] {
+            let options = parse_args(["--", name]).unwrap();
+            assert_eq!(options.command, Command::Analyze);
+            assert_eq!(options.folder, Some(PathBuf::from(name)));
+
+            let dotted = format!("./{}", name);
+            let options = parse_args([dotted.as_str()]).unwrap();
+            assert_eq!(options.command, Command::Analyze);
+            assert_eq!(options.folder, Some(PathBuf::from(dotted)));
+        }
+
+        // 选项仍然可以写在 `--` 之前，`--` 之后以 `-` 开头的参数也是位置参数
+        let options = parse_args(["--no-history", "--", "-weird"]).unwrap();
+        assert!(options.no_history);
+        assert_eq!(options.folder, Some(PathBuf::from("-weird")));
+
+        // `--` 出现在子命令之后时不影响子命令的识别
+        assert_eq!(
+            parse_args(["sort", "--", "check"]).unwrap().command,
+            Command::Sort { results_file: PathBuf::from("check") }
+        );
+        assert_eq!(parse_args(["check"]).unwrap().command, Command::Check { full: false });
+    }
+
+    /// 测试位置参数中的 `%` 和 `$` 按字面处理，不会因为变量未设置而失败
+    #[test]
+    fn test_positional_paths_are_literal() {
+        for folder in ["/tmp/50%off%sale", "/tmp/$ecret", "/tmp/${HOME}"] {
+            let options = parse_args(["analyze", folder]).unwrap();
+            assert_eq!(options.folder, Some(PathBuf::from(folder)));
+        }
+        assert_eq!(
+            parse_args(["sort", "$ecret.txt"]).unwrap().command,
+            Command::Sort { results_file: PathBuf::from("$ecret.txt") }
+        );
+    }
+
+    /// 测试子命令解析
+    #[test]
+    fn test_parse_subcommands() {
+        let analyze = parse_args(["analyze", "/music"]).unwrap();
+        assert_eq!(analyze.command, Command::Analyze);
+        assert_eq!(analyze.folder, Some(PathBuf::from("/music")));
+
+        assert_eq!(
+            parse_args(["sort", "results.txt"]).unwrap().command,
+            Command::Sort { results_file: PathBuf::from("results.txt") }
+        );
+        assert_eq!(
+            parse_args(["stats", "results.txt"]).unwrap().command,
+            Command::Stats { results_file: PathBuf::from("results.txt"), top: None }
+        );
+        assert_eq!(
+            parse_args(["stats", "--top=5", "results.txt"]).unwrap().command,
+            Command::Stats { results_file: PathBuf::from("results.txt"), top: Some(5) }
+        );
+        assert_eq!(
+            parse_args(["history", "lra_history.jsonl"]).unwrap().command,
+            Command::History { history_file: PathBuf::from("lra_history.jsonl") }
+        );
+        let compare = parse_args(["compare-versions", "results.txt", "--min-spread", "2.5", "--keep-track-numbers"]).unwrap();
+        assert_eq!(compare.command, Command::CompareVersions { results_file: PathBuf::from("results.txt") });
+        assert_eq!(compare.min_spread, Some(2.5));
+        assert!(!compare.name_normalization().strip_track_numbers);
+        assert!(parse_args(["--compare-versions", "/music"]).unwrap().compare_versions);
+        assert!(parse_args(["--min-spread", "2", "/music"]).is_err());
+        assert!(parse_args(["compare-versions", "results.txt", "--min-spread", "-1"]).is_err());
+        assert!(parse_args(["sort", "results.txt", "--compare-versions"]).is_err());
+        assert_eq!(
+            parse_args(["--run-history", "h.jsonl"]).unwrap().run_history,
+            Some(PathBuf::from("h.jsonl"))
+        );
+        assert_eq!(parse_args(["check"]).unwrap().command, Command::Check { full: false });
+        assert_eq!(
+            parse_args(["check", "--full"]).unwrap().command,
+            Command::Check { full: true }
+        );
+
+        // 选项可以出现在子命令之前或之后
+        let merge = parse_args(["-o", "out.txt", "merge", "a.txt", "b.txt"]).unwrap();
+        assert_eq!(
+            merge.command,
+            Command::Merge { inputs: vec![PathBuf::from("a.txt"), PathBuf::from("b.txt")] }
+        );
+        assert_eq!(merge.output, Some(PathBuf::from("out.txt")));
+
+        assert_eq!(
+            parse_args(["file", "a.flac", "--json", "b.mp3"]).unwrap().command,
+            Command::File { paths: vec![PathBuf::from("a.flac"), PathBuf::from("b.mp3")], json: true }
+        );
+    }
+
+    /// 测试子命令的参数错误
+    #[test]
+    fn test_parse_subcommand_errors() {
+        for args in [
+            vec!["sort"],
+            vec!["stats", "a.txt", "b.txt"],
+            vec!["history"],
+            vec!["merge", "a.txt"],
+            vec!["merge", "-o", "out.txt"],
+            vec!["check", "extra"],
+            vec!["analyze", "/a", "/b"],
+            vec!["sort", "a.txt", "--full"],
+            vec!["sort", "a.txt", "--top", "3"],
+            vec!["stats", "a.txt", "--top", "0"],
+            vec!["file"],
+            vec!["stats", "a.txt", "--json"],
+        ] {
+            assert!(
+                matches!(parse_args(args.clone()), Err(AppError::Configuration(_))),
+                "参数 {:?} 应该解析失败",
+                args
+            );
+        }
+    }
+
+    /// 测试 --porcelain 参数
+    #[test]
+    fn test_parse_porcelain() {
+        let options = parse_args(["--porcelain", "ndjson", "/music"]).unwrap();
+        assert_eq!(options.porcelain, Some(PorcelainFormat::Ndjson));
+        assert_eq!(options.folder, Some(PathBuf::from("/music")));
+        let options = parse_args(["--porcelain=progress"]).unwrap();
+        assert_eq!(options.porcelain, Some(PorcelainFormat::Progress));
+
+        assert!(matches!(
+            parse_args(["--porcelain=xml"]),
+            Err(AppError::Configuration(_))
+        ));
+    }
+
+    /// 测试 CI 门禁阈值参数
+    #[test]
+    fn test_parse_thresholds() {
+        let options = parse_args(["--fail-if-lra-above", "15", "--fail-if-lra-below=4.5"]).unwrap();
+        assert_eq!(
+            options.thresholds,
+            LraThresholds { max_lra: Some(15.0), min_lra: Some(4.5), lufs_range: None }
+        );
+        assert_eq!(
+            parse_args(["--fail-if-lufs-outside=-24:-14"]).unwrap().thresholds.lufs_range,
+            Some(LufsRange { min: -24.0, max: -14.0 })
+        );
+        assert_eq!(
+            parse_args(["--fail-if-lufs-outside", "-16:-16"]).unwrap().thresholds.lufs_range,
+            Some(LufsRange { min: -16.0, max: -16.0 })
+        );
+
+        for args in [
+            vec!["--fail-if-lra-above", "loud"],
+            vec!["--fail-if-lra-below", "-1"],
+            vec!["--fail-if-lra-above", "5", "--fail-if-lra-below", "10"],
+            vec!["--fail-if-lufs-outside", "-14:-24"],
+            vec!["--fail-if-lufs-outside", "-24"],
+            vec!["--fail-if-lufs-outside", "loud:quiet"],
+        ] {
+            assert!(matches!(parse_args(args), Err(AppError::Configuration(_))));
+        }
+    }
+
+    /// 测试限量和抽样参数
+    #[test]
+    fn test_parse_limit_and_sample() {
+        assert_eq!(parse_args(["--limit", "500"]).unwrap().limit, Some(500));
+        let options = parse_args(["--sample=500", "--seed", "42"]).unwrap();
+        assert_eq!((options.sample, options.seed), (Some(500), Some(42)));
+        assert_eq!(parse_args(["--sample", "10"]).unwrap().seed, None);
+        let shuffled = parse_args(["--shuffle", "--seed", "7"]).unwrap();
+        assert_eq!((shuffled.shuffle, shuffled.seed), (true, Some(7)));
+
+        for args in [
+            vec!["--limit", "0"],
+            vec!["--sample", "many"],
+            vec!["--sample", "10", "--seed", "-1"],
+            vec!["--seed", "1"],
+            vec!["--limit", "5", "--sample", "5"],
+            vec!["--pipeline", "--limit", "5"],
+            vec!["--pipeline", "--shuffle"],
+        ] {
+            assert!(matches!(parse_args(args), Err(AppError::Configuration(_))));
+        }
+    }
+
+    /// 测试基线比较参数
+    #[test]
+    fn test_parse_baseline() {
+        let options = parse_args(["--baseline", "base.txt", "--regression-threshold=0.5", "--update-baseline"]).unwrap();
+        assert_eq!(options.baseline, Some(PathBuf::from("base.txt")));
+        assert_eq!(options.regression_threshold, Some(0.5));
+        assert!(options.update_baseline);
+        assert_eq!(parse_args(["--baseline", "base.txt"]).unwrap().regression_threshold, None);
+
+        for args in [
+            vec!["--regression-threshold", "1"],
+            vec!["--update-baseline"],
+            vec!["--baseline", "base.txt", "--regression-threshold", "-1"],
+        ] {
+            assert!(matches!(parse_args(args), Err(AppError::Configuration(_))));
+        }
+    }
+
+    /// 测试错误的参数
+    #[test]
+    fn test_parse_invalid_args() {
+        // 缺少参数值
+        let missing = parse_args(["--stats-json"]);
+        assert!(matches!(missing, Err(AppError::Configuration(_))));
+
+        // 未知参数
+        let unknown = parse_args(["--frobnicate"]);
+        match unknown {
+            Err(AppError::Configuration(msg)) => assert!(msg.contains("--frobnicate")),
+            other => panic!("期望得到 AppError::Configuration 错误, 实际为 {:?}", other),
+        }
+    }
+
+    /// 测试文件大小参数的解析
+    #[test]
+    fn test_parse_size_flags() {
+        let options = parse_args(["--min-size", "100k", "--max-size=1.5G"]).unwrap();
+        assert_eq!(options.min_size, Some(100 * 1024));
+        assert_eq!(options.max_size, Some(1536 * 1024 * 1024));
+
+        assert_eq!(parse_byte_size("20 MB").unwrap(), 20 * 1024 * 1024);
+        assert_eq!(parse_byte_size("7b").unwrap(), 7);
+        assert!(parse_byte_size("").is_err());
+        assert!(parse_byte_size("k").is_err());
+        assert!(parse_byte_size("-5k").is_err());
+        assert!(parse_byte_size("10x").is_err());
+        assert!(parse_args(["--min-size", "2G", "--max-size", "1G"]).is_err());
+    }
+
+    /// 测试时长参数的解析
+    #[test]
+    fn test_parse_max_duration() {
+        assert_eq!(parse_args(["--max-duration", "2h"]).unwrap().max_duration, Some(Duration::from_secs(7200)));
+
+        // 流式写入：刷新参数隐含启用，未指定的使用默认值
+        assert_eq!(parse_args(["--stream-results"]).unwrap().stream_results, Some(StreamingConfig::default()));
+        let tuned = parse_args(["--flush-every", "5", "--flush-interval", "1m"]).unwrap().stream_results.unwrap();
+        assert_eq!((tuned.flush_every, tuned.flush_interval), (5, Duration::from_secs(60)));
+        assert!(parse_args(["--flush-every", "0"]).is_err());
+        assert!(parse_args(["--stream-sorted"]).unwrap().stream_results.unwrap().sorted);
+        assert_eq!(parse_args(Vec::<&str>::new()).unwrap().stream_results, None);
+
+        // 断路器：任一参数隐含启用
+        let outage = parse_args(["--outage-budget", "30m"]).unwrap().outage.unwrap();
+        assert_eq!((outage.threshold, outage.pause_budget), (10, Duration::from_secs(1800)));
+        assert_eq!(parse_args(["--outage-threshold", "3"]).unwrap().outage.unwrap().threshold, 3);
+        assert!(parse_args(["--outage-threshold", "0"]).is_err());
+        assert_eq!(parse_args(Vec::<&str>::new()).unwrap().outage, None);
+
+        // 提前中止和失败信息保留数量
+        assert_eq!(parse_args(["--abort-after", "5"]).unwrap().abort_after, Some(5));
+        assert_eq!(parse_args(["--abort-after=0"]).unwrap().abort_after, Some(0));
+        assert!(parse_args(["--abort-after", "-1"]).is_err());
+        assert_eq!(parse_args(["--max-error-messages", "3"]).unwrap().max_error_messages, Some(3));
+        assert!(parse_args(["--max-error-messages", "0"]).is_err());
+        assert_eq!(parse_duration("1h 30m 15s").unwrap(), Duration::from_secs(5415));
+        assert_eq!(parse_duration("10min").unwrap(), Duration::from_secs(600));
+        for invalid in ["", "0", "h", "2d", "-1h", "1..5h", "1h-"] {
+            assert!(parse_duration(invalid).is_err(), "'{}' 应该解析失败", invalid);
+        }
+    }
+
+    /// 测试 --adaptive-jobs 区间的解析
+    #[test]
+    fn test_parse_adaptive_jobs() {
+        let options = parse_args(["--adaptive-jobs", "2-8"]).unwrap();
+        assert_eq!(options.adaptive_jobs, Some(ConcurrencyBand { min: 2, max: 8 }));
+        assert_eq!(parse_concurrency_band("4").unwrap(), ConcurrencyBand { min: 4, max: 4 });
+        for invalid in ["", "0-4", "8-2", "a-b", "2-", "-3", "1-2-3"] {
+            assert!(parse_concurrency_band(invalid).is_err(), "'{}' 应该解析失败", invalid);
+        }
+    }
+
+    /// 测试时间参数的解析
+    #[test]
+    fn test_parse_newer_than() {
+        let rfc3339 = parse_timestamp("2024-01-01T00:00:00Z").unwrap();
+        assert_eq!(
+            rfc3339.duration_since(std::time::UNIX_EPOCH).unwrap().as_secs(),
+            1_704_067_200
+        );
+
+        let date_only = parse_timestamp("2024-01-01").unwrap();
+        let local_midnight = parse_timestamp("2024-01-01 00:00").unwrap();
+        assert_eq!(date_only, local_midnight);
+        assert!(parse_timestamp("2024-01-01T12:30:00").unwrap() > date_only);
+
+        assert!(parse_timestamp("yesterday").is_err());
+        assert!(parse_timestamp("2024-13-01").is_err());
+
+        let options = parse_args(["--newer-than", "2024-01-01"]).unwrap();
+        assert_eq!(options.newer_than, Some(date_only));
+        assert!(parse_args(["--newer-than-results"]).unwrap().newer_than_results);
+        assert!(parse_args(["--newer-than", "2024-01-01", "--newer-than-results"]).is_err());
+        // 增量更新无法重新计算专辑增益
+        for incremental in [&["--newer-than", "2024-01-01"][..], &["--newer-than-results"]] {
+            let args = incremental.iter().copied().chain(["--replaygain"]);
+            assert!(matches!(parse_args(args), Err(AppError::Configuration(msg)) if msg.contains("--replaygain")));
+        }
+    }
+
+    /// 测试扩展名参数的解析和规范化
+    #[test]
+    fn test_parse_extension_flags() {
+        let options = parse_args(["--add-ext", "ape,.WV", "--add-ext=dsf", "--remove-ext", "wma"]).unwrap();
+        assert_eq!(options.add_extensions, vec!["ape", "wv", "dsf"]);
+        assert_eq!(options.remove_extensions, vec!["wma"]);
+
+        assert!(parse_args(["--add-ext", "ape,,wv"]).is_err());
+        assert!(parse_args(["--remove-ext"]).is_err());
+    }
+
+    /// 测试帮助参数
+    #[test]
+    fn test_parse_help() {
+        assert!(parse_args(["-h"]).unwrap().show_help);
+        assert!(parse_args(["--help"]).unwrap().show_help);
+        assert!(parse_args(["--no-history"]).unwrap().no_history);
+        assert!(parse_args(["--force"]).unwrap().force);
+        assert!(parse_args(["--no-cache"]).unwrap().no_cache);
+        assert!(parse_args(["--no-empty-results"]).unwrap().no_empty_results);
+        assert!(parse_args(["--probe-unknown"]).unwrap().probe_unknown);
+        assert!(parse_args(["--dedup-case"]).unwrap().dedup_case);
+        assert!(parse_args(["--no-default-excludes"]).unwrap().no_default_excludes);
+        assert!(parse_args(["--pipeline"]).unwrap().pipeline);
+        assert!(parse_args(["-v"]).unwrap().verbose);
+        assert!(parse_args(["--verbose"]).unwrap().verbose);
+        assert!(parse_args(["-q"]).unwrap().quiet);
+        assert!(parse_args(["--quiet"]).unwrap().quiet);
+        assert_eq!(parse_args(["--advice-file=advice.txt"]).unwrap().advice_file, Some(PathBuf::from("advice.txt")));
+        assert!(parse_args(["--advice-file"]).is_err());
+        assert!(parse_args(["--keep-all-streams"]).unwrap().keep_all_streams);
+        assert!(parse_args(["--replaygain"]).unwrap().replaygain);
+        assert!(parse_args(["--album-lra"]).unwrap().album_lra);
+        assert!(parse_args(["--with-tags"]).unwrap().with_tags);
+        assert_eq!(parse_args(["--group-by=artist"]).unwrap().group_by, Some(GroupKey::Artist));
+        assert!(parse_args(["--group-by", "genre"]).is_err());
+        assert_eq!(parse_args(["--hash=sha1"]).unwrap().hash, Some(HashAlgorithm::Sha1));
+        assert!(parse_args(["--hash", "md5"]).is_err());
+        assert_eq!(parse_args(["--color=never"]).unwrap().color, ColorChoice::Never);
+        assert_eq!(parse_args(Vec::<String>::new()).unwrap().color, ColorChoice::Auto);
+        assert!(parse_args(["--color", "sometimes"]).is_err());
+        assert_eq!(parse_args(Vec::<String>::new()).unwrap().charset, CharsetChoice::Auto);
+        assert_eq!(parse_args(["--ascii"]).unwrap().charset, CharsetChoice::Ascii);
+        assert_eq!(parse_args(["--no-emoji"]).unwrap().charset, CharsetChoice::Ascii);
+        assert_eq!(parse_args(["--no-emoji", "--unicode"]).unwrap().charset, CharsetChoice::Unicode);
+        assert!(parse_args(["--summary-footer"]).unwrap().summary_footer);
+        assert!(parse_args(["--inspect-script"]).unwrap().inspect_script);
+        assert!(parse_args(["--hydrate"]).unwrap().hydrate);
+        assert!(parse_args(["--nice"]).unwrap().nice);
+        assert!(parse_args(["--no-verify"]).unwrap().no_verify);
+        let excel = parse_args(["--bom", "--crlf"]).unwrap();
+        assert_eq!(excel.text_format, TextFormat { bom: true, crlf: true });
+        assert!(parse_args(["--pipeline", "--probe-unknown"]).is_err());
+        assert!(parse_args(["--dry-run"]).unwrap().dry_run);
+        assert!(parse_args(["--pipeline", "--dry-run"]).is_err());
+        assert_eq!(
+            parse_args(["--exclude-dir", "Samples", "--exclude-dir=.stems"]).unwrap().exclude_dirs,
+            vec!["Samples", ".stems"]
+        );
+        assert!(parse_args(["--exclude-dir", "a/b"]).is_err());
+        assert!(parse_args(["--exclude-dir", ".."]).is_err());
+        let patterns = parse_args(["--only", " *.Mp3", "--only=take[0-9]*", "--exclude", "*.bak"]).unwrap();
+        assert_eq!(patterns.only_patterns, vec!["*.Mp3", "take[0-9]*"]);
+        assert_eq!(patterns.exclude_patterns, vec!["*.bak"]);
+        assert!(parse_args(["--only", "take[12.wav"]).is_err());
+        assert!(parse_args(["--exclude", "Album/*.flac"]).is_err());
+        assert_eq!(
+            parse_args(["--cache-version-policy=mark-stale"]).unwrap().cache_version_policy,
+            CacheVersionPolicy::MarkStale
+        );
+        assert!(parse_args(["--cache-version-policy", "never"]).is_err());
+        assert!(usage().contains("--stats-json"));
+    }
+}
@@ -0,0 +1,114 @@
+//! 时钟模块 (Clock Module)
+//!
+//! 命令行程序取当前时间的地方（欢迎和完成信息、运行 ID、运行历史记录、`--output-name` 中的日期）
+//! 都经过 [`Clock`]，而不是直接读取系统时间。默认使用 [`SystemClock`]；
+//! 测试中注入 [`FixedClock`]，启动真实程序的集成测试设置环境变量
+//! [`FAKE_TIME_ENV`]（`LRA_FAKE_TIME=<Unix 秒数>`），命令行输出和结果文件的元数据因此可以与黄金文件逐字比较。
+//!
+//! 耗时统计使用 [`std::time::Instant`]，不经过时钟。
+
+use std::ffi::OsString;
+use std::fmt;
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+
+/// 固定当前时间的环境变量，取值为 Unix 纪元以来的秒数
+pub const FAKE_TIME_ENV: &str = "LRA_FAKE_TIME";
+
+/// 时钟 (Clock)
+pub trait Clock: fmt::Debug + Send + Sync {
+    /// 当前时间
+    fn now(&self) -> SystemTime;
+
+    /// 是否是固定时钟：固定时钟下运行 ID 的随机后缀和默认随机种子也是固定的，整次运行的输出可以复现
+    fn is_fixed(&self) -> bool {
+        false
+    }
+}
+
+/// 系统时钟 (System Clock)
+#[derive(Debug, Clone, Copy, Default)]
+pub struct SystemClock;
+
+impl Clock for SystemClock {
+    fn now(&self) -> SystemTime {
+        SystemTime::now()
+    }
+}
+
+/// 固定时钟 (Fixed Clock)
+///
+/// 总是返回同一个时间。
+///
+/// # 示例
+/// ```
+/// use lra_calculator_rust::clock::{Clock, FixedClock};
+///
+/// let clock = FixedClock::from_unix_secs(1_717_255_812);
+/// assert_eq!(clock.now(), clock.now());
+/// assert!(clock.is_fixed());
+/// ```
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct FixedClock(pub SystemTime);
+
+impl FixedClock {
+    /// 由 Unix 纪元以来的秒数创建
+    pub fn from_unix_secs(seconds: u64) -> Self {
+        Self(UNIX_EPOCH + Duration::from_secs(seconds))
+    }
+}
+
+impl Clock for FixedClock {
+    fn now(&self) -> SystemTime {
+        self.0
+    }
+
+    fn is_fixed(&self) -> bool {
+        true
+    }
+}
+
+/// 按环境变量选择时钟 (Clock from Environment)
+///
+/// 设置了 [`FAKE_TIME_ENV`] 时返回固定在该时间的 [`FixedClock`]，否则返回 [`SystemClock`]。
+/// 取值不是非负整数时返回 [`InvalidFakeTime`]，由调用方决定是报错还是退回系统时钟。
+pub fn clock_from_env() -> Result<Box<dyn Clock>, InvalidFakeTime> {
+    let Some(value) = std::env::var_os(FAKE_TIME_ENV) else {
+        return Ok(Box::new(SystemClock));
+    };
+    match value.to_str().and_then(parse_fake_time) {
+        Some(clock) => Ok(Box::new(clock)),
+        None => Err(InvalidFakeTime(value)),
+    }
+}
+
+/// [`FAKE_TIME_ENV`] 的取值无效 (Invalid Fake Time)
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct InvalidFakeTime(pub OsString);
+
+impl fmt::Display for InvalidFakeTime {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{} 的取值 {:?} 无效（需要 Unix 秒数）", FAKE_TIME_ENV, self.0)
+    }
+}
+
+impl std::error::Error for InvalidFakeTime {}
+
+/// 解析 [`FAKE_TIME_ENV`] 的取值
+fn parse_fake_time(value: &str) -> Option<FixedClock> {
+    value.trim().parse::<u64>().ok().map(FixedClock::from_unix_secs)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// 测试固定时间的解析
+    #[test]
+    fn test_parse_fake_time() {
+        assert_eq!(parse_fake_time(" 1717255812 "), Some(FixedClock::from_unix_secs(1_717_255_812)));
+        for invalid in ["", "-1", "2024-06-01", "1.5"] {
+            assert_eq!(parse_fake_time(invalid), None, "{:?} 应该无效", invalid);
+        }
+        assert!(!SystemClock.is_fixed());
+    }
+}
@@ -0,0 +1,111 @@
+//! 云端占位文件模块 (Cloud Placeholder Module)
+//!
+//! OneDrive、iCloud 等同步客户端会把"仅在线"的文件留作占位文件：目录里看得到，
+//! 内容却不在本地。FFmpeg 读取时会触发下载，每个文件可能卡上几分钟，或者直接失败。
+//! 分析之前先识别这些文件，默认以 [`crate::processor::SkipReason::CloudPlaceholder`]
+//! 跳过（iCloud 存根为 [`crate::processor::SkipReason::ICloudStub`]）；`--hydrate` 时照常分析（即允许触发下载）。
+//!
+//! 识别依据：
+//!
+//! - **Windows**: 文件属性中的 `RECALL_ON_DATA_ACCESS`、`RECALL_ON_OPEN` 或 `OFFLINE`
+//!   （OneDrive 占位文件是带有这些属性的重解析点）
+//! - **macOS**: 文件标志中的 `SF_DATALESS`（iCloud 已移出本地的"无数据"文件）
+//! - **所有平台**: iCloud 的 `.<文件名>.icloud` 存根。存根只是一个小的属性列表文件，
+//!   读取它不会下载原文件，因此即使 `--hydrate` 也无法分析
+
+use std::fs;
+use std::path::Path;
+
+/// iCloud 存根文件的扩展名
+const ICLOUD_STUB_EXTENSION: &str = ".icloud";
+
+/// 从 iCloud 存根的文件名还原原文件名 (iCloud Stub Target)
+///
+/// # 示例
+/// ```
+/// use lra_calculator_rust::cloud::icloud_stub_target;
+///
+/// assert_eq!(icloud_stub_target(".Song.flac.icloud"), Some("Song.flac"));
+/// assert_eq!(icloud_stub_target("Song.flac"), None);
+/// assert_eq!(icloud_stub_target(".icloud"), None);
+/// ```
+pub fn icloud_stub_target(file_name: &str) -> Option<&str> {
+    file_name
+        .strip_prefix('.')?
+        .strip_suffix(ICLOUD_STUB_EXTENSION)
+        .filter(|target| !target.is_empty())
+}
+
+/// 判断路径是否为 iCloud 存根
+pub fn is_icloud_stub(path: &Path) -> bool {
+    path.file_name()
+        .and_then(|name| name.to_str())
+        .and_then(icloud_stub_target)
+        .is_some()
+}
+
+/// 判断文件是否为云端占位文件 (Is Cloud Placeholder)
+///
+/// 读取文件元数据（不跟随符号链接），不会打开文件，因此不会触发下载。
+/// 元数据无法读取时返回 `false`，由后续的分析报告真正的错误。
+///
+/// # 参数
+/// - `path` - 文件路径
+///
+/// # 返回值
+/// - `true` - iCloud 存根，或元数据表明内容不在本地
+pub fn is_cloud_placeholder(path: &Path) -> bool {
+    is_icloud_stub(path) || fs::symlink_metadata(path).is_ok_and(|metadata| is_dataless(&metadata))
+}
+
+/// 元数据是否表明文件内容不在本地
+#[cfg(windows)]
+fn is_dataless(metadata: &fs::Metadata) -> bool {
+    use std::os::windows::fs::MetadataExt;
+
+    const FILE_ATTRIBUTE_OFFLINE: u32 = 0x0000_1000;
+    const FILE_ATTRIBUTE_RECALL_ON_OPEN: u32 = 0x0004_0000;
+    const FILE_ATTRIBUTE_RECALL_ON_DATA_ACCESS: u32 = 0x0040_0000;
+
+    metadata.file_attributes()
+        & (FILE_ATTRIBUTE_OFFLINE | FILE_ATTRIBUTE_RECALL_ON_OPEN | FILE_ATTRIBUTE_RECALL_ON_DATA_ACCESS)
+        != 0
+}
+
+/// 元数据是否表明文件内容不在本地
+#[cfg(target_os = "macos")]
+fn is_dataless(metadata: &fs::Metadata) -> bool {
+    use std::os::macos::fs::MetadataExt;
+
+    const SF_DATALESS: u32 = 0x4000_0000;
+
+    metadata.st_flags() & SF_DATALESS != 0
+}
+
+/// 元数据是否表明文件内容不在本地（其他平台没有占位文件的概念）
+#[cfg(not(any(windows, target_os = "macos")))]
+fn is_dataless(_metadata: &fs::Metadata) -> bool {
+    false
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempfile::TempDir;
+
+    /// 测试 iCloud 存根的识别，普通文件不是占位文件
+    #[test]
+    fn test_is_cloud_placeholder() {
+        let temp_dir = TempDir::new().expect("无法创建临时目录");
+        let stub = temp_dir.path().join(".Track 01.flac.icloud");
+        let regular = temp_dir.path().join("Track 02.flac");
+        fs::write(&stub, b"bplist00").unwrap();
+        fs::write(&regular, b"fLaC").unwrap();
+
+        assert!(is_cloud_placeholder(&stub));
+        assert!(!is_cloud_placeholder(&regular));
+        assert!(!is_cloud_placeholder(&temp_dir.path().join("missing.flac")));
+        assert!(!is_icloud_stub(Path::new("music/.hidden.flac")));
+        assert_eq!(icloud_stub_target("..icloud"), None);
+    }
+}
@@ -0,0 +1,393 @@
+//! 自适应并发模块 (Adaptive Concurrency Module)
+//!
+//! 在共享的构建服务器上，固定的线程数要么太保守、要么抢占其他任务的资源。
+//! `--adaptive-jobs MIN-MAX` 时同时运行的 FFmpeg 数量由 [`ConcurrencyLimiter`] 控制，
+//! [`AdaptiveMonitor`] 每隔 [`SAMPLE_INTERVAL`] 读取一次系统的 1 分钟平均负载和可用内存，
+//! 按 [`adjust_limit`] 在区间内逐个提高或降低上限：
+//!
+//! - 可用内存低于 [`LOW_MEMORY_FRACTION`]，或负载超过 CPU 核心数的 [`OVERLOAD_FACTOR`] 倍时降低
+//! - 负载低于 CPU 核心数的 [`IDLE_FACTOR`] 倍且内存充足时提高
+//!
+//! 采样通过 sysinfo（[`SystemSampler`]）进行，支持 Linux、macOS 和 Windows 等平台；Windows 没有平均负载，
+//! 用 CPU 使用率折算。sysinfo 不支持的平台或采样失败时不启动监视线程，并发上限保持初始值不变。
+
+use std::fmt;
+use std::sync::{mpsc, Arc, Condvar, Mutex, MutexGuard};
+use std::thread::{self, JoinHandle};
+use std::time::Duration;
+
+use crate::console::Glyph;
+
+/// 两次采样之间的间隔
+pub const SAMPLE_INTERVAL: Duration = Duration::from_secs(5);
+
+/// 负载超过 CPU 核心数的该倍数时降低并发
+pub const OVERLOAD_FACTOR: f64 = 1.25;
+
+/// 负载低于 CPU 核心数的该倍数时提高并发
+pub const IDLE_FACTOR: f64 = 0.75;
+
+/// 可用内存低于该比例时降低并发
+pub const LOW_MEMORY_FRACTION: f64 = 0.10;
+
+/// 可用内存低于该比例时不提高并发
+const COMFORTABLE_MEMORY_FRACTION: f64 = 0.20;
+
+/// 并发数区间 (Concurrency Band)，对应 `--adaptive-jobs MIN-MAX`
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct ConcurrencyBand {
+    /// 最少同时运行的任务数（至少为 1）
+    pub min: usize,
+    /// 最多同时运行的任务数
+    pub max: usize,
+}
+
+impl ConcurrencyBand {
+    /// 把数值限制在区间内
+    pub fn clamp(&self, value: usize) -> usize {
+        value.clamp(self.min, self.max)
+    }
+}
+
+impl fmt::Display for ConcurrencyBand {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}-{}", self.min, self.max)
+    }
+}
+
+/// 可调上限的并发限制器 (Concurrency Limiter)
+///
+/// 计数信号量：[`acquire`](Self::acquire) 在运行中的任务数达到上限时等待，
+/// 返回的 [`ConcurrencyPermit`] 被丢弃时释放名额。克隆得到的限制器共享同一个状态。
+/// 降低上限不会中断已经在运行的任务，只是在它们结束之前不再放行新的任务。
+///
+/// # 示例
+/// ```
+/// use lra_calculator_rust::concurrency::ConcurrencyLimiter;
+///
+/// let limiter = ConcurrencyLimiter::new(2);
+/// let first = limiter.acquire();
+/// let _second = limiter.acquire();
+/// assert_eq!(limiter.running(), 2);
+/// drop(first);
+/// assert_eq!(limiter.running(), 1);
+/// ```
+#[derive(Debug, Clone)]
+pub struct ConcurrencyLimiter {
+    shared: Arc<(Mutex<LimiterState>, Condvar)>,
+}
+
+/// 限制器的内部状态
+#[derive(Debug)]
+struct LimiterState {
+    limit: usize,
+    running: usize,
+}
+
+impl ConcurrencyLimiter {
+    /// 创建限制器（上限为 0 时按 1 处理）
+    pub fn new(limit: usize) -> Self {
+        Self { shared: Arc::new((Mutex::new(LimiterState { limit: limit.max(1), running: 0 }), Condvar::new())) }
+    }
+
+    /// 等待并占用一个名额
+    pub fn acquire(&self) -> ConcurrencyPermit {
+        let (state, available) = &*self.shared;
+        let mut guard = lock(state);
+        while guard.running >= guard.limit {
+            guard = available.wait(guard).unwrap_or_else(|poisoned| poisoned.into_inner());
+        }
+        guard.running += 1;
+        ConcurrencyPermit { limiter: self.clone() }
+    }
+
+    /// 修改上限（为 0 时按 1 处理），提高时立即放行等待中的任务
+    pub fn set_limit(&self, limit: usize) {
+        let (state, available) = &*self.shared;
+        lock(state).limit = limit.max(1);
+        available.notify_all();
+    }
+
+    /// 当前上限
+    pub fn limit(&self) -> usize {
+        lock(&self.shared.0).limit
+    }
+
+    /// 正在运行的任务数
+    pub fn running(&self) -> usize {
+        lock(&self.shared.0).running
+    }
+}
+
+/// 同一个限制器的克隆视为相等
+impl PartialEq for ConcurrencyLimiter {
+    fn eq(&self, other: &Self) -> bool {
+        Arc::ptr_eq(&self.shared, &other.shared)
+    }
+}
+
+/// 任务不会在持有锁时 panic，中毒时照常使用内部状态
+fn lock(state: &Mutex<LimiterState>) -> MutexGuard<'_, LimiterState> {
+    state.lock().unwrap_or_else(|poisoned| poisoned.into_inner())
+}
+
+/// 占用的并发名额 (Concurrency Permit)，丢弃时释放
+#[derive(Debug)]
+pub struct ConcurrencyPermit {
+    limiter: ConcurrencyLimiter,
+}
+
+impl Drop for ConcurrencyPermit {
+    fn drop(&mut self) {
+        let (state, available) = &*self.limiter.shared;
+        lock(state).running -= 1;
+        available.notify_all();
+    }
+}
+
+/// 一次系统采样 (System Sample)
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct SystemSample {
+    /// 1 分钟平均负载
+    pub load_average: f64,
+    /// 可用内存占总内存的比例（0 到 1）
+    pub available_memory: f64,
+}
+
+/// 系统采样器 (System Sampler)
+///
+/// 通过 sysinfo 读取系统的 1 分钟平均负载和可用内存。Windows 没有平均负载，
+/// 改用两次采样之间的 CPU 使用率折算（使用率 × CPU 核心数），因此采样器需要保留下来重复使用。
+#[derive(Debug)]
+pub struct SystemSampler {
+    system: sysinfo::System,
+    cpu_count: usize,
+}
+
+impl SystemSampler {
+    /// 创建采样器
+    ///
+    /// # 返回值
+    /// - sysinfo 不支持当前平台时返回 `None`
+    pub fn new() -> Option<Self> {
+        if !sysinfo::IS_SUPPORTED_SYSTEM {
+            return None;
+        }
+        let mut system = sysinfo::System::new();
+        // CPU 使用率是两次刷新之间的差值，先记录一次起点
+        system.refresh_cpu_usage();
+        Some(Self { system, cpu_count: thread::available_parallelism().map_or(1, usize::from) })
+    }
+
+    /// 读取系统负载和可用内存 (Sample)
+    ///
+    /// # 返回值
+    /// - 采样结果；读取失败（如总内存为 0）时返回 `None`
+    pub fn sample(&mut self) -> Option<SystemSample> {
+        self.system.refresh_memory();
+        let load_average = if cfg!(windows) {
+            self.system.refresh_cpu_usage();
+            f64::from(self.system.global_cpu_usage()) / 100.0 * self.cpu_count as f64
+        } else {
+            sysinfo::System::load_average().one
+        };
+        memory_sample(load_average, self.system.available_memory(), self.system.total_memory())
+    }
+}
+
+/// 由负载和内存字节数构造采样结果；总内存为 0 或负载无效时视为读取失败
+fn memory_sample(load_average: f64, available: u64, total: u64) -> Option<SystemSample> {
+    if total == 0 || !load_average.is_finite() || load_average < 0.0 {
+        return None;
+    }
+    Some(SystemSample { load_average, available_memory: (available as f64 / total as f64).clamp(0.0, 1.0) })
+}
+
+/// 并发上限的一次调整 (Limit Change)
+#[derive(Debug, Clone, PartialEq)]
+pub struct LimitChange {
+    /// 调整前的上限
+    pub from: usize,
+    /// 调整后的上限
+    pub to: usize,
+    /// 调整的原因
+    pub reason: String,
+}
+
+impl fmt::Display for LimitChange {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "并发上限 {} {} {}: {}", self.from, Glyph::Arrow, self.to, self.reason)
+    }
+}
+
+/// 根据采样结果决定新的并发上限 (Adjust Limit)
+///
+/// 每次最多调整 1，结果总在 `band` 之内。内存不足优先于负载判断。
+///
+/// # 参数
+/// - `current` - 当前上限
+/// - `band` - 允许的区间
+/// - `sample` - 系统采样
+/// - `cpu_count` - CPU 核心数
+///
+/// # 返回值
+/// - 需要调整时返回调整内容；保持不变（包括已到区间边界）时返回 `None`
+///
+/// # 示例
+/// ```
+/// use lra_calculator_rust::concurrency::{adjust_limit, ConcurrencyBand, SystemSample};
+///
+/// let band = ConcurrencyBand { min: 1, max: 8 };
+/// let busy = SystemSample { load_average: 12.0, available_memory: 0.5 };
+/// assert_eq!(adjust_limit(4, band, &busy, 8).map(|change| change.to), Some(3));
+/// ```
+pub fn adjust_limit(current: usize, band: ConcurrencyBand, sample: &SystemSample, cpu_count: usize) -> Option<LimitChange> {
+    let cpus = cpu_count.max(1) as f64;
+    let memory_percent = sample.available_memory * 100.0;
+    let (target, reason) = if sample.available_memory < LOW_MEMORY_FRACTION {
+        (current.saturating_sub(1), format!("可用内存只剩 {:.0}%", memory_percent))
+    } else if sample.load_average > cpus * OVERLOAD_FACTOR {
+        (current.saturating_sub(1), format!("1 分钟平均负载 {:.1} 超过 CPU 核心数 {}", sample.load_average, cpu_count))
+    } else if sample.load_average < cpus * IDLE_FACTOR && sample.available_memory >= COMFORTABLE_MEMORY_FRACTION {
+        (
+            current + 1,
+            format!(
+                "1 分钟平均负载 {:.1} 低于 CPU 核心数 {}，可用内存 {:.0}%",
+                sample.load_average, cpu_count, memory_percent
+            ),
+        )
+    } else {
+        return None;
+    };
+
+    let to = band.clamp(target);
+    (to != current).then_some(LimitChange { from: current, to, reason })
+}
+
+/// 自适应并发的监视线程 (Adaptive Monitor)
+///
+/// 定期采样并调整 [`ConcurrencyLimiter`] 的上限，丢弃时停止监视线程。
+#[derive(Debug)]
+pub struct AdaptiveMonitor {
+    stop: Option<mpsc::Sender<()>>,
+    handle: Option<JoinHandle<()>>,
+}
+
+impl AdaptiveMonitor {
+    /// 启动监视线程 (Start Monitor)
+    ///
+    /// 先采样一次确认当前平台支持（见 [`SystemSampler`]）；不支持时不启动线程，限制器保持当前上限。
+    ///
+    /// # 参数
+    /// - `limiter` - 要调整的限制器
+    /// - `band` - 允许的区间
+    /// - `on_change` - 每次调整上限后调用，用于记录调整和原因
+    ///
+    /// # 返回值
+    /// - 监视器，需要在处理期间一直持有；无法采样时返回 `None`
+    pub fn start<F>(limiter: ConcurrencyLimiter, band: ConcurrencyBand, on_change: F) -> Option<Self>
+    where
+        F: Fn(&LimitChange) + Send + 'static,
+    {
+        let mut sampler = SystemSampler::new()?;
+        sampler.sample()?;
+        let cpu_count = sampler.cpu_count;
+        let (stop, stopped) = mpsc::channel::<()>();
+        let handle = thread::spawn(move || {
+            // 发送端被丢弃时 recv_timeout 立即返回 Disconnected，监视结束
+            while let Err(mpsc::RecvTimeoutError::Timeout) = stopped.recv_timeout(SAMPLE_INTERVAL) {
+                // 偶尔读取失败时跳过这一次，上限保持不变
+                let Some(sample) = sampler.sample() else { continue };
+                if let Some(change) = adjust_limit(limiter.limit(), band, &sample, cpu_count) {
+                    limiter.set_limit(change.to);
+                    on_change(&change);
+                }
+            }
+        });
+        Some(Self { stop: Some(stop), handle: Some(handle) })
+    }
+}
+
+impl Drop for AdaptiveMonitor {
+    fn drop(&mut self) {
+        drop(self.stop.take());
+        if let Some(handle) = self.handle.take() {
+            let _ = handle.join();
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::sync::atomic::{AtomicUsize, Ordering};
+
+    /// 测试各种负载和内存组合下的调整决定
+    #[test]
+    fn test_adjust_limit_table() {
+        let band = ConcurrencyBand { min: 2, max: 6 };
+        // (当前上限, 负载, 可用内存, 调整后的上限)
+        let cases = [
+            (4, 8.0, 0.5, None),
+            (4, 2.0, 0.5, Some(5)),
+            (6, 2.0, 0.5, None),
+            (4, 2.0, 0.15, None),
+            (4, 11.0, 0.5, Some(3)),
+            (2, 11.0, 0.5, None),
+            (4, 2.0, 0.05, Some(3)),
+            (4, 7.0, 0.05, Some(3)),
+        ];
+
+        for (current, load_average, available_memory, expected) in cases {
+            let sample = SystemSample { load_average, available_memory };
+            let change = adjust_limit(current, band, &sample, 8).map(|change| change.to);
+            assert_eq!(change, expected, "上限 {}, 负载 {}, 内存 {}", current, load_average, available_memory);
+        }
+
+        let change = adjust_limit(4, band, &SystemSample { load_average: 2.0, available_memory: 0.05 }, 8).unwrap();
+        assert_eq!(change.to_string(), "并发上限 4 → 3: 可用内存只剩 5%");
+    }
+
+    /// 测试由负载和内存字节数构造采样结果，读取失败的值被拒绝
+    #[test]
+    fn test_memory_sample() {
+        assert_eq!(memory_sample(3.52, 4_000, 16_000), Some(SystemSample { load_average: 3.52, available_memory: 0.25 }));
+        assert_eq!(memory_sample(1.0, 200, 100).unwrap().available_memory, 1.0);
+        assert_eq!(memory_sample(1.0, 100, 0), None);
+        assert_eq!(memory_sample(f64::NAN, 100, 200), None);
+    }
+
+    /// 测试 sysinfo 支持的平台上可以采样
+    #[test]
+    fn test_system_sampler() {
+        let Some(mut sampler) = SystemSampler::new() else { return };
+        let sample = sampler.sample().expect("支持的平台上应当可以采样");
+        assert!(sample.load_average >= 0.0);
+        assert!((0.0..=1.0).contains(&sample.available_memory));
+    }
+
+    /// 测试限制器在上限处等待，提高上限后放行
+    #[test]
+    fn test_limiter_blocks_at_limit() {
+        let limiter = ConcurrencyLimiter::new(1);
+        let started = Arc::new(AtomicUsize::new(0));
+        let held = limiter.acquire();
+
+        let waiter = {
+            let (limiter, started) = (limiter.clone(), Arc::clone(&started));
+            thread::spawn(move || {
+                let _permit = limiter.acquire();
+                started.fetch_add(1, Ordering::SeqCst);
+            })
+        };
+        thread::sleep(Duration::from_millis(50));
+        assert_eq!(started.load(Ordering::SeqCst), 0, "达到上限时应等待");
+
+        limiter.set_limit(2);
+        waiter.join().unwrap();
+        assert_eq!(started.load(Ordering::SeqCst), 1);
+        drop(held);
+        assert_eq!(limiter.running(), 0);
+        assert_eq!(limiter, limiter.clone());
+    }
+}
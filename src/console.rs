@@ -0,0 +1,620 @@
+//! 控制台输出模块 (Console Output Module)
+//!
+//! 程序的人类可读输出（进度、提示、统计）统一通过 [`human_println!`] 和
+//! [`human_print!`] 输出。默认写入 stdout；机器可读模式（`--porcelain ndjson`）
+//! 下 stdout 专用于事件流，人类可读输出会被切换到 stderr。
+//!
+//! 错误和警告始终通过 [`human_eprintln!`] 写入 stderr，不受此开关影响。
+//!
+//! 写入 stdout/stderr 的函数和宏只在 `cli` 特性下提供：库本身不打印任何内容，
+//! 警告通过返回值或 [`EventSink`](crate::events::EventSink) 交给调用方。
+//! 字符集和颜色的格式化部分（[`human_format!`]、[`Glyph`]、[`ColorMode`]）不受此限制。
+//!
+//! ## 颜色
+//!
+//! 统计摘要、完成信息和错误用 ANSI 颜色区分（成功绿色、失败红色、LRA 分类各自的颜色）。
+//! `--color auto`（默认）只在输出目标是终端且没有设置 `NO_COLOR` 时启用颜色；
+//! `always` 和 `never` 强制开关。格式化函数接受 [`ColorMode`] 参数，
+//! 不依赖全局状态，测试可以逐字节比较无颜色的输出。
+//!
+//! ## 字符集
+//!
+//! CI 日志查看器和旧的 Windows 控制台会把 emoji 和 `✓`、`•` 这样的符号显示成乱码。
+//! `--ascii`（`--no-emoji`）时程序自己的模板文本经过 [`Charset::render`]：状态符号换成
+//! `[OK]`、`[ERROR]`、`[WARN]` 这样的标记，纯装饰的 emoji 去掉，其他符号换成 ASCII 近似字符；
+//! 中文文本保持不变。默认按区域设置和终端自动判断（见 [`detect_utf8_support`]），`--unicode` 强制关闭。
+//!
+//! 只有格式字符串按字符集转换：[`human_println!`]、[`human_format!`] 等宏把每个参数包装成
+//! [`Verbatim`]，路径、标签和错误信息这样的用户数据原样输出（文件名中的 `—`、`·` 或 emoji 不会被改写）。
+//! 作为参数输出、又含有程序自己的符号的值（如 `Display` 实现中的箭头）使用 [`Glyph`] 按当前字符集查找。
+//! 横幅等格式化函数接受 [`Charset`] 参数（见 [`format_with_charset!`]），测试可以同时比较两种输出。
+
+use std::borrow::Cow;
+use std::cell::Cell;
+use std::env;
+use std::fmt::{self, Write as _};
+use std::io::{self, IsTerminal};
+#[cfg(feature = "cli")]
+use std::io::Write;
+use std::sync::atomic::{AtomicBool, Ordering};
+
+/// 人类可读输出是否写入 stderr
+static HUMAN_OUTPUT_TO_STDERR: AtomicBool = AtomicBool::new(false);
+
+/// stdout 是否使用颜色
+static STDOUT_COLOR: AtomicBool = AtomicBool::new(false);
+
+/// stderr 是否使用颜色
+static STDERR_COLOR: AtomicBool = AtomicBool::new(false);
+
+/// 颜色开关 (Color Choice)，对应 `--color always|auto|never`
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum ColorChoice {
+    /// 输出目标是终端且没有设置 `NO_COLOR` 时使用颜色（默认）
+    #[default]
+    Auto,
+    /// 总是使用颜色（忽略 `NO_COLOR`）
+    Always,
+    /// 从不使用颜色
+    Never,
+}
+
+impl ColorChoice {
+    /// 从命令行取值解析（`always`、`auto` 或 `never`）
+    pub fn parse(value: &str) -> Option<Self> {
+        match value {
+            "auto" => Some(Self::Auto),
+            "always" => Some(Self::Always),
+            "never" => Some(Self::Never),
+            _ => None,
+        }
+    }
+
+    /// 决定某个输出目标的颜色模式
+    ///
+    /// # 参数
+    /// - `is_terminal` - 输出目标是否为终端
+    /// - `no_color` - 是否设置了非空的 `NO_COLOR` 环境变量
+    ///
+    /// # 示例
+    /// ```
+    /// use lra_calculator_rust::console::{ColorChoice, ColorMode};
+    ///
+    /// assert_eq!(ColorChoice::Auto.resolve(true, false), ColorMode::Ansi);
+    /// assert_eq!(ColorChoice::Auto.resolve(true, true), ColorMode::Plain);
+    /// assert_eq!(ColorChoice::Always.resolve(false, true), ColorMode::Ansi);
+    /// ```
+    pub fn resolve(self, is_terminal: bool, no_color: bool) -> ColorMode {
+        match self {
+            Self::Always => ColorMode::Ansi,
+            Self::Never => ColorMode::Plain,
+            Self::Auto if is_terminal && !no_color => ColorMode::Ansi,
+            Self::Auto => ColorMode::Plain,
+        }
+    }
+}
+
+/// 颜色模式 (Color Mode)
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum ColorMode {
+    /// 不输出任何控制字符
+    #[default]
+    Plain,
+    /// 使用 ANSI 颜色
+    Ansi,
+}
+
+/// 文本样式 (Style)
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Style {
+    /// 成功（绿色）
+    Success,
+    /// 失败和错误（红色）
+    Failure,
+    /// 警告（黄色）
+    Warning,
+    /// LRA > 15 LU，动态范围丰富（青色）
+    LraHigh,
+    /// LRA 8-15 LU，适中动态范围（绿色）
+    LraMedium,
+    /// LRA < 8 LU，动态范围较小（黄色）
+    LraLow,
+}
+
+impl Style {
+    /// ANSI 前景色代码
+    fn ansi_code(self) -> u8 {
+        match self {
+            Self::Success | Self::LraMedium => 32,
+            Self::Failure => 31,
+            Self::Warning | Self::LraLow => 33,
+            Self::LraHigh => 36,
+        }
+    }
+}
+
+impl ColorMode {
+    /// 按样式渲染文本；[`ColorMode::Plain`] 时原样返回
+    ///
+    /// # 示例
+    /// ```
+    /// use lra_calculator_rust::console::{ColorMode, Style};
+    ///
+    /// assert_eq!(ColorMode::Plain.paint(Style::Failure, "失败"), "失败");
+    /// assert_eq!(ColorMode::Ansi.paint(Style::Failure, "失败"), "\x1b[31m失败\x1b[0m");
+    /// ```
+    pub fn paint(self, style: Style, text: impl fmt::Display) -> String {
+        match self {
+            Self::Plain => text.to_string(),
+            Self::Ansi => format!("\x1b[{}m{}\x1b[0m", style.ansi_code(), text),
+        }
+    }
+}
+
+/// 输出字符集 (Charset)
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum Charset {
+    /// 使用 emoji 和 Unicode 符号（默认）
+    #[default]
+    Unicode,
+    /// 只使用 ASCII 的标记和符号
+    Ascii,
+}
+
+/// ASCII 模式下替换的符号；不在表中的 emoji 直接去掉（连同之后的一个空格）
+const ASCII_GLYPHS: &[(char, &str)] = &[
+    ('✅', "[OK]"),
+    ('✓', "[OK]"),
+    ('❌', "[ERROR]"),
+    ('✗', "[FAIL]"),
+    ('⚠', "[WARN]"),
+    ('💡', "[HINT]"),
+    ('⏭', "[SKIP]"),
+    ('•', "*"),
+    ('→', "->"),
+    ('…', "..."),
+    ('–', "-"),
+    ('—', "-"),
+    ('×', "x"),
+    ('·', "-"),
+    ('═', "="),
+    ('─', "-"),
+    ('│', "|"),
+];
+
+impl Charset {
+    /// 按字符集输出文本 (Render)
+    ///
+    /// [`Charset::Unicode`] 时原样返回。
+    ///
+    /// # 示例
+    /// ```
+    /// use lra_calculator_rust::console::Charset;
+    ///
+    /// assert_eq!(Charset::Ascii.render("✅ 扫描完成 → 3 个文件"), "[OK] 扫描完成 -> 3 个文件");
+    /// assert_eq!(Charset::Ascii.render("⚠️  无法写入"), "[WARN]  无法写入");
+    /// assert_eq!(Charset::Ascii.render("📄 结果文件位置: a.txt"), "结果文件位置: a.txt");
+    /// assert_eq!(Charset::Unicode.render("🎵 LRA"), "🎵 LRA");
+    /// ```
+    pub fn render(self, text: &str) -> Cow<'_, str> {
+        if self == Charset::Unicode || text.is_ascii() {
+            return Cow::Borrowed(text);
+        }
+        let mut rendered = String::with_capacity(text.len());
+        let mut chars = text.chars().peekable();
+        while let Some(ch) = chars.next() {
+            if let Some((_, replacement)) = ASCII_GLYPHS.iter().find(|(glyph, _)| *glyph == ch) {
+                rendered.push_str(replacement);
+            } else if is_emoji(ch) {
+                chars.next_if_eq(&'\u{fe0f}');
+                chars.next_if_eq(&' ');
+            } else if ch != '\u{fe0f}' {
+                rendered.push(ch);
+            }
+        }
+        Cow::Owned(rendered)
+    }
+
+    /// 按字符集格式化 (Format)
+    ///
+    /// 格式字符串按 [`Charset::render`] 转换，[`Verbatim`] 包装的参数原样输出。
+    /// 通常通过 [`format_with_charset!`] 和 [`human_format!`] 调用。
+    ///
+    /// # 示例
+    /// ```
+    /// use lra_calculator_rust::console::{Charset, Verbatim};
+    ///
+    /// let path = "Live — 2024 · 🎵.flac";
+    /// let text = Charset::Ascii.format(format_args!("✅ 完成: {}", Verbatim(path)));
+    /// assert_eq!(text, "[OK] 完成: Live — 2024 · 🎵.flac");
+    /// ```
+    pub fn format(self, args: fmt::Arguments) -> String {
+        if self == Charset::Unicode {
+            return fmt::format(args);
+        }
+        let mut renderer = TemplateRenderer { charset: self, output: String::new() };
+        // 写入 String 不会失败
+        let _ = renderer.write_fmt(args);
+        renderer.output
+    }
+}
+
+thread_local! {
+    /// 正在输出的 [`Verbatim`] 的层数；大于 0 时写入的文本原样输出
+    static VERBATIM_DEPTH: Cell<usize> = const { Cell::new(0) };
+}
+
+/// 原样输出的值 (Verbatim)
+///
+/// 按 [`Charset::Ascii`] 格式化时，包装的值不做任何转换。[`human_println!`] 等宏自动包装每个参数；
+/// 直接用 `write!` 写入 [`HumanWriter`] 时，用户数据需要手动包装。
+/// 格式说明（宽度、精度等）作用于包装的值。
+#[derive(Clone, Copy)]
+pub struct Verbatim<T>(pub T);
+
+/// 在作用域内标记正在输出 [`Verbatim`] 的值，结束（包括 panic）时恢复
+struct VerbatimScope;
+
+impl VerbatimScope {
+    fn enter() -> Self {
+        VERBATIM_DEPTH.set(VERBATIM_DEPTH.get() + 1);
+        VerbatimScope
+    }
+}
+
+impl Drop for VerbatimScope {
+    fn drop(&mut self) {
+        VERBATIM_DEPTH.set(VERBATIM_DEPTH.get() - 1);
+    }
+}
+
+impl<T: fmt::Display> fmt::Display for Verbatim<T> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let _scope = VerbatimScope::enter();
+        self.0.fmt(f)
+    }
+}
+
+impl<T: fmt::Debug> fmt::Debug for Verbatim<T> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let _scope = VerbatimScope::enter();
+        self.0.fmt(f)
+    }
+}
+
+/// 按字符集转换模板文本、原样写入 [`Verbatim`] 的值
+struct TemplateRenderer {
+    charset: Charset,
+    output: String,
+}
+
+impl fmt::Write for TemplateRenderer {
+    fn write_str(&mut self, text: &str) -> fmt::Result {
+        if VERBATIM_DEPTH.get() > 0 {
+            self.output.push_str(text);
+        } else {
+            self.output.push_str(&self.charset.render(text));
+        }
+        Ok(())
+    }
+}
+
+/// 程序自己的符号 (Glyph)
+///
+/// 用在含有用户数据的 `Display` 实现中：这样的值作为 [`Verbatim`] 参数原样输出，
+/// 其中的符号通过 `Glyph` 按当前字符集（[`output_charset`]）查找。
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Glyph {
+    /// `→`，ASCII 为 `->`
+    Arrow,
+    /// `–`（范围），ASCII 为 `-`
+    RangeDash,
+    /// `…`（截断），ASCII 为 `...`
+    Ellipsis,
+}
+
+impl Glyph {
+    /// 按字符集取得符号
+    ///
+    /// # 示例
+    /// ```
+    /// use lra_calculator_rust::console::{Charset, Glyph};
+    ///
+    /// assert_eq!(Glyph::Arrow.as_str(Charset::Unicode), "→");
+    /// assert_eq!(Glyph::Arrow.as_str(Charset::Ascii), "->");
+    /// ```
+    pub fn as_str(self, charset: Charset) -> &'static str {
+        match (self, charset) {
+            (Self::Arrow, Charset::Unicode) => "→",
+            (Self::Arrow, Charset::Ascii) => "->",
+            (Self::RangeDash, Charset::Unicode) => "–",
+            (Self::RangeDash, Charset::Ascii) => "-",
+            (Self::Ellipsis, Charset::Unicode) => "…",
+            (Self::Ellipsis, Charset::Ascii) => "...",
+        }
+    }
+}
+
+impl fmt::Display for Glyph {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.as_str(output_charset()))
+    }
+}
+
+/// 是否为 emoji 或杂项符号（ASCII 模式下去掉）
+fn is_emoji(ch: char) -> bool {
+    matches!(ch as u32, 0x2300..=0x23FF | 0x2600..=0x27BF | 0x2B00..=0x2BFF | 0x1F000..=0x1FAFF)
+}
+
+/// 字符集开关 (Charset Choice)，对应 `--ascii` / `--unicode`
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum CharsetChoice {
+    /// 按运行环境判断（默认）
+    #[default]
+    Auto,
+    /// 总是只使用 ASCII（`--ascii`、`--no-emoji`）
+    Ascii,
+    /// 总是使用 Unicode（`--unicode`）
+    Unicode,
+}
+
+impl CharsetChoice {
+    /// 决定使用的字符集
+    ///
+    /// # 参数
+    /// - `supports_utf8` - 运行环境能否正常显示 UTF-8（见 [`detect_utf8_support`]）
+    pub fn resolve(self, supports_utf8: bool) -> Charset {
+        match self {
+            Self::Ascii => Charset::Ascii,
+            Self::Unicode => Charset::Unicode,
+            Self::Auto if supports_utf8 => Charset::Unicode,
+            Self::Auto => Charset::Ascii,
+        }
+    }
+}
+
+/// 判断运行环境能否正常显示 UTF-8 (Detect UTF-8 Support)
+///
+/// 按 `LC_ALL`、`LC_CTYPE`、`LANG` 中第一个非空的值判断，值中包含 `UTF-8`（不区分大小写，
+/// 也接受 `utf8`）时支持，`C`、`POSIX` 等其他区域设置不支持。都没有设置时，Windows 上只有
+/// Windows Terminal（`WT_SESSION`）和设置了 `TERM_PROGRAM` 的终端算作支持，旧的控制台不支持；
+/// 其他平台视为支持。
+///
+/// # 参数
+/// - `lookup` - 根据变量名返回环境变量值的函数
+/// - `windows` - 是否运行在 Windows 上
+///
+/// # 示例
+/// ```
+/// use lra_calculator_rust::console::detect_utf8_support;
+///
+/// let env = |pairs: &'static [(&'static str, &'static str)]| {
+///     move |name: &str| pairs.iter().find(|(key, _)| *key == name).map(|(_, value)| value.to_string())
+/// };
+/// assert!(detect_utf8_support(env(&[("LANG", "zh_CN.UTF-8")]), false));
+/// assert!(!detect_utf8_support(env(&[("LC_ALL", "C"), ("LANG", "zh_CN.UTF-8")]), false));
+/// assert!(!detect_utf8_support(env(&[]), true));
+/// assert!(detect_utf8_support(env(&[("WT_SESSION", "1")]), true));
+/// ```
+pub fn detect_utf8_support<F>(lookup: F, windows: bool) -> bool
+where
+    F: Fn(&str) -> Option<String>,
+{
+    let locale = ["LC_ALL", "LC_CTYPE", "LANG"].into_iter().find_map(|name| lookup(name).filter(|value| !value.is_empty()));
+    match locale {
+        Some(locale) => {
+            let locale = locale.to_ascii_lowercase();
+            locale.contains("utf-8") || locale.contains("utf8")
+        }
+        None if windows => lookup("WT_SESSION").is_some() || lookup("TERM_PROGRAM").is_some(),
+        None => true,
+    }
+}
+
+/// 人类可读输出和错误是否只使用 ASCII
+static ASCII_OUTPUT: AtomicBool = AtomicBool::new(false);
+
+/// 按 `--ascii` / `--unicode` 和运行环境配置输出字符集
+pub fn configure_charset(choice: CharsetChoice) {
+    let supports_utf8 = detect_utf8_support(|name| env::var(name).ok(), cfg!(windows));
+    ASCII_OUTPUT.store(choice.resolve(supports_utf8) == Charset::Ascii, Ordering::Relaxed);
+}
+
+/// 当前的输出字符集
+pub fn output_charset() -> Charset {
+    if ASCII_OUTPUT.load(Ordering::Relaxed) {
+        Charset::Ascii
+    } else {
+        Charset::Unicode
+    }
+}
+
+/// 按 `--color` 和运行环境配置 stdout、stderr 的颜色
+///
+/// `NO_COLOR` 设置为非空值时 `auto` 不使用颜色（见 <https://no-color.org>）。
+pub fn configure_colors(choice: ColorChoice) {
+    let no_color = env::var_os("NO_COLOR").is_some_and(|value| !value.is_empty());
+    let stdout = choice.resolve(io::stdout().is_terminal(), no_color);
+    let stderr = choice.resolve(io::stderr().is_terminal(), no_color);
+    STDOUT_COLOR.store(stdout == ColorMode::Ansi, Ordering::Relaxed);
+    STDERR_COLOR.store(stderr == ColorMode::Ansi, Ordering::Relaxed);
+}
+
+/// 人类可读输出当前目标的颜色模式
+pub fn human_color_mode() -> ColorMode {
+    if human_output_to_stderr() {
+        error_color_mode()
+    } else if STDOUT_COLOR.load(Ordering::Relaxed) {
+        ColorMode::Ansi
+    } else {
+        ColorMode::Plain
+    }
+}
+
+/// stderr（错误和警告）的颜色模式
+pub fn error_color_mode() -> ColorMode {
+    if STDERR_COLOR.load(Ordering::Relaxed) {
+        ColorMode::Ansi
+    } else {
+        ColorMode::Plain
+    }
+}
+
+/// 设置人类可读输出的目标
+///
+/// # 参数
+/// - `to_stderr` - 为 `true` 时写入 stderr，否则写入 stdout
+pub fn set_human_output_to_stderr(to_stderr: bool) {
+    HUMAN_OUTPUT_TO_STDERR.store(to_stderr, Ordering::Relaxed);
+}
+
+/// 人类可读输出当前是否写入 stderr
+pub fn human_output_to_stderr() -> bool {
+    HUMAN_OUTPUT_TO_STDERR.load(Ordering::Relaxed)
+}
+
+/// 人类可读输出当前的目标是否为终端
+pub fn human_output_is_terminal() -> bool {
+    if human_output_to_stderr() {
+        io::stderr().is_terminal()
+    } else {
+        io::stdout().is_terminal()
+    }
+}
+
+/// 写入一段人类可读输出（[`human_print!`] 和 [`human_println!`] 的实现）
+#[cfg(feature = "cli")]
+#[doc(hidden)]
+pub fn write_human(args: fmt::Arguments) {
+    if human_output_to_stderr() {
+        write_rendered(&mut io::stderr().lock(), args);
+    } else {
+        write_rendered(&mut io::stdout().lock(), args);
+    }
+}
+
+/// 写入一段错误或警告（[`human_eprintln!`] 的实现）
+#[cfg(feature = "cli")]
+#[doc(hidden)]
+pub fn write_error(args: fmt::Arguments) {
+    write_rendered(&mut io::stderr().lock(), args);
+}
+
+/// 按当前字符集写入（只转换模板文本，见 [`Charset::format`]）
+#[cfg(feature = "cli")]
+fn write_rendered(writer: &mut impl Write, args: fmt::Arguments) {
+    // 与 println! 一致：写入失败（如管道关闭）时不中断程序
+    let _ = match output_charset() {
+        Charset::Unicode => writer.write_fmt(args),
+        Charset::Ascii => writer.write_all(Charset::Ascii.format(args).as_bytes()),
+    };
+}
+
+/// 刷新人类可读输出，确保不带换行的提示立即显示
+#[cfg(feature = "cli")]
+pub fn flush_human() -> io::Result<()> {
+    if human_output_to_stderr() {
+        io::stderr().flush()
+    } else {
+        io::stdout().flush()
+    }
+}
+
+/// 写入人类可读输出的 [`Write`] 实现 (Human Writer)
+///
+/// 与 [`human_print!`] 相同：写入当前的输出目标并按当前字符集输出，
+/// 供接受 `impl Write` 的函数（如交互式路径输入）使用。写入失败同样被忽略。
+/// `write!` 不会自动包装参数，用户数据需要用 [`Verbatim`] 包装后再写入。
+///
+/// # 示例
+/// ```
+/// use std::io::Write;
+/// use lra_calculator_rust::console::HumanWriter;
+///
+/// writeln!(HumanWriter, "✅ 完成").unwrap();
+/// ```
+#[cfg(feature = "cli")]
+#[derive(Debug, Clone, Copy, Default)]
+pub struct HumanWriter;
+
+#[cfg(feature = "cli")]
+impl Write for HumanWriter {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        write_human(format_args!("{}", Verbatim(String::from_utf8_lossy(buf))));
+        Ok(buf.len())
+    }
+
+    /// 按格式字符串转换，emoji 和后面的空格不会被拆开
+    fn write_fmt(&mut self, args: fmt::Arguments) -> io::Result<()> {
+        write_human(args);
+        Ok(())
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        flush_human()
+    }
+}
+
+/// 输出一行人类可读信息，用法与 `println!` 相同
+///
+/// 格式字符串按当前字符集输出，参数原样输出（见 [`Verbatim`](crate::console::Verbatim)）。
+#[cfg(feature = "cli")]
+#[macro_export]
+macro_rules! human_println {
+    () => {
+        $crate::console::write_human(format_args!("\n"))
+    };
+    ($fmt:literal $(, $arg:expr)* $(,)?) => {
+        $crate::console::write_human(format_args!(concat!($fmt, "\n") $(, $crate::console::Verbatim(&$arg))*))
+    };
+}
+
+/// 向 stderr 输出一行错误或警告，用法与 `eprintln!` 相同
+///
+/// 与 [`human_println!`] 一样按当前字符集输出，但不受输出目标切换的影响。
+#[cfg(feature = "cli")]
+#[macro_export]
+macro_rules! human_eprintln {
+    ($fmt:literal $(, $arg:expr)* $(,)?) => {
+        $crate::console::write_error(format_args!(concat!($fmt, "\n") $(, $crate::console::Verbatim(&$arg))*))
+    };
+}
+
+/// 输出人类可读信息（不换行），用法与 `print!` 相同
+#[cfg(feature = "cli")]
+#[macro_export]
+macro_rules! human_print {
+    ($fmt:literal $(, $arg:expr)* $(,)?) => {
+        $crate::console::write_human(format_args!($fmt $(, $crate::console::Verbatim(&$arg))*))
+    };
+}
+
+/// 按指定字符集格式化，用法与 `format!` 相同（第一个参数为 [`Charset`](crate::console::Charset)）
+///
+/// 格式字符串按字符集转换，参数原样输出。
+///
+/// # 示例
+/// ```
+/// use lra_calculator_rust::console::Charset;
+/// use lra_calculator_rust::format_with_charset;
+///
+/// let path = "Mozart · Requiem — Live.flac";
+/// assert_eq!(format_with_charset!(Charset::Ascii, "📄 结果: {} → 完成", path), "结果: Mozart · Requiem — Live.flac -> 完成");
+/// ```
+#[macro_export]
+macro_rules! format_with_charset {
+    ($charset:expr, $fmt:literal $(, $arg:expr)* $(,)?) => {
+        $crate::console::Charset::format($charset, format_args!($fmt $(, $crate::console::Verbatim(&$arg))*))
+    };
+}
+
+/// 按当前字符集格式化人类可读文本，用法与 `format!` 相同
+///
+/// 用于先拼成字符串、之后再通过 [`human_println!`] 输出的文本：
+/// 字符串作为参数输出时不再转换，模板中的符号需要在这里转换。
+#[macro_export]
+macro_rules! human_format {
+    ($($arg:tt)*) => {
+        $crate::format_with_charset!($crate::console::output_charset(), $($arg)*)
+    };
+}
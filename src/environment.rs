@@ -0,0 +1,436 @@
+//! 运行环境检测模块 (Runtime Environment Module)
+//!
+//! 收集运行 LRA 分析所需的环境信息（FFmpeg 位置和版本、ebur128 滤波器、
+//! ffprobe、CPU 核心数），并以结构化报告的形式返回，
+//! 便于嵌入本库的应用程序向用户展示检测结果。
+//!
+//! ## 容错策略
+//!
+//! 报告中无法确定的字段使用 `Option` 表示，不会导致整个检测失败。
+//! 只有 [`EnvironmentReport::ensure_ready`] 会把致命问题转换为 `AppError`。
+//!
+//! ## 可执行文件的位置
+//!
+//! 同时装了几个 FFmpeg 时，用户往往不知道实际用的是哪一个。检测时 [`resolve_program`]
+//! 按操作系统的规则在 PATH 中找到可执行文件的绝对路径并记录下来，之后
+//! [`tool_command`] 创建的命令都使用这个路径，检测和分析不会用到不同的可执行文件。
+
+use std::collections::HashMap;
+use std::ffi::OsStr;
+use std::fmt;
+use std::path::{Path, PathBuf};
+use std::sync::{LazyLock, RwLock};
+
+use crate::error::{AppError, FfmpegErrorKind};
+use crate::priority::tool_command;
+
+/// FFmpeg 可执行状态 (FFmpeg Status)
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[cfg_attr(feature = "serde", serde(rename_all = "snake_case"))]
+pub enum FfmpegStatus {
+    /// `ffmpeg -version` 执行成功
+    Available,
+    /// 无法启动 FFmpeg（未安装或不在 PATH 中）
+    NotFound,
+    /// FFmpeg 可以启动但执行失败
+    Failed,
+}
+
+impl FfmpegStatus {
+    /// 将状态转换为结果，不可用时返回附带安装建议的错误（见 [`AppError::suggestions`]）
+    ///
+    /// # 返回值
+    /// - `Ok(())` - FFmpeg 可用
+    /// - `Err(AppError::Ffmpeg)` - FFmpeg 不存在或无法正常运行
+    pub fn ensure_available(self) -> Result<(), AppError> {
+        match self {
+            FfmpegStatus::Available => Ok(()),
+            FfmpegStatus::Failed => Err(AppError::Ffmpeg {
+                kind: FfmpegErrorKind::NotRunnable,
+                message: "FFmpeg 存在但无法正常运行".to_string(),
+            }),
+            FfmpegStatus::NotFound => Err(AppError::Ffmpeg {
+                kind: FfmpegErrorKind::NotFound,
+                message: "未找到 FFmpeg".to_string(),
+            }),
+        }
+    }
+
+    /// 状态的中文说明
+    pub fn label(self) -> &'static str {
+        match self {
+            FfmpegStatus::Available => "可用",
+            FfmpegStatus::NotFound => "未找到",
+            FfmpegStatus::Failed => "存在但无法正常运行",
+        }
+    }
+}
+
+impl fmt::Display for FfmpegStatus {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.label())
+    }
+}
+
+/// 运行环境报告 (Environment Report)
+///
+/// 由 [`check_environment_report`] 生成。除 `ffmpeg_status` 外，
+/// 所有无法确定的信息都以 `None` 表示。
+#[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct EnvironmentReport {
+    /// FFmpeg 可执行状态
+    pub ffmpeg_status: FfmpegStatus,
+    /// 在 PATH 中找到的 FFmpeg 可执行文件的绝对路径，分析时使用同一个文件（见 [`resolve_program`]）
+    pub ffmpeg_path: Option<PathBuf>,
+    /// FFmpeg 版本号（如 `6.1.1`）
+    pub ffmpeg_version: Option<String>,
+    /// 是否包含 ebur128 滤波器；无法查询滤波器列表时为 `None`
+    pub ebur128_available: Option<bool>,
+    /// ffprobe 是否可用
+    pub ffprobe_available: bool,
+    /// 可用的 CPU 核心数
+    pub cpu_count: Option<usize>,
+}
+
+impl EnvironmentReport {
+    /// 检查报告中是否存在致命问题
+    ///
+    /// # 返回值
+    /// - `Ok(())` - 环境可以进行 LRA 分析
+    /// - `Err(AppError::Ffmpeg)` - FFmpeg 不可用或缺少 ebur128 滤波器
+    pub fn ensure_ready(&self) -> Result<(), AppError> {
+        self.ffmpeg_status.ensure_available()?;
+
+        if self.ebur128_available == Some(false) {
+            return Err(AppError::Ffmpeg {
+                kind: FfmpegErrorKind::MissingEbur128,
+                message: "当前 FFmpeg 构建不包含 ebur128 滤波器，无法计算 LRA".to_string(),
+            });
+        }
+
+        Ok(())
+    }
+}
+
+/// 生成运行环境报告 (Check Environment Report)
+///
+/// 依次探测 FFmpeg、ebur128 滤波器、ffprobe 和 CPU 核心数。
+/// FFmpeg 不可用时不会继续查询滤波器列表。
+///
+/// # 返回值
+/// - 运行环境报告；调用 [`EnvironmentReport::ensure_ready`] 判断是否可以运行
+pub fn check_environment_report() -> EnvironmentReport {
+    let (ffmpeg_status, ffmpeg_version) = probe_ffmpeg();
+
+    let ebur128_available = if ffmpeg_status == FfmpegStatus::Available {
+        probe_ebur128_filter()
+    } else {
+        None
+    };
+
+    resolve_program("ffprobe");
+    EnvironmentReport {
+        ffmpeg_status,
+        ffmpeg_path: resolved_program("ffmpeg"),
+        ffmpeg_version,
+        ebur128_available,
+        ffprobe_available: command_succeeds("ffprobe", &["-version"]),
+        cpu_count: std::thread::available_parallelism().ok().map(usize::from),
+    }
+}
+
+/// 探测 FFmpeg 状态和版本号
+///
+/// 先用 [`resolve_program`] 重新确定 FFmpeg 的位置，之后的命令都使用这个文件。
+///
+/// # 返回值
+/// - FFmpeg 状态，以及能够解析时的版本号
+pub fn probe_ffmpeg() -> (FfmpegStatus, Option<String>) {
+    resolve_program("ffmpeg");
+    match tool_command("ffmpeg").arg("-version").output() {
+        Ok(output) if output.status.success() => (
+            FfmpegStatus::Available,
+            extract_ffmpeg_version(&output.stdout),
+        ),
+        Ok(_) => (FfmpegStatus::Failed, None),
+        Err(_) => (FfmpegStatus::NotFound, None),
+    }
+}
+
+/// 查询 FFmpeg 滤波器列表中是否包含 ebur128
+fn probe_ebur128_filter() -> Option<bool> {
+    let output = tool_command("ffmpeg")
+        .args(["-hide_banner", "-filters"])
+        .output()
+        .ok()
+        .filter(|output| output.status.success())?;
+
+    has_ebur128_filter(&String::from_utf8_lossy(&output.stdout))
+}
+
+/// 判断 `ffmpeg -filters` 的输出中是否列出了 ebur128
+///
+/// 每行格式为 ` TSC ebur128  A->N  EBU R128 scanner.`，滤波器名是第二列。
+/// 输出中没有任何可识别的滤波器行时无法下结论，返回 `None`。
+fn has_ebur128_filter(filters_output: &str) -> Option<bool> {
+    let mut filter_names = filters_output.lines().filter_map(|line| {
+        let mut columns = line.split_whitespace();
+        let name = columns.nth(1)?;
+        columns.next().filter(|io| io.contains("->")).map(|_| name)
+    });
+
+    let first = filter_names.next()?;
+    Some(first == "ebur128" || filter_names.any(|name| name == "ebur128"))
+}
+
+/// 从 FFmpeg 输出中提取版本号 (Extract FFmpeg Version)
+///
+/// # 参数
+/// - `version_output` - `ffmpeg -version` 的输出，首行格式为 `ffmpeg version X.X.X ...`
+///
+/// # 返回值
+/// - 版本号；无法解析时返回 `None`
+fn extract_ffmpeg_version(version_output: &[u8]) -> Option<String> {
+    let output_str = String::from_utf8_lossy(version_output);
+    let first_line = output_str.lines().next()?;
+
+    first_line
+        .strip_prefix("ffmpeg version")?
+        .split_whitespace()
+        .next()
+        .map(str::to_string)
+}
+
+/// 执行命令并判断是否成功
+fn command_succeeds(program: &str, args: &[&str]) -> bool {
+    tool_command(program)
+        .args(args)
+        .output()
+        .map(|output| output.status.success())
+        .unwrap_or(false)
+}
+
+/// 已确定位置的外部程序，按程序名索引
+static RESOLVED_PROGRAMS: LazyLock<RwLock<HashMap<String, PathBuf>>> = LazyLock::new(RwLock::default);
+
+/// 确定外部程序的位置 (Resolve Program)
+///
+/// 在 PATH 中查找 `program` 并记录它的绝对路径：之后 [`tool_command`] 创建的该程序的命令都使用这个路径。
+/// 找不到时清除之前的记录，命令按程序名启动，由操作系统决定（通常随即启动失败）。
+///
+/// # 参数
+/// - `program` - 程序名，如 `ffmpeg`、`ffprobe`
+///
+/// # 返回值
+/// - 可执行文件的绝对路径；找不到时为 `None`
+pub fn resolve_program(program: &str) -> Option<PathBuf> {
+    let resolved = std::env::var_os("PATH").and_then(|path_var| find_in_search_path(program, &path_var));
+    let mut programs = RESOLVED_PROGRAMS.write().unwrap_or_else(|poisoned| poisoned.into_inner());
+    match &resolved {
+        Some(path) => programs.insert(program.to_string(), path.clone()),
+        None => programs.remove(program),
+    };
+    resolved
+}
+
+/// 已确定位置的外部程序的绝对路径；尚未确定或找不到时为 `None`
+///
+/// # 参数
+/// - `program` - 程序名
+///
+/// # 示例
+/// ```
+/// use lra_calculator_rust::environment::resolved_program;
+///
+/// assert_eq!(resolved_program("not-a-real-program"), None);
+/// ```
+pub fn resolved_program(program: &str) -> Option<PathBuf> {
+    RESOLVED_PROGRAMS.read().unwrap_or_else(|poisoned| poisoned.into_inner()).get(program).cloned()
+}
+
+/// 按 PATH 的值查找可执行文件 (Find in Search Path)
+///
+/// 与操作系统的查找顺序相同：按顺序检查每个目录，取第一个可以执行的文件；
+/// 空的或相对的目录按当前目录解析，结果总是绝对路径。
+///
+/// # 参数
+/// - `program` - 程序名（Windows 上会自动追加 `.exe`）
+/// - `path_var` - PATH 环境变量的值
+///
+/// # 返回值
+/// - 第一个匹配的可执行文件的绝对路径
+fn find_in_search_path(program: &str, path_var: &OsStr) -> Option<PathBuf> {
+    let candidate = find_in_dirs(program, std::env::split_paths(path_var))?;
+    std::path::absolute(&candidate).ok()
+}
+
+/// 在指定目录列表中查找可执行文件
+fn find_in_dirs<I, P>(program: &str, dirs: I) -> Option<PathBuf>
+where
+    I: IntoIterator<Item = P>,
+    P: AsRef<Path>,
+{
+    let file_name = if cfg!(windows) {
+        format!("{}.exe", program)
+    } else {
+        program.to_string()
+    };
+
+    dirs.into_iter()
+        .map(|dir| dir.as_ref().join(OsStr::new(&file_name)))
+        .find(|candidate| is_executable(candidate))
+}
+
+/// 是否为可以执行的文件；Unix 上要求有执行权限，与 shell 查找 PATH 时一样跳过没有权限的文件
+#[cfg(unix)]
+fn is_executable(path: &Path) -> bool {
+    use std::os::unix::fs::PermissionsExt;
+
+    path.metadata().is_ok_and(|metadata| metadata.is_file() && metadata.permissions().mode() & 0o111 != 0)
+}
+
+/// 是否为可以执行的文件
+#[cfg(not(unix))]
+fn is_executable(path: &Path) -> bool {
+    path.is_file()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::suggestion::Suggestion;
+    use std::fs::File;
+    use tempfile::TempDir;
+
+    /// 测试 FFmpeg 状态的中文显示
+    #[test]
+    fn test_ffmpeg_status_display() {
+        assert_eq!(FfmpegStatus::Available.to_string(), "可用");
+        assert_eq!(FfmpegStatus::NotFound.to_string(), "未找到");
+        assert_eq!(FfmpegStatus::Failed.to_string(), "存在但无法正常运行");
+    }
+
+    /// 测试 FFmpeg 版本号提取
+    #[test]
+    fn test_extract_ffmpeg_version() {
+        let version_output = b"ffmpeg version 4.4.2 Copyright (c) 2000-2021 the FFmpeg developers\n";
+        assert_eq!(extract_ffmpeg_version(version_output), Some("4.4.2".to_string()));
+
+        assert_eq!(extract_ffmpeg_version(b"some other output\n"), None);
+        assert_eq!(extract_ffmpeg_version(b""), None);
+    }
+
+    /// 测试从滤波器列表中识别 ebur128
+    #[test]
+    fn test_has_ebur128_filter() {
+        let filters = "Filters:\n\
+                       \x20 T.. = Timeline support\n\
+                       \x20... ebur128           A->N       EBU R128 scanner.\n\
+                       \x20... volume            A->A       Change input volume.\n";
+        assert_eq!(has_ebur128_filter(filters), Some(true));
+        assert_eq!(
+            has_ebur128_filter("Filters:\n ... volume  A->A  Change input volume.\n"),
+            Some(false)
+        );
+        // 无法识别的输出不下结论
+        assert_eq!(has_ebur128_filter("unexpected output\n"), None);
+    }
+
+    /// 创建可以执行的空文件
+    fn create_executable(path: &Path) {
+        File::create(path).unwrap();
+        #[cfg(unix)]
+        {
+            use std::os::unix::fs::PermissionsExt;
+            std::fs::set_permissions(path, std::fs::Permissions::from_mode(0o755)).unwrap();
+        }
+    }
+
+    /// 测试在目录列表中查找可执行文件
+    #[test]
+    fn test_find_in_dirs() {
+        let temp_dir = TempDir::new().expect("无法创建临时目录");
+        let bin_dir = temp_dir.path().join("bin");
+        std::fs::create_dir(&bin_dir).unwrap();
+        let name = if cfg!(windows) { "ffmpeg.exe" } else { "ffmpeg" };
+        create_executable(&bin_dir.join(name));
+
+        let found = find_in_dirs("ffmpeg", [temp_dir.path().to_path_buf(), bin_dir.clone()]);
+        assert_eq!(found, Some(bin_dir.join(name)));
+        assert_eq!(find_in_dirs("ffprobe", [bin_dir]), None);
+    }
+
+    /// 测试按 PATH 的顺序取第一个可执行文件，结果为绝对路径
+    #[test]
+    fn test_find_in_search_path_order() {
+        let temp_dir = TempDir::new().expect("无法创建临时目录");
+        let name = if cfg!(windows) { "ffmpeg.exe" } else { "ffmpeg" };
+        let [local, system, empty] = ["local", "system", "empty"].map(|dir| temp_dir.path().join(dir));
+        for dir in [&local, &system, &empty] {
+            std::fs::create_dir(dir).unwrap();
+        }
+        create_executable(&local.join(name));
+        create_executable(&system.join(name));
+        let path_var = |dirs: &[&PathBuf]| std::env::join_paths(dirs).unwrap();
+
+        let found = find_in_search_path("ffmpeg", &path_var(&[&empty, &local, &system]));
+        assert_eq!(found, Some(local.join(name)));
+        let found = find_in_search_path("ffmpeg", &path_var(&[&system, &local]));
+        assert_eq!(found, Some(system.join(name)));
+        assert_eq!(find_in_search_path("ffmpeg", &path_var(&[&empty])), None);
+
+        // 没有执行权限的文件与操作系统一样跳过
+        #[cfg(unix)]
+        {
+            File::create(empty.join(name)).unwrap();
+            let found = find_in_search_path("ffmpeg", &path_var(&[&empty, &system]));
+            assert_eq!(found, Some(system.join(name)));
+        }
+    }
+
+    /// 测试确定位置后外部命令以绝对路径启动，找不到的程序按程序名启动
+    #[cfg(unix)]
+    #[test]
+    fn test_tool_command_uses_resolved_path() {
+        let sh = resolve_program("sh").expect("PATH 中应该有 sh");
+        assert!(sh.is_absolute());
+        assert_eq!(resolved_program("sh"), Some(sh.clone()));
+        assert_eq!(tool_command("sh").get_program(), sh.as_os_str());
+
+        assert_eq!(resolve_program("lra-no-such-program"), None);
+        assert_eq!(tool_command("lra-no-such-program").get_program(), "lra-no-such-program");
+    }
+
+    /// 测试报告中的致命问题映射为错误
+    #[test]
+    fn test_report_ensure_ready() {
+        let mut report = EnvironmentReport {
+            ffmpeg_status: FfmpegStatus::Available,
+            ffmpeg_path: None,
+            ffmpeg_version: None,
+            ebur128_available: None,
+            ffprobe_available: false,
+            cpu_count: None,
+        };
+        // 无法确定的字段不会导致失败
+        assert!(report.ensure_ready().is_ok());
+
+        report.ebur128_available = Some(false);
+        let missing_filter = report.ensure_ready().unwrap_err();
+        assert!(matches!(missing_filter, AppError::Ffmpeg { kind: FfmpegErrorKind::MissingEbur128, .. }));
+        assert_eq!(missing_filter.suggestions(), vec![Suggestion::InstallFullFfmpegBuild]);
+
+        report.ffmpeg_status = FfmpegStatus::NotFound;
+        let not_found = report.ensure_ready().unwrap_err();
+        match &not_found {
+            AppError::Ffmpeg { kind, message } => {
+                assert_eq!(*kind, FfmpegErrorKind::NotFound);
+                assert!(message.contains("未找到 FFmpeg"));
+            }
+            other => panic!("期望得到 AppError::Ffmpeg 错误, 实际为 {:?}", other),
+        }
+        assert_eq!(not_found.suggestions(), vec![Suggestion::InstallFfmpeg]);
+    }
+}
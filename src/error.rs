@@ -10,8 +10,14 @@
 //! - **上下文保留**: 保留错误发生时的上下文信息，便于调试
 //! - **用户友好**: 提供清晰的中文错误信息，帮助用户理解问题
 //! - **错误链**: 支持错误链追踪，保留原始错误信息
+//! - **建议分离**: 错误信息只陈述事实，解决方法由 [`AppError::suggestions`] 和
+//!   [`ProcessFileError::suggestions`] 以 [`Suggestion`] 返回，命令行程序输出为列表
 
 use std::fmt;
+use std::path::PathBuf;
+
+use crate::file_key::FileKey;
+use crate::suggestion::Suggestion;
 
 /// 文件处理错误结构体 (File Processing Error)
 ///
@@ -23,20 +29,64 @@ use std::fmt;
 /// - FFmpeg 分析失败
 /// - 文件读取权限问题
 /// - LRA 值解析失败
-#[derive(Debug, Clone)]
+///
+/// 启用 `serde` 特性后可序列化为 JSON，字段名构成稳定的对外契约。
+#[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct ProcessFileError {
-    /// 出错的文件路径（相对路径，用于显示）
-    pub file_path: String,
+    /// 出错的文件路径（相对路径，用于显示；与处理结果共享同一份字符串）
+    pub file_path: FileKey,
     /// 错误描述信息（详细的错误原因）
     pub message: String,
     /// 错误类型分类（用于统计和分析）
     pub error_type: FileErrorType,
+    /// FFmpeg 进程的退出码（仅在 FFmpeg 以非零状态退出时存在）
+    pub exit_code: Option<i32>,
+    /// FFmpeg stderr 输出的截断摘录（最多 [`MAX_STDERR_EXCERPT_BYTES`] 字节）
+    pub stderr_excerpt: Option<String>,
+    /// 文件在扫描之后、分析之前被移动或删除（见 [`ProcessFileError::vanished`]）；
+    /// 错误类型为 [`FileErrorType::FileAccess`]
+    #[cfg_attr(feature = "serde", serde(default))]
+    pub vanished: bool,
+}
+
+/// 文件在扫描之后消失时的错误描述
+pub const VANISHED_MESSAGE: &str = "文件已不存在（扫描之后被移动或删除）";
+
+/// stderr 摘录的最大字节数
+///
+/// 大量文件失败时，每个错误都保存完整的 FFmpeg 输出会占用大量内存，
+/// 因此只保留前 2 KB 用于调试。
+pub const MAX_STDERR_EXCERPT_BYTES: usize = 2048;
+
+/// 截断 stderr 输出为摘录 (Truncate Stderr to Excerpt)
+///
+/// 保留前 [`MAX_STDERR_EXCERPT_BYTES`] 字节，并确保在 UTF-8 字符边界处截断。
+///
+/// # 参数
+/// - `stderr` - FFmpeg 的完整 stderr 输出
+///
+/// # 返回值
+/// - 截断后的字符串
+pub fn truncate_stderr_excerpt(stderr: &str) -> String {
+    if stderr.len() <= MAX_STDERR_EXCERPT_BYTES {
+        return stderr.to_string();
+    }
+
+    let mut end = MAX_STDERR_EXCERPT_BYTES;
+    while !stderr.is_char_boundary(end) {
+        end -= 1;
+    }
+    stderr[..end].to_string()
 }
 
 /// 文件处理错误类型分类 (File Error Type Classification)
 ///
-/// 用于对文件处理错误进行分类，便于统计分析和针对性处理
-#[derive(Debug, Clone, PartialEq)]
+/// 用于对文件处理错误进行分类，便于统计分析和针对性处理。
+/// 序列化时使用稳定的 snake_case 字符串（如 `"ffmpeg_execution"`）。
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, PartialOrd, Ord)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[cfg_attr(feature = "serde", serde(rename_all = "snake_case"))]
 pub enum FileErrorType {
     /// FFmpeg 执行失败（如格式不支持、文件损坏）
     FfmpegExecution,
@@ -44,46 +94,119 @@ pub enum FileErrorType {
     LraParsingFailed,
     /// 文件访问失败（权限、文件不存在等）
     FileAccess,
+    /// 云端占位文件，内容不在本地（OneDrive 仅在线文件、iCloud 存根等），见 [`crate::cloud`]
+    CloudPlaceholder,
+    /// 运行被取消（如 `--max-duration` 到期），文件没有开始分析，见 [`crate::cancel`]
+    Cancelled,
     /// 其他未分类错误
     Other,
 }
 
+impl FileErrorType {
+    /// 稳定的 snake_case 名称，与序列化形式相同
+    pub fn as_str(self) -> &'static str {
+        match self {
+            FileErrorType::FfmpegExecution => "ffmpeg_execution",
+            FileErrorType::LraParsingFailed => "lra_parsing_failed",
+            FileErrorType::FileAccess => "file_access",
+            FileErrorType::CloudPlaceholder => "cloud_placeholder",
+            FileErrorType::Cancelled => "cancelled",
+            FileErrorType::Other => "other",
+        }
+    }
+}
+
 impl ProcessFileError {
     /// 创建新的文件处理错误
     ///
     /// # 参数
-    /// - `file_path` - 出错的文件路径
+    /// - `file_path` - 出错的文件路径（`String`、`&str` 或 [`FileKey`]）
     /// - `message` - 错误描述信息
     /// - `error_type` - 错误类型分类
-    pub fn new(file_path: String, message: String, error_type: FileErrorType) -> Self {
+    pub fn new(file_path: impl Into<FileKey>, message: String, error_type: FileErrorType) -> Self {
         Self {
-            file_path,
+            file_path: file_path.into(),
             message,
             error_type,
+            exit_code: None,
+            stderr_excerpt: None,
+            vanished: false,
         }
     }
 
+    /// 创建文件消失错误 (Vanished File Error)
+    ///
+    /// 文件在扫描时存在，分析时已经不存在（同步中的音乐库里改名或删除了文件）。
+    /// 错误类型为 [`FileErrorType::FileAccess`]，统计中另外计入
+    /// [`crate::processor::ProcessingStats::vanished`]，增量更新时不保留该文件的旧结果。
+    pub fn vanished(file_path: impl Into<FileKey>) -> Self {
+        Self { vanished: true, ..Self::new(file_path, VANISHED_MESSAGE.to_string(), FileErrorType::FileAccess) }
+    }
+
+    /// 附加 FFmpeg 执行上下文
+    ///
+    /// 记录 FFmpeg 的退出码和 stderr 摘录，供库用户进行结构化调试。
+    /// stderr 会被截断为最多 [`MAX_STDERR_EXCERPT_BYTES`] 字节。
+    ///
+    /// # 参数
+    /// - `exit_code` - FFmpeg 进程退出码（被信号终止时为 `None`）
+    /// - `stderr` - FFmpeg 的 stderr 输出
+    pub fn with_ffmpeg_context(mut self, exit_code: Option<i32>, stderr: &str) -> Self {
+        self.exit_code = exit_code;
+        self.stderr_excerpt = Some(truncate_stderr_excerpt(stderr));
+        self
+    }
+
     /// 创建 FFmpeg 执行错误
-    pub fn ffmpeg_error(file_path: String, message: String) -> Self {
+    pub fn ffmpeg_error(file_path: impl Into<FileKey>, message: String) -> Self {
         Self::new(file_path, message, FileErrorType::FfmpegExecution)
     }
 
     /// 创建 LRA 解析错误
-    pub fn lra_parsing_error(file_path: String, message: String) -> Self {
+    pub fn lra_parsing_error(file_path: impl Into<FileKey>, message: String) -> Self {
         Self::new(file_path, message, FileErrorType::LraParsingFailed)
     }
 
     /// 创建文件访问错误
-    pub fn file_access_error(file_path: String, message: String) -> Self {
+    pub fn file_access_error(file_path: impl Into<FileKey>, message: String) -> Self {
         Self::new(file_path, message, FileErrorType::FileAccess)
     }
 
+    /// 解决这个错误的建议 (Suggestions)
+    ///
+    /// 按错误类型给出；文件消失、云端占位文件和取消的文件没有建议。
+    ///
+    /// # 示例
+    /// ```
+    /// use lra_calculator_rust::error::ProcessFileError;
+    /// use lra_calculator_rust::suggestion::Suggestion;
+    ///
+    /// let error = ProcessFileError::lra_parsing_error("a.flac", "无法解析".to_string());
+    /// assert!(error.suggestions().contains(&Suggestion::CheckAudioDuration));
+    /// assert!(ProcessFileError::vanished("b.flac").suggestions().is_empty());
+    /// ```
+    pub fn suggestions(&self) -> Vec<Suggestion> {
+        match self.error_type {
+            FileErrorType::FfmpegExecution => vec![Suggestion::CheckAudioFile],
+            FileErrorType::LraParsingFailed => {
+                vec![Suggestion::CheckAudioFile, Suggestion::CheckAudioDuration, Suggestion::CheckFfmpegVersion]
+            }
+            FileErrorType::FileAccess if !self.vanished => vec![Suggestion::CheckPermissions],
+            FileErrorType::FileAccess
+            | FileErrorType::CloudPlaceholder
+            | FileErrorType::Cancelled
+            | FileErrorType::Other => Vec::new(),
+        }
+    }
+
     /// 获取错误类型的中文描述
     pub fn error_type_description(&self) -> &'static str {
         match self.error_type {
             FileErrorType::FfmpegExecution => "FFmpeg 执行失败",
             FileErrorType::LraParsingFailed => "LRA 值解析失败",
             FileErrorType::FileAccess => "文件访问失败",
+            FileErrorType::CloudPlaceholder => "云端占位文件",
+            FileErrorType::Cancelled => "已取消，未处理",
             FileErrorType::Other => "其他错误",
         }
     }
@@ -103,6 +226,274 @@ impl fmt::Display for ProcessFileError {
 
 impl std::error::Error for ProcessFileError {}
 
+/// LRA 计算错误 (LRA Calculation Error)
+///
+/// 由 `calculate_lra_direct` 返回的类型化错误。调用方可以根据变体
+/// 直接判断失败原因，而不需要在错误信息中查找关键词。
+///
+/// 该类型实现了 `std::error::Error + Send + Sync`，因此仍可通过 `?`
+/// 或 `.into()` 转换为 `Box<dyn std::error::Error + Send + Sync>`。
+#[derive(Debug)]
+pub enum LraCalculationError {
+    /// 无法启动 FFmpeg 进程（未安装、不在 PATH 中或没有执行权限）
+    Spawn(std::io::Error),
+
+    /// FFmpeg 以非零状态退出（格式不支持、文件损坏等）
+    NonZeroExit {
+        /// 进程退出码（被信号终止时为 `None`）
+        code: Option<i32>,
+        /// stderr 输出摘录（最多 [`MAX_STDERR_EXCERPT_BYTES`] 字节）
+        stderr: String,
+        /// 实际执行的命令行（已按 shell 规则加引号，可直接复制运行）
+        command: String,
+    },
+
+    /// FFmpeg 输出中找不到 LRA 值
+    ParseFailed {
+        /// FFmpeg 输出的非空前几行，用于诊断
+        output_excerpt: String,
+    },
+
+    /// 找到了 LRA 字段，但其值不是有效的数字
+    InvalidValue {
+        /// 原始的 LRA 字符串
+        value: String,
+        /// 底层的数字解析错误
+        source: std::num::ParseFloatError,
+    },
+
+    /// FFmpeg 运行超出了时间限制，进程已被终止（异步接口的超时）
+    TimedOut {
+        /// 时间限制
+        timeout: std::time::Duration,
+        /// 实际执行的命令行
+        command: String,
+    },
+}
+
+impl fmt::Display for LraCalculationError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            LraCalculationError::Spawn(err) => write!(f, "执行 FFmpeg 命令失败: {err}"),
+            LraCalculationError::NonZeroExit { code, stderr, command } => write!(
+                f,
+                "FFmpeg 分析失败 (退出码: {}). 错误信息: {}. 命令: {}",
+                code.unwrap_or(-1),
+                stderr.lines().take(3).collect::<Vec<_>>().join("; "),
+                command
+            ),
+            LraCalculationError::ParseFailed { output_excerpt } => {
+                write!(f, "无法从 FFmpeg 输出中解析 LRA 值。FFmpeg 输出摘要: {output_excerpt}")
+            }
+            LraCalculationError::InvalidValue { value, source } => {
+                write!(f, "解析 LRA 值 '{value}' 失败: {source}")
+            }
+            LraCalculationError::TimedOut { timeout, command } => {
+                write!(f, "FFmpeg 分析超时 (超过 {:.1} 秒，进程已终止). 命令: {command}", timeout.as_secs_f64())
+            }
+        }
+    }
+}
+
+impl std::error::Error for LraCalculationError {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            LraCalculationError::Spawn(err) => Some(err),
+            LraCalculationError::InvalidValue { source, .. } => Some(source),
+            LraCalculationError::NonZeroExit { .. }
+            | LraCalculationError::ParseFailed { .. }
+            | LraCalculationError::TimedOut { .. } => None,
+        }
+    }
+}
+
+impl LraCalculationError {
+    /// 对应的文件错误类型分类
+    ///
+    /// FFmpeg 因为找不到输入文件而失败时为 [`FileErrorType::FileAccess`]（见 [`Self::is_missing_input`]）。
+    pub fn file_error_type(&self) -> FileErrorType {
+        match self {
+            _ if self.is_missing_input() => FileErrorType::FileAccess,
+            LraCalculationError::Spawn(_)
+            | LraCalculationError::NonZeroExit { .. }
+            | LraCalculationError::TimedOut { .. } => FileErrorType::FfmpegExecution,
+            LraCalculationError::ParseFailed { .. } | LraCalculationError::InvalidValue { .. } => {
+                FileErrorType::LraParsingFailed
+            }
+        }
+    }
+
+    /// 解决这个错误的建议 (Suggestions)
+    ///
+    /// 无法启动 FFmpeg 时建议安装；找不到 LRA 值时列出可能的原因。
+    pub fn suggestions(&self) -> Vec<Suggestion> {
+        match self {
+            LraCalculationError::Spawn(_) => vec![Suggestion::InstallFfmpeg],
+            LraCalculationError::ParseFailed { .. } => {
+                vec![Suggestion::CheckAudioFile, Suggestion::CheckAudioDuration, Suggestion::CheckFfmpegVersion]
+            }
+            LraCalculationError::NonZeroExit { .. }
+            | LraCalculationError::InvalidValue { .. }
+            | LraCalculationError::TimedOut { .. } => Vec::new(),
+        }
+    }
+
+    /// FFmpeg 是否因为输入文件不存在而失败
+    ///
+    /// 按 stderr 中的 `No such file or directory` 判断（FFmpeg 打开输入时的 `ENOENT` 信息）。
+    pub fn is_missing_input(&self) -> bool {
+        matches!(self, LraCalculationError::NonZeroExit { stderr, .. } if stderr.contains("No such file or directory"))
+    }
+}
+
+/// 结果行解析错误 (Result Line Parse Error)
+///
+/// 由 `parse_result_line` 返回，区分格式错误、数字无效和数值超出范围三种情况。
+/// `Display` 输出的中文信息与以前的字符串错误保持一致。
+#[derive(Debug, Clone, PartialEq)]
+pub enum ResultLineParseError {
+    /// 行中没有 `" - "` 分隔符
+    MissingSeparator,
+
+    /// 分隔符后的内容不是数字
+    InvalidNumber {
+        /// 去掉首尾空白后的原始字符串
+        raw: String,
+        /// 底层的数字解析错误
+        source: std::num::ParseFloatError,
+    },
+
+    /// 数值不是非负有限数（负数、无穷大或 NaN）
+    OutOfRange {
+        /// 解析得到的数值
+        value: f64,
+    },
+}
+
+impl fmt::Display for ResultLineParseError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ResultLineParseError::MissingSeparator => {
+                write!(f, "行格式不正确 (期望格式: '文件路径 - LRA值')")
+            }
+            ResultLineParseError::InvalidNumber { raw, source } => {
+                write!(f, "无法解析 LRA 值 '{raw}': {source}")
+            }
+            ResultLineParseError::OutOfRange { value } => {
+                write!(f, "LRA 值 '{value}' 超出合理范围 (应为非负有限数)")
+            }
+        }
+    }
+}
+
+impl std::error::Error for ResultLineParseError {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            ResultLineParseError::InvalidNumber { source, .. } => Some(source),
+            ResultLineParseError::MissingSeparator | ResultLineParseError::OutOfRange { .. } => None,
+        }
+    }
+}
+
+/// 结果文件校验错误 (Results Verification Error)
+///
+/// 写入结果文件后重新读取，读回的内容与写入的不一致时返回（磁盘已满、网络共享
+/// 或不可靠的 U 盘可能悄悄截断文件）。由 `verify_results_file` 包装在
+/// `io::ErrorKind::InvalidData` 类型的 `io::Error` 中返回。
+#[derive(Debug, Clone, PartialEq)]
+pub enum ResultsVerificationError {
+    /// 读回的条目数量与写入的不同
+    CountMismatch {
+        /// 写入的条目数量
+        written: usize,
+        /// 读回的条目数量
+        read_back: usize,
+    },
+
+    /// 某个条目读回的路径或 LRA 值与写入的不同
+    EntryMismatch {
+        /// 条目在数据中的序号（从 1 开始）
+        index: usize,
+        /// 写入的条目（`路径 - LRA`）
+        written: String,
+        /// 读回的条目（`路径 - LRA`）
+        read_back: String,
+    },
+}
+
+impl fmt::Display for ResultsVerificationError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ResultsVerificationError::CountMismatch { written, read_back } => {
+                write!(f, "结果文件校验失败: 写入 {written} 个条目，读回 {read_back} 个")
+            }
+            ResultsVerificationError::EntryMismatch { index, written, read_back } => {
+                write!(f, "结果文件校验失败: 第 {index} 个条目写入 '{written}'，读回 '{read_back}'")
+            }
+        }
+    }
+}
+
+impl std::error::Error for ResultsVerificationError {}
+
+/// 自检阶段 (Self-Test Stage)
+///
+/// 标识安装自检在哪个阶段失败，便于判断是 FFmpeg 缺少功能、
+/// 分析本身出错，还是 ebur128 的输出格式与预期不符。
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SelfTestStage {
+    /// 使用 lavfi 生成测试音频
+    Generation,
+    /// 使用 ebur128 分析测试音频
+    Analysis,
+    /// 从分析输出中解析 LRA 值
+    Parsing,
+}
+
+impl fmt::Display for SelfTestStage {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let description = match self {
+            SelfTestStage::Generation => "生成测试音频",
+            SelfTestStage::Analysis => "FFmpeg 响度分析",
+            SelfTestStage::Parsing => "LRA 结果解析",
+        };
+        write!(f, "{}", description)
+    }
+}
+
+impl From<&LraCalculationError> for SelfTestStage {
+    fn from(err: &LraCalculationError) -> Self {
+        match err.file_error_type() {
+            FileErrorType::LraParsingFailed => SelfTestStage::Parsing,
+            _ => SelfTestStage::Analysis,
+        }
+    }
+}
+
+/// 自检错误 (Self-Test Error)
+#[derive(Debug)]
+pub struct SelfTestError {
+    /// 失败的阶段
+    pub stage: SelfTestStage,
+    /// 详细错误信息
+    pub message: String,
+}
+
+impl SelfTestError {
+    /// 创建新的自检错误
+    pub fn new(stage: SelfTestStage, message: String) -> Self {
+        Self { stage, message }
+    }
+}
+
+impl fmt::Display for SelfTestError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "自检在「{}」阶段失败: {}", self.stage, self.message)
+    }
+}
+
+impl std::error::Error for SelfTestError {}
+
 /// 应用程序的主要错误类型 (Main Application Error Types)
 ///
 /// 这是应用程序的顶层错误类型，用于处理不同类别的系统级错误。
@@ -115,6 +506,14 @@ impl std::error::Error for ProcessFileError {}
 /// - `Ffmpeg`: FFmpeg 环境问题，如未安装、版本不兼容等
 /// - `Path`: 路径相关问题，如路径不存在、权限不足等
 /// - `Configuration`: 配置相关错误，如参数无效、配置文件格式错误等
+/// - `NoFilesFound`: 扫描目录中没有找到支持的音频文件
+/// - `Interrupted`: 运行被用户取消或中断
+/// - `Timeout`: 运行超出时间限制
+/// - `PartiallyCompleted`: 运行完成，但有文件处理失败
+/// - `ThresholdViolated`: 运行完成，但有文件的 LRA 超出允许范围（CI 门禁）
+/// - `RegressionDetected`: 运行完成，但有文件的 LRA 相对基线的变化超过阈值
+///
+/// 每个变体都对应一个独立的进程退出码，见 [`AppError::exit_code`]。
 #[derive(Debug)]
 pub enum AppError {
     /// 输入/输出错误 - 系统级 I/O 操作失败
@@ -130,17 +529,268 @@ pub enum AppError {
     /// FFmpeg 相关错误 - FFmpeg 环境或执行问题
     ///
     /// 包括 FFmpeg 未安装、版本不兼容、执行失败等系统级问题
-    Ffmpeg(String),
+    Ffmpeg {
+        /// 错误的具体情况，决定 [`AppError::suggestions`] 给出的建议
+        kind: FfmpegErrorKind,
+        /// 错误信息
+        message: String,
+    },
 
     /// 路径相关错误 - 路径验证和访问问题
     ///
     /// 包括路径不存在、不是目录、权限不足等
-    Path(String),
+    Path {
+        /// 错误的具体情况，决定 [`AppError::suggestions`] 给出的建议
+        kind: PathErrorKind,
+        /// 错误信息
+        message: String,
+    },
 
     /// 配置错误 - 程序配置和参数问题
     ///
     /// 包括无效参数、配置文件错误等
     Configuration(String),
+
+    /// 未找到音频文件 - 扫描根目录下没有支持的音频文件
+    ///
+    /// 命令行程序把这种情况作为 [`RunOutcome::NothingToDo`](crate::outcome::RunOutcome::NothingToDo)
+    /// 而不是错误报告；该变体留给需要把它视为失败的调用方，退出码相同。
+    NoFilesFound {
+        /// 扫描的根目录
+        root: PathBuf,
+        /// 没有找到文件的具体原因
+        reason: EmptyScanReason,
+    },
+
+    /// 运行被中断 - 用户取消或收到中断信号
+    Interrupted {
+        /// 中断前已完成的文件数量
+        completed: usize,
+        /// 待处理的文件总数
+        total: usize,
+    },
+
+    /// 超时 - 运行或单个操作超出时间限制
+    Timeout(String),
+
+    /// 部分完成 - 运行结束，但有文件处理失败
+    PartiallyCompleted {
+        /// 成功处理的文件数量
+        successful: usize,
+        /// 处理失败的文件数量
+        failed: usize,
+    },
+
+    /// 阈值检查失败 - 有文件的结果违反了 `--fail-if-*` 规则
+    ThresholdViolated {
+        /// 违反规则的条目数量
+        violations: usize,
+    },
+
+    /// 回归检测失败 - 有文件的 LRA 相对 `--baseline` 的变化超过 `--regression-threshold`
+    RegressionDetected {
+        /// 变化超过阈值的条目数量
+        regressions: usize,
+    },
+}
+
+/// FFmpeg 错误的具体情况 (FFmpeg Error Kind)
+///
+/// 在创建 [`AppError::Ffmpeg`] 时确定，调用方据此选择建议或界面操作，不需要解析错误信息。
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum FfmpegErrorKind {
+    /// PATH 中找不到 FFmpeg
+    NotFound,
+    /// FFmpeg 存在但无法正常运行
+    NotRunnable,
+    /// 当前 FFmpeg 构建不包含 ebur128 滤波器
+    MissingEbur128,
+    /// `check --full` 的自检失败
+    SelfTestFailed,
+    /// 最先完成的文件全部以同一种错误失败，运行已中止（`--abort-after`）
+    Aborted,
+}
+
+/// 路径错误的具体情况 (Path Error Kind)
+///
+/// 在创建 [`AppError::Path`] 时确定，调用方据此选择建议或界面操作，不需要解析错误信息。
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum PathErrorKind {
+    /// 路径不存在
+    NotFound,
+    /// 需要文件夹，但路径是一个文件
+    NotADirectory,
+    /// 需要音频文件（`file` 子命令），但路径是一个目录
+    IsADirectory,
+    /// 文件的扩展名不是支持的音频格式
+    UnsupportedFormat,
+    /// 路径存在但无法读取（通常是权限问题）
+    Unreadable,
+    /// 路径无法规范化
+    Invalid,
+    /// 无法在输出目录中写入结果文件
+    NotWritable,
+}
+
+/// 扫描结果为空的原因 (Reason for an Empty Scan)
+///
+/// 区分"目录确实为空"、"有文件但没有支持的音频"和"子目录无法读取"，
+/// 以便给出针对性的提示，并让脚本通过退出码区分这些情况。
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum EmptyScanReason {
+    /// 目录中没有任何条目
+    EmptyDirectory,
+    /// 目录中有文件，但没有支持的音频格式
+    NoSupportedAudio {
+        /// 被跳过的非音频文件数量
+        other_files: usize,
+    },
+    /// 部分子目录或文件无法读取（通常是权限问题）
+    Unreadable {
+        /// 无法读取的路径数量
+        unreadable_paths: usize,
+    },
+    /// 找到了音频文件，但全部被扫描过滤条件（如文件大小）排除
+    AllFiltered {
+        /// 被过滤的音频文件数量
+        filtered_files: usize,
+    },
+    /// 目录中只有本程序生成的文件（结果文件、缓存、生成文件清单中的路径等），都已被忽略
+    OnlyGeneratedFiles {
+        /// 被忽略的生成文件和目录数量
+        excluded_files: usize,
+    },
+}
+
+impl EmptyScanReason {
+    /// 该原因对应的进程退出码（7、9、10、12），见 [`AppError::exit_code`]
+    ///
+    /// 只有生成文件的目录与空目录一样没有需要分析的内容，使用相同的退出码。
+    pub fn exit_code(&self) -> u8 {
+        match self {
+            EmptyScanReason::EmptyDirectory | EmptyScanReason::OnlyGeneratedFiles { .. } => 7,
+            EmptyScanReason::NoSupportedAudio { .. } => 9,
+            EmptyScanReason::Unreadable { .. } => 10,
+            EmptyScanReason::AllFiltered { .. } => 12,
+        }
+    }
+}
+
+impl fmt::Display for EmptyScanReason {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            EmptyScanReason::EmptyDirectory => write!(f, "目录为空"),
+            EmptyScanReason::NoSupportedAudio { other_files } => write!(
+                f,
+                "目录中有 {other_files} 个文件，但都不是支持的音频格式"
+            ),
+            EmptyScanReason::Unreadable { unreadable_paths } => write!(
+                f,
+                "有 {unreadable_paths} 个子目录或文件无法读取，请检查权限设置"
+            ),
+            EmptyScanReason::AllFiltered { filtered_files } => write!(
+                f,
+                "找到 {filtered_files} 个音频文件，但都被过滤条件排除了"
+            ),
+            EmptyScanReason::OnlyGeneratedFiles { excluded_files } => write!(
+                f,
+                "目录中只有本程序生成的 {excluded_files} 个文件（结果文件、缓存等），已忽略"
+            ),
+        }
+    }
+}
+
+impl AppError {
+    /// 获取该错误对应的进程退出码
+    ///
+    /// 不同类别的错误使用不同的退出码，便于脚本根据退出码做出反应：
+    ///
+    /// | 错误 | 退出码 |
+    /// |------|--------|
+    /// | `Configuration` | 2 |
+    /// | `Path` | 3 |
+    /// | `Ffmpeg` | 4 |
+    /// | `Io` | 5 |
+    /// | `FileProcessing` | 6 |
+    /// | `NoFilesFound`（目录为空或只有生成的文件） | 7 |
+    /// | `PartiallyCompleted` | 8 |
+    /// | `NoFilesFound`（没有支持的音频） | 9 |
+    /// | `NoFilesFound`（子目录无法读取） | 10 |
+    /// | `ThresholdViolated` | 11 |
+    /// | `NoFilesFound`（全部被过滤） | 12 |
+    /// | `RegressionDetected` | 13 |
+    /// | `Timeout` | 124 |
+    /// | `Interrupted` | 130 |
+    pub fn exit_code(&self) -> u8 {
+        match self {
+            AppError::Configuration(_) => 2,
+            AppError::Path { .. } => 3,
+            AppError::Ffmpeg { .. } => 4,
+            AppError::Io(_) => 5,
+            AppError::FileProcessing(_) => 6,
+            AppError::NoFilesFound { reason, .. } => reason.exit_code(),
+            AppError::PartiallyCompleted { .. } => 8,
+            AppError::ThresholdViolated { .. } => 11,
+            AppError::RegressionDetected { .. } => 13,
+            AppError::Timeout(_) => 124,
+            AppError::Interrupted { .. } => 130,
+        }
+    }
+
+    /// 解决这个错误的建议 (Suggestions)
+    ///
+    /// 路径和 FFmpeg 错误按 [`PathErrorKind`] 和 [`FfmpegErrorKind`] 给出，单个文件处理失败时
+    /// 使用 [`ProcessFileError::suggestions`] 给出的建议；没有建议时为空。
+    ///
+    /// # 示例
+    /// ```
+    /// use lra_calculator_rust::error::AppError;
+    /// use lra_calculator_rust::suggestion::Suggestion;
+    /// use lra_calculator_rust::validate_folder_path;
+    ///
+    /// let error = validate_folder_path(std::path::Path::new("/no/such/folder")).unwrap_err();
+    /// assert!(matches!(error, AppError::Path { .. }));
+    /// assert_eq!(error.suggestions(), vec![Suggestion::CheckPathSpelling, Suggestion::CheckPathMoved]);
+    /// assert!(AppError::Configuration("无效参数".to_string()).suggestions().is_empty());
+    /// ```
+    pub fn suggestions(&self) -> Vec<Suggestion> {
+        match self {
+            AppError::Path { kind, .. } => kind.suggestions(),
+            AppError::Ffmpeg { kind, .. } => kind.suggestions(),
+            AppError::FileProcessing(err) => err.suggestions(),
+            _ => Vec::new(),
+        }
+    }
+}
+
+impl PathErrorKind {
+    /// 这种路径错误的建议
+    fn suggestions(self) -> Vec<Suggestion> {
+        match self {
+            PathErrorKind::NotFound => vec![Suggestion::CheckPathSpelling, Suggestion::CheckPathMoved],
+            PathErrorKind::NotADirectory => vec![Suggestion::SelectFolder],
+            PathErrorKind::IsADirectory => vec![Suggestion::DropFileSubcommand],
+            PathErrorKind::UnsupportedFormat => vec![Suggestion::AddExtension],
+            PathErrorKind::Unreadable => vec![Suggestion::CheckPermissions, Suggestion::CloseOtherPrograms],
+            PathErrorKind::Invalid => vec![Suggestion::CheckPathSpelling],
+            PathErrorKind::NotWritable => vec![Suggestion::UseOutputFlag],
+        }
+    }
+}
+
+impl FfmpegErrorKind {
+    /// 这种 FFmpeg 错误的建议
+    ///
+    /// 中止时错误信息已包含第一个失败文件的错误和关闭检查的方法，不再给出建议。
+    fn suggestions(self) -> Vec<Suggestion> {
+        match self {
+            FfmpegErrorKind::NotFound => vec![Suggestion::InstallFfmpeg],
+            FfmpegErrorKind::NotRunnable => vec![Suggestion::ReinstallFfmpeg],
+            FfmpegErrorKind::MissingEbur128 => vec![Suggestion::InstallFullFfmpegBuild],
+            FfmpegErrorKind::SelfTestFailed => vec![Suggestion::CheckFfmpegVersion],
+            FfmpegErrorKind::Aborted => Vec::new(),
+        }
+    }
 }
 
 impl fmt::Display for AppError {
@@ -148,9 +798,25 @@ impl fmt::Display for AppError {
         match self {
             AppError::Io(err) => write!(f, "输入/输出错误: {err}"),
             AppError::FileProcessing(err) => write!(f, "{err}"),
-            AppError::Ffmpeg(msg) => write!(f, "FFmpeg 错误: {msg}"),
-            AppError::Path(msg) => write!(f, "路径错误: {msg}"),
+            AppError::Ffmpeg { message, .. } => write!(f, "FFmpeg 错误: {message}"),
+            AppError::Path { message, .. } => write!(f, "路径错误: {message}"),
             AppError::Configuration(msg) => write!(f, "配置错误: {msg}"),
+            AppError::NoFilesFound { root, reason } => {
+                write!(f, "在 '{}' 下没有找到要处理的音频文件: {}", root.display(), reason)
+            }
+            AppError::Interrupted { completed, total } => {
+                write!(f, "运行已中断 (已完成 {completed}/{total} 个文件)")
+            }
+            AppError::Timeout(msg) => write!(f, "超时: {msg}"),
+            AppError::PartiallyCompleted { successful, failed } => {
+                write!(f, "运行部分完成: 成功 {successful} 个文件，失败 {failed} 个文件")
+            }
+            AppError::ThresholdViolated { violations } => {
+                write!(f, "阈值检查失败: {violations} 个文件的结果超出允许范围")
+            }
+            AppError::RegressionDetected { regressions } => {
+                write!(f, "回归检测失败: {regressions} 个文件的 LRA 相对基线的变化超过阈值")
+            }
         }
     }
 }
@@ -160,7 +826,15 @@ impl std::error::Error for AppError {
         match self {
             AppError::Io(err) => Some(err),
             AppError::FileProcessing(err) => Some(err),
-            AppError::Ffmpeg(_) | AppError::Path(_) | AppError::Configuration(_) => None,
+            AppError::Ffmpeg { .. }
+            | AppError::Path { .. }
+            | AppError::Configuration(_)
+            | AppError::NoFilesFound { .. }
+            | AppError::Interrupted { .. }
+            | AppError::Timeout(_)
+            | AppError::PartiallyCompleted { .. }
+            | AppError::ThresholdViolated { .. }
+            | AppError::RegressionDetected { .. } => None,
         }
     }
 }
@@ -171,8 +845,132 @@ impl From<std::io::Error> for AppError {
     }
 }
 
+impl From<SelfTestError> for AppError {
+    fn from(err: SelfTestError) -> Self {
+        AppError::Ffmpeg { kind: FfmpegErrorKind::SelfTestFailed, message: err.to_string() }
+    }
+}
+
 impl From<ProcessFileError> for AppError {
     fn from(err: ProcessFileError) -> Self {
         AppError::FileProcessing(err)
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// 测试每个错误变体都有独立的退出码
+    #[test]
+    fn test_app_error_exit_codes_are_distinct() {
+        let errors = vec![
+            AppError::Io(std::io::Error::other("io")),
+            AppError::FileProcessing(ProcessFileError::ffmpeg_error(
+                "a.mp3".to_string(),
+                "失败".to_string(),
+            )),
+            AppError::Ffmpeg { kind: FfmpegErrorKind::NotFound, message: "ffmpeg".to_string() },
+            AppError::Path { kind: PathErrorKind::NotFound, message: "path".to_string() },
+            AppError::Configuration("config".to_string()),
+            AppError::NoFilesFound {
+                root: PathBuf::from("/music"),
+                reason: EmptyScanReason::EmptyDirectory,
+            },
+            AppError::NoFilesFound {
+                root: PathBuf::from("/music"),
+                reason: EmptyScanReason::NoSupportedAudio { other_files: 2 },
+            },
+            AppError::NoFilesFound {
+                root: PathBuf::from("/music"),
+                reason: EmptyScanReason::Unreadable { unreadable_paths: 1 },
+            },
+            AppError::NoFilesFound {
+                root: PathBuf::from("/music"),
+                reason: EmptyScanReason::AllFiltered { filtered_files: 3 },
+            },
+            AppError::Interrupted { completed: 3, total: 10 },
+            AppError::Timeout("2h".to_string()),
+            AppError::PartiallyCompleted { successful: 9, failed: 1 },
+            AppError::ThresholdViolated { violations: 2 },
+            AppError::RegressionDetected { regressions: 1 },
+        ];
+
+        let mut codes: Vec<u8> = errors.iter().map(AppError::exit_code).collect();
+        assert!(codes.iter().all(|&code| code != 0));
+        codes.sort_unstable();
+        codes.dedup();
+        assert_eq!(codes.len(), errors.len());
+    }
+
+    /// 测试新错误变体的显示信息
+    #[test]
+    fn test_app_error_display() {
+        let no_files = AppError::NoFilesFound {
+            root: PathBuf::from("/music"),
+            reason: EmptyScanReason::Unreadable { unreadable_paths: 4 },
+        };
+        assert!(no_files.to_string().contains("/music"));
+        assert!(no_files.to_string().contains("4 个子目录"));
+
+        // 只有生成文件的目录不报告为空目录，但与空目录使用相同的退出码
+        let generated_only = EmptyScanReason::OnlyGeneratedFiles { excluded_files: 2 };
+        assert_eq!(generated_only.to_string(), "目录中只有本程序生成的 2 个文件（结果文件、缓存等），已忽略");
+        assert_eq!(generated_only.exit_code(), EmptyScanReason::EmptyDirectory.exit_code());
+
+        let interrupted = AppError::Interrupted { completed: 3, total: 10 };
+        assert!(interrupted.to_string().contains("3/10"));
+        assert_eq!(interrupted.exit_code(), 130);
+
+        let partial = AppError::PartiallyCompleted { successful: 9, failed: 1 };
+        assert!(partial.to_string().contains("失败 1"));
+        assert!(std::error::Error::source(&partial).is_none());
+    }
+
+    /// 测试自检错误按 LRA 错误类型定位失败阶段
+    #[test]
+    fn test_self_test_stage_from_lra_error() {
+        let spawn = LraCalculationError::Spawn(std::io::Error::other("missing"));
+        assert_eq!(SelfTestStage::from(&spawn), SelfTestStage::Analysis);
+
+        let parse = LraCalculationError::ParseFailed { output_excerpt: String::new() };
+        assert_eq!(SelfTestStage::from(&parse), SelfTestStage::Parsing);
+
+        let err = SelfTestError::new(SelfTestStage::Generation, "lavfi 不可用".to_string());
+        assert!(err.to_string().contains("生成测试音频"));
+        let error = AppError::from(err);
+        assert!(matches!(error, AppError::Ffmpeg { kind: FfmpegErrorKind::SelfTestFailed, .. }));
+        assert_eq!(error.suggestions(), vec![Suggestion::CheckFfmpegVersion]);
+    }
+
+    /// 测试建议按变体和错误的具体情况给出，不改变错误的变体
+    #[test]
+    fn test_app_error_suggestions() {
+        let cases = [
+            (AppError::Ffmpeg { kind: FfmpegErrorKind::NotFound, message: String::new() }, vec![Suggestion::InstallFfmpeg]),
+            (AppError::Ffmpeg { kind: FfmpegErrorKind::NotRunnable, message: String::new() }, vec![Suggestion::ReinstallFfmpeg]),
+            (AppError::Ffmpeg { kind: FfmpegErrorKind::Aborted, message: String::new() }, vec![]),
+            (AppError::Path { kind: PathErrorKind::NotADirectory, message: String::new() }, vec![Suggestion::SelectFolder]),
+            (AppError::Path { kind: PathErrorKind::NotWritable, message: String::new() }, vec![Suggestion::UseOutputFlag]),
+            (AppError::Path { kind: PathErrorKind::UnsupportedFormat, message: String::new() }, vec![Suggestion::AddExtension]),
+            // 建议只由错误的具体情况决定，与错误信息的文本无关
+            (
+                AppError::Path { kind: PathErrorKind::Unreadable, message: "路径 '/a' 不存在".to_string() },
+                vec![Suggestion::CheckPermissions, Suggestion::CloseOtherPrograms],
+            ),
+            (AppError::Configuration("路径 '/a' 不存在".to_string()), vec![]),
+        ];
+        for (error, expected) in cases {
+            assert_eq!(error.suggestions(), expected, "{}", error);
+        }
+
+        // 单个文件的错误带有按错误类型给出的建议
+        let file = AppError::FileProcessing(ProcessFileError::file_access_error("a.flac", "拒绝访问".to_string()));
+        assert_eq!(file.suggestions(), vec![Suggestion::CheckPermissions]);
+        assert!(std::error::Error::source(&file).is_some());
+
+        let spawn = LraCalculationError::Spawn(std::io::Error::other("missing"));
+        assert_eq!(spawn.suggestions(), vec![Suggestion::InstallFfmpeg]);
+        assert!(!spawn.to_string().contains("请确保"));
+    }
+}
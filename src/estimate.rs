@@ -0,0 +1,246 @@
+//! 运行时间预估模块 (Run Estimate Module)
+//!
+//! 开始分析之前，根据待分析文件的数量、总大小和以往运行的平均速度估算需要多长时间，例如
+//! "1,234 个文件 (~68.0 GB)；按历史平均速度 14 个文件/分钟，预计需要 ~1.5 小时"。
+//!
+//! 历史速度由 [`ThroughputHistory`](crate::utils::history::ThroughputHistory) 在每次分析结束后记录；
+//! 没有历史时使用偏保守的 [`DEFAULT_THROUGHPUT`]，并把结果标注为粗略估计。
+//! [`estimate_run`] 是纯函数，不读取文件，也不访问历史记录。
+//!
+//! 大小按 1024 进制换算，与 `--min-size` 等参数一致。
+
+use std::fmt;
+use std::time::Duration;
+
+/// 1 MB 对应的字节数
+const BYTES_PER_MB: f64 = 1024.0 * 1024.0;
+
+/// 没有历史记录时使用的分析速度
+///
+/// 按单核、网络存储上的无损文件取的保守值，宁可高估用时。
+pub const DEFAULT_THROUGHPUT: Throughput = Throughput { files_per_minute: 10.0, mb_per_minute: 200.0 };
+
+/// 分析速度 (Throughput)
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Throughput {
+    /// 每分钟分析的文件数
+    pub files_per_minute: f64,
+    /// 每分钟分析的数据量（MB）
+    pub mb_per_minute: f64,
+}
+
+impl Throughput {
+    /// 由一次运行分析的文件数、总字节数和用时计算速度
+    ///
+    /// # 返回值
+    /// - `Some(Throughput)` - 分析了至少一个文件且用时不为零
+    /// - `None` - 没有可用的数据
+    ///
+    /// # 示例
+    /// ```
+    /// use std::time::Duration;
+    /// use lra_calculator_rust::estimate::Throughput;
+    ///
+    /// let rate = Throughput::from_run(30, 600 * 1024 * 1024, Duration::from_secs(120)).unwrap();
+    /// assert_eq!(rate.files_per_minute, 15.0);
+    /// assert_eq!(rate.mb_per_minute, 300.0);
+    /// ```
+    pub fn from_run(files: usize, bytes: u64, elapsed: Duration) -> Option<Self> {
+        let minutes = elapsed.as_secs_f64() / 60.0;
+        if files == 0 || minutes <= 0.0 {
+            return None;
+        }
+        Some(Self { files_per_minute: files as f64 / minutes, mb_per_minute: bytes as f64 / BYTES_PER_MB / minutes })
+    }
+
+    /// 文件速度是否为有限正数、数据速度是否为有限非负数
+    ///
+    /// 历史上只分析过极小的文件时数据速度可能为零，此时只按文件数估算。
+    fn is_usable(&self) -> bool {
+        self.files_per_minute.is_finite()
+            && self.files_per_minute > 0.0
+            && self.mb_per_minute.is_finite()
+            && self.mb_per_minute >= 0.0
+    }
+}
+
+/// 运行时间预估 (Run Estimate)
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct RunEstimate {
+    /// 待分析的文件数量
+    pub files: usize,
+    /// 待分析文件的总大小（字节）
+    pub total_bytes: u64,
+    /// 估算所用的速度
+    pub throughput: Throughput,
+    /// 预计用时
+    pub duration: Duration,
+    /// 是否基于内置的 [`DEFAULT_THROUGHPUT`]（没有可用的历史速度）
+    pub rough: bool,
+}
+
+/// 估算运行时间 (Estimate Run)
+///
+/// 按文件数和按数据量分别估算，取较长的一个：大量小文件时进程启动开销占主导，
+/// 少量长文件时解码占主导。历史速度缺失或无效时使用 [`DEFAULT_THROUGHPUT`]。
+///
+/// # 参数
+/// - `files` - 待分析的文件数量
+/// - `total_bytes` - 待分析文件的总大小（字节）
+/// - `history` - 以往运行的平均速度
+///
+/// # 返回值
+/// - 预计用时及估算依据
+///
+/// # 示例
+/// ```
+/// use std::time::Duration;
+/// use lra_calculator_rust::estimate::{estimate_run, Throughput};
+///
+/// let history = Throughput { files_per_minute: 14.0, mb_per_minute: 700.0 };
+/// let estimate = estimate_run(1260, 10 * 1024 * 1024 * 1024, Some(history));
+/// assert_eq!(estimate.duration, Duration::from_secs(90 * 60));
+/// assert!(!estimate.rough);
+/// ```
+pub fn estimate_run(files: usize, total_bytes: u64, history: Option<Throughput>) -> RunEstimate {
+    let (throughput, rough) = match history.filter(Throughput::is_usable) {
+        Some(throughput) => (throughput, false),
+        None => (DEFAULT_THROUGHPUT, true),
+    };
+    let by_files = files as f64 / throughput.files_per_minute;
+    let by_size = match throughput.mb_per_minute {
+        rate if rate > 0.0 => total_bytes as f64 / BYTES_PER_MB / rate,
+        _ => 0.0,
+    };
+    let duration = Duration::try_from_secs_f64(by_files.max(by_size) * 60.0).unwrap_or(Duration::MAX);
+    RunEstimate { files, total_bytes, throughput, duration, rough }
+}
+
+impl fmt::Display for RunEstimate {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{} 个文件 (~{})；", group_thousands(self.files), format_size(self.total_bytes))?;
+        if self.rough {
+            write!(f, "没有历史速度，按默认的 {:.0} 个文件/分钟粗略估计", self.throughput.files_per_minute)?;
+        } else {
+            write!(
+                f,
+                "按历史平均速度 {:.0} 个文件/分钟、{:.0} MB/分钟",
+                self.throughput.files_per_minute, self.throughput.mb_per_minute
+            )?;
+        }
+        write!(f, "，预计需要 ~{}", format_estimate_duration(self.duration))
+    }
+}
+
+/// 整数加千位分隔符，如 `1,234`
+fn group_thousands(value: usize) -> String {
+    let digits = value.to_string();
+    let mut grouped = String::new();
+    for (index, digit) in digits.chars().enumerate() {
+        if index > 0 && (digits.len() - index).is_multiple_of(3) {
+            grouped.push(',');
+        }
+        grouped.push(digit);
+    }
+    grouped
+}
+
+/// 字节数格式化为 `68.0 GB`、`512 MB` 这样的形式（1024 进制）
+fn format_size(bytes: u64) -> String {
+    let mb = bytes as f64 / BYTES_PER_MB;
+    match mb {
+        mb if mb >= 1024.0 * 1024.0 => format!("{:.1} TB", mb / 1024.0 / 1024.0),
+        mb if mb >= 1024.0 => format!("{:.1} GB", mb / 1024.0),
+        mb if mb >= 1.0 => format!("{:.0} MB", mb),
+        _ => format!("{} KB", bytes.div_ceil(1024)),
+    }
+}
+
+/// 预计用时格式化为 `1.5 小时`、`25 分钟`、`40 秒` 这样的形式
+fn format_estimate_duration(duration: Duration) -> String {
+    let seconds = duration.as_secs_f64();
+    match seconds {
+        s if s >= 3600.0 => format!("{:.1} 小时", s / 3600.0),
+        s if s >= 60.0 => format!("{:.0} 分钟", s / 60.0),
+        s => format!("{:.0} 秒", s.max(1.0)),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const GB: u64 = 1024 * 1024 * 1024;
+
+    /// 测试按文件数和按数据量估算时取较长的一个
+    #[test]
+    fn test_estimate_takes_slower_rate() {
+        let history = Some(Throughput { files_per_minute: 10.0, mb_per_minute: 100.0 });
+        let cases: [(usize, u64, u64); 5] = [
+            // (文件数, 总字节数, 预计秒数)
+            (0, 0, 0),
+            (10, 0, 60),
+            (10, 1024 * 1024 * 1000, 600),
+            (600, 1024 * 1024 * 100, 3600),
+            (1, GB, 614),
+        ];
+        for (files, bytes, expected_secs) in cases {
+            let estimate = estimate_run(files, bytes, history);
+            assert_eq!(estimate.duration.as_secs(), expected_secs, "{} 个文件、{} 字节", files, bytes);
+            assert!(!estimate.rough);
+        }
+    }
+
+    /// 测试没有可用历史速度时使用默认速度并标注为粗略估计
+    #[test]
+    fn test_estimate_falls_back_to_default() {
+        for history in [
+            None,
+            Some(Throughput { files_per_minute: 0.0, mb_per_minute: 100.0 }),
+            Some(Throughput { files_per_minute: f64::NAN, mb_per_minute: 100.0 }),
+            Some(Throughput { files_per_minute: 10.0, mb_per_minute: f64::INFINITY }),
+            Some(Throughput { files_per_minute: 10.0, mb_per_minute: -1.0 }),
+        ] {
+            let estimate = estimate_run(100, 0, history);
+            assert!(estimate.rough);
+            assert_eq!(estimate.throughput, DEFAULT_THROUGHPUT);
+            assert_eq!(estimate.duration, Duration::from_secs(600));
+        }
+
+        // 数据速度为零时只按文件数估算
+        let files_only = estimate_run(100, GB, Some(Throughput { files_per_minute: 20.0, mb_per_minute: 0.0 }));
+        assert!(!files_only.rough);
+        assert_eq!(files_only.duration, Duration::from_secs(300));
+    }
+
+    /// 测试由单次运行计算速度
+    #[test]
+    fn test_throughput_from_run() {
+        assert_eq!(Throughput::from_run(0, 100, Duration::from_secs(60)), None);
+        assert_eq!(Throughput::from_run(5, 100, Duration::ZERO), None);
+        let rate = Throughput::from_run(5, 50 * 1024 * 1024, Duration::from_secs(30)).unwrap();
+        assert_eq!(rate, Throughput { files_per_minute: 10.0, mb_per_minute: 100.0 });
+    }
+
+    /// 测试预估信息的格式
+    #[test]
+    fn test_estimate_display() {
+        let history = Some(Throughput { files_per_minute: 14.0, mb_per_minute: 1000.0 });
+        assert_eq!(
+            estimate_run(1260, 68 * GB, history).to_string(),
+            "1,260 个文件 (~68.0 GB)；按历史平均速度 14 个文件/分钟、1000 MB/分钟，预计需要 ~1.5 小时"
+        );
+        assert_eq!(
+            estimate_run(5, 300 * 1024 * 1024, None).to_string(),
+            "5 个文件 (~300 MB)；没有历史速度，按默认的 10 个文件/分钟粗略估计，预计需要 ~2 分钟"
+        );
+
+        assert_eq!(group_thousands(0), "0");
+        assert_eq!(group_thousands(999), "999");
+        assert_eq!(group_thousands(1234567), "1,234,567");
+        assert_eq!(format_size(0), "0 KB");
+        assert_eq!(format_size(1500), "2 KB");
+        assert_eq!(format_size(3 * 1024 * GB), "3.0 TB");
+        assert_eq!(format_estimate_duration(Duration::from_millis(200)), "1 秒");
+    }
+}
@@ -0,0 +1,752 @@
+//! 进度事件模块 (Progress Events Module)
+//!
+//! 扫描和处理层通过 [`EventSink`] 发出 [`RunEvent`]，这是运行进度的唯一来源：
+//!
+//! - `ConsoleSink` 输出人类可读的进度（命令行默认的控制台输出，`cli` 特性）
+//! - `--porcelain ndjson` 把事件转换为 [`ProgressEvent`] 逐行序列化到 stdout
+//! - `--porcelain progress` 由 [`ProgressLineFormatter`] 把 [`ProgressEvent`] 转换为简单的文本记录
+//! - [`BufferSink`] 按顺序记下所有事件，便于在测试中断言整个流程
+//!
+//! [`ProgressEvent`] 是 [`RunEvent`] 面向外部程序的稳定序列化形式，
+//! 由 [`RunEvent::to_progress_event`] 转换得到。
+
+use std::fmt;
+use std::path::PathBuf;
+use std::sync::Mutex;
+#[cfg(feature = "cli")]
+use std::thread;
+
+use crate::error::FileErrorType;
+use crate::file_key::FileKey;
+use crate::hash::ContentHash;
+use crate::precision::LraValue;
+use crate::processor::{FileMeasurement, FileOutcome, ProcessingStats, SkipReason};
+use crate::progress::AnalysisProgress;
+
+/// 文件在本次运行中的位置 (File Position)
+///
+/// 显示为 `3/120`；边扫描边处理时扫描尚未结束、总数仍在增长，显示为 `3/120+`。
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct FilePosition {
+    /// 开始分析的序号，从 1 开始
+    pub current: usize,
+    /// 到目前为止已知的文件总数
+    pub total: usize,
+    /// 总数是否已经确定
+    pub total_final: bool,
+}
+
+impl fmt::Display for FilePosition {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}/{}", self.current, self.total)?;
+        if !self.total_final {
+            write!(f, "+")?;
+        }
+        Ok(())
+    }
+}
+
+/// 运行事件 (Run Event)
+///
+/// 扫描和处理层产生的全部进度信息。文件相关的事件在工作线程中发出，
+/// 同一个文件的 `FileStarted`、`FileProgress`、`FileFinished` 按顺序发出，不同文件之间可能交错。
+#[derive(Debug, Clone, PartialEq)]
+pub enum RunEvent {
+    /// 开始扫描
+    ScanStarted {
+        /// 扫描根目录
+        root: PathBuf,
+    },
+    /// 扫描进行中，每发现 [`crate::processor::SCAN_PROGRESS_INTERVAL`] 个音频文件报告一次
+    ScanProgress {
+        /// 到目前为止发现的音频文件数量
+        found: usize,
+    },
+    /// 扫描结束，待分析的文件已经确定
+    ScanFinished {
+        /// 需要分析的文件数量（不含复用缓存结果和未被选中的文件）
+        total: usize,
+    },
+    /// 开始分析一个文件
+    FileStarted {
+        /// 显示路径
+        path: FileKey,
+        /// 文件的位置
+        position: FilePosition,
+    },
+    /// 长文件仍在分析：运行超过 [`crate::progress::LONG_FILE_THRESHOLD`] 后定期报告
+    FileProgress {
+        /// 显示路径
+        path: FileKey,
+        /// 文件的位置
+        position: FilePosition,
+        /// FFmpeg 报告的进度
+        progress: AnalysisProgress,
+    },
+    /// 一个文件分析结束（运行取消后未开始的文件不产生事件）
+    FileFinished {
+        /// 文件的位置
+        position: FilePosition,
+        /// 分析结果：成功、失败或跳过（如云端占位文件）
+        outcome: Box<FileOutcome>,
+    },
+    /// 连续的断连类失败超过阈值，暂停开始新的文件（见 [`crate::breaker`]）
+    OutagePaused {
+        /// 触发暂停的连续失败数量
+        consecutive_failures: usize,
+    },
+    /// 扫描根目录恢复访问，继续处理
+    OutageResumed {
+        /// 这次暂停的时长（秒）
+        paused_secs: f64,
+    },
+    /// 累计暂停时间超过上限，放弃剩下的文件
+    OutageGaveUp {
+        /// 这次暂停的时长（秒）
+        paused_secs: f64,
+    },
+    /// 断连期间失败的文件重新排队分析，之后会再次产生 `FileStarted` 和 `FileFinished`
+    Requeued {
+        /// 重新分析的文件数量
+        files: usize,
+    },
+    /// 全部结果的统计已经生成
+    StatsReady {
+        /// 处理统计
+        stats: Box<ProcessingStats>,
+    },
+    /// 运行结束
+    RunFinished {
+        /// 成功处理的文件数量
+        successful: usize,
+        /// 处理失败的文件数量
+        failed: usize,
+        /// 结果文件路径
+        results_file: String,
+        /// 运行总用时（秒）
+        elapsed_secs: f64,
+    },
+}
+
+impl RunEvent {
+    /// 转换为对外的 [`ProgressEvent`] (To Progress Event)
+    ///
+    /// # 返回值
+    /// - `Some(ProgressEvent)` - 对应的稳定事件
+    /// - `None` - 没有对应的稳定事件（开始扫描、开始分析和统计）
+    ///
+    /// # 示例
+    /// ```
+    /// use lra_calculator_rust::events::{ProgressEvent, RunEvent};
+    ///
+    /// let event = RunEvent::ScanFinished { total: 12 };
+    /// assert_eq!(event.to_progress_event(), Some(ProgressEvent::ScanDone { files: 12 }));
+    /// assert_eq!(RunEvent::ScanStarted { root: "/music".into() }.to_progress_event(), None);
+    /// ```
+    pub fn to_progress_event(&self) -> Option<ProgressEvent> {
+        match self {
+            RunEvent::ScanStarted { .. } | RunEvent::FileStarted { .. } | RunEvent::StatsReady { .. } => None,
+            RunEvent::ScanProgress { found } => Some(ProgressEvent::ScanProgress { discovered: *found }),
+            RunEvent::ScanFinished { total } => Some(ProgressEvent::ScanDone { files: *total }),
+            RunEvent::FileProgress { path, progress, .. } => Some(ProgressEvent::FileProgress {
+                path: path.clone(),
+                elapsed_secs: progress.elapsed.as_secs(),
+                percent: progress.percent,
+            }),
+            RunEvent::FileFinished { outcome, .. } => match outcome.as_ref() {
+                FileOutcome::Success(measurement) => Some(ProgressEvent::file_done(measurement)),
+                FileOutcome::Failed(error) => Some(ProgressEvent::FileFailed {
+                    path: error.file_path.clone(),
+                    error_type: error.error_type,
+                    message: error.message.clone(),
+                }),
+                FileOutcome::Skipped { path, reason } => {
+                    Some(ProgressEvent::FileSkipped { path: path.clone(), reason: *reason })
+                }
+            },
+            RunEvent::OutagePaused { consecutive_failures } => {
+                Some(ProgressEvent::Paused { consecutive_failures: *consecutive_failures })
+            }
+            RunEvent::OutageResumed { paused_secs } => Some(ProgressEvent::Resumed { paused_secs: *paused_secs }),
+            RunEvent::OutageGaveUp { paused_secs } => Some(ProgressEvent::GaveUp { paused_secs: *paused_secs }),
+            RunEvent::Requeued { files } => Some(ProgressEvent::Requeued { files: *files }),
+            RunEvent::RunFinished { successful, failed, results_file, elapsed_secs } => Some(ProgressEvent::Summary {
+                successful: *successful,
+                failed: *failed,
+                results_file: results_file.clone(),
+                elapsed_secs: *elapsed_secs,
+            }),
+        }
+    }
+}
+
+/// 事件接收者 (Event Sink)
+///
+/// 文件相关的事件在工作线程中并发发出，实现应尽快返回；需要输出时自行加锁。
+/// 闭包 `Fn(&RunEvent) + Send + Sync` 自动实现该 trait。
+pub trait EventSink: Send + Sync {
+    /// 接收一个事件
+    fn emit(&self, event: &RunEvent);
+}
+
+impl<F> EventSink for F
+where
+    F: Fn(&RunEvent) + Send + Sync,
+{
+    fn emit(&self, event: &RunEvent) {
+        self(event)
+    }
+}
+
+/// 控制台输出 (Console Sink)
+///
+/// 把事件输出为人类可读的进度信息（经过 [`crate::console`]，`--quiet` 等设置同样生效）。
+/// 文件相关的行带有工作线程 ID，便于调试并发问题。
+#[cfg(feature = "cli")]
+#[derive(Debug, Clone, Copy, Default)]
+pub struct ConsoleSink;
+
+#[cfg(feature = "cli")]
+impl EventSink for ConsoleSink {
+    fn emit(&self, event: &RunEvent) {
+        match event {
+            RunEvent::ScanStarted { root } => human_println!("🔍 正在递归扫描文件夹: {}", root.display()),
+            RunEvent::ScanProgress { found } => human_println!("🔍 已发现 {} 个音频文件...", found),
+            RunEvent::ScanFinished { total } => human_println!("✅ 扫描完成，发现 {} 个音频文件待处理", total),
+            RunEvent::FileStarted { path, position } => {
+                human_println!("  [线程 {:?}] ({}) 开始分析: {}", thread::current().id(), position, path)
+            }
+            RunEvent::FileProgress { path, position, progress } => human_println!(
+                "    [线程 {:?}] ({}) ⏳ 仍在分析: {} → {}",
+                thread::current().id(),
+                position,
+                path,
+                progress
+            ),
+            RunEvent::FileFinished { position, outcome } => match outcome.as_ref() {
+                FileOutcome::Success(measurement) => {
+                    human_println!(
+                        "    [线程 {:?}] ({}) ✓ 分析成功: {} → LRA: {} LU",
+                        thread::current().id(),
+                        position,
+                        measurement.path,
+                        LraValue(measurement.lra)
+                    );
+                    for warning in &measurement.analysis_warnings {
+                        human_eprintln!("⚠️  {}: {}", measurement.path, warning);
+                    }
+                }
+                FileOutcome::Failed(error) => human_println!(
+                    "    [线程 {:?}] ({}) ✗ 分析失败: {} → {}",
+                    thread::current().id(),
+                    position,
+                    error.file_path,
+                    error.message
+                ),
+                FileOutcome::Skipped { path, reason } => human_println!(
+                    "    [线程 {:?}] ({}) ⏭️  已跳过: {} → {}",
+                    thread::current().id(),
+                    position,
+                    path,
+                    reason.description()
+                ),
+            },
+            RunEvent::OutagePaused { consecutive_failures } => human_eprintln!(
+                "⚠️  连续 {} 个文件无法访问，可能是网络共享断开，暂停处理并等待扫描根目录恢复...",
+                consecutive_failures
+            ),
+            RunEvent::OutageResumed { paused_secs } => {
+                human_println!("✅ 扫描根目录已恢复访问（暂停 {:.0} 秒），继续处理", paused_secs)
+            }
+            RunEvent::OutageGaveUp { paused_secs } => {
+                human_eprintln!("❌ 扫描根目录在 {:.0} 秒内没有恢复，超出暂停时间上限，放弃剩下的文件", paused_secs)
+            }
+            RunEvent::Requeued { files } => human_println!("🔁 重新分析断连期间失败的 {} 个文件", files),
+            RunEvent::StatsReady { stats } => crate::processor::display_processing_stats(stats),
+            _ => {}
+        }
+    }
+}
+
+/// 不接受接收者的便捷函数使用的默认接收者
+///
+/// 启用 `cli` 特性时是 [`ConsoleSink`]；否则库不输出任何内容，事件被丢弃。
+#[cfg(feature = "cli")]
+pub(crate) type DefaultSink = ConsoleSink;
+
+/// 不接受接收者的便捷函数使用的默认接收者
+///
+/// 启用 `cli` 特性时是 `ConsoleSink`；否则库不输出任何内容，事件被丢弃。
+#[cfg(not(feature = "cli"))]
+pub(crate) type DefaultSink = DiscardSink;
+
+/// 丢弃所有事件的接收者
+#[cfg(not(feature = "cli"))]
+#[derive(Debug, Clone, Copy, Default)]
+pub(crate) struct DiscardSink;
+
+#[cfg(not(feature = "cli"))]
+impl EventSink for DiscardSink {
+    fn emit(&self, _event: &RunEvent) {}
+}
+
+/// 缓冲事件 (Buffer Sink)
+///
+/// 按收到的顺序记下所有事件，用于测试和需要事后检查事件的调用方。
+///
+/// # 示例
+/// ```
+/// use lra_calculator_rust::events::{BufferSink, EventSink, RunEvent};
+///
+/// let sink = BufferSink::new();
+/// sink.emit(&RunEvent::ScanFinished { total: 0 });
+/// assert_eq!(sink.events(), [RunEvent::ScanFinished { total: 0 }]);
+/// ```
+#[derive(Debug, Default)]
+pub struct BufferSink {
+    events: Mutex<Vec<RunEvent>>,
+}
+
+impl BufferSink {
+    /// 创建空的缓冲
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// 到目前为止收到的事件
+    pub fn events(&self) -> Vec<RunEvent> {
+        self.events.lock().unwrap_or_else(|poisoned| poisoned.into_inner()).clone()
+    }
+
+    /// 取出全部事件
+    pub fn into_events(self) -> Vec<RunEvent> {
+        self.events.into_inner().unwrap_or_else(|poisoned| poisoned.into_inner())
+    }
+}
+
+impl EventSink for BufferSink {
+    fn emit(&self, event: &RunEvent) {
+        self.events.lock().unwrap_or_else(|poisoned| poisoned.into_inner()).push(event.clone());
+    }
+}
+
+/// 进度事件 (Progress Event)
+///
+/// ## NDJSON 格式
+///
+/// 每个事件序列化为一行 JSON 对象，`event` 字段标识事件类型：
+///
+/// ```text
+/// {"event":"scan_progress","discovered":1000}
+/// {"event":"scan_done","files":1234}
+/// {"event":"file_progress","path":"Live/set.flac","elapsed_secs":40,"percent":23.5}
+/// {"event":"file_done","path":"Album/01.flac","lra":12.3}
+/// {"event":"file_failed","path":"bad.mp3","error_type":"ffmpeg_execution","message":"..."}
+/// {"event":"file_skipped","path":"Cloud/song.flac","reason":"cloud_placeholder"}
+/// {"event":"paused","consecutive_failures":11}
+/// {"event":"resumed","paused_secs":35.2}
+/// {"event":"requeued","files":11}
+/// {"event":"summary","successful":1233,"failed":1,"results_file":"/music/lra_results.txt","elapsed_secs":95.4}
+/// ```
+///
+/// ## 稳定性约定
+///
+/// 该格式面向外部程序，事件名和已有字段的名称、含义保持不变；
+/// 以后只会新增事件类型或追加字段，消费方应忽略不认识的事件和字段。
+/// `error_type` 的取值与 [`FileErrorType`] 的序列化形式一致，`reason` 的取值与 [`SkipReason`] 的一致。
+#[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[cfg_attr(feature = "serde", serde(tag = "event", rename_all = "snake_case"))]
+pub enum ProgressEvent {
+    /// 扫描进行中，定期报告已发现的音频文件数量
+    ScanProgress {
+        /// 到目前为止发现的音频文件数量
+        discovered: usize,
+    },
+    /// 扫描完成
+    ScanDone {
+        /// 待处理的音频文件数量
+        files: usize,
+    },
+    /// 长文件仍在分析：运行超过 [`crate::progress::LONG_FILE_THRESHOLD`] 后定期报告
+    FileProgress {
+        /// 相对于扫描根目录的显示路径
+        path: FileKey,
+        /// FFmpeg 已经运行的秒数
+        elapsed_secs: u64,
+        /// 完成百分比；文件总时长未知时省略
+        #[cfg_attr(feature = "serde", serde(default, skip_serializing_if = "Option::is_none"))]
+        percent: Option<f64>,
+    },
+    /// 单个文件分析成功
+    FileDone {
+        /// 相对于扫描根目录的显示路径
+        path: FileKey,
+        /// LRA 值（LU），序列化时按 `--precision` 舍入（见 [`crate::precision`]）
+        #[cfg_attr(feature = "serde", serde(serialize_with = "crate::precision::serialize_rounded"))]
+        lra: f64,
+        /// 文件内容的摘要（`--hash`），形如 `xxh3:2d06800538d394c2`；未启用或计算失败时省略
+        #[cfg_attr(feature = "serde", serde(default, skip_serializing_if = "Option::is_none"))]
+        content_hash: Option<ContentHash>,
+        /// LRA 下限（LUFS）；FFmpeg 没有输出或结果来自旧版缓存时省略
+        #[cfg_attr(feature = "serde", serde(default, skip_serializing_if = "Option::is_none"))]
+        lra_low_lufs: Option<f64>,
+        /// LRA 上限（LUFS）；同上
+        #[cfg_attr(feature = "serde", serde(default, skip_serializing_if = "Option::is_none"))]
+        lra_high_lufs: Option<f64>,
+        /// 综合响度的相对门限（LUFS）；同上
+        #[cfg_attr(feature = "serde", serde(default, skip_serializing_if = "Option::is_none"))]
+        threshold_lufs: Option<f64>,
+    },
+    /// 单个文件分析失败
+    FileFailed {
+        /// 相对于扫描根目录的显示路径
+        path: FileKey,
+        /// 错误类型
+        error_type: FileErrorType,
+        /// 错误描述
+        message: String,
+    },
+    /// 单个文件没有分析就跳过（不算失败）
+    FileSkipped {
+        /// 相对于扫描根目录的显示路径
+        path: FileKey,
+        /// 跳过的原因
+        reason: SkipReason,
+    },
+    /// 网络共享断开，暂停开始新的文件
+    Paused {
+        /// 触发暂停的连续失败数量
+        consecutive_failures: usize,
+    },
+    /// 扫描根目录恢复访问，继续处理
+    Resumed {
+        /// 这次暂停的时长（秒）
+        paused_secs: f64,
+    },
+    /// 超出暂停时间上限，剩下的文件不再分析
+    GaveUp {
+        /// 这次暂停的时长（秒）
+        paused_secs: f64,
+    },
+    /// 断连期间失败的文件重新排队；这些文件之前已经报告过 `file_failed`，之后会再次报告结果
+    Requeued {
+        /// 重新分析的文件数量
+        files: usize,
+    },
+    /// 运行结束时的汇总
+    Summary {
+        /// 成功处理的文件数量
+        successful: usize,
+        /// 处理失败的文件数量
+        failed: usize,
+        /// 结果文件路径
+        results_file: String,
+        /// 运行总用时（秒）
+        #[cfg_attr(feature = "serde", serde(default))]
+        elapsed_secs: f64,
+    },
+}
+
+impl ProgressEvent {
+    /// 由成功的测量结果创建 [`ProgressEvent::FileDone`] 事件
+    pub fn file_done(measurement: &FileMeasurement) -> Self {
+        Self::FileDone {
+            path: measurement.path.clone(),
+            lra: measurement.lra,
+            content_hash: measurement.content_hash,
+            lra_low_lufs: measurement.lra_low_lufs,
+            lra_high_lufs: measurement.lra_high_lufs,
+            threshold_lufs: measurement.threshold_lufs,
+        }
+    }
+
+    /// 序列化为单行 JSON（不含换行符）
+    ///
+    /// # 返回值
+    /// - `Ok(String)` - 单行 JSON 字符串
+    /// - `Err(serde_json::Error)` - 序列化失败
+    #[cfg(feature = "serde")]
+    pub fn to_json_line(&self) -> Result<String, serde_json::Error> {
+        serde_json::to_string(self)
+    }
+}
+
+/// `--porcelain progress` 的逐行格式化器 (Progress Line Formatter)
+///
+/// 把进度事件转换为空格分隔的文本记录，供只能逐行匹配控制台输出的 CI 脚本使用。
+///
+/// ## 语法
+///
+/// ```text
+/// record   = progress | ok | fail | skip | paused | resumed | gaveup | requeued | done
+/// progress = "PROGRESS" SP <done> SP <total>
+/// ok       = "OK" SP <lra> SP <path>
+/// fail     = "FAIL" SP <error_type> SP <path>
+/// skip     = "SKIP" SP <reason> SP <path>
+/// paused   = "PAUSED" SP <failures>
+/// resumed  = "RESUMED" SP <seconds>
+/// gaveup   = "GAVEUP" SP <seconds>
+/// requeued = "REQUEUED" SP <files>
+/// done     = "DONE" SP <succeeded> SP <failed> SP <seconds>
+/// ```
+///
+/// - `<done>`、`<succeeded>`、`<failed>` 为非负整数；`<done>` 包括成功、失败和跳过的文件
+/// - `<total>` 为需要分析的文件数量（不含命中缓存的文件）；流水线模式扫描结束之前为 `?`
+/// - `<lra>` 为十进制数，小数位数由 `--precision` 决定（默认一位，如 `12.3`）；
+///   `<seconds>` 保留一位小数
+/// - `<error_type>` 取值与 NDJSON 的 `error_type` 相同，如 `ffmpeg_execution`；
+///   `<reason>` 取值与 NDJSON 的 `reason` 相同，如 `cloud_placeholder`
+/// - `<path>` 是行内剩余的全部内容（可以包含空格），换行符和回车符转义为 `\n`、`\r`
+///
+/// 扫描结束时输出一条 `PROGRESS 0 <total>`（流水线模式下为已完成数量），之后每个
+/// `OK`、`FAIL` 或 `SKIP` 记录后面紧跟一条 `PROGRESS`，运行结束时输出一条 `DONE`。
+/// 网络共享断开时输出 `PAUSED`，恢复时输出 `RESUMED`，放弃时输出 `GAVEUP`；
+/// 断连期间失败的文件重新排队时输出 `REQUEUED`，`<done>` 相应减少，随后的 `PROGRESS` 反映这一点，
+/// 这些文件会再次产生 `OK` 或 `FAIL`。
+/// 与 NDJSON 一样，以后只会新增记录类型，消费方应忽略不认识的行。
+///
+/// # 示例
+/// ```
+/// use lra_calculator_rust::events::{ProgressEvent, ProgressLineFormatter};
+/// use lra_calculator_rust::processor::FileMeasurement;
+///
+/// let mut formatter = ProgressLineFormatter::default();
+/// assert_eq!(formatter.lines(&ProgressEvent::ScanDone { files: 2 }), ["PROGRESS 0 2"]);
+/// let done = ProgressEvent::file_done(&FileMeasurement::new("Album/01 Intro.flac", 12.34));
+/// assert_eq!(formatter.lines(&done), ["OK 12.3 Album/01 Intro.flac", "PROGRESS 1 2"]);
+/// ```
+#[derive(Debug, Clone)]
+pub struct ProgressLineFormatter {
+    /// 已完成（成功、失败或跳过）的文件数量
+    completed: usize,
+    /// 需要分析的文件总数；扫描结束之前未知
+    total: Option<usize>,
+}
+
+impl ProgressLineFormatter {
+    /// 创建格式化器（可用于 `static` 初始化）
+    pub const fn new() -> Self {
+        Self { completed: 0, total: None }
+    }
+
+    /// 将一个事件转换为零到多条记录（不含换行符）
+    ///
+    /// # 参数
+    /// - `event` - 进度事件
+    ///
+    /// # 返回值
+    /// - 按顺序输出的记录；扫描进度和长文件进度不产生记录
+    pub fn lines(&mut self, event: &ProgressEvent) -> Vec<String> {
+        match event {
+            ProgressEvent::ScanProgress { .. } | ProgressEvent::FileProgress { .. } => Vec::new(),
+            ProgressEvent::ScanDone { files } => {
+                self.total = Some(*files);
+                vec![self.progress_line()]
+            }
+            ProgressEvent::FileDone { path, lra, .. } => {
+                self.completed += 1;
+                vec![format!("OK {} {}", LraValue(*lra), escape_path(path.as_str())), self.progress_line()]
+            }
+            ProgressEvent::FileFailed { path, error_type, .. } => {
+                self.completed += 1;
+                vec![format!("FAIL {} {}", error_type.as_str(), escape_path(path.as_str())), self.progress_line()]
+            }
+            ProgressEvent::FileSkipped { path, reason } => {
+                self.completed += 1;
+                vec![format!("SKIP {} {}", reason.as_str(), escape_path(path.as_str())), self.progress_line()]
+            }
+            ProgressEvent::Paused { consecutive_failures } => vec![format!("PAUSED {}", consecutive_failures)],
+            ProgressEvent::Resumed { paused_secs } => vec![format!("RESUMED {:.1}", paused_secs)],
+            ProgressEvent::GaveUp { paused_secs } => vec![format!("GAVEUP {:.1}", paused_secs)],
+            ProgressEvent::Requeued { files } => {
+                self.completed = self.completed.saturating_sub(*files);
+                vec![format!("REQUEUED {}", files), self.progress_line()]
+            }
+            ProgressEvent::Summary { successful, failed, elapsed_secs, .. } => {
+                vec![format!("DONE {} {} {:.1}", successful, failed, elapsed_secs)]
+            }
+        }
+    }
+
+    fn progress_line(&self) -> String {
+        match self.total {
+            Some(total) => format!("PROGRESS {} {}", self.completed, total),
+            None => format!("PROGRESS {} ?", self.completed),
+        }
+    }
+}
+
+impl Default for ProgressLineFormatter {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// 转义路径中会破坏逐行格式的字符
+fn escape_path(path: &str) -> String {
+    path.replace('\n', "\\n").replace('\r', "\\r")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::error::ProcessFileError;
+    use std::time::Duration;
+
+    /// 测试运行事件到稳定事件的转换和位置的显示
+    #[test]
+    fn test_run_event_to_progress_event() {
+        let position = FilePosition { current: 3, total: 120, total_final: true };
+        assert_eq!(position.to_string(), "3/120");
+        assert_eq!(FilePosition { total_final: false, ..position }.to_string(), "3/120+");
+
+        let started = RunEvent::FileStarted { path: "a.flac".into(), position };
+        assert_eq!(started.to_progress_event(), None);
+        let progress = RunEvent::FileProgress {
+            path: "a.flac".into(),
+            position,
+            progress: AnalysisProgress { elapsed: Duration::from_millis(40_900), percent: Some(23.5) },
+        };
+        assert_eq!(
+            progress.to_progress_event(),
+            Some(ProgressEvent::FileProgress { path: "a.flac".into(), elapsed_secs: 40, percent: Some(23.5) })
+        );
+        let measurement = FileMeasurement::new("a.flac", 7.2);
+        let done = RunEvent::FileFinished { position, outcome: Box::new(FileOutcome::Success(measurement.clone())) };
+        assert_eq!(done.to_progress_event(), Some(ProgressEvent::file_done(&measurement)));
+        let error = ProcessFileError::new("b.mp3", "损坏".to_string(), FileErrorType::FfmpegExecution);
+        let failed = RunEvent::FileFinished { position, outcome: Box::new(FileOutcome::Failed(error)) };
+        assert!(matches!(
+            failed.to_progress_event(),
+            Some(ProgressEvent::FileFailed { error_type: FileErrorType::FfmpegExecution, .. })
+        ));
+        let skipped = RunEvent::FileFinished {
+            position,
+            outcome: Box::new(FileOutcome::Skipped { path: "c.flac".into(), reason: SkipReason::CloudPlaceholder }),
+        };
+        assert_eq!(
+            skipped.to_progress_event(),
+            Some(ProgressEvent::FileSkipped { path: "c.flac".into(), reason: SkipReason::CloudPlaceholder })
+        );
+        let stats = RunEvent::StatsReady { stats: Box::default() };
+        assert_eq!(stats.to_progress_event(), None);
+    }
+
+    /// 测试 progress 记录的格式与文档中的语法一致
+    #[test]
+    fn test_progress_lines() {
+        let mut formatter = ProgressLineFormatter::default();
+        let failed = ProgressEvent::FileFailed {
+            path: "bad\nname.mp3".into(),
+            error_type: FileErrorType::FfmpegExecution,
+            message: "损坏".to_string(),
+        };
+        // 流水线模式下扫描结束之前总数未知
+        assert_eq!(formatter.lines(&failed), ["FAIL ffmpeg_execution bad\\nname.mp3", "PROGRESS 1 ?"]);
+        assert!(formatter.lines(&ProgressEvent::ScanProgress { discovered: 1000 }).is_empty());
+        assert_eq!(formatter.lines(&ProgressEvent::ScanDone { files: 3 }), ["PROGRESS 1 3"]);
+        let progress = ProgressEvent::FileProgress { path: "live.flac".into(), elapsed_secs: 40, percent: None };
+        assert!(formatter.lines(&progress).is_empty());
+        let done = ProgressEvent::file_done(&FileMeasurement::new("A B/01.flac", 7.26));
+        assert_eq!(formatter.lines(&done), ["OK 7.3 A B/01.flac", "PROGRESS 2 3"]);
+        let skipped = ProgressEvent::FileSkipped { path: "Cloud/c.flac".into(), reason: SkipReason::ICloudStub };
+        assert_eq!(formatter.lines(&skipped), ["SKIP icloud_stub Cloud/c.flac", "PROGRESS 3 3"]);
+
+        // 断连期间失败的文件重新排队，完成数量相应减少
+        assert_eq!(formatter.lines(&ProgressEvent::Paused { consecutive_failures: 11 }), ["PAUSED 11"]);
+        assert_eq!(formatter.lines(&ProgressEvent::Resumed { paused_secs: 35.24 }), ["RESUMED 35.2"]);
+        assert_eq!(formatter.lines(&ProgressEvent::Requeued { files: 1 }), ["REQUEUED 1", "PROGRESS 2 3"]);
+        assert_eq!(formatter.lines(&ProgressEvent::GaveUp { paused_secs: 600.0 }), ["GAVEUP 600.0"]);
+
+        let summary = ProgressEvent::Summary {
+            successful: 2,
+            failed: 1,
+            results_file: "/music/lra_results.txt".to_string(),
+            elapsed_secs: 95.44,
+        };
+        assert_eq!(formatter.lines(&summary), ["DONE 2 1 95.4"]);
+    }
+}
+
+#[cfg(all(test, feature = "serde"))]
+mod serde_tests {
+    use super::*;
+
+    /// 测试事件序列化格式与文档约定一致
+    #[test]
+    fn test_event_json_schema() {
+        let scan = ProgressEvent::ScanDone { files: 1234 };
+        assert_eq!(scan.to_json_line().unwrap(), r#"{"event":"scan_done","files":1234}"#);
+
+        let done = ProgressEvent::file_done(&FileMeasurement::new("a/b.flac", 12.3));
+        assert_eq!(
+            done.to_json_line().unwrap(),
+            r#"{"event":"file_done","path":"a/b.flac","lra":12.3}"#
+        );
+        let hashed = ProgressEvent::file_done(
+            &FileMeasurement::new("a/b.flac", 12.3).with_content_hash(Some(ContentHash::Xxh3(0x2d06800538d394c2))),
+        );
+        assert_eq!(
+            hashed.to_json_line().unwrap(),
+            r#"{"event":"file_done","path":"a/b.flac","lra":12.3,"content_hash":"xxh3:2d06800538d394c2"}"#
+        );
+        let bounds = ProgressEvent::file_done(
+            &FileMeasurement::new("a/b.flac", 6.4).with_range_bounds(Some(-23.5), Some(-17.1), Some(-29.8)),
+        );
+        assert_eq!(
+            bounds.to_json_line().unwrap(),
+            r#"{"event":"file_done","path":"a/b.flac","lra":6.4,"lra_low_lufs":-23.5,"lra_high_lufs":-17.1,"threshold_lufs":-29.8}"#
+        );
+
+        let progress = ProgressEvent::FileProgress { path: "live.flac".into(), elapsed_secs: 40, percent: Some(23.5) };
+        assert_eq!(
+            progress.to_json_line().unwrap(),
+            r#"{"event":"file_progress","path":"live.flac","elapsed_secs":40,"percent":23.5}"#
+        );
+        let unknown = ProgressEvent::FileProgress { path: "live.flac".into(), elapsed_secs: 40, percent: None };
+        assert_eq!(unknown.to_json_line().unwrap(), r#"{"event":"file_progress","path":"live.flac","elapsed_secs":40}"#);
+
+        let failed = ProgressEvent::FileFailed {
+            path: "bad.mp3".into(),
+            error_type: FileErrorType::FfmpegExecution,
+            message: "损坏".to_string(),
+        };
+        let line = failed.to_json_line().unwrap();
+        assert!(line.starts_with(r#"{"event":"file_failed","path":"bad.mp3","error_type":"ffmpeg_execution""#));
+        assert!(!line.contains('\n'));
+        let skipped = ProgressEvent::FileSkipped { path: "Cloud/c.flac".into(), reason: SkipReason::CloudPlaceholder };
+        assert_eq!(
+            skipped.to_json_line().unwrap(),
+            r#"{"event":"file_skipped","path":"Cloud/c.flac","reason":"cloud_placeholder"}"#
+        );
+        for reason in [
+            SkipReason::Cancelled,
+            SkipReason::Outage,
+            SkipReason::Aborted,
+            SkipReason::CloudPlaceholder,
+            SkipReason::ICloudStub,
+        ] {
+            assert_eq!(serde_json::to_value(reason).unwrap(), reason.as_str());
+        }
+        for error_type in [
+            FileErrorType::FfmpegExecution,
+            FileErrorType::LraParsingFailed,
+            FileErrorType::FileAccess,
+            FileErrorType::CloudPlaceholder,
+            FileErrorType::Cancelled,
+            FileErrorType::Other,
+        ] {
+            assert_eq!(serde_json::to_value(error_type).unwrap(), error_type.as_str());
+        }
+
+        let summary = ProgressEvent::Summary {
+            successful: 1,
+            failed: 0,
+            results_file: "/music/lra_results.txt".to_string(),
+            elapsed_secs: 95.4,
+        };
+        let parsed: ProgressEvent = serde_json::from_str(&summary.to_json_line().unwrap()).unwrap();
+        assert_eq!(parsed, summary);
+    }
+}
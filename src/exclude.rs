@@ -0,0 +1,598 @@
+//! 扫描排除模块 (Scan Exclusion Module)
+//!
+//! 扫描时需要跳过程序自己生成的文件（结果文件、失败记录、缓存、锁文件、
+//! JSON 统计等），以及同一目录下历史运行留下的 `lra_results_2023.txt`、
+//! `lra_errors.txt` 之类的副本。[`ExcludeSet`] 同时支持精确路径和文件名模式。
+//!
+//! 此外还可以按目录名剪枝：`node_modules`、`.git` 这类目录在遍历时直接跳过，
+//! 其子树完全不会被访问（见 [`DEFAULT_PRUNED_DIRS`]）。
+//!
+//! ## 生成文件清单
+//!
+//! 按模板命名的结果文件（如 `--output-name mix_{time}.wav`）、放在扫描根目录下的调试输出目录
+//! 和诊断脚本，下一次运行时名字或选项可能已经不同，仅凭本次的选项无法排除。每次运行结束时
+//! （包括出错和被中断）生成的文件和目录记录到扫描根目录下的清单 [`ARTIFACT_MANIFEST_FILE_NAME`] 中（见 [`record_artifacts`]），
+//! 之后在同一根目录下的扫描都会读取清单并跳过这些路径。
+//!
+//! ## 文件名模式
+//!
+//! 模式只匹配文件名（不含目录），支持 `*`（任意多个字符）、`?`（单个字符）和字符类
+//! `[abc]`、`[a-z]`、`[!0-9]`，匹配时忽略 ASCII 大小写。用户提供的模式（`--exclude`、`--only`）
+//! 在解析命令行时由 [`validate_pattern`] 校验，写错的模式直接报错，而不是静默地什么都不匹配。
+
+use std::fs;
+use std::io;
+use std::path::{Path, PathBuf};
+
+use crate::error::AppError;
+
+/// 默认排除的文件名模式：本程序历史上生成的结果和错误文件
+pub const DEFAULT_EXCLUDE_PATTERNS: [&str; 2] = ["lra_results*", "lra_errors*"];
+
+/// 默认剪枝的目录名：版本控制、依赖缓存、回收站和 NAS 索引目录
+pub const DEFAULT_PRUNED_DIRS: [&str; 11] = [
+    ".git",
+    ".svn",
+    ".hg",
+    "node_modules",
+    ".cache",
+    "__pycache__",
+    ".Trash",
+    ".Trashes",
+    "$RECYCLE.BIN",
+    "System Volume Information",
+    "@eaDir",
+];
+
+/// 生成文件清单的文件名，位于扫描根目录下
+pub const ARTIFACT_MANIFEST_FILE_NAME: &str = ".lra_artifacts";
+
+/// 生成文件清单的首行说明
+const ARTIFACT_MANIFEST_HEADER: &str = "# LRA-Calculator 生成的文件（相对扫描根目录），扫描时跳过";
+
+/// 扫描排除集合 (Exclude Set)
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct ExcludeSet {
+    /// 精确排除的路径
+    paths: Vec<PathBuf>,
+    /// 本次运行持有的临时文件（如锁文件），同样排除，但不算作目录中已有的生成文件
+    transient_paths: Vec<PathBuf>,
+    /// 排除的生成文件的文件名模式
+    patterns: Vec<String>,
+    /// 用户指定的排除模式（`--exclude`），排除的是用户的文件而不是生成文件
+    user_patterns: Vec<String>,
+    /// 遍历时整体跳过的目录名（精确匹配）
+    pruned_dirs: Vec<String>,
+}
+
+impl ExcludeSet {
+    /// 创建空的排除集合
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// 创建包含 [`DEFAULT_EXCLUDE_PATTERNS`] 的排除集合
+    pub fn with_default_patterns() -> Self {
+        let mut set = Self::new();
+        for pattern in DEFAULT_EXCLUDE_PATTERNS {
+            set.add_pattern(pattern);
+        }
+        set
+    }
+
+    /// 只排除单个路径（兼容旧的 `Option<&Path>` 参数）
+    pub fn from_optional_path(path: Option<&Path>) -> Self {
+        let mut set = Self::new();
+        if let Some(path) = path {
+            set.add_path(path);
+        }
+        set
+    }
+
+    /// 添加精确排除的路径
+    ///
+    /// 父目录存在时还会记录规范化后的路径，使相对路径（如 `-o results.txt`）
+    /// 也能与扫描到的绝对路径匹配；文件本身可以尚不存在。
+    pub fn add_path(&mut self, path: impl Into<PathBuf>) -> &mut Self {
+        let path = path.into();
+        if let Some(canonical) = canonicalize_parent(&path).filter(|canonical| *canonical != path) {
+            self.paths.push(canonical);
+        }
+        self.paths.push(path);
+        self
+    }
+
+    /// 添加本次运行持有的临时文件（如锁文件）
+    ///
+    /// 与 [`ExcludeSet::add_path`] 一样排除，但 [`ExcludeSet::is_transient`] 为 `true`，
+    /// 扫描报告不把它计入已有的生成文件，空目录仍然报告为空。
+    pub fn add_transient_path(&mut self, path: impl Into<PathBuf>) -> &mut Self {
+        let path = path.into();
+        if let Some(canonical) = canonicalize_parent(&path).filter(|canonical| *canonical != path) {
+            self.transient_paths.push(canonical);
+        }
+        self.transient_paths.push(path);
+        self
+    }
+
+    /// 路径是否为本次运行持有的临时文件（见 [`ExcludeSet::add_transient_path`]）
+    pub fn is_transient(&self, path: &Path) -> bool {
+        self.transient_paths.iter().any(|transient| path == transient)
+    }
+
+    /// 添加扫描根目录下生成文件清单中记录的路径，以及清单文件本身
+    ///
+    /// 清单不存在或无法读取时只排除清单文件。
+    pub fn add_artifact_manifest(&mut self, root: &Path) -> &mut Self {
+        self.add_path(root.join(ARTIFACT_MANIFEST_FILE_NAME));
+        for relative in read_artifact_manifest(root) {
+            self.add_path(root.join(relative));
+        }
+        self
+    }
+
+    /// 添加排除的生成文件的文件名模式
+    pub fn add_pattern(&mut self, pattern: impl Into<String>) -> &mut Self {
+        self.patterns.push(pattern.into());
+        self
+    }
+
+    /// 添加用户指定的排除模式（`--exclude`）
+    ///
+    /// 与 [`ExcludeSet::add_pattern`] 一样排除匹配的文件，但 [`ExcludeSet::is_generated`]
+    /// 不把它们算作生成文件，扫描报告把其中的音频文件计为被过滤的文件。
+    pub fn add_user_pattern(&mut self, pattern: impl Into<String>) -> &mut Self {
+        self.user_patterns.push(pattern.into());
+        self
+    }
+
+    /// 添加剪枝的目录名
+    pub fn add_pruned_dir(&mut self, name: impl Into<String>) -> &mut Self {
+        self.pruned_dirs.push(name.into());
+        self
+    }
+
+    /// 添加 [`DEFAULT_PRUNED_DIRS`] 中的所有目录名
+    pub fn add_default_pruned_dirs(&mut self) -> &mut Self {
+        for name in DEFAULT_PRUNED_DIRS {
+            self.add_pruned_dir(name);
+        }
+        self
+    }
+
+    /// 判断目录是否应被剪枝（连同子树一起跳过）
+    ///
+    /// # 参数
+    /// - `dir_name` - 目录名（不含路径）
+    pub fn is_pruned_dir(&self, dir_name: &str) -> bool {
+        self.pruned_dirs.iter().any(|name| name == dir_name)
+    }
+
+    /// 判断文件是否应被排除
+    ///
+    /// # 参数
+    /// - `path` - 扫描到的文件路径
+    ///
+    /// # 返回值
+    /// - `true` - 路径与某个精确路径相同或位于其下（排除目录时），或文件名匹配某个模式
+    ///
+    /// # 示例
+    /// ```
+    /// use std::path::Path;
+    /// use lra_calculator_rust::exclude::ExcludeSet;
+    ///
+    /// let set = ExcludeSet::with_default_patterns();
+    /// assert!(set.is_excluded(Path::new("/music/lra_results_2023.txt")));
+    /// assert!(!set.is_excluded(Path::new("/music/song.flac")));
+    /// ```
+    pub fn is_excluded(&self, path: &Path) -> bool {
+        self.is_generated(path) || self.is_transient(path) || self.is_user_excluded(path)
+    }
+
+    /// 文件是否作为本程序生成的文件被排除：精确路径（含生成文件清单中的路径）或生成文件的文件名模式
+    pub fn is_generated(&self, path: &Path) -> bool {
+        self.paths.iter().any(|excluded| path.starts_with(excluded))
+            || file_name_matches(&self.patterns, path)
+    }
+
+    /// 文件名是否匹配用户指定的排除模式（见 [`ExcludeSet::add_user_pattern`]）
+    pub fn is_user_excluded(&self, path: &Path) -> bool {
+        file_name_matches(&self.user_patterns, path)
+    }
+}
+
+/// 文件名是否匹配任一模式；非 UTF-8 文件名不匹配
+fn file_name_matches(patterns: &[String], path: &Path) -> bool {
+    let Some(file_name) = path.file_name().and_then(|name| name.to_str()) else {
+        return false;
+    };
+    patterns
+        .iter()
+        .any(|pattern| wildcard_match(pattern.as_bytes(), file_name.as_bytes()))
+}
+
+/// 记录生成的文件 (Record Artifacts)
+///
+/// 把 `artifacts` 中位于 `root` 之下且存在的文件或目录加入生成文件清单，
+/// 同时去掉清单中已经不存在的路径。清单没有变化时不写入。
+///
+/// # 参数
+/// - `root` - 扫描根目录
+/// - `artifacts` - 本次运行生成的文件和目录，不在扫描根目录下的被忽略
+///
+/// # 返回值
+/// - `Ok(usize)` - 新记录的路径数
+/// - `Err(io::Error)` - 清单写入失败
+///
+/// # 示例
+/// ```
+/// use lra_calculator_rust::exclude::{record_artifacts, ExcludeSet};
+///
+/// let root = tempfile::TempDir::new().unwrap();
+/// let results = root.path().join("mix_153012.wav");
+/// std::fs::write(&results, "").unwrap();
+/// assert_eq!(record_artifacts(root.path(), &[results.clone()]).unwrap(), 1);
+///
+/// let mut exclude = ExcludeSet::new();
+/// exclude.add_artifact_manifest(root.path());
+/// assert!(exclude.is_excluded(&results));
+/// ```
+pub fn record_artifacts(root: &Path, artifacts: &[PathBuf]) -> io::Result<usize> {
+    let existing = read_artifact_manifest(root);
+    let mut recorded: Vec<PathBuf> = existing.iter().filter(|relative| root.join(relative).exists()).cloned().collect();
+    let canonical_root = root.canonicalize()?;
+    let mut added = 0;
+    for artifact in artifacts {
+        let Some(relative) = artifact
+            .canonicalize()
+            .ok()
+            .and_then(|canonical| canonical.strip_prefix(&canonical_root).ok().map(Path::to_path_buf))
+            .filter(|relative| !relative.as_os_str().is_empty())
+        else {
+            continue;
+        };
+        if !recorded.contains(&relative) {
+            recorded.push(relative);
+            added += 1;
+        }
+    }
+    if recorded != existing {
+        let mut manifest = format!("{}\n", ARTIFACT_MANIFEST_HEADER);
+        for relative in &recorded {
+            manifest.push_str(&relative.to_string_lossy());
+            manifest.push('\n');
+        }
+        fs::write(root.join(ARTIFACT_MANIFEST_FILE_NAME), manifest)?;
+    }
+    Ok(added)
+}
+
+/// 读取生成文件清单中的相对路径；清单不存在时为空
+fn read_artifact_manifest(root: &Path) -> Vec<PathBuf> {
+    let Ok(manifest) = fs::read_to_string(root.join(ARTIFACT_MANIFEST_FILE_NAME)) else {
+        return Vec::new();
+    };
+    manifest
+        .lines()
+        .map(str::trim)
+        .filter(|line| !line.is_empty() && !line.starts_with('#'))
+        .map(PathBuf::from)
+        .collect()
+}
+
+/// 校验文件名模式 (Validate File Name Pattern)
+///
+/// 去除首尾空白后检查模式能否按预期匹配：不能为空，不能包含路径分隔符（模式只匹配文件名），
+/// 每个 `[` 都要有对应的 `]`，字符类只能包含 ASCII 字符，范围的起点不能大于终点。
+/// 匹配本身忽略 ASCII 大小写，`*.Mp3` 与 `*.mp3` 等价，无需改写。
+///
+/// # 参数
+/// - `pattern` - 用户输入的模式
+///
+/// # 返回值
+/// - `Ok(String)` - 去除首尾空白后的模式
+/// - `Err(AppError::Configuration)` - 模式无效，错误信息包含原始模式和原因
+///
+/// # 示例
+/// ```
+/// use lra_calculator_rust::exclude::validate_pattern;
+///
+/// assert_eq!(validate_pattern(" *.Mp3 ").unwrap(), "*.Mp3");
+/// assert!(validate_pattern("take[12.wav").is_err());
+/// assert!(validate_pattern("Album/*.flac").is_err());
+/// ```
+pub fn validate_pattern(pattern: &str) -> Result<String, AppError> {
+    let invalid = |reason: &str| AppError::Configuration(format!("无效的文件名模式 '{}': {}", pattern, reason));
+    let trimmed = pattern.trim();
+    if trimmed.is_empty() {
+        return Err(invalid("模式为空"));
+    }
+    if trimmed.contains(['/', '\\']) {
+        return Err(invalid("模式只匹配文件名，不能包含路径分隔符"));
+    }
+
+    let bytes = trimmed.as_bytes();
+    let mut p = 0;
+    while p < bytes.len() {
+        if bytes[p] != b'[' {
+            p += 1;
+            continue;
+        }
+        let class = CharClass::parse(bytes, p).ok_or_else(|| invalid("'[' 没有对应的 ']'"))?;
+        if !class.members.is_ascii() {
+            return Err(invalid("字符类只能包含 ASCII 字符"));
+        }
+        if class.ranges().any(|(low, high)| low > high) {
+            return Err(invalid("字符范围的起点大于终点"));
+        }
+        p = class.end;
+    }
+    Ok(trimmed.to_string())
+}
+
+/// 文件名是否匹配模式 (Match File Name)
+///
+/// 模式语法见[模块文档](self)；未经 [`validate_pattern`] 校验的模式中没有对应 `]` 的 `[` 按普通字符匹配。
+///
+/// # 示例
+/// ```
+/// use lra_calculator_rust::exclude::matches_pattern;
+///
+/// assert!(matches_pattern("take[0-9].WAV", "Take3.wav"));
+/// assert!(!matches_pattern("take[!0-9].wav", "take3.wav"));
+/// ```
+pub fn matches_pattern(pattern: &str, file_name: &str) -> bool {
+    wildcard_match(pattern.as_bytes(), file_name.as_bytes())
+}
+
+/// 字符类 `[...]`
+struct CharClass<'a> {
+    /// 是否取反（`[!...]` 或 `[^...]`）
+    negated: bool,
+    /// 括号内的成员（不含取反标记）
+    members: &'a [u8],
+    /// 紧跟 `]` 之后的位置
+    end: usize,
+}
+
+impl<'a> CharClass<'a> {
+    /// 解析从 `start`（指向 `[`）开始的字符类；没有对应的 `]` 时返回 `None`
+    ///
+    /// 紧跟 `[` 或取反标记的 `]` 是普通成员，因此 `[]]` 匹配 `]`。
+    fn parse(pattern: &'a [u8], start: usize) -> Option<Self> {
+        let mut first = start + 1;
+        let negated = matches!(pattern.get(first), Some(b'!' | b'^'));
+        if negated {
+            first += 1;
+        }
+        let close = pattern.get(first + 1..)?.iter().position(|&c| c == b']')? + first + 1;
+        Some(Self { negated, members: &pattern[first..close], end: close + 1 })
+    }
+
+    /// 成员中的范围和单个字符，单个字符表示为起点和终点相同的范围
+    fn ranges(&self) -> impl Iterator<Item = (u8, u8)> + '_ {
+        let members = self.members;
+        let mut i = 0;
+        std::iter::from_fn(move || {
+            let low = *members.get(i)?;
+            // 末尾的 `-` 是普通字符
+            if members.get(i + 1) == Some(&b'-') && i + 2 < members.len() {
+                i += 3;
+                Some((low, members[i - 1]))
+            } else {
+                i += 1;
+                Some((low, low))
+            }
+        })
+    }
+
+    /// 字符是否属于该类（忽略 ASCII 大小写）
+    fn matches(&self, c: u8) -> bool {
+        let contains = |c: u8| self.ranges().any(|(low, high)| (low..=high).contains(&c));
+        let found = contains(c) || contains(c.to_ascii_lowercase()) || contains(c.to_ascii_uppercase());
+        found != self.negated
+    }
+}
+
+/// 规范化路径的父目录并拼接文件名
+fn canonicalize_parent(path: &Path) -> Option<PathBuf> {
+    let file_name = path.file_name()?;
+    let parent = match path.parent() {
+        Some(parent) if !parent.as_os_str().is_empty() => parent,
+        _ => Path::new("."),
+    };
+    Some(parent.canonicalize().ok()?.join(file_name))
+}
+
+/// 通配符匹配（`*`、`?` 和字符类，忽略 ASCII 大小写）
+fn wildcard_match(pattern: &[u8], text: &[u8]) -> bool {
+    let (mut p, mut t) = (0, 0);
+    // 最近一个 `*` 的位置，以及它当时对应的文本位置
+    let mut backtrack: Option<(usize, usize)> = None;
+
+    while t < text.len() {
+        // 当前字符匹配成功时模式的下一个位置
+        let next = match pattern.get(p) {
+            Some(b'*') => {
+                backtrack = Some((p, t));
+                p += 1;
+                continue;
+            }
+            Some(b'[') => match CharClass::parse(pattern, p) {
+                Some(class) => class.matches(text[t]).then_some(class.end),
+                None => (text[t] == b'[').then_some(p + 1),
+            },
+            Some(&c) if c == b'?' || c.eq_ignore_ascii_case(&text[t]) => Some(p + 1),
+            _ => None,
+        };
+        match next {
+            Some(next) => {
+                p = next;
+                t += 1;
+            }
+            None => match backtrack {
+                // 让上一个 `*` 多匹配一个字符后重试
+                Some((star_p, star_t)) => {
+                    backtrack = Some((star_p, star_t + 1));
+                    p = star_p + 1;
+                    t = star_t + 1;
+                }
+                None => return false,
+            },
+        }
+    }
+
+    pattern[p..].iter().all(|&c| c == b'*')
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// 测试通配符匹配
+    #[test]
+    fn test_wildcard_match() {
+        assert!(wildcard_match(b"lra_results*", b"lra_results.txt"));
+        assert!(wildcard_match(b"lra_results*", b"LRA_Results_2023.csv"));
+        assert!(wildcard_match(b"*.bak", b"lra_results.txt.bak"));
+        assert!(wildcard_match(b"take?.wav", b"take1.wav"));
+        assert!(wildcard_match(b"*", b""));
+        assert!(!wildcard_match(b"take?.wav", b"take10.wav"));
+        assert!(!wildcard_match(b"lra_errors*", b"my_lra_errors.txt"));
+        assert!(!wildcard_match(b"*.bak", b"song.flac"));
+    }
+
+    /// 测试字符类匹配
+    #[test]
+    fn test_char_class_match() {
+        let cases: [(&str, &str, bool); 14] = [
+            ("take[12].wav", "take1.wav", true),
+            ("take[12].wav", "take3.wav", false),
+            ("take[0-9].wav", "TAKE7.WAV", true),
+            ("take[!0-9].wav", "takeX.wav", true),
+            ("take[^0-9].wav", "take7.wav", false),
+            ("[a-c]*", "Beat.flac", true),
+            ("[A-C]*", "beat.flac", true),
+            ("[a-c]*", "drums.flac", false),
+            ("[]]x", "]x", true),
+            ("[a-]x", "-x", true),
+            ("*[!.]", "song.", false),
+            ("*mix[0-9][0-9].*", "final_mix12.mp3", true),
+            // 没有对应 `]` 的 `[` 按普通字符匹配
+            ("take[1", "take[1", true),
+            ("take[1", "take1", false),
+        ];
+        for (pattern, file_name, expected) in cases {
+            assert_eq!(matches_pattern(pattern, file_name), expected, "{} ~ {}", pattern, file_name);
+        }
+    }
+
+    /// 测试模式校验：合法模式去除首尾空白，非法模式的错误信息包含该模式
+    #[test]
+    fn test_validate_pattern() {
+        let valid = [
+            ("*.flac", "*.flac"),
+            ("  *.Mp3 ", "*.Mp3"),
+            ("take?.wav", "take?.wav"),
+            ("take[0-9].wav", "take[0-9].wav"),
+            ("[!._]*", "[!._]*"),
+            ("[]]", "[]]"),
+            ("a-b.wav", "a-b.wav"),
+            ("歌曲*.flac", "歌曲*.flac"),
+        ];
+        for (pattern, expected) in valid {
+            assert_eq!(validate_pattern(pattern).unwrap(), expected, "{}", pattern);
+        }
+
+        let invalid = [
+            ("", "模式为空"),
+            ("   ", "模式为空"),
+            ("Album/*.flac", "路径分隔符"),
+            ("Album\\*.flac", "路径分隔符"),
+            ("take[12.wav", "没有对应"),
+            ("*.[fF", "没有对应"),
+            ("[]", "没有对应"),
+            ("[!]", "没有对应"),
+            ("[z-a]*", "起点大于终点"),
+            ("[歌曲]*", "ASCII"),
+        ];
+        for (pattern, reason) in invalid {
+            match validate_pattern(pattern) {
+                Err(AppError::Configuration(message)) => {
+                    assert!(message.contains(&format!("'{}'", pattern)), "{}", message);
+                    assert!(message.contains(reason), "{}", message);
+                }
+                other => panic!("模式 '{}' 应当无效，得到 {:?}", pattern, other),
+            }
+        }
+    }
+
+    /// 测试精确路径和模式的组合
+    #[test]
+    fn test_exclude_set() {
+        let mut set = ExcludeSet::with_default_patterns();
+        set.add_path("/music/report.txt").add_pattern("*.bak");
+
+        assert!(set.is_excluded(Path::new("/music/report.txt")));
+        assert!(set.is_excluded(Path::new("/music/sub/lra_errors.txt")));
+        assert!(set.is_excluded(Path::new("/music/song.mp3.bak")));
+        assert!(!set.is_excluded(Path::new("/music/sub/report.txt")));
+        assert!(!set.is_excluded(Path::new("/music/song.mp3")));
+
+        // 临时文件同样排除，但可以与已有的生成文件区分
+        set.add_transient_path("/music/run.lock");
+        assert!(set.is_excluded(Path::new("/music/run.lock")));
+        assert!(set.is_transient(Path::new("/music/run.lock")));
+        assert!(!set.is_transient(Path::new("/music/report.txt")));
+
+        // 用户的排除模式同样排除，但不算作生成文件
+        set.add_user_pattern("*.flac");
+        assert!(set.is_excluded(Path::new("/music/song.flac")));
+        assert!(set.is_user_excluded(Path::new("/music/song.flac")));
+        assert!(!set.is_generated(Path::new("/music/song.flac")));
+        assert!(set.is_generated(Path::new("/music/lra_results.txt")));
+
+        // 未规范化的路径也能与扫描到的绝对路径匹配
+        let temp_dir = tempfile::TempDir::new().expect("无法创建临时目录");
+        std::fs::create_dir(temp_dir.path().join("sub")).unwrap();
+        let mut normalized = ExcludeSet::new();
+        normalized.add_path(temp_dir.path().join("sub/../out.txt"));
+        assert!(normalized.is_excluded(&temp_dir.path().canonicalize().unwrap().join("out.txt")));
+
+        let mut pruning = ExcludeSet::new();
+        pruning.add_default_pruned_dirs().add_pruned_dir("Samples");
+        assert!(pruning.is_pruned_dir("node_modules"));
+        assert!(pruning.is_pruned_dir("Samples"));
+        assert!(!pruning.is_pruned_dir("samples"));
+        assert!(!pruning.is_pruned_dir("Album"));
+
+        assert!(!ExcludeSet::from_optional_path(None).is_excluded(Path::new("/music/lra_results.txt")));
+    }
+
+    /// 测试生成文件清单：目录连同其中的文件被排除，已不存在的路径从清单中去掉
+    #[test]
+    fn test_artifact_manifest() {
+        let temp_dir = tempfile::TempDir::new().expect("无法创建临时目录");
+        let root = temp_dir.path();
+        let dump_dir = root.join("dump");
+        std::fs::create_dir(&dump_dir).unwrap();
+        std::fs::write(dump_dir.join("take.wav"), "").unwrap();
+        let results = root.join("mix_1.wav");
+        std::fs::write(&results, "").unwrap();
+        let outside = tempfile::TempDir::new().expect("无法创建临时目录");
+
+        let artifacts = [results.clone(), dump_dir.clone(), root.join("missing.txt"), outside.path().join("x.txt")];
+        assert_eq!(record_artifacts(root, &artifacts).unwrap(), 2);
+        assert_eq!(record_artifacts(root, &artifacts).unwrap(), 0);
+
+        let mut exclude = ExcludeSet::new();
+        exclude.add_artifact_manifest(root);
+        assert!(exclude.is_excluded(&results));
+        assert!(exclude.is_excluded(&dump_dir.join("take.wav")));
+        assert!(!exclude.is_excluded(&root.join("dump.wav")));
+
+        std::fs::remove_file(&results).unwrap();
+        record_artifacts(root, &[]).unwrap();
+        assert_eq!(read_artifact_manifest(root), [PathBuf::from("dump")]);
+    }
+}
@@ -0,0 +1,205 @@
+//! 失败流模块 (Failure Stream Module)
+//!
+//! FFmpeg 配置错误时一个 20 万文件的音乐库每个文件都会失败。[`FailureStream`] 在失败发生时接收错误：
+//! 每个错误立即交给处理函数（命令行程序写入失败记录文件），内存中只保留最前和最后各
+//! [`FailureStream::retained_per_side`] 个错误，以及失败数量、按类型的计数和消失的文件数量。
+//!
+//! 设置了 [`RunOptions::failures`](crate::processor::RunOptions::failures) 时并行处理
+//! 把失败直接交给它，返回的结果中不再包含失败；[`analyze_results_streaming`](crate::processor::analyze_results_streaming)
+//! 把其余结果中的失败交给同一个失败流，再从中取出统计。
+//!
+//! 错误按完成的顺序到达，与结果的顺序（[`ResultOrder`](crate::processor::ResultOrder)）无关。
+
+use std::collections::{BTreeMap, VecDeque};
+use std::fmt;
+use std::sync::{Arc, Mutex, MutexGuard};
+
+use crate::error::{FileErrorType, ProcessFileError};
+
+/// 逐条接收错误的处理函数
+type FailureHandler = Box<dyn FnMut(&ProcessFileError) + Send>;
+
+/// 失败的汇总 (Failure Summary)
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct FailureSummary {
+    /// 失败的文件数量
+    pub failed: usize,
+    /// 按错误类型统计的失败数量
+    pub error_type_counts: BTreeMap<FileErrorType, usize>,
+    /// 分析期间消失的文件数量（见 [`ProcessFileError::vanished`]）
+    pub vanished: usize,
+    /// 保留的错误：最前的若干个，然后是最后的若干个
+    pub errors: Vec<ProcessFileError>,
+    /// 没有保留的错误数量
+    pub omitted_errors: usize,
+}
+
+/// 失败流 (Failure Stream)
+///
+/// 克隆得到的实例共享同一个状态，工作线程在文件失败时调用 [`record`](Self::record)。
+///
+/// # 示例
+/// ```
+/// use std::sync::{Arc, Mutex};
+/// use lra_calculator_rust::error::{FileErrorType, ProcessFileError};
+/// use lra_calculator_rust::failures::FailureStream;
+///
+/// let written = Arc::new(Mutex::new(Vec::new()));
+/// let log = Arc::clone(&written);
+/// let failures = FailureStream::with_handler(2, move |error| log.lock().unwrap().push(error.file_path.to_string()));
+/// for index in 0..10 {
+///     failures.record(ProcessFileError::new(format!("{}.flac", index), "无法启动".to_string(), FileErrorType::FfmpegExecution));
+/// }
+///
+/// let summary = failures.take_summary();
+/// assert_eq!(written.lock().unwrap().len(), 10);
+/// assert_eq!((summary.failed, summary.errors.len(), summary.omitted_errors), (10, 4, 6));
+/// assert_eq!(summary.errors[3].file_path, "9.flac");
+/// ```
+#[derive(Clone)]
+pub struct FailureStream {
+    retained_per_side: usize,
+    state: Arc<Mutex<StreamState>>,
+}
+
+/// 失败流的内部状态
+#[derive(Default)]
+struct StreamState {
+    /// 处理函数；没有时只保留和计数
+    handler: Option<FailureHandler>,
+    /// 已经汇总的失败；`errors` 中只有最前的错误
+    summary: FailureSummary,
+    /// 最后的错误
+    last_errors: VecDeque<ProcessFileError>,
+}
+
+impl FailureStream {
+    /// 创建只保留最前和最后各 `retained_per_side` 个错误的失败流
+    pub fn new(retained_per_side: usize) -> Self {
+        Self { retained_per_side, state: Arc::new(Mutex::new(StreamState::default())) }
+    }
+
+    /// 创建失败流，每个错误（包括没有保留的）都按到达的顺序交给 `handler`
+    pub fn with_handler<F>(retained_per_side: usize, handler: F) -> Self
+    where
+        F: FnMut(&ProcessFileError) + Send + 'static,
+    {
+        let stream = Self::new(retained_per_side);
+        stream.lock().handler = Some(Box::new(handler));
+        stream
+    }
+
+    /// 最前和最后各保留的错误数量
+    pub fn retained_per_side(&self) -> usize {
+        self.retained_per_side
+    }
+
+    /// 记录一个失败的文件 (Record)
+    ///
+    /// 先交给处理函数，再计数；超出保留数量时丢弃中间最早的一个。
+    pub fn record(&self, error: ProcessFileError) {
+        let mut state = self.lock();
+        let StreamState { handler, summary, last_errors } = &mut *state;
+        if let Some(handler) = handler {
+            handler(&error);
+        }
+        summary.failed += 1;
+        *summary.error_type_counts.entry(error.error_type).or_insert(0) += 1;
+        summary.vanished += usize::from(error.vanished);
+        if summary.errors.len() < self.retained_per_side {
+            summary.errors.push(error);
+        } else {
+            last_errors.push_back(error);
+            if last_errors.len() > self.retained_per_side {
+                last_errors.pop_front();
+                summary.omitted_errors += 1;
+            }
+        }
+    }
+
+    /// 已记录的失败数量
+    pub fn failed(&self) -> usize {
+        self.lock().summary.failed
+    }
+
+    /// 取出汇总 (Take Summary)
+    ///
+    /// 取出后计数归零，处理函数保留。
+    pub fn take_summary(&self) -> FailureSummary {
+        let mut state = self.lock();
+        let mut summary = std::mem::take(&mut state.summary);
+        summary.errors.extend(state.last_errors.drain(..));
+        summary
+    }
+
+    /// 处理函数不会在持有锁时 panic，中毒时照常使用内部状态
+    fn lock(&self) -> MutexGuard<'_, StreamState> {
+        self.state.lock().unwrap_or_else(|poisoned| poisoned.into_inner())
+    }
+}
+
+impl fmt::Debug for FailureStream {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let state = self.lock();
+        f.debug_struct("FailureStream")
+            .field("retained_per_side", &self.retained_per_side)
+            .field("has_handler", &state.handler.is_some())
+            .field("failed", &state.summary.failed)
+            .finish()
+    }
+}
+
+/// 同一个失败流的克隆视为相等
+impl PartialEq for FailureStream {
+    fn eq(&self, other: &Self) -> bool {
+        Arc::ptr_eq(&self.state, &other.state)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn failure(path: &str, error_type: FileErrorType) -> ProcessFileError {
+        ProcessFileError::new(path, "失败".to_string(), error_type)
+    }
+
+    /// 测试保留最前和最后的错误、计数和消失的文件，处理函数收到每个错误
+    #[test]
+    fn test_retains_first_and_last() {
+        let handled = Arc::new(Mutex::new(Vec::new()));
+        let log = Arc::clone(&handled);
+        let failures = FailureStream::with_handler(2, move |error| log.lock().unwrap().push(error.file_path.to_string()));
+        let shared = failures.clone();
+        assert_eq!(shared, failures);
+
+        for index in 0..7 {
+            let error_type = if index == 3 { FileErrorType::FileAccess } else { FileErrorType::FfmpegExecution };
+            let mut error = failure(&format!("{}.flac", index), error_type);
+            error.vanished = index == 3;
+            shared.record(error);
+        }
+        assert_eq!(failures.failed(), 7);
+        assert_eq!(handled.lock().unwrap().len(), 7);
+
+        let summary = failures.take_summary();
+        let retained: Vec<&str> = summary.errors.iter().map(|error| error.file_path.as_str()).collect();
+        assert_eq!(retained, ["0.flac", "1.flac", "5.flac", "6.flac"]);
+        assert_eq!(summary.omitted_errors, 3);
+        assert_eq!(summary.error_type_counts[&FileErrorType::FfmpegExecution], 6);
+        assert_eq!(summary.vanished, 1);
+
+        // 取出后重新计数
+        assert_eq!(failures.take_summary(), FailureSummary::default());
+    }
+
+    /// 测试错误少于保留数量时全部保留
+    #[test]
+    fn test_keeps_all_when_few() {
+        let failures = FailureStream::new(usize::MAX);
+        failures.record(failure("a.flac", FileErrorType::Other));
+        failures.record(failure("b.flac", FileErrorType::Other));
+        let summary = failures.take_summary();
+        assert_eq!((summary.failed, summary.errors.len(), summary.omitted_errors), (2, 2, 0));
+    }
+}
@@ -0,0 +1,324 @@
+//! 文件键模块 (File Key Module)
+//!
+//! 同一个文件的显示路径会同时出现在处理结果、失败记录、缓存和写出的结果条目中。
+//! [`FileKey`] 用 `Arc<str>` 保存路径，克隆只增加引用计数，
+//! 大型音乐库中每个路径字符串只需分配一次。
+//!
+//! 显示路径必须唯一，否则后写入的结果会悄悄覆盖结果文件和缓存中先写入的条目。
+//! 不同的文件也可能得到相同的显示路径：非 UTF-8 文件名经过替换后相同，或者把多个
+//! 扫描根目录的文件合在一起处理。[`DisplayPathDeduplicator`] 让先出现的文件保留原路径，
+//! 给后出现的文件加上根目录名或序号，并记录每一次冲突。
+
+use std::borrow::Borrow;
+use std::collections::HashSet;
+use std::fmt;
+use std::ops::Deref;
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+
+use crate::console::Glyph;
+
+/// 文件键 (File Key)
+///
+/// 不可变的共享显示路径（相对于扫描根目录）。比较、哈希和排序都与 `str` 一致，
+/// 因此可以用 `&str` 在以 `FileKey` 为键的集合中查找。
+///
+/// # 示例
+/// ```
+/// use lra_calculator_rust::file_key::FileKey;
+///
+/// let key = FileKey::from("Album/01.flac");
+/// let shared = key.clone();
+/// assert_eq!(shared, "Album/01.flac");
+/// assert!(FileKey::ptr_eq(&key, &shared));
+/// ```
+#[derive(Clone, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub struct FileKey(Arc<str>);
+
+impl FileKey {
+    /// 以字符串切片形式访问路径
+    pub fn as_str(&self) -> &str {
+        &self.0
+    }
+
+    /// 两个键是否共享同一份字符串
+    pub fn ptr_eq(a: &FileKey, b: &FileKey) -> bool {
+        Arc::ptr_eq(&a.0, &b.0)
+    }
+}
+
+impl Deref for FileKey {
+    type Target = str;
+
+    fn deref(&self) -> &str {
+        &self.0
+    }
+}
+
+impl AsRef<str> for FileKey {
+    fn as_ref(&self) -> &str {
+        &self.0
+    }
+}
+
+impl Borrow<str> for FileKey {
+    fn borrow(&self) -> &str {
+        &self.0
+    }
+}
+
+impl From<String> for FileKey {
+    fn from(path: String) -> Self {
+        Self(Arc::from(path))
+    }
+}
+
+impl From<&str> for FileKey {
+    fn from(path: &str) -> Self {
+        Self(Arc::from(path))
+    }
+}
+
+impl From<&String> for FileKey {
+    fn from(path: &String) -> Self {
+        Self(Arc::from(path.as_str()))
+    }
+}
+
+impl From<Arc<str>> for FileKey {
+    fn from(path: Arc<str>) -> Self {
+        Self(path)
+    }
+}
+
+impl From<FileKey> for String {
+    fn from(key: FileKey) -> Self {
+        key.0.to_string()
+    }
+}
+
+impl PartialEq<str> for FileKey {
+    fn eq(&self, other: &str) -> bool {
+        &*self.0 == other
+    }
+}
+
+impl PartialEq<&str> for FileKey {
+    fn eq(&self, other: &&str) -> bool {
+        &*self.0 == *other
+    }
+}
+
+impl PartialEq<String> for FileKey {
+    fn eq(&self, other: &String) -> bool {
+        *self.0 == **other
+    }
+}
+
+impl fmt::Debug for FileKey {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        fmt::Debug::fmt(&*self.0, f)
+    }
+}
+
+impl fmt::Display for FileKey {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        fmt::Display::fmt(&*self.0, f)
+    }
+}
+
+/// 显示路径冲突 (Display Path Collision)
+///
+/// 一个文件的显示路径已经被另一个文件使用，改用了 `assigned`。
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct DisplayPathCollision {
+    /// 原本的显示路径（由先出现的文件使用）
+    pub display_path: FileKey,
+    /// 被改名的文件的完整路径
+    pub full_path: PathBuf,
+    /// 改用的显示路径
+    pub assigned: FileKey,
+}
+
+impl fmt::Display for DisplayPathCollision {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{} {} {} ({})", self.display_path, Glyph::Arrow, self.assigned, self.full_path.display())
+    }
+}
+
+/// 显示路径去重器 (Display Path Deduplicator)
+///
+/// 按文件出现的顺序分配显示路径：第一次出现的显示路径原样保留；
+/// 重复的显示路径改为 `<路径> [<根目录名>]`，根目录名无法确定或同样被占用时改为
+/// `<路径> [2]`、`<路径> [3]`……。同样的文件按同样的顺序出现时，分配结果总是相同，
+/// 因此增量更新和合并时同一个文件得到的键与上一次运行一致。
+///
+/// # 示例
+/// ```
+/// use std::path::Path;
+/// use lra_calculator_rust::file_key::DisplayPathDeduplicator;
+///
+/// let mut paths = DisplayPathDeduplicator::new();
+/// assert_eq!(paths.assign(Path::new("/a/music/album/01.flac"), "album/01.flac".to_string()), "album/01.flac");
+/// assert_eq!(paths.assign(Path::new("/b/new/album/01.flac"), "album/01.flac".to_string()), "album/01.flac [new]");
+/// assert_eq!(paths.collisions().len(), 1);
+/// ```
+#[derive(Debug, Default)]
+pub struct DisplayPathDeduplicator {
+    /// 已经分配的显示路径
+    assigned: HashSet<String>,
+    /// 尚未取走的冲突记录
+    collisions: Vec<DisplayPathCollision>,
+}
+
+impl DisplayPathDeduplicator {
+    /// 创建空的去重器
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// 为文件分配唯一的显示路径 (Assign Display Path)
+    ///
+    /// # 参数
+    /// - `full_path` - 文件的完整路径，用于确定根目录名
+    /// - `display_path` - 文件的显示路径（相对于它的扫描根目录）
+    ///
+    /// # 返回值
+    /// - 唯一的显示路径；与 `display_path` 不同时记录一次冲突
+    pub fn assign(&mut self, full_path: &Path, display_path: String) -> String {
+        if !self.assigned.contains(&display_path) {
+            self.assigned.insert(display_path.clone());
+            return display_path;
+        }
+
+        let labelled = root_label(full_path, &display_path).map(|label| format!("{} [{}]", display_path, label));
+        let assigned = labelled
+            .into_iter()
+            .chain((2..).map(|index| format!("{} [{}]", display_path, index)))
+            .find(|candidate| !self.assigned.contains(candidate))
+            .expect("序号候选是无限的");
+        self.assigned.insert(assigned.clone());
+        self.collisions.push(DisplayPathCollision {
+            display_path: display_path.into(),
+            full_path: full_path.to_path_buf(),
+            assigned: FileKey::from(&assigned),
+        });
+        assigned
+    }
+
+    /// 到目前为止的冲突记录
+    pub fn collisions(&self) -> &[DisplayPathCollision] {
+        &self.collisions
+    }
+
+    /// 取走到目前为止的冲突记录，之后的冲突重新开始记录
+    pub fn take_collisions(&mut self) -> Vec<DisplayPathCollision> {
+        std::mem::take(&mut self.collisions)
+    }
+}
+
+/// 文件所在扫描根目录的名称
+///
+/// 显示路径有几层，就从完整路径向上退几层得到根目录。显示路径经过了替换
+/// （非 UTF-8 文件名）或不是完整路径的后缀时无法确定，返回 `None`。
+fn root_label(full_path: &Path, display_path: &str) -> Option<String> {
+    let depth = Path::new(display_path).components().count();
+    let root = full_path.ancestors().nth(depth)?;
+    if full_path.strip_prefix(root).ok()?.to_str()? != display_path {
+        return None;
+    }
+    root.file_name()?.to_str().map(str::to_string)
+}
+
+/// 为合并在一起的文件列表分配唯一的显示路径 (Disambiguate Display Paths)
+///
+/// 单个根目录的扫描结果已经去重（见 [`crate::audio::ScanReport::path_collisions`]）；
+/// 把多个根目录的扫描结果合在一起处理之前，用这个函数处理合并后的列表。
+///
+/// # 参数
+/// - `files` - (完整路径, 显示路径) 列表，重复的显示路径会被原地改写
+///
+/// # 返回值
+/// - 按列表顺序排列的冲突记录
+pub fn disambiguate_display_paths(files: &mut [(PathBuf, String)]) -> Vec<DisplayPathCollision> {
+    let mut deduplicator = DisplayPathDeduplicator::new();
+    for (full_path, display_path) in files.iter_mut() {
+        *display_path = deduplicator.assign(full_path, std::mem::take(display_path));
+    }
+    deduplicator.take_collisions()
+}
+
+/// 序列化为普通字符串，JSON 格式与使用 `String` 时完全相同
+#[cfg(feature = "serde")]
+impl serde::Serialize for FileKey {
+    fn serialize<S: serde::Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.serialize_str(&self.0)
+    }
+}
+
+#[cfg(feature = "serde")]
+impl<'de> serde::Deserialize<'de> for FileKey {
+    fn deserialize<D: serde::Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        String::deserialize(deserializer).map(FileKey::from)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::audio::scan_audio_files;
+    use std::collections::{BTreeMap, HashMap};
+    use std::fs;
+    use tempfile::TempDir;
+
+    /// 测试克隆共享同一份字符串，且可以用 &str 在集合中查找
+    #[test]
+    fn test_file_key_sharing_and_lookup() {
+        let key = FileKey::from("Album/01.flac".to_string());
+        let clone = key.clone();
+        assert!(FileKey::ptr_eq(&key, &clone));
+        assert!(!FileKey::ptr_eq(&key, &FileKey::from("Album/01.flac")));
+
+        let mut by_hash = HashMap::new();
+        by_hash.insert(key.clone(), 1);
+        assert_eq!(by_hash.get("Album/01.flac"), Some(&1));
+
+        let mut by_order = BTreeMap::new();
+        by_order.insert(FileKey::from("b"), 2);
+        by_order.insert(FileKey::from("a"), 1);
+        assert_eq!(by_order.keys().map(FileKey::as_str).collect::<Vec<_>>(), vec!["a", "b"]);
+        assert_eq!(by_order.get("b"), Some(&2));
+
+        assert_eq!(format!("{} {:?}", key, key), "Album/01.flac \"Album/01.flac\"");
+    }
+
+    /// 测试两个扫描根目录中相同的相对路径被区分开
+    #[test]
+    fn test_disambiguate_two_roots() {
+        let temp_dir = TempDir::new().expect("无法创建临时目录");
+        let mut files = Vec::new();
+        for root in ["live", "studio", "other/live", "more/live"] {
+            let album = temp_dir.path().join(root).join("album");
+            fs::create_dir_all(&album).unwrap();
+            fs::write(album.join("track01.flac"), b"").unwrap();
+            files.extend(scan_audio_files(&temp_dir.path().join(root), None));
+        }
+
+        let collisions = disambiguate_display_paths(&mut files);
+        let display_paths: Vec<&str> = files.iter().map(|(_, display)| display.as_str()).collect();
+        let track = Path::new("album").join("track01.flac").to_string_lossy().into_owned();
+        assert_eq!(
+            display_paths,
+            [track.clone(), format!("{} [studio]", track), format!("{} [live]", track), format!("{} [2]", track)]
+        );
+        // 根目录名已被占用时改用序号
+        assert_eq!(collisions.len(), 3);
+        assert_eq!(collisions[0].display_path, track);
+        assert_eq!(collisions[2].full_path, temp_dir.path().join("more/live/album/track01.flac"));
+        assert_eq!(collisions[2].assigned, format!("{} [2]", track));
+
+        // 没有冲突时路径保持不变
+        assert!(disambiguate_display_paths(&mut files).is_empty());
+    }
+}
@@ -0,0 +1,198 @@
+//! 音频格式注册表模块 (Audio Format Registry Module)
+//!
+//! 扫描时识别哪些扩展名属于音频文件。注册表默认包含
+//! [`SUPPORTED_EXTENSIONS`] 中的格式，可在运行时通过 `--add-ext` /
+//! `--remove-ext` 增删（例如 FFmpeg 能解码的 `ape`、`wv`、`dsf`）。
+//!
+//! 进程级的全局注册表是格式判断的唯一依据：[`crate::audio::scan_audio_files`]
+//! 和 [`crate::audio::is_supported_audio_format`] 都会查询它。
+//! `SUPPORTED_EXTENSIONS` 仅作为默认值保留，以兼容已有代码。
+
+use std::collections::BTreeSet;
+use std::sync::{OnceLock, RwLock};
+
+use crate::audio::SUPPORTED_EXTENSIONS;
+use crate::error::AppError;
+
+/// 音频格式注册表 (Format Registry)
+///
+/// 扩展名统一以小写、不带前导点的形式保存。
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct FormatRegistry {
+    /// 已注册的扩展名
+    extensions: BTreeSet<String>,
+}
+
+impl Default for FormatRegistry {
+    /// 使用 [`SUPPORTED_EXTENSIONS`] 初始化
+    fn default() -> Self {
+        Self {
+            extensions: SUPPORTED_EXTENSIONS.iter().map(|ext| ext.to_string()).collect(),
+        }
+    }
+}
+
+impl FormatRegistry {
+    /// 创建不包含任何格式的注册表
+    pub fn empty() -> Self {
+        Self { extensions: BTreeSet::new() }
+    }
+
+    /// 注册扩展名
+    ///
+    /// # 参数
+    /// - `extension` - 扩展名，大小写和前导点不影响结果（如 `.APE`）
+    ///
+    /// # 返回值
+    /// - `Ok(bool)` - 是否为新注册的扩展名
+    /// - `Err(AppError::Configuration)` - 扩展名无效
+    pub fn add(&mut self, extension: &str) -> Result<bool, AppError> {
+        Ok(self.extensions.insert(normalize_extension(extension)?))
+    }
+
+    /// 移除扩展名
+    ///
+    /// # 返回值
+    /// - `Ok(bool)` - 扩展名原本是否已注册
+    /// - `Err(AppError::Configuration)` - 扩展名无效
+    pub fn remove(&mut self, extension: &str) -> Result<bool, AppError> {
+        Ok(self.extensions.remove(&normalize_extension(extension)?))
+    }
+
+    /// 是否支持该扩展名（应为小写、不带前导点）
+    pub fn contains(&self, extension: &str) -> bool {
+        self.extensions.contains(extension)
+    }
+
+    /// 按字母顺序遍历已注册的扩展名
+    pub fn extensions(&self) -> impl Iterator<Item = &str> {
+        self.extensions.iter().map(String::as_str)
+    }
+
+    /// 已注册的扩展名数量
+    pub fn len(&self) -> usize {
+        self.extensions.len()
+    }
+
+    /// 是否没有注册任何扩展名
+    pub fn is_empty(&self) -> bool {
+        self.extensions.is_empty()
+    }
+
+    /// 获取全局注册表的副本
+    pub fn global() -> Self {
+        global_lock()
+            .read()
+            .unwrap_or_else(|poisoned| poisoned.into_inner())
+            .clone()
+    }
+
+    /// 替换全局注册表
+    ///
+    /// 应在扫描开始之前调用，通常只在程序启动解析参数后调用一次。
+    pub fn set_global(registry: FormatRegistry) {
+        *global_lock()
+            .write()
+            .unwrap_or_else(|poisoned| poisoned.into_inner()) = registry;
+    }
+}
+
+/// 查询全局注册表是否支持该扩展名
+pub(crate) fn is_registered(extension: &str) -> bool {
+    global_lock()
+        .read()
+        .unwrap_or_else(|poisoned| poisoned.into_inner())
+        .contains(extension)
+}
+
+/// 全局注册表，首次访问时用默认格式初始化
+fn global_lock() -> &'static RwLock<FormatRegistry> {
+    static GLOBAL_REGISTRY: OnceLock<RwLock<FormatRegistry>> = OnceLock::new();
+    GLOBAL_REGISTRY.get_or_init(|| RwLock::new(FormatRegistry::default()))
+}
+
+/// 规范化用户提供的扩展名 (Normalize Extension)
+///
+/// 去除首尾空白和前导点并转换为小写。
+///
+/// # 参数
+/// - `extension` - 用户输入的扩展名
+///
+/// # 返回值
+/// - `Ok(String)` - 规范化后的扩展名
+/// - `Err(AppError::Configuration)` - 扩展名为空或包含路径分隔符、空白、点、通配符等字符
+///
+/// # 示例
+/// ```
+/// use lra_calculator_rust::formats::normalize_extension;
+///
+/// assert_eq!(normalize_extension(".APE").unwrap(), "ape");
+/// assert!(normalize_extension("a/b").is_err());
+/// ```
+pub fn normalize_extension(extension: &str) -> Result<String, AppError> {
+    let normalized = extension.trim().trim_start_matches('.').to_lowercase();
+
+    let invalid = normalized.is_empty()
+        || normalized
+            .chars()
+            .any(|c| matches!(c, '.' | '/' | '\\' | '*' | '?' | '[' | ']') || c.is_whitespace());
+    if invalid {
+        return Err(AppError::Configuration(format!("无效的文件扩展名 '{}'", extension)));
+    }
+    Ok(normalized)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// 测试默认注册表与 SUPPORTED_EXTENSIONS 一致
+    #[test]
+    fn test_default_registry() {
+        let registry = FormatRegistry::default();
+        assert_eq!(registry.len(), SUPPORTED_EXTENSIONS.len());
+        assert!(SUPPORTED_EXTENSIONS.iter().all(|ext| registry.contains(ext)));
+        assert!(!registry.contains("ape"));
+    }
+
+    /// 测试增删扩展名时的规范化
+    #[test]
+    fn test_add_and_remove() {
+        let mut registry = FormatRegistry::default();
+
+        assert!(registry.add(".APE").unwrap());
+        assert!(registry.add("wv").unwrap());
+        assert!(!registry.add("ape").unwrap());
+        assert!(registry.contains("ape"));
+
+        assert!(registry.remove("WMA").unwrap());
+        assert!(!registry.contains("wma"));
+        assert!(!registry.remove("wma").unwrap());
+
+        assert!(registry.add("").is_err());
+        assert!(registry.add("..").is_err());
+        assert!(registry.remove("tar.gz").is_err());
+    }
+
+    /// 测试扩展名规范化：大小写、前导点和空白不影响结果，无法匹配任何文件的扩展名被拒绝
+    #[test]
+    fn test_normalize_extension() {
+        let valid = [("flac", "flac"), (".FLAC", "flac"), ("..Mp3", "mp3"), (" wv ", "wv"), ("DSF", "dsf")];
+        for (extension, expected) in valid {
+            assert_eq!(normalize_extension(extension).unwrap(), expected, "{}", extension);
+        }
+
+        for extension in ["", " ", ".", "..", "tar.gz", "a/b", "a\\b", "m 4a", "*.mp3", "*", "mp?", "[mf]lac"] {
+            assert!(normalize_extension(extension).is_err(), "扩展名 '{}' 应当无效", extension);
+        }
+    }
+
+    /// 测试空注册表
+    #[test]
+    fn test_empty_registry() {
+        let mut registry = FormatRegistry::empty();
+        assert!(registry.is_empty());
+        registry.add("dsf").unwrap();
+        assert_eq!(registry.extensions().collect::<Vec<_>>(), vec!["dsf"]);
+    }
+}
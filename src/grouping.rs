@@ -0,0 +1,188 @@
+//! 分组统计模块 (Grouping Module)
+//!
+//! `--group-by <键>` 按艺术家、专辑标签或所在目录把成功的音轨分组，
+//! 计算每组的文件数和平均、最小、最大 LRA，回答"库里哪些艺术家的动态压缩最严重"
+//! 这类问题。结果按平均 LRA 从高到低写入结果文件旁的 `lra_by_<键>.txt`，
+//! 每行依次为组名、文件数、平均、最小、最大 LRA，以制表符分隔。
+//!
+//! 分组函数 [`group_statistics`] 不关心分组键从哪里来，标签和目录共用同一套计算。
+
+use std::collections::BTreeMap;
+use std::fmt;
+
+use crate::precision::{LraValue, DEFAULT_LRA_PRECISION};
+
+/// 没有分组键（缺少标签）的文件所在的组名
+pub const UNKNOWN_GROUP: &str = "(unknown)";
+
+/// 分组键 (Group Key)
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum GroupKey {
+    /// 艺术家标签
+    Artist,
+    /// 专辑标签
+    Album,
+    /// 所在目录（与 [`crate::album::album_key`] 相同，扫描根目录为 `.`）
+    Directory,
+}
+
+impl GroupKey {
+    /// 从命令行取值解析（`artist`、`album` 或 `directory`）
+    pub fn parse(value: &str) -> Option<Self> {
+        match value {
+            "artist" => Some(Self::Artist),
+            "album" => Some(Self::Album),
+            "directory" => Some(Self::Directory),
+            _ => None,
+        }
+    }
+
+    /// 是否需要读取标签
+    pub fn needs_tags(&self) -> bool {
+        matches!(self, Self::Artist | Self::Album)
+    }
+
+    /// 输出文件中分组列的列名
+    pub fn column_name(&self) -> &'static str {
+        match self {
+            Self::Artist => "艺术家",
+            Self::Album => "专辑",
+            Self::Directory => "目录",
+        }
+    }
+
+    /// 输出文件名，例如 `lra_by_artist.txt`
+    pub fn file_name(&self) -> String {
+        format!("lra_by_{}.txt", self)
+    }
+}
+
+impl fmt::Display for GroupKey {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Artist => write!(f, "artist"),
+            Self::Album => write!(f, "album"),
+            Self::Directory => write!(f, "directory"),
+        }
+    }
+}
+
+/// 一个分组的 LRA 统计 (Group Statistics)
+#[derive(Debug, Clone, PartialEq)]
+pub struct GroupStatistics {
+    /// 组名
+    pub name: String,
+    /// 文件数
+    pub count: usize,
+    /// 平均 LRA
+    pub mean: f64,
+    /// 最小 LRA
+    pub min: f64,
+    /// 最大 LRA
+    pub max: f64,
+}
+
+impl GroupStatistics {
+    /// 输出文件中的一行：组名、文件数、平均、最小、最大，以制表符分隔
+    ///
+    /// # 参数
+    /// - `precision` - LRA 值保留的小数位数（与结果文件相同）
+    pub fn line(&self, precision: u8) -> String {
+        format!(
+            "{}\t{}\t{}\t{}\t{}",
+            self.name,
+            self.count,
+            LraValue(self.mean).with_precision(precision),
+            LraValue(self.min).with_precision(precision),
+            LraValue(self.max).with_precision(precision)
+        )
+    }
+}
+
+impl fmt::Display for GroupStatistics {
+    /// 按默认精度输出的一行，见 [`GroupStatistics::line`]
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(&self.line(DEFAULT_LRA_PRECISION))
+    }
+}
+
+/// 输出文件的表头行
+pub fn group_header_line(key: GroupKey) -> String {
+    format!("{}\t文件数\t平均 LRA (LU)\t最小 LRA (LU)\t最大 LRA (LU)", key.column_name())
+}
+
+/// 按键分组统计 LRA (Group Statistics)
+///
+/// 纯函数：分组键由调用方提供，没有键（或键为空）的条目归入 [`UNKNOWN_GROUP`]。
+///
+/// # 参数
+/// - `entries` - (分组键, LRA)
+///
+/// # 返回值
+/// - 各组的统计，按平均 LRA 从高到低排序，平均值相同时按组名排序
+///
+/// # 示例
+/// ```
+/// use lra_calculator_rust::grouping::group_statistics;
+///
+/// let groups = group_statistics([(Some("A"), 10.0), (Some("A"), 6.0), (None, 9.0)]);
+/// assert_eq!(groups[0].name, "(unknown)");
+/// assert_eq!((groups[1].count, groups[1].mean), (2, 8.0));
+/// ```
+pub fn group_statistics<'a, I>(entries: I) -> Vec<GroupStatistics>
+where
+    I: IntoIterator<Item = (Option<&'a str>, f64)>,
+{
+    let mut groups: BTreeMap<&str, Vec<f64>> = BTreeMap::new();
+    for (key, lra) in entries {
+        let name = key.filter(|key| !key.is_empty()).unwrap_or(UNKNOWN_GROUP);
+        groups.entry(name).or_default().push(lra);
+    }
+
+    let mut statistics: Vec<GroupStatistics> = groups
+        .into_iter()
+        .map(|(name, values)| GroupStatistics {
+            name: name.to_string(),
+            count: values.len(),
+            mean: values.iter().sum::<f64>() / values.len() as f64,
+            min: values.iter().copied().fold(f64::INFINITY, f64::min),
+            max: values.iter().copied().fold(f64::NEG_INFINITY, f64::max),
+        })
+        .collect();
+    // BTreeMap 已按组名排序，稳定排序保证平均值相同时仍按组名排列
+    statistics.sort_by(|a, b| b.mean.total_cmp(&a.mean));
+    statistics
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// 测试分组统计的计算、排序和未知分组
+    #[test]
+    fn test_group_statistics() {
+        let groups = group_statistics([
+            (Some("Pink Floyd"), 12.0),
+            (Some("Pink Floyd"), 8.0),
+            (Some("Metallica"), 4.0),
+            (Some(""), 5.0),
+            (None, 7.0),
+            (Some("Bach"), 10.0),
+        ]);
+        let names: Vec<&str> = groups.iter().map(|group| group.name.as_str()).collect();
+        assert_eq!(names, vec!["Bach", "Pink Floyd", "(unknown)", "Metallica"]);
+
+        let pink_floyd = &groups[1];
+        assert_eq!((pink_floyd.count, pink_floyd.mean, pink_floyd.min, pink_floyd.max), (2, 10.0, 8.0, 12.0));
+        assert_eq!(groups[2].count, 2);
+        assert_eq!(pink_floyd.to_string(), "Pink Floyd\t2\t10.0\t8.0\t12.0");
+        assert_eq!(pink_floyd.line(2), "Pink Floyd\t2\t10.00\t8.00\t12.00");
+        assert!(group_statistics(std::iter::empty()).is_empty());
+
+        assert_eq!(GroupKey::parse("artist"), Some(GroupKey::Artist));
+        assert_eq!(GroupKey::parse("genre"), None);
+        assert_eq!(GroupKey::Album.file_name(), "lra_by_album.txt");
+        assert!(!GroupKey::Directory.needs_tags());
+        assert!(group_header_line(GroupKey::Artist).starts_with("艺术家\t文件数"));
+    }
+}
@@ -0,0 +1,219 @@
+//! 内容哈希模块 (Content Hash Module)
+//!
+//! `--hash xxh3|sha1` 时为每个文件的内容计算摘要，写入结果文件的附加列、NDJSON 事件和缓存，
+//! 日后可以证明某个测量值属于哪一版文件。
+//!
+//! - 文件按 [`HASH_BUFFER_SIZE`] 分块流式读取，不会整体载入内存
+//! - 并行处理时哈希在单独的线程中与 FFmpeg 同时进行，通常不会延长总耗时
+//! - 哈希失败不影响测量结果，只是该文件的哈希列为空
+//!
+//! xxh3 速度接近内存带宽，适合只需要识别文件版本的场景；
+//! sha1 慢得多，但几乎所有系统都自带校验工具（`sha1sum`、`certutil`）。
+
+use std::fmt;
+use std::fs::File;
+use std::io::{self, Read};
+use std::path::Path;
+
+/// 每次读取的字节数
+pub const HASH_BUFFER_SIZE: usize = 256 * 1024;
+
+/// 哈希算法 (Hash Algorithm)
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum HashAlgorithm {
+    /// XXH3 64 位
+    Xxh3,
+    /// SHA-1
+    Sha1,
+}
+
+impl HashAlgorithm {
+    /// 从命令行取值解析（`xxh3` 或 `sha1`）
+    pub fn parse(value: &str) -> Option<Self> {
+        match value {
+            "xxh3" => Some(Self::Xxh3),
+            "sha1" => Some(Self::Sha1),
+            _ => None,
+        }
+    }
+
+    /// 算法名称，同时用作结果文件中哈希列的列名
+    pub fn name(&self) -> &'static str {
+        match self {
+            Self::Xxh3 => "xxh3",
+            Self::Sha1 => "sha1",
+        }
+    }
+}
+
+impl fmt::Display for HashAlgorithm {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.name())
+    }
+}
+
+/// 文件内容的摘要 (Content Hash)
+///
+/// `Display` 输出 `算法:十六进制摘要`（缓存文件中也使用这种形式），
+/// 结果文件的哈希列只写 [`ContentHash::hex`]，算法由列名表示。
+///
+/// # 示例
+/// ```
+/// use lra_calculator_rust::hash::{ContentHash, HashAlgorithm};
+///
+/// let hash = ContentHash::Xxh3(0x2d06800538d394c2);
+/// assert_eq!(hash.to_string(), "xxh3:2d06800538d394c2");
+/// assert_eq!(ContentHash::parse("xxh3:2d06800538d394c2"), Some(hash));
+/// assert_eq!(hash.algorithm(), HashAlgorithm::Xxh3);
+/// ```
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[cfg_attr(feature = "serde", serde(into = "String", try_from = "String"))]
+pub enum ContentHash {
+    /// XXH3 64 位摘要
+    Xxh3(u64),
+    /// SHA-1 摘要
+    Sha1([u8; 20]),
+}
+
+impl ContentHash {
+    /// 计算摘要使用的算法
+    pub fn algorithm(&self) -> HashAlgorithm {
+        match self {
+            Self::Xxh3(_) => HashAlgorithm::Xxh3,
+            Self::Sha1(_) => HashAlgorithm::Sha1,
+        }
+    }
+
+    /// 十六进制摘要（小写，与 `xxhsum -H3`、`sha1sum` 的输出相同）
+    pub fn hex(&self) -> String {
+        match self {
+            Self::Xxh3(digest) => format!("{:016x}", digest),
+            Self::Sha1(digest) => digest.iter().map(|byte| format!("{:02x}", byte)).collect(),
+        }
+    }
+
+    /// 解析 `算法:十六进制摘要` 形式的字符串
+    pub fn parse(value: &str) -> Option<Self> {
+        let (algorithm, hex) = value.split_once(':')?;
+        match HashAlgorithm::parse(algorithm)? {
+            HashAlgorithm::Xxh3 if hex.len() == 16 => u64::from_str_radix(hex, 16).ok().map(Self::Xxh3),
+            HashAlgorithm::Sha1 if hex.len() == 40 => {
+                let mut digest = [0u8; 20];
+                for (byte, pair) in digest.iter_mut().zip(hex.as_bytes().chunks(2)) {
+                    *byte = u8::from_str_radix(std::str::from_utf8(pair).ok()?, 16).ok()?;
+                }
+                Some(Self::Sha1(digest))
+            }
+            _ => None,
+        }
+    }
+}
+
+impl fmt::Display for ContentHash {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}:{}", self.algorithm(), self.hex())
+    }
+}
+
+impl From<ContentHash> for String {
+    fn from(hash: ContentHash) -> Self {
+        hash.to_string()
+    }
+}
+
+impl TryFrom<String> for ContentHash {
+    type Error = String;
+
+    fn try_from(value: String) -> Result<Self, Self::Error> {
+        Self::parse(&value).ok_or_else(|| format!("无效的内容哈希 '{}'", value))
+    }
+}
+
+/// 计算数据流的摘要 (Hash Reader)
+///
+/// # 参数
+/// - `reader` - 数据来源，按 [`HASH_BUFFER_SIZE`] 分块读取
+/// - `algorithm` - 哈希算法
+///
+/// # 示例
+/// ```
+/// use lra_calculator_rust::hash::{hash_reader, HashAlgorithm};
+///
+/// let hash = hash_reader(&b"abc"[..], HashAlgorithm::Sha1).unwrap();
+/// assert_eq!(hash.hex(), "a9993e364706816aba3e25717850c26c9cd0d89d");
+/// ```
+pub fn hash_reader(mut reader: impl Read, algorithm: HashAlgorithm) -> io::Result<ContentHash> {
+    let mut buffer = vec![0u8; HASH_BUFFER_SIZE];
+    match algorithm {
+        HashAlgorithm::Xxh3 => {
+            let mut hasher = xxhash_rust::xxh3::Xxh3::new();
+            read_chunks(&mut reader, &mut buffer, |chunk| hasher.update(chunk))?;
+            Ok(ContentHash::Xxh3(hasher.digest()))
+        }
+        HashAlgorithm::Sha1 => {
+            let mut hasher = sha1_smol::Sha1::new();
+            read_chunks(&mut reader, &mut buffer, |chunk| hasher.update(chunk))?;
+            Ok(ContentHash::Sha1(hasher.digest().bytes()))
+        }
+    }
+}
+
+/// 计算文件内容的摘要 (Hash File)
+///
+/// # 参数
+/// - `path` - 文件路径
+/// - `algorithm` - 哈希算法
+///
+/// # 返回值
+/// - `Ok(ContentHash)` - 文件内容的摘要
+/// - `Err(io::Error)` - 文件无法打开或读取
+pub fn hash_file(path: &Path, algorithm: HashAlgorithm) -> io::Result<ContentHash> {
+    hash_reader(File::open(path)?, algorithm)
+}
+
+/// 逐块读取直到结束，每块交给 `consume`
+fn read_chunks(reader: &mut impl Read, buffer: &mut [u8], mut consume: impl FnMut(&[u8])) -> io::Result<()> {
+    loop {
+        match reader.read(buffer) {
+            Ok(0) => return Ok(()),
+            Ok(read) => consume(&buffer[..read]),
+            Err(e) if e.kind() == io::ErrorKind::Interrupted => {}
+            Err(e) => return Err(e),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempfile::TempDir;
+
+    /// 测试两种算法的已知摘要、跨块读取和字符串往返
+    #[test]
+    fn test_hash_file() {
+        let temp_dir = TempDir::new().expect("无法创建临时目录");
+        let path = temp_dir.path().join("track.flac");
+        std::fs::write(&path, b"").unwrap();
+
+        let empty_xxh3 = hash_file(&path, HashAlgorithm::Xxh3).unwrap();
+        assert_eq!(empty_xxh3.hex(), "2d06800538d394c2");
+        let empty_sha1 = hash_file(&path, HashAlgorithm::Sha1).unwrap();
+        assert_eq!(empty_sha1.hex(), "da39a3ee5e6b4b0d3255bfef95601890afd80709");
+
+        // 超过一块的内容与一次性计算的结果相同
+        let content: Vec<u8> = (0..HASH_BUFFER_SIZE * 2 + 123).map(|i| (i % 251) as u8).collect();
+        std::fs::write(&path, &content).unwrap();
+        assert_eq!(
+            hash_file(&path, HashAlgorithm::Xxh3).unwrap(),
+            ContentHash::Xxh3(xxhash_rust::xxh3::xxh3_64(&content))
+        );
+
+        for hash in [empty_xxh3, empty_sha1] {
+            assert_eq!(ContentHash::parse(&hash.to_string()), Some(hash));
+        }
+        assert_eq!(ContentHash::parse("sha1:da39"), None);
+        assert_eq!(ContentHash::parse("md5:d41d8cd98f00b204e9800998ecf8427e"), None);
+        assert!(hash_file(&temp_dir.path().join("missing.flac"), HashAlgorithm::Sha1).is_err());
+    }
+}
@@ -0,0 +1,206 @@
+//! 失败文件诊断脚本模块 (Failure Inspection Script Module)
+//!
+//! 运行结束后有文件失败时，通常要对每个文件手动跑一遍 ffprobe 或 FFmpeg 查看原因。
+//! `--inspect-script` 根据结构化的错误列表在结果文件旁生成 `lra_inspect_failures.sh`
+//! （Windows 上为 `.ps1`），每个失败文件一条诊断命令，按 [`FileErrorType`] 分组：
+//!
+//! - FFmpeg 执行失败、LRA 解析失败、已取消：与分析时完全相同的 FFmpeg 命令，可以直接看到完整输出
+//! - 文件访问失败、云端占位文件、其他错误：`ffprobe` 显示容器和流信息
+//!
+//! 路径的引号规则见 [`crate::shell`]，与 `--verbose` 打印的命令行相同。
+
+use std::collections::BTreeMap;
+use std::fs;
+use std::io;
+use std::path::{Path, PathBuf};
+use std::process::Command;
+
+use crate::audio::{build_ebur128_command, CalculationOptions};
+use crate::error::{FileErrorType, ProcessFileError};
+use crate::priority::tool_command;
+use crate::shell::ShellSyntax;
+
+/// 诊断脚本的文件名（不含扩展名）
+pub const INSPECT_SCRIPT_STEM: &str = "lra_inspect_failures";
+
+/// 获取结果文件对应的诊断脚本路径
+///
+/// 诊断脚本与结果文件位于同一目录，扩展名由 shell 决定。
+///
+/// # 示例
+/// ```
+/// use std::path::Path;
+/// use lra_calculator_rust::inspect::inspect_script_path_for;
+/// use lra_calculator_rust::shell::ShellSyntax;
+///
+/// let path = inspect_script_path_for(Path::new("/music/lra_results.txt"), ShellSyntax::Posix);
+/// assert_eq!(path, Path::new("/music/lra_inspect_failures.sh"));
+/// ```
+pub fn inspect_script_path_for(results_file_path: &Path, shell: ShellSyntax) -> PathBuf {
+    results_file_path.with_file_name(format!("{}.{}", INSPECT_SCRIPT_STEM, shell.script_extension()))
+}
+
+/// 构建失败文件的诊断命令 (Diagnostic Command)
+///
+/// # 参数
+/// - `error_type` - 失败类型
+/// - `audio_file_path` - 音频文件的完整路径
+/// - `options` - 分析选项（FFmpeg 命令与分析时相同）
+pub fn diagnostic_command(error_type: FileErrorType, audio_file_path: &Path, options: &CalculationOptions) -> Command {
+    match error_type {
+        FileErrorType::FfmpegExecution | FileErrorType::LraParsingFailed | FileErrorType::Cancelled => {
+            build_ebur128_command(audio_file_path, options)
+        }
+        FileErrorType::FileAccess | FileErrorType::CloudPlaceholder | FileErrorType::Other => {
+            let mut command = tool_command("ffprobe");
+            command.args(["-hide_banner", "-show_format", "-show_streams"]).arg(audio_file_path);
+            command
+        }
+    }
+}
+
+/// 生成诊断脚本内容 (Render Inspect Script)
+///
+/// 每组以注释行开头，写明失败类型和文件数量；每个文件先用一行注释写出错误信息的第一行，
+/// 再写诊断命令。
+///
+/// # 参数
+/// - `shell` - 脚本语法
+/// - `base_folder_path` - 扫描根目录（错误列表中的路径相对于它）
+/// - `errors` - 失败文件的错误列表
+/// - `options` - 分析选项
+///
+/// # 返回值
+/// - 脚本全文，行尾为 `\n`
+pub fn render_inspect_script(
+    shell: ShellSyntax,
+    base_folder_path: &Path,
+    errors: &[ProcessFileError],
+    options: &CalculationOptions,
+) -> String {
+    let mut groups: BTreeMap<FileErrorType, Vec<&ProcessFileError>> = BTreeMap::new();
+    for error in errors {
+        groups.entry(error.error_type).or_default().push(error);
+    }
+
+    let mut script = String::new();
+    if shell == ShellSyntax::Posix {
+        script.push_str("#!/bin/sh\n");
+    }
+    script.push_str("# LRA 计算器失败文件诊断命令：可以逐条复制运行，也可以运行整个脚本\n");
+    for group in groups.values() {
+        script.push_str(&format!("\n# ===== {} ({} 个文件) =====\n", group[0].error_type_description(), group.len()));
+        for error in group {
+            let reason = error.message.lines().next().unwrap_or_default().trim();
+            script.push_str(&format!("# {}: {}\n", error.file_path, reason));
+            let command = diagnostic_command(error.error_type, &base_folder_path.join(error.file_path.as_str()), options);
+            script.push_str(&shell.render_command_line(&command.get_program().to_string_lossy(), command.get_args()));
+            script.push('\n');
+        }
+    }
+    script
+}
+
+/// 写入诊断脚本 (Write Inspect Script)
+///
+/// 没有失败文件时删除上一次运行遗留的脚本。POSIX 脚本设为可执行；
+/// PowerShell 脚本带 UTF-8 BOM，Windows PowerShell 5.1 才能正确读取非 ASCII 路径。
+///
+/// # 参数
+/// - `script_path` - 脚本路径（见 [`inspect_script_path_for`]）
+/// - `shell` - 脚本语法
+/// - `base_folder_path` - 扫描根目录
+/// - `errors` - 失败文件的错误列表
+/// - `options` - 分析选项
+///
+/// # 返回值
+/// - `Ok(true)` - 已写入脚本
+/// - `Ok(false)` - 没有失败文件，未写入
+/// - `Err(io::Error)` - 写入或清理失败
+pub fn write_inspect_script(
+    script_path: &Path,
+    shell: ShellSyntax,
+    base_folder_path: &Path,
+    errors: &[ProcessFileError],
+    options: &CalculationOptions,
+) -> io::Result<bool> {
+    if errors.is_empty() {
+        return match fs::remove_file(script_path) {
+            Err(e) if e.kind() != io::ErrorKind::NotFound => Err(e),
+            _ => Ok(false),
+        };
+    }
+
+    let script = render_inspect_script(shell, base_folder_path, errors, options);
+    let contents = match shell {
+        ShellSyntax::Posix => script,
+        ShellSyntax::PowerShell => format!("\u{feff}{}", script),
+    };
+    fs::write(script_path, contents)?;
+
+    #[cfg(unix)]
+    if shell == ShellSyntax::Posix {
+        use std::os::unix::fs::PermissionsExt;
+        fs::set_permissions(script_path, fs::Permissions::from_mode(0o755))?;
+    }
+    Ok(true)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempfile::TempDir;
+
+    fn failure(path: &str, error_type: FileErrorType) -> ProcessFileError {
+        ProcessFileError::new(path, "第一行原因\n更多细节".to_string(), error_type)
+    }
+
+    /// 测试脚本按错误类型分组，路径正确加引号
+    #[test]
+    fn test_render_inspect_script() {
+        let errors = [
+            failure("A/it's 01.flac", FileErrorType::FileAccess),
+            failure("坏 文件.mp3", FileErrorType::FfmpegExecution),
+            failure("B/x.wav", FileErrorType::FileAccess),
+        ];
+        let options = CalculationOptions::default();
+        let script = render_inspect_script(ShellSyntax::Posix, Path::new("/music"), &errors, &options);
+        let lines: Vec<&str> = script.lines().collect();
+
+        assert_eq!(lines[0], "#!/bin/sh");
+        assert_eq!(lines[3], "# ===== FFmpeg 执行失败 (1 个文件) =====");
+        assert_eq!(lines[4], "# 坏 文件.mp3: 第一行原因");
+        assert!(lines[5].starts_with("ffmpeg -i '/music/坏 文件.mp3' "));
+        assert_eq!(lines[7], "# ===== 文件访问失败 (2 个文件) =====");
+        assert_eq!(lines[9], r"ffprobe -hide_banner -show_format -show_streams '/music/A/it'\''s 01.flac'");
+        assert_eq!(lines[11], "ffprobe -hide_banner -show_format -show_streams /music/B/x.wav");
+        assert_eq!(lines.len(), 12);
+
+        let script = render_inspect_script(ShellSyntax::PowerShell, Path::new("/music"), &errors, &options);
+        assert!(!script.starts_with("#!"));
+        assert!(script.contains("ffprobe -hide_banner -show_format -show_streams '/music/A/it''s 01.flac'\n"));
+    }
+
+    /// 测试写入的脚本语法正确，没有失败时删除旧脚本
+    #[test]
+    fn test_write_inspect_script() {
+        let temp_dir = TempDir::new().expect("无法创建临时目录");
+        let options = CalculationOptions::default();
+        let errors = [failure("$(touch pwned) `x` \"q\".flac", FileErrorType::Other)];
+
+        let script_path = inspect_script_path_for(&temp_dir.path().join("lra_results.txt"), ShellSyntax::Posix);
+        assert!(write_inspect_script(&script_path, ShellSyntax::Posix, temp_dir.path(), &errors, &options).unwrap());
+        #[cfg(unix)]
+        {
+            let status = Command::new("sh").arg("-n").arg(&script_path).status().expect("无法执行 sh");
+            assert!(status.success());
+        }
+
+        let ps1_path = inspect_script_path_for(&temp_dir.path().join("lra_results.txt"), ShellSyntax::PowerShell);
+        write_inspect_script(&ps1_path, ShellSyntax::PowerShell, temp_dir.path(), &errors, &options).unwrap();
+        assert!(fs::read(&ps1_path).unwrap().starts_with("\u{feff}".as_bytes()));
+
+        assert!(!write_inspect_script(&script_path, ShellSyntax::Posix, temp_dir.path(), &[], &options).unwrap());
+        assert!(!script_path.exists());
+    }
+}
@@ -13,9 +13,10 @@
 //! 
 //! ## 使用示例
 //! 
+//! 常用的函数和类型都可以从 [`prelude`] 一次导入：
+//! 
 //! ```rust,no_run
-//! use lra_calculator_rust::audio::{scan_audio_files, check_ffmpeg_availability};
-//! use lra_calculator_rust::processor::process_files_parallel;
+//! use lra_calculator_rust::prelude::*;
 //! use std::path::Path;
 //! 
 //! // 检查 FFmpeg 环境
@@ -30,29 +31,187 @@
 //! println!("处理了 {} 个文件", results.len());
 //! ```
 //! 
+//! ## 特性 (Features)
+//! 
+//! - `cli`（默认启用）- 命令行程序所需的参数解析、交互式路径输入、最近路径记录和
+//!   控制台统计和表格输出；可执行文件要求启用此特性。嵌入库时使用
+//!   `default-features = false` 可以去掉这些代码以及 chrono 等依赖
+//! - `serde`（默认启用）- JSON 序列化（`--stats-json`、缓存、NDJSON 事件）
+//! - `async` - 基于 `tokio::process` 的异步接口，见 `async_api` 模块
+//! 
 //! ## 模块结构
 //! 
+//! - [`abort`] - 最先完成的文件全部以同一种错误失败时提前中止（`--abort-after`）
+//! - [`album`] - 按目录分组专辑，命名专辑条目
+//! - [`baseline`] - 与基线结果文件比较，检测 LRA 回归（`--baseline`）
+//! - `async_api` - 基于 tokio 的异步分析接口（`async` 特性）
 //! - [`audio`] - 音频文件处理和 LRA 计算核心功能
+//! - `banner` - 欢迎、完成横幅和 LRA 使用建议（`cli` 特性）
+//! - [`breaker`] - 网络共享断连时暂停、恢复并重新分析（`--outage-threshold`、`--outage-budget`）
+//! - [`cache`] - 基于文件大小和修改时间的结果缓存
+//! - [`case_alias`] - 跳过只有大小写不同的同一个文件（`--dedup-case`）
+//! - [`cancel`] - 取消令牌和到期自动取消（`--max-duration`）
+//! - [`channels`] - 逐声道测量综合响度和 LRA，标记声道不平衡的文件（`--per-channel`）
+//! - `cli` - 命令行参数解析（`cli` 特性）
+//! - [`clock`] - 可替换的时钟，测试中固定时间（`LRA_FAKE_TIME`）
+//! - [`cloud`] - 识别 OneDrive、iCloud 等云端占位文件
+//! - [`concurrency`] - 按系统负载和可用内存调整并发数（`--adaptive-jobs`）
+//! - [`console`] - 人类可读输出（可切换到 stderr）
+//! - [`environment`] - 运行环境检测和结构化报告
+//! - [`estimate`] - 开始分析前按文件数、总大小和历史速度估算用时
+//! - [`exclude`] - 扫描时排除结果文件等辅助文件
+//! - [`file_key`] - 在结果、统计和输出之间共享的文件路径，以及显示路径去重
+//! - [`events`] - 运行事件和事件接收者（控制台、NDJSON、GUI 回调共用同一个事件定义）
+//! - [`formats`] - 运行时可配置的音频格式注册表
+//! - [`grouping`] - 按艺术家、专辑或目录分组统计 LRA
+//! - [`hash`] - 文件内容哈希（`--hash xxh3|sha1`）
+//! - [`inspect`] - 为失败文件生成诊断命令脚本（`--inspect-script`）
+//! - [`outcome`] - 运行结果：正常完成或没有需要处理的文件（不是错误）
+//! - [`peak`] - 真峰值超过上限的削波风险检查（`--true-peak`）
+//! - [`plan`] - 扫描、生成可编辑的处理计划、执行计划三个步骤（`--dry-run` 输出计划）
+//! - [`precision`] - LRA 值的显示精度和合理范围（`--precision`、`--lra-range`）
+//! - [`prelude`] - 典型使用场景所需的函数和类型
+//! - [`priority`] - 外部进程（FFmpeg、ffprobe）的优先级（`--nice`）
+//! - [`probe`] - 按内容识别没有扩展名的音频文件
 //! - [`processor`] - 并行处理和进度跟踪
+//! - [`progress`] - 解析 FFmpeg 的 `-progress` 输出，报告长文件的分析进度
+//! - [`replaygain`] - 由综合响度计算 ReplayGain 2.0 增益
+//! - [`run_history`] - 跨运行的响度分布历史（`--run-history`、`history` 子命令）
+//! - [`run_id`] - 每次运行的唯一 ID，用于对应同一次运行的各个产物
+//! - [`scan_stats`] - 找到的文件按扩展名统计的数量和总大小
+//! - [`selection`] - 扫描后只选择部分文件分析（`--limit`、`--sample`）
+//! - [`shell`] - 可复制运行的命令行（POSIX shell 和 PowerShell 的引号规则）
+//! - [`suggestion`] - 与错误信息分开的解决建议，可按语言输出
+//! - `table` - 列对齐的控制台结果表格（`cli` 特性）
+//! - [`tags`] - 读取艺术家、专辑、标题标签
 //! - [`error`] - 错误类型定义和处理
+//! - [`thresholds`] - CI 门禁的阈值检查
 //! - [`utils`] - 通用工具函数和辅助功能
+//! - [`versions`] - 比较不同目录中同名音轨（不同母带版本）的 LRA（`compare-versions`）
+//! - [`warnings`] - 识别分析成功的文件在 FFmpeg 输出中的警告
+//!
+//! 公开函数签名中出现的类型都在 crate 根重新导出。
+
+#[macro_use]
+pub mod console;
 
+pub mod abort;
+pub mod album;
+#[cfg(feature = "async")]
+pub mod async_api;
 pub mod audio;
+#[cfg(feature = "cli")]
+pub mod banner;
+pub mod baseline;
+pub mod breaker;
+pub mod cache;
+pub mod cancel;
+pub mod case_alias;
+pub mod channels;
+#[cfg(feature = "cli")]
+pub mod cli;
+pub mod clock;
+pub mod cloud;
+pub mod concurrency;
+pub mod environment;
 pub mod error;
+pub mod estimate;
+pub mod events;
+pub mod exclude;
+pub mod failures;
+pub mod file_key;
+pub mod formats;
+pub mod grouping;
+pub mod hash;
+pub mod inspect;
+pub mod outcome;
+pub mod peak;
+pub mod plan;
+pub mod precision;
+pub mod prelude;
+pub mod priority;
+pub mod probe;
 pub mod processor;
+pub mod progress;
+pub mod replaygain;
+pub mod run_history;
+pub mod run_id;
+pub mod scan_stats;
+pub mod selection;
+pub mod shell;
+pub mod suggestion;
+#[cfg(feature = "cli")]
+pub mod table;
+pub mod tags;
+pub mod thresholds;
 pub mod utils;
+pub mod versions;
+pub mod warnings;
 
 // 重新导出常用类型和函数，方便使用
 pub use audio::{
-    scan_audio_files, calculate_lra_direct, check_ffmpeg_availability,
-    extract_file_extension, is_supported_audio_format, SUPPORTED_EXTENSIONS
+    scan_audio_files, scan_audio_files_with_report, scan_audio_files_with_formats, scan_audio_files_with_options, scan_audio_files_iter, ScanIter, ScanOptions, ScanReport, calculate_lra_direct, check_ffmpeg_availability, self_test,
+    extract_file_extension, is_supported_audio_format, SUPPORTED_EXTENSIONS,
+    calculate_lra_with_options, measure_loudness, measure_album_loudness, measure_downmixed_lra, CalculationOptions, Downmix, LoudnessSummary
+};
+pub use environment::{check_environment_report, EnvironmentReport, FfmpegStatus};
+pub use events::{BufferSink, EventSink, FilePosition, ProgressEvent, RunEvent};
+#[cfg(feature = "cli")]
+pub use events::ConsoleSink;
+pub use exclude::ExcludeSet;
+pub use failures::{FailureStream, FailureSummary};
+pub use file_key::{DisplayPathCollision, DisplayPathDeduplicator, FileKey};
+pub use formats::FormatRegistry;
+pub use processor::{
+    process_files_parallel, process_files_parallel_with_events, process_files_parallel_ordered, process_files_pipelined_with_events,
+    process_files_parallel_with_options, process_files_pipelined_with_options, ResultOrder, RunOptions, ScheduleOrder, analyze_results,
+    analyze_results_streaming, ProcessingStats, FileMeasurement, FileOutcome, LraDistribution, SkipReason, SuspiciousMeasurement, WorkerUtilization
+};
+#[cfg(feature = "cli")]
+pub use processor::display_processing_stats;
+pub use error::{
+    AppError, EmptyScanReason, FfmpegErrorKind, PathErrorKind, ProcessFileError, FileErrorType, LraCalculationError, ResultLineParseError, ResultsVerificationError,
+    SelfTestError, SelfTestStage,
 };
-pub use processor::{process_files_parallel, analyze_results, display_processing_stats, ProcessingStats};
-pub use error::{AppError, ProcessFileError, FileErrorType};
 pub use utils::{
-    validate_folder_path, sort_lra_results_file, get_folder_path_from_user,
-    parse_result_line, sort_entries_by_lra
+    validate_folder_path, sort_lra_results_file,
+    parse_result_line, sort_entries_by_lra, sort_entries, SortOrder, read_and_parse_results_file, ResultsFile, ResultsFileContents, ResultsSet, MergePolicy, ExtraColumnTable,
+    InvalidResultLine, SortReport, MergeReport,
+};
+#[cfg(feature = "cli")]
+pub use utils::{get_folder_path_from_user, history::PathHistory};
+pub use utils::lock::ResultsLock;
+pub use cache::{
+    CacheEntry, CachePlan, CacheVersionPolicy, FileFingerprint, ProbeCacheEntry, ResultsCache, VersionChange
 };
+#[cfg(feature = "cli")]
+pub use cli::{CliOptions, Command, PorcelainFormat};
+pub use grouping::{GroupKey, GroupStatistics};
+pub use hash::{ContentHash, HashAlgorithm};
+pub use probe::{FfprobeReport, MagicVerdict, ProbeOutcome};
+pub use baseline::{BaselineChange, BaselineComparison};
+pub use outcome::RunOutcome;
+pub use breaker::{CircuitBreaker, OutageConfig};
+pub use cancel::CancellationToken;
+pub use case_alias::CaseAlias;
+pub use clock::{Clock, FixedClock, SystemClock};
+pub use channels::{ChannelImbalance, ChannelLoudness};
+pub use progress::AnalysisProgress;
+pub use concurrency::{ConcurrencyBand, ConcurrencyLimiter};
+pub use peak::ClippingRisk;
+pub use plan::{PlanOptions, PlannedFile, ProcessingOutcome, ProcessingPlan, ReusedFile, ScanResult};
+pub use precision::{LraRange, LraValue, LraValueWithPrecision};
+pub use priority::ProcessPriority;
+pub use replaygain::ReplayGain;
+pub use run_history::{RunHistory, RunRecord};
+pub use run_id::RunId;
+pub use scan_stats::ScanStatistics;
+pub use selection::{FileSelection, SelectionReport};
+pub use shell::ShellSyntax;
+pub use suggestion::{Language, Suggestion};
+pub use tags::TrackTags;
+pub use thresholds::{LraThresholds, LufsRange, ThresholdRule, ThresholdViolation};
+pub use versions::{NameNormalization, VersionGroup};
 
 /// 库版本信息
 pub const VERSION: &str = env!("CARGO_PKG_VERSION");
@@ -77,11 +236,12 @@ pub fn get_version_info() -> String {
 /// 检查库的运行环境
 /// 
 /// 验证所有必要的依赖和环境配置是否正确。
-/// 这是一个便利函数，组合了多个环境检查。
+/// 这是 [`check_environment_report`] 的便利包装，只关心能否运行；
+/// 需要展示检测详情时请直接使用报告。
 /// 
 /// # 返回值
 /// - `Ok(())` - 环境检查通过
-/// - `Err(AppError)` - 环境检查失败
+/// - `Err(AppError)` - 环境检查失败（FFmpeg 不可用或缺少 ebur128 滤波器）
 /// 
 /// # 示例
 /// ```rust,no_run
@@ -93,13 +253,7 @@ pub fn get_version_info() -> String {
 /// }
 /// ```
 pub fn check_environment() -> Result<(), AppError> {
-    // 检查 FFmpeg 可用性
-    check_ffmpeg_availability()?;
-    
-    // 未来可以添加更多环境检查
-    // 例如：检查系统资源、权限等
-    
-    Ok(())
+    check_environment_report().ensure_ready()
 }
 
 #[cfg(test)]
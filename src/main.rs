@@ -10,20 +10,104 @@
 //! - 基于 EBU R128 标准的精确 LRA 计算
 //! - 结果自动排序和保存
 
-mod audio;
-mod error;
-mod processor;
-mod utils;
-
+use std::collections::{BTreeMap, HashMap, HashSet};
 use std::fs::File;
-use std::io::{BufWriter, Write};
+use std::io::{self, BufWriter, Write};
+use std::ops::ControlFlow;
 use std::path::{Path, PathBuf};
+use std::process::ExitCode;
+use std::sync::{Arc, LazyLock, Mutex};
+use std::time::{Instant, SystemTime};
+
+use chrono::{DateTime, Local};
+use rayon::prelude::*;
+
+use lra_calculator_rust::abort::{EarlyAbort, DEFAULT_ABORT_AFTER};
+use lra_calculator_rust::cancel::{CancellationToken, DeadlineTimer};
+use lra_calculator_rust::case_alias::CaseAlias;
+use lra_calculator_rust::channels::{channel_column_values, find_channel_imbalances, CHANNEL_COLUMNS, DEFAULT_CHANNEL_IMBALANCE};
+use lra_calculator_rust::breaker::CircuitBreaker;
+use lra_calculator_rust::clock::{clock_from_env, Clock, SystemClock};
+use lra_calculator_rust::concurrency::{AdaptiveMonitor, ConcurrencyLimiter};
+use lra_calculator_rust::banner::{completion_banner, lra_advice, show_banners, welcome_banner};
+use lra_calculator_rust::baseline::{compare_with_baseline, BaselineComparison, DEFAULT_REGRESSION_THRESHOLD};
+use lra_calculator_rust::audio::{
+    check_ffmpeg_availability, measure_album_loudness, self_test,
+    CalculationOptions, ScanOptions,
+    ScanReport, DOWNMIXED_LRA_COLUMN, RANGE_DETAIL_COLUMNS, SELF_TEST_DURATION_SECS,
+};
+use lra_calculator_rust::cache::{
+    cache_path_for, load_cache, rebuild_cache, save_cache,
+    CacheVersionPolicy, FileFingerprint, ResultsCache,
+};
+use lra_calculator_rust::cli::{parse_args, usage, CliOptions, Command, PorcelainFormat};
+use lra_calculator_rust::console::{
+    configure_charset, configure_colors, error_color_mode, human_color_mode, human_output_is_terminal, output_charset,
+    set_human_output_to_stderr, Style,
+};
+use lra_calculator_rust::error::{AppError, FfmpegErrorKind, ProcessFileError, ResultsVerificationError};
+use lra_calculator_rust::events::{ConsoleSink, EventSink, ProgressEvent, ProgressLineFormatter, RunEvent};
+use lra_calculator_rust::exclude::{record_artifacts, ExcludeSet, ARTIFACT_MANIFEST_FILE_NAME};
+use lra_calculator_rust::failures::FailureStream;
+use lra_calculator_rust::file_key::FileKey;
+use lra_calculator_rust::versions::{compare_versions, VersionGroup, DEFAULT_MIN_SPREAD};
+use lra_calculator_rust::grouping::{group_header_line, group_statistics, GroupKey, GroupStatistics};
+use lra_calculator_rust::{human_eprintln, human_format, human_println};
+use lra_calculator_rust::precision::LraValue;
+use lra_calculator_rust::inspect::{inspect_script_path_for, write_inspect_script};
+use lra_calculator_rust::peak::{find_clipping_risks, write_clipping_report, DEFAULT_TRUE_PEAK_CEILING};
+use lra_calculator_rust::plan::{
+    execute_with_sink, scan_and_execute, scan_with_events, PlanOptions, ProcessingOutcome, ProcessingPlan,
+};
+use lra_calculator_rust::shell::ShellSyntax;
+use lra_calculator_rust::suggestion::{render_suggestions, Language};
+use lra_calculator_rust::scan_stats::ScanStatistics;
+use lra_calculator_rust::selection::FileSelection;
+use lra_calculator_rust::run_history::{append_run_record, read_run_history, RunRecord};
+use lra_calculator_rust::run_id::RunId;
+use lra_calculator_rust::table::{render_results_table, render_table, terminal_width, Align};
+use lra_calculator_rust::thresholds::{evaluate_thresholds, ThresholdViolation};
+use lra_calculator_rust::priority::{set_child_priority, ProcessPriority};
+use lra_calculator_rust::probe::probe_unknown_files;
+use lra_calculator_rust::processor::{
+    analyze_results_streaming, display_lra_distribution, display_worker_utilization, format_file_error, DEFAULT_RETAINED_ERRORS,
+    process_files_parallel_with_options, LraDistribution,
+    FileMeasurement, FileOutcome, ProcessingStats, ResultOrder, RunOptions, ScheduleOrder,
+};
+use lra_calculator_rust::album::{album_directory, album_entry_key, album_key, group_albums, is_album_entry};
+use lra_calculator_rust::replaygain::{album_replaygain, replaygain_fields, MISSING_REPLAYGAIN, REPLAYGAIN_COLUMNS};
+use lra_calculator_rust::tags::{read_tags, TrackTags, TAG_COLUMNS};
+use lra_calculator_rust::utils::history::{PathHistory, ThroughputHistory};
+use lra_calculator_rust::utils::lock::{lock_path_for, ResultsLock};
+use lra_calculator_rust::utils::stream::{partial_path_for, StreamingConfig, StreamingResultsWriter};
+use lra_calculator_rust::utils::template::{FileNameValues, RESULTS_FORMAT_EXTENSION};
+use lra_calculator_rust::utils::{
+    check_free_space_for_results, extend_results_file, failures_path_for, remaining_path_for, get_folder_path_with_history, merge_results_files,
+    merge_entries, resolve_folder_argument, sort_entries, sort_results_file,
+    results_footer_lines, run_metadata_line, temp_path_for, validate_audio_file_path, validate_output_writable, verify_results_file,
+    write_results_header, AtomicFile, ExtraColumnTable, InvalidResultLine,
+    ResultsFile, ResultsSet, SortOrder, SortReport, TextFormat, RESULTS_FORMAT_VERSION, RESULTS_HEADER_LINE,
+};
+use lra_calculator_rust::environment::{check_environment_report, resolved_program, EnvironmentReport};
+use lra_calculator_rust::estimate::{estimate_run, RunEstimate, Throughput};
+use lra_calculator_rust::formats::FormatRegistry;
+use lra_calculator_rust::outcome::RunOutcome;
+use lra_calculator_rust::{get_version_info, SUPPORTED_EXTENSIONS};
+
+/// 并行处理结果列表：成功为测量结果，失败为文件错误
+type ProcessingResults = Vec<FileOutcome>;
 
-use chrono::Local;
+/// 结果文件中各条目的附加列（以制表符开头）
+type ExtraColumns = HashMap<FileKey, String>;
 
-use audio::{check_ffmpeg_availability, scan_audio_files};
-use processor::{analyze_results, display_processing_stats, process_files_parallel};
-use utils::{get_folder_path_from_user, sort_lra_results_file};
+/// 按显示路径索引的标签
+type KnownTags = BTreeMap<String, TrackTags>;
+
+/// 按显示路径索引的文件指纹，用于更新缓存
+type Fingerprints = HashMap<FileKey, FileFingerprint>;
+
+/// 准备好的处理计划：计划、探测时读到的标签、用时预估和格式统计
+type PreparedPlan = (ProcessingPlan, KnownTags, RunEstimate, ScanStatistics);
 
 
 /// 程序主入口函数 (Main Entry Point)
@@ -80,315 +164,2784 @@ use utils::{get_folder_path_from_user, sort_lra_results_file};
 /// - 权限不足但可以修复
 ///
 /// # 返回值
-/// - `Ok(())` - 程序成功执行完成，所有步骤都正常
-/// - `Err(Box<dyn std::error::Error>)` - 发生不可恢复的错误，程序需要终止
+/// - `ExitCode::SUCCESS` - 程序成功执行完成，所有步骤都正常
+/// - 没有需要处理的文件时输出成功信息，退出码由 [`RunOutcome::exit_code`] 决定
+/// - 其他退出码 - 发生不可恢复的错误，退出码由 [`AppError::exit_code`] 决定
 ///
 /// # 性能特性
 /// - 自动利用所有可用 CPU 核心进行并行处理
 /// - 内存使用量与文件数量成正比，通常保持在合理范围内
 /// - 支持处理大型音乐库（数万个文件）
 /// - 提供实时进度反馈，避免用户等待焦虑
-fn main() -> Result<(), Box<dyn std::error::Error>> {
-    // 1. 程序初始化和环境检查
-    display_welcome_message();
-    check_system_environment()?;
+fn main() -> ExitCode {
+    match run() {
+        Ok(RunOutcome::Completed) => ExitCode::SUCCESS,
+        Ok(outcome) => {
+            human_println!("✅ {}", outcome);
+            ExitCode::from(outcome.exit_code())
+        }
+        Err(e) => {
+            human_eprintln!("{}", error_color_mode().paint(Style::Failure, human_format!("❌ {}", e)));
+            if let Some(suggestions) = render_suggestions(&e.suggestions(), Language::Chinese) {
+                human_eprintln!("{}", suggestions);
+            }
+            ExitCode::from(e.exit_code())
+        }
+    }
+}
 
-    // 2. 获取用户输入和路径验证
-    let base_folder_path = get_user_input_with_validation()?;
+/// 解析命令行并分派子命令 (Parse Arguments and Dispatch Subcommand)
+///
+/// # 返回值
+/// - `Ok(RunOutcome)` - 子命令正常完成，或分析时没有需要处理的文件
+/// - `Err(AppError)` - 发生不可恢复的错误
+fn run() -> Result<RunOutcome, AppError> {
+    let options = parse_args(std::env::args().skip(1))?;
+    if options.show_help {
+        human_println!("{}", usage());
+        return Ok(RunOutcome::Completed);
+    }
 
-    // 3. 文件发现和预处理
-    let (files_to_process, results_file_path) = discover_and_prepare_files(&base_folder_path)?;
+    // 机器可读模式下 stdout 专用于事件流
+    if options.porcelain.is_some() {
+        if options.porcelain == Some(PorcelainFormat::Ndjson) && cfg!(not(feature = "serde")) {
+            return Err(AppError::Configuration(
+                "--porcelain 需要启用 serde 特性重新编译".to_string(),
+            ));
+        }
+        set_human_output_to_stderr(true);
+    }
+    // 结果输出到 stdout 时只有数据行写到 stdout
+    if options.output_to_stdout() {
+        set_human_output_to_stderr(true);
+    }
+    configure_colors(options.color);
+    configure_charset(options.charset);
+    if options.nice {
+        set_child_priority(ProcessPriority::Low);
+    }
+    configure_formats(&options)?;
+
+    match &options.command {
+        Command::Analyze => return run_analyze(&options),
+        Command::Sort { results_file } => run_sort(results_file, &options)?,
+        Command::Merge { inputs } => run_merge(inputs, &options)?,
+        Command::Stats { results_file, top } => run_stats(results_file, *top, options.lra_precision())?,
+        Command::CompareVersions { results_file } => run_compare_versions(results_file, &options)?,
+        Command::Check { full } => run_check(*full)?,
+        Command::File { paths, json } => run_file(paths, *json, &options)?,
+        Command::History { history_file } => run_history_command(history_file)?,
+    }
+    Ok(RunOutcome::Completed)
+}
+
+/// 配置音频格式注册表 (Configure Formats)
+///
+/// 在默认格式的基础上应用 `--add-ext` 和 `--remove-ext`，
+/// 并替换全局注册表，之后的扫描和格式判断都以它为准。
+///
+/// # 参数
+/// - `options` - 命令行选项
+///
+/// # 返回值
+/// - `Ok(())` - 配置完成
+/// - `Err(AppError::Configuration)` - 移除后没有任何支持的格式
+fn configure_formats(options: &CliOptions) -> Result<(), AppError> {
+    if options.add_extensions.is_empty() && options.remove_extensions.is_empty() {
+        return Ok(());
+    }
 
-    // 4. 并行处理和进度跟踪
-    let processing_results = execute_parallel_processing(files_to_process);
+    let mut registry = FormatRegistry::default();
+    for extension in &options.add_extensions {
+        registry.add(extension)?;
+    }
+    for extension in &options.remove_extensions {
+        registry.remove(extension)?;
+    }
 
-    // 5. 结果处理和输出
-    finalize_and_output_results(processing_results, &results_file_path)?;
+    if registry.is_empty() {
+        return Err(AppError::Configuration(
+            "--remove-ext 移除了所有支持的格式，没有可扫描的文件类型".to_string(),
+        ));
+    }
 
-    display_completion_message(&results_file_path);
+    FormatRegistry::set_global(registry);
     Ok(())
 }
 
-/// 显示欢迎信息 (Display Welcome Message)
+/// 执行完整的分析流程 (Run the Analyze Workflow)
 ///
-/// 显示程序的欢迎信息、版本信息和基本说明。
-/// 这有助于用户了解程序的功能和当前运行状态。
-fn display_welcome_message() {
-    println!("🎵 ==========================================");
-    println!("🎵   LRA 音频响度范围计算器");
-    println!("🎵   高性能版 - 基于 FFmpeg 直接分析");
-    println!("🎵 ==========================================");
-    println!("📅 启动时间: {}", Local::now().format("%Y-%m-%d %H:%M:%S"));
-    println!("🔧 基于 EBU R128 标准进行精确 LRA 计算");
-    println!("⚡ 支持多线程并行处理，充分利用 CPU 资源");
-    println!();
-}
-
-/// 检查系统环境 (Check System Environment)
+/// `analyze` 子命令，也是不带子命令时的默认行为。
 ///
-/// 验证程序运行所需的系统环境，主要是 FFmpeg 的可用性。
-/// 如果环境检查失败，程序将终止并提供详细的错误信息。
+/// # 参数
+/// - `options` - 命令行选项
 ///
 /// # 返回值
-/// - `Ok(())` - 系统环境检查通过
-/// - `Err(...)` - 环境检查失败，包含详细错误信息
-fn check_system_environment() -> Result<(), Box<dyn std::error::Error>> {
-    println!("🔍 正在检查系统环境...");
+/// - `Ok(RunOutcome::Completed)` - 所有步骤都正常完成
+/// - `Ok(RunOutcome::NothingToDo)` - 没有找到需要处理的音频文件
+/// - `Err(AppError)` - 发生不可恢复的错误
+fn run_analyze(options: &CliOptions) -> Result<RunOutcome, AppError> {
+    // 1. 程序初始化和环境检查
+    let started_at = Instant::now();
+    let run_id = RunId::generate_with(CLOCK.as_ref());
+    let advice = load_advice(options)?;
+    let show_banners = show_banners(options.quiet, human_output_is_terminal());
+    if show_banners {
+        human_println!("{}", welcome_banner(&run_id, local_now(), output_charset()));
+    }
+    let environment = check_system_environment()?;
 
-    match check_ffmpeg_availability() {
-        Ok(()) => {
-            println!("✅ 系统环境检查完成，所有依赖都已就绪");
-            Ok(())
+    // 2. 获取用户输入和路径验证
+    let base_folder_path = get_user_input_with_validation(options)?;
+    let outage = start_outage_breaker(&base_folder_path, options);
+    let early_abort = EarlyAbort::new(options.abort_after.unwrap_or(DEFAULT_ABORT_AFTER));
+
+    // 3. 确定结果文件并加锁，防止其他实例同时写入
+    let results_file_path = prepare_results_file(&base_folder_path, options)?;
+    // 输出到 stdout 或只输出计划时不写结果文件，也就不需要锁
+    let results_lock = match options.output_to_stdout() || options.dry_run {
+        true => None,
+        false => Some(ResultsLock::acquire(&results_file_path, options.force)?),
+    };
+    // 无论本次运行如何结束（出错、阈值违规、被中断），已经生成的文件都记录到生成文件清单
+    let artifacts = results_lock.is_some().then(|| RunArtifacts::new(&base_folder_path, &results_file_path, options));
+    let _artifact_guard = artifacts.clone().map(ArtifactGuard);
+    install_interrupt_handler(results_lock.as_ref().map(|lock| lock.path().to_path_buf()), artifacts);
+    let newer_than = resolve_newer_than(&results_file_path, options);
+    // 失败在发生时写入失败记录文件，统计中只保留最前和最后的若干条
+    let failures = RunFailures::start(&results_file_path, options, &run_id);
+    let ctx = RunContext {
+        options,
+        base_folder_path,
+        results_file_path,
+        run_id,
+        newer_than,
+        outage,
+        early_abort,
+        failures,
+        started_at,
+    };
+
+    // 4. 读取上次运行的结果缓存
+    // 缩混后的测量值与缓存中按原始声道测量的值不可比较，不读写缓存
+    let cache_path = (!options.no_cache && options.downmix.is_none()).then(|| cache_path_for(&ctx.results_file_path));
+    let mut previous_cache = cache_path.as_deref().map(|path| {
+        let mut cache = load_results_cache(path, environment.ffmpeg_version.as_deref(), options.cache_version_policy);
+        if options.also_downmixed || options.per_channel {
+            // 缓存不保存缩混和逐声道测量值，所有文件都需要重新分析
+            cache.entries.clear();
         }
-        Err(e) => {
-            eprintln!("❌ 系统环境检查失败: {}", e);
-            eprintln!("💡 请按照错误提示安装必要的依赖后重试");
-            Err(e.into())
+        if options.replaygain || options.thresholds.lufs_range.is_some() {
+            // 旧版本写入的条目没有综合响度，无法计算增益或检查响度范围，需要重新分析
+            cache.entries.retain(|_, entry| entry.integrated_lufs.is_some());
+        }
+        if options.true_peak {
+            // 没有以 --true-peak 分析过的条目没有真峰值
+            cache.entries.retain(|_, entry| entry.true_peak_dbtp.is_some());
+        }
+        if options.range_details {
+            // 旧版本写入的条目没有 LRA 上下限
+            cache.entries.retain(|_, entry| entry.lra_low_lufs.is_some());
         }
+        if let Some(algorithm) = options.hash {
+            // 没有该算法摘要的条目需要重新读取文件
+            cache.entries.retain(|_, entry| entry.content_hash.is_some_and(|hash| hash.algorithm() == algorithm));
+        }
+        cache
+    });
+
+    if let Some(dump_dir) = &options.debug_dump {
+        human_println!("🐛 每个文件的 FFmpeg 输出将写入: {}", dump_dir.display());
+    }
+    if needs_tags(options) && !environment.ffprobe_available {
+        human_eprintln!("⚠️  未找到 ffprobe，标签将全部为空");
+    }
+
+    // 5-7. 文件发现、跳过未变化的文件、并行处理
+    let mut throughput_history =
+        if options.no_history { ThroughputHistory::disabled() } else { ThroughputHistory::load_default() };
+    let selection = resolve_file_selection(options);
+    let nothing_to_do = |outcome: RunOutcome, results_lock: Option<ResultsLock>| {
+        drop(results_lock);
+        Ok(outcome)
+    };
+    let (outcome, known_tags, scan_statistics) = if options.pipeline {
+        match scan_and_process_pipelined(&ctx, previous_cache.as_ref())? {
+            ControlFlow::Continue(outcome) => (outcome, BTreeMap::new(), None),
+            ControlFlow::Break(outcome) => return nothing_to_do(outcome, results_lock),
+        }
+    } else {
+        let discovered =
+            discover_and_prepare_files(&ctx, selection, previous_cache.as_mut(), throughput_history.average())?;
+        let (plan, known_tags, estimate, statistics) = match discovered {
+            ControlFlow::Continue(discovered) => discovered,
+            ControlFlow::Break(outcome) => return nothing_to_do(outcome, results_lock),
+        };
+        if options.dry_run {
+            display_plan(&plan, options.lra_precision());
+            return Ok(RunOutcome::Completed);
+        }
+        let processing_started = Instant::now();
+        let outcome = execute_parallel_processing(plan, &ctx);
+        let failed = ctx.failures.stream.failed();
+        record_throughput(&mut throughput_history, &estimate, &outcome, failed, processing_started.elapsed());
+        (outcome, known_tags, Some(statistics))
+    };
+    if let (Some(cache_path), Some(previous_cache)) = (&cache_path, &previous_cache) {
+        update_results_cache(
+            cache_path,
+            previous_cache,
+            &outcome.fingerprints,
+            &outcome.results,
+            // 增量更新和限量/抽样运行没有看到所有文件，保留其余文件的缓存
+            ctx.newer_than.is_some() || outcome.selection.is_some(),
+        );
     }
+
+    // 8. 结果处理和输出（增量更新时与已有结果合并）
+    let finalized = finalize_and_output_results(&ctx, outcome, known_tags, scan_statistics);
+    drop(results_lock);
+    // 放弃时已完成的结果照常写出，退出码表明网络共享不可用而不是时间预算到期
+    if let Some(breaker) = ctx.outage.as_ref().filter(|breaker| breaker.gave_up()) {
+        return Err(AppError::Io(io::Error::other(format!(
+            "扫描根目录 {} 在 {} 内没有恢复访问，已放弃剩下的文件",
+            ctx.base_folder_path.display(),
+            format_duration(breaker.config().pause_budget)
+        ))));
+    }
+    // 环境问题导致的中止以 FFmpeg 错误退出，而不是时间预算到期
+    if let Some(reason) = ctx.early_abort.reason() {
+        return Err(AppError::Ffmpeg {
+            kind: FfmpegErrorKind::Aborted,
+            message: format!("{}；已中止，剩下的文件没有分析（--abort-after 0 关闭此检查）", reason),
+        });
+    }
+    finalized?;
+
+    display_completion_message(&ctx.results_file_path, &ctx.run_id, options, show_banners, advice.as_deref());
+    Ok(RunOutcome::Completed)
 }
 
-/// 获取用户输入并验证 (Get User Input with Validation)
+/// 确定结果文件路径 (Prepare Results File)
 ///
-/// 获取用户输入的文件夹路径，并进行完整的验证。
-/// 这个函数封装了用户交互逻辑，提供友好的错误处理。
+/// 结果文件默认位于扫描根目录下，可通过 `--output` 覆盖；`--output-name` 指定扫描根目录下的文件名模板，
+/// 在这里按当前时间和根目录名展开。扫描时按展开后的路径排除结果文件及其辅助文件。
+/// 在耗时的处理开始之前确认结果文件可以写入（`--dry-run` 时跳过）。
+///
+/// # 参数
+/// - `base_folder_path` - 扫描根目录
+/// - `options` - 命令行选项
 ///
 /// # 返回值
-/// - `Ok(PathBuf)` - 验证通过的文件夹路径
-/// - `Err(...)` - 用户输入无效或验证失败
-fn get_user_input_with_validation() -> Result<PathBuf, Box<dyn std::error::Error>> {
-    println!("📂 请选择要处理的音频文件夹...");
-
-    match get_folder_path_from_user() {
-        Ok(path) => {
-            println!("✅ 文件夹路径验证成功: {}", path.display());
-            Ok(path)
-        }
-        Err(e) => {
-            eprintln!("❌ 文件夹路径获取失败: {}", e);
-            Err(e)
+/// - `Ok(PathBuf)` - 结果文件路径
+/// - `Err(AppError::Path)` - 结果文件位置不可写
+/// - `Err(AppError::Configuration)` - 文件名模板展开后不是合法的文件名
+fn prepare_results_file(base_folder_path: &Path, options: &CliOptions) -> Result<PathBuf, AppError> {
+    // 输出到 stdout 时仍以默认结果文件路径推导缓存等辅助文件的位置
+    if options.output_to_stdout() {
+        return Ok(base_folder_path.join("lra_results.txt"));
+    }
+    let results_file_path = match (&options.output, &options.output_name) {
+        (Some(output), _) => output.clone(),
+        (None, Some(template)) => {
+            let now = local_now();
+            let (date, time) = (now.format("%Y-%m-%d").to_string(), now.format("%H%M%S").to_string());
+            let root = base_folder_path.file_name().map(|name| name.to_string_lossy()).unwrap_or_default();
+            let values = FileNameValues { date: &date, time: &time, root: &root, format: RESULTS_FORMAT_EXTENSION };
+            base_folder_path.join(template.expand(&values)?)
         }
+        (None, None) => base_folder_path.join("lra_results.txt"),
+    };
+
+    // --dry-run 不写入结果文件，也就不需要检查能否写入
+    if !options.dry_run {
+        validate_output_writable(&results_file_path)?;
     }
+    Ok(results_file_path)
 }
 
-/// 发现和准备文件 (Discover and Prepare Files)
+/// 确定增量更新的时间点 (Resolve Newer-Than)
 ///
-/// 扫描指定目录中的音频文件，并准备处理所需的数据结构。
-/// 这个函数还会创建结果文件路径并处理空目录的情况。
+/// `--newer-than-results` 使用现有结果文件的修改时间；结果文件不存在或
+/// 无法读取修改时间时退回完整扫描。
 ///
 /// # 参数
-/// - `base_folder_path` - 要扫描的基础文件夹路径
+/// - `results_file_path` - 结果文件路径
+/// - `options` - 命令行选项
 ///
 /// # 返回值
-/// - `Ok((Vec<(PathBuf, String)>, PathBuf))` - 文件列表和结果文件路径
-/// - `Err(...)` - 文件扫描或准备过程中的错误
-fn discover_and_prepare_files(
-    base_folder_path: &Path
-) -> Result<(Vec<(PathBuf, String)>, PathBuf), Box<dyn std::error::Error>> {
-    println!("🔍 正在递归扫描文件夹: {}", base_folder_path.display());
+/// - `Some(SystemTime)` - 只处理晚于该时刻修改的文件（增量更新模式）
+/// - `None` - 完整扫描
+fn resolve_newer_than(results_file_path: &Path, options: &CliOptions) -> Option<SystemTime> {
+    if !options.newer_than_results {
+        return options.newer_than;
+    }
+
+    match std::fs::metadata(results_file_path).and_then(|metadata| metadata.modified()) {
+        Ok(modified) => {
+            human_println!(
+                "🕒 只分析 {} 之后修改的文件",
+                chrono::DateTime::<Local>::from(modified).format("%Y-%m-%d %H:%M:%S")
+            );
+            Some(modified)
+        }
+        Err(_) => {
+            human_eprintln!(
+                "⚠️  无法读取结果文件 {} 的修改时间，将完整扫描所有文件",
+                results_file_path.display()
+            );
+            None
+        }
+    }
+}
 
-    let results_file_path = base_folder_path.join("lra_results.txt");
-    let files_to_process = scan_audio_files(base_folder_path, Some(&results_file_path));
+/// 安装 Ctrl+C 处理器 (Install Interrupt Handler)
+///
+/// 用户中断运行时删除结果文件锁，避免下次启动时被误判为有其他实例在运行，
+/// 并记录已经生成的文件（退出时不会执行 [`ArtifactGuard`]），然后以退出码 130 退出。
+///
+/// # 参数
+/// - `lock_path` - 需要清理的锁文件路径；输出到 stdout 时没有锁文件
+/// - `artifacts` - 需要记录的生成文件；不写结果文件时为 `None`
+fn install_interrupt_handler(lock_path: Option<PathBuf>, artifacts: Option<RunArtifacts>) {
+    let result = ctrlc::set_handler(move || {
+        if let Some(lock_path) = &lock_path {
+            let _ = std::fs::remove_file(lock_path);
+        }
+        if let Some(artifacts) = &artifacts {
+            artifacts.record();
+        }
+        human_eprintln!("\n⚠️  运行已被用户中断");
+        std::process::exit(130);
+    });
 
-    if files_to_process.is_empty() {
-        println!("⚠️  在指定路径下没有找到支持的音频文件");
-        println!("📝 创建空的结果文件...");
+    if let Err(e) = result {
+        human_eprintln!("⚠️  无法安装 Ctrl+C 处理器，中断时可能残留锁文件: {}", e);
+    }
+}
 
-        // 创建空的结果文件
-        let header_line = "文件路径 (相对) - LRA 数值 (LU)";
-        let mut writer = BufWriter::new(File::create(&results_file_path)?);
-        writeln!(writer, "{}", header_line)?;
-        writer.flush()?;
+/// 重新排序结果文件 (Sort Subcommand)
+///
+/// # 参数
+/// - `results_file` - 已有的结果文件
+/// - `options` - 命令行选项（`--sort`、`--no-verify`）；未指定 `--sort` 时沿用文件中记录的排序方式
+///
+/// # 返回值
+/// - `Ok(())` - 排序完成
+/// - `Err(AppError::Io)` - 文件读取、写入或校验失败
+fn run_sort(results_file: &Path, options: &CliOptions) -> Result<(), AppError> {
+    human_println!("\n📊 正在排序结果文件: {}", results_file.display());
+    let precision = options.lra_precision();
+    let report = sort_results_file(results_file, RESULTS_HEADER_LINE, options.sort_order, precision, !options.no_verify)
+        .map_err(|e| verification_failure(results_file, e))?;
+    display_sort_report(&report);
+    Ok(())
+}
 
-        println!("✅ 空结果文件已创建: {}", results_file_path.display());
-        return Err("没有找到要处理的音频文件".into());
+/// 显示排序报告 (Display Sort Report)
+///
+/// 无法解析的行（已原样保留）、格式升级、补上或移除的表头，以及写回的条目数量。
+fn display_sort_report(report: &SortReport) {
+    warn_invalid_lines(None, &report.invalid_lines);
+    if !report.invalid_lines.is_empty() {
+        human_println!(
+            "📋 解析完成: 成功 {} 行，保留 {} 行无法解析的内容",
+            report.entries, report.invalid_lines.len()
+        );
+    }
+    if let Some(version) = report.upgraded_from {
+        human_println!("📝 结果文件是格式版本 {}，写回时已升级到版本 {}", version, RESULTS_FORMAT_VERSION);
+    }
+    if report.added_header {
+        human_println!("📝 结果文件缺少表头，写回时已补上");
+    }
+    if report.removed_headers > 0 {
+        human_println!("🧹 移除了 {} 个重复的表头行", report.removed_headers);
     }
+    if report.entries == 0 {
+        human_println!("📝 结果文件为空或没有有效数据，创建仅包含表头的文件。");
+    } else {
+        human_println!("✅ 排序完成，共处理 {} 个条目", report.entries);
+    }
+}
 
-    println!(
-        "✅ 扫描完成，发现 {} 个音频文件待处理",
-        files_to_process.len()
-    );
+/// 警告结果文件中无法解析的行 (Warn Invalid Lines)
+///
+/// 这些行已作为批注原样保留，只是提示可能是损坏的数据。
+///
+/// # 参数
+/// - `file` - 行所在的文件；只读取一个文件、不需要区分时为 `None`
+/// - `invalid_lines` - 无法解析的行
+fn warn_invalid_lines(file: Option<&Path>, invalid_lines: &[InvalidResultLine]) {
+    for line in invalid_lines {
+        match file {
+            Some(file) => human_eprintln!("⚠️  警告 ({} {})，已原样保留", file.display(), line),
+            None => human_eprintln!("⚠️  警告 ({})，已原样保留", line),
+        }
+    }
+}
 
-    // 显示文件格式统计
-    display_file_format_statistics(&files_to_process);
+/// 合并结果文件 (Merge Subcommand)
+///
+/// # 参数
+/// - `inputs` - 要合并的结果文件
+/// - `options` - 命令行选项（`--output` 为输出路径）
+///
+/// # 返回值
+/// - `Ok(())` - 合并完成
+/// - `Err(AppError)` - 缺少输出路径或文件读写失败
+fn run_merge(inputs: &[PathBuf], options: &CliOptions) -> Result<(), AppError> {
+    let output = options.output.as_deref().ok_or_else(|| {
+        AppError::Configuration("子命令 'merge' 需要用 -o/--output 指定输出文件".to_string())
+    })?;
 
-    Ok((files_to_process, results_file_path))
+    human_println!("🔀 正在合并 {} 个结果文件...", inputs.len());
+    let precision = options.lra_precision();
+    let report =
+        merge_results_files(inputs, output, RESULTS_HEADER_LINE, options.text_format, precision, !options.no_verify)?;
+    for (input, line) in &report.invalid_lines {
+        warn_invalid_lines(Some(input), std::slice::from_ref(line));
+    }
+    human_println!("✅ 合并完成，共 {} 个条目: {}", report.entries, output.display());
+    Ok(())
 }
 
-/// 显示文件格式统计 (Display File Format Statistics)
+/// 显示结果文件的分布统计 (Stats Subcommand)
 ///
-/// 分析发现的音频文件，按格式进行统计并显示给用户。
-/// 这有助于用户了解文件库的组成情况。
+/// 只解析已有的结果文件，不会重新分析音频。
 ///
 /// # 参数
-/// - `files` - 发现的文件列表
-fn display_file_format_statistics(files: &[(PathBuf, String)]) {
-    use std::collections::HashMap;
+/// - `results_file` - 已有的结果文件
+/// - `top` - 额外以表格列出 LRA 最高和最低的各 N 个条目
+/// - `precision` - 表格中 LRA 值保留的小数位数
+///
+/// # 返回值
+/// - `Ok(())` - 统计完成
+/// - `Err(AppError::Io)` - 文件读取失败
+fn run_stats(results_file: &Path, top: Option<usize>, precision: u8) -> Result<(), AppError> {
+    let contents = ResultsFile::read(results_file)?.contents;
+    warn_invalid_lines(Some(results_file), &contents.invalid_lines);
+    let set = ResultsSet::from_contents(contents);
+    // 专辑条目不参与音轨级的统计
+    let tracks = ResultsSet::new(set.into_entries().into_iter().filter(|(path, _)| !is_album_entry(path)).collect());
+    let values: Vec<f64> = tracks.entries().iter().map(|(_, lra)| *lra).collect();
 
-    let mut format_counts: HashMap<String, usize> = HashMap::new();
+    match LraDistribution::from_values(&values) {
+        Some(distribution) => display_lra_distribution(&distribution),
+        None => {
+            human_println!("📝 结果文件中没有有效条目: {}", results_file.display());
+            return Ok(());
+        }
+    }
 
-    for (file_path, _) in files {
-        if let Some(extension) = file_path.extension().and_then(|ext| ext.to_str()) {
-            let ext_lower = extension.to_lowercase();
-            *format_counts.entry(ext_lower).or_insert(0) += 1;
+    if let Some(count) = top {
+        let sorted = tracks.sort(SortOrder::LraDescending);
+        let width = terminal_width();
+        if sorted.len() <= count * 2 {
+            let title = human_format!("📋 全部 {} 个文件 (按 LRA 从高到低)", sorted.len());
+            display_table(&title, sorted.entries(), width, precision);
+        } else {
+            let lowest: Vec<(FileKey, f64)> = sorted.entries().iter().rev().take(count).cloned().collect();
+            let highest = sorted.top_n(count);
+            display_table(&human_format!("📈 LRA 最高的 {} 个文件", count), highest.entries(), width, precision);
+            display_table(&human_format!("📉 LRA 最低的 {} 个文件", count), &lowest, width, precision);
         }
     }
+    Ok(())
+}
+
+/// 比较同名音轨 (Compare-Versions Subcommand)
+///
+/// 只解析已有的结果文件，不会重新分析音频。
+///
+/// # 参数
+/// - `results_file` - 已有的结果文件
+/// - `options` - 命令行选项（`--min-spread`、`--keep-track-numbers`）
+///
+/// # 返回值
+/// - `Ok(())` - 比较完成
+/// - `Err(AppError::Io)` - 文件读取失败
+fn run_compare_versions(results_file: &Path, options: &CliOptions) -> Result<(), AppError> {
+    let contents = ResultsFile::read(results_file)?.contents;
+    warn_invalid_lines(Some(results_file), &contents.invalid_lines);
+    display_version_comparison(&compare_versions(&contents.entries, &options.name_normalization()), options);
+    Ok(())
+}
 
-    println!("📊 文件格式统计:");
-    let mut formats: Vec<_> = format_counts.into_iter().collect();
-    formats.sort_by(|a, b| b.1.cmp(&a.1)); // 按数量降序排序
+/// 显示同名音轨的 LRA 差异 (Display Version Comparison)
+///
+/// 只列出差异不小于 `--min-spread` 的组，每组先显示差异和规范化后的名称，再逐行列出各版本。
+fn display_version_comparison(groups: &[VersionGroup], options: &CliOptions) {
+    let min_spread = options.min_spread.unwrap_or(DEFAULT_MIN_SPREAD);
+    let shown: Vec<&VersionGroup> = groups.iter().filter(|group| group.spread() >= min_spread).collect();
+    human_println!(
+        "\n🔁 找到 {} 组同名音轨，其中 {} 组的 LRA 差异不小于 {} LU",
+        groups.len(),
+        shown.len(),
+        min_spread
+    );
+    let precision = options.lra_precision();
+    for group in shown {
+        human_println!("\n  差异 {} LU  {}", LraValue(group.spread()).with_precision(precision), group.name);
+        for (path, lra) in &group.members {
+            human_println!("    {:>6}  {}", LraValue(*lra).with_precision(precision).to_string(), path);
+        }
+    }
+}
 
-    for (format, count) in formats {
-        println!("   {} 格式: {} 个文件", format.to_uppercase(), count);
+/// 输出带标题的结果表格
+fn display_table(title: &str, entries: &[(FileKey, f64)], width: usize, precision: u8) {
+    human_println!("\n{}", title);
+    for line in render_results_table(entries, width, precision) {
+        human_println!("{}", line);
     }
-    println!();
 }
 
-/// 执行并行处理 (Execute Parallel Processing)
+/// 显示运行历史 (History Subcommand)
 ///
-/// 启动多线程并行处理，计算所有音频文件的 LRA 值。
-/// 这是程序的核心处理阶段，会显示详细的进度信息。
+/// 按记录顺序列出每次运行的分布统计，"变化" 列为平均 LRA 相对上一条记录的差值。
+/// 无法解析的行只警告并跳过。
 ///
 /// # 参数
-/// - `files_to_process` - 要处理的文件列表
+/// - `history_file` - `--run-history` 写入的历史文件
 ///
 /// # 返回值
-/// - 处理结果列表，包含成功和失败的结果
-fn execute_parallel_processing(
-    files_to_process: Vec<(PathBuf, String)>
-) -> Vec<Result<(String, f64), crate::error::ProcessFileError>> {
-    println!("⚡ 开始并行处理阶段...");
+/// - `Ok(())` - 显示完成
+/// - `Err(AppError::Io)` - 文件读取失败
+fn run_history_command(history_file: &Path) -> Result<(), AppError> {
+    let history = read_run_history(history_file)?;
+    for line_number in &history.invalid_lines {
+        human_eprintln!("⚠️  运行历史文件第 {} 行无法解析，已跳过", line_number);
+    }
+    if history.records.is_empty() {
+        human_println!("📝 运行历史中没有记录: {}", history_file.display());
+        return Ok(());
+    }
 
-    let start_time = std::time::Instant::now();
-    let results = process_files_parallel(files_to_process);
-    let elapsed = start_time.elapsed();
+    let format_lra = |value: Option<f64>| value.map_or_else(|| "-".to_string(), |lra| format!("{:.1}", lra));
+    let mut previous_mean = None;
+    let rows: Vec<Vec<String>> = history
+        .records
+        .iter()
+        .map(|record| {
+            let change = match (previous_mean, record.mean_lra) {
+                (Some(previous), Some(mean)) => format!("{:+.1}", mean - previous),
+                _ => "-".to_string(),
+            };
+            previous_mean = record.mean_lra.or(previous_mean);
+            vec![
+                format_timestamp(record.timestamp),
+                record.files.to_string(),
+                record.failed.to_string(),
+                format_lra(record.mean_lra),
+                change,
+                format_lra(record.median_lra),
+                format_lra(record.std_dev_lra),
+                record.run_id.as_ref().map_or_else(|| "-".to_string(), ToString::to_string),
+                record.root.clone(),
+            ]
+        })
+        .collect();
 
-    println!("⏱️  并行处理耗时: {:.2} 秒", elapsed.as_secs_f64());
+    human_println!("📈 共 {} 次运行: {}", history.records.len(), history_file.display());
+    let headers = ["时间", "文件数", "失败", "平均 LRA", "变化", "中位数", "标准差", "运行 ID", "根目录"];
+    let alignments = [
+        Align::Left,
+        Align::Right,
+        Align::Right,
+        Align::Right,
+        Align::Right,
+        Align::Right,
+        Align::Right,
+        Align::Left,
+        Align::Left,
+    ];
+    for line in render_table(&headers, &alignments, &rows) {
+        human_println!("{}", line);
+    }
+    Ok(())
+}
 
-    results
+/// 把 Unix 秒格式化为本地时间
+fn format_timestamp(timestamp: u64) -> String {
+    let time = SystemTime::UNIX_EPOCH + std::time::Duration::from_secs(timestamp);
+    chrono::DateTime::<Local>::from(time).format("%Y-%m-%d %H:%M").to_string()
 }
 
-/// 完成处理并输出结果 (Finalize and Output Results)
+/// 直接分析音频文件 (File Subcommand)
 ///
-/// 分析处理结果，写入结果文件，并进行排序。
-/// 这是程序的最后阶段，负责生成最终的输出文件。
+/// 先验证所有路径，再并行分析，按参数顺序为每个文件输出一段简短的报告（`--json` 时输出一行 JSON）。
+/// 不扫描目录，也不读写结果文件和缓存。
 ///
 /// # 参数
-/// - `processing_results` - 并行处理的结果
-/// - `results_file_path` - 结果文件路径
+/// - `paths` - 要分析的音频文件
+/// - `json` - 是否以 JSON 输出测量结果
+/// - `options` - 命令行选项（分析相关的选项同样生效，如 `--true-peak`、`--per-channel`）
 ///
 /// # 返回值
-/// - `Ok(())` - 结果处理成功
-/// - `Err(...)` - 文件写入或排序失败
-fn finalize_and_output_results(
-    processing_results: Vec<Result<(String, f64), crate::error::ProcessFileError>>,
-    results_file_path: &Path
-) -> Result<(), Box<dyn std::error::Error>> {
-    println!("📊 正在分析处理结果...");
+/// - `Ok(())` - 所有文件都分析成功
+/// - `Err(AppError::Path)` - 某个路径不是支持的音频文件
+/// - `Err(AppError::FileProcessing)` - 只有一个文件且分析失败
+/// - `Err(AppError::PartiallyCompleted)` - 多个文件中有文件分析失败
+fn run_file(paths: &[PathBuf], json: bool, options: &CliOptions) -> Result<(), AppError> {
+    if json && cfg!(not(feature = "serde")) {
+        return Err(AppError::Configuration("--json 需要启用 serde 特性重新编译".to_string()));
+    }
+    for path in paths {
+        validate_audio_file_path(path)?;
+    }
+    let ffmpeg_path = check_ffmpeg_availability()?;
+    if options.verbose {
+        human_println!("✓ 使用 FFmpeg: {}", ffmpeg_path.display());
+    }
 
-    // 分析结果
-    let (stats, successful_results) = analyze_results(processing_results);
+    let files: Vec<(PathBuf, String)> = paths.iter().map(|path| (path.clone(), path.display().to_string())).collect();
+    let outcomes = with_worker_pool(options, || {
+        let (calculation_options, run) = (calculation_options(options), RunOptions::default());
+        process_files_parallel_with_options(files, ResultOrder::InputOrder, &calculation_options, &run, &|_: &RunEvent| {})
+    });
 
-    // 显示统计信息
-    display_processing_stats(&stats);
+    let total = outcomes.len();
+    let mut failures = Vec::new();
+    for outcome in outcomes {
+        match outcome.into_result() {
+            Ok(measurement) if json => emit_measurement_json(&measurement),
+            Ok(measurement) => display_file_report(&measurement, options.lra_precision()),
+            Err(error) => failures.push(error),
+        }
+    }
 
-    // 写入结果文件
-    write_initial_results_file(results_file_path, &successful_results)?;
+    match failures.len() {
+        0 => Ok(()),
+        _ if total == 1 => Err(AppError::FileProcessing(failures.remove(0))),
+        failed => {
+            for error in &failures {
+                human_eprintln!("{}", error_color_mode().paint(Style::Failure, human_format!("❌ {}", error)));
+            }
+            Err(AppError::PartiallyCompleted { successful: total - failed, failed })
+        }
+    }
+}
 
-    // 排序结果文件
-    if stats.successful > 0 {
-        sort_results_file_if_needed(results_file_path, &stats)?;
-    } else {
-        println!("📝 没有成功处理的文件，跳过排序步骤");
+/// 输出单个文件的测量报告：第一行是路径和 LRA，第二行列出其余可用的测量值
+fn display_file_report(measurement: &FileMeasurement, precision: u8) {
+    human_println!("🎵 {}  LRA: {} LU", measurement.path, LraValue(measurement.lra).with_precision(precision));
+
+    let mut details = Vec::new();
+    if let Some(integrated) = measurement.integrated_lufs {
+        details.push(format!("综合响度 {:.1} LUFS", integrated));
+    }
+    if let (Some(low), Some(high)) = (measurement.lra_low_lufs, measurement.lra_high_lufs) {
+        details.push(format!("范围 {:.1} ~ {:.1} LUFS", low, high));
+    }
+    if let Some(true_peak) = measurement.true_peak_dbtp {
+        details.push(format!("真峰值 {:.1} dBTP", true_peak));
+    }
+    if let Some(downmixed) = measurement.downmixed_lra {
+        details.push(format!("缩混 LRA {} LU", LraValue(downmixed).with_precision(precision)));
+    }
+    if let Some(channels) = &measurement.channels {
+        let lras: Vec<String> =
+            channels.iter().map(|channel| LraValue(channel.lra).with_precision(precision).to_string()).collect();
+        details.push(format!("各声道 LRA {} LU", lras.join("/")));
+    }
+    if let Some(duration) = measurement.audio_duration {
+        details.push(format!("时长 {}", format_duration(duration)));
+    }
+    if let Some(elapsed) = measurement.analysis_time {
+        details.push(format!("分析用时 {:.2} 秒", elapsed.as_secs_f64()));
     }
+    if !details.is_empty() {
+        human_println!("   {}", details.join(&human_format!(" · ")));
+    }
+    for warning in &measurement.warnings {
+        human_println!("   ⚠️  FFmpeg 警告: {}", warning);
+    }
+}
 
-    Ok(())
+/// 把测量结果序列化为一行 JSON 写入 stdout
+#[cfg(feature = "serde")]
+fn emit_measurement_json(measurement: &FileMeasurement) {
+    match measurement.to_json_line() {
+        Ok(line) => {
+            let mut stdout = std::io::stdout().lock();
+            let _ = writeln!(stdout, "{}", line);
+            let _ = stdout.flush();
+        }
+        Err(e) => human_eprintln!("⚠️  无法序列化测量结果: {}", e),
+    }
 }
 
-/// 写入初始结果文件 (Write Initial Results File)
-///
-/// 将成功处理的结果写入文件，包含表头和数据行。
+#[cfg(not(feature = "serde"))]
+fn emit_measurement_json(_measurement: &FileMeasurement) {}
+
+/// 检查运行环境 (Check Subcommand)
 ///
 /// # 参数
-/// - `results_file_path` - 结果文件路径
-/// - `successful_results` - 成功处理的结果列表
+/// - `full` - 是否额外执行分析自检（见 [`self_test`]）
 ///
 /// # 返回值
-/// - `Ok(())` - 写入成功
-/// - `Err(...)` - 写入失败
-fn write_initial_results_file(
-    results_file_path: &Path,
-    successful_results: &[(String, f64)]
-) -> Result<(), Box<dyn std::error::Error>> {
-    println!("📝 正在写入结果文件...");
+/// - `Ok(())` - 环境检查通过
+/// - `Err(AppError)` - 环境检查失败
+fn run_check(full: bool) -> Result<(), AppError> {
+    human_println!("🔍 {} 环境诊断", get_version_info());
 
-    let header_line = "文件路径 (相对) - LRA 数值 (LU)";
-    let mut writer = BufWriter::new(File::create(results_file_path)?);
+    let report = check_environment_report();
+    let optional = |value: Option<String>| value.unwrap_or_else(|| "未知".to_string());
+    let yes_no = |value: bool| if value { "是" } else { "否" };
 
-    writeln!(writer, "{}", header_line)?;
-    for (path_str, lra) in successful_results {
-        writeln!(writer, "{} - {:.1}", path_str, lra)?;
-    }
-    writer.flush()?;
+    human_println!("   FFmpeg 状态: {}", report.ffmpeg_status);
+    human_println!(
+        "   FFmpeg 路径: {}",
+        optional(report.ffmpeg_path.as_ref().map(|path| path.display().to_string()))
+    );
+    human_println!("   FFmpeg 版本: {}", optional(report.ffmpeg_version.clone()));
+    human_println!(
+        "   ebur128 滤波器: {}",
+        optional(report.ebur128_available.map(|available| yes_no(available).to_string()))
+    );
+    human_println!("   ffprobe 可用: {}", yes_no(report.ffprobe_available));
+    human_println!("   CPU 核心数: {}", optional(report.cpu_count.map(|count| count.to_string())));
+    human_println!(
+        "   支持的格式: {}",
+        FormatRegistry::global().extensions().collect::<Vec<_>>().join(", ")
+    );
+
+    report.ensure_ready()?;
+    human_println!("✅ 环境检查通过");
 
-    println!("✅ 结果文件写入完成");
+    if full {
+        human_println!("🧪 正在执行分析自检 (生成 {} 秒测试音频)...", SELF_TEST_DURATION_SECS);
+        let lra = self_test()?;
+        human_println!("✅ 分析自检通过，测试音频 LRA: {:.1} LU", lra);
+    }
     Ok(())
 }
 
-/// 根据需要排序结果文件 (Sort Results File If Needed)
+/// 读取自定义的 LRA 使用建议 (Load Advice)
 ///
-/// 对结果文件进行排序，并处理可能的排序错误。
+/// 在开始分析之前读取 `--advice-file`，文件不可读时立即报错，而不是分析完成后才发现。
+///
+/// # 返回值
+/// - `Ok(Some(String))` - 文件内容
+/// - `Ok(None)` - 没有指定 `--advice-file`，使用默认建议
+/// - `Err(AppError::Configuration)` - 文件无法读取
+fn load_advice(options: &CliOptions) -> Result<Option<String>, AppError> {
+    let Some(path) = &options.advice_file else {
+        return Ok(None);
+    };
+    std::fs::read_to_string(path).map(Some).map_err(|e| {
+        AppError::Configuration(format!("无法读取 --advice-file 文件 '{}': {}", path.display(), e))
+    })
+}
+
+/// 检查系统环境 (Check System Environment)
+///
+/// 验证程序运行所需的系统环境，主要是 FFmpeg 的可用性，
+/// 并显示环境报告中的关键信息。
+/// 如果环境检查失败，程序将终止并提供详细的错误信息。
+///
+/// # 返回值
+/// - `Ok(EnvironmentReport)` - 系统环境检查通过，返回环境报告
+/// - `Err(...)` - 环境检查失败，包含详细错误信息
+fn check_system_environment() -> Result<EnvironmentReport, AppError> {
+    human_println!("🔍 正在检查系统环境...");
+
+    let report = check_environment_report();
+    if let Err(e) = report.ensure_ready() {
+        human_eprintln!("💡 请按照错误提示安装必要的依赖后重试");
+        return Err(e);
+    }
+
+    display_environment_summary(&report);
+    human_println!("✅ 系统环境检查完成，所有依赖都已就绪");
+    Ok(report)
+}
+
+/// 显示环境摘要 (Display Environment Summary)
 ///
 /// # 参数
-/// - `results_file_path` - 结果文件路径
-/// - `stats` - 处理统计信息
+/// - `report` - 运行环境报告
+fn display_environment_summary(report: &EnvironmentReport) {
+    match &report.ffmpeg_version {
+        Some(version) => human_println!("✓ FFmpeg 检测成功 (版本: {})", version),
+        None => human_println!("✓ FFmpeg 检测成功"),
+    }
+    if let Some(ffmpeg_path) = &report.ffmpeg_path {
+        human_println!("✓ 使用 FFmpeg: {}", ffmpeg_path.display());
+    }
+    if let Some(cpu_count) = report.cpu_count {
+        human_println!("✓ 可用 CPU 核心数: {}", cpu_count);
+    }
+}
+
+/// 获取用户输入并验证 (Get User Input with Validation)
+///
+/// 获取用户输入的文件夹路径，并进行完整的验证。
+/// 这个函数封装了用户交互逻辑，提供友好的错误处理。
+/// 如果命令行已指定文件夹则直接验证，不再询问。
+/// 除非指定了 `--no-history`，否则会提供最近使用的文件夹供选择。
+///
+/// # 参数
+/// - `options` - 命令行选项
 ///
 /// # 返回值
-/// - `Ok(())` - 排序成功或跳过
-/// - `Err(...)` - 排序失败
-fn sort_results_file_if_needed(
-    results_file_path: &Path,
-    stats: &crate::processor::ProcessingStats
-) -> Result<(), Box<dyn std::error::Error>> {
-    println!("🔄 正在对结果文件进行排序...");
-
-    let header_line = "文件路径 (相对) - LRA 数值 (LU)";
-    match sort_lra_results_file(results_file_path, header_line) {
-        Ok(()) => {
-            println!("✅ 结果文件排序完成");
-            Ok(())
-        }
-        Err(e) => {
-            eprintln!(
-                "⚠️  排序结果文件失败: {}\n\
-                 📝 原始结果文件仍然可用: {}",
-                e,
-                results_file_path.display()
-            );
-            // 排序失败不应该导致整个程序失败
-            Ok(())
-        }
+/// - `Ok(PathBuf)` - 验证通过的文件夹路径
+/// - `Err(...)` - 用户输入无效或验证失败
+fn get_user_input_with_validation(options: &CliOptions) -> Result<PathBuf, AppError> {
+    // 命令行已指定文件夹时跳过交互提示
+    if let Some(folder) = &options.folder {
+        let path = resolve_folder_argument(folder)?;
+        human_println!("✅ 文件夹路径验证成功: {}", path.display());
+        return Ok(path);
     }
+
+    human_println!("📂 请选择要处理的音频文件夹...");
+
+    let mut history = if options.no_history {
+        PathHistory::disabled()
+    } else {
+        PathHistory::load_default()
+    };
+
+    // 错误由 main 统一输出，这里不再重复打印
+    let path = get_folder_path_with_history(&mut history)?;
+    human_println!("✅ 文件夹路径验证成功: {}", path.display());
+    Ok(path)
 }
 
-/// 显示完成信息 (Display Completion Message)
+/// 发现和准备文件 (Discover and Prepare Files)
 ///
-/// 显示程序完成的信息，包括结果文件位置和使用建议。
+/// 扫描指定目录中的音频文件（[`scan_with_progress`]），按内容识别没有扩展名的文件后
+/// 生成处理计划（[`ScanResult::into_plan`]）。这个函数还会处理空目录的情况。
 ///
 /// # 参数
-/// - `results_file_path` - 结果文件路径
-fn display_completion_message(results_file_path: &Path) {
-    println!("\n🎉 ==========================================");
-    println!("🎉   所有操作已成功完成！");
-    println!("🎉 ==========================================");
-    println!("📄 结果文件位置: {}", results_file_path.display());
-    println!("📊 文件已按 LRA 值从高到低排序");
-    println!("💡 使用建议:");
-    println!("   • LRA > 15 LU: 动态范围丰富（古典、爵士）");
-    println!("   • LRA 8-15 LU: 适中动态范围（摇滚、民谣）");
-    println!("   • LRA < 8 LU: 动态范围较小（流行、播客）");
-    println!("⏰ 完成时间: {}", Local::now().format("%Y-%m-%d %H:%M:%S"));
-    println!("🎵 感谢使用 LRA 计算器！");
+/// - `ctx` - 本次运行的上下文（扫描根目录、扫描时排除的结果文件、增量更新的时间阈值、记录在计划中的运行 ID）
+/// - `selection` - `--limit` / `--sample` 时只分析选中的文件
+/// - `cache` - 结果缓存，用于复用和保存探测结果以及划出未变化的文件；`--no-cache` 时为 `None`
+/// - `throughput` - 以往运行的平均速度，用于估算用时；没有历史时按默认速度粗略估计
+///
+/// # 返回值
+/// - `Ok((ProcessingPlan, 标签, 预估, 统计))` - 处理计划（增量更新且没有新文件时为空），
+///   探测时由 ffprobe 顺便读到的标签，需要分析的文件的用时预估，以及扫描到的文件的格式统计
+/// - `Err(...)` - 文件扫描或准备过程中的错误
+fn discover_and_prepare_files(
+    ctx: &RunContext,
+    selection: Option<FileSelection>,
+    mut cache: Option<&mut ResultsCache>,
+    throughput: Option<Throughput>,
+) -> Result<ControlFlow<RunOutcome, PreparedPlan>, AppError> {
+    let options = ctx.options;
+    let sink = CliSink::new(options);
+    let scan_options = build_scan_options(&ctx.results_file_path, options, ctx.newer_than);
+    let mut scanned = scan_with_events(&ctx.base_folder_path, &scan_options, &sink);
+    display_scan_summary(&scanned.report);
+
+    let known_tags = if options.probe_unknown {
+        probe_extensionless_files(&mut scanned.report, cache.as_deref_mut())
+    } else {
+        BTreeMap::new()
+    };
+
+    if scanned.report.files.is_empty() {
+        if let Some(outcome) = handle_empty_scan(ctx, &scanned.report)? {
+            return Ok(ControlFlow::Break(outcome));
+        }
+        let plan = scanned.into_plan(&PlanOptions { run_id: Some(&ctx.run_id), ..PlanOptions::default() });
+        return Ok(ControlFlow::Continue((plan, known_tags, estimate_run(0, 0, throughput), ScanStatistics::default())));
+    }
+
+    let (found, scanned_bytes) = (scanned.report.files.len(), scanned.report.total_bytes);
+    let statistics = ScanStatistics::from_files(&scanned.report.files).with_total_bytes(scanned_bytes);
+    let plan = scanned.into_plan(&PlanOptions { selection, cache: cache.as_deref(), run_id: Some(&ctx.run_id) });
+    if let Some(report) = &plan.selection {
+        human_println!("🎲 {}", report);
+    }
+
+    // 与流水线模式一致：只计入需要分析的文件，命中缓存的文件不会产生进度事件
+    sink.emit(&RunEvent::ScanFinished { total: plan.files.len() });
+
+    if let Some(warning) = check_free_space_for_results(&ctx.results_file_path, plan.len()) {
+        human_eprintln!("⚠️  {}", warning);
+    }
+
+    // 显示文件格式统计
+    display_file_format_statistics(&statistics);
+
+    if !plan.reused.is_empty() {
+        human_println!(
+            "♻️  {} 个文件未变化，复用缓存结果；{} 个文件需要分析",
+            plan.reused.len(),
+            plan.files.len()
+        );
+    }
+    let estimate = estimate_run(plan.files.len(), bytes_to_analyze(&plan, found, scanned_bytes), throughput);
+    if estimate.files > 0 {
+        human_println!("⏳ {}", estimate);
+    }
+
+    Ok(ControlFlow::Continue((plan, known_tags, estimate, statistics)))
+}
+
+/// 需要分析的文件的总大小 (Bytes to Analyze)
+///
+/// 使用缓存时计划中的文件都带有指纹，直接求和；否则（或有文件无法读取元数据时）
+/// 按文件数比例从扫描到的总大小折算，限量、抽样和复用缓存的文件都不计入。
+fn bytes_to_analyze(plan: &ProcessingPlan, found: usize, scanned_bytes: u64) -> u64 {
+    let fingerprinted: Option<u64> = plan.files.iter().map(|file| file.fingerprint.map(|fingerprint| fingerprint.size)).sum();
+    fingerprinted.unwrap_or_else(|| (scanned_bytes as f64 * plan.files.len() as f64 / found.max(1) as f64) as u64)
+}
+
+/// 记录本次运行的分析速度 (Record Throughput)
+///
+/// 只计入实际分析的文件（不含复用缓存和没有开始的文件），字节数按预估中的平均文件大小折算。
+/// 失败的文件已经交给失败流，不在结果中，由 `failed` 给出。
+/// 保存失败只会让下一次的预估不准，静默忽略。
+fn record_throughput(
+    history: &mut ThroughputHistory,
+    estimate: &RunEstimate,
+    outcome: &ProcessingOutcome,
+    failed: usize,
+    elapsed: std::time::Duration,
+) {
+    let analyzed_results = &outcome.results[..outcome.results.len() - outcome.reused];
+    let analyzed = failed + analyzed_results.iter().filter(|result| !matches!(result, FileOutcome::Skipped { .. })).count();
+    if analyzed == 0 {
+        return;
+    }
+    let bytes = (estimate.total_bytes as f64 * analyzed as f64 / estimate.files.max(1) as f64) as u64;
+    history.record(analyzed, bytes, elapsed);
+    let _ = history.save();
+}
+
+/// 输出处理计划 (Display Plan)
+///
+/// `--dry-run` 使用：列出需要分析和复用缓存结果的文件，不分析任何文件，也不写入结果文件和缓存。
+/// 复用的 LRA 值保留 `precision` 位小数。
+fn display_plan(plan: &ProcessingPlan, precision: u8) {
+    human_println!(
+        "\n📋 处理计划: {} 个文件需要分析，{} 个文件复用缓存结果",
+        plan.files.len(),
+        plan.reused.len()
+    );
+    for file in &plan.files {
+        human_println!("   分析  {}", file.display_path);
+    }
+    for file in &plan.reused {
+        human_println!("   复用  {} ({} LU)", file.display_path, LraValue(file.entry.lra).with_precision(precision));
+    }
+    human_println!("\n🔎 --dry-run: 没有分析任何文件，也没有写入结果文件");
+}
+
+/// 确定文件选择方式 (Resolve File Selection)
+///
+/// `--sample` 未指定 `--seed` 时由当前时间生成种子，并在输出中显示，便于重现。
+///
+/// # 参数
+/// - `options` - 命令行选项
+///
+/// # 返回值
+/// - `Some(FileSelection)` - 设置了 `--limit` 或 `--sample`
+/// - `None` - 处理所有文件
+fn resolve_file_selection(options: &CliOptions) -> Option<FileSelection> {
+    if let Some(count) = options.limit {
+        return Some(FileSelection::Limit(count));
+    }
+    let count = options.sample?;
+    Some(FileSelection::Sample { count, seed: options.seed.unwrap_or_else(random_seed) })
+}
+
+/// 确定调度顺序 (Resolve Schedule Order)
+///
+/// `--shuffle` 未指定 `--seed` 时由当前时间生成种子，并在输出中显示，便于重现。
+fn resolve_schedule_order(options: &CliOptions) -> ScheduleOrder {
+    if !options.shuffle {
+        return ScheduleOrder::InputOrder;
+    }
+    let seed = options.seed.unwrap_or_else(random_seed);
+    human_println!("🔀 处理顺序已随机打乱（用 --seed {} 可重现）", seed);
+    ScheduleOrder::Shuffle { seed }
+}
+
+/// 本次运行的时钟：默认是系统时钟，设置了 `LRA_FAKE_TIME` 时固定在该时间（见 [`clock_from_env`]）
+static CLOCK: LazyLock<Box<dyn Clock>> = LazyLock::new(|| {
+    clock_from_env().unwrap_or_else(|error| {
+        human_eprintln!("⚠️  {}，使用系统时间", error);
+        Box::new(SystemClock)
+    })
+});
+
+/// 按本次运行的时钟取当前的本地时间
+fn local_now() -> DateTime<Local> {
+    DateTime::<Local>::from(CLOCK.now())
+}
+
+/// 未指定 `--seed` 时使用的随机种子（固定时钟下也是固定的）
+fn random_seed() -> u64 {
+    CLOCK.now().duration_since(SystemTime::UNIX_EPOCH).map_or(0, |elapsed| elapsed.as_nanos() as u64)
+}
+
+/// 边扫描边处理 (Scan and Process Pipelined)
+///
+/// `--pipeline` 模式：扫描线程发现的文件立即交给工作线程分析，
+/// 命中缓存的文件在扫描线程中直接跳过。由于事先不知道文件总数，
+/// 该模式不做磁盘空间预估，也不显示文件格式统计。
+///
+/// # 参数
+/// - `ctx` - 本次运行的上下文；失败交给其中的失败流，不在返回的结果中
+/// - `cache` - 结果缓存；`--no-cache` 时为 `None`
+///
+/// # 返回值
+/// - `Ok(ControlFlow::Continue(ProcessingOutcome))` - 全部结果（包括复用的缓存结果）、用于更新缓存的指纹和扫描时被改名的文件
+/// - `Ok(ControlFlow::Break(RunOutcome::NothingToDo))` - 没有找到任何音频文件
+fn scan_and_process_pipelined(
+    ctx: &RunContext,
+    cache: Option<&ResultsCache>,
+) -> Result<ControlFlow<RunOutcome, ProcessingOutcome>, AppError> {
+    let options = ctx.options;
+    human_println!("⚡ 边扫描边分析...");
+    let scan_options = build_scan_options(&ctx.results_file_path, options, ctx.newer_than);
+
+    let (cancellation, _deadline) = start_deadline(options);
+    let (concurrency, _monitor) = start_adaptive_concurrency(options);
+    let stream = start_streaming(&ctx.results_file_path, options);
+    let calculation_options = ctx.calculation_options();
+    let run = RunOptions { cancellation, concurrency, ..ctx.run_options() };
+    let start_time = std::time::Instant::now();
+    let (scan_report, outcome) = with_worker_pool(options, || {
+        scan_and_execute(
+            &ctx.base_folder_path,
+            &scan_options,
+            cache,
+            &calculation_options,
+            &run,
+            &CliSink { options, stream: stream.as_ref() },
+        )
+    });
+    finish_streaming(stream);
+    human_println!("⏱️  并行处理耗时: {:.2} 秒", start_time.elapsed().as_secs_f64());
+
+    display_scan_summary(&scan_report);
+    if outcome.reused > 0 {
+        human_println!(
+            "♻️  {} 个文件未变化，复用了缓存结果；分析了 {} 个文件",
+            outcome.reused,
+            outcome.results.len() - outcome.reused
+        );
+    }
+
+    if outcome.results.is_empty() && ctx.failures.stream.failed() == 0 {
+        if let Some(run_outcome) = handle_empty_scan(ctx, &scan_report)? {
+            return Ok(ControlFlow::Break(run_outcome));
+        }
+    }
+    Ok(ControlFlow::Continue(outcome))
+}
+
+/// 构建扫描选项 (Build Scan Options)
+fn build_scan_options(
+    results_file_path: &Path,
+    options: &CliOptions,
+    newer_than: Option<SystemTime>,
+) -> ScanOptions {
+    ScanOptions {
+        exclude: build_exclude_set(results_file_path, options),
+        min_size: options.min_size,
+        max_size: options.max_size,
+        newer_than,
+        only: options.only_patterns.clone(),
+        dedup_case_aliases: options.dedup_case,
+    }
+}
+
+/// 显示扫描摘要：无法读取的路径、跳过的目录和被过滤的文件
+fn display_scan_summary(scan_report: &ScanReport) {
+    display_unreadable_paths(&scan_report.unreadable_paths);
+    if scan_report.pruned_dirs > 0 {
+        human_println!("⏭️  跳过了 {} 个无关目录", scan_report.pruned_dirs);
+    }
+    if scan_report.filtered_by_size > 0 {
+        human_println!("⏭️  按文件大小跳过了 {} 个音频文件", scan_report.filtered_by_size);
+    }
+    if scan_report.filtered_by_mtime > 0 {
+        human_println!("⏭️  跳过了 {} 个未修改的音频文件", scan_report.filtered_by_mtime);
+    }
+    if scan_report.filtered_by_pattern > 0 {
+        human_println!("⏭️  跳过了 {} 个文件名不匹配 --only 的音频文件", scan_report.filtered_by_pattern);
+    }
+    if scan_report.filtered_by_exclude > 0 {
+        human_println!("⏭️  跳过了 {} 个文件名匹配 --exclude 的音频文件", scan_report.filtered_by_exclude);
+    }
+    for pattern in scan_report.unmatched_patterns() {
+        human_eprintln!("⚠️  --only 模式 '{}' 没有匹配任何音频文件", pattern);
+    }
+    display_case_aliases(&scan_report.case_aliases);
+}
+
+/// 处理没有找到音频文件的情况 (Handle Empty Scan)
+///
+/// 增量更新时没有新文件是正常情况，保留已有结果；否则创建只有表头的结果文件（`--no-empty-results` 时不创建）
+/// 并报告原因。输出到 stdout 时只向 stdout 输出表头，不创建任何文件。表头之后记录 `run_id` 和使用的 FFmpeg。
+///
+/// # 返回值
+/// - `Ok(None)` - 增量更新且没有新增或修改的文件，继续与已有结果合并
+/// - `Ok(Some(RunOutcome::NothingToDo))` - 没有找到任何音频文件
+/// - `Err(AppError::Io)` - 写入表头失败
+fn handle_empty_scan(ctx: &RunContext, scan_report: &ScanReport) -> Result<Option<RunOutcome>, AppError> {
+    let (options, results_file_path, run_id) = (ctx.options, &ctx.results_file_path, &ctx.run_id);
+    if ctx.newer_than.is_some() && scan_report.filtered_by_mtime > 0 {
+        human_println!("✅ 自上次运行以来没有新增或修改的音频文件");
+        return Ok(None);
+    }
+
+    let Some(outcome) = RunOutcome::from_scan(&ctx.base_folder_path, scan_report) else {
+        return Ok(None);
+    };
+    if options.output_to_stdout() {
+        let mut stdout = std::io::stdout().lock();
+        let ffmpeg_path = resolved_program("ffmpeg");
+        write_results_header(&mut stdout, options.text_format, RESULTS_HEADER_LINE, Some(run_id), ffmpeg_path.as_deref())?;
+        stdout.flush()?;
+    } else if !options.dry_run && !options.no_empty_results {
+        human_println!("📝 创建空的结果文件...");
+
+        // 创建空的结果文件
+        let format = options.text_format;
+        let mut writer = format.create_file(results_file_path)?;
+        let ffmpeg_path = resolved_program("ffmpeg");
+        write_results_header(&mut writer, format, RESULTS_HEADER_LINE, Some(run_id), ffmpeg_path.as_deref())?;
+        writer.flush()?;
+
+        human_println!("✅ 空结果文件已创建: {}", results_file_path.display());
+    }
+    Ok(Some(outcome))
+}
+
+/// 构建扫描排除集合 (Build Exclude Set)
+///
+/// 排除历史结果文件（`lra_results*`、`lra_errors*`），以及本次运行会生成的
+/// 结果文件、失败记录、缓存、锁文件和 JSON 统计，无论它们被配置为什么名字。
+/// 默认还会剪枝 `.git`、`node_modules` 等无关目录（`--no-default-excludes` 关闭），
+/// 并加上 `--exclude-dir` 指定的目录名和 `--exclude` 指定的文件名模式。
+///
+/// # 参数
+/// - `results_file_path` - 结果文件路径
+/// - `options` - 命令行选项
+///
+/// # 返回值
+/// - 扫描时使用的排除集合
+fn build_exclude_set(results_file_path: &Path, options: &CliOptions) -> ExcludeSet {
+    let mut exclude = ExcludeSet::with_default_patterns();
+    // 扫描时锁文件已经由本次运行创建，不算作目录中已有的生成文件
+    let lock_path = lock_path_for(results_file_path);
+    for path in artifact_paths(results_file_path, options) {
+        if path == lock_path {
+            exclude.add_transient_path(path);
+        } else {
+            exclude.add_path(path);
+        }
+    }
+    if !options.no_default_excludes {
+        exclude.add_default_pruned_dirs();
+    }
+    for dir_name in &options.exclude_dirs {
+        exclude.add_pruned_dir(dir_name.as_str());
+    }
+    for pattern in &options.exclude_patterns {
+        exclude.add_user_pattern(pattern.as_str());
+    }
+    exclude
+}
+
+/// 本次运行可能生成的文件和目录 (Artifact Paths)
+///
+/// 结果文件及其辅助文件（失败记录、剩余文件列表、缓存、锁文件、临时文件、诊断脚本），
+/// 以及 JSON 统计、运行历史、削波报告、基线、分组统计文件和调试输出目录。
+fn artifact_paths(results_file_path: &Path, options: &CliOptions) -> Vec<PathBuf> {
+    let mut paths = vec![
+        results_file_path.to_path_buf(),
+        failures_path_for(results_file_path),
+        remaining_path_for(results_file_path),
+        cache_path_for(results_file_path),
+        lock_path_for(results_file_path),
+        temp_path_for(results_file_path),
+        inspect_script_path_for(results_file_path, ShellSyntax::native()),
+    ];
+    paths.extend(
+        [&options.stats_json, &options.run_history, &options.clipping_report, &options.baseline, &options.debug_dump]
+            .into_iter()
+            .flatten()
+            .cloned(),
+    );
+    if let Some(key) = options.group_by {
+        paths.push(results_file_path.with_file_name(key.file_name()));
+    }
+    paths
+}
+
+/// 本次运行生成的文件 (Run Artifacts)
+///
+/// 位于扫描根目录下的生成文件写入生成文件清单，之后在同一根目录下的扫描会跳过它们，
+/// 即使下次运行的结果文件名或选项已经不同。锁文件在退出时删除，不记录。
+#[derive(Clone)]
+struct RunArtifacts {
+    base_folder_path: PathBuf,
+    paths: Vec<PathBuf>,
+}
+
+impl RunArtifacts {
+    fn new(base_folder_path: &Path, results_file_path: &Path, options: &CliOptions) -> Self {
+        let lock_path = lock_path_for(results_file_path);
+        let paths = artifact_paths(results_file_path, options).into_iter().filter(|path| *path != lock_path).collect();
+        Self { base_folder_path: base_folder_path.to_path_buf(), paths }
+    }
+
+    /// 把已经存在的生成文件写入清单；写入失败只警告
+    fn record(&self) {
+        if let Err(e) = record_artifacts(&self.base_folder_path, &self.paths) {
+            human_eprintln!(
+                "⚠️  无法更新生成文件清单 {}: {}",
+                self.base_folder_path.join(ARTIFACT_MANIFEST_FILE_NAME).display(),
+                e
+            );
+        }
+    }
+}
+
+/// 离开作用域时记录生成文件 (Artifact Guard)
+///
+/// 运行的每条退出路径（包括出错返回）都会经过这里；中断由 [`install_interrupt_handler`] 记录。
+struct ArtifactGuard(RunArtifacts);
+
+impl Drop for ArtifactGuard {
+    fn drop(&mut self) {
+        self.0.record();
+    }
+}
+
+/// 探测没有扩展名的文件 (Probe Extensionless Files)
+///
+/// 按内容识别扫描报告中没有扩展名的文件，识别为音频的文件加入待处理列表。
+/// 探测结果写入缓存，文件未变化时下次运行不再重复探测。
+///
+/// # 参数
+/// - `scan_report` - 扫描报告，识别出的音频文件会移入 `files`
+/// - `cache` - 结果缓存；为 `None` 时每次都重新探测
+///
+/// # 返回值
+/// - 经 ffprobe 确认为音频的文件的标签（`--with-tags` 时复用）
+fn probe_extensionless_files(
+    scan_report: &mut ScanReport,
+    cache: Option<&mut ResultsCache>,
+) -> BTreeMap<String, TrackTags> {
+    const PROGRESS_INTERVAL: usize = 100;
+
+    let candidates = std::mem::take(&mut scan_report.extensionless_files);
+    if candidates.is_empty() {
+        return BTreeMap::new();
+    }
+
+    human_println!("🔎 正在按内容探测 {} 个没有扩展名的文件...", candidates.len());
+    let no_cache = Default::default();
+    let cached_probes = cache.as_ref().map_or(&no_cache, |cache| &cache.probes);
+
+    let outcome = probe_unknown_files(candidates, cached_probes, |done, total| {
+        if done % PROGRESS_INTERVAL == 0 || done == total {
+            human_println!("   探测进度: {}/{}", done, total);
+        }
+    });
+
+    human_println!(
+        "✅ 探测完成，{} 个文件识别为音频（{} 个使用了缓存的探测结果）",
+        outcome.audio_files.len(),
+        outcome.cache.len() - outcome.probed
+    );
+
+    scan_report.skipped_files -= outcome.audio_files.len();
+    scan_report.files.extend(outcome.audio_files);
+    if let Some(cache) = cache {
+        cache.probes = outcome.cache;
+    }
+    outcome.tags
+}
+
+/// 显示跳过的大小写别名 (Display Case Aliases)
+///
+/// # 参数
+/// - `case_aliases` - `--dedup-case` 时跳过的路径和保留的路径
+fn display_case_aliases(case_aliases: &[CaseAlias]) {
+    const MAX_DISPLAY_PATHS: usize = 5;
+
+    if case_aliases.is_empty() {
+        return;
+    }
+
+    human_println!("⏭️  跳过了 {} 个只有大小写不同的重复路径 (--dedup-case):", case_aliases.len());
+    for alias in case_aliases.iter().take(MAX_DISPLAY_PATHS) {
+        human_println!("   {} (与 {} 是同一个文件)", alias.alias.display(), alias.kept.display());
+    }
+    if case_aliases.len() > MAX_DISPLAY_PATHS {
+        human_println!("   ... 还有 {} 个未显示", case_aliases.len() - MAX_DISPLAY_PATHS);
+    }
+}
+
+/// 显示无法读取的路径 (Display Unreadable Paths)
+///
+/// 扫描时无法访问的子目录会导致其中的音频文件被遗漏，
+/// 即使找到了其他文件，也需要提醒用户。
+///
+/// # 参数
+/// - `unreadable_paths` - 无法读取的路径及错误原因
+fn display_unreadable_paths(unreadable_paths: &[(PathBuf, String)]) {
+    const MAX_DISPLAY_PATHS: usize = 5;
+
+    if unreadable_paths.is_empty() {
+        return;
+    }
+
+    human_eprintln!("⚠️  有 {} 个子目录或文件无法读取，其中的音频文件将被跳过:", unreadable_paths.len());
+    for (path, error) in unreadable_paths.iter().take(MAX_DISPLAY_PATHS) {
+        human_eprintln!("   • {}: {}", path.display(), error);
+    }
+    if unreadable_paths.len() > MAX_DISPLAY_PATHS {
+        human_eprintln!("   ... 还有 {} 个未显示", unreadable_paths.len() - MAX_DISPLAY_PATHS);
+    }
+}
+
+/// 显示文件格式统计 (Display File Format Statistics)
+///
+/// 按格式显示发现的音频文件数量，顺序见 [`ScanStatistics::by_count`]。
+/// 这有助于用户了解文件库的组成情况。
+///
+/// # 参数
+/// - `statistics` - 扫描到的文件的格式统计
+fn display_file_format_statistics(statistics: &ScanStatistics) {
+    human_println!("📊 文件格式统计:");
+    for (format, count) in statistics.by_count() {
+        // 通过 --probe-unknown 识别的文件没有扩展名，单独归为一类
+        if format.is_empty() {
+            human_println!("   无扩展名 (按内容识别): {} 个文件", count);
+            continue;
+        }
+        // 通过 --add-ext 加入的格式单独标注
+        let custom = if SUPPORTED_EXTENSIONS.contains(&format) { "" } else { " (自定义)" };
+        human_println!("   {} 格式{}: {} 个文件", format.to_uppercase(), custom, count);
+    }
+    human_println!();
+}
+
+/// 读取结果缓存 (Load Results Cache)
+///
+/// 缓存文件损坏时只输出警告并使用空缓存。FFmpeg 版本与缓存记录不同时，
+/// 按 `--cache-version-policy` 处理旧条目并说明受影响的条目数量。
+///
+/// # 参数
+/// - `cache_path` - 缓存文件路径
+/// - `ffmpeg_version` - 当前检测到的 FFmpeg 版本
+/// - `policy` - 版本变化时的处理策略
+///
+/// # 返回值
+/// - 可用于本次运行的缓存
+fn load_results_cache(
+    cache_path: &Path,
+    ffmpeg_version: Option<&str>,
+    policy: CacheVersionPolicy,
+) -> ResultsCache {
+    let mut cache = load_cache(cache_path).unwrap_or_else(|e| {
+        human_eprintln!("⚠️  {}，将忽略缓存重新分析所有文件", e);
+        ResultsCache::default()
+    });
+
+    if let Some(change) = cache.apply_version_policy(ffmpeg_version, policy) {
+        let action = match change.policy {
+            CacheVersionPolicy::Invalidate => "已失效，将重新分析",
+            CacheVersionPolicy::MarkStale => "已标记为过期，仍会复用",
+        };
+        human_println!(
+            "🔄 FFmpeg 版本已从 {} 变为 {}，{} 个缓存条目{}",
+            change.previous,
+            change.current,
+            change.affected,
+            action
+        );
+    }
+    if cache.ffmpeg_version.is_none() {
+        cache.ffmpeg_version = ffmpeg_version.map(str::to_string);
+    }
+    cache
+}
+
+/// 更新结果缓存 (Update Results Cache)
+///
+/// 用本次运行的成功结果重建缓存。写入失败只输出警告，不影响本次运行。
+///
+/// # 参数
+/// - `cache_path` - 缓存文件路径
+/// - `previous_cache` - 本次运行开始时使用的缓存
+/// - `fingerprints` - 文件的当前指纹
+/// - `processing_results` - 本次运行的全部结果（包括复用的缓存结果）
+/// - `keep_unseen` - 是否保留本次未扫描到的旧条目（增量更新时文件被时间过滤掉，并未删除）
+fn update_results_cache(
+    cache_path: &Path,
+    previous_cache: &ResultsCache,
+    fingerprints: &Fingerprints,
+    processing_results: &ProcessingResults,
+    keep_unseen: bool,
+) {
+    let successful_results: Vec<FileMeasurement> = processing_results
+        .iter()
+        .filter_map(|result| result.measurement().cloned())
+        .collect();
+
+    let mut cache = rebuild_cache(previous_cache, fingerprints, &successful_results);
+    if keep_unseen {
+        for (path, entry) in &previous_cache.entries {
+            if !fingerprints.contains_key(path) {
+                cache.entries.entry(path.clone()).or_insert(*entry);
+            }
+        }
+    }
+
+    if let Err(e) = save_cache(cache_path, &cache) {
+        human_eprintln!("⚠️  无法写入缓存文件 {}: {}", cache_path.display(), e);
+    }
+}
+
+/// 执行并行处理 (Execute Parallel Processing)
+///
+/// 启动多线程并行处理，计算所有音频文件的 LRA 值。
+/// 这是程序的核心处理阶段，会显示详细的进度信息。
+///
+/// # 参数
+/// - `plan` - 处理计划
+/// - `ctx` - 本次运行的上下文（`--stream-results` 的部分结果文件与结果文件相邻，失败交给其中的失败流）
+///
+/// # 返回值
+/// - 执行结果，包含成功的结果、没有开始的文件以及复用的缓存结果；失败已经交给 `failures`
+fn execute_parallel_processing(plan: ProcessingPlan, ctx: &RunContext) -> ProcessingOutcome {
+    let options = ctx.options;
+    human_println!("⚡ 开始并行处理阶段...");
+
+    let (cancellation, _deadline) = start_deadline(options);
+    let (concurrency, _monitor) = start_adaptive_concurrency(options);
+    // 处理结束（包括时间预算到期）时执行最后一次刷新
+    let stream = start_streaming(&ctx.results_file_path, options);
+    let calculation_options = ctx.calculation_options();
+    let run = RunOptions { schedule: resolve_schedule_order(options), cancellation, concurrency, ..ctx.run_options() };
+    let start_time = std::time::Instant::now();
+    // 无论调度顺序如何，结果都按计划中的顺序排列，结果文件不受影响（失败记录按完成的顺序写入）
+    let outcome = with_worker_pool(options, || {
+        execute_with_sink(plan, &calculation_options, &run, &CliSink { options, stream: stream.as_ref() })
+    });
+    finish_streaming(stream);
+    let elapsed = start_time.elapsed();
+
+    human_println!("⏱️  并行处理耗时: {:.2} 秒", elapsed.as_secs_f64());
+
+    outcome
+}
+
+/// 打开部分结果文件 (Start Streaming Results)
+///
+/// 设置了 `--stream-results`（或刷新参数）时创建 [`partial_path_for`] 给出的部分结果文件。
+/// 创建失败只警告，本次运行照常进行，只是不流式写入。
+///
+/// # 返回值
+/// - 写入器；需要持有到处理结束，再交给 [`finish_streaming`]
+fn start_streaming(results_file_path: &Path, options: &CliOptions) -> Option<StreamingResultsWriter> {
+    let config =
+        StreamingConfig { format: options.text_format, precision: options.lra_precision(), ..options.stream_results? };
+    let partial_path = partial_path_for(results_file_path);
+    match StreamingResultsWriter::create(&partial_path, RESULTS_HEADER_LINE, config) {
+        Ok(writer) => {
+            if config.sorted {
+                human_println!("📝 完成的结果将按 LRA 排序写入 {}（条目越多重写越少）", partial_path.display());
+            } else {
+                human_println!(
+                    "📝 完成的结果将写入 {}（每 {} 个结果或每 {} 刷新一次）",
+                    partial_path.display(),
+                    config.flush_every,
+                    format_duration(config.flush_interval)
+                );
+            }
+            Some(writer)
+        }
+        Err(e) => {
+            human_eprintln!("⚠️  无法创建部分结果文件 {}，本次不流式写入: {}", partial_path.display(), e);
+            None
+        }
+    }
+}
+
+/// 关闭部分结果文件 (Finish Streaming Results)
+///
+/// 执行最后一次刷新；失败只警告，结果文件本身不受影响。
+fn finish_streaming(stream: Option<StreamingResultsWriter>) {
+    if let Some(Err(e)) = stream.map(|writer| writer.flush()) {
+        human_eprintln!("⚠️  无法刷新部分结果文件: {}", e);
+    }
+}
+
+/// 命令行程序的事件接收者 (CLI Event Sink)
+///
+/// 控制台输出（[`ConsoleSink`]）之外，按命令行选项把事件写入 `--porcelain` 输出，
+/// 并把成功的结果追加到部分结果文件。
+struct CliSink<'a> {
+    options: &'a CliOptions,
+    /// 部分结果文件；未启用 `--stream-results` 或不在处理阶段时为 `None`
+    stream: Option<&'a StreamingResultsWriter>,
+}
+
+impl<'a> CliSink<'a> {
+    /// 不写入部分结果文件的接收者
+    fn new(options: &'a CliOptions) -> Self {
+        Self { options, stream: None }
+    }
+}
+
+impl EventSink for CliSink<'_> {
+    fn emit(&self, event: &RunEvent) {
+        ConsoleSink.emit(event);
+        if let (Some(stream), RunEvent::FileFinished { outcome, .. }) = (self.stream, event) {
+            if let FileOutcome::Success(measurement) = outcome.as_ref() {
+                if let Err(e) = stream.append(&measurement.path, measurement.lra) {
+                    human_eprintln!("⚠️  无法写入部分结果文件: {}", e);
+                }
+            }
+        }
+        if let Some(event) = event.to_progress_event() {
+            emit_event(self.options, &event);
+        }
+    }
+}
+
+/// 由命令行选项构建单个文件的计算选项
+fn calculation_options(options: &CliOptions) -> CalculationOptions {
+    CalculationOptions {
+        dump_sink: options.debug_dump.clone(),
+        verbose: options.verbose,
+        keep_all_streams: options.keep_all_streams,
+        hydrate: options.hydrate,
+        hash: options.hash,
+        downmix: options.downmix,
+        also_downmixed: options.also_downmixed,
+        per_channel: options.per_channel,
+        true_peak: options.true_peak,
+        run_id: None,
+    }
+}
+
+/// 启动自适应并发 (Start Adaptive Concurrency)
+///
+/// 设置了 `--adaptive-jobs` 时创建并发限制器，初始上限为 CPU 核心数（限制在区间内），
+/// 并启动监视线程按系统负载调整。调整和原因在 `--verbose` 时输出；
+/// 无法采样的平台上只警告一次，上限保持初始值。
+///
+/// # 返回值
+/// - (并发限制器, 监视器)；监视器需要持有到处理结束，丢弃时监视线程随之结束
+fn start_adaptive_concurrency(options: &CliOptions) -> (Option<ConcurrencyLimiter>, Option<AdaptiveMonitor>) {
+    let Some(band) = options.adaptive_jobs else {
+        return (None, None);
+    };
+    let cpu_count = std::thread::available_parallelism().map_or(1, usize::from);
+    let limiter = ConcurrencyLimiter::new(band.clamp(cpu_count));
+    human_println!("🎚️  自适应并发: 同时运行 {} 个 FFmpeg，按系统负载在 {} 之间调整", limiter.limit(), band);
+
+    let verbose = options.verbose;
+    let monitor = AdaptiveMonitor::start(limiter.clone(), band, move |change| {
+        if verbose {
+            human_println!("    🎚️  {}", change);
+        }
+    });
+    if monitor.is_none() {
+        human_eprintln!(
+            "⚠️  无法读取系统负载和可用内存，自适应并发不可用，固定同时运行 {} 个 FFmpeg",
+            limiter.limit()
+        );
+    }
+    (Some(limiter), monitor)
+}
+
+/// 在足够容纳 `--adaptive-jobs` 上限的线程池中执行处理
+///
+/// 全局线程池按 CPU 核心数创建；区间上限更大时为本次处理单独创建线程池，
+/// 创建失败时警告并使用全局线程池。
+fn with_worker_pool<T: Send>(options: &CliOptions, work: impl FnOnce() -> T + Send) -> T {
+    let Some(band) = options.adaptive_jobs.filter(|band| band.max > rayon::current_num_threads()) else {
+        return work();
+    };
+    match rayon::ThreadPoolBuilder::new().num_threads(band.max).build() {
+        Ok(pool) => pool.install(work),
+        Err(e) => {
+            human_eprintln!("⚠️  无法创建 {} 个工作线程，最多同时运行 {} 个 FFmpeg: {}", band.max, rayon::current_num_threads(), e);
+            work()
+        }
+    }
+}
+
+/// 启动处理阶段的时间预算 (Start Deadline)
+///
+/// 设置了 `--max-duration` 时创建取消令牌并启动计时线程，到期后不再开始新的文件。
+///
+/// # 返回值
+/// - (取消令牌, 计时器)；计时器需要持有到处理结束，丢弃时计时线程随之结束
+fn start_deadline(options: &CliOptions) -> (Option<CancellationToken>, Option<DeadlineTimer>) {
+    let Some(max_duration) = options.max_duration else {
+        return (None, None);
+    };
+    human_println!("⏳ 时间预算: {}，到期后不再开始新的文件", format_duration(max_duration));
+    let token = CancellationToken::new();
+    let timer = token.cancel_after(max_duration);
+    (Some(token), Some(timer))
+}
+
+/// 启动网络共享断连的断路器 (Start Outage Breaker)
+///
+/// 设置了 `--outage-threshold` 或 `--outage-budget` 时创建检查扫描根目录的断路器。
+fn start_outage_breaker(base_folder_path: &Path, options: &CliOptions) -> Option<CircuitBreaker> {
+    let config = options.outage?;
+    human_println!(
+        "🔌 连续 {} 个以上文件无法访问时暂停并等待扫描根目录恢复，最多暂停 {}",
+        config.threshold,
+        format_duration(config.pause_budget)
+    );
+    Some(CircuitBreaker::new(base_folder_path, config))
+}
+
+/// 把时长格式化为 `1h30m`、`45s` 这样的形式
+fn format_duration(duration: std::time::Duration) -> String {
+    let total = duration.as_secs();
+    let (hours, minutes, seconds) = (total / 3600, total / 60 % 60, total % 60);
+    let mut text = String::new();
+    if hours > 0 {
+        text.push_str(&format!("{}h", hours));
+    }
+    if minutes > 0 {
+        text.push_str(&format!("{}m", minutes));
+    }
+    if seconds > 0 || text.is_empty() {
+        text.push_str(&format!("{}s", seconds));
+    }
+    text
+}
+
+/// 取出因取消而没有处理的文件 (Split Unprocessed Files)
+///
+/// 运行取消或断路器放弃后跳过的文件（[`lra_calculator_rust::processor::SkipReason::is_unprocessed`]）写入未处理文件列表；
+/// 其他原因跳过的文件（如云端占位文件）留在结果中，计入跳过统计。
+///
+/// # 返回值
+/// - (未处理文件的显示路径, 其余处理结果)
+fn split_unprocessed(processing_results: ProcessingResults) -> (Vec<FileKey>, ProcessingResults) {
+    let mut unprocessed = Vec::new();
+    let mut processed = Vec::with_capacity(processing_results.len());
+    for result in processing_results {
+        match result {
+            FileOutcome::Skipped { path, reason } if reason.is_unprocessed() => unprocessed.push(path),
+            other => processed.push(other),
+        }
+    }
+    (unprocessed, processed)
+}
+
+/// 写入或清理未处理文件列表 (Write Remaining File)
+///
+/// 每行一个显示路径；全部处理完时删除旧的列表。
+fn write_remaining_file(remaining_path: &Path, unprocessed: &[FileKey]) -> Result<(), AppError> {
+    if unprocessed.is_empty() {
+        return match std::fs::remove_file(remaining_path) {
+            Err(e) if e.kind() != io::ErrorKind::NotFound => Err(e.into()),
+            _ => Ok(()),
+        };
+    }
+
+    let mut writer = BufWriter::new(File::create(remaining_path)?);
+    for path in unprocessed {
+        writeln!(writer, "{}", path)?;
+    }
+    writer.flush()?;
+    human_println!("📄 未处理的文件列表已写入: {}", remaining_path.display());
+    Ok(())
+}
+
+/// 完成处理并输出结果 (Finalize and Output Results)
+///
+/// 分析处理结果，写入结果文件，并进行排序。
+/// 这是程序的最后阶段，负责生成最终的输出文件。
+///
+/// # 参数
+/// - `ctx` - 本次运行的上下文。增量更新时新结果与已有结果文件合并；运行 ID 记录在统计信息中，
+///   并写入结果文件、失败记录、统计 JSON 和运行历史；结果中剩下的失败同样交给其中的失败流
+/// - `outcome` - 执行结果；限量/抽样的候选和选中数量会在统计中注明，扫描时被改名的文件会在统计中列出
+/// - `known_tags` - 探测阶段已经读到的标签（`--with-tags` 时不再重复读取）
+/// - `scan_statistics` - 计划中的文件的格式统计，写入统计 JSON；流水线模式下为 `None`
+///
+/// # 返回值
+/// - `Ok(())` - 结果处理成功
+/// - `Err(AppError::ThresholdViolated)` - 有文件违反了阈值规则
+/// - `Err(AppError::RegressionDetected)` - 有文件的 LRA 相对基线的变化超过阈值
+/// - `Err(...)` - 文件写入失败
+fn finalize_and_output_results(
+    ctx: &RunContext,
+    outcome: ProcessingOutcome,
+    known_tags: KnownTags,
+    scan_statistics: Option<ScanStatistics>,
+) -> Result<(), AppError> {
+    let RunContext { options, base_folder_path, results_file_path, run_id, failures, started_at, .. } = ctx;
+    let (options, incremental) = (*options, ctx.newer_than.is_some());
+    let ProcessingOutcome { results: processing_results, selection: selection_report, path_collisions, .. } = outcome;
+    human_println!("📊 正在分析处理结果...");
+
+    // 分析结果（时间预算到期、网络共享长时间断开或提前中止后没有开始的文件不算失败）
+    let (unprocessed, processing_results) = split_unprocessed(processing_results);
+    let (mut stats, measurements) = analyze_results_streaming(processing_results, &failures.stream);
+    stats.flag_suspicious(&measurements, options.sanity_range());
+    let FailureRecorder { log: failures_log, vanished, inspect_errors, .. } = failures.take_recorder();
+    stats.wall_seconds = Some(started_at.elapsed().as_secs_f64());
+    stats.path_collisions = path_collisions;
+    stats.scan = scan_statistics;
+    stats.run_id = Some(run_id.clone());
+    stats.unprocessed = unprocessed.len();
+    let mut extra_columns = ExtraColumnTable::default();
+    if options.replaygain {
+        extra_columns.add_columns(&REPLAYGAIN_COLUMNS, MISSING_REPLAYGAIN);
+    }
+    if options.with_tags {
+        extra_columns.add_columns(&TAG_COLUMNS, "");
+    }
+    if let Some(algorithm) = options.hash {
+        // 哈希失败的文件该列留空
+        extra_columns.add_columns(&[algorithm.name()], "");
+        for measurement in &measurements {
+            if let Some(hash) = measurement.content_hash {
+                extra_columns.set(&measurement.path, &[algorithm.name()], [hash.hex()]);
+            }
+        }
+    }
+    if options.also_downmixed {
+        // 不是多声道的文件该列留空
+        extra_columns.add_columns(&[DOWNMIXED_LRA_COLUMN], "");
+        for measurement in &measurements {
+            if let Some(downmixed_lra) = measurement.downmixed_lra {
+                let value = LraValue(downmixed_lra).with_precision(options.lra_precision()).to_string();
+                extra_columns.set(&measurement.path, &[DOWNMIXED_LRA_COLUMN], [value]);
+            }
+        }
+    }
+    if options.per_channel {
+        // 单声道文件该列留空
+        extra_columns.add_columns(&CHANNEL_COLUMNS, "");
+        for measurement in &measurements {
+            if let Some(channels) = &measurement.channels {
+                let values = channel_column_values(channels, options.lra_precision());
+                extra_columns.set(&measurement.path, &CHANNEL_COLUMNS, values);
+            }
+        }
+        stats.channel_imbalances = find_channel_imbalances(
+            &measurements,
+            options.channel_imbalance.unwrap_or(DEFAULT_CHANNEL_IMBALANCE),
+        );
+    }
+    if options.range_details {
+        // FFmpeg 没有输出的值留空
+        extra_columns.add_columns(&RANGE_DETAIL_COLUMNS, "");
+        for measurement in &measurements {
+            let values = [measurement.lra_low_lufs, measurement.lra_high_lufs, measurement.threshold_lufs]
+                .map(|lufs| lufs.map_or_else(String::new, |lufs| format!("{:.1}", lufs)));
+            extra_columns.set(&measurement.path, &RANGE_DETAIL_COLUMNS, values);
+        }
+    }
+    if options.true_peak {
+        stats.clipping_risks = find_clipping_risks(
+            &measurements,
+            options.true_peak_ceiling.unwrap_or(DEFAULT_TRUE_PEAK_CEILING),
+        );
+    }
+    if options.replaygain {
+        for (path, fields) in replaygain_extra_columns(&measurements) {
+            extra_columns.set(&path, &REPLAYGAIN_COLUMNS, fields);
+        }
+    }
+    let fresh_results = measurements.iter().map(FileMeasurement::entry).collect();
+    let mut successful_results = if incremental {
+        let vanished: HashSet<&FileKey> = vanished.iter().collect();
+        merge_with_existing_results(results_file_path, base_folder_path, fresh_results, &vanished, &mut extra_columns)?
+    } else {
+        fresh_results
+    };
+    if options.album_lra {
+        // 增量更新时只重新分析有音轨变化的目录，其他目录沿用旧的专辑条目
+        let changed_directories: Option<HashSet<&str>> =
+            incremental.then(|| measurements.iter().map(|m| album_key(&m.path)).collect());
+        let album_results = analyze_albums(base_folder_path, &successful_results, changed_directories.as_ref(), options);
+        successful_results = merge_entries(successful_results.into_iter().chain(album_results));
+    }
+    let tags = if needs_tags(options) {
+        collect_tags(base_folder_path, &successful_results, known_tags)
+    } else {
+        HashMap::new()
+    };
+    if options.with_tags {
+        for (path, track_tags) in &tags {
+            extra_columns.set(path, &TAG_COLUMNS, track_tags.fields());
+        }
+    }
+    let header_line = extra_columns.header_line();
+    let extra_columns = extra_columns.render(successful_results.iter().map(|(path, _)| path));
+
+    // 显示统计信息
+    let sink = CliSink::new(options);
+    sink.emit(&RunEvent::StatsReady { stats: Box::new(stats.clone()) });
+    if options.verbose {
+        display_worker_utilization(&stats);
+    }
+    if !unprocessed.is_empty() {
+        human_eprintln!(
+            "{}",
+            error_color_mode().paint(
+                Style::Warning,
+                human_format!(
+                    "⏳ 处理提前停止: {} 个文件未处理，结果只包含已完成的文件。再次运行相同的命令即可继续（已完成的文件命中缓存）",
+                    unprocessed.len()
+                ),
+            )
+        );
+    }
+    if let Some(key) = options.group_by {
+        let format = options.text_format;
+        write_group_statistics(results_file_path, key, &successful_results, &tags, format, options.lra_precision())?;
+    }
+    if options.compare_versions {
+        display_version_comparison(&compare_versions(&successful_results, &options.name_normalization()), options);
+    }
+    sink.emit(&RunEvent::RunFinished {
+        successful: stats.successful,
+        failed: stats.failed,
+        results_file: results_file_path.display().to_string(),
+        elapsed_secs: started_at.elapsed().as_secs_f64(),
+    });
+
+    // 专辑条目不参与页脚统计和阈值检查
+    let track_results: Vec<(FileKey, f64)> =
+        successful_results.iter().filter(|(path, _)| !is_album_entry(path)).cloned().collect();
+    let values: Vec<f64> = track_results.iter().map(|(_, lra)| *lra).collect();
+    let distribution = LraDistribution::from_values(&values);
+    if let Some(report) = selection_report {
+        // 限量或抽样运行的目的就是快速了解分布
+        human_println!("🎲 {}，以下统计只代表这部分文件", report);
+        if let Some(distribution) = &distribution {
+            display_lra_distribution(distribution);
+        }
+    }
+    let footer = if options.summary_footer {
+        results_footer_lines(&stats, distribution.as_ref(), started_at.elapsed())
+    } else {
+        Vec::new()
+    };
+
+    // --stream-sorted 和 --output -: 条目在内存中排好序再输出，省去读回并重写结果文件的排序步骤
+    // （--stream-sorted 只与默认排序方式一起使用，见 cli 的参数检查）
+    let sort_order = options.sort_order.unwrap_or_default();
+    let sorted_in_memory =
+        options.output_to_stdout() || options.stream_results.is_some_and(|config| config.sorted);
+    if sorted_in_memory {
+        successful_results = sort_entries(successful_results, sort_order);
+    }
+
+    if options.output_to_stdout() {
+        write_results_to_stdout(
+            &header_line,
+            run_id,
+            sort_order,
+            &successful_results,
+            &extra_columns,
+            &footer,
+            options.text_format,
+            options.lra_precision(),
+        )?;
+    } else {
+        // 写入结果文件
+        write_initial_results_file(
+            results_file_path,
+            &header_line,
+            run_id,
+            &successful_results,
+            &extra_columns,
+            &footer,
+            options.text_format,
+            options.lra_precision(),
+            !options.no_verify,
+        )?;
+    }
+
+    // 完整的结果文件已经写入，部分结果文件不再需要
+    if options.stream_results.is_some() {
+        let partial_path = partial_path_for(results_file_path);
+        if let Err(e) = std::fs::remove_file(&partial_path) {
+            if e.kind() != std::io::ErrorKind::NotFound {
+                human_eprintln!("⚠️  无法删除部分结果文件 {}: {}", partial_path.display(), e);
+            }
+        }
+    }
+
+    // 排序结果文件
+    if options.output_to_stdout() {
+        human_println!("✅ 结果已按{}排序输出到 stdout", sort_order.description());
+    } else if sorted_in_memory {
+        human_println!("✅ 结果已按{}排序写入，跳过排序步骤", sort_order.description());
+    } else if !successful_results.is_empty() {
+        let (entry_count, precision) = (successful_results.len(), options.lra_precision());
+        sort_results_file_if_needed(results_file_path, entry_count, sort_order, precision, !options.no_verify)?;
+    } else {
+        human_println!("📝 没有成功处理的文件，跳过排序步骤");
+    }
+
+    // 导出 JSON 统计信息
+    if let Some(stats_json_path) = &options.stats_json {
+        write_stats_json(stats_json_path, &stats)?;
+    }
+    if let Some(report_path) = &options.clipping_report {
+        write_clipping_report(report_path, &stats.clipping_risks, options.text_format)?;
+        human_println!("📄 削波风险报告已写入: {} ({} 个文件)", report_path.display(), stats.clipping_risks.len());
+    }
+
+    // 追加运行历史：写入失败只警告，不影响本次运行
+    if let Some(run_history_path) = &options.run_history {
+        let record = RunRecord::new(base_folder_path, &stats, distribution.as_ref(), CLOCK.now());
+        match append_run_record(run_history_path, &record) {
+            Ok(()) => human_println!("📈 运行记录已追加: {}", run_history_path.display()),
+            Err(e) => human_eprintln!("⚠️  无法写入运行历史 {}: {}", run_history_path.display(), e),
+        }
+    }
+
+    // 回归检测：与基线比较
+    let regressions = match &options.baseline {
+        Some(baseline_path) => check_baseline(baseline_path, &successful_results, options)?,
+        None => 0,
+    };
+
+    // CI 门禁：检查成功结果是否满足阈值；增量更新时沿用的旧条目没有本次测得的综合响度
+    let loudness: HashMap<&FileKey, f64> =
+        measurements.iter().filter_map(|m| m.integrated_lufs.map(|lufs| (&m.path, lufs))).collect();
+    let threshold_inputs: Vec<(FileKey, f64, Option<f64>)> =
+        track_results.iter().map(|(path, lra)| (path.clone(), *lra, loudness.get(path).copied())).collect();
+    let violations = evaluate_thresholds(&threshold_inputs, &options.thresholds);
+    // 输出到 stdout 时不在磁盘上留下任何结果相关的文件
+    if let Some(failures_log) = failures_log {
+        write_failures_file(failures_log, &violations)?;
+        write_remaining_file(&remaining_path_for(results_file_path), &unprocessed)?;
+        if options.inspect_script {
+            write_failure_inspect_script(results_file_path, base_folder_path, &inspect_errors, options)?;
+        }
+    }
+    if !unprocessed.is_empty() {
+        let listed_in = match options.output_to_stdout() {
+            true => String::new(),
+            false => format!("，列表见 {}", remaining_path_for(results_file_path).display()),
+        };
+        return Err(AppError::Timeout(format!(
+            "超出 --max-duration 时间预算，{} 个文件未处理{}",
+            unprocessed.len(),
+            listed_in
+        )));
+    }
+
+    if !violations.is_empty() {
+        let color = error_color_mode();
+        human_eprintln!("{}", color.paint(Style::Failure, human_format!("❌ {} 个文件违反了阈值规则:", violations.len())));
+        for violation in &violations {
+            human_eprintln!("{}", color.paint(Style::Failure, human_format!("   • {}", violation)));
+        }
+        return Err(AppError::ThresholdViolated { violations: violations.len() });
+    }
+    if regressions > 0 {
+        return Err(AppError::RegressionDetected { regressions });
+    }
+
+    Ok(())
+}
+
+/// 与基线结果文件比较 (Check Baseline)
+///
+/// 列出 LRA 变化（旧值 → 新值，超过阈值的标红）、新文件和基线中缺失的文件；
+/// `--update-baseline` 时把新文件追加到基线文件。基线文件不存在时视为空基线。
+///
+/// # 参数
+/// - `baseline_path` - 基线结果文件
+/// - `results` - 本次的完整结果
+/// - `options` - 命令行选项（回归阈值、是否更新基线）
+///
+/// # 返回值
+/// - `Ok(usize)` - 变化超过阈值的条目数量
+/// - `Err(AppError::Io)` - 基线文件无法读取，或更新基线失败
+fn check_baseline(baseline_path: &Path, results: &[(FileKey, f64)], options: &CliOptions) -> Result<usize, AppError> {
+    let baseline = match ResultsFile::read(baseline_path) {
+        Ok(file) => {
+            warn_invalid_lines(Some(baseline_path), &file.contents.invalid_lines);
+            file.contents.entries
+        }
+        Err(e) if e.kind() == io::ErrorKind::NotFound => {
+            human_eprintln!("⚠️  基线文件不存在，所有文件都视为新文件: {}", baseline_path.display());
+            Vec::new()
+        }
+        Err(e) => return Err(e.into()),
+    };
+    let threshold = options.regression_threshold.unwrap_or(DEFAULT_REGRESSION_THRESHOLD);
+    let comparison = compare_with_baseline(&baseline, results, threshold);
+    display_baseline_comparison(&comparison, threshold);
+
+    if options.update_baseline && !comparison.new.is_empty() {
+        let precision = options.lra_precision();
+        let added =
+            extend_results_file(baseline_path, RESULTS_HEADER_LINE, &comparison.new, precision, !options.no_verify)?;
+        human_println!("📌 已把 {} 个新文件追加到基线: {}", added, baseline_path.display());
+    }
+    Ok(comparison.regressions().count())
+}
+
+/// 输出与基线比较的结果
+fn display_baseline_comparison(comparison: &BaselineComparison, threshold: f64) {
+    if comparison.is_unchanged() {
+        human_println!("📐 与基线一致");
+        return;
+    }
+
+    let color = human_color_mode();
+    human_println!(
+        "📐 与基线比较: {} 个变化（{} 个超过 {:.1} LU），{} 个新文件，{} 个缺失",
+        comparison.changed.len(),
+        comparison.regressions().count(),
+        threshold,
+        comparison.new.len(),
+        comparison.missing.len()
+    );
+    for change in &comparison.changed {
+        let line = human_format!("   • {}", change);
+        if change.regression {
+            human_println!("{}", color.paint(Style::Failure, line));
+        } else {
+            human_println!("{}", line);
+        }
+    }
+    for (path, lra) in &comparison.new {
+        human_println!("   • {}: {:.1} LU (新)", path, lra);
+    }
+    for path in &comparison.missing {
+        human_println!("   • {}: 基线中有，本次没有结果", path);
+    }
+}
+
+/// 与已有结果合并 (Merge with Existing Results)
+///
+/// 增量更新只分析了新增或修改过的文件，需要与上一次的结果合并，
+/// 输出才是完整的。同一文件以本次结果为准；已被删除的文件不再保留。
+///
+/// # 参数
+/// - `results_file_path` - 已有的结果文件（不存在时视为空）
+/// - `base_folder_path` - 扫描根目录，用于检查文件是否仍然存在
+/// - `fresh_results` - 本次运行的成功结果
+/// - `vanished` - 本次分析时已经消失的文件；即使之后又出现，也不保留它们的旧结果
+/// - `extra_columns` - 附加列；本次没有重新计算的值按列名沿用已有结果文件中的值
+///
+/// # 返回值
+/// - `Ok(Vec)` - 合并后的完整结果
+/// - `Err(AppError::Io)` - 已有结果文件无法读取
+fn merge_with_existing_results(
+    results_file_path: &Path,
+    base_folder_path: &Path,
+    fresh_results: Vec<(FileKey, f64)>,
+    vanished: &HashSet<&FileKey>,
+    extra_columns: &mut ExtraColumnTable,
+) -> Result<Vec<(FileKey, f64)>, AppError> {
+    let existing = match ResultsFile::read(results_file_path) {
+        Ok(ResultsFile { contents, .. }) => {
+            warn_invalid_lines(Some(results_file_path), &contents.invalid_lines);
+            extra_columns.adopt(&contents);
+            contents.entries
+        }
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => Vec::new(),
+        Err(e) => return Err(e.into()),
+    };
+
+    let kept: Vec<_> = existing
+        .into_iter()
+        .filter(|(path, _)| !vanished.contains(path))
+        .filter(|(path, _)| match album_directory(path) {
+            Some(directory) => base_folder_path.join(directory).is_dir(),
+            None => base_folder_path.join(path.as_str()).exists(),
+        })
+        .collect();
+    human_println!("🔁 增量更新：合并 {} 条已有结果", kept.len());
+
+    Ok(merge_entries(kept.into_iter().chain(fresh_results)))
+}
+
+/// 专辑合并分析 (Analyze Albums)
+///
+/// 对每个至少有两首成功音轨的目录，把其中的音轨拼接后再做一次 ebur128 分析，
+/// 得到整张专辑的 LRA。各专辑并行分析；失败的专辑只警告，不计入失败文件。
+///
+/// # 参数
+/// - `base_folder_path` - 扫描根目录
+/// - `entries` - 成功的结果条目（已有的专辑条目会被忽略）
+/// - `only_directories` - 只分析这些目录；`None` 表示分析全部目录
+/// - `options` - 命令行选项
+///
+/// # 返回值
+/// - 专辑条目：(`ALBUM:<目录>`, 合并后的 LRA 值)
+fn analyze_albums(
+    base_folder_path: &Path,
+    entries: &[(FileKey, f64)],
+    only_directories: Option<&HashSet<&str>>,
+    options: &CliOptions,
+) -> Vec<(FileKey, f64)> {
+    let albums: Vec<(&str, Vec<&str>)> = group_albums(entries.iter().map(|(path, _)| path.as_str()))
+        .into_iter()
+        .filter(|(directory, _)| only_directories.is_none_or(|only| only.contains(directory)))
+        .collect();
+    if albums.is_empty() {
+        return Vec::new();
+    }
+
+    human_println!("💿 正在合并分析 {} 个专辑...", albums.len());
+    let calculation_options = calculation_options(options);
+    albums
+        .par_iter()
+        .filter_map(|(directory, tracks)| {
+            let album = album_entry_key(directory);
+            let paths: Vec<PathBuf> = tracks.iter().map(|track| base_folder_path.join(track)).collect();
+            match measure_album_loudness(&paths, &album, &calculation_options) {
+                Ok(summary) => {
+                    human_println!("    💿 {} ({} 首) → LRA: {:.1} LU", album, tracks.len(), summary.lra);
+                    Some((album, summary.lra))
+                }
+                Err(e) => {
+                    human_eprintln!("⚠️  专辑 {} 合并分析失败，已跳过: {}", album, e);
+                    None
+                }
+            }
+        })
+        .collect()
+}
+
+/// 读取音轨标签 (Collect Tags)
+///
+/// 探测阶段已经由 ffprobe 读到标签的文件直接复用，其余音轨各调用一次 ffprobe。
+/// 专辑条目没有标签。
+///
+/// # 参数
+/// - `base_folder_path` - 扫描根目录
+/// - `entries` - 成功的结果条目
+/// - `known_tags` - 探测阶段读到的标签
+///
+/// # 返回值
+/// - 各音轨的标签
+fn collect_tags(
+    base_folder_path: &Path,
+    entries: &[(FileKey, f64)],
+    mut known_tags: BTreeMap<String, TrackTags>,
+) -> HashMap<FileKey, TrackTags> {
+    const PROGRESS_INTERVAL: usize = 100;
+
+    let mut tags = HashMap::new();
+    let mut to_read = Vec::new();
+    for (path, _) in entries.iter().filter(|(path, _)| !is_album_entry(path)) {
+        match known_tags.remove(path.as_str()) {
+            Some(known) => {
+                tags.insert(path.clone(), known);
+            }
+            None => to_read.push((base_folder_path.join(path.as_str()), path.clone())),
+        }
+    }
+    if to_read.is_empty() {
+        return tags;
+    }
+
+    human_println!("🏷️  正在读取 {} 个文件的标签...", to_read.len());
+    tags.extend(read_tags(to_read, |done, total| {
+        if done % PROGRESS_INTERVAL == 0 || done == total {
+            human_println!("   标签读取进度: {}/{}", done, total);
+        }
+    }));
+    tags
+}
+
+/// 是否需要读取标签（`--with-tags` 或按标签分组）
+fn needs_tags(options: &CliOptions) -> bool {
+    options.with_tags || options.group_by.is_some_and(|key| key.needs_tags())
+}
+
+/// 写入分组统计 (Write Group Statistics)
+///
+/// 按 `key` 对音轨条目分组（专辑条目不参与），结果按平均 LRA 从高到低写入
+/// 结果文件旁的 `lra_by_<键>.txt`，并打印平均 LRA 最高和最低的分组。
+///
+/// # 参数
+/// - `results_file_path` - 结果文件路径，分组文件写在同一目录
+/// - `key` - 分组键
+/// - `entries` - 全部成功结果
+/// - `tags` - 各音轨的标签（按目录分组时不使用）
+/// - `format` - BOM 和换行格式
+/// - `precision` - LRA 值保留的小数位数
+///
+/// # 返回值
+/// - `Ok(())` - 写入成功
+/// - `Err(AppError::Io)` - 写入失败
+fn write_group_statistics(
+    results_file_path: &Path,
+    key: GroupKey,
+    entries: &[(FileKey, f64)],
+    tags: &HashMap<FileKey, TrackTags>,
+    format: TextFormat,
+    precision: u8,
+) -> Result<(), AppError> {
+    let groups = group_statistics(entries.iter().filter(|(path, _)| !is_album_entry(path)).map(|(path, lra)| {
+        let name = match key {
+            GroupKey::Artist => tags.get(path).and_then(|tags| tags.artist.as_deref()),
+            GroupKey::Album => tags.get(path).and_then(|tags| tags.album.as_deref()),
+            GroupKey::Directory => Some(match album_key(path) {
+                "" => ".",
+                directory => directory,
+            }),
+        };
+        (name, *lra)
+    }));
+
+    let group_file_path = results_file_path.with_file_name(key.file_name());
+    let mut writer = format.create_file(&group_file_path)?;
+    format.write_line(&mut writer, group_header_line(key))?;
+    for group in &groups {
+        format.write_line(&mut writer, group.line(precision))?;
+    }
+    writer.flush()?;
+
+    human_println!(
+        "📚 按{}分组: {} 组，已写入 {}",
+        key.column_name(),
+        groups.len(),
+        group_file_path.display()
+    );
+    let describe = |group: &GroupStatistics| {
+        format!("{} ({} 个文件, 平均 {:.1} LU)", group.name, group.count, group.mean)
+    };
+    if let (Some(highest), Some(lowest)) = (groups.first(), groups.last()) {
+        human_println!("   平均 LRA 最高: {}", describe(highest));
+        if groups.len() > 1 {
+            human_println!("   平均 LRA 最低: {}", describe(lowest));
+        }
+    }
+    Ok(())
+}
+
+/// 计算 ReplayGain 列 (ReplayGain Extra Columns)
+///
+/// 音轨增益来自每个文件的综合响度；专辑增益把同一目录下本次测得综合响度的文件
+/// 视为一张专辑，按 [`album_replaygain`] 的能量平均近似计算。
+///
+/// # 参数
+/// - `measurements` - 本次运行的成功结果（包括复用的缓存结果）
+///
+/// # 返回值
+/// - 各文件的 ReplayGain 列值，对应 [`REPLAYGAIN_COLUMNS`]
+fn replaygain_extra_columns(measurements: &[FileMeasurement]) -> Vec<(FileKey, [String; 2])> {
+    let albums = album_replaygain(
+        measurements
+            .iter()
+            .filter_map(|m| m.integrated_lufs.map(|lufs| (m.path.as_str(), lufs))),
+    );
+    measurements
+        .iter()
+        .map(|m| {
+            let album = m.integrated_lufs.and_then(|_| albums.get(album_key(&m.path)).copied());
+            (m.path.clone(), replaygain_fields(m.replaygain_track_gain(), album))
+        })
+        .collect()
+}
+
+/// 一次分析运行的上下文 (Run Context)
+///
+/// 确定扫描根目录和结果文件之后不再变化的状态，由发现、处理和输出各阶段共享。
+struct RunContext<'a> {
+    options: &'a CliOptions,
+    /// 扫描根目录
+    base_folder_path: PathBuf,
+    /// 结果文件路径；输出到 stdout 时用于推导缓存等辅助文件的位置
+    results_file_path: PathBuf,
+    run_id: RunId,
+    /// 增量更新时只分析该时刻之后修改的文件，新结果与已有结果文件合并
+    newer_than: Option<SystemTime>,
+    /// 网络共享断连时的断路器（`--outage-threshold`、`--outage-budget`）
+    outage: Option<CircuitBreaker>,
+    /// 最先完成的文件全部以同一种错误失败时中止（`--abort-after`）
+    early_abort: EarlyAbort,
+    failures: RunFailures,
+    /// 本次运行的开始时间（汇总页脚中的用时）
+    started_at: Instant,
+}
+
+impl RunContext<'_> {
+    /// 单个文件的计算选项：命令行选项之外带上运行 ID（调试输出的文件名中使用）
+    fn calculation_options(&self) -> CalculationOptions {
+        CalculationOptions { run_id: Some(self.run_id.clone()), ..calculation_options(self.options) }
+    }
+
+    /// 并行处理的运行选项：断路器、提前中止和失败流；调度、取消和并发名额由各阶段补充
+    fn run_options(&self) -> RunOptions {
+        RunOptions {
+            outage: self.outage.clone(),
+            early_abort: Some(self.early_abort.clone()),
+            failures: Some(self.failures.stream.clone()),
+            ..RunOptions::default()
+        }
+    }
+}
+
+/// 本次运行的失败 (Run Failures)
+///
+/// 失败流在文件失败时把错误交给 [`FailureRecorder`]：立即写入失败记录文件，
+/// 并记下增量合并和诊断脚本需要的信息。
+struct RunFailures {
+    stream: FailureStream,
+    recorder: Arc<Mutex<FailureRecorder>>,
+}
+
+/// 逐条接收失败的文件 (Failure Recorder)
+#[derive(Default)]
+struct FailureRecorder {
+    /// 失败记录文件；输出到 stdout 时为 `None`
+    log: Option<FailuresLog>,
+    /// 分析期间消失的文件
+    vanished: HashSet<FileKey>,
+    /// 是否保留完整的错误列表（`--inspect-script`）
+    keep_errors: bool,
+    /// 全部失败文件的错误，只在 `keep_errors` 时保留
+    inspect_errors: Vec<ProcessFileError>,
+}
+
+impl RunFailures {
+    /// 创建失败流，统计中保留 `--max-error-messages` 条
+    fn start(results_file_path: &Path, options: &CliOptions, run_id: &RunId) -> Self {
+        let recorder = Arc::new(Mutex::new(FailureRecorder {
+            log: (!options.output_to_stdout()).then(|| FailuresLog::new(failures_path_for(results_file_path), run_id)),
+            keep_errors: options.inspect_script,
+            ..FailureRecorder::default()
+        }));
+        let shared = Arc::clone(&recorder);
+        let stream = FailureStream::with_handler(
+            options.max_error_messages.unwrap_or(DEFAULT_RETAINED_ERRORS),
+            move |error| {
+                let mut recorder = shared.lock().unwrap_or_else(|poisoned| poisoned.into_inner());
+                if let Some(log) = recorder.log.as_mut() {
+                    log.write_line(&format_file_error(error));
+                }
+                if error.vanished {
+                    recorder.vanished.insert(error.file_path.clone());
+                }
+                if recorder.keep_errors {
+                    recorder.inspect_errors.push(error.clone());
+                }
+            },
+        );
+        Self { stream, recorder }
+    }
+
+    /// 取出记录（分析结果之后）；之后的失败不再写入失败记录文件
+    fn take_recorder(&self) -> FailureRecorder {
+        std::mem::take(&mut *self.recorder.lock().unwrap_or_else(|poisoned| poisoned.into_inner()))
+    }
+}
+
+/// 失败记录文件 (Failures Log)
+///
+/// 文件失败时逐条写入（见 [`RunFailures`]），完整的错误列表不保存在内存中。第一次写入时才创建文件，
+/// 第一行是运行 ID（`# run: <ID>`）。写入失败时记下第一个错误，由 [`write_failures_file`] 返回。
+struct FailuresLog {
+    path: PathBuf,
+    run_id: RunId,
+    writer: Option<BufWriter<File>>,
+    error: Option<io::Error>,
+}
+
+impl FailuresLog {
+    fn new(path: PathBuf, run_id: &RunId) -> Self {
+        Self { path, run_id: run_id.clone(), writer: None, error: None }
+    }
+
+    /// 追加一行
+    fn write_line(&mut self, line: &dyn std::fmt::Display) {
+        if self.error.is_some() {
+            return;
+        }
+        let result = match self.writer.take() {
+            Some(writer) => Ok(writer),
+            None => self.create(),
+        }
+        .and_then(|mut writer| {
+            writeln!(writer, "{}", line)?;
+            Ok(writer)
+        });
+        match result {
+            Ok(writer) => self.writer = Some(writer),
+            Err(e) => self.error = Some(e),
+        }
+    }
+
+    fn create(&self) -> io::Result<BufWriter<File>> {
+        let mut writer = BufWriter::new(File::create(&self.path)?);
+        if let Some(line) = run_metadata_line(Some(&self.run_id)) {
+            writeln!(writer, "{}", line)?;
+        }
+        Ok(writer)
+    }
+}
+
+/// 写入失败记录文件 (Write Failures File)
+///
+/// 在已经逐条写入的失败文件之后追加违反阈值规则的文件，每行一条。
+/// 两者都为空时删除上一次运行遗留的失败记录文件，避免误导。
+///
+/// # 参数
+/// - `failures_log` - 已写入失败文件的失败记录
+/// - `violations` - 阈值违规记录
+///
+/// # 返回值
+/// - `Ok(())` - 写入或清理成功
+/// - `Err(AppError::Io)` - 写入失败
+fn write_failures_file(mut failures_log: FailuresLog, violations: &[ThresholdViolation]) -> Result<(), AppError> {
+    for violation in violations {
+        failures_log.write_line(violation);
+    }
+    if let Some(e) = failures_log.error {
+        return Err(e.into());
+    }
+    let Some(mut writer) = failures_log.writer else {
+        return match std::fs::remove_file(&failures_log.path) {
+            Err(e) if e.kind() != std::io::ErrorKind::NotFound => Err(e.into()),
+            _ => Ok(()),
+        };
+    };
+    writer.flush()?;
+
+    human_println!("📄 失败记录已写入: {}", failures_log.path.display());
+    Ok(())
+}
+
+/// 写入失败文件诊断脚本 (Write Failure Inspect Script)
+///
+/// # 参数
+/// - `results_file_path` - 结果文件路径（脚本写在同一目录）
+/// - `base_folder_path` - 扫描根目录
+/// - `errors` - 全部失败文件的错误（统计中只保留了一部分）
+/// - `options` - 命令行选项
+///
+/// # 返回值
+/// - `Ok(())` - 写入或清理成功
+/// - `Err(AppError::Io)` - 写入失败
+fn write_failure_inspect_script(
+    results_file_path: &Path,
+    base_folder_path: &Path,
+    errors: &[ProcessFileError],
+    options: &CliOptions,
+) -> Result<(), AppError> {
+    let shell = ShellSyntax::native();
+    let script_path = inspect_script_path_for(results_file_path, shell);
+    if write_inspect_script(&script_path, shell, base_folder_path, errors, &calculation_options(options))? {
+        human_println!("🩺 诊断命令脚本已写入: {}", script_path.display());
+    }
+    Ok(())
+}
+
+/// `--porcelain progress` 的格式化器，跨工作线程共享已完成计数
+static PROGRESS_LINES: Mutex<ProgressLineFormatter> = Mutex::new(ProgressLineFormatter::new());
+
+/// 输出进度事件 (Emit Progress Event)
+///
+/// `--porcelain` 模式下将事件写入 stdout 并立即刷新，便于包装程序逐行读取：
+/// `ndjson` 每个事件一行 JSON，`progress` 转换为文本记录；其他模式下不输出任何内容。
+///
+/// # 参数
+/// - `options` - 命令行选项
+/// - `event` - 进度事件
+fn emit_event(options: &CliOptions, event: &ProgressEvent) {
+    match options.porcelain {
+        Some(PorcelainFormat::Ndjson) => emit_json_line(event),
+        Some(PorcelainFormat::Progress) => {
+            // 持有锁直到写完，保证多个工作线程的记录不会交错、PROGRESS 计数单调递增
+            let mut formatter = PROGRESS_LINES.lock().unwrap_or_else(|poisoned| poisoned.into_inner());
+            let mut stdout = std::io::stdout().lock();
+            for line in formatter.lines(event) {
+                let _ = writeln!(stdout, "{}", line);
+            }
+            let _ = stdout.flush();
+        }
+        None => {}
+    }
+}
+
+/// 将事件序列化为一行 JSON 写入 stdout
+#[cfg(feature = "serde")]
+fn emit_json_line(event: &ProgressEvent) {
+    match event.to_json_line() {
+        Ok(line) => {
+            let mut stdout = std::io::stdout().lock();
+            let _ = writeln!(stdout, "{}", line);
+            let _ = stdout.flush();
+        }
+        Err(e) => human_eprintln!("⚠️  无法序列化进度事件: {}", e),
+    }
+}
+
+#[cfg(not(feature = "serde"))]
+fn emit_json_line(_event: &ProgressEvent) {}
+
+/// 写入 JSON 统计文件 (Write Stats JSON)
+///
+/// 将处理统计（计数、按错误类型的分类、错误列表）写入 JSON 文件，
+/// 便于仪表盘等外部工具直接读取。
+///
+/// # 参数
+/// - `stats_json_path` - JSON 输出路径
+/// - `stats` - 处理统计信息
+///
+/// # 返回值
+/// - `Ok(())` - 写入成功
+/// - `Err(...)` - 序列化或写入失败
+#[cfg(feature = "serde")]
+fn write_stats_json(
+    stats_json_path: &Path,
+    stats: &ProcessingStats
+) -> Result<(), AppError> {
+    let json = stats.to_json().map_err(std::io::Error::from)?;
+    std::fs::write(stats_json_path, json)?;
+    human_println!("📄 统计信息已导出: {}", stats_json_path.display());
+    Ok(())
+}
+
+#[cfg(not(feature = "serde"))]
+fn write_stats_json(
+    _stats_json_path: &Path,
+    _stats: &ProcessingStats
+) -> Result<(), AppError> {
+    Err(AppError::Configuration(
+        "--stats-json 需要启用 serde 特性重新编译".to_string()
+    ))
+}
+
+/// 写入初始结果文件 (Write Initial Results File)
+///
+/// 将成功处理的结果写入文件，包含表头和数据行。
+///
+/// # 参数
+/// - `results_file_path` - 结果文件路径
+/// - `header_line` - 表头行（包括附加列名）
+/// - `run_id` - 本次运行的 ID，与使用的 FFmpeg 一起写在格式标记之后
+/// - `successful_results` - 成功处理的结果列表
+/// - `extra_columns` - 各条目的附加列，追加在 LRA 值之后
+/// - `footer` - 写在数据之后的汇总页脚行（之后的排序会保留）
+/// - `format` - BOM 和换行格式（之后的排序会保持该格式）
+/// - `verify` - 替换结果文件之前重新读取校验
+///
+/// 先写入临时文件，校验通过后才替换结果文件，失败时上一次的结果文件保持不变。
+///
+/// # 返回值
+/// - `Ok(())` - 写入成功
+/// - `Err(...)` - 写入或校验失败
+#[allow(clippy::too_many_arguments)]
+fn write_initial_results_file(
+    results_file_path: &Path,
+    header_line: &str,
+    run_id: &RunId,
+    successful_results: &[(FileKey, f64)],
+    extra_columns: &ExtraColumns,
+    footer: &[String],
+    format: TextFormat,
+    precision: u8,
+    verify: bool,
+) -> Result<(), AppError> {
+    human_println!("📝 正在写入结果文件...");
+
+    let mut file = AtomicFile::create(results_file_path, format)?;
+    let writer = file.writer();
+
+    write_results_header(writer, format, header_line, Some(run_id), resolved_program("ffmpeg").as_deref())?;
+    for (path_str, lra) in successful_results {
+        let extra = extra_columns.get(path_str).map_or("", String::as_str);
+        let lra = LraValue(*lra).with_precision(precision);
+        format.write_line(writer, format_args!("{} - {}{}", path_str, lra, extra))?;
+    }
+    for line in footer {
+        format.write_line(writer, line)?;
+    }
+    file.sync()?;
+    if verify {
+        verify_results_file(file.temp_path(), header_line, successful_results, precision)
+            .map_err(|e| verification_failure(results_file_path, e))?;
+    }
+    file.commit()?;
+
+    human_println!("✅ 结果文件写入完成");
+    Ok(())
+}
+
+/// 将结果输出到 stdout (Write Results to Stdout)
+///
+/// `--output -` 使用：条目已经在内存中排好序，格式与结果文件相同。
+/// 人类可读输出此时已切换到 stderr，stdout 上只有结果数据。
+///
+/// # 参数
+/// - `header_line` - 表头行
+/// - `run_id` - 本次运行的 ID，与使用的 FFmpeg 一起写在格式标记之后
+/// - `sort_order` - 条目的排序方式（不是默认排序时在表头后写入元数据行）
+/// - `successful_results` - 已排序的成功结果
+/// - `extra_columns` - 附加列
+/// - `footer` - 汇总页脚行
+/// - `format` - 行尾和编码
+/// - `precision` - LRA 值保留的小数位数
+///
+/// # 返回值
+/// - `Ok(())` - 输出成功
+/// - `Err(...)` - 写入 stdout 失败（例如管道已关闭）
+#[allow(clippy::too_many_arguments)]
+fn write_results_to_stdout(
+    header_line: &str,
+    run_id: &RunId,
+    sort_order: SortOrder,
+    successful_results: &[(FileKey, f64)],
+    extra_columns: &ExtraColumns,
+    footer: &[String],
+    format: TextFormat,
+    precision: u8,
+) -> Result<(), AppError> {
+    let mut stdout = std::io::BufWriter::new(std::io::stdout().lock());
+
+    write_results_header(&mut stdout, format, header_line, Some(run_id), resolved_program("ffmpeg").as_deref())?;
+    if let Some(metadata_line) = sort_order.metadata_line() {
+        format.write_line(&mut stdout, metadata_line)?;
+    }
+    for (path_str, lra) in successful_results {
+        let extra = extra_columns.get(path_str).map_or("", String::as_str);
+        let lra = LraValue(*lra).with_precision(precision);
+        format.write_line(&mut stdout, format_args!("{} - {}{}", path_str, lra, extra))?;
+    }
+    for line in footer {
+        format.write_line(&mut stdout, line)?;
+    }
+    stdout.flush()?;
+    Ok(())
+}
+
+/// 根据需要排序结果文件 (Sort Results File If Needed)
+///
+/// 对结果文件进行排序，并处理可能的排序错误。
+///
+/// # 参数
+/// - `results_file_path` - 结果文件路径
+/// - `entry_count` - 结果文件中的条目数量
+/// - `sort_order` - 排序方式
+/// - `precision` - LRA 值至少保留的小数位数
+/// - `verify` - 替换结果文件之前重新读取校验
+///
+/// # 返回值
+/// - `Ok(())` - 排序成功或跳过（排序失败时未排序的结果文件仍然可用）
+/// - `Err(...)` - 排序后的内容校验失败
+fn sort_results_file_if_needed(
+    results_file_path: &Path,
+    entry_count: usize,
+    sort_order: SortOrder,
+    precision: u8,
+    verify: bool,
+) -> Result<(), AppError> {
+    human_println!("🔄 正在对结果文件进行排序 ({} 个条目)...", entry_count);
+
+    match sort_results_file(results_file_path, RESULTS_HEADER_LINE, Some(sort_order), precision, verify) {
+        Ok(report) => {
+            display_sort_report(&report);
+            human_println!("✅ 结果文件排序完成");
+            Ok(())
+        }
+        // 校验失败说明写入的文件不可信，不能当作普通的排序失败忽略
+        Err(e) if is_verification_failure(&e) => Err(verification_failure(results_file_path, e)),
+        Err(e) => {
+            human_eprintln!(
+                "⚠️  排序结果文件失败: {}\n\
+                 📝 原始结果文件仍然可用: {}",
+                e,
+                results_file_path.display()
+            );
+            // 排序失败不应该导致整个程序失败
+            Ok(())
+        }
+    }
+}
+
+/// 是否为结果文件校验失败
+fn is_verification_failure(error: &io::Error) -> bool {
+    error.get_ref().is_some_and(|inner| inner.is::<ResultsVerificationError>())
+}
+
+/// 报告结果文件校验失败 (Report Verification Failure)
+///
+/// 校验失败时提示结果文件保持上一次的内容，其他错误原样返回。
+///
+/// # 参数
+/// - `results_file_path` - 结果文件路径
+/// - `error` - 写入或校验时的错误
+fn verification_failure(results_file_path: &Path, error: io::Error) -> AppError {
+    if is_verification_failure(&error) {
+        human_eprintln!(
+            "{}",
+            error_color_mode().paint(
+                Style::Failure,
+                format!(
+                    "❌ 写入的结果文件读回后与写入的内容不一致 (磁盘已满或存储设备不可靠?)\n\
+                     📝 {} 保持上一次的内容，未被替换",
+                    results_file_path.display()
+                ),
+            )
+        );
+    }
+    AppError::Io(error)
+}
+
+/// 显示完成信息 (Display Completion Message)
+///
+/// 显示横幅时输出完整的完成横幅和使用建议；否则（`--quiet` 或输出不是终端）只输出结果位置，
+/// `--quiet` 时连结果位置也不输出。
+///
+/// # 参数
+/// - `results_file_path` - 结果文件路径
+/// - `run_id` - 本次运行的 ID
+/// - `options` - 命令行选项（`--output -` 时结果在 stdout 上）
+/// - `show_banner` - 是否显示完成横幅，见 [`show_banners`]
+/// - `custom_advice` - `--advice-file` 的内容
+fn display_completion_message(
+    results_file_path: &Path,
+    run_id: &RunId,
+    options: &CliOptions,
+    show_banner: bool,
+    custom_advice: Option<&str>,
+) {
+    let location = match options.output_to_stdout() {
+        true => human_format!("📄 结果已输出到 stdout"),
+        false => human_format!("📄 结果文件位置: {}", results_file_path.display()),
+    };
+    if show_banner {
+        let color = human_color_mode();
+        human_println!(
+            "{}",
+            completion_banner(
+                &location,
+                &options.sort_order.unwrap_or_default().description(),
+                run_id,
+                local_now(),
+                &lra_advice(custom_advice, color, output_charset()),
+                color,
+                output_charset()
+            )
+        );
+    } else if !options.quiet {
+        human_println!("{}", location);
+    }
 }
 
 
@@ -0,0 +1,74 @@
+//! 运行结果模块 (Run Outcome Module)
+//!
+//! 扫描根目录下没有需要处理的文件（空目录、只有非音频文件等）不是失败：每天定时运行时
+//! "没有新文件"是正常结果。[`RunOutcome`] 把这种情况与正常完成区分开，而不是作为
+//! [`AppError`](crate::error::AppError) 返回；命令行程序据此输出成功信息，并以各原因独立的退出码退出，
+//! 脚本仍然可以区分这些情况。
+
+use std::fmt;
+use std::path::{Path, PathBuf};
+
+use crate::audio::ScanReport;
+use crate::error::EmptyScanReason;
+
+/// 运行结果 (Run Outcome)
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum RunOutcome {
+    /// 正常完成
+    Completed,
+    /// 没有需要处理的文件
+    NothingToDo {
+        /// 扫描的根目录
+        root: PathBuf,
+        /// 没有找到文件的具体原因
+        reason: EmptyScanReason,
+    },
+}
+
+impl RunOutcome {
+    /// 由扫描报告判断是否无事可做 (Outcome from Scan Report)
+    ///
+    /// # 返回值
+    /// - `Some(RunOutcome::NothingToDo)` - 没有找到音频文件
+    /// - `None` - 找到了至少一个音频文件，需要继续处理
+    ///
+    /// # 示例
+    /// ```
+    /// use std::path::Path;
+    /// use lra_calculator_rust::audio::ScanReport;
+    /// use lra_calculator_rust::error::EmptyScanReason;
+    /// use lra_calculator_rust::outcome::RunOutcome;
+    ///
+    /// let report = ScanReport { skipped_files: 2, ..ScanReport::default() };
+    /// let outcome = RunOutcome::from_scan(Path::new("/music"), &report).unwrap();
+    /// assert_eq!(outcome.reason(), Some(&EmptyScanReason::NoSupportedAudio { other_files: 2 }));
+    /// assert_eq!(outcome.exit_code(), 9);
+    /// ```
+    pub fn from_scan(root: &Path, report: &ScanReport) -> Option<Self> {
+        report.empty_reason().map(|reason| RunOutcome::NothingToDo { root: root.to_path_buf(), reason })
+    }
+
+    /// 没有需要处理的文件时的原因
+    pub fn reason(&self) -> Option<&EmptyScanReason> {
+        match self {
+            RunOutcome::Completed => None,
+            RunOutcome::NothingToDo { reason, .. } => Some(reason),
+        }
+    }
+
+    /// 对应的进程退出码：正常完成为 0，无事可做时见 [`EmptyScanReason::exit_code`]
+    pub fn exit_code(&self) -> u8 {
+        self.reason().map_or(0, EmptyScanReason::exit_code)
+    }
+}
+
+impl fmt::Display for RunOutcome {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            RunOutcome::Completed => write!(f, "运行完成"),
+            RunOutcome::NothingToDo { root, reason } => {
+                write!(f, "'{}' 下没有需要处理的音频文件: {}", root.display(), reason)
+            }
+        }
+    }
+}
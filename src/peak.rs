@@ -0,0 +1,123 @@
+//! 真峰值检查模块 (True Peak Check Module)
+//!
+//! `--true-peak` 让 ebur128 额外测量真峰值（`ebur128=peak=true`）。真峰值接近 0 dBTP
+//! 的文件在有损编码（MP3、AAC、Opus）后通常会削波，因此运行结束时列出真峰值超过上限
+//! （默认 [`DEFAULT_TRUE_PEAK_CEILING`]，`--true-peak-ceiling`）的文件，
+//! 需要时写入单独的报告文件（`--clipping-report`）。
+//!
+//! 检查只针对有真峰值的测量结果：未启用 `--true-peak` 或输出中找不到真峰值的文件
+//! 不参与检查，也不视为失败。
+
+use std::io::{self, Write};
+use std::path::Path;
+
+use crate::file_key::FileKey;
+use crate::processor::FileMeasurement;
+use crate::utils::TextFormat;
+
+/// 默认的真峰值上限（dBTP）
+pub const DEFAULT_TRUE_PEAK_CEILING: f64 = -1.0;
+
+/// 削波风险报告的表头
+pub const CLIPPING_REPORT_HEADER: &str = "文件路径\t真峰值 (dBTP)";
+
+/// 真峰值超过上限的文件 (Clipping Risk)
+#[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct ClippingRisk {
+    /// 显示路径
+    pub path: FileKey,
+    /// 真峰值（dBTP）
+    pub true_peak_dbtp: f64,
+}
+
+/// 找出真峰值超过上限的文件 (Find Clipping Risks)
+///
+/// # 参数
+/// - `measurements` - 成功的测量结果；没有真峰值的文件被忽略
+/// - `ceiling` - 真峰值上限（dBTP），等于上限的文件不算超过
+///
+/// # 返回值
+/// - 超过上限的文件，按真峰值从高到低排列，相同时按路径排列
+///
+/// # 示例
+/// ```
+/// use lra_calculator_rust::peak::{find_clipping_risks, DEFAULT_TRUE_PEAK_CEILING};
+/// use lra_calculator_rust::processor::FileMeasurement;
+///
+/// let measurements = [
+///     FileMeasurement::new("loud.flac", 5.0).with_true_peak(Some(0.3)),
+///     FileMeasurement::new("safe.flac", 9.0).with_true_peak(Some(-1.5)),
+///     FileMeasurement::new("unknown.flac", 9.0),
+/// ];
+/// let risks = find_clipping_risks(&measurements, DEFAULT_TRUE_PEAK_CEILING);
+/// assert_eq!(risks.len(), 1);
+/// assert_eq!(risks[0].path, "loud.flac");
+/// ```
+pub fn find_clipping_risks(measurements: &[FileMeasurement], ceiling: f64) -> Vec<ClippingRisk> {
+    let mut risks: Vec<ClippingRisk> = measurements
+        .iter()
+        .filter_map(|measurement| {
+            let true_peak_dbtp = measurement.true_peak_dbtp.filter(|peak| *peak > ceiling)?;
+            Some(ClippingRisk { path: measurement.path.clone(), true_peak_dbtp })
+        })
+        .collect();
+    risks.sort_by(|a, b| b.true_peak_dbtp.total_cmp(&a.true_peak_dbtp).then_with(|| a.path.cmp(&b.path)));
+    risks
+}
+
+/// 写入削波风险报告 (Write Clipping Report)
+///
+/// 第一行是 [`CLIPPING_REPORT_HEADER`]，之后每个文件一行，制表符分隔。
+/// 没有超过上限的文件时只写表头，便于脚本区分"没有风险"和"没有运行"。
+///
+/// # 参数
+/// - `report_path` - 报告文件路径
+/// - `risks` - 见 [`find_clipping_risks`]
+/// - `format` - BOM 和换行格式
+pub fn write_clipping_report(report_path: &Path, risks: &[ClippingRisk], format: TextFormat) -> io::Result<()> {
+    let mut writer = format.create_file(report_path)?;
+    format.write_line(&mut writer, CLIPPING_REPORT_HEADER)?;
+    for risk in risks {
+        format.write_line(&mut writer, format_args!("{}\t{:.1}", risk.path, risk.true_peak_dbtp))?;
+    }
+    writer.flush()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::fs;
+    use tempfile::TempDir;
+
+    /// 测试上限边界、排序和缺少真峰值的文件
+    #[test]
+    fn test_find_clipping_risks() {
+        let measurements = [
+            FileMeasurement::new("b.flac", 5.0).with_true_peak(Some(-0.5)),
+            FileMeasurement::new("at_ceiling.flac", 5.0).with_true_peak(Some(-1.0)),
+            FileMeasurement::new("a.flac", 5.0).with_true_peak(Some(-0.5)),
+            FileMeasurement::new("hot.flac", 5.0).with_true_peak(Some(1.2)),
+            FileMeasurement::new("no_peak.flac", 5.0),
+        ];
+        let risks = find_clipping_risks(&measurements, DEFAULT_TRUE_PEAK_CEILING);
+        let paths: Vec<&str> = risks.iter().map(|risk| risk.path.as_str()).collect();
+        assert_eq!(paths, ["hot.flac", "a.flac", "b.flac"]);
+        assert!(find_clipping_risks(&measurements, 2.0).is_empty());
+        assert_eq!(find_clipping_risks(&measurements, -3.0).len(), 4);
+    }
+
+    /// 测试报告文件的表头和行格式
+    #[test]
+    fn test_write_clipping_report() {
+        let temp_dir = TempDir::new().expect("无法创建临时目录");
+        let report_path = temp_dir.path().join("clipping.txt");
+        let risks = [ClippingRisk { path: "Album/01.flac".into(), true_peak_dbtp: 0.34 }];
+        write_clipping_report(&report_path, &risks, TextFormat { bom: false, crlf: false }).unwrap();
+        let contents = fs::read_to_string(&report_path).unwrap();
+        assert_eq!(contents.lines().collect::<Vec<_>>(), [CLIPPING_REPORT_HEADER, "Album/01.flac\t0.3"]);
+
+        write_clipping_report(&report_path, &[], TextFormat { bom: false, crlf: false }).unwrap();
+        assert_eq!(fs::read_to_string(&report_path).unwrap().lines().count(), 1);
+    }
+}
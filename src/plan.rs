@@ -0,0 +1,509 @@
+//! 处理计划模块 (Processing Plan Module)
+//!
+//! 把一次分析拆成三个步骤，调用方（例如 GUI）可以在扫描之后、分析之前检查并修改要处理的文件：
+//!
+//! 1. [`scan`] - 遍历目录，得到 [`ScanResult`]。大小、修改时间（增量更新）和排除规则在扫描时过滤，
+//!    相同的显示路径在扫描时去重（见 [`crate::file_key::DisplayPathDeduplicator`]）
+//! 2. [`ScanResult::into_plan`] - 按缓存划出未变化、可以直接复用结果的文件，在其余需要分析的文件中
+//!    应用 `--limit` / `--sample` 选择，得到 [`ProcessingPlan`]。计划可以序列化，[`ProcessingPlan::files`] 可以在执行前任意增删
+//! 3. [`execute`] - 分析计划中的文件，与复用的结果一起得到 [`ProcessingOutcome`]
+//!
+//! 每个计划带有一个运行 ID（[`ProcessingPlan::run_id`]），执行时用于调试输出的文件名，
+//! 并在结果中返回，调用方可以把它写入自己的产物。
+//!
+//! 命令行程序使用同样的步骤，`--dry-run` 在第 2 步之后输出计划并退出。
+//! `--pipeline` 边扫描边分析，使用 [`scan_and_execute`]：按同样的规则逐个划分扫描到的文件，
+//! 不等到完整的计划，结果同样是 [`ProcessingOutcome`]。
+//!
+//! # 示例
+//! ```rust,no_run
+//! use std::path::Path;
+//! use lra_calculator_rust::audio::ScanOptions;
+//! use lra_calculator_rust::plan::{execute, scan, PlanOptions};
+//! use lra_calculator_rust::CalculationOptions;
+//!
+//! let mut plan = scan(Path::new("/music"), &ScanOptions::default()).into_plan(&PlanOptions::default());
+//! // 用户在界面上取消勾选了一个文件
+//! plan.deselect("Live/01.flac");
+//! let outcome = execute(plan, &CalculationOptions::default());
+//! println!("成功 {} 个", outcome.results.iter().filter(|result| result.is_success()).count());
+//! ```
+
+use std::collections::HashMap;
+use std::path::{Path, PathBuf};
+
+use crate::audio::{scan_audio_files_iter, CalculationOptions, ScanOptions, ScanReport};
+use crate::cache::{CacheEntry, FileFingerprint, ResultsCache};
+use crate::events::{DefaultSink, EventSink, RunEvent};
+use crate::file_key::{DisplayPathCollision, FileKey};
+use crate::processor::{
+    process_files_parallel_with_options, process_files_pipelined_with_options, FileOutcome, ResultOrder, RunOptions,
+    SCAN_PROGRESS_INTERVAL,
+};
+use crate::run_id::RunId;
+use crate::selection::{FileSelection, SelectionReport};
+
+/// 扫描结果 (Scan Result)
+#[derive(Debug, Clone, Default)]
+pub struct ScanResult {
+    /// 扫描根目录
+    pub root: PathBuf,
+    /// 扫描报告；`files` 可以在生成计划之前补充（例如按内容识别出的音频文件）
+    pub report: ScanReport,
+}
+
+/// 扫描目录 (Scan)
+///
+/// # 参数
+/// - `root` - 扫描根目录
+/// - `options` - 排除规则和过滤条件
+pub fn scan(root: &Path, options: &ScanOptions) -> ScanResult {
+    scan_with_events(root, options, &|_: &RunEvent| {})
+}
+
+/// 扫描目录并报告事件 (Scan with Events)
+///
+/// 与 [`scan`] 相同，开始时发出 [`RunEvent::ScanStarted`]，之后每发现 [`SCAN_PROGRESS_INTERVAL`]
+/// 个音频文件发出一次 [`RunEvent::ScanProgress`]。待分析的文件要在生成计划之后才能确定，
+/// 因此 [`RunEvent::ScanFinished`] 由调用方发出。
+pub fn scan_with_events<S: EventSink + ?Sized>(root: &Path, options: &ScanOptions, sink: &S) -> ScanResult {
+    sink.emit(&RunEvent::ScanStarted { root: root.to_path_buf() });
+    let mut iter = scan_audio_files_iter(root, options);
+    let mut files = Vec::new();
+    for file in iter.by_ref() {
+        files.push(file);
+        if files.len() % SCAN_PROGRESS_INTERVAL == 0 {
+            sink.emit(&RunEvent::ScanProgress { found: files.len() });
+        }
+    }
+    ScanResult { root: root.to_path_buf(), report: ScanReport { files, ..iter.into_report() } }
+}
+
+/// 生成计划的选项 (Plan Options)
+#[derive(Debug, Clone, Copy, Default)]
+pub struct PlanOptions<'a> {
+    /// 只分析部分文件（`--limit`、`--sample`）；只在需要分析的文件中选择，复用缓存的文件总是保留
+    pub selection: Option<FileSelection>,
+    /// 上次运行的结果缓存；文件未变化时直接复用缓存结果
+    pub cache: Option<&'a ResultsCache>,
+    /// 计划使用的运行 ID；为 `None` 时生成新的 ID
+    pub run_id: Option<&'a RunId>,
+}
+
+/// 计划分析的文件 (Planned File)
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct PlannedFile {
+    /// 完整路径
+    pub path: PathBuf,
+    /// 显示路径
+    pub display_path: FileKey,
+    /// 生成计划时的文件指纹，用于更新缓存；没有使用缓存或无法读取元数据时为 `None`
+    #[cfg_attr(feature = "serde", serde(default))]
+    pub fingerprint: Option<FileFingerprint>,
+}
+
+impl PlannedFile {
+    /// 创建没有指纹的计划文件
+    pub fn new(path: impl Into<PathBuf>, display_path: impl Into<FileKey>) -> Self {
+        Self { path: path.into(), display_path: display_path.into(), fingerprint: None }
+    }
+}
+
+/// 直接复用缓存结果的文件 (Reused File)
+#[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct ReusedFile {
+    /// 完整路径
+    pub path: PathBuf,
+    /// 显示路径
+    pub display_path: FileKey,
+    /// 复用的缓存条目，其大小和修改时间与文件当前的一致
+    pub entry: CacheEntry,
+}
+
+/// 处理计划 (Processing Plan)
+///
+/// 由 [`ScanResult::into_plan`] 生成，交给 [`execute`] 执行。
+#[derive(Debug, Clone, Default, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct ProcessingPlan {
+    /// 扫描根目录
+    pub root: PathBuf,
+    /// 需要分析的文件，按扫描顺序排列；执行前可以增删
+    pub files: Vec<PlannedFile>,
+    /// 未变化、直接复用缓存结果的文件
+    pub reused: Vec<ReusedFile>,
+    /// 限量或抽样时的候选和选中数量
+    pub selection: Option<SelectionReport>,
+    /// 扫描时显示路径被改名的文件
+    pub path_collisions: Vec<DisplayPathCollision>,
+    /// 运行 ID；[`ScanResult::into_plan`] 总是设置，为 `None` 时（例如手工构造的计划）执行时生成
+    #[cfg_attr(feature = "serde", serde(default))]
+    pub run_id: Option<RunId>,
+}
+
+impl ProcessingPlan {
+    /// 计划中的文件总数（需要分析的加上复用结果的）
+    pub fn len(&self) -> usize {
+        self.files.len() + self.reused.len()
+    }
+
+    /// 计划中是否没有任何文件
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    /// 从计划中去掉一个文件，无论它需要分析还是复用结果 (Deselect)
+    ///
+    /// # 返回值
+    /// - `true` - 找到并去掉了该文件
+    pub fn deselect(&mut self, display_path: &str) -> bool {
+        let before = self.len();
+        self.files.retain(|file| file.display_path != display_path);
+        self.reused.retain(|file| file.display_path != display_path);
+        self.len() != before
+    }
+
+    /// 计划中所有文件的完整路径，需要分析的在前
+    pub fn paths(&self) -> impl Iterator<Item = &Path> {
+        self.files.iter().map(|file| file.path.as_path()).chain(self.reused.iter().map(|file| file.path.as_path()))
+    }
+}
+
+impl ScanResult {
+    /// 生成处理计划 (Into Plan)
+    ///
+    /// 先按 `options.cache` 划出未变化的文件，再在需要分析的文件中应用 `options.selection`：
+    /// `--limit 10` 分析 10 个缓存中没有的文件，而不是在前 10 个文件中去掉命中缓存的。
+    /// 无法读取元数据的文件总是需要分析（分析阶段会给出具体错误）。
+    ///
+    /// # 示例
+    /// ```
+    /// use lra_calculator_rust::plan::{PlanOptions, ScanResult};
+    /// use lra_calculator_rust::selection::FileSelection;
+    ///
+    /// let mut scan = ScanResult::default();
+    /// scan.report.files = vec![("/m/a.flac".into(), "a.flac".into()), ("/m/b.flac".into(), "b.flac".into())];
+    /// let plan = scan.into_plan(&PlanOptions { selection: Some(FileSelection::Limit(1)), ..PlanOptions::default() });
+    /// assert_eq!(plan.files.len(), 1);
+    /// assert_eq!(plan.selection.unwrap().candidates, 2);
+    /// assert!(plan.run_id.is_some());
+    /// ```
+    pub fn into_plan(self, options: &PlanOptions) -> ProcessingPlan {
+        let ScanResult { root, report } = self;
+        let mut files = Vec::new();
+        let mut reused = Vec::new();
+        for (path, display_path) in report.files {
+            match admit(path, display_path.into(), options.cache) {
+                Admission::Analyze(file) => files.push(file),
+                Admission::Reuse(file) => reused.push(file),
+            }
+        }
+
+        let candidates = files.len();
+        let selection = options.selection.map(|selection| {
+            files = selection.apply(std::mem::take(&mut files));
+            SelectionReport { selection, candidates, selected: files.len() }
+        });
+        ProcessingPlan {
+            root,
+            files,
+            reused,
+            selection,
+            path_collisions: report.path_collisions,
+            run_id: Some(options.run_id.cloned().unwrap_or_else(RunId::generate)),
+        }
+    }
+}
+
+/// 一个扫描到的文件按缓存划分的结果
+enum Admission {
+    /// 需要分析
+    Analyze(PlannedFile),
+    /// 未变化，直接复用缓存结果
+    Reuse(ReusedFile),
+}
+
+/// 按缓存划分一个文件；没有缓存时不读取指纹
+fn admit(path: PathBuf, display_path: FileKey, cache: Option<&ResultsCache>) -> Admission {
+    let Some(cache) = cache else {
+        return Admission::Analyze(PlannedFile { path, display_path, fingerprint: None });
+    };
+    let fingerprint = FileFingerprint::of(&path).ok();
+    match fingerprint.and_then(|fingerprint| cache.cached_entry(&display_path, &fingerprint)) {
+        Some(entry) => Admission::Reuse(ReusedFile { path, display_path, entry: *entry }),
+        None => Admission::Analyze(PlannedFile { path, display_path, fingerprint }),
+    }
+}
+
+/// 执行结果 (Processing Outcome)
+#[derive(Debug, Default)]
+pub struct ProcessingOutcome {
+    /// 分析结果（按计划中的顺序），之后是复用的缓存结果
+    pub results: Vec<FileOutcome>,
+    /// 复用缓存结果的文件数量
+    pub reused: usize,
+    /// 计划中已知的文件指纹，用于更新缓存
+    pub fingerprints: HashMap<FileKey, FileFingerprint>,
+    /// 见 [`ProcessingPlan::selection`]
+    pub selection: Option<SelectionReport>,
+    /// 见 [`ProcessingPlan::path_collisions`]
+    pub path_collisions: Vec<DisplayPathCollision>,
+    /// 执行使用的运行 ID：`options.run_id`，没有设置时为计划的 ID（计划也没有时新生成）
+    pub run_id: Option<RunId>,
+}
+
+/// 执行处理计划 (Execute)
+///
+/// 启用 `cli` 特性时分析进度输出到控制台（`ConsoleSink`），否则不报告进度；使用默认的运行选项。
+///
+/// # 参数
+/// - `plan` - 处理计划
+/// - `options` - 单个文件的计算选项
+pub fn execute(plan: ProcessingPlan, options: &CalculationOptions) -> ProcessingOutcome {
+    execute_with_sink(plan, options, &RunOptions::default(), &DefaultSink::default())
+}
+
+/// 执行处理计划并报告事件 (Execute with Sink)
+///
+/// 与 [`execute`] 相同，按 `run` 控制调度、取消和并发名额等，分析过程中的事件交给 `sink`（见
+/// [`process_files_parallel_with_options`]）；复用缓存的文件不产生事件。
+pub fn execute_with_sink<S>(
+    plan: ProcessingPlan,
+    options: &CalculationOptions,
+    run: &RunOptions,
+    sink: &S,
+) -> ProcessingOutcome
+where
+    S: EventSink + ?Sized,
+{
+    let run_id = options.run_id.clone().or(plan.run_id).unwrap_or_else(RunId::generate);
+    let options = &CalculationOptions { run_id: Some(run_id.clone()), ..options.clone() };
+    let mut fingerprints = HashMap::new();
+    let mut to_process = Vec::with_capacity(plan.files.len());
+    for file in plan.files {
+        if let Some(fingerprint) = file.fingerprint {
+            fingerprints.insert(file.display_path.clone(), fingerprint);
+        }
+        to_process.push((file.path, file.display_path));
+    }
+
+    // 无论调度顺序如何，结果都按计划中的顺序排列
+    let results = process_files_parallel_with_options(to_process, ResultOrder::InputOrder, options, run, sink);
+    let outcome = ProcessingOutcome {
+        selection: plan.selection,
+        path_collisions: plan.path_collisions,
+        ..ProcessingOutcome::default()
+    };
+    outcome.with_results(results, plan.reused, fingerprints, run_id)
+}
+
+/// 边扫描边执行 (Scan and Execute)
+///
+/// `--pipeline` 使用的流式版本：发出 [`RunEvent::ScanStarted`] 后遍历目录，每个扫描到的文件
+/// 按 [`ScanResult::into_plan`] 的规则立即划分，需要分析的文件不等扫描完成就交给工作线程
+/// （[`process_files_pipelined_with_options`]）。事先不知道文件总数，因此不做 `--limit` / `--sample` 选择。
+///
+/// # 参数
+/// - `root` - 扫描根目录
+/// - `scan_options` - 排除规则和过滤条件
+/// - `cache` - 上次运行的结果缓存；文件未变化时直接复用缓存结果
+/// - `options` - 单个文件的计算选项；`run_id` 为 `None` 时生成新的 ID
+/// - `run` - 运行选项；总是按发现顺序调度
+/// - `sink` - 事件接收者
+///
+/// # 返回值
+/// - 扫描报告（`files` 为空，文件已经交给分析）和与 [`execute_with_sink`] 形式相同的执行结果
+pub fn scan_and_execute<S>(
+    root: &Path,
+    scan_options: &ScanOptions,
+    cache: Option<&ResultsCache>,
+    options: &CalculationOptions,
+    run: &RunOptions,
+    sink: &S,
+) -> (ScanReport, ProcessingOutcome)
+where
+    S: EventSink + ?Sized,
+{
+    sink.emit(&RunEvent::ScanStarted { root: root.to_path_buf() });
+    let run_id = options.run_id.clone().unwrap_or_else(RunId::generate);
+    let options = &CalculationOptions { run_id: Some(run_id.clone()), ..options.clone() };
+    let mut scan = scan_audio_files_iter(root, scan_options);
+    let mut fingerprints = HashMap::new();
+    let mut reused = Vec::new();
+    let results = {
+        let mut files = scan.by_ref().filter_map(|(path, display_path)| match admit(path, display_path.into(), cache) {
+            Admission::Analyze(file) => {
+                if let Some(fingerprint) = file.fingerprint {
+                    fingerprints.insert(file.display_path.clone(), fingerprint);
+                }
+                Some((file.path, file.display_path))
+            }
+            Admission::Reuse(file) => {
+                reused.push(file);
+                None
+            }
+        });
+        // 按发现顺序输出结果，使结果文件等产物在多次运行之间保持一致
+        process_files_pipelined_with_options(&mut files, ResultOrder::InputOrder, options, run, sink)
+    };
+
+    let report = scan.into_report();
+    let outcome = ProcessingOutcome { path_collisions: report.path_collisions.clone(), ..ProcessingOutcome::default() };
+    (report, outcome.with_results(results, reused, fingerprints, run_id))
+}
+
+impl ProcessingOutcome {
+    /// 填入分析结果，之后追加复用的缓存结果及其指纹
+    fn with_results(
+        mut self,
+        mut results: Vec<FileOutcome>,
+        reused: Vec<ReusedFile>,
+        mut fingerprints: HashMap<FileKey, FileFingerprint>,
+        run_id: RunId,
+    ) -> Self {
+        self.reused = reused.len();
+        for file in reused {
+            fingerprints.insert(file.display_path.clone(), file.entry.fingerprint());
+            results.push(FileOutcome::Success(file.entry.measurement(file.display_path)));
+        }
+        self.results = results;
+        self.fingerprints = fingerprints;
+        self.run_id = Some(run_id);
+        self
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::fs;
+    use tempfile::TempDir;
+
+    /// 建立包含三个音频文件的目录，返回扫描结果
+    fn scan_fixture(temp_dir: &TempDir) -> ScanResult {
+        for name in ["a.flac", "b.mp3", "c.wav"] {
+            fs::write(temp_dir.path().join(name), b"audio").unwrap();
+        }
+        fs::write(temp_dir.path().join("notes.txt"), b"text").unwrap();
+        let mut result = scan(temp_dir.path(), &ScanOptions::default());
+        result.report.files.sort_by(|a, b| a.1.cmp(&b.1));
+        result
+    }
+
+    /// 测试缓存划分、选择和取消选择
+    #[test]
+    fn test_into_plan() {
+        let temp_dir = TempDir::new().expect("无法创建临时目录");
+        let scanned = scan_fixture(&temp_dir);
+        assert_eq!(scanned.report.files.len(), 3);
+        assert_eq!(scanned.report.skipped_files, 1);
+
+        let mut cache = ResultsCache::default();
+        let fingerprint = FileFingerprint::of(&temp_dir.path().join("b.mp3")).unwrap();
+        cache.entries.insert("b.mp3".into(), CacheEntry::new(fingerprint, 8.5));
+        let run_id = RunId::parse("run-1").unwrap();
+        let mut plan = scanned.clone().into_plan(&PlanOptions { cache: Some(&cache), run_id: Some(&run_id), ..PlanOptions::default() });
+        assert_eq!(plan.run_id.as_ref(), Some(&run_id));
+        let planned: Vec<&str> = plan.files.iter().map(|file| file.display_path.as_str()).collect();
+        assert_eq!(planned, ["a.flac", "c.wav"]);
+        assert!(plan.files.iter().all(|file| file.fingerprint.is_some()));
+        assert_eq!(plan.reused.len(), 1);
+        assert_eq!(plan.len(), 3);
+
+        assert!(plan.deselect("b.mp3"));
+        assert!(plan.deselect("c.wav"));
+        assert!(!plan.deselect("missing.flac"));
+        assert_eq!(plan.paths().collect::<Vec<_>>(), [temp_dir.path().join("a.flac")]);
+
+        // 没有缓存时不读取指纹
+        let limited = scanned.into_plan(&PlanOptions { selection: Some(FileSelection::Limit(2)), ..PlanOptions::default() });
+        assert!(limited.run_id.is_some_and(|generated| generated != run_id));
+        assert_eq!(limited.files.len(), 2);
+        assert!(limited.files.iter().all(|file| file.fingerprint.is_none()));
+        assert_eq!(limited.selection.map(|report| (report.candidates, report.selected)), Some((3, 2)));
+    }
+
+    /// 测试限量只在需要分析的文件中选择：命中缓存的文件不占用名额，也总是保留
+    #[test]
+    fn test_selection_applies_after_cache_split() {
+        let temp_dir = TempDir::new().expect("无法创建临时目录");
+        let scanned = scan_fixture(&temp_dir);
+        let mut cache = ResultsCache::default();
+        let fingerprint = FileFingerprint::of(&temp_dir.path().join("a.flac")).unwrap();
+        cache.entries.insert("a.flac".into(), CacheEntry::new(fingerprint, 8.5));
+
+        let plan = scanned.into_plan(&PlanOptions {
+            selection: Some(FileSelection::Limit(1)),
+            cache: Some(&cache),
+            ..PlanOptions::default()
+        });
+        let planned: Vec<&str> = plan.files.iter().map(|file| file.display_path.as_str()).collect();
+        assert_eq!(planned, ["b.mp3"]);
+        assert_eq!(plan.reused.iter().map(|file| file.display_path.as_str()).collect::<Vec<_>>(), ["a.flac"]);
+        assert_eq!(plan.selection.map(|report| (report.candidates, report.selected)), Some((2, 1)));
+    }
+
+    /// 测试边扫描边执行按缓存划分文件：全部命中缓存时不需要 FFmpeg，结果与计划执行的形式相同
+    #[test]
+    fn test_scan_and_execute_reuses_cache() {
+        let temp_dir = TempDir::new().expect("无法创建临时目录");
+        let mut cache = ResultsCache::default();
+        for (name, lra) in [("a.flac", 8.5), ("b.mp3", 6.0)] {
+            fs::write(temp_dir.path().join(name), b"audio").unwrap();
+            let fingerprint = FileFingerprint::of(&temp_dir.path().join(name)).unwrap();
+            cache.entries.insert(name.into(), CacheEntry::new(fingerprint, lra));
+        }
+
+        let (options, run) = (CalculationOptions::default(), RunOptions::default());
+        let (report, outcome) =
+            scan_and_execute(temp_dir.path(), &ScanOptions::default(), Some(&cache), &options, &run, &|_: &RunEvent| {});
+        assert!(report.files.is_empty());
+        assert_eq!(outcome.reused, 2);
+        assert_eq!(outcome.results.len(), 2);
+        assert_eq!(outcome.fingerprints.len(), 2);
+        assert!(outcome.run_id.is_some());
+        let mut lras: Vec<f64> =
+            outcome.results.iter().filter_map(|result| result.measurement()).map(|m| m.lra).collect();
+        lras.sort_by(f64::total_cmp);
+        assert_eq!(lras, [6.0, 8.5]);
+    }
+
+    /// 测试只有复用结果时执行不需要 FFmpeg，指纹与缓存条目一致
+    #[test]
+    fn test_execute_reused_only() {
+        let entry = CacheEntry { integrated_lufs: Some(-14.0), ..CacheEntry::new(FileFingerprint { size: 5, mtime_ms: 7 }, 9.5) };
+        let plan = ProcessingPlan {
+            reused: vec![ReusedFile { path: "/m/a.flac".into(), display_path: "a.flac".into(), entry }],
+            ..ProcessingPlan::default()
+        };
+        let outcome = execute(plan, &CalculationOptions::default());
+        assert_eq!(outcome.reused, 1);
+        // 手工构造的计划没有运行 ID，执行时生成
+        assert!(outcome.run_id.is_some());
+        let measurement = outcome.results[0].measurement().unwrap();
+        assert_eq!((measurement.lra, measurement.integrated_lufs), (9.5, Some(-14.0)));
+        assert_eq!(outcome.fingerprints["a.flac"], FileFingerprint { size: 5, mtime_ms: 7 });
+    }
+
+    /// 测试计划可以序列化后还原
+    #[cfg(feature = "serde")]
+    #[test]
+    fn test_plan_round_trip() {
+        let plan = ProcessingPlan {
+            root: "/music".into(),
+            files: vec![PlannedFile::new("/music/a.flac", "a.flac")],
+            reused: vec![ReusedFile {
+                path: "/music/b.flac".into(),
+                display_path: "b.flac".into(),
+                entry: CacheEntry::new(FileFingerprint { size: 1, mtime_ms: 2 }, 6.0),
+            }],
+            selection: Some(SelectionReport { selection: FileSelection::Sample { count: 2, seed: 42 }, candidates: 9, selected: 2 }),
+            path_collisions: Vec::new(),
+            run_id: RunId::parse("20240601-153012-a3f9"),
+        };
+        let json = serde_json::to_string(&plan).unwrap();
+        assert_eq!(serde_json::from_str::<ProcessingPlan>(&json).unwrap(), plan);
+    }
+}
@@ -0,0 +1,184 @@
+//! LRA 数值精度和合理范围模块 (LRA Precision and Sanity Range Module)
+//!
+//! 结果文件默认保留一位小数；比较编码器设置等场景需要更多位数（`--precision 2`）。
+//! 精度不是全局设置：写出 LRA 值的函数和结构体（结果文件、流式部分结果、分组统计等）
+//! 都接收 `precision` 参数，用 [`LraValue::with_precision`] 格式化；[`LraValue`] 本身的
+//! `Display` 总是使用 [`DEFAULT_LRA_PRECISION`]。
+//!
+//! LRA 超出合理范围（默认 0–40 LU，`--lra-range`）的测量值通常意味着文件异常
+//! （大段静音、测试信号等）。这些值照常写入结果文件，只在统计中标记为可疑，
+//! 范围由调用方传入（见 [`crate::processor::ProcessingStats::flag_suspicious`]）。
+//! 解析结果文件时只拒绝不可能出现的值（负数、NaN、无穷大），因此用不同的精度或
+//! 合理范围写出的文件都可以照常排序和合并。
+
+use std::fmt;
+
+use crate::console::Glyph;
+
+/// 默认保留的小数位数
+pub const DEFAULT_LRA_PRECISION: u8 = 1;
+
+/// 允许的最大小数位数（FFmpeg 的 ebur128 汇总本身只输出一位小数，更多位数只在比较时有意义）
+pub const MAX_LRA_PRECISION: u8 = 6;
+
+/// 按默认精度显示的 LRA 值 (LRA Value)
+///
+/// # 示例
+/// ```
+/// use lra_calculator_rust::precision::LraValue;
+///
+/// assert_eq!(LraValue(12.345).to_string(), "12.3");
+/// assert_eq!(LraValue(12.345).with_precision(2).to_string(), "12.35");
+/// ```
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct LraValue(pub f64);
+
+impl LraValue {
+    /// 使用指定精度而不是 [`DEFAULT_LRA_PRECISION`] 显示；超过 [`MAX_LRA_PRECISION`] 时按最大值处理
+    pub fn with_precision(self, precision: u8) -> LraValueWithPrecision {
+        LraValueWithPrecision { value: self.0, precision: precision.min(MAX_LRA_PRECISION) }
+    }
+}
+
+impl fmt::Display for LraValue {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        self.with_precision(DEFAULT_LRA_PRECISION).fmt(f)
+    }
+}
+
+/// 按指定精度显示的 LRA 值，见 [`LraValue::with_precision`]
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct LraValueWithPrecision {
+    value: f64,
+    precision: u8,
+}
+
+impl fmt::Display for LraValueWithPrecision {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{:.*}", usize::from(self.precision), self.value)
+    }
+}
+
+/// 按指定精度舍入 LRA 值，用于 JSON 等以数值形式输出的场合
+///
+/// # 示例
+/// ```
+/// use lra_calculator_rust::precision::round_lra;
+///
+/// assert_eq!(round_lra(12.34, 1), 12.3);
+/// assert_eq!(round_lra(12.345, 2), 12.35);
+/// ```
+pub fn round_lra(value: f64, precision: u8) -> f64 {
+    // 经过文本格式化再解析，保证与同一精度写出的结果文件中的值完全相同
+    LraValue(value).with_precision(precision).to_string().parse().unwrap_or(value)
+}
+
+/// 数值文本的小数位数 (Decimal Places)
+///
+/// 用于识别已有结果文件的精度，排序和合并时不丢失位数。
+///
+/// # 示例
+/// ```
+/// use lra_calculator_rust::precision::decimal_places;
+///
+/// assert_eq!(decimal_places("7.25"), 2);
+/// assert_eq!(decimal_places("12"), 0);
+/// ```
+pub fn decimal_places(text: &str) -> u8 {
+    text.trim()
+        .split_once('.')
+        .map_or(0, |(_, fraction)| fraction.bytes().take_while(u8::is_ascii_digit).count())
+        .min(usize::from(MAX_LRA_PRECISION)) as u8
+}
+
+/// LRA 值是否可能出现：有限且不小于 0
+///
+/// 这是解析结果文件时唯一的检查；超出合理范围的值仍然是有效的结果。
+pub fn is_possible_lra(value: f64) -> bool {
+    value.is_finite() && value >= 0.0
+}
+
+/// LRA 合理范围 (LRA Sanity Range)
+#[derive(Debug, Clone, Copy, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct LraRange {
+    /// 下限（LU，含）
+    pub min: f64,
+    /// 上限（LU，含）
+    pub max: f64,
+}
+
+impl Default for LraRange {
+    fn default() -> Self {
+        Self { min: 0.0, max: 40.0 }
+    }
+}
+
+impl LraRange {
+    /// 值是否在范围内
+    pub fn contains(&self, value: f64) -> bool {
+        (self.min..=self.max).contains(&value)
+    }
+
+    /// 解析 `MIN-MAX` 形式的范围 (Parse Range)
+    ///
+    /// # 返回值
+    /// - `Some(LraRange)` - 两端都是非负数且下限不大于上限
+    /// - `None` - 格式错误
+    ///
+    /// # 示例
+    /// ```
+    /// use lra_calculator_rust::precision::LraRange;
+    ///
+    /// assert_eq!(LraRange::parse("1-25.5"), Some(LraRange { min: 1.0, max: 25.5 }));
+    /// assert_eq!(LraRange::parse("25-1"), None);
+    /// ```
+    pub fn parse(text: &str) -> Option<Self> {
+        let (min, max) = text.split_once('-')?;
+        let range = Self { min: min.trim().parse().ok()?, max: max.trim().parse().ok()? };
+        (is_possible_lra(range.min) && is_possible_lra(range.max) && range.min <= range.max).then_some(range)
+    }
+}
+
+impl fmt::Display for LraRange {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}{}{} LU", self.min, Glyph::RangeDash, self.max)
+    }
+}
+
+/// 序列化 LRA 值（serde 的 `serialize_with`）
+///
+/// JSON 不知道写出结果文件时的精度，按 [`MAX_LRA_PRECISION`] 舍入：去掉浮点误差，
+/// 但不丢失任何 `--precision` 写出的位数。
+#[cfg(feature = "serde")]
+pub(crate) fn serialize_rounded<S: serde::Serializer>(value: &f64, serializer: S) -> Result<S::Ok, S::Error> {
+    serializer.serialize_f64(round_lra(*value, MAX_LRA_PRECISION))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// 测试默认合理范围与常量一致
+    #[test]
+    fn test_default_sanity_range() {
+        assert_eq!(LraRange::default(), LraRange { min: 0.0, max: 40.0 });
+        assert!(LraRange::default().contains(40.0));
+        assert!(!LraRange::default().contains(250.0));
+        assert_eq!(LraRange::default().to_string(), "0–40 LU");
+        assert!(LraRange::parse("abc").is_none());
+        assert!(LraRange::parse("5").is_none());
+    }
+
+    /// 测试数值文本的小数位数
+    #[test]
+    fn test_decimal_places() {
+        assert_eq!(decimal_places("7.3"), 1);
+        assert_eq!(decimal_places(" 7.300 "), 3);
+        assert_eq!(decimal_places("7."), 0);
+        assert_eq!(decimal_places("1e2"), 0);
+        assert_eq!(decimal_places("0.123456789"), MAX_LRA_PRECISION);
+        assert_eq!(LraValue(7.0).with_precision(0).to_string(), "7");
+        assert_eq!(LraValue(7.0).with_precision(9).to_string(), "7.000000");
+    }
+}
@@ -0,0 +1,55 @@
+//! 预导入模块 (Prelude)
+//!
+//! 典型的库使用者只需要一行导入：扫描、并行分析、读取结果文件，以及这些函数
+//! 签名中出现的选项、结果和错误类型。更专门的功能（缓存、探测、分组统计等）
+//! 仍从各自的模块或 crate 根导入。
+//!
+//! # 示例
+//! ```rust,no_run
+//! use lra_calculator_rust::prelude::*;
+//! use std::path::Path;
+//!
+//! fn main() -> Result<(), AppError> {
+//!     check_environment()?;
+//!
+//!     let options = ScanOptions::default();
+//!     let report: ScanReport = scan_audio_files_with_options(Path::new("/music"), &options);
+//!     let results = process_files_parallel_with_options(
+//!         report.files,
+//!         ResultOrder::InputOrder,
+//!         &CalculationOptions::default(),
+//!         &RunOptions::default(),
+//!         &ConsoleSink,
+//!     );
+//!
+//!     let (stats, measurements): (ProcessingStats, Vec<FileMeasurement>) = analyze_results(results);
+//!     for measurement in &measurements {
+//!         let path: &FileKey = &measurement.path;
+//!         println!("{} - {:.1}", path, measurement.lra);
+//!     }
+//!     for error in &stats.errors {
+//!         let error: &ProcessFileError = error;
+//!         eprintln!("{}", error);
+//!     }
+//!
+//!     let contents: ResultsFileContents = read_and_parse_results_file(Path::new("/music/lra_results.txt"))?;
+//!     println!("结果文件中有 {} 个条目", contents.entries.len());
+//!     Ok(())
+//! }
+//! ```
+
+pub use crate::audio::{
+    calculate_lra_direct, calculate_lra_with_options, check_ffmpeg_availability, measure_loudness,
+    scan_audio_files, scan_audio_files_with_options, CalculationOptions, LoudnessSummary, ScanOptions, ScanReport,
+};
+pub use crate::error::{AppError, FileErrorType, LraCalculationError, ProcessFileError, ResultLineParseError};
+pub use crate::events::{EventSink, ProgressEvent, RunEvent};
+#[cfg(feature = "cli")]
+pub use crate::events::ConsoleSink;
+pub use crate::file_key::FileKey;
+pub use crate::processor::{
+    analyze_results, process_files_parallel, process_files_parallel_with_options, FileMeasurement, FileOutcome,
+    ProcessingStats, ResultOrder, RunOptions, SkipReason,
+};
+pub use crate::utils::{read_and_parse_results_file, sort_lra_results_file, ResultsFileContents};
+pub use crate::check_environment;
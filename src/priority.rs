@@ -0,0 +1,167 @@
+//! 子进程优先级模块 (Child Process Priority Module)
+//!
+//! 分析整个音乐库要连续运行几个小时的 FFmpeg，占满所有核心时前台程序会明显卡顿。
+//! `--nice` 把程序启动的每个外部进程（FFmpeg、ffprobe）设为低优先级：
+//!
+//! - Unix: 在子进程 exec 之前调用 `nice(10)`；Linux 上同时把 I/O 优先级降到
+//!   best-effort 类的最低级别
+//! - Windows: 以 `BELOW_NORMAL_PRIORITY_CLASS` 创建子进程
+//!
+//! 与格式注册表一样，优先级是进程级的设置（见 [`set_child_priority`]）：
+//! 所有外部命令都通过 [`tool_command`] 创建，可用性检查、自检和逐文件分析都遵守同一设置，
+//! 也都使用检测时确定的同一个可执行文件（见 [`crate::environment::resolve_program`]）。
+//! 默认保持正常优先级。
+
+use std::process::Command;
+use std::sync::atomic::{AtomicBool, Ordering};
+
+use crate::environment::resolved_program;
+
+/// 子进程是否使用低优先级
+static LOW_CHILD_PRIORITY: AtomicBool = AtomicBool::new(false);
+
+/// Unix 上低优先级子进程在当前 nice 值基础上增加的数值
+#[cfg(unix)]
+const NICE_INCREMENT: libc::c_int = 10;
+
+/// 子进程优先级 (Process Priority)
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum ProcessPriority {
+    /// 与本程序相同的优先级（默认）
+    #[default]
+    Normal,
+    /// 降低 CPU 优先级，支持时同时降低 I/O 优先级（`--nice`）
+    Low,
+}
+
+impl ProcessPriority {
+    /// 让命令以该优先级启动 (Apply to Command)
+    ///
+    /// 降低优先级失败（例如受系统策略限制）时子进程照常以原优先级运行。
+    ///
+    /// # 参数
+    /// - `command` - 尚未启动的命令
+    pub fn apply(self, command: &mut Command) {
+        if self == ProcessPriority::Low {
+            lower_priority(command);
+        }
+    }
+}
+
+/// Unix: 在 exec 之前降低 CPU 和 I/O 优先级
+#[cfg(unix)]
+fn lower_priority(command: &mut Command) {
+    use std::os::unix::process::CommandExt;
+
+    // SAFETY: 闭包在 fork 之后、exec 之前执行，只调用 nice 和 ioprio_set 两个系统调用，
+    // 不分配内存、不获取锁，满足 pre_exec 对异步信号安全的要求
+    unsafe {
+        command.pre_exec(|| {
+            // 返回值无法区分 -1 的 nice 值和失败，失败时保持原优先级即可
+            libc::nice(NICE_INCREMENT);
+            lower_io_priority();
+            Ok(())
+        });
+    }
+}
+
+/// Linux: 把 I/O 优先级降到 best-effort 类的最低级别（失败时忽略）
+#[cfg(target_os = "linux")]
+fn lower_io_priority() {
+    const IOPRIO_WHO_PROCESS: libc::c_long = 1;
+    const IOPRIO_CLASS_BE: libc::c_long = 2;
+    const IOPRIO_CLASS_SHIFT: u32 = 13;
+    const LOWEST_BE_LEVEL: libc::c_long = 7;
+
+    // SAFETY: ioprio_set 只读取三个整数参数
+    unsafe {
+        libc::syscall(
+            libc::SYS_ioprio_set,
+            IOPRIO_WHO_PROCESS,
+            0 as libc::c_long,
+            (IOPRIO_CLASS_BE << IOPRIO_CLASS_SHIFT) | LOWEST_BE_LEVEL,
+        );
+    }
+}
+
+/// 其他 Unix 系统没有可移植的 I/O 优先级接口
+#[cfg(all(unix, not(target_os = "linux")))]
+fn lower_io_priority() {}
+
+/// Windows: 以低于正常的优先级类创建子进程
+#[cfg(windows)]
+fn lower_priority(command: &mut Command) {
+    use std::os::windows::process::CommandExt;
+
+    const BELOW_NORMAL_PRIORITY_CLASS: u32 = 0x0000_4000;
+
+    command.creation_flags(BELOW_NORMAL_PRIORITY_CLASS);
+}
+
+/// 其他平台不支持调整子进程优先级
+#[cfg(not(any(unix, windows)))]
+fn lower_priority(_command: &mut Command) {}
+
+/// 设置之后启动的外部进程的优先级 (Set Child Priority)
+///
+/// # 参数
+/// - `priority` - 子进程优先级
+pub fn set_child_priority(priority: ProcessPriority) {
+    LOW_CHILD_PRIORITY.store(priority == ProcessPriority::Low, Ordering::Relaxed);
+}
+
+/// 当前的子进程优先级
+pub fn child_priority() -> ProcessPriority {
+    if LOW_CHILD_PRIORITY.load(Ordering::Relaxed) {
+        ProcessPriority::Low
+    } else {
+        ProcessPriority::Normal
+    }
+}
+
+/// 创建外部工具命令 (Tool Command)
+///
+/// 与 `Command::new` 相同，额外应用 [`child_priority`]；已经用
+/// [`resolve_program`](crate::environment::resolve_program) 确定了位置的程序以绝对路径启动。
+///
+/// # 参数
+/// - `program` - 程序名，如 `ffmpeg`、`ffprobe`
+///
+/// # 返回值
+/// - 尚未启动的命令
+pub fn tool_command(program: &str) -> Command {
+    let mut command = match resolved_program(program) {
+        Some(path) => Command::new(path),
+        None => Command::new(program),
+    };
+    child_priority().apply(&mut command);
+    command
+}
+
+#[cfg(all(test, target_os = "linux"))]
+mod tests {
+    use super::*;
+
+    /// 从 `/proc/<pid>/stat` 的内容中读取 nice 值（第 19 个字段）
+    fn niceness(stat: &str) -> i32 {
+        // 第 2 个字段是括号中的进程名，可能含空格，从最后一个右括号之后开始数
+        let fields: Vec<&str> = stat[stat.rfind(')').unwrap() + 1..].split_whitespace().collect();
+        fields[16].parse().unwrap()
+    }
+
+    /// 测试低优先级的子进程 nice 值比本进程高
+    #[test]
+    fn test_low_priority_child_niceness() {
+        let own = niceness(&std::fs::read_to_string("/proc/self/stat").unwrap());
+        let child_niceness = |priority: ProcessPriority| {
+            let mut command = Command::new("cat");
+            command.arg("/proc/self/stat");
+            priority.apply(&mut command);
+            let output = command.output().expect("无法执行 cat");
+            niceness(&String::from_utf8_lossy(&output.stdout))
+        };
+
+        assert_eq!(child_niceness(ProcessPriority::Normal), own);
+        assert_eq!(child_niceness(ProcessPriority::Low), (own + NICE_INCREMENT).min(19));
+    }
+}
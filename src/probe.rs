@@ -0,0 +1,400 @@
+//! 内容探测模块 (Content Probe Module)
+//!
+//! 老的录音归档中常有大量没有扩展名的文件，按扩展名扫描会全部跳过。
+//! 启用 `--probe-unknown` 后，这些文件会按内容识别：
+//!
+//! 1. **魔数识别**: 读取文件头，常见音频容器（WAV、FLAC、MP3、AIFF 等）直接判定为音频，
+//!    常见非音频格式（图片、PDF、压缩包、纯文本）直接排除
+//! 2. **ffprobe 回退**: 可能包含视频或无法识别的容器（Ogg、MP4、Matroska 等）
+//!    交给 ffprobe 检查是否有音频流
+//!
+//! 探测阶段在独立的线程池中运行，并发数不超过 [`MAX_PROBE_CONCURRENCY`]，
+//! 避免在超大目录树上同时启动过多 ffprobe 进程。
+//!
+//! ffprobe 调用同时读取艺术家、专辑和标题标签（见 [`crate::tags`]），
+//! `--with-tags` 不需要为这些文件再启动一次 ffprobe。
+
+use std::collections::BTreeMap;
+use std::fs::File;
+use std::io::{self, Read};
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::time::Duration;
+
+use rayon::prelude::*;
+
+use crate::cache::{FileFingerprint, ProbeCacheEntry};
+use crate::priority::tool_command;
+use crate::tags::{TrackTags, TAG_KEYS};
+
+/// 探测阶段的最大并发数
+pub const MAX_PROBE_CONCURRENCY: usize = 4;
+
+/// 识别魔数时读取的文件头字节数
+const HEADER_LEN: usize = 64;
+
+/// 魔数识别结果 (Magic Verdict)
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum MagicVerdict {
+    /// 确定是音频文件
+    Audio,
+    /// 确定不是音频文件
+    NotAudio,
+    /// 无法仅凭文件头判断，需要 ffprobe 确认
+    Ambiguous,
+}
+
+/// 根据文件头识别文件类型 (Sniff Magic Bytes)
+///
+/// # 参数
+/// - `header` - 文件开头的若干字节（建议至少 16 字节）
+///
+/// # 返回值
+/// - 识别结果
+///
+/// # 示例
+/// ```
+/// use lra_calculator_rust::probe::{sniff_magic, MagicVerdict};
+///
+/// assert_eq!(sniff_magic(b"fLaC\0\0\0\x22"), MagicVerdict::Audio);
+/// assert_eq!(sniff_magic(b"%PDF-1.7"), MagicVerdict::NotAudio);
+/// assert_eq!(sniff_magic(b"OggS\0\x02"), MagicVerdict::Ambiguous);
+/// ```
+pub fn sniff_magic(header: &[u8]) -> MagicVerdict {
+    const AUDIO_PREFIXES: [&[u8]; 7] = [b"fLaC", b"ID3", b"MAC ", b"wvpk", b"TTA1", b"DSD ", b"#!AMR"];
+    const NOT_AUDIO_PREFIXES: [&[u8]; 7] = [
+        b"\x89PNG", b"\xFF\xD8\xFF", b"GIF8", b"%PDF", b"PK\x03\x04", b"\x1F\x8B", b"\x7FELF",
+    ];
+    // 可能只含视频或其他数据的容器
+    const CONTAINER_PREFIXES: [&[u8]; 4] = [b"OggS", b"\x1A\x45\xDF\xA3", b"\x30\x26\xB2\x75", b".RMF"];
+
+    if header.len() >= 12 && &header[0..4] == b"RIFF" {
+        return match &header[8..12] {
+            b"WAVE" => MagicVerdict::Audio,
+            b"AVI " => MagicVerdict::Ambiguous,
+            _ => MagicVerdict::NotAudio,
+        };
+    }
+    if header.len() >= 12 && &header[0..4] == b"FORM" && matches!(&header[8..12], b"AIFF" | b"AIFC") {
+        return MagicVerdict::Audio;
+    }
+    if header.len() >= 12 && &header[4..8] == b"ftyp" {
+        // M4A 品牌是纯音频，其他 MP4 品牌可能是视频
+        return match &header[8..12] {
+            b"M4A " | b"M4B " => MagicVerdict::Audio,
+            _ => MagicVerdict::Ambiguous,
+        };
+    }
+    if AUDIO_PREFIXES.iter().any(|prefix| header.starts_with(prefix)) {
+        return MagicVerdict::Audio;
+    }
+    // MPEG 音频帧同步字（11 位全 1），用于没有 ID3 标签的 MP3 和 ADTS AAC
+    if header.len() >= 2 && header[0] == 0xFF && header[1] & 0xE0 == 0xE0 {
+        return MagicVerdict::Audio;
+    }
+    if NOT_AUDIO_PREFIXES.iter().any(|prefix| header.starts_with(prefix)) {
+        return MagicVerdict::NotAudio;
+    }
+    if CONTAINER_PREFIXES.iter().any(|prefix| header.starts_with(prefix)) {
+        return MagicVerdict::Ambiguous;
+    }
+    if looks_like_text(header) {
+        return MagicVerdict::NotAudio;
+    }
+    MagicVerdict::Ambiguous
+}
+
+/// 文件头是否像纯文本（空文件也视为文本）
+fn looks_like_text(header: &[u8]) -> bool {
+    header
+        .iter()
+        .all(|&byte| byte.is_ascii_graphic() || byte.is_ascii_whitespace() || byte >= 0x80)
+}
+
+/// 读取文件头
+fn read_header(path: &Path) -> io::Result<Vec<u8>> {
+    let mut header = Vec::with_capacity(HEADER_LEN);
+    File::open(path)?.take(HEADER_LEN as u64).read_to_end(&mut header)?;
+    Ok(header)
+}
+
+/// ffprobe 的检查结果 (FFprobe Report)
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct FfprobeReport {
+    /// 是否包含至少一个音频流
+    pub has_audio: bool,
+    /// 容器和第一条音频流上的艺术家、专辑、标题标签
+    pub tags: TrackTags,
+}
+
+/// 使用 ffprobe 读取文件的总时长 (Probe Duration)
+///
+/// 用于计算长文件的分析进度，见 [`crate::progress`]。
+///
+/// # 返回值
+/// - 容器记录的总时长；ffprobe 无法执行、文件没有时长信息或时长为 0 时返回 `None`
+pub fn probe_duration(path: &Path) -> Option<Duration> {
+    let output = tool_command("ffprobe")
+        .args(["-v", "error", "-show_entries", "format=duration", "-of", "default=noprint_wrappers=1:nokey=1"])
+        .arg(path)
+        .output()
+        .ok()
+        .filter(|output| output.status.success())?;
+    let seconds: f64 = String::from_utf8_lossy(&output.stdout).trim().parse().ok()?;
+    (seconds.is_finite() && seconds > 0.0).then(|| Duration::from_secs_f64(seconds))
+}
+
+/// 使用 ffprobe 读取第一条音频流的声道数 (Probe Channels)
+///
+/// 用于 `--also-downmixed` 判断文件是否为多声道。
+///
+/// # 返回值
+/// - 声道数；ffprobe 无法执行、文件没有音频流或输出无法解析时返回 `None`
+pub fn probe_channels(path: &Path) -> Option<u32> {
+    let output = tool_command("ffprobe")
+        .args(["-v", "error", "-select_streams", "a:0", "-show_entries", "stream=channels"])
+        .args(["-of", "default=noprint_wrappers=1:nokey=1"])
+        .arg(path)
+        .output()
+        .ok()
+        .filter(|output| output.status.success())?;
+    String::from_utf8_lossy(&output.stdout).lines().next()?.trim().parse().ok().filter(|channels| *channels > 0)
+}
+
+/// 使用 ffprobe 检查文件的音频流和标签 (Run FFprobe)
+///
+/// 一次调用同时取得流类型和标签，探测和 `--with-tags` 共用。
+///
+/// # 返回值
+/// - `Ok(FfprobeReport)` - ffprobe 执行完成（无法识别的文件没有音频流、没有标签）
+/// - `Err(String)` - ffprobe 无法执行
+pub fn run_ffprobe(path: &Path) -> Result<FfprobeReport, String> {
+    let keys = TAG_KEYS.join(",");
+    let entries = format!("stream=codec_type:stream_tags={keys}:format_tags={keys}");
+    let output = tool_command("ffprobe")
+        .args(["-v", "error", "-show_entries", &entries, "-of", "default"])
+        .arg(path)
+        .output()
+        .map_err(|e| format!("无法执行 ffprobe: {}", e))?;
+
+    if !output.status.success() {
+        return Ok(FfprobeReport::default());
+    }
+    Ok(parse_ffprobe_output(&String::from_utf8_lossy(&output.stdout)))
+}
+
+/// 解析 ffprobe 默认格式的输出
+///
+/// 输出由 `[STREAM]` 和 `[FORMAT]` 小节组成，标签行形如 `TAG:artist=…`（键名大小写取决于容器）。
+/// 容器标签优先，缺少的标签取第一条音频流上的值（Ogg/Opus 的标签保存在流上）。
+fn parse_ffprobe_output(output: &str) -> FfprobeReport {
+    let mut has_audio = false;
+    let mut format_tags = TrackTags::default();
+    let mut stream_tags = None;
+
+    let mut section_is_audio = false;
+    let mut section_tags = TrackTags::default();
+    for line in output.lines().map(str::trim) {
+        match line {
+            "[STREAM]" | "[FORMAT]" => {
+                section_is_audio = false;
+                section_tags = TrackTags::default();
+            }
+            "[/STREAM]" => {
+                if section_is_audio {
+                    has_audio = true;
+                    stream_tags.get_or_insert(std::mem::take(&mut section_tags));
+                }
+            }
+            "[/FORMAT]" => format_tags = std::mem::take(&mut section_tags),
+            "codec_type=audio" => section_is_audio = true,
+            _ => {
+                if let Some((key, value)) = line.strip_prefix("TAG:").and_then(|tag| tag.split_once('=')) {
+                    section_tags.set(key, value);
+                }
+            }
+        }
+    }
+
+    FfprobeReport {
+        has_audio,
+        tags: format_tags.or(stream_tags.unwrap_or_default()),
+    }
+}
+
+/// 判断文件是否为音频 (Probe Is Audio)
+///
+/// 先识别魔数，无法判断时回退到 ffprobe。读取失败或 ffprobe 不可用时视为非音频。
+///
+/// # 参数
+/// - `path` - 文件路径
+pub fn probe_is_audio(path: &Path) -> bool {
+    probe_file(path).0
+}
+
+/// 判断文件是否为音频，并返回 ffprobe 顺便读到的标签
+///
+/// # 返回值
+/// - `(是否为音频, 标签)` - 只有回退到 ffprobe 的文件才有标签
+fn probe_file(path: &Path) -> (bool, Option<TrackTags>) {
+    match read_header(path).map(|header| sniff_magic(&header)) {
+        Ok(MagicVerdict::Audio) => (true, None),
+        Ok(MagicVerdict::NotAudio) | Err(_) => (false, None),
+        Ok(MagicVerdict::Ambiguous) => match run_ffprobe(path) {
+            Ok(report) => (report.has_audio, Some(report.tags)),
+            Err(_) => (false, None),
+        },
+    }
+}
+
+/// 探测结果 (Probe Outcome)
+#[derive(Debug, Default)]
+pub struct ProbeOutcome {
+    /// 识别为音频的文件：(完整路径, 显示路径)
+    pub audio_files: Vec<(PathBuf, String)>,
+    /// 本次所有候选文件的探测记录，用于写回缓存
+    pub cache: BTreeMap<String, ProbeCacheEntry>,
+    /// 实际探测（未命中缓存）的文件数量
+    pub probed: usize,
+    /// 探测时经 ffprobe 确认为音频的文件的标签，`--with-tags` 可以直接复用
+    pub tags: BTreeMap<String, TrackTags>,
+}
+
+/// 并行探测没有扩展名的文件 (Probe Unknown Files)
+///
+/// 大小和修改时间与缓存记录一致的文件直接使用缓存的判断结果。
+///
+/// # 参数
+/// - `candidates` - 候选文件：(完整路径, 显示路径)
+/// - `cached` - 上次运行保存的探测记录
+/// - `on_progress` - 每探测完一个文件调用一次，参数为 (已完成数, 需要探测的总数)
+///
+/// # 返回值
+/// - 识别为音频的文件和新的探测记录
+pub fn probe_unknown_files<F>(
+    candidates: Vec<(PathBuf, String)>,
+    cached: &BTreeMap<String, ProbeCacheEntry>,
+    on_progress: F,
+) -> ProbeOutcome
+where
+    F: Fn(usize, usize) + Sync,
+{
+    let mut outcome = ProbeOutcome::default();
+    let mut to_probe = Vec::new();
+
+    for (full_path, display_path) in candidates {
+        let Ok(fingerprint) = FileFingerprint::of(&full_path) else {
+            continue;
+        };
+        match cached.get(&display_path).filter(|entry| entry.matches(&fingerprint)) {
+            Some(entry) => {
+                if entry.is_audio {
+                    outcome.audio_files.push((full_path, display_path.clone()));
+                }
+                outcome.cache.insert(display_path, *entry);
+            }
+            None => to_probe.push((full_path, display_path, fingerprint)),
+        }
+    }
+
+    let total = to_probe.len();
+    let done = AtomicUsize::new(0);
+    let probe_all = || {
+        to_probe
+            .into_par_iter()
+            .map(|(full_path, display_path, fingerprint)| {
+                let (is_audio, tags) = probe_file(&full_path);
+                on_progress(done.fetch_add(1, Ordering::SeqCst) + 1, total);
+                (full_path, display_path, ProbeCacheEntry::new(fingerprint, is_audio), tags)
+            })
+            .collect::<Vec<_>>()
+    };
+
+    // 使用独立的小线程池限制并发；创建失败时退回全局线程池
+    let probed = match rayon::ThreadPoolBuilder::new()
+        .num_threads(MAX_PROBE_CONCURRENCY)
+        .build()
+    {
+        Ok(pool) => pool.install(probe_all),
+        Err(_) => probe_all(),
+    };
+
+    outcome.probed = probed.len();
+    for (full_path, display_path, entry, tags) in probed {
+        if entry.is_audio {
+            outcome.audio_files.push((full_path, display_path.clone()));
+            if let Some(tags) = tags {
+                outcome.tags.insert(display_path.clone(), tags);
+            }
+        }
+        outcome.cache.insert(display_path, entry);
+    }
+
+    outcome
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::fs;
+    use tempfile::TempDir;
+
+    /// 测试常见文件头的识别
+    #[test]
+    fn test_sniff_magic() {
+        assert_eq!(sniff_magic(b"RIFF\x24\0\0\0WAVEfmt "), MagicVerdict::Audio);
+        assert_eq!(sniff_magic(b"RIFF\x24\0\0\0AVI LIST"), MagicVerdict::Ambiguous);
+        assert_eq!(sniff_magic(b"FORM\0\0\0\0AIFFCOMM"), MagicVerdict::Audio);
+        assert_eq!(sniff_magic(b"ID3\x04\0\0"), MagicVerdict::Audio);
+        assert_eq!(sniff_magic(&[0xFF, 0xFB, 0x90, 0x64]), MagicVerdict::Audio);
+        assert_eq!(sniff_magic(b"\0\0\0\x20ftypM4A \0\0"), MagicVerdict::Audio);
+        assert_eq!(sniff_magic(b"\0\0\0\x20ftypisom\0\0"), MagicVerdict::Ambiguous);
+        assert_eq!(sniff_magic(b"\x89PNG\r\n\x1a\n"), MagicVerdict::NotAudio);
+        assert_eq!(sniff_magic(b"Track notes: take 3\n"), MagicVerdict::NotAudio);
+        assert_eq!(sniff_magic(&[0x00, 0x01, 0x02, 0x03]), MagicVerdict::Ambiguous);
+    }
+
+    /// 测试解析 ffprobe 输出：容器标签优先，缺少的取第一条音频流上的标签
+    #[test]
+    fn test_parse_ffprobe_output() {
+        let output = "[STREAM]\ncodec_type=video\nTAG:title=Cover\n[/STREAM]\n\
+                      [STREAM]\ncodec_type=audio\nTAG:TITLE=Time\nTAG:ARTIST=Stream Artist\n[/STREAM]\n\
+                      [STREAM]\ncodec_type=audio\nTAG:album=Second\n[/STREAM]\n\
+                      [FORMAT]\nTAG:artist=Pink Floyd\n[/FORMAT]\n";
+        let report = parse_ffprobe_output(output);
+        assert!(report.has_audio);
+        assert_eq!(report.tags.artist.as_deref(), Some("Pink Floyd"));
+        assert_eq!(report.tags.title.as_deref(), Some("Time"));
+        assert_eq!(report.tags.album, None);
+
+        let video_only = parse_ffprobe_output("[STREAM]\ncodec_type=video\n[/STREAM]\n[FORMAT]\n[/FORMAT]\n");
+        assert_eq!(video_only, FfprobeReport::default());
+    }
+
+    /// 测试探测结果使用缓存，未变化的文件不会重复探测
+    #[test]
+    fn test_probe_uses_cache() {
+        let temp_dir = TempDir::new().expect("无法创建临时目录");
+        let wav = temp_dir.path().join("take1");
+        let notes = temp_dir.path().join("README");
+        fs::write(&wav, b"RIFF\x24\0\0\0WAVEfmt ").unwrap();
+        fs::write(&notes, b"plain text notes").unwrap();
+
+        let candidates = vec![(wav.clone(), "take1".to_string()), (notes.clone(), "README".to_string())];
+        let progress = AtomicUsize::new(0);
+        let outcome = probe_unknown_files(candidates.clone(), &BTreeMap::new(), |_, total| {
+            assert_eq!(total, 2);
+            progress.fetch_add(1, Ordering::SeqCst);
+        });
+
+        assert_eq!(outcome.probed, 2);
+        assert_eq!(progress.load(Ordering::SeqCst), 2);
+        assert_eq!(outcome.audio_files, vec![(wav.clone(), "take1".to_string())]);
+        assert_eq!(outcome.cache.len(), 2);
+
+        let second = probe_unknown_files(candidates, &outcome.cache, |_, _| panic!("不应重复探测"));
+        assert_eq!(second.probed, 0);
+        assert_eq!(second.audio_files, vec![(wav, "take1".to_string())]);
+        assert_eq!(second.cache, outcome.cache);
+    }
+}
@@ -19,14 +19,363 @@
 //! ### 进度跟踪 (Progress Tracking)
 //! 使用原子计数器实现线程安全的进度跟踪，为用户提供实时反馈。
 
+use std::collections::{BTreeMap, HashSet};
+use std::fmt;
 use std::path::{Path, PathBuf};
-use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::atomic::{AtomicBool, AtomicUsize, Ordering};
+use std::sync::{mpsc, Mutex};
 use std::thread;
+use std::time::{Duration, Instant};
 
 use rayon::prelude::*;
 
-use crate::audio::calculate_lra_direct;
-use crate::error::ProcessFileError;
+use crate::abort::EarlyAbort;
+use crate::audio::{measure_downmixed_lra, measure_loudness_with_progress, CalculationOptions, Downmix, LoudnessSummary};
+use crate::channels::{measure_channels, ChannelImbalance, ChannelLoudness};
+use crate::peak::ClippingRisk;
+use crate::breaker::{is_outage_error, CircuitBreaker};
+use crate::cancel::CancellationToken;
+use crate::cloud::{is_cloud_placeholder, is_icloud_stub};
+use crate::concurrency::ConcurrencyLimiter;
+use crate::error::{FileErrorType, LraCalculationError, ProcessFileError};
+use crate::events::{DefaultSink, EventSink, FilePosition, ProgressEvent, RunEvent};
+use crate::failures::{FailureStream, FailureSummary};
+use crate::file_key::{DisplayPathCollision, FileKey};
+use crate::precision::LraRange;
+use crate::progress::AnalysisProgress;
+use crate::hash::{hash_file, ContentHash, HashAlgorithm};
+use crate::replaygain::ReplayGain;
+use crate::run_id::RunId;
+use crate::scan_stats::ScanStatistics;
+use crate::selection::shuffle;
+#[cfg(feature = "cli")]
+use crate::console::{human_color_mode, ColorMode, Style};
+#[cfg(feature = "cli")]
+use crate::precision::LraValue;
+#[cfg(feature = "cli")]
+use crate::table::{render_table, Align};
+
+/// 单个文件的测量结果 (File Measurement)
+///
+/// 处理成功时的结果。除 LRA 外还保存了 ebur128 汇总中的综合响度，
+/// 用于计算 ReplayGain 等附加信息。序列化为 JSON 时时长以秒为单位
+/// （`audio_duration_secs`、`analysis_time_secs`），没有的值为 `null`。
+#[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
+pub struct FileMeasurement {
+    /// 显示路径（相对于扫描根目录）
+    pub path: FileKey,
+    /// LRA 值（LU）
+    pub lra: f64,
+    /// 综合响度（LUFS）；FFmpeg 输出或缓存中没有记录时为 `None`
+    pub integrated_lufs: Option<f64>,
+    /// 真峰值（dBTP，`--true-peak`）；未启用、FFmpeg 输出或缓存中没有记录时为 `None`
+    pub true_peak_dbtp: Option<f64>,
+    /// LRA 下限（LUFS）；旧版 FFmpeg 构建不输出、或缓存中没有记录时为 `None`
+    pub lra_low_lufs: Option<f64>,
+    /// LRA 上限（LUFS）；同上
+    pub lra_high_lufs: Option<f64>,
+    /// 综合响度的相对门限（LUFS）；同上
+    pub threshold_lufs: Option<f64>,
+    /// 文件内容的摘要（`--hash`）；未启用或计算失败时为 `None`
+    pub content_hash: Option<ContentHash>,
+    /// 音频时长；FFmpeg 没有输出时长或结果来自缓存时为 `None`
+    #[cfg_attr(feature = "serde", serde(rename = "audio_duration_secs", serialize_with = "serialize_secs"))]
+    pub audio_duration: Option<Duration>,
+    /// 本次分析这个文件所用的时间；结果来自缓存时为 `None`
+    #[cfg_attr(feature = "serde", serde(rename = "analysis_time_secs", serialize_with = "serialize_secs"))]
+    pub analysis_time: Option<Duration>,
+    /// 分析这个文件的 Rayon 工作线程序号；结果来自缓存或不在线程池中分析时为 `None`
+    pub worker: Option<usize>,
+    /// 立体声缩混后的 LRA（`--also-downmixed`）；不是多声道文件、声道数未知或未启用时为 `None`
+    pub downmixed_lra: Option<f64>,
+    /// 各声道的测量值（`--per-channel`）；单声道、声道数未知或未启用时为 `None`
+    pub channels: Option<Vec<ChannelLoudness>>,
+    /// 分析成功但 FFmpeg 输出了警告（见 [`crate::warnings`]）；结果来自缓存时为空
+    pub warnings: Vec<String>,
+    /// 主测量成功、但附加测量（缩混、逐声道）或内容哈希失败的说明；失败的值为 `None`
+    pub analysis_warnings: Vec<String>,
+}
+
+impl FileMeasurement {
+    /// 创建只有 LRA 值的测量结果
+    pub fn new(path: impl Into<FileKey>, lra: f64) -> Self {
+        Self {
+            path: path.into(),
+            lra,
+            integrated_lufs: None,
+            true_peak_dbtp: None,
+            lra_low_lufs: None,
+            lra_high_lufs: None,
+            threshold_lufs: None,
+            content_hash: None,
+            audio_duration: None,
+            analysis_time: None,
+            worker: None,
+            downmixed_lra: None,
+            channels: None,
+            warnings: Vec::new(),
+            analysis_warnings: Vec::new(),
+        }
+    }
+
+    /// 设置综合响度
+    pub fn with_integrated_lufs(mut self, integrated_lufs: Option<f64>) -> Self {
+        self.integrated_lufs = integrated_lufs;
+        self
+    }
+
+    /// 设置文件内容的摘要
+    pub fn with_content_hash(mut self, content_hash: Option<ContentHash>) -> Self {
+        self.content_hash = content_hash;
+        self
+    }
+
+    /// 设置音频时长和本次分析所用的时间
+    pub fn with_timing(mut self, audio_duration: Option<Duration>, analysis_time: Duration) -> Self {
+        self.audio_duration = audio_duration;
+        self.analysis_time = Some(analysis_time);
+        self
+    }
+
+    /// 设置真峰值
+    pub fn with_true_peak(mut self, true_peak_dbtp: Option<f64>) -> Self {
+        self.true_peak_dbtp = true_peak_dbtp;
+        self
+    }
+
+    /// 设置 LRA 上下限和综合响度门限（LUFS）
+    pub fn with_range_bounds(mut self, lra_low_lufs: Option<f64>, lra_high_lufs: Option<f64>, threshold_lufs: Option<f64>) -> Self {
+        self.lra_low_lufs = lra_low_lufs;
+        self.lra_high_lufs = lra_high_lufs;
+        self.threshold_lufs = threshold_lufs;
+        self
+    }
+
+    /// 设置立体声缩混后的 LRA
+    pub fn with_downmixed_lra(mut self, downmixed_lra: Option<f64>) -> Self {
+        self.downmixed_lra = downmixed_lra;
+        self
+    }
+
+    /// 设置各声道的测量值
+    pub fn with_channels(mut self, channels: Option<Vec<ChannelLoudness>>) -> Self {
+        self.channels = channels;
+        self
+    }
+
+    /// 设置 FFmpeg 警告
+    pub fn with_warnings(mut self, warnings: Vec<String>) -> Self {
+        self.warnings = warnings;
+        self
+    }
+
+    /// 设置附加测量和内容哈希失败的说明
+    pub fn with_analysis_warnings(mut self, analysis_warnings: Vec<String>) -> Self {
+        self.analysis_warnings = analysis_warnings;
+        self
+    }
+
+    /// 设置分析这个文件的工作线程序号（见 [`rayon::current_thread_index`]）
+    pub fn with_worker(mut self, worker: Option<usize>) -> Self {
+        self.worker = worker;
+        self
+    }
+
+    /// ReplayGain 2.0 音轨增益（见 [`ReplayGain::from_integrated_loudness`]）
+    ///
+    /// # 返回值
+    /// - `Some(ReplayGain)` - 增益，可能被限幅或标记为静音
+    /// - `None` - 没有综合响度
+    pub fn replaygain_track_gain(&self) -> Option<ReplayGain> {
+        self.integrated_lufs.map(ReplayGain::from_integrated_loudness)
+    }
+
+    /// ReplayGain 2.0 音轨增益（dB，保留两位小数）
+    ///
+    /// # 返回值
+    /// - `Some(f64)` - `-18 LUFS − 综合响度`，超出 ±24 dB 时已限幅
+    /// - `None` - 没有综合响度，或音频为静音
+    pub fn replaygain_track_gain_db(&self) -> Option<f64> {
+        self.replaygain_track_gain().and_then(|gain| gain.gain_db())
+    }
+
+    /// 结果文件中的条目：(显示路径, LRA 值)
+    pub fn entry(&self) -> (FileKey, f64) {
+        (self.path.clone(), self.lra)
+    }
+
+    /// 序列化为单行 JSON（不含换行符），供 `file --json` 使用
+    ///
+    /// # 返回值
+    /// - `Ok(String)` - 单行 JSON 字符串
+    /// - `Err(serde_json::Error)` - 序列化失败
+    #[cfg(feature = "serde")]
+    pub fn to_json_line(&self) -> Result<String, serde_json::Error> {
+        serde_json::to_string(self)
+    }
+}
+
+/// 把时长序列化为秒数
+#[cfg(feature = "serde")]
+fn serialize_secs<S: serde::Serializer>(duration: &Option<Duration>, serializer: S) -> Result<S::Ok, S::Error> {
+    match duration {
+        Some(duration) => serializer.serialize_some(&duration.as_secs_f64()),
+        None => serializer.serialize_none(),
+    }
+}
+
+/// 跳过文件的原因 (Skip Reason)
+///
+/// 跳过既不是成功也不是失败：文件没有被分析，但这是预期中的情况，不计入失败统计。
+/// 序列化时使用稳定的 snake_case 字符串（如 `"cloud_placeholder"`）。
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, PartialOrd, Ord)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[cfg_attr(feature = "serde", serde(rename_all = "snake_case"))]
+pub enum SkipReason {
+    /// 运行被取消（如 `--max-duration` 到期），文件没有开始分析，见 [`crate::cancel`]
+    Cancelled,
+    /// 网络共享长时间无法访问，断路器放弃了剩下的文件，见 [`crate::breaker`]
+    Outage,
+    /// 最先完成的文件全部以同一种错误失败，判定为环境问题并中止，见 [`crate::abort`]
+    Aborted,
+    /// 云端占位文件，内容不在本地（OneDrive 仅在线文件等），见 [`crate::cloud`]
+    CloudPlaceholder,
+    /// iCloud 存根文件，原文件尚未下载
+    #[cfg_attr(feature = "serde", serde(rename = "icloud_stub"))]
+    ICloudStub,
+}
+
+impl SkipReason {
+    /// 稳定的 snake_case 名称，与序列化形式相同
+    pub fn as_str(self) -> &'static str {
+        match self {
+            SkipReason::Cancelled => "cancelled",
+            SkipReason::Outage => "outage",
+            SkipReason::Aborted => "aborted",
+            SkipReason::CloudPlaceholder => "cloud_placeholder",
+            SkipReason::ICloudStub => "icloud_stub",
+        }
+    }
+
+    /// 简短的中文描述，用于统计
+    pub fn label(self) -> &'static str {
+        match self {
+            SkipReason::Cancelled => "运行已取消",
+            SkipReason::Outage => "网络共享断开",
+            SkipReason::Aborted => "运行已中止",
+            SkipReason::CloudPlaceholder => "云端占位文件",
+            SkipReason::ICloudStub => "iCloud 存根文件",
+        }
+    }
+
+    /// 完整的说明，包括用户可以采取的措施
+    pub fn description(self) -> &'static str {
+        match self {
+            SkipReason::Cancelled => "运行已取消，文件未处理",
+            SkipReason::Outage => "网络共享长时间无法访问，文件未处理",
+            SkipReason::Aborted => "最先完成的文件全部以同一种错误失败，运行已中止，文件未处理",
+            SkipReason::CloudPlaceholder => "云端占位文件，内容尚未下载到本地；使用 --hydrate 允许读取时下载",
+            SkipReason::ICloudStub => "iCloud 存根文件，原文件尚未下载到本地；请先在访达中下载后再分析",
+        }
+    }
+
+    /// 旧的 `Result` 形式中对应的错误类型（见 [`FileOutcome::into_result`]）
+    pub fn error_type(self) -> FileErrorType {
+        match self {
+            SkipReason::Cancelled | SkipReason::Outage | SkipReason::Aborted => FileErrorType::Cancelled,
+            SkipReason::CloudPlaceholder | SkipReason::ICloudStub => FileErrorType::CloudPlaceholder,
+        }
+    }
+
+    /// 文件是否因运行提前结束而没有处理（取消、断路器放弃或提前中止），下次运行应该继续处理
+    pub fn is_unprocessed(self) -> bool {
+        matches!(self, SkipReason::Cancelled | SkipReason::Outage | SkipReason::Aborted)
+    }
+}
+
+impl fmt::Display for SkipReason {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.label())
+    }
+}
+
+/// 单个文件的处理结果 (File Outcome)
+///
+/// 处理层返回的元素类型：成功、失败或跳过。需要旧的 `Result` 形式时使用
+/// [`FileOutcome::into_result`]，跳过的文件会变成对应错误类型的 [`ProcessFileError`]。
+///
+/// # 示例
+/// ```
+/// use lra_calculator_rust::error::FileErrorType;
+/// use lra_calculator_rust::processor::{FileOutcome, SkipReason};
+///
+/// let skipped = FileOutcome::Skipped { path: "a.flac".into(), reason: SkipReason::CloudPlaceholder };
+/// assert_eq!(skipped.path().as_str(), "a.flac");
+/// let error = skipped.into_result().unwrap_err();
+/// assert_eq!(error.error_type, FileErrorType::CloudPlaceholder);
+/// ```
+#[derive(Debug, Clone, PartialEq)]
+pub enum FileOutcome {
+    /// 分析成功
+    Success(FileMeasurement),
+    /// 分析失败
+    Failed(ProcessFileError),
+    /// 没有分析
+    Skipped {
+        /// 显示路径
+        path: FileKey,
+        /// 跳过的原因
+        reason: SkipReason,
+    },
+}
+
+impl FileOutcome {
+    /// 文件的显示路径
+    pub fn path(&self) -> &FileKey {
+        match self {
+            FileOutcome::Success(measurement) => &measurement.path,
+            FileOutcome::Failed(error) => &error.file_path,
+            FileOutcome::Skipped { path, .. } => path,
+        }
+    }
+
+    /// 成功的测量结果
+    pub fn measurement(&self) -> Option<&FileMeasurement> {
+        match self {
+            FileOutcome::Success(measurement) => Some(measurement),
+            _ => None,
+        }
+    }
+
+    /// 是否分析成功
+    pub fn is_success(&self) -> bool {
+        matches!(self, FileOutcome::Success(_))
+    }
+
+    /// 转换为旧的 `Result` 形式 (Into Result)
+    ///
+    /// 兼容只区分成功和失败的调用方：跳过的文件变成错误类型为
+    /// [`SkipReason::error_type`] 的 [`ProcessFileError`]。
+    pub fn into_result(self) -> Result<FileMeasurement, ProcessFileError> {
+        match self {
+            FileOutcome::Success(measurement) => Ok(measurement),
+            FileOutcome::Failed(error) => Err(error),
+            FileOutcome::Skipped { path, reason } => {
+                Err(ProcessFileError::new(path, reason.description().to_string(), reason.error_type()))
+            }
+        }
+    }
+}
+
+impl From<Result<FileMeasurement, ProcessFileError>> for FileOutcome {
+    fn from(result: Result<FileMeasurement, ProcessFileError>) -> Self {
+        match result {
+            Ok(measurement) => FileOutcome::Success(measurement),
+            Err(error) => FileOutcome::Failed(error),
+        }
+    }
+}
 
 /// 并行处理音频文件的 LRA 计算 (Parallel LRA Calculation for Audio Files)
 ///
@@ -62,70 +411,575 @@ use crate::error::ProcessFileError;
 ///
 /// # 返回值
 /// 返回处理结果的向量，每个元素为：
-/// - `Ok((String, f64))` - 成功：(显示路径, LRA值)
+/// - `Ok(FileMeasurement)` - 成功：显示路径、LRA 值和综合响度
 /// - `Err(ProcessFileError)` - 失败：包含错误详情的结构体
 ///
 /// # 线程安全性
 /// - 使用原子操作进行计数，避免数据竞争
 /// - 每个文件的处理完全独立，无共享状态
 /// - 输出操作使用 println! 宏，内部有锁保护
-pub fn process_files_parallel(
-    files_to_process: Vec<(PathBuf, String)>,
-) -> Vec<Result<(String, f64), ProcessFileError>> {
+pub fn process_files_parallel<K>(
+    files_to_process: Vec<(PathBuf, K)>,
+) -> Vec<Result<FileMeasurement, ProcessFileError>>
+where
+    K: Into<FileKey> + Send,
+{
+    process_files_parallel_with_events(files_to_process, |_| {})
+}
+
+/// 并行处理音频文件并报告进度事件 (Parallel Processing with Progress Events)
+///
+/// 与 [`process_files_parallel`] 相同，但每个文件处理完成后都会调用 `on_event`，
+/// 传入 [`ProgressEvent::FileDone`] 或 [`ProgressEvent::FileFailed`]。
+/// 回调会在工作线程中被并发调用，因此需要满足 `Sync`。启用 `cli` 特性时控制台进度照常输出（见 `ConsoleSink`）。
+///
+/// # 参数
+/// - `files_to_process` - 要处理的文件列表：(完整路径, 显示路径)
+/// - `on_event` - 进度事件回调
+///
+/// # 返回值
+/// - 与 [`process_files_parallel`] 相同的处理结果
+pub fn process_files_parallel_with_events<K, F>(
+    files_to_process: Vec<(PathBuf, K)>,
+    on_event: F,
+) -> Vec<Result<FileMeasurement, ProcessFileError>>
+where
+    K: Into<FileKey> + Send,
+    F: Fn(ProgressEvent) + Sync,
+{
+    process_files_parallel_ordered(files_to_process, ResultOrder::InputOrder, on_event)
+}
+
+/// 结果顺序 (Result Order)
+///
+/// 并行处理时各文件完成的先后取决于线程调度，每次运行都可能不同。
+/// 需要可复现的输出（如 CI 产物）时使用 [`ResultOrder::InputOrder`]。
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum ResultOrder {
+    /// 与输入顺序一致（默认）：结果带着输入序号收集，处理结束后按序号重排
+    #[default]
+    InputOrder,
+    /// 按完成的先后排列
+    CompletionOrder,
+}
+
+/// 调度顺序 (Schedule Order)
+///
+/// 决定文件交给工作线程的先后，与结果的排列顺序（[`ResultOrder`]）无关：
+/// 打乱调度后 [`ResultOrder::InputOrder`] 的结果仍按输入顺序排列。
+/// 只影响 [`process_files_parallel_with_options`]，边扫描边处理时总是按发现顺序调度。
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum ScheduleOrder {
+    /// 按输入顺序调度（默认）
+    #[default]
+    InputOrder,
+    /// 按种子打乱后调度（`--shuffle`），把同一目录、同一磁盘上的文件分散开，
+    /// 避免并行读取集中在一块机械硬盘上
+    Shuffle {
+        /// 随机种子，相同的种子和文件列表得到相同的调度顺序
+        seed: u64,
+    },
+}
+
+/// 运行选项 (Run Options)
+///
+/// 控制一次并行处理整体的行为，在所有文件之间共享，默认全部关闭。
+/// 单个文件如何分析由 [`CalculationOptions`] 决定。
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct RunOptions {
+    /// 文件交给工作线程的顺序（`--shuffle`）；只影响 [`process_files_parallel_with_options`]
+    pub schedule: ScheduleOrder,
+    /// 取消令牌（`--max-duration`）：取消后不再开始新的文件，未开始的文件以 [`SkipReason::Cancelled`] 跳过
+    pub cancellation: Option<CancellationToken>,
+    /// 同时运行的 FFmpeg 数量限制（`--adaptive-jobs`）：每个文件分析前占用一个名额
+    pub concurrency: Option<ConcurrencyLimiter>,
+    /// 网络共享断连时暂停并重新分析（`--outage-threshold`、`--outage-budget`，见 [`crate::breaker`]）
+    pub outage: Option<CircuitBreaker>,
+    /// 最先完成的文件全部以同一种错误失败时中止（`--abort-after`，见 [`crate::abort`]）：中止后不再开始新的文件，
+    /// 未开始的文件以 [`SkipReason::Aborted`] 跳过
+    pub early_abort: Option<EarlyAbort>,
+    /// 失败发生时直接交给失败流（见 [`crate::failures`]），返回的结果中不再包含失败
+    pub failures: Option<FailureStream>,
+}
+
+/// 按指定顺序并行处理音频文件 (Parallel Processing with Result Order)
+///
+/// 与 [`process_files_parallel_with_events`] 相同，但可以选择结果的排列顺序。
+///
+/// # 参数
+/// - `files_to_process` - 要处理的文件列表：(完整路径, 显示路径)
+/// - `order` - 结果顺序
+/// - `on_event` - 进度事件回调
+///
+/// # 返回值
+/// - 按 `order` 排列的处理结果
+pub fn process_files_parallel_ordered<K, F>(
+    files_to_process: Vec<(PathBuf, K)>,
+    order: ResultOrder,
+    on_event: F,
+) -> Vec<Result<FileMeasurement, ProcessFileError>>
+where
+    K: Into<FileKey> + Send,
+    F: Fn(ProgressEvent) + Sync,
+{
+    let options = CalculationOptions::default();
+    process_files_parallel_with_options(files_to_process, order, &options, &RunOptions::default(), &console_and(&on_event))
+        .into_iter()
+        .map(FileOutcome::into_result)
+        .collect()
+}
+
+/// 默认接收者（[`DefaultSink`]）加上 [`ProgressEvent`] 回调，供接受回调的旧接口使用
+fn console_and<F>(on_event: &F) -> impl EventSink + '_
+where
+    F: Fn(ProgressEvent) + Sync,
+{
+    move |event: &RunEvent| {
+        DefaultSink::default().emit(event);
+        if let Some(event) = event.to_progress_event() {
+            on_event(event);
+        }
+    }
+}
+
+/// 按计算选项并行处理音频文件 (Parallel Processing with Calculation Options)
+///
+/// 与 [`process_files_parallel_ordered`] 相同，每个文件按 `options` 分析
+/// （例如写出调试输出，见 [`CalculationOptions::dump_sink`]），调度、取消和并发名额等按 `run` 控制。
+/// 进度只通过 `sink` 报告，需要控制台输出时使用 `ConsoleSink`（`cli` 特性）或在自己的接收者中转发给它。
+/// 结果区分成功、失败和跳过（[`FileOutcome`]）：取消或断路器放弃后没有开始的文件、
+/// 云端占位文件都是跳过，不算失败。
+///
+/// # 参数
+/// - `files_to_process` - 要处理的文件列表：(完整路径, 显示路径)
+/// - `order` - 结果顺序
+/// - `options` - 单个文件的计算选项
+/// - `run` - 运行选项
+/// - `sink` - 事件接收者，接收每个文件的 [`RunEvent::FileStarted`]、[`RunEvent::FileProgress`]
+///   和 [`RunEvent::FileFinished`]
+///
+/// # 返回值
+/// - 按 `order` 排列的处理结果
+pub fn process_files_parallel_with_options<K, S>(
+    files_to_process: Vec<(PathBuf, K)>,
+    order: ResultOrder,
+    options: &CalculationOptions,
+    run: &RunOptions,
+    sink: &S,
+) -> Vec<FileOutcome>
+where
+    K: Into<FileKey> + Send,
+    S: EventSink + ?Sized,
+{
+    process_collected(files_to_process, order, run.schedule, StopControls::from_options(run), sink, |file_path, display_path, on_progress| {
+        process_single_file(file_path, display_path, options, run.concurrency.as_ref(), on_progress)
+    })
+}
+
+/// 边扫描边处理音频文件 (Pipelined Processing)
+///
+/// 扫描和分析同时进行：`files` 在独立的扫描线程中迭代，发现的文件经过容量为
+/// [`PIPELINE_CAPACITY`] 的有界通道交给 Rayon 工作线程。扫描远快于分析时，
+/// 扫描线程会在通道满后等待，因此内存占用与目录树大小无关。
+///
+/// 扫描过程中每发现 [`SCAN_PROGRESS_INTERVAL`] 个文件报告一次
+/// [`ProgressEvent::ScanProgress`]，扫描结束时报告 [`ProgressEvent::ScanDone`]。
+/// 启用 `cli` 特性时控制台进度照常输出（见 `ConsoleSink`）。
+///
+/// # 参数
+/// - `files` - 文件迭代器（通常是 [`crate::audio::scan_audio_files_iter`]），
+///   调用结束后已耗尽，可继续用于读取扫描统计
+/// - `order` - 结果顺序；[`ResultOrder::InputOrder`] 时按迭代器产出的顺序排列
+/// - `on_event` - 进度事件回调
+///
+/// # 返回值
+/// - 按 `order` 排列的处理结果
+pub fn process_files_pipelined_with_events<I, K, F>(
+    files: &mut I,
+    order: ResultOrder,
+    on_event: F,
+) -> Vec<Result<FileMeasurement, ProcessFileError>>
+where
+    I: Iterator<Item = (PathBuf, K)> + Send,
+    K: Into<FileKey> + Send,
+    F: Fn(ProgressEvent) + Sync,
+{
+    let options = CalculationOptions::default();
+    process_files_pipelined_with_options(files, order, &options, &RunOptions::default(), &console_and(&on_event))
+        .into_iter()
+        .map(FileOutcome::into_result)
+        .collect()
+}
+
+/// 按计算选项边扫描边处理音频文件 (Pipelined Processing with Calculation Options)
+///
+/// 与 [`process_files_pipelined_with_events`] 相同，每个文件按 `options` 分析，
+/// 进度只通过 `sink` 报告（扫描线程发出 [`RunEvent::ScanProgress`] 和 [`RunEvent::ScanFinished`]）。
+/// 结果与 [`process_files_parallel_with_options`] 一样区分成功、失败和跳过。
+///
+/// # 参数
+/// - `files` - 文件迭代器，调用结束后已耗尽
+/// - `order` - 结果顺序
+/// - `options` - 单个文件的计算选项
+/// - `run` - 运行选项；总是按发现顺序调度，忽略 `schedule`
+/// - `sink` - 事件接收者
+///
+/// # 返回值
+/// - 按 `order` 排列的处理结果
+pub fn process_files_pipelined_with_options<I, K, S>(
+    files: &mut I,
+    order: ResultOrder,
+    options: &CalculationOptions,
+    run: &RunOptions,
+    sink: &S,
+) -> Vec<FileOutcome>
+where
+    I: Iterator<Item = (PathBuf, K)> + Send,
+    K: Into<FileKey> + Send,
+    S: EventSink + ?Sized,
+{
+    process_pipelined(files, order, StopControls::from_options(run), sink, |file_path, display_path, on_progress| {
+        process_single_file(file_path, display_path, options, run.concurrency.as_ref(), on_progress)
+    })
+}
+
+/// 扫描线程与工作线程之间的通道容量
+pub const PIPELINE_CAPACITY: usize = 256;
+
+/// 扫描进度的报告间隔（文件数）
+pub const SCAN_PROGRESS_INTERVAL: usize = 1000;
+
+/// 按 [`ResultOrder`] 收集并行产生的结果
+///
+/// 工作线程完成一个文件就带着输入序号放入收集器，结束时按需要重排。
+struct ResultCollector<T> {
+    /// 结果顺序
+    order: ResultOrder,
+    /// 按完成顺序排列的 (输入序号, 结果)
+    completed: Mutex<Vec<(usize, T)>>,
+}
+
+impl<T> ResultCollector<T> {
+    fn new(order: ResultOrder, capacity: usize) -> Self {
+        Self { order, completed: Mutex::new(Vec::with_capacity(capacity)) }
+    }
+
+    fn push(&self, index: usize, result: T) {
+        self.completed
+            .lock()
+            .unwrap_or_else(|poisoned| poisoned.into_inner())
+            .push((index, result));
+    }
+
+    /// 丢弃这些输入序号的结果（重新分析之前）
+    fn discard(&self, indices: &HashSet<usize>) {
+        self.completed
+            .lock()
+            .unwrap_or_else(|poisoned| poisoned.into_inner())
+            .retain(|(index, _)| !indices.contains(index));
+    }
+
+    fn into_vec(self) -> Vec<T> {
+        let mut completed = self.completed.into_inner().unwrap_or_else(|poisoned| poisoned.into_inner());
+        if self.order == ResultOrder::InputOrder {
+            completed.sort_unstable_by_key(|(index, _)| *index);
+        }
+        completed.into_iter().map(|(_, result)| result).collect()
+    }
+}
+
+impl ResultCollector<FileOutcome> {
+    /// 取出结果；设置了失败流时，仍在收集器中的失败（等待重新分析的断连类失败）交给失败流
+    fn into_outcomes(self, failures: Option<&FailureStream>) -> Vec<FileOutcome> {
+        let outcomes = self.into_vec();
+        let Some(failures) = failures else {
+            return outcomes;
+        };
+        outcomes
+            .into_iter()
+            .filter_map(|outcome| match outcome {
+                FileOutcome::Failed(error) => {
+                    failures.record(error);
+                    None
+                }
+                other => Some(other),
+            })
+            .collect()
+    }
+}
+
+/// 决定是否继续开始新文件的控制项：取消令牌、断路器和提前中止判断，以及接收失败的失败流
+#[derive(Clone, Copy, Default)]
+struct StopControls<'a> {
+    cancellation: Option<&'a CancellationToken>,
+    breaker: Option<&'a CircuitBreaker>,
+    early_abort: Option<&'a EarlyAbort>,
+    failures: Option<&'a FailureStream>,
+}
+
+impl<'a> StopControls<'a> {
+    fn from_options(run: &'a RunOptions) -> Self {
+        Self {
+            cancellation: run.cancellation.as_ref(),
+            breaker: run.outage.as_ref(),
+            early_abort: run.early_abort.as_ref(),
+            failures: run.failures.as_ref(),
+        }
+    }
+}
+
+/// 处理完整文件列表，`runner` 负责分析单个文件
+fn process_collected<K, S, R>(
+    files_to_process: Vec<(PathBuf, K)>,
+    order: ResultOrder,
+    schedule: ScheduleOrder,
+    controls: StopControls,
+    sink: &S,
+    runner: R,
+) -> Vec<FileOutcome>
+where
+    K: Into<FileKey> + Send,
+    S: EventSink + ?Sized,
+    R: Fn(&Path, &FileKey, &dyn Fn(AnalysisProgress)) -> FileOutcome + Sync,
+{
     let total_files = files_to_process.len();
     let processed_count = AtomicUsize::new(0);
+    let collector = ResultCollector::new(order, total_files);
 
-    println!("开始多线程直接分析...");
-    println!("总文件数: {}, 可用 CPU 核心数: {}", total_files, rayon::current_num_threads());
+    // 先记下输入序号再调整调度顺序，结果仍可以按输入顺序重排
+    let mut indexed_files: Vec<(usize, (PathBuf, K))> = files_to_process.into_iter().enumerate().collect();
+    if let ScheduleOrder::Shuffle { seed } = schedule {
+        shuffle(&mut indexed_files, seed);
+    }
 
     // 使用 Rayon 的并行迭代器进行数据并行处理
     // into_par_iter() 将 Vec 转换为并行迭代器，自动分配到多个线程
-    files_to_process
+    indexed_files
         .into_par_iter()
-        .map(|(current_file_path, display_path_str)| {
+        .for_each(|(index, (current_file_path, display_path))| {
+            let display_path = display_path.into();
+            if let Some(reason) = stop_reason(controls, sink) {
+                collector.push(index, FileOutcome::Skipped { path: display_path, reason });
+                return;
+            }
             // 原子性地增加已处理计数，确保线程安全
             // fetch_add 返回增加前的值，所以需要 +1 得到当前处理的文件序号
             let current_processed_atomic = processed_count.fetch_add(1, Ordering::SeqCst) + 1;
+            let position = FilePosition { current: current_processed_atomic, total: total_files, total_final: true };
+            process_with_progress(&current_file_path, display_path, position, sink, &runner, |result| {
+                record_outcome(controls, &collector, index, &current_file_path, result, sink)
+            });
+        });
+
+    requeue_outage_failures(controls, &collector, sink, &runner);
+    collector.into_outcomes(controls.failures)
+}
 
-            // 显示开始处理的信息，包含线程 ID 用于调试
-            println!(
-                "  [线程 {:?}] ({}/{}) 开始分析: {}",
-                thread::current().id(),
-                current_processed_atomic,
-                total_files,
-                display_path_str
-            );
-
-            // 执行实际的 LRA 计算
-            let result = process_single_file(&current_file_path, &display_path_str);
-
-            // 根据处理结果显示相应的信息
-            match &result {
-                Ok((_, lra)) => {
-                    println!(
-                        "    [线程 {:?}] ({}/{}) ✓ 分析成功: {} → LRA: {:.1} LU",
-                        thread::current().id(),
-                        current_processed_atomic,
-                        total_files,
-                        display_path_str,
-                        lra
-                    );
+/// 边扫描边处理，`runner` 负责分析单个文件
+fn process_pipelined<I, K, S, R>(
+    files: &mut I,
+    order: ResultOrder,
+    controls: StopControls,
+    sink: &S,
+    runner: R,
+) -> Vec<FileOutcome>
+where
+    I: Iterator<Item = (PathBuf, K)> + Send,
+    K: Into<FileKey> + Send,
+    S: EventSink + ?Sized,
+    R: Fn(&Path, &FileKey, &dyn Fn(AnalysisProgress)) -> FileOutcome + Sync,
+{
+    let discovered = AtomicUsize::new(0);
+    let scan_finished = AtomicBool::new(false);
+    let processed_count = AtomicUsize::new(0);
+    let collector = ResultCollector::new(order, PIPELINE_CAPACITY);
+
+    let (discovered, scan_finished) = (&discovered, &scan_finished);
+    thread::scope(|scope| {
+        let (sender, receiver) = mpsc::sync_channel(PIPELINE_CAPACITY);
+
+        scope.spawn(move || {
+            for (index, file) in files.enumerate() {
+                // 取消或中止后没有必要继续扫描，未发现的文件不计入未处理的文件
+                if controls.cancellation.is_some_and(CancellationToken::is_cancelled)
+                    || controls.early_abort.is_some_and(EarlyAbort::is_tripped)
+                {
+                    break;
+                }
+                let count = discovered.fetch_add(1, Ordering::SeqCst) + 1;
+                if count % SCAN_PROGRESS_INTERVAL == 0 {
+                    sink.emit(&RunEvent::ScanProgress { found: count });
                 }
-                Err(error) => {
-                    println!(
-                        "    [线程 {:?}] ({}/{}) ✗ 分析失败: {} → {}",
-                        thread::current().id(),
-                        current_processed_atomic,
-                        total_files,
-                        display_path_str,
-                        error.message
-                    );
+                // 接收端只会在处理结束后关闭，发送失败时直接停止扫描
+                if sender.send((index, file)).is_err() {
+                    break;
                 }
             }
+            scan_finished.store(true, Ordering::SeqCst);
+            sink.emit(&RunEvent::ScanFinished { total: discovered.load(Ordering::SeqCst) });
+            // 线程结束时 sender 被丢弃，工作线程取完剩余文件后结束
+        });
+
+        receiver
+            .into_iter()
+            .par_bridge()
+            .for_each(|(index, (current_file_path, display_path))| {
+                let display_path = display_path.into();
+                if let Some(reason) = stop_reason(controls, sink) {
+                    collector.push(index, FileOutcome::Skipped { path: display_path, reason });
+                    return;
+                }
+                let current = processed_count.fetch_add(1, Ordering::SeqCst) + 1;
+                // 扫描未结束时总数仍在增长
+                let position = FilePosition {
+                    current,
+                    total: discovered.load(Ordering::SeqCst),
+                    total_final: scan_finished.load(Ordering::SeqCst),
+                };
+                process_with_progress(&current_file_path, display_path, position, sink, &runner, |result| {
+                    record_outcome(controls, &collector, index, &current_file_path, result, sink)
+                });
+            });
+    });
+
+    requeue_outage_failures(controls, &collector, sink, &runner);
+    collector.into_outcomes(controls.failures)
+}
 
-            result
-        })
-        .collect()  // 收集所有结果到 Vec 中
+/// 开始一个文件之前的检查：断路器断开时等待恢复，然后检查运行是否已经中止或取消
+///
+/// # 返回值
+/// - `None` - 可以开始分析
+/// - `Some(原因)` - 不再开始新的文件，剩下的文件以这个原因跳过
+fn stop_reason<S>(controls: StopControls, sink: &S) -> Option<SkipReason>
+where
+    S: EventSink + ?Sized,
+{
+    let StopControls { cancellation, breaker, early_abort, .. } = controls;
+    if breaker.is_some_and(|breaker| !breaker.wait_until_closed(cancellation, sink) && breaker.gave_up()) {
+        return Some(SkipReason::Outage);
+    }
+    if early_abort.is_some_and(EarlyAbort::is_tripped) {
+        return Some(SkipReason::Aborted);
+    }
+    cancellation.is_some_and(CancellationToken::is_cancelled).then_some(SkipReason::Cancelled)
+}
+
+/// 把结果交给断路器、提前中止判断和收集器
+///
+/// 断路器只关心失败，跳过的文件与成功一样打断连续失败；提前中止判断不观察跳过的文件，
+/// 启用断路器时也不观察断连类失败（它们会被重新分析）。设置了失败流时失败直接交给失败流，
+/// 不进入收集器；启用断路器时断连类失败仍留在收集器中，重新分析时才能替换。
+fn record_outcome<S>(
+    controls: StopControls,
+    collector: &ResultCollector<FileOutcome>,
+    index: usize,
+    file_path: &Path,
+    outcome: FileOutcome,
+    sink: &S,
+) where
+    S: EventSink + ?Sized,
+{
+    if let Some(breaker) = controls.breaker {
+        let failure = match &outcome {
+            FileOutcome::Failed(error) => Err(error.clone()),
+            _ => Ok(()),
+        };
+        breaker.record(index, file_path, &failure, sink);
+    }
+    if let Some(early_abort) = controls.early_abort {
+        match &outcome {
+            FileOutcome::Success(_) => {
+                early_abort.record(Ok(()));
+            }
+            FileOutcome::Failed(error) if !(controls.breaker.is_some() && is_outage_error(error)) => {
+                early_abort.record(Err(error));
+            }
+            _ => {}
+        }
+    }
+    match (controls.failures, outcome) {
+        (Some(failures), FileOutcome::Failed(error)) if !(controls.breaker.is_some() && is_outage_error(&error)) => {
+            failures.record(error);
+        }
+        (_, outcome) => collector.push(index, outcome),
+    }
+}
+
+/// 重新分析断连期间失败的文件 (Requeue Outage Failures)
+///
+/// 处理结束后取出断路器记下的文件，丢弃它们之前的结果再并行分析一次；
+/// 重新分析期间再次断连时重复，每个文件最多重新分析一次（见 [`CircuitBreaker::take_requeue`]）。
+fn requeue_outage_failures<S, R>(
+    controls: StopControls,
+    collector: &ResultCollector<FileOutcome>,
+    sink: &S,
+    runner: &R,
+) where
+    S: EventSink + ?Sized,
+    R: Fn(&Path, &FileKey, &dyn Fn(AnalysisProgress)) -> FileOutcome + Sync,
+{
+    let Some(breaker) = controls.breaker else {
+        return;
+    };
+    loop {
+        let requeue = breaker.take_requeue();
+        if requeue.is_empty() {
+            return;
+        }
+        // 最后几个文件触发断开时先等待恢复；放弃或取消后保留原来的失败结果
+        if stop_reason(controls, sink).is_some() {
+            return;
+        }
+        collector.discard(&requeue.iter().map(|(index, ..)| *index).collect());
+        sink.emit(&RunEvent::Requeued { files: requeue.len() });
+
+        let total = requeue.len();
+        let processed_count = AtomicUsize::new(0);
+        requeue.into_par_iter().for_each(|(index, file_path, display_path)| {
+            if let Some(reason) = stop_reason(controls, sink) {
+                collector.push(index, FileOutcome::Skipped { path: display_path, reason });
+                return;
+            }
+            let current = processed_count.fetch_add(1, Ordering::SeqCst) + 1;
+            let position = FilePosition { current, total, total_final: true };
+            process_with_progress(&file_path, display_path, position, sink, runner, |result| {
+                record_outcome(controls, collector, index, &file_path, result, sink)
+            });
+        });
+    }
+}
+
+/// 分析单个文件并报告事件
+///
+/// # 参数
+/// - `position` - 文件的位置，如 `3/120`
+/// - `record` - 接收分析结果
+fn process_with_progress<S, R>(
+    current_file_path: &Path,
+    display_path: FileKey,
+    position: FilePosition,
+    sink: &S,
+    runner: &R,
+    record: impl FnOnce(FileOutcome),
+) where
+    S: EventSink + ?Sized,
+    R: Fn(&Path, &FileKey, &dyn Fn(AnalysisProgress)) -> FileOutcome,
+{
+    sink.emit(&RunEvent::FileStarted { path: display_path.clone(), position });
+
+    // 长文件运行较久时报告进度，避免看起来像卡住了
+    let report_progress = |progress: AnalysisProgress| {
+        sink.emit(&RunEvent::FileProgress { path: display_path.clone(), position, progress });
+    };
+
+    // 执行实际的 LRA 计算
+    let outcome = runner(current_file_path, &display_path, &report_progress);
+
+    // 先记录结果再报告事件：收到事件时结果已经就位
+    let event = RunEvent::FileFinished { position, outcome: Box::new(outcome.clone()) };
+    record(outcome);
+    sink.emit(&event);
 }
 
 /// 处理单个音频文件 (Process Single Audio File)
@@ -133,50 +987,206 @@ pub fn process_files_parallel(
 /// 这个辅助函数封装了单个文件的处理逻辑，包括 LRA 计算和错误分类。
 /// 分离这个逻辑可以提高代码的可读性和可测试性。
 ///
-/// ## 错误分类策略
-/// 根据错误信息的内容自动判断错误类型：
-/// - FFmpeg 相关错误：包含 "ffmpeg" 或 "FFmpeg" 关键词
-/// - LRA 解析错误：包含 "解析" 或 "LRA" 关键词
-/// - 其他错误：未分类的错误类型
-///
 /// # 参数
 /// - `file_path` - 文件的完整路径
 /// - `display_path` - 用于显示的路径
+/// - `options` - 计算选项
+/// - `concurrency` - 并发限制器（[`RunOptions::concurrency`]）；分析前占用一个名额
+/// - `on_progress` - 长文件的进度回调，见 [`measure_loudness_with_progress`]
 ///
 /// # 返回值
-/// - `Ok((String, f64))` - 成功：(显示路径, LRA值)
-/// - `Err(ProcessFileError)` - 失败：分类后的错误信息
+/// - `FileOutcome::Success` - 成功：显示路径、LRA 值和综合响度
+/// - `FileOutcome::Failed` - 失败：分类后的错误信息
+/// - `FileOutcome::Skipped` - 云端占位文件，没有分析
 fn process_single_file(
     file_path: &Path,
-    display_path: &str
-) -> Result<(String, f64), ProcessFileError> {
-    match calculate_lra_direct(file_path) {
-        Ok(lra) => Ok((display_path.to_string(), lra)),
-        Err(e) => {
-            let err_msg = format!("分析失败: {e}");
-
-            // 根据错误信息内容自动分类错误类型
-            let error = if err_msg.contains("ffmpeg") || err_msg.contains("FFmpeg") {
-                ProcessFileError::ffmpeg_error(display_path.to_string(), err_msg)
-            } else if err_msg.contains("解析") || err_msg.contains("LRA") {
-                ProcessFileError::lra_parsing_error(display_path.to_string(), err_msg)
-            } else {
-                ProcessFileError::new(
-                    display_path.to_string(),
-                    err_msg,
-                    crate::error::FileErrorType::Other
-                )
-            };
+    display_path: &FileKey,
+    options: &CalculationOptions,
+    concurrency: Option<&ConcurrencyLimiter>,
+    on_progress: &dyn Fn(AnalysisProgress),
+) -> FileOutcome {
+    if let Some(outcome) = check_file_available(file_path, display_path, options) {
+        return outcome;
+    }
+
+    // 哈希在单独的线程中进行，与 FFmpeg 的分析同时读取文件
+    let (analysis, content_hash) = thread::scope(|scope| {
+        let hasher = options.hash.map(|algorithm| scope.spawn(move || content_hash(file_path, algorithm)));
+        // 名额只覆盖 FFmpeg 的运行时间，等待期间不占用
+        let permit = concurrency.map(ConcurrencyLimiter::acquire);
+        let started = Instant::now();
+        let analysis = measure_loudness_with_progress(file_path, display_path, options, on_progress)
+            .map(|summary| FileAnalysis::complete(summary, file_path, display_path, options, started));
+        drop(permit);
+        (analysis, hasher.and_then(|handle| handle.join().ok()))
+    });
+    analysis
+        .map(|analysis| analysis.into_measurement(display_path, content_hash, rayon::current_thread_index()))
+        .map_err(|e| classify_lra_error(display_path, e))
+        .into()
+}
+
+/// 单个文件的 FFmpeg 分析结果 (File Analysis)
+///
+/// 主测量和按选项进行的附加测量。同步路径 [`process_single_file`] 和
+/// [`crate::async_api`] 都经由这里组装 [`FileMeasurement`]，两者的结果包含相同的字段。
+#[derive(Debug)]
+pub(crate) struct FileAnalysis {
+    /// ebur128 主测量的汇总
+    summary: LoudnessSummary,
+    /// `also_downmixed` 时缩混后的 LRA
+    downmixed_lra: Option<f64>,
+    /// `per_channel` 时各声道的测量值
+    channels: Option<Vec<ChannelLoudness>>,
+    /// 附加测量失败的说明
+    warnings: Vec<String>,
+    /// 从主测量开始到附加测量结束的用时
+    analysis_time: Duration,
+}
 
-            Err(error)
+impl FileAnalysis {
+    /// 主测量成功后进行附加测量 (Complete Analysis)
+    ///
+    /// 缩混测量和逐声道测量是同一个文件后续的 FFmpeg 运行，调用方持有的并发名额同样覆盖它们。
+    /// 附加测量失败时只保留主测量的值，失败的说明记录在 [`FileMeasurement::analysis_warnings`] 中。
+    ///
+    /// # 参数
+    /// - `summary` - 主测量的汇总
+    /// - `started` - 主测量开始的时刻，用于计算分析用时
+    pub(crate) fn complete(
+        summary: LoudnessSummary,
+        file_path: &Path,
+        display_path: &str,
+        options: &CalculationOptions,
+        started: Instant,
+    ) -> Self {
+        let mut warnings = Vec::new();
+        let downmixed = options.also_downmixed.then(|| measure_downmixed_lra(file_path, display_path, options));
+        let downmixed_lra = match downmixed {
+            Some(Err(e)) => {
+                warnings.push(format!("{}缩混测量失败，只保留原始声道的测量值: {}", Downmix::Stereo, e));
+                None
+            }
+            measured => measured.and_then(Result::ok).flatten(),
+        };
+        let channels = match options.per_channel.then(|| measure_channels(file_path, options)) {
+            Some(Err(e)) => {
+                warnings.push(format!("逐声道测量失败，只保留整体测量值: {}", e));
+                None
+            }
+            measured => measured.and_then(Result::ok).flatten(),
+        };
+        Self { summary, downmixed_lra, channels, warnings, analysis_time: started.elapsed() }
+    }
+
+    /// 组装测量结果 (Into Measurement)
+    ///
+    /// # 参数
+    /// - `content_hash` - 与分析同时计算的内容摘要（见 [`content_hash`]）；未启用时为 `None`
+    /// - `worker` - 分析所在的 Rayon 工作线程；异步路径为 `None`
+    pub(crate) fn into_measurement(
+        self,
+        display_path: &FileKey,
+        content_hash: Option<Result<ContentHash, String>>,
+        worker: Option<usize>,
+    ) -> FileMeasurement {
+        let summary = self.summary;
+        let mut analysis_warnings = self.warnings;
+        let content_hash = content_hash.and_then(|hash| hash.map_err(|warning| analysis_warnings.push(warning)).ok());
+        FileMeasurement::new(display_path.clone(), summary.lra)
+            .with_integrated_lufs(summary.integrated_lufs)
+            .with_true_peak(summary.true_peak_dbtp)
+            .with_range_bounds(summary.lra_low_lufs, summary.lra_high_lufs, summary.threshold_lufs)
+            .with_content_hash(content_hash)
+            .with_downmixed_lra(self.downmixed_lra)
+            .with_channels(self.channels)
+            .with_timing(summary.duration, self.analysis_time)
+            .with_worker(worker)
+            .with_warnings(summary.warnings)
+            .with_analysis_warnings(analysis_warnings)
+    }
+}
+
+/// 计算文件内容的摘要 (Content Hash)
+///
+/// 哈希只是附加信息，计算失败不应让测量失败：失败时返回记录在
+/// [`FileMeasurement::analysis_warnings`] 中的说明，对应的哈希列留空。
+pub(crate) fn content_hash(file_path: &Path, algorithm: HashAlgorithm) -> Result<ContentHash, String> {
+    hash_file(file_path, algorithm).map_err(|e| format!("无法计算 {} 哈希，哈希列将留空: {}", algorithm, e))
+}
+
+/// 分析前检查文件是否可以分析 (Check File Available)
+///
+/// 先确认文件仍然存在：同步中的音乐库里，文件可能在扫描之后被改名或删除，
+/// 这时直接返回 [`ProcessFileError::vanished`]，不启动 FFmpeg。无法确定是否存在时（例如权限不足）
+/// 照常交给 FFmpeg，由它给出具体错误。然后检查云端占位文件（[`cloud_skip_reason`]）。
+///
+/// # 返回值
+/// - `None` - 可以分析
+/// - `Some(FileOutcome)` - 不分析：文件已消失（失败）或是云端占位文件（跳过）
+pub(crate) fn check_file_available(
+    file_path: &Path,
+    display_path: &FileKey,
+    options: &CalculationOptions,
+) -> Option<FileOutcome> {
+    if matches!(file_path.try_exists(), Ok(false)) {
+        return Some(FileOutcome::Failed(ProcessFileError::vanished(display_path.clone())));
+    }
+    cloud_skip_reason(file_path, options).map(|reason| FileOutcome::Skipped { path: display_path.clone(), reason })
+}
+
+/// 分析前检查云端占位文件 (Cloud Skip Reason)
+///
+/// 占位文件默认不分析，避免 FFmpeg 读取时触发下载；`hydrate` 时只跳过 iCloud 存根，
+/// 因为读取存根本身不会下载原文件。
+///
+/// # 返回值
+/// - `None` - 可以分析
+/// - `Some(SkipReason)` - [`SkipReason::ICloudStub`] 或 [`SkipReason::CloudPlaceholder`]
+pub(crate) fn cloud_skip_reason(file_path: &Path, options: &CalculationOptions) -> Option<SkipReason> {
+    if is_icloud_stub(file_path) {
+        Some(SkipReason::ICloudStub)
+    } else if !options.hydrate && is_cloud_placeholder(file_path) {
+        Some(SkipReason::CloudPlaceholder)
+    } else {
+        None
+    }
+}
+
+/// 将 LRA 计算错误转换为文件处理错误 (Classify LRA Calculation Error)
+///
+/// ## 错误分类策略
+/// 根据 [`LraCalculationError`] 的变体确定错误类型，而不是匹配错误信息文本：
+/// - `Spawn` / `NonZeroExit` / `TimedOut` → FFmpeg 执行失败
+/// - `ParseFailed` / `InvalidValue` → LRA 值解析失败
+///
+/// 对于 `NonZeroExit`，退出码和 stderr 摘录会被附加到返回的错误上。FFmpeg 报告输入文件不存在时
+/// （[`LraCalculationError::is_missing_input`]），说明文件在检查之后才消失，返回 [`ProcessFileError::vanished`]。
+///
+/// # 参数
+/// - `display_path` - 用于显示的路径
+/// - `error` - LRA 计算错误
+///
+/// # 返回值
+/// - 分类后的 `ProcessFileError`
+pub(crate) fn classify_lra_error(display_path: &FileKey, error: LraCalculationError) -> ProcessFileError {
+    let file_error = match error.is_missing_input() {
+        true => ProcessFileError::vanished(display_path.clone()),
+        false => ProcessFileError::new(display_path.clone(), format!("分析失败: {error}"), error.file_error_type()),
+    };
+
+    match error {
+        LraCalculationError::NonZeroExit { code, stderr, .. } => {
+            file_error.with_ffmpeg_context(code, &stderr)
         }
+        _ => file_error,
     }
 }
 
 /// 处理结果统计信息 (Processing Statistics)
 ///
 /// 这个结构体用于汇总并行处理的统计信息，提供处理结果的概览。
-/// 它不仅包含成功和失败的数量，还保存了详细的错误信息用于调试和用户反馈。
+/// 它不仅包含成功和失败的数量，还保存了失败文件的错误用于调试和用户反馈。
 ///
 /// ## 设计考虑
 ///
@@ -186,20 +1196,141 @@ fn process_single_file(
 /// - **错误详情**: 保存所有错误信息，便于问题诊断和用户反馈
 ///
 /// ### 内存管理
-/// - 错误信息使用 `Vec<String>` 存储，避免生命周期复杂性
-/// - 在大批量处理时，错误信息可能占用较多内存，但通常错误数量有限
+/// - 直接保存 [`ProcessFileError`]，不预先格式化错误信息；
+///   需要文本时通过 [`ProcessingStats::error_messages`] 按需格式化
+/// - 全部失败时错误数量与文件数量相当：[`analyze_results_streaming`] 只保留最前和最后的若干条
+///   （省略的数量见 [`ProcessingStats::omitted_errors`]），完整的列表逐条交给回调写入文件
+///
+/// ### 耗时统计
+/// - 音频总时长取自 FFmpeg 输出的时长（与 ffprobe 的 `format=duration` 相同），
+///   只统计本次实际分析的文件，缓存命中的文件不计入
+/// - 墙钟时间由调用方在处理结束后设置（[`ProcessingStats::wall_seconds`]），
+///   实时倍率和吞吐量都基于墙钟时间，反映并行处理的整体速度
+/// - 分析时间按执行的工作线程累计（[`ProcessingStats::workers`]），
+///   与墙钟时间比较可以看出线程是一直在分析还是在等待
 ///
 /// ### 扩展性
-/// - 结构体设计便于未来添加更多统计维度（如处理时间、文件大小等）
+/// - 结构体设计便于未来添加更多统计维度（如文件大小等）
 /// - 所有字段都是公开的，便于外部代码访问和分析
-#[derive(Debug, Clone)]
+/// - 启用 `serde` 特性后可通过 [`ProcessingStats::to_json`] 导出为 JSON
+#[derive(Debug, Clone, Default, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Deserialize))]
 pub struct ProcessingStats {
     /// 成功处理的文件数量
     pub successful: usize,
     /// 失败的文件数量
     pub failed: usize,
-    /// 详细的错误信息列表，每个元素包含文件路径和错误描述
-    pub error_messages: Vec<String>,
+    /// 按错误类型统计的失败数量
+    pub error_type_counts: BTreeMap<FileErrorType, usize>,
+    /// 扫描之后消失的文件数量（见 [`ProcessFileError::vanished`]），同时计入
+    /// `error_type_counts` 中的 [`FileErrorType::FileAccess`]
+    #[cfg_attr(feature = "serde", serde(default))]
+    pub vanished: usize,
+    /// 失败文件的错误，按处理结果的顺序排列；设置了保留上限时只有最前和最后的若干条
+    #[cfg_attr(feature = "serde", serde(default))]
+    pub errors: Vec<ProcessFileError>,
+    /// 超出保留上限而没有保存在 `errors` 中的错误数量（见 [`analyze_results_streaming`]）
+    #[cfg_attr(feature = "serde", serde(default))]
+    pub omitted_errors: usize,
+    /// 本次分析成功的文件数量（不含缓存命中）
+    #[cfg_attr(feature = "serde", serde(default))]
+    pub analyzed_files: usize,
+    /// 本次分析成功的文件的音频总时长（秒）；没有任何文件的时长已知时为 `None`
+    #[cfg_attr(feature = "serde", serde(default))]
+    pub audio_seconds: Option<f64>,
+    /// 各文件分析时间之和（秒）
+    #[cfg_attr(feature = "serde", serde(default))]
+    pub analysis_seconds: f64,
+    /// 整个处理过程的墙钟时间（秒）；由调用方设置，未设置时为 `None`
+    #[cfg_attr(feature = "serde", serde(default))]
+    pub wall_seconds: Option<f64>,
+    /// 各工作线程的忙碌时间，按线程序号排列
+    #[cfg_attr(feature = "serde", serde(default))]
+    pub workers: Vec<WorkerUtilization>,
+    /// LRA 超出合理范围的成功结果（照常写入结果文件），按处理结果的顺序排列
+    #[cfg_attr(feature = "serde", serde(default))]
+    pub suspicious: Vec<SuspiciousMeasurement>,
+    /// 标记 `suspicious` 时使用的合理范围，见 [`ProcessingStats::flag_suspicious`]
+    #[cfg_attr(feature = "serde", serde(default))]
+    pub sanity_range: LraRange,
+    /// 显示路径与其他文件相同而被改名的文件；来自扫描报告，由调用方设置
+    #[cfg_attr(feature = "serde", serde(default))]
+    pub path_collisions: Vec<DisplayPathCollision>,
+    /// 声道间综合响度差异超过阈值的文件（`--per-channel`）；由调用方设置
+    #[cfg_attr(feature = "serde", serde(default))]
+    pub channel_imbalances: Vec<ChannelImbalance>,
+    /// 真峰值超过上限的文件（`--true-peak`），按真峰值从高到低排列；由调用方设置
+    #[cfg_attr(feature = "serde", serde(default))]
+    pub clipping_risks: Vec<ClippingRisk>,
+    /// 本次运行的 ID；由调用方设置，旧版本导出的统计没有此字段
+    #[cfg_attr(feature = "serde", serde(default))]
+    pub run_id: Option<RunId>,
+    /// 跳过的文件数量（见 [`FileOutcome::Skipped`]），不计入成功或失败
+    #[cfg_attr(feature = "serde", serde(default))]
+    pub skipped: usize,
+    /// 按原因统计的跳过数量
+    #[cfg_attr(feature = "serde", serde(default))]
+    pub skip_reason_counts: BTreeMap<SkipReason, usize>,
+    /// 时间预算到期或运行提前停止而没有处理的文件数量（见 [`SkipReason::is_unprocessed`]）；
+    /// 由调用方设置，计入统计摘要中的总文件数，但不计入 [`ProcessingStats::total`] 和成功率
+    #[cfg_attr(feature = "serde", serde(default))]
+    pub unprocessed: usize,
+    /// 分析成功但 FFmpeg 输出了警告的文件，按处理结果的顺序排列
+    #[cfg_attr(feature = "serde", serde(default))]
+    pub ffmpeg_warnings: Vec<FileWarnings>,
+    /// 本次运行计划中的文件按扩展名的统计；由调用方设置，流水线模式下为 `None`
+    #[cfg_attr(feature = "serde", serde(default))]
+    pub scan: Option<ScanStatistics>,
+}
+
+/// 有 FFmpeg 警告的文件 (File Warnings)
+#[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct FileWarnings {
+    /// 显示路径
+    pub path: FileKey,
+    /// 识别出的警告，见 [`crate::warnings::extract_ffmpeg_warnings`]
+    pub warnings: Vec<String>,
+}
+
+/// 超出合理范围的测量值 (Suspicious Measurement)
+///
+/// 合理范围见 [`ProcessingStats::sanity_range`]（默认 0–40 LU，`--lra-range`）。
+#[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct SuspiciousMeasurement {
+    /// 显示路径
+    pub path: FileKey,
+    /// LRA 值（LU）
+    #[cfg_attr(feature = "serde", serde(serialize_with = "crate::precision::serialize_rounded"))]
+    pub lra: f64,
+}
+
+/// 单个工作线程的忙碌时间 (Worker Utilization)
+///
+/// 只统计本次成功分析的文件；失败的文件没有计时，缓存命中的文件不占用工作线程。
+#[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct WorkerUtilization {
+    /// Rayon 工作线程序号（线程池内从 0 开始）
+    pub index: usize,
+    /// 这个线程分析的文件数量
+    pub files: usize,
+    /// 这个线程分析文件所用时间之和（秒）
+    pub busy_seconds: f64,
+}
+
+impl WorkerUtilization {
+    /// 利用率（百分比）：忙碌时间占墙钟时间的比例
+    ///
+    /// # 参数
+    /// - `wall_seconds` - 整个处理过程的墙钟时间（秒）
+    ///
+    /// # 返回值
+    /// - 0 到 100 之间的百分比；墙钟时间不为正数时返回 `None`
+    pub fn utilization_percent(&self, wall_seconds: f64) -> Option<f64> {
+        (wall_seconds > 0.0).then(|| (self.busy_seconds / wall_seconds * 100.0).min(100.0))
+    }
 }
 
 impl ProcessingStats {
@@ -208,16 +1339,40 @@ impl ProcessingStats {
     /// # 参数
     /// - `successful` - 成功处理的文件数量
     /// - `failed` - 失败的文件数量
-    /// - `error_messages` - 错误信息列表
-    pub fn new(successful: usize, failed: usize, error_messages: Vec<String>) -> Self {
+    /// - `errors` - 失败文件的错误
+    pub fn new(successful: usize, failed: usize, errors: Vec<ProcessFileError>) -> Self {
         Self {
             successful,
             failed,
-            error_messages,
+            error_type_counts: BTreeMap::new(),
+            errors,
+            ..Self::default()
         }
     }
 
-    /// 获取总处理文件数量
+    /// 格式化后的错误信息，每个元素包含文件路径、错误类型和错误描述
+    ///
+    /// 每次调用都会重新格式化；只需要遍历时使用 [`ProcessingStats::formatted_errors`]。
+    pub fn error_messages(&self) -> Vec<String> {
+        self.formatted_errors().collect()
+    }
+
+    /// 按需逐条格式化错误信息
+    ///
+    /// 有省略的错误时（[`ProcessingStats::omitted_errors`]），在保留的最前和最后两部分之间
+    /// 插入一条说明省略数量的信息。
+    pub fn formatted_errors(&self) -> impl Iterator<Item = String> + '_ {
+        let split = match self.omitted_errors {
+            0 => self.errors.len(),
+            _ => self.errors.len() / 2,
+        };
+        let (head, tail) = self.errors.split_at(split);
+        let marker = (self.omitted_errors > 0)
+            .then(|| format!("... 另有 {} 个失败文件未列出（完整列表见失败记录文件）", self.omitted_errors));
+        head.iter().map(format_file_error).chain(marker).chain(tail.iter().map(format_file_error))
+    }
+
+    /// 获取总处理文件数量（成功和失败，不含跳过的文件）
     pub fn total(&self) -> usize {
         self.successful + self.failed
     }
@@ -231,10 +1386,261 @@ impl ProcessingStats {
         }
     }
 
-    /// 检查是否有处理失败的文件
-    pub fn has_failures(&self) -> bool {
-        self.failed > 0
-    }
+    /// 检查是否有处理失败的文件
+    pub fn has_failures(&self) -> bool {
+        self.failed > 0
+    }
+
+    /// 扫描之后消失的文件的显示路径；省略的错误不在其中
+    pub fn vanished_paths(&self) -> impl Iterator<Item = &FileKey> {
+        self.errors.iter().filter(|error| error.vanished).map(|error| &error.file_path)
+    }
+
+    /// 整体实时倍率：音频总时长除以墙钟时间
+    ///
+    /// # 返回值
+    /// - `Some(f64)` - 例如 `120.0` 表示一秒钟分析了两分钟的音频
+    /// - `None` - 音频时长或墙钟时间未知
+    pub fn realtime_factor(&self) -> Option<f64> {
+        let wall = self.wall_seconds.filter(|&wall| wall > 0.0)?;
+        Some(self.audio_seconds? / wall)
+    }
+
+    /// 平均吞吐量（每分钟分析的文件数）
+    ///
+    /// # 返回值
+    /// - `Some(f64)` - 每分钟文件数
+    /// - `None` - 墙钟时间未知或本次没有分析任何文件
+    pub fn files_per_minute(&self) -> Option<f64> {
+        let wall = self.wall_seconds.filter(|&wall| wall > 0.0)?;
+        (self.analyzed_files > 0).then(|| self.analyzed_files as f64 / wall * 60.0)
+    }
+
+    /// 按合理范围重新标记可疑测量值 (Flag Suspicious)
+    ///
+    /// [`analyze_results`] 使用默认范围；需要其他范围（`--lra-range`）时用同一批成功结果再调用一次。
+    ///
+    /// # 参数
+    /// - `measurements` - 成功结果，即 [`analyze_results`] 返回的第二项
+    /// - `range` - 合理范围；超出范围的值只标记，不从结果中去掉
+    ///
+    /// # 示例
+    /// ```
+    /// use lra_calculator_rust::precision::LraRange;
+    /// use lra_calculator_rust::processor::{analyze_results, FileMeasurement, FileOutcome};
+    ///
+    /// let outcomes = vec![FileOutcome::Success(FileMeasurement::new("a.flac", 30.0))];
+    /// let (mut stats, measurements) = analyze_results(outcomes);
+    /// assert!(stats.suspicious.is_empty());
+    /// stats.flag_suspicious(&measurements, LraRange { min: 0.0, max: 25.0 });
+    /// assert_eq!(stats.suspicious.len(), 1);
+    /// ```
+    pub fn flag_suspicious(&mut self, measurements: &[FileMeasurement], range: LraRange) {
+        self.suspicious = measurements
+            .iter()
+            .filter(|measurement| !range.contains(measurement.lra))
+            .map(|measurement| SuspiciousMeasurement { path: measurement.path.clone(), lra: measurement.lra })
+            .collect();
+        self.sanity_range = range;
+    }
+
+    /// 分析成功但 FFmpeg 输出了警告的文件数量
+    pub fn files_with_warnings(&self) -> usize {
+        self.ffmpeg_warnings.len()
+    }
+
+    /// 平均每个文件的分析时间（秒）；本次没有分析任何文件时为 `None`
+    pub fn mean_analysis_seconds(&self) -> Option<f64> {
+        (self.analyzed_files > 0).then(|| self.analysis_seconds / self.analyzed_files as f64)
+    }
+
+    /// 将统计信息序列化为格式化的 JSON 字符串
+    ///
+    /// 输出包含成功/失败计数、按错误类型的分类统计、格式化的错误信息列表
+    /// (`error_messages`) 和结构化的错误列表 (`errors`)。
+    ///
+    /// # 返回值
+    /// - `Ok(String)` - JSON 字符串
+    /// - `Err(serde_json::Error)` - 序列化失败
+    #[cfg(feature = "serde")]
+    pub fn to_json(&self) -> Result<String, serde_json::Error> {
+        serde_json::to_string_pretty(self)
+    }
+}
+
+/// JSON 中保留格式化的 `error_messages` 字段，兼容读取旧版统计文件的程序；
+/// 反序列化时只读取结构化的 `errors`。
+#[cfg(feature = "serde")]
+impl serde::Serialize for ProcessingStats {
+    fn serialize<S: serde::Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        use serde::ser::SerializeStruct;
+
+        let mut state = serializer.serialize_struct("ProcessingStats", 24)?;
+        state.serialize_field("run_id", &self.run_id)?;
+        state.serialize_field("successful", &self.successful)?;
+        state.serialize_field("failed", &self.failed)?;
+        state.serialize_field("error_type_counts", &self.error_type_counts)?;
+        state.serialize_field("vanished", &self.vanished)?;
+        state.serialize_field("skipped", &self.skipped)?;
+        state.serialize_field("skip_reason_counts", &self.skip_reason_counts)?;
+        state.serialize_field("unprocessed", &self.unprocessed)?;
+        state.serialize_field("error_messages", &self.error_messages())?;
+        state.serialize_field("errors", &self.errors)?;
+        state.serialize_field("omitted_errors", &self.omitted_errors)?;
+        state.serialize_field("analyzed_files", &self.analyzed_files)?;
+        state.serialize_field("audio_seconds", &self.audio_seconds)?;
+        state.serialize_field("analysis_seconds", &self.analysis_seconds)?;
+        state.serialize_field("wall_seconds", &self.wall_seconds)?;
+        state.serialize_field("workers", &self.workers)?;
+        state.serialize_field("suspicious", &self.suspicious)?;
+        state.serialize_field("sanity_range", &self.sanity_range)?;
+        state.serialize_field("path_collisions", &self.path_collisions)?;
+        state.serialize_field("channel_imbalances", &self.channel_imbalances)?;
+        state.serialize_field("clipping_risks", &self.clipping_risks)?;
+        state.serialize_field("files_with_warnings", &self.files_with_warnings())?;
+        state.serialize_field("ffmpeg_warnings", &self.ffmpeg_warnings)?;
+        state.serialize_field("scan", &self.scan)?;
+        state.end()
+    }
+}
+
+/// 格式化单个错误 (Format File Error)
+///
+/// 与 [`ProcessingStats::error_messages`] 和失败记录文件中的每一行相同。
+///
+/// # 示例
+/// ```
+/// use lra_calculator_rust::error::ProcessFileError;
+/// use lra_calculator_rust::processor::format_file_error;
+///
+/// let error = ProcessFileError::ffmpeg_error("a.mp3".to_string(), "无法启动".to_string());
+/// assert_eq!(format_file_error(&error), "文件 'a.mp3' [FFmpeg 执行失败]: 无法启动");
+/// ```
+pub fn format_file_error(error: &ProcessFileError) -> String {
+    format!("文件 '{}' [{}]: {}", error.file_path, error.error_type_description(), error.message)
+}
+
+/// 默认保留的错误数量（最前和最后各这么多条，`--max-error-messages`）
+pub const DEFAULT_RETAINED_ERRORS: usize = 50;
+
+/// LRA 分布统计 (LRA Distribution)
+///
+/// 对一组 LRA 值进行汇总，按动态范围划分为三个区间：
+/// 高（> 15 LU）、中（8-15 LU）、低（< 8 LU）。
+/// 既可用于刚处理完的结果，也可用于已有结果文件（`stats` 子命令）。
+#[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct LraDistribution {
+    /// 条目数量
+    pub count: usize,
+    /// 最小 LRA 值
+    pub min: f64,
+    /// 最大 LRA 值
+    pub max: f64,
+    /// 平均 LRA 值
+    pub mean: f64,
+    /// LRA 中位数
+    pub median: f64,
+    /// LRA 的总体标准差；旧版本导出的统计没有此字段，读取时为 0
+    #[cfg_attr(feature = "serde", serde(default))]
+    pub std_dev: f64,
+    /// LRA > 15 LU 的条目数量
+    pub high_count: usize,
+    /// 8 LU ≤ LRA ≤ 15 LU 的条目数量
+    pub medium_count: usize,
+    /// LRA < 8 LU 的条目数量
+    pub low_count: usize,
+}
+
+impl LraDistribution {
+    /// 高动态范围的下限（不含）
+    pub const HIGH_THRESHOLD: f64 = 15.0;
+    /// 低动态范围的上限（不含）
+    pub const LOW_THRESHOLD: f64 = 8.0;
+
+    /// 根据 LRA 值计算分布统计
+    ///
+    /// # 参数
+    /// - `values` - LRA 值列表
+    ///
+    /// # 返回值
+    /// - `Some(LraDistribution)` - 统计结果
+    /// - `None` - 列表为空
+    pub fn from_values(values: &[f64]) -> Option<Self> {
+        if values.is_empty() {
+            return None;
+        }
+
+        let mut sorted = values.to_vec();
+        sorted.sort_by(f64::total_cmp);
+
+        let count = sorted.len();
+        let middle = count / 2;
+        let median = if count.is_multiple_of(2) {
+            (sorted[middle - 1] + sorted[middle]) / 2.0
+        } else {
+            sorted[middle]
+        };
+
+        let mean = sorted.iter().sum::<f64>() / count as f64;
+        let variance = sorted.iter().map(|v| (v - mean).powi(2)).sum::<f64>() / count as f64;
+
+        Some(Self {
+            count,
+            min: sorted[0],
+            max: sorted[count - 1],
+            mean,
+            median,
+            std_dev: variance.sqrt(),
+            high_count: sorted.iter().filter(|&&v| v > Self::HIGH_THRESHOLD).count(),
+            medium_count: sorted
+                .iter()
+                .filter(|&&v| (Self::LOW_THRESHOLD..=Self::HIGH_THRESHOLD).contains(&v))
+                .count(),
+            low_count: sorted.iter().filter(|&&v| v < Self::LOW_THRESHOLD).count(),
+        })
+    }
+}
+
+/// 显示 LRA 分布统计 (Display LRA Distribution)
+///
+/// # 参数
+/// - `distribution` - LRA 分布统计
+#[cfg(feature = "cli")]
+pub fn display_lra_distribution(distribution: &LraDistribution) {
+    human_println!("{}", format_lra_distribution(distribution, human_color_mode()));
+}
+
+/// 格式化 LRA 分布统计 (Format LRA Distribution)
+///
+/// 三个 LRA 分类按各自的颜色显示，见 [`Style`]。
+///
+/// # 参数
+/// - `distribution` - LRA 分布统计
+/// - `mode` - 颜色模式
+#[cfg(feature = "cli")]
+pub fn format_lra_distribution(distribution: &LraDistribution, mode: ColorMode) -> String {
+    let percent = |n: usize| n as f64 / distribution.count as f64 * 100.0;
+    let (high, low) = (LraDistribution::HIGH_THRESHOLD, LraDistribution::LOW_THRESHOLD);
+    let category = |style: Style, label: String, count: usize| {
+        mode.paint(style, format!("{}: {} ({:.1}%)", label, count, percent(count)))
+    };
+
+    [
+        String::new(),
+        "==================== LRA 分布统计 ====================".to_string(),
+        format!("条目数量: {}", distribution.count),
+        format!("最小值: {:.1} LU", distribution.min),
+        format!("最大值: {:.1} LU", distribution.max),
+        format!("平均值: {:.1} LU", distribution.mean),
+        format!("中位数: {:.1} LU", distribution.median),
+        format!("标准差: {:.1} LU", distribution.std_dev),
+        category(Style::LraHigh, format!("LRA > {} LU (动态范围丰富)", high), distribution.high_count),
+        category(Style::LraMedium, format!("LRA {}-{} LU (适中动态范围)", low, high), distribution.medium_count),
+        category(Style::LraLow, format!("LRA < {} LU (动态范围较小)", low), distribution.low_count),
+        "=====================================================".to_string(),
+    ]
+    .join("\n")
 }
 
 /// 分析处理结果并生成统计信息 (Analyze Processing Results and Generate Statistics)
@@ -247,65 +1653,136 @@ impl ProcessingStats {
 /// ### 结果分类
 /// - **成功结果**: 提取文件路径和 LRA 值，用于后续的文件输出
 /// - **失败结果**: 收集错误信息，用于用户反馈和问题诊断
+/// - **跳过的文件**: 只按原因计数，不计入失败
 ///
 /// ### 统计计算
-/// - 统计成功和失败的文件数量
-/// - 格式化错误信息，包含文件路径和错误类型
+/// - 统计成功、失败和跳过的文件数量
+/// - 累计本次分析的文件的音频时长和分析时间（缓存命中的文件没有计时，不计入），
+///   分析时间同时按执行的工作线程累计
+/// - 按默认合理范围标记可疑测量值；其他范围见 [`ProcessingStats::flag_suspicious`]
+/// - 保留原始的错误值，错误信息在显示时才格式化
 /// - 生成便于后续处理的数据结构
 ///
 /// ### 内存优化
-/// - 使用 `Vec::with_capacity` 预分配内存（如果知道大小）
-/// - 避免不必要的字符串克隆
+/// - 按结果数量预分配成功结果的向量
+/// - 结果直接移动到输出中，不克隆路径字符串
 /// - 使用迭代器进行高效的数据转换
 ///
 /// # 参数
-/// - `results` - 并行处理的结果向量，每个元素为成功或失败的结果
+/// - `results` - 并行处理的结果向量，元素为 [`FileOutcome`]，
+///   或旧的 `Result<FileMeasurement, ProcessFileError>`（只有成功和失败）
 ///
 /// # 返回值
 /// 返回一个元组：
 /// - `ProcessingStats` - 包含统计信息和错误详情的结构体
-/// - `Vec<(String, f64)>` - 成功处理的文件列表，包含路径和 LRA 值
+/// - `Vec<FileMeasurement>` - 成功处理的文件列表，包含路径、LRA 值和综合响度
 ///
 /// # 性能特性
 /// - 时间复杂度: O(n)，其中 n 是结果数量
 /// - 空间复杂度: O(n)，需要存储所有成功结果和错误信息
-pub fn analyze_results(
-    results: Vec<Result<(String, f64), ProcessFileError>>,
-) -> (ProcessingStats, Vec<(String, f64)>) {
-    // 预分配向量容量以提高性能
-    let total_count = results.len();
-    let mut successful_results = Vec::with_capacity(total_count);
-    let mut error_messages = Vec::new();
-    let mut successful_count = 0;
-    let mut failed_count = 0;
-
-    // 使用迭代器处理结果，避免索引访问
-    for result in results {
-        match result {
-            Ok((path_str, lra)) => {
-                successful_results.push((path_str, lra));
-                successful_count += 1;
+pub fn analyze_results<O>(results: Vec<O>) -> (ProcessingStats, Vec<FileMeasurement>)
+where
+    O: Into<FileOutcome>,
+{
+    analyze_results_streaming(results, &FailureStream::new(usize::MAX))
+}
+
+/// 分析处理结果，失败交给失败流 (Analyze Results Streaming)
+///
+/// 与 [`analyze_results`] 相同，但结果中的失败交给 `failures`（见 [`FailureStream`]），失败的统计取自
+/// 失败流的汇总：[`ProcessingStats::errors`] 只保留最前和最后各 [`FailureStream::retained_per_side`] 条，
+/// 其余的只计入 [`ProcessingStats::omitted_errors`]。并行处理时设置了
+/// [`RunOptions::failures`] 的话，失败在发生时就已经交给了同一个失败流，结果中不再包含失败，
+/// 完整的错误列表从不保存在内存中。结果逐个处理，不会先收集成向量。
+///
+/// # 参数
+/// - `results` - 并行处理的结果
+/// - `failures` - 失败流；汇总在这里被取出
+///
+/// # 示例
+/// ```
+/// use lra_calculator_rust::error::ProcessFileError;
+/// use lra_calculator_rust::failures::FailureStream;
+/// use lra_calculator_rust::processor::{analyze_results_streaming, FileOutcome};
+///
+/// let results = (0..10)
+///     .map(|index| FileOutcome::Failed(ProcessFileError::ffmpeg_error(format!("{}.flac", index), "无法启动".to_string())));
+/// let failures = FailureStream::new(2);
+/// let (stats, _) = analyze_results_streaming(results, &failures);
+/// assert_eq!((stats.failed, stats.errors.len(), stats.omitted_errors), (10, 4, 6));
+/// ```
+pub fn analyze_results_streaming<I>(results: I, failures: &FailureStream) -> (ProcessingStats, Vec<FileMeasurement>)
+where
+    I: IntoIterator,
+    I::Item: Into<FileOutcome>,
+{
+    let results = results.into_iter();
+    let mut successful_results = Vec::with_capacity(results.size_hint().0);
+    let mut skip_reason_counts = BTreeMap::new();
+    let mut analyzed_files = 0;
+    let mut audio_seconds: Option<f64> = None;
+    let mut analysis_seconds = 0.0;
+    let mut workers: BTreeMap<usize, WorkerUtilization> = BTreeMap::new();
+
+    // 成功结果和错误都直接移动，不克隆也不格式化
+    for outcome in results {
+        match outcome.into() {
+            FileOutcome::Success(measurement) => {
+                if let Some(analysis_time) = measurement.analysis_time {
+                    analyzed_files += 1;
+                    analysis_seconds += analysis_time.as_secs_f64();
+                    if let Some(duration) = measurement.audio_duration {
+                        *audio_seconds.get_or_insert(0.0) += duration.as_secs_f64();
+                    }
+                    if let Some(index) = measurement.worker {
+                        let worker = workers.entry(index).or_insert(WorkerUtilization { index, files: 0, busy_seconds: 0.0 });
+                        worker.files += 1;
+                        worker.busy_seconds += analysis_time.as_secs_f64();
+                    }
+                }
+                successful_results.push(measurement);
             }
-            Err(error) => {
-                // 格式化错误信息，包含错误类型和详细描述
-                let formatted_error = format!(
-                    "文件 '{}' [{}]: {}",
-                    error.file_path,
-                    error.error_type_description(),
-                    error.message
-                );
-                error_messages.push(formatted_error);
-                failed_count += 1;
+            FileOutcome::Failed(error) => failures.record(error),
+            FileOutcome::Skipped { reason, .. } => {
+                *skip_reason_counts.entry(reason).or_insert(0) += 1;
             }
         }
     }
+    let skipped = skip_reason_counts.values().sum();
+    let FailureSummary { failed, error_type_counts, vanished, errors, omitted_errors } = failures.take_summary();
+
+    let ffmpeg_warnings = successful_results
+        .iter()
+        .filter(|measurement| !measurement.warnings.is_empty())
+        .map(|measurement| FileWarnings { path: measurement.path.clone(), warnings: measurement.warnings.clone() })
+        .collect();
 
     // 创建统计信息结构体
-    let stats = ProcessingStats {
-        successful: successful_count,
-        failed: failed_count,
-        error_messages,
+    let mut stats = ProcessingStats {
+        successful: successful_results.len(),
+        failed,
+        error_type_counts,
+        vanished,
+        errors,
+        omitted_errors,
+        analyzed_files,
+        audio_seconds,
+        analysis_seconds,
+        wall_seconds: None,
+        workers: workers.into_values().collect(),
+        suspicious: Vec::new(),
+        sanity_range: LraRange::default(),
+        path_collisions: Vec::new(),
+        channel_imbalances: Vec::new(),
+        clipping_risks: Vec::new(),
+        run_id: None,
+        skipped,
+        skip_reason_counts,
+        unprocessed: 0,
+        ffmpeg_warnings,
+        scan: None,
     };
+    stats.flag_suspicious(&successful_results, LraRange::default());
 
     (stats, successful_results)
 }
@@ -345,37 +1822,359 @@ pub fn analyze_results(
 /// 失败文件详情:
 ///   - 文件 'corrupted.mp3' [FFmpeg 执行失败]: 音频文件损坏
 ///   - 文件 'invalid.wav' [LRA 值解析失败]: 无法解析 LRA 值
+///
+/// 音频总时长: 10 小时 24 分 13 秒
+/// 总用时: 4 分 05 秒
+/// 实时倍率: 152.8×
+/// 平均吞吐量: 36.2 个文件/分钟（平均每个文件分析 11.7 秒）
 /// =====================================================
 /// ```
+///
+/// 耗时各行只在对应的时长已知时显示：没有设置墙钟时间时都不显示，
+/// FFmpeg 没有输出音频时长时不显示音频总时长和实时倍率。
+#[cfg(feature = "cli")]
 pub fn display_processing_stats(stats: &ProcessingStats) {
-    println!("\n==================== 处理结果统计 ====================");
+    human_println!("{}", format_processing_stats(stats, human_color_mode()));
+}
+
+/// 格式化处理结果统计 (Format Processing Statistics)
+///
+/// 与 [`display_processing_stats`] 的输出相同：成功行为绿色，失败行和错误详情为红色。
+///
+/// # 参数
+/// - `stats` - 处理统计信息
+/// - `mode` - 颜色模式
+#[cfg(feature = "cli")]
+pub fn format_processing_stats(stats: &ProcessingStats, mode: ColorMode) -> String {
+    let mut lines = vec![String::new(), "==================== 处理结果统计 ====================".to_string()];
 
-    let total = stats.successful + stats.failed;
-    println!("总文件数: {}", total);
+    let total = stats.successful + stats.failed + stats.unprocessed;
+    lines.push(format!("总文件数: {}", total));
 
     if total > 0 {
         let success_rate = (stats.successful as f64 / total as f64) * 100.0;
-        println!("成功处理: {} 个文件 ({:.1}%)", stats.successful, success_rate);
+        lines.push(mode.paint(
+            Style::Success,
+            format!("成功处理: {} 个文件 ({:.1}%)", stats.successful, success_rate),
+        ));
 
         if stats.failed > 0 {
             let failure_rate = (stats.failed as f64 / total as f64) * 100.0;
-            println!("处理失败: {} 个文件 ({:.1}%)", stats.failed, failure_rate);
+            lines.push(mode.paint(
+                Style::Failure,
+                format!("处理失败: {} 个文件 ({:.1}%)", stats.failed, failure_rate),
+            ));
+            if stats.vanished > 0 {
+                lines.push(mode.paint(
+                    Style::Warning,
+                    human_format!("🫥 其中 {} 个文件在扫描之后被移动或删除 (已消失)", stats.vanished),
+                ));
+            }
+        }
+        if stats.unprocessed > 0 {
+            let unprocessed_rate = (stats.unprocessed as f64 / total as f64) * 100.0;
+            lines.push(mode.paint(
+                Style::Warning,
+                human_format!("⏳ 未处理: {} 个文件 ({:.1}%)，运行提前停止", stats.unprocessed, unprocessed_rate),
+            ));
+        }
 
-            println!("\n失败文件详情:");
-            display_error_details(&stats.error_messages);
-        } else {
-            println!("🎉 所有文件都已成功处理！");
+        if stats.failed > 0 {
+            lines.push(String::new());
+            lines.push("失败文件详情:".to_string());
+            lines.extend(format_error_details(stats, mode));
+        } else if stats.unprocessed == 0 {
+            lines.push(mode.paint(Style::Success, human_format!("🎉 所有文件都已成功处理！")));
         }
+    } else if stats.skipped == 0 {
+        lines.push(mode.paint(Style::Warning, human_format!("⚠️  没有找到要处理的文件。")));
+    }
+    lines.extend(format_skipped(stats, mode));
+    lines.extend(format_suspicious(stats, mode));
+    lines.extend(format_ffmpeg_warnings(stats, mode));
+    lines.extend(format_path_collisions(stats, mode));
+    lines.extend(format_channel_imbalances(stats, mode));
+    lines.extend(format_clipping_risks(stats, mode));
+
+    let timing = format_timing(stats);
+    if !timing.is_empty() {
+        lines.push(String::new());
+        lines.extend(timing);
+    }
+
+    lines.push("=====================================================".to_string());
+    lines.join("\n")
+}
+
+/// 格式化跳过的文件 (Format Skipped Files)
+///
+/// 跳过的文件与失败分开列出，按原因显示数量。
+///
+/// # 返回值
+/// - 要显示的各行（以空行开头）；没有跳过的文件时返回空列表
+#[cfg(feature = "cli")]
+fn format_skipped(stats: &ProcessingStats, mode: ColorMode) -> Vec<String> {
+    if stats.skipped == 0 {
+        return Vec::new();
+    }
+    let mut lines = vec![
+        String::new(),
+        mode.paint(Style::Warning, human_format!("⏭️  跳过: {} 个文件 (不计入失败)", stats.skipped)),
+    ];
+    for (reason, count) in &stats.skip_reason_counts {
+        let hint = match reason {
+            SkipReason::CloudPlaceholder => "，内容不在本地 (--hydrate 允许读取时下载)",
+            SkipReason::ICloudStub => "，请先在访达中下载",
+            SkipReason::Cancelled | SkipReason::Outage | SkipReason::Aborted => "",
+        };
+        lines.push(format!("  - {}: {} 个{}", reason.label(), count, hint));
+    }
+    lines
+}
+
+/// 可疑测量值最多列出的条数
+#[cfg(feature = "cli")]
+const MAX_SUSPICIOUS_SHOWN: usize = 10;
+
+/// 格式化可疑测量值 (Format Suspicious Measurements)
+///
+/// # 返回值
+/// - 要显示的各行（以空行开头）；没有可疑值时返回空列表
+#[cfg(feature = "cli")]
+fn format_suspicious(stats: &ProcessingStats, mode: ColorMode) -> Vec<String> {
+    if stats.suspicious.is_empty() {
+        return Vec::new();
+    }
+    let mut lines = vec![
+        String::new(),
+        mode.paint(
+            Style::Warning,
+            human_format!(
+                "⚠️  可疑测量值: {} 个文件的 LRA 超出合理范围 {}（已照常写入结果）",
+                stats.suspicious.len(),
+                stats.sanity_range
+            ),
+        ),
+    ];
+    for measurement in stats.suspicious.iter().take(MAX_SUSPICIOUS_SHOWN) {
+        lines.push(format!("  - {}: {} LU", measurement.path, LraValue(measurement.lra)));
+    }
+    if stats.suspicious.len() > MAX_SUSPICIOUS_SHOWN {
+        lines.push(format!("  ... 另有 {} 个", stats.suspicious.len() - MAX_SUSPICIOUS_SHOWN));
+    }
+    lines
+}
+
+/// 有 FFmpeg 警告的文件最多列出的条数
+#[cfg(feature = "cli")]
+const MAX_WARNED_FILES_SHOWN: usize = 10;
+
+/// 格式化 FFmpeg 警告 (Format FFmpeg Warnings)
+///
+/// 每个文件只显示第一条警告，完整列表见 `--stats-json` 或 `--debug-dump`。
+///
+/// # 返回值
+/// - 要显示的各行（以空行开头）；没有警告时返回空列表
+#[cfg(feature = "cli")]
+fn format_ffmpeg_warnings(stats: &ProcessingStats, mode: ColorMode) -> Vec<String> {
+    if stats.ffmpeg_warnings.is_empty() {
+        return Vec::new();
+    }
+    let mut lines = vec![
+        String::new(),
+        mode.paint(
+            Style::Warning,
+            human_format!("⚠️  FFmpeg 警告: {} 个文件（已照常写入结果，可能需要重新抓取）", stats.files_with_warnings()),
+        ),
+    ];
+    for file in stats.ffmpeg_warnings.iter().take(MAX_WARNED_FILES_SHOWN) {
+        let more = match file.warnings.len() {
+            1 => String::new(),
+            count => format!("（共 {} 种）", count),
+        };
+        lines.push(format!("  - {}: {}{}", file.path, file.warnings[0], more));
+    }
+    if stats.ffmpeg_warnings.len() > MAX_WARNED_FILES_SHOWN {
+        lines.push(format!("  ... 另有 {} 个", stats.ffmpeg_warnings.len() - MAX_WARNED_FILES_SHOWN));
+    }
+    lines
+}
+
+/// 显示路径冲突最多列出的条数
+#[cfg(feature = "cli")]
+const MAX_COLLISIONS_SHOWN: usize = 10;
+
+/// 格式化显示路径冲突 (Format Path Collisions)
+///
+/// # 返回值
+/// - 要显示的各行（以空行开头）；没有冲突时返回空列表
+#[cfg(feature = "cli")]
+fn format_path_collisions(stats: &ProcessingStats, mode: ColorMode) -> Vec<String> {
+    if stats.path_collisions.is_empty() {
+        return Vec::new();
+    }
+    let mut lines = vec![
+        String::new(),
+        mode.paint(
+            Style::Warning,
+            human_format!(
+                "⚠️  显示路径冲突: {} 个文件与其他文件的显示路径相同，已在结果中改名",
+                stats.path_collisions.len()
+            ),
+        ),
+    ];
+    for collision in stats.path_collisions.iter().take(MAX_COLLISIONS_SHOWN) {
+        lines.push(format!("  - {}", collision));
+    }
+    if stats.path_collisions.len() > MAX_COLLISIONS_SHOWN {
+        lines.push(format!("  ... 另有 {} 个", stats.path_collisions.len() - MAX_COLLISIONS_SHOWN));
+    }
+    lines
+}
+
+/// 声道不平衡的文件最多列出的条数
+#[cfg(feature = "cli")]
+const MAX_IMBALANCES_SHOWN: usize = 10;
+
+/// 格式化声道不平衡的文件 (Format Channel Imbalances)
+///
+/// # 返回值
+/// - 要显示的各行（以空行开头）；没有这样的文件时返回空列表
+#[cfg(feature = "cli")]
+fn format_channel_imbalances(stats: &ProcessingStats, mode: ColorMode) -> Vec<String> {
+    if stats.channel_imbalances.is_empty() {
+        return Vec::new();
+    }
+    let mut lines = vec![
+        String::new(),
+        mode.paint(
+            Style::Warning,
+            human_format!("⚠️  声道不平衡: {} 个文件的声道间综合响度差异超过阈值", stats.channel_imbalances.len()),
+        ),
+    ];
+    for imbalance in stats.channel_imbalances.iter().take(MAX_IMBALANCES_SHOWN) {
+        lines.push(format!("  - {}", imbalance));
+    }
+    if stats.channel_imbalances.len() > MAX_IMBALANCES_SHOWN {
+        lines.push(format!("  ... 另有 {} 个", stats.channel_imbalances.len() - MAX_IMBALANCES_SHOWN));
+    }
+    lines
+}
+
+/// 有削波风险的文件最多列出的条数
+#[cfg(feature = "cli")]
+const MAX_CLIPPING_RISKS_SHOWN: usize = 10;
+
+/// 格式化削波风险 (Format Clipping Risks)
+///
+/// # 返回值
+/// - 要显示的各行（以空行开头）；没有这样的文件时返回空列表
+#[cfg(feature = "cli")]
+fn format_clipping_risks(stats: &ProcessingStats, mode: ColorMode) -> Vec<String> {
+    if stats.clipping_risks.is_empty() {
+        return Vec::new();
+    }
+    let mut lines = vec![
+        String::new(),
+        mode.paint(
+            Style::Warning,
+            human_format!("⚠️  削波风险: {} 个文件的真峰值超过上限，有损编码后可能削波", stats.clipping_risks.len()),
+        ),
+    ];
+    for risk in stats.clipping_risks.iter().take(MAX_CLIPPING_RISKS_SHOWN) {
+        lines.push(format!("  - {}: {:+.1} dBTP", risk.path, risk.true_peak_dbtp));
+    }
+    if stats.clipping_risks.len() > MAX_CLIPPING_RISKS_SHOWN {
+        lines.push(format!("  ... 另有 {} 个", stats.clipping_risks.len() - MAX_CLIPPING_RISKS_SHOWN));
+    }
+    lines
+}
+
+/// 格式化耗时统计 (Format Timing)
+///
+/// # 返回值
+/// - 要显示的各行；时长未知的行被省略，墙钟时间未知时返回空列表
+#[cfg(feature = "cli")]
+fn format_timing(stats: &ProcessingStats) -> Vec<String> {
+    let Some(wall_seconds) = stats.wall_seconds else {
+        return Vec::new();
+    };
+    let mut lines = Vec::new();
+    if let Some(audio_seconds) = stats.audio_seconds {
+        lines.push(format!("音频总时长: {}", format_seconds(audio_seconds)));
+    }
+    lines.push(format!("总用时: {}", format_seconds(wall_seconds)));
+    if let Some(factor) = stats.realtime_factor() {
+        lines.push(human_format!("实时倍率: {:.1}×", factor));
+    }
+    if let (Some(per_minute), Some(mean)) = (stats.files_per_minute(), stats.mean_analysis_seconds()) {
+        lines.push(format!("平均吞吐量: {:.1} 个文件/分钟（平均每个文件分析 {:.1} 秒）", per_minute, mean));
+    }
+    lines
+}
+
+/// 把秒数格式化为 `1 小时 02 分 03 秒`、`4 分 05 秒` 或 `12.3 秒`
+#[cfg(feature = "cli")]
+fn format_seconds(seconds: f64) -> String {
+    if seconds < 60.0 {
+        return format!("{:.1} 秒", seconds);
+    }
+    let total = seconds.round() as u64;
+    let (hours, minutes, seconds) = (total / 3600, total / 60 % 60, total % 60);
+    if hours > 0 {
+        format!("{} 小时 {:02} 分 {:02} 秒", hours, minutes, seconds)
     } else {
-        println!("⚠️  没有找到要处理的文件。");
+        format!("{} 分 {:02} 秒", minutes, seconds)
+    }
+}
+
+/// 显示各工作线程的利用率 (Display Worker Utilization)
+///
+/// 用于 `--verbose`：利用率明显低于 100% 的线程大部分时间在等待（读取文件、排队），
+/// 说明增加线程数不会更快。
+///
+/// # 参数
+/// - `stats` - 设置了墙钟时间的处理统计信息
+#[cfg(feature = "cli")]
+pub fn display_worker_utilization(stats: &ProcessingStats) {
+    for line in format_worker_utilization(stats) {
+        human_println!("{}", line);
     }
+}
 
-    println!("=====================================================");
+/// 格式化各工作线程的利用率 (Format Worker Utilization)
+///
+/// # 返回值
+/// - 标题和表格的各行；没有线程数据或墙钟时间未知时返回空列表
+#[cfg(feature = "cli")]
+pub fn format_worker_utilization(stats: &ProcessingStats) -> Vec<String> {
+    let Some(wall_seconds) = stats.wall_seconds.filter(|_| !stats.workers.is_empty()) else {
+        return Vec::new();
+    };
+    let rows: Vec<Vec<String>> = stats
+        .workers
+        .iter()
+        .map(|worker| {
+            vec![
+                worker.index.to_string(),
+                worker.files.to_string(),
+                format!("{:.1}", worker.busy_seconds),
+                worker.utilization_percent(wall_seconds).map_or_else(|| "-".to_string(), |percent| format!("{:.1}%", percent)),
+            ]
+        })
+        .collect();
+    let mut lines = vec![human_format!("🧵 工作线程利用率（总用时 {}）:", format_seconds(wall_seconds))];
+    lines.extend(render_table(
+        &["线程", "文件数", "忙碌 (秒)", "利用率"],
+        &[Align::Right, Align::Right, Align::Right, Align::Right],
+        &rows,
+    ));
+    lines
 }
 
-/// 显示错误详情 (Display Error Details)
+/// 格式化错误详情 (Format Error Details)
 ///
-/// 这个辅助函数负责格式化和显示错误信息，支持大量错误的合理处理。
+/// 这个辅助函数负责格式化错误信息，支持大量错误的合理处理。
 ///
 /// ## 显示策略
 /// - 如果错误数量较少（≤10），显示所有错误
@@ -383,46 +2182,80 @@ pub fn display_processing_stats(stats: &ProcessingStats) {
 /// - 按错误类型进行分组（未来扩展）
 ///
 /// # 参数
-/// - `error_messages` - 错误信息列表的引用
-fn display_error_details(error_messages: &[String]) {
+/// - `stats` - 包含失败文件错误的统计信息
+/// - `mode` - 颜色模式
+///
+/// # 返回值
+/// - 要显示的各行
+#[cfg(feature = "cli")]
+fn format_error_details(stats: &ProcessingStats, mode: ColorMode) -> Vec<String> {
     const MAX_DISPLAY_ERRORS: usize = 10;
 
-    let display_count = error_messages.len().min(MAX_DISPLAY_ERRORS);
-
-    for (index, error_msg) in error_messages.iter().take(display_count).enumerate() {
-        println!("  {}. {}", index + 1, error_msg);
-    }
-
-    if error_messages.len() > MAX_DISPLAY_ERRORS {
-        let remaining = error_messages.len() - MAX_DISPLAY_ERRORS;
-        println!("  ... 还有 {} 个错误未显示", remaining);
-        println!("  💡 提示: 检查日志文件获取完整错误列表");
+    // 只格式化需要显示的前几条错误
+    let mut lines: Vec<String> = stats
+        .errors
+        .iter()
+        .take(MAX_DISPLAY_ERRORS)
+        .enumerate()
+        .map(|(index, error)| mode.paint(Style::Failure, format!("  {}. {}", index + 1, format_file_error(error))))
+        .collect();
+
+    // 省略的错误（见 ProcessingStats::omitted_errors）也计入未显示的数量
+    let remaining = stats.errors.len() + stats.omitted_errors - lines.len();
+    if remaining > 0 {
+        lines.push(format!("  ... 还有 {} 个错误未显示", remaining));
+        lines.push(human_format!("  💡 提示: 检查日志文件获取完整错误列表"));
     }
+    lines
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
     use crate::error::{ProcessFileError, FileErrorType};
+    use crate::events::BufferSink;
+    use std::sync::Arc;
 
     /// 测试 ProcessingStats 结构体的基本功能
     #[test]
     fn test_processing_stats_creation() {
-        let error_messages = vec![
-            "错误1".to_string(),
-            "错误2".to_string(),
+        let errors = vec![
+            ProcessFileError::ffmpeg_error("a.mp3".to_string(), "错误1".to_string()),
+            ProcessFileError::new("b.wav".to_string(), "错误2".to_string(), FileErrorType::Other),
         ];
 
-        let stats = ProcessingStats {
-            successful: 10,
-            failed: 2,
-            error_messages: error_messages.clone(),
-        };
+        let stats = ProcessingStats::new(10, 2, errors);
 
         assert_eq!(stats.successful, 10);
         assert_eq!(stats.failed, 2);
-        assert_eq!(stats.error_messages.len(), 2);
-        assert_eq!(stats.error_messages, error_messages);
+        assert_eq!(stats.errors.len(), 2);
+        assert_eq!(
+            stats.error_messages(),
+            vec![
+                "文件 'a.mp3' [FFmpeg 执行失败]: 错误1".to_string(),
+                "文件 'b.wav' [其他错误]: 错误2".to_string(),
+            ]
+        );
+    }
+
+    /// 测试 LRA 分布统计
+    #[test]
+    fn test_lra_distribution() {
+        assert!(LraDistribution::from_values(&[]).is_none());
+
+        let distribution = LraDistribution::from_values(&[20.0, 3.0, 8.0, 15.0]).unwrap();
+        assert_eq!(distribution.count, 4);
+        assert_eq!(distribution.min, 3.0);
+        assert_eq!(distribution.max, 20.0);
+        assert!((distribution.mean - 11.5).abs() < 1e-9);
+        assert!((distribution.median - 11.5).abs() < 1e-9);
+        assert!((distribution.std_dev - 6.5).abs() < 1e-9);
+        assert_eq!(distribution.high_count, 1);
+        assert_eq!(distribution.medium_count, 2);
+        assert_eq!(distribution.low_count, 1);
+
+        let odd = LraDistribution::from_values(&[5.0, 1.0, 9.0]).unwrap();
+        assert_eq!(odd.median, 5.0);
     }
 
     /// 测试 ProcessingStats 的便利方法
@@ -431,7 +2264,9 @@ mod tests {
         let stats = ProcessingStats::new(
             15,
             3,
-            vec!["错误1".to_string(), "错误2".to_string(), "错误3".to_string()]
+            (1..=3)
+                .map(|i| ProcessFileError::new(format!("file{}.mp3", i), format!("错误{}", i), FileErrorType::Other))
+                .collect()
         );
 
         // 测试总数计算
@@ -461,18 +2296,18 @@ mod tests {
     fn test_analyze_results() {
         // 创建测试数据
         let test_results = vec![
-            Ok(("file1.mp3".to_string(), 12.5)),
-            Ok(("file2.wav".to_string(), 8.3)),
+            Ok(FileMeasurement::new("file1.mp3", 12.5)),
+            Ok(FileMeasurement::new("file2.wav", 8.3)),
             Err(ProcessFileError::ffmpeg_error(
                 "file3.flac".to_string(),
                 "FFmpeg 执行失败".to_string()
             )),
-            Ok(("file4.m4a".to_string(), 15.7)),
+            Ok(FileMeasurement::new("file4.m4a", 15.7)),
             Err(ProcessFileError::lra_parsing_error(
                 "file5.mp3".to_string(),
                 "LRA 解析失败".to_string()
             )),
-            Ok(("file6.ogg".to_string(), 9.1)),
+            Ok(FileMeasurement::new("file6.ogg", 9.1)),
         ];
 
         // 执行分析
@@ -481,31 +2316,95 @@ mod tests {
         // 验证统计信息
         assert_eq!(stats.successful, 4);
         assert_eq!(stats.failed, 2);
-        assert_eq!(stats.error_messages.len(), 2);
+        assert_eq!(stats.error_messages().len(), 2);
 
         // 验证成功结果
         assert_eq!(successful_results.len(), 4);
-        assert_eq!(successful_results[0], ("file1.mp3".to_string(), 12.5));
-        assert_eq!(successful_results[1], ("file2.wav".to_string(), 8.3));
-        assert_eq!(successful_results[2], ("file4.m4a".to_string(), 15.7));
-        assert_eq!(successful_results[3], ("file6.ogg".to_string(), 9.1));
+        assert_eq!(successful_results[0], FileMeasurement::new("file1.mp3", 12.5));
+        assert_eq!(successful_results[1], FileMeasurement::new("file2.wav", 8.3));
+        assert_eq!(successful_results[2], FileMeasurement::new("file4.m4a", 15.7));
+        assert_eq!(successful_results[3], FileMeasurement::new("file6.ogg", 9.1));
 
         // 验证错误信息格式
-        assert!(stats.error_messages[0].contains("file3.flac"));
-        assert!(stats.error_messages[0].contains("FFmpeg 执行失败"));
-        assert!(stats.error_messages[1].contains("file5.mp3"));
-        assert!(stats.error_messages[1].contains("LRA 解析失败"));
+        assert!(stats.error_messages()[0].contains("file3.flac"));
+        assert!(stats.error_messages()[0].contains("FFmpeg 执行失败"));
+        assert!(stats.error_messages()[1].contains("file5.mp3"));
+        assert!(stats.error_messages()[1].contains("LRA 解析失败"));
+
+        // 验证按错误类型的统计
+        assert_eq!(stats.error_type_counts.get(&FileErrorType::FfmpegExecution), Some(&1));
+        assert_eq!(stats.error_type_counts.get(&FileErrorType::LraParsingFailed), Some(&1));
+        assert_eq!(stats.error_type_counts.get(&FileErrorType::Other), None);
+        assert!(stats.suspicious.is_empty());
+    }
+
+    /// 测试超出合理范围的值被标记为可疑，但仍保留在成功结果中
+    #[cfg(feature = "cli")]
+    #[test]
+    fn test_suspicious_measurements() {
+        let (stats, successful_results) = analyze_results(vec![
+            Ok(FileMeasurement::new("silence.wav", 250.0)),
+            Ok(FileMeasurement::new("normal.flac", 40.0)),
+        ]);
+        assert_eq!(successful_results.len(), 2);
+        assert_eq!(stats.suspicious, vec![SuspiciousMeasurement { path: "silence.wav".into(), lra: 250.0 }]);
+
+        let formatted = format_processing_stats(&stats, ColorMode::Plain);
+        assert!(formatted.contains("⚠️  可疑测量值: 1 个文件的 LRA 超出合理范围 0–40 LU（已照常写入结果）\n  - silence.wav: 250.0 LU"));
+
+        // 按其他范围重新标记时替换原来的结果，显示使用的范围
+        let mut narrow = stats.clone();
+        narrow.flag_suspicious(&successful_results, LraRange { min: 1.0, max: 25.5 });
+        assert_eq!(narrow.suspicious.len(), 2);
+        assert_eq!(narrow.sanity_range, LraRange { min: 1.0, max: 25.5 });
+        assert!(format_processing_stats(&narrow, ColorMode::Plain).contains("2 个文件的 LRA 超出合理范围 1–25.5 LU"));
+
+        let many = ProcessingStats {
+            suspicious: (0..12).map(|i| SuspiciousMeasurement { path: format!("{}.wav", i).into(), lra: 99.0 }).collect(),
+            ..ProcessingStats::default()
+        };
+        let lines = format_suspicious(&many, ColorMode::Plain);
+        assert_eq!(lines.len(), 2 + MAX_SUSPICIOUS_SHOWN + 1);
+        assert_eq!(lines.last().unwrap(), "  ... 另有 2 个");
+    }
+
+    /// 测试有 FFmpeg 警告的成功结果计入统计，并在统计摘要中列出
+    #[cfg(feature = "cli")]
+    #[test]
+    fn test_ffmpeg_warnings_in_stats() {
+        let backstep = "[mp3float] invalid new backstep -1（3 次）".to_string();
+        let estimate = "[mp3] Estimating duration from bitrate, this may be inaccurate".to_string();
+        let (stats, successful_results) = analyze_results(vec![
+            Ok(FileMeasurement::new("rip.mp3", 6.0).with_warnings(vec![backstep.clone(), estimate.clone()])),
+            Ok(FileMeasurement::new("clean.flac", 8.0)),
+            Ok(FileMeasurement::new("vbr.mp3", 7.0).with_warnings(vec![estimate.clone()])),
+        ]);
+        assert_eq!(successful_results.len(), 3);
+        assert_eq!(stats.files_with_warnings(), 2);
+        assert_eq!(stats.ffmpeg_warnings[0], FileWarnings { path: "rip.mp3".into(), warnings: vec![backstep, estimate] });
+
+        let formatted = format_processing_stats(&stats, ColorMode::Plain);
+        assert!(formatted.contains(
+            "⚠️  FFmpeg 警告: 2 个文件（已照常写入结果，可能需要重新抓取）\n  - rip.mp3: [mp3float] invalid new backstep -1（3 次）（共 2 种）\n  - vbr.mp3: [mp3] Estimating"
+        ));
+
+        #[cfg(feature = "serde")]
+        {
+            let json: serde_json::Value = serde_json::from_str(&stats.to_json().unwrap()).unwrap();
+            assert_eq!(json["files_with_warnings"], 2);
+            assert_eq!(json["ffmpeg_warnings"][1]["path"], "vbr.mp3");
+        }
     }
 
     /// 测试空结果的分析
     #[test]
     fn test_analyze_empty_results() {
-        let empty_results = vec![];
+        let empty_results: Vec<FileOutcome> = vec![];
         let (stats, successful_results) = analyze_results(empty_results);
 
         assert_eq!(stats.successful, 0);
         assert_eq!(stats.failed, 0);
-        assert!(stats.error_messages.is_empty());
+        assert!(stats.error_messages().is_empty());
         assert!(successful_results.is_empty());
     }
 
@@ -513,16 +2412,16 @@ mod tests {
     #[test]
     fn test_analyze_only_successful_results() {
         let success_only_results = vec![
-            Ok(("file1.mp3".to_string(), 12.5)),
-            Ok(("file2.wav".to_string(), 8.3)),
-            Ok(("file3.flac".to_string(), 15.7)),
+            Ok(FileMeasurement::new("file1.mp3", 12.5)),
+            Ok(FileMeasurement::new("file2.wav", 8.3)),
+            Ok(FileMeasurement::new("file3.flac", 15.7)),
         ];
 
         let (stats, successful_results) = analyze_results(success_only_results);
 
         assert_eq!(stats.successful, 3);
         assert_eq!(stats.failed, 0);
-        assert!(stats.error_messages.is_empty());
+        assert!(stats.error_messages().is_empty());
         assert_eq!(successful_results.len(), 3);
     }
 
@@ -544,18 +2443,360 @@ mod tests {
 
         assert_eq!(stats.successful, 0);
         assert_eq!(stats.failed, 2);
-        assert_eq!(stats.error_messages.len(), 2);
+        assert_eq!(stats.error_messages().len(), 2);
         assert!(successful_results.is_empty());
     }
 
+    /// 测试只保留最前和最后的若干个错误，每个错误都交给失败流的处理函数
+    #[test]
+    fn test_analyze_results_streaming_retention() {
+        let results = (0..200)
+            .map(|i| FileOutcome::Failed(ProcessFileError::ffmpeg_error(format!("{}.flac", i), "无法启动".to_string())));
+        let streamed = Arc::new(Mutex::new(Vec::new()));
+        let log = Arc::clone(&streamed);
+        let failures = FailureStream::with_handler(3, move |error| log.lock().unwrap().push(error.file_path.to_string()));
+
+        let (stats, _) = analyze_results_streaming(results, &failures);
+
+        assert_eq!(*streamed.lock().unwrap(), (0..200).map(|i| format!("{}.flac", i)).collect::<Vec<_>>());
+        assert_eq!((stats.failed, stats.omitted_errors), (200, 194));
+        assert_eq!(stats.error_type_counts[&FileErrorType::FfmpegExecution], 200);
+        let paths: Vec<&str> = stats.errors.iter().map(|error| error.file_path.as_str()).collect();
+        assert_eq!(paths, vec!["0.flac", "1.flac", "2.flac", "197.flac", "198.flac", "199.flac"]);
+        let messages = stats.error_messages();
+        assert_eq!(messages.len(), 7);
+        assert_eq!(messages[3], "... 另有 194 个失败文件未列出（完整列表见失败记录文件）");
+        assert!(messages[4].starts_with("文件 '197.flac'"));
+
+        // 不超过上限时全部保留
+        let few = vec![FileOutcome::Failed(ProcessFileError::ffmpeg_error("a.flac".to_string(), "失败".to_string()))];
+        let (stats, _) = analyze_results_streaming(few, &FailureStream::new(3));
+        assert_eq!((stats.errors.len(), stats.omitted_errors), (1, 0));
+    }
+
+    /// 测试全部失败的运行中失败在发生时交给失败流：结果中没有失败，内存中只保留最前和最后的错误
+    #[test]
+    fn test_failures_streamed_during_processing() {
+        let files: Vec<(PathBuf, String)> = (0..500).map(|i| (PathBuf::from(format!("{}.flac", i)), i.to_string())).collect();
+        let streamed = Arc::new(AtomicUsize::new(0));
+        let counter = Arc::clone(&streamed);
+        let failures = FailureStream::with_handler(5, move |_| {
+            counter.fetch_add(1, Ordering::SeqCst);
+        });
+        let controls = StopControls { failures: Some(&failures), ..StopControls::default() };
+
+        let results = process_collected(files, ResultOrder::InputOrder, ScheduleOrder::InputOrder, controls, &BufferSink::new(), |_, path, _| {
+            match path.as_str() {
+                "7" => FileOutcome::Success(FileMeasurement::new(path.clone(), 8.0)),
+                _ => FileOutcome::Failed(ProcessFileError::ffmpeg_error(path.clone(), "无法启动 FFmpeg".to_string())),
+            }
+        });
+
+        assert_eq!(results.len(), 1);
+        assert_eq!(streamed.load(Ordering::SeqCst), 499);
+        assert_eq!(failures.failed(), 499);
+        let (stats, successful_results) = analyze_results_streaming(results, &failures);
+        assert_eq!((stats.successful, stats.failed), (1, 499));
+        assert_eq!((stats.errors.len(), stats.omitted_errors), (10, 489));
+        assert_eq!(successful_results[0].path, "7");
+    }
+
+    /// 测试跳过的文件按原因计数，不计入失败；兼容接口把跳过的文件还原为错误
+    #[test]
+    fn test_analyze_skipped_results() {
+        let outcomes = vec![
+            FileOutcome::Success(FileMeasurement::new("ok.flac", 9.0)),
+            FileOutcome::Failed(ProcessFileError::ffmpeg_error("bad.mp3", "损坏".to_string())),
+            FileOutcome::Skipped { path: "a.flac".into(), reason: SkipReason::CloudPlaceholder },
+            FileOutcome::Skipped { path: ".b.flac.icloud".into(), reason: SkipReason::ICloudStub },
+            FileOutcome::Skipped { path: "c.flac".into(), reason: SkipReason::CloudPlaceholder },
+        ];
+
+        let (stats, successful_results) = analyze_results(outcomes.clone());
+        assert_eq!((stats.successful, stats.failed, stats.skipped), (1, 1, 3));
+        assert_eq!(stats.total(), 2);
+        assert_eq!(
+            stats.skip_reason_counts.into_iter().collect::<Vec<_>>(),
+            [(SkipReason::CloudPlaceholder, 2), (SkipReason::ICloudStub, 1)]
+        );
+        assert!(!stats.error_type_counts.contains_key(&FileErrorType::CloudPlaceholder));
+        assert_eq!(successful_results.len(), 1);
+
+        // 旧的 Result 形式只有成功和失败
+        let flattened: Vec<Result<FileMeasurement, ProcessFileError>> =
+            outcomes.into_iter().map(FileOutcome::into_result).collect();
+        let (legacy, _) = analyze_results(flattened);
+        assert_eq!((legacy.successful, legacy.failed, legacy.skipped), (1, 4, 0));
+        assert_eq!(legacy.error_type_counts[&FileErrorType::CloudPlaceholder], 3);
+    }
+
     /// 测试并行处理空文件列表
     #[test]
     fn test_process_empty_file_list() {
-        let empty_files = vec![];
+        let empty_files: Vec<(PathBuf, String)> = vec![];
         let results = process_files_parallel(empty_files);
         assert!(results.is_empty());
     }
 
+    /// 测试边扫描边处理：扫描线程产出的所有文件都被处理，扫描进度按间隔报告
+    #[test]
+    fn test_process_pipelined() {
+        let file_count = SCAN_PROGRESS_INTERVAL * 2 + 7;
+        let mut files = (0..file_count).map(|i| (PathBuf::from(format!("{}.wav", i)), format!("{}.wav", i)));
+        let sink = BufferSink::new();
+
+        let results = process_pipelined(
+            &mut files,
+            ResultOrder::CompletionOrder,
+            StopControls::default(),
+            &sink,
+            |_, display_path, _| FileOutcome::Success(FileMeasurement::new(display_path.clone(), display_path.len() as f64)),
+        );
+
+        assert_eq!(results.len(), file_count);
+        let mut paths: Vec<FileKey> = results.iter().map(|result| result.path().clone()).collect();
+        paths.sort_by_key(|path| path.trim_end_matches(".wav").parse::<usize>().unwrap());
+        assert_eq!(paths.first().map(FileKey::as_str), Some("0.wav"));
+        assert_eq!(paths.last().unwrap(), &format!("{}.wav", file_count - 1));
+        assert!(files.next().is_none());
+
+        let events = sink.into_events();
+        assert_eq!(events.iter().filter(|event| matches!(event, RunEvent::FileFinished { .. })).count(), file_count);
+        let scan_events: Vec<RunEvent> = events
+            .into_iter()
+            .filter(|event| matches!(event, RunEvent::ScanProgress { .. } | RunEvent::ScanFinished { .. }))
+            .collect();
+        assert_eq!(
+            scan_events,
+            vec![
+                RunEvent::ScanProgress { found: SCAN_PROGRESS_INTERVAL },
+                RunEvent::ScanProgress { found: SCAN_PROGRESS_INTERVAL * 2 },
+                RunEvent::ScanFinished { total: file_count },
+            ]
+        );
+    }
+
+    /// 测试每个文件的事件顺序：开始、长文件进度、结束，结束事件带有完整的结果
+    #[test]
+    fn test_file_events() {
+        let pool = rayon::ThreadPoolBuilder::new().num_threads(1).build().unwrap();
+        let files = vec![(PathBuf::from("/m/long.flac"), "long.flac"), (PathBuf::from("/m/bad.mp3"), "bad.mp3")];
+        let sink = BufferSink::new();
+        let results = pool.install(|| {
+            process_collected(files, ResultOrder::InputOrder, ScheduleOrder::InputOrder, StopControls::default(), &sink, |_, path, on_progress| {
+                if path.as_str() == "bad.mp3" {
+                    return FileOutcome::Failed(ProcessFileError::new(path.clone(), "损坏".to_string(), FileErrorType::FfmpegExecution));
+                }
+                on_progress(AnalysisProgress { elapsed: Duration::from_secs(40), percent: Some(50.0) });
+                FileOutcome::Success(FileMeasurement::new(path.clone(), 8.0))
+            })
+        });
+
+        let first = FilePosition { current: 1, total: 2, total_final: true };
+        let second = FilePosition { current: 2, total: 2, total_final: true };
+        assert_eq!(
+            sink.into_events(),
+            vec![
+                RunEvent::FileStarted { path: "long.flac".into(), position: first },
+                RunEvent::FileProgress {
+                    path: "long.flac".into(),
+                    position: first,
+                    progress: AnalysisProgress { elapsed: Duration::from_secs(40), percent: Some(50.0) },
+                },
+                RunEvent::FileFinished { position: first, outcome: Box::new(results[0].clone()) },
+                RunEvent::FileStarted { path: "bad.mp3".into(), position: second },
+                RunEvent::FileFinished { position: second, outcome: Box::new(results[1].clone()) },
+            ]
+        );
+        assert!(matches!(results[1], FileOutcome::Failed(_)));
+    }
+
+    /// 倒序完成的模拟分析：第 i 个文件要等第 i+1 个文件的完成事件之后才结束
+    ///
+    /// 等待设有超时，线程数不足时不会死锁（只是不再保证完成顺序）。
+    struct ReverseFinishing {
+        finished: Mutex<Vec<bool>>,
+        condvar: std::sync::Condvar,
+    }
+
+    impl ReverseFinishing {
+        fn new(file_count: usize) -> Self {
+            Self { finished: Mutex::new(vec![false; file_count]), condvar: std::sync::Condvar::new() }
+        }
+
+        fn run(&self, display_path: &FileKey) -> FileOutcome {
+            let index: usize = display_path.parse().unwrap();
+            let finished = self.finished.lock().unwrap();
+            if index + 1 < finished.len() {
+                let _ = self
+                    .condvar
+                    .wait_timeout_while(finished, std::time::Duration::from_secs(5), |done| !done[index + 1])
+                    .unwrap();
+            }
+            FileOutcome::Success(FileMeasurement::new(display_path.clone(), index as f64))
+        }
+
+        fn on_event(&self, event: &RunEvent) {
+            if let RunEvent::FileFinished { outcome, .. } = event {
+                if let FileOutcome::Success(measurement) = outcome.as_ref() {
+                    self.finished.lock().unwrap()[measurement.path.parse::<usize>().unwrap()] = true;
+                    self.condvar.notify_all();
+                }
+            }
+        }
+    }
+
+    /// 测试两种结果顺序：文件故意倒序完成，收集和边扫描边处理两条路径都要遵守指定顺序
+    #[test]
+    fn test_result_order() {
+        const FILE_COUNT: usize = 4;
+        let pool = rayon::ThreadPoolBuilder::new().num_threads(FILE_COUNT).build().unwrap();
+        let files: Vec<(PathBuf, String)> =
+            (0..FILE_COUNT).map(|i| (PathBuf::from(format!("{}.wav", i)), i.to_string())).collect();
+        let order_of = |results: Vec<FileOutcome>| -> Vec<f64> {
+            results.iter().map(|result| result.measurement().unwrap().lra).collect()
+        };
+        let input_order = vec![0.0, 1.0, 2.0, 3.0];
+        let completion_order = vec![3.0, 2.0, 1.0, 0.0];
+
+        pool.install(|| {
+            let collected = |order| {
+                let mock = ReverseFinishing::new(FILE_COUNT);
+                order_of(process_collected(
+                    files.clone(),
+                    order,
+                    ScheduleOrder::InputOrder,
+                    StopControls::default(),
+                    &|event: &RunEvent| mock.on_event(event),
+                    |_, path, _| mock.run(path),
+                ))
+            };
+            assert_eq!(collected(ResultOrder::InputOrder), input_order);
+            assert_eq!(collected(ResultOrder::CompletionOrder), completion_order);
+
+            let pipelined = |order| {
+                let mock = ReverseFinishing::new(FILE_COUNT);
+                let mut iter = files.clone().into_iter();
+                order_of(process_pipelined(&mut iter, order, StopControls::default(), &|event: &RunEvent| mock.on_event(event), |_, path, _| mock.run(path)))
+            };
+            assert_eq!(pipelined(ResultOrder::InputOrder), input_order);
+            assert_eq!(pipelined(ResultOrder::CompletionOrder), completion_order);
+        });
+    }
+
+    /// 测试打乱调度顺序：按洗牌后的顺序分派，结果仍按输入顺序排列
+    #[test]
+    fn test_shuffled_schedule() {
+        const FILE_COUNT: usize = 20;
+        const SEED: u64 = 5;
+        // 单线程时分派顺序就是处理顺序
+        let pool = rayon::ThreadPoolBuilder::new().num_threads(1).build().unwrap();
+        let files: Vec<(PathBuf, String)> =
+            (0..FILE_COUNT).map(|i| (PathBuf::from(format!("{}.wav", i)), i.to_string())).collect();
+
+        let dispatched = Mutex::new(Vec::new());
+        let results = pool.install(|| {
+            let schedule = ScheduleOrder::Shuffle { seed: SEED };
+            process_collected(files.clone(), ResultOrder::InputOrder, schedule, StopControls::default(), &BufferSink::new(), |_, path, _| {
+                dispatched.lock().unwrap().push(path.to_string());
+                FileOutcome::Success(FileMeasurement::new(path.clone(), path.parse::<f64>().unwrap()))
+            })
+        });
+
+        let mut expected_dispatch: Vec<String> = files.iter().map(|(_, path)| path.clone()).collect();
+        shuffle(&mut expected_dispatch, SEED);
+        assert_eq!(dispatched.into_inner().unwrap(), expected_dispatch);
+        let lras: Vec<f64> = results.iter().map(|result| result.measurement().unwrap().lra).collect();
+        assert_eq!(lras, (0..FILE_COUNT).map(|i| i as f64).collect::<Vec<_>>());
+    }
+
+    /// 测试取消后不再开始新的文件：已开始的文件照常完成，其余文件以 Cancelled 原因跳过
+    #[test]
+    fn test_cancellation_stops_dispatch() {
+        let pool = rayon::ThreadPoolBuilder::new().num_threads(1).build().unwrap();
+        let files: Vec<(PathBuf, String)> = (0..5).map(|i| (PathBuf::from(format!("{}.wav", i)), i.to_string())).collect();
+        let token = CancellationToken::new();
+
+        let controls = StopControls { cancellation: Some(&token), ..StopControls::default() };
+
+        let results = pool.install(|| {
+            process_collected(files, ResultOrder::InputOrder, ScheduleOrder::InputOrder, controls, &BufferSink::new(), |_, path, _| {
+                // 第二个文件分析期间到期
+                if path.as_str() == "1" {
+                    token.cancel();
+                }
+                FileOutcome::Success(FileMeasurement::new(path.clone(), 1.0))
+            })
+        });
+
+        let skip_reasons: Vec<Option<SkipReason>> = results
+            .iter()
+            .map(|result| match result {
+                FileOutcome::Skipped { reason, .. } => Some(*reason),
+                _ => None,
+            })
+            .collect();
+        assert_eq!(
+            skip_reasons,
+            vec![None, None, Some(SkipReason::Cancelled), Some(SkipReason::Cancelled), Some(SkipReason::Cancelled)]
+        );
+        assert_eq!(results[4].path(), "4");
+        // 旧的 Result 形式中仍是 Cancelled 错误
+        assert_eq!(results[4].clone().into_result().unwrap_err().error_type, FileErrorType::Cancelled);
+    }
+
+    /// 测试全部失败的运行：最先完成的文件都以同一种错误失败后中止，其余文件以 Aborted 原因跳过
+    #[test]
+    fn test_early_abort_on_total_failure() {
+        let pool = rayon::ThreadPoolBuilder::new().num_threads(1).build().unwrap();
+        let files: Vec<(PathBuf, String)> = (0..100).map(|i| (PathBuf::from(format!("{}.flac", i)), i.to_string())).collect();
+        let early_abort = EarlyAbort::new(5);
+        let controls = StopControls { early_abort: Some(&early_abort), ..StopControls::default() };
+        let analyzed = AtomicUsize::new(0);
+
+        let results = pool.install(|| {
+            process_collected(files, ResultOrder::InputOrder, ScheduleOrder::InputOrder, controls, &BufferSink::new(), |_, path, _| {
+                analyzed.fetch_add(1, Ordering::SeqCst);
+                FileOutcome::Failed(ProcessFileError::ffmpeg_error(path.clone(), "无法启动 FFmpeg".to_string()))
+            })
+        });
+
+        assert_eq!(analyzed.into_inner(), 5);
+        assert!(results[..5].iter().all(|result| matches!(result, FileOutcome::Failed(_))));
+        assert!(results[5..]
+            .iter()
+            .all(|result| matches!(result, FileOutcome::Skipped { reason: SkipReason::Aborted, .. })));
+        let reason = early_abort.reason().unwrap();
+        assert_eq!((reason.failures, reason.error_type), (5, FileErrorType::FfmpegExecution));
+        assert_eq!(reason.first_error.file_path, "0");
+
+        // 跳过的文件算作未处理，不计入失败
+        let (stats, _) = analyze_results(results);
+        assert_eq!((stats.failed, stats.skip_reason_counts[&SkipReason::Aborted]), (5, 95));
+        assert!(SkipReason::Aborted.is_unprocessed());
+    }
+
+    /// 测试有成功的文件时不中止
+    #[test]
+    fn test_early_abort_disarmed_by_success() {
+        let pool = rayon::ThreadPoolBuilder::new().num_threads(1).build().unwrap();
+        let files: Vec<(PathBuf, String)> = (0..20).map(|i| (PathBuf::from(format!("{}.flac", i)), i.to_string())).collect();
+        let early_abort = EarlyAbort::new(5);
+        let controls = StopControls { early_abort: Some(&early_abort), ..StopControls::default() };
+
+        let results = pool.install(|| {
+            process_collected(files, ResultOrder::InputOrder, ScheduleOrder::InputOrder, controls, &BufferSink::new(), |_, path, _| {
+                match path.as_str() {
+                    "2" => FileOutcome::Success(FileMeasurement::new(path.clone(), 8.0)),
+                    _ => FileOutcome::Failed(ProcessFileError::ffmpeg_error(path.clone(), "解码失败".to_string())),
+                }
+            })
+        });
+
+        assert!(!early_abort.is_tripped());
+        assert!(results.iter().all(|result| !matches!(result, FileOutcome::Skipped { .. })));
+    }
+
     /// 测试单个文件处理函数（模拟）
     #[test]
     fn test_process_single_file_error_classification() {
@@ -585,32 +2826,470 @@ mod tests {
         assert_eq!(ffmpeg_error.error_type_description(), "FFmpeg 执行失败");
         assert_eq!(lra_error.error_type_description(), "LRA 值解析失败");
         assert_eq!(other_error.error_type_description(), "其他错误");
+
+        // 默认不携带 FFmpeg 上下文
+        assert_eq!(ffmpeg_error.exit_code, None);
+        assert_eq!(ffmpeg_error.stderr_excerpt, None);
+
+        // 附加 FFmpeg 上下文后，Display 输出保持不变
+        let display_before = ffmpeg_error.to_string();
+        let with_context = ffmpeg_error.with_ffmpeg_context(Some(1), "Invalid data found");
+        assert_eq!(with_context.exit_code, Some(1));
+        assert_eq!(with_context.stderr_excerpt.as_deref(), Some("Invalid data found"));
+        assert_eq!(with_context.to_string(), display_before);
+    }
+
+    /// 测试云端占位文件在调用 FFmpeg 之前就被跳过
+    #[test]
+    fn test_process_single_file_skips_cloud_placeholder() {
+        let temp_dir = tempfile::TempDir::new().expect("无法创建临时目录");
+        let stub = temp_dir.path().join(".Song.flac.icloud");
+        std::fs::write(&stub, b"bplist00").unwrap();
+        let display_path = FileKey::from(".Song.flac.icloud");
+
+        for hydrate in [false, true] {
+            let options = CalculationOptions { hydrate, ..Default::default() };
+            let outcome = process_single_file(&stub, &display_path, &options, None, &|_| {});
+            assert_eq!(outcome, FileOutcome::Skipped { path: display_path.clone(), reason: SkipReason::ICloudStub });
+            let error = outcome.into_result().unwrap_err();
+            assert_eq!(error.error_type, FileErrorType::CloudPlaceholder);
+            assert_eq!(error.error_type_description(), "云端占位文件");
+        }
+        let regular = temp_dir.path().join("Song.flac");
+        assert_eq!(cloud_skip_reason(&regular, &CalculationOptions::default()), None);
     }
 
-    /// 测试显示错误详情功能
+    /// 测试扫描之后消失的文件：分析前检查和 FFmpeg 的 stderr 都归为文件访问失败，统计中单独计数
     #[test]
-    fn test_display_error_details() {
-        // 这个测试主要验证函数不会崩溃
-        // 实际的输出需要手动验证
+    fn test_vanished_files() {
+        let temp_dir = tempfile::TempDir::new().expect("无法创建临时目录");
+        let gone = temp_dir.path().join("gone.flac");
+        let error = process_single_file(&gone, &"gone.flac".into(), &CalculationOptions::default(), None, &|_| {})
+            .into_result()
+            .unwrap_err();
+        assert_eq!(error, ProcessFileError::vanished("gone.flac"));
+        assert_eq!(error.error_type, FileErrorType::FileAccess);
+
+        // 检查之后才消失时，FFmpeg 报告找不到输入文件
+        let exit_error = LraCalculationError::NonZeroExit {
+            code: Some(254),
+            stderr: "late.flac: No such file or directory".to_string(),
+            command: "ffmpeg -i late.flac".to_string(),
+        };
+        assert_eq!(exit_error.file_error_type(), FileErrorType::FileAccess);
+        let classified = classify_lra_error(&"late.flac".into(), exit_error);
+        assert!(classified.vanished);
+        assert_eq!(classified.exit_code, Some(254));
+
+        let (stats, _) = analyze_results(vec![
+            Err(error),
+            Err(classified),
+            Err(ProcessFileError::file_access_error("locked.flac", "权限不足".to_string())),
+            Ok(FileMeasurement::new("ok.flac", 7.0)),
+        ]);
+        assert_eq!((stats.failed, stats.vanished), (3, 2));
+        assert_eq!(stats.error_type_counts[&FileErrorType::FileAccess], 3);
+        assert_eq!(stats.vanished_paths().map(FileKey::as_str).collect::<Vec<_>>(), ["gone.flac", "late.flac"]);
+    }
+
+    /// 测试哈希失败时只留空并记录说明，不影响测量
+    #[test]
+    fn test_content_hash() {
+        let temp_dir = tempfile::TempDir::new().expect("无法创建临时目录");
+        let file = temp_dir.path().join("Song.flac");
+        std::fs::write(&file, b"fLaC").unwrap();
+
+        let hash = content_hash(&file, HashAlgorithm::Xxh3).unwrap();
+        assert_eq!(hash.algorithm(), HashAlgorithm::Xxh3);
+        let failed = content_hash(&temp_dir.path().join("gone.flac"), HashAlgorithm::Sha1);
+        assert!(failed.is_err());
+
+        let analysis = || FileAnalysis {
+            summary: LoudnessSummary {
+                lra: 9.5,
+                integrated_lufs: None,
+                duration: None,
+                true_peak_dbtp: None,
+                lra_low_lufs: None,
+                lra_high_lufs: None,
+                threshold_lufs: None,
+                warnings: Vec::new(),
+            },
+            downmixed_lra: None,
+            channels: None,
+            warnings: Vec::new(),
+            analysis_time: Duration::ZERO,
+        };
+        let measurement = analysis().into_measurement(&FileKey::from("Song.flac"), Some(Ok(hash)), None);
+        assert_eq!(measurement.content_hash, Some(hash));
+        assert!(measurement.analysis_warnings.is_empty());
+
+        let measurement = analysis().into_measurement(&FileKey::from("gone.flac"), Some(failed), None);
+        assert_eq!(measurement.content_hash, None);
+        assert_eq!(measurement.lra, 9.5);
+        assert_eq!(measurement.analysis_warnings.len(), 1);
+        assert!(measurement.analysis_warnings[0].contains("哈希列将留空"));
+    }
+
+    /// 测试按错误变体（而不是错误信息文本）进行分类
+    #[test]
+    fn test_classify_lra_error_by_variant() {
+        // 信息中包含 "LRA" 的 I/O 错误仍然应归类为 FFmpeg 执行失败
+        let spawn_error = LraCalculationError::Spawn(std::io::Error::new(
+            std::io::ErrorKind::NotFound,
+            "LRA 解析器未找到",
+        ));
+        let classified = classify_lra_error(&"a.mp3".into(), spawn_error);
+        assert_eq!(classified.error_type, FileErrorType::FfmpegExecution);
+        assert_eq!(classified.exit_code, None);
+
+        // 非零退出会携带退出码和 stderr 摘录
+        let exit_error = LraCalculationError::NonZeroExit {
+            code: Some(1),
+            stderr: "Invalid data found when processing input".to_string(),
+            command: "ffmpeg -i b.mp3".to_string(),
+        };
+        let classified = classify_lra_error(&"b.mp3".into(), exit_error);
+        assert_eq!(classified.error_type, FileErrorType::FfmpegExecution);
+        assert_eq!(classified.exit_code, Some(1));
+        assert_eq!(
+            classified.stderr_excerpt.as_deref(),
+            Some("Invalid data found when processing input")
+        );
+
+        // 输出中没有 LRA 值
+        let parse_error = LraCalculationError::ParseFailed {
+            output_excerpt: "ffmpeg 输出".to_string(),
+        };
+        let classified = classify_lra_error(&"c.mp3".into(), parse_error);
+        assert_eq!(classified.error_type, FileErrorType::LraParsingFailed);
+        assert_eq!(classified.file_path, "c.mp3");
+    }
+
+    /// 测试 stderr 摘录的截断
+    #[test]
+    fn test_ffmpeg_context_stderr_is_capped() {
+        use crate::error::MAX_STDERR_EXCERPT_BYTES;
+
+        // 使用多字节字符，验证截断发生在字符边界上
+        let long_stderr = "错误".repeat(MAX_STDERR_EXCERPT_BYTES);
+        let error = ProcessFileError::ffmpeg_error("test.mp3".to_string(), "失败".to_string())
+            .with_ffmpeg_context(Some(183), &long_stderr);
 
-        let few_errors = vec![
-            "错误1".to_string(),
-            "错误2".to_string(),
-            "错误3".to_string(),
+        let excerpt = error.stderr_excerpt.expect("应该包含 stderr 摘录");
+        assert!(excerpt.len() <= MAX_STDERR_EXCERPT_BYTES);
+        assert!(long_stderr.starts_with(&excerpt));
+    }
+
+    /// 测试统计信息的 JSON 字段名（稳定契约）和往返序列化
+    #[cfg(feature = "serde")]
+    #[test]
+    fn test_processing_stats_json_round_trip() {
+        let results = vec![
+            FileOutcome::Success(FileMeasurement::new("file1.mp3", 12.5)),
+            FileOutcome::Failed(ProcessFileError::ffmpeg_error("file2.wav".to_string(), "失败".to_string())),
+            FileOutcome::Skipped { path: ".file3.flac.icloud".into(), reason: SkipReason::ICloudStub },
         ];
+        let (mut stats, _) = analyze_results(results);
+        stats.run_id = RunId::parse("20240601-153012-a3f9");
+        stats.scan = Some(ScanStatistics {
+            total_files: 3,
+            total_bytes: Some(4096),
+            extension_counts: BTreeMap::from([("flac".to_string(), 2), ("mp3".to_string(), 1)]),
+        });
+
+        let json = stats.to_json().expect("序列化失败");
+        let value: serde_json::Value = serde_json::from_str(&json).expect("JSON 无效");
+
+        assert_eq!(value["run_id"], "20240601-153012-a3f9");
+        assert_eq!(value["successful"], 1);
+        assert_eq!(value["failed"], 1);
+        assert_eq!(value["error_type_counts"]["ffmpeg_execution"], 1);
+        assert!(value["error_messages"][0].as_str().unwrap().contains("file2.wav"));
+        assert_eq!(value["workers"], serde_json::json!([]));
+        assert_eq!(value["skipped"], 1);
+        assert_eq!(value["skip_reason_counts"], serde_json::json!({"icloud_stub": 1}));
+        assert_eq!(
+            value["scan"],
+            serde_json::json!({"total_files": 3, "total_bytes": 4096, "extension_counts": {"flac": 2, "mp3": 1}})
+        );
 
-        // 测试少量错误（不应该崩溃）
-        display_error_details(&few_errors);
+        let restored: ProcessingStats = serde_json::from_str(&json).expect("反序列化失败");
+        assert_eq!(restored.successful, stats.successful);
+        assert_eq!(restored.failed, stats.failed);
+        assert_eq!(restored.error_type_counts, stats.error_type_counts);
+        assert_eq!(restored.error_messages(), stats.error_messages());
+        assert_eq!(restored.run_id, stats.run_id);
+        assert_eq!(restored.skip_reason_counts, stats.skip_reason_counts);
+        assert_eq!(restored.scan, stats.scan);
+    }
 
-        // 测试大量错误
-        let many_errors: Vec<String> = (0..20)
-            .map(|i| format!("错误{}", i))
-            .collect();
+    /// 测试测量结果的 JSON 字段名：时长以秒输出，没有的值为 null
+    #[cfg(feature = "serde")]
+    #[test]
+    fn test_file_measurement_json() {
+        let measurement = FileMeasurement::new("Album/01.flac", 7.3)
+            .with_integrated_lufs(Some(-14.2))
+            .with_timing(Some(Duration::from_millis(225_500)), Duration::from_millis(800))
+            .with_channels(Some(vec![ChannelLoudness { integrated_lufs: -15.0, lra: 6.1 }]));
+
+        let line = measurement.to_json_line().expect("序列化失败");
+        assert!(!line.contains('\n'));
+        let value: serde_json::Value = serde_json::from_str(&line).expect("JSON 无效");
+        assert_eq!(value["path"], "Album/01.flac");
+        assert_eq!(value["lra"], 7.3);
+        assert_eq!(value["integrated_lufs"], -14.2);
+        assert_eq!(value["audio_duration_secs"], 225.5);
+        assert_eq!(value["analysis_time_secs"], 0.8);
+        assert_eq!(value["true_peak_dbtp"], serde_json::Value::Null);
+        assert_eq!(value["channels"][0]["lra"], 6.1);
+        assert_eq!(value["warnings"], serde_json::json!([]));
+
+        let warned = FileMeasurement::new("rip.mp3", 6.0).with_warnings(vec!["[mp3float] invalid new backstep -1".to_string()]);
+        let value: serde_json::Value = serde_json::from_str(&warned.to_json_line().unwrap()).unwrap();
+        assert_eq!(value["warnings"][0], "[mp3float] invalid new backstep -1");
+    }
+
+    /// 测试同步和异步路径共用的结果组装保留主测量、附加测量、计时和工作线程
+    #[test]
+    fn test_file_analysis_into_measurement() {
+        let analysis = FileAnalysis {
+            summary: LoudnessSummary {
+                lra: 7.3,
+                integrated_lufs: Some(-14.2),
+                duration: Some(Duration::from_secs(180)),
+                true_peak_dbtp: Some(-0.5),
+                lra_low_lufs: Some(-19.8),
+                lra_high_lufs: Some(-12.5),
+                threshold_lufs: Some(-34.6),
+                warnings: vec!["[mp3] 警告".to_string()],
+            },
+            downmixed_lra: Some(6.9),
+            channels: Some(vec![ChannelLoudness { integrated_lufs: -14.0, lra: 7.0 }; 2]),
+            warnings: Vec::new(),
+            analysis_time: Duration::from_millis(250),
+        };
+        let measurement = analysis.into_measurement(&FileKey::from("a.flac"), None, Some(3));
+
+        assert_eq!(measurement.path, "a.flac");
+        assert_eq!(measurement.lra, 7.3);
+        assert_eq!(measurement.integrated_lufs, Some(-14.2));
+        assert_eq!(measurement.true_peak_dbtp, Some(-0.5));
+        assert_eq!(measurement.downmixed_lra, Some(6.9));
+        assert_eq!(measurement.channels.as_ref().map(Vec::len), Some(2));
+        assert_eq!(measurement.audio_duration, Some(Duration::from_secs(180)));
+        assert_eq!(measurement.analysis_time, Some(Duration::from_millis(250)));
+        assert_eq!(measurement.worker, Some(3));
+        assert_eq!(measurement.warnings.len(), 1);
+    }
+
+    /// 测试音轨增益：dB 值保留两位小数，静音和缺少综合响度时为 None
+    #[test]
+    fn test_replaygain_track_gain() {
+        let measurement = FileMeasurement::new("a.flac", 7.3).with_integrated_lufs(Some(-14.237));
+        assert_eq!(measurement.replaygain_track_gain(), Some(ReplayGain::Gain(-3.76)));
+        assert_eq!(measurement.replaygain_track_gain_db(), Some(-3.76));
+
+        let quiet = FileMeasurement::new("b.flac", 2.0).with_integrated_lufs(Some(-60.0));
+        assert_eq!(quiet.replaygain_track_gain(), Some(ReplayGain::Clamped(24.0)));
+        assert_eq!(quiet.replaygain_track_gain_db(), Some(24.0));
+
+        let silent = FileMeasurement::new("c.flac", 0.0).with_integrated_lufs(Some(f64::NEG_INFINITY));
+        assert_eq!(silent.replaygain_track_gain(), Some(ReplayGain::Silent));
+        assert_eq!(silent.replaygain_track_gain_db(), None);
 
-        display_error_details(&many_errors);
+        assert_eq!(FileMeasurement::new("d.flac", 5.0).replaygain_track_gain_db(), None);
+    }
+
+    /// 测试文件错误的 JSON 字段名（稳定契约）和往返序列化
+    #[cfg(feature = "serde")]
+    #[test]
+    fn test_process_file_error_json_round_trip() {
+        let error = ProcessFileError::lra_parsing_error("a.flac".to_string(), "无 LRA".to_string())
+            .with_ffmpeg_context(Some(1), "stderr");
+
+        let value = serde_json::to_value(&error).expect("序列化失败");
+        assert_eq!(value["file_path"], "a.flac");
+        assert_eq!(value["message"], "无 LRA");
+        assert_eq!(value["error_type"], "lra_parsing_failed");
+        assert_eq!(value["exit_code"], 1);
+        assert_eq!(value["stderr_excerpt"], "stderr");
+
+        let restored: ProcessFileError = serde_json::from_value(value).expect("反序列化失败");
+        assert_eq!(restored.file_path, error.file_path);
+        assert_eq!(restored.error_type, FileErrorType::LraParsingFailed);
+        assert_eq!(restored.exit_code, Some(1));
+
+        // 所有错误类型都使用稳定的 snake_case 字符串
+        for (error_type, expected) in [
+            (FileErrorType::FfmpegExecution, "ffmpeg_execution"),
+            (FileErrorType::LraParsingFailed, "lra_parsing_failed"),
+            (FileErrorType::FileAccess, "file_access"),
+            (FileErrorType::CloudPlaceholder, "cloud_placeholder"),
+            (FileErrorType::Cancelled, "cancelled"),
+            (FileErrorType::Other, "other"),
+        ] {
+            assert_eq!(serde_json::to_value(error_type).unwrap(), expected);
+        }
+    }
+
+    /// 测试错误详情的格式化和截断
+    #[cfg(feature = "cli")]
+    #[test]
+    fn test_format_error_details() {
+        let errors_of = |count: usize| -> ProcessingStats {
+            let errors: Vec<ProcessFileError> = (0..count)
+                .map(|i| ProcessFileError::new(format!("file{}.mp3", i), format!("错误{}", i), FileErrorType::Other))
+                .collect();
+            ProcessingStats::new(0, count, errors)
+        };
+
+        // 少量错误全部显示
+        assert_eq!(format_error_details(&errors_of(3), ColorMode::Plain).len(), 3);
+
+        // 大量错误只显示前 10 个，并提示剩余数量
+        let many = format_error_details(&errors_of(20), ColorMode::Plain);
+        assert_eq!(many.len(), 12);
+        assert_eq!(many[10], "  ... 还有 10 个错误未显示");
+
+        // 省略的错误计入剩余数量
+        let omitted = ProcessingStats { omitted_errors: 30, ..errors_of(20) };
+        assert_eq!(format_error_details(&omitted, ColorMode::Plain)[10], "  ... 还有 40 个错误未显示");
+        let few_retained = ProcessingStats { omitted_errors: 1, ..errors_of(4) };
+        assert_eq!(format_error_details(&few_retained, ColorMode::Plain)[4], "  ... 还有 1 个错误未显示");
+
+        // 空错误列表
+        assert!(format_error_details(&errors_of(0), ColorMode::Plain).is_empty());
+    }
+
+    /// 测试统计摘要的无颜色输出逐字节一致，颜色模式只添加 ANSI 控制字符
+    #[cfg(feature = "cli")]
+    #[test]
+    fn test_format_processing_stats() {
+        let stats = ProcessingStats::new(
+            3,
+            1,
+            vec![ProcessFileError::new("bad.mp3", "损坏".to_string(), FileErrorType::Other)],
+        );
+        assert_eq!(
+            format_processing_stats(&stats, ColorMode::Plain),
+            "\n==================== 处理结果统计 ====================\n\
+             总文件数: 4\n\
+             成功处理: 3 个文件 (75.0%)\n\
+             处理失败: 1 个文件 (25.0%)\n\
+             \n\
+             失败文件详情:\n\
+             \x20 1. 文件 'bad.mp3' [其他错误]: 损坏\n\
+             ====================================================="
+        );
+
+        let colored = format_processing_stats(&stats, ColorMode::Ansi);
+        assert!(colored.contains("\x1b[32m成功处理: 3 个文件 (75.0%)\x1b[0m"));
+        assert!(colored.contains("\x1b[31m处理失败"));
+        assert_eq!(colored.replace("\x1b[32m", "").replace("\x1b[31m", "").replace("\x1b[0m", ""), format_processing_stats(&stats, ColorMode::Plain));
+
+        // 跳过的文件与失败分开列出，不影响成功率
+        let (with_skipped, _) = analyze_results(vec![
+            FileOutcome::Success(FileMeasurement::new("ok.flac", 7.0)),
+            FileOutcome::Skipped { path: "a.flac".into(), reason: SkipReason::CloudPlaceholder },
+            FileOutcome::Skipped { path: "b.flac".into(), reason: SkipReason::CloudPlaceholder },
+        ]);
+        let text = format_processing_stats(&with_skipped, ColorMode::Plain);
+        assert!(text.contains("\n成功处理: 1 个文件 (100.0%)\n🎉"));
+        assert!(text.contains("\n⏭️  跳过: 2 个文件 (不计入失败)\n  - 云端占位文件: 2 个，内容不在本地"));
+        assert!(!text.contains("失败文件详情"));
+
+        // 提前停止时未处理的文件计入总数，不显示全部完成
+        let mut partial = ProcessingStats::new(2, 0, Vec::new());
+        partial.unprocessed = 10;
+        let text = format_processing_stats(&partial, ColorMode::Plain);
+        assert!(text.contains("总文件数: 12\n成功处理: 2 个文件 (16.7%)\n⏳ 未处理: 10 个文件 (83.3%)，运行提前停止\n"), "{}", text);
+        assert!(!text.contains("🎉"));
+
+        let distribution = LraDistribution::from_values(&[20.0, 10.0, 5.0, 3.0]).unwrap();
+        let plain = format_lra_distribution(&distribution, ColorMode::Plain);
+        assert!(plain.contains("\nLRA < 8 LU (动态范围较小): 2 (50.0%)\n"));
+        assert!(!plain.contains('\x1b'));
+        assert!(format_lra_distribution(&distribution, ColorMode::Ansi).contains("\x1b[36mLRA > 15 LU"));
+    }
+
+    /// 测试耗时统计的累计和显示，时长未知的行被省略
+    #[cfg(feature = "cli")]
+    #[test]
+    fn test_format_timing() {
+        let minutes = |m: u64| Duration::from_secs(m * 60);
+        let results = vec![
+            Ok(FileMeasurement::new("a.flac", 8.0).with_timing(Some(minutes(45)), Duration::from_secs(6))),
+            Ok(FileMeasurement::new("b.flac", 9.0).with_timing(Some(minutes(75)), Duration::from_secs(10))),
+            // 没有时长的文件只计入吞吐量，缓存命中的文件都不计入
+            Ok(FileMeasurement::new("c.flac", 7.0).with_timing(None, Duration::from_secs(2))),
+            Ok(FileMeasurement::new("cached.flac", 7.0)),
+        ];
+        let (mut stats, _) = analyze_results(results);
+        assert_eq!(stats.analyzed_files, 3);
+        assert_eq!(stats.audio_seconds, Some(7200.0));
+        assert_eq!(stats.analysis_seconds, 18.0);
+        assert!(format_timing(&stats).is_empty(), "墙钟时间未设置时不显示耗时");
+
+        stats.wall_seconds = Some(36.0);
+        assert_eq!(stats.realtime_factor(), Some(200.0));
+        assert_eq!(
+            format_timing(&stats),
+            vec![
+                "音频总时长: 2 小时 00 分 00 秒",
+                "总用时: 36.0 秒",
+                "实时倍率: 200.0×",
+                "平均吞吐量: 5.0 个文件/分钟（平均每个文件分析 6.0 秒）",
+            ]
+        );
+        assert!(format_processing_stats(&stats, ColorMode::Plain).contains("\n\n音频总时长: 2 小时 00 分 00 秒\n"));
+
+        // 所有文件都没有时长时省略音频总时长和实时倍率
+        stats.audio_seconds = None;
+        stats.wall_seconds = Some(125.0);
+        assert_eq!(stats.realtime_factor(), None);
+        assert_eq!(
+            format_timing(&stats),
+            vec!["总用时: 2 分 05 秒", "平均吞吐量: 1.4 个文件/分钟（平均每个文件分析 6.0 秒）"]
+        );
+    }
 
-        // 测试空错误列表
-        let empty_errors: Vec<String> = vec![];
-        display_error_details(&empty_errors);
+    /// 测试按工作线程累计忙碌时间和利用率表格
+    #[cfg(feature = "cli")]
+    #[test]
+    fn test_worker_utilization() {
+        let timed = |path: &str, seconds: u64, worker: usize| {
+            Ok(FileMeasurement::new(path, 8.0).with_timing(None, Duration::from_secs(seconds)).with_worker(Some(worker)))
+        };
+        let results = vec![
+            timed("a.flac", 4, 1),
+            timed("b.flac", 3, 0),
+            timed("c.flac", 5, 1),
+            Ok(FileMeasurement::new("cached.flac", 7.0)),
+            Err(ProcessFileError::ffmpeg_error("bad.flac".to_string(), "失败".to_string())),
+        ];
+        let (mut stats, _) = analyze_results(results);
+        assert_eq!(
+            stats.workers,
+            vec![
+                WorkerUtilization { index: 0, files: 1, busy_seconds: 3.0 },
+                WorkerUtilization { index: 1, files: 2, busy_seconds: 9.0 },
+            ]
+        );
+        assert!(format_worker_utilization(&stats).is_empty(), "墙钟时间未设置时不显示");
+
+        stats.wall_seconds = Some(12.0);
+        assert_eq!(
+            format_worker_utilization(&stats),
+            vec![
+                "🧵 工作线程利用率（总用时 12.0 秒）:",
+                "线程  文件数  忙碌 (秒)  利用率",
+                "-------------------------------",
+                "   0       1        3.0   25.0%",
+                "   1       2        9.0   75.0%",
+            ]
+        );
+        assert_eq!(WorkerUtilization { index: 0, files: 1, busy_seconds: 20.0 }.utilization_percent(12.0), Some(100.0));
+        assert_eq!(stats.workers[0].utilization_percent(0.0), None);
     }
 }
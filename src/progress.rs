@@ -0,0 +1,152 @@
+//! 单文件进度模块 (Per-File Progress Module)
+//!
+//! 分析几个小时长的现场录音时，一个文件就要跑好几分钟，程序看起来像卡住了。
+//! 并行处理时 FFmpeg 以 `-progress pipe:1` 运行，在 stdout 上按块输出 `key=value` 行，
+//! 每块以 `progress=continue` 或 `progress=end` 结束。[`ProgressParser`] 把这些行
+//! 还原成已处理的音频时长，再结合 ffprobe 得到的总时长算出百分比（见
+//! [`crate::audio::measure_loudness_with_progress`]）。
+//!
+//! 只有运行超过 [`LONG_FILE_THRESHOLD`] 的文件才报告进度，之后每隔
+//! [`PROGRESS_REPORT_INTERVAL`] 报告一次；总时长未知时只报告已用时间。
+
+use std::fmt;
+use std::time::Duration;
+
+/// 分析时间超过该值的文件开始报告进度
+pub const LONG_FILE_THRESHOLD: Duration = Duration::from_secs(30);
+
+/// 两次进度报告之间的最短间隔
+pub const PROGRESS_REPORT_INTERVAL: Duration = Duration::from_secs(10);
+
+/// 长文件的分析进度 (Analysis Progress)
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct AnalysisProgress {
+    /// FFmpeg 已经运行的时间
+    pub elapsed: Duration,
+    /// 完成百分比（0 到 100）；文件总时长未知时为 `None`
+    pub percent: Option<f64>,
+}
+
+impl fmt::Display for AnalysisProgress {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self.percent {
+            Some(percent) => write!(f, "{:.0}%（已用 {} 秒）", percent, self.elapsed.as_secs()),
+            None => write!(f, "已用 {} 秒", self.elapsed.as_secs()),
+        }
+    }
+}
+
+/// `-progress` 输出中的一个完整块 (Progress Block)
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct ProgressBlock {
+    /// 已处理的音频时长；FFmpeg 尚未输出时（`N/A`）为 `None`
+    pub out_time: Option<Duration>,
+    /// 是否为最后一块（`progress=end`）
+    pub finished: bool,
+}
+
+/// `-progress` 输出的逐行解析器 (Progress Parser)
+///
+/// # 示例
+/// ```
+/// use lra_calculator_rust::progress::ProgressParser;
+/// use std::time::Duration;
+///
+/// let mut parser = ProgressParser::default();
+/// assert_eq!(parser.feed("out_time_us=90000000"), None);
+/// let block = parser.feed("progress=continue").unwrap();
+/// assert_eq!(block.out_time, Some(Duration::from_secs(90)));
+/// assert!(!block.finished);
+/// ```
+#[derive(Debug, Clone, Default)]
+pub struct ProgressParser {
+    /// 当前块中读到的已处理时长
+    out_time: Option<Duration>,
+}
+
+impl ProgressParser {
+    /// 读入一行 (Feed Line)
+    ///
+    /// 已处理时长取自 `out_time_us`；旧版本 FFmpeg 只有 `out_time_ms`（单位同样是微秒）。
+    /// 不认识的键和无法解析的值被忽略。
+    ///
+    /// # 参数
+    /// - `line` - `-progress` 输出的一行
+    ///
+    /// # 返回值
+    /// - 读到块结尾（`progress=...`）时返回这一块的内容，否则返回 `None`
+    pub fn feed(&mut self, line: &str) -> Option<ProgressBlock> {
+        let (key, value) = line.trim().split_once('=')?;
+        match key {
+            "out_time_us" | "out_time_ms" => {
+                if let Ok(micros) = value.parse::<u64>() {
+                    self.out_time = Some(Duration::from_micros(micros));
+                }
+                None
+            }
+            "progress" => Some(ProgressBlock { out_time: self.out_time.take(), finished: value == "end" }),
+            _ => None,
+        }
+    }
+}
+
+/// 由已处理时长和总时长计算完成百分比
+///
+/// # 返回值
+/// - 0 到 100 之间的百分比；任一时长未知或总时长为 0 时返回 `None`
+pub fn percent_complete(out_time: Option<Duration>, total: Option<Duration>) -> Option<f64> {
+    let total = total.filter(|total| !total.is_zero())?;
+    Some((out_time?.as_secs_f64() / total.as_secs_f64() * 100.0).clamp(0.0, 100.0))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// 测试解析真实的 -progress 输出，包括开头的 N/A 和结尾块
+    #[test]
+    fn test_progress_parser() {
+        let output = "\
+bitrate=N/A
+total_size=N/A
+out_time_us=N/A
+out_time_ms=N/A
+out_time=N/A
+speed=N/A
+progress=continue
+bitrate=N/A
+out_time_us=5400000000
+out_time_ms=5400000000
+out_time=01:30:00.000000
+speed= 312x
+progress=continue
+out_time_us=10800000000
+progress=end
+";
+        let mut parser = ProgressParser::default();
+        let blocks: Vec<ProgressBlock> = output.lines().filter_map(|line| parser.feed(line)).collect();
+        assert_eq!(
+            blocks,
+            vec![
+                ProgressBlock { out_time: None, finished: false },
+                ProgressBlock { out_time: Some(Duration::from_secs(5400)), finished: false },
+                ProgressBlock { out_time: Some(Duration::from_secs(10800)), finished: true },
+            ]
+        );
+    }
+
+    /// 测试百分比计算和显示
+    #[test]
+    fn test_percent_complete() {
+        let hours = |h: u64| Some(Duration::from_secs(h * 3600));
+        assert_eq!(percent_complete(hours(1), hours(4)), Some(25.0));
+        assert_eq!(percent_complete(hours(5), hours(4)), Some(100.0));
+        assert_eq!(percent_complete(None, hours(4)), None);
+        assert_eq!(percent_complete(hours(1), None), None);
+        assert_eq!(percent_complete(hours(1), Some(Duration::ZERO)), None);
+
+        let elapsed = Duration::from_secs(75);
+        assert_eq!(AnalysisProgress { elapsed, percent: Some(42.4) }.to_string(), "42%（已用 75 秒）");
+        assert_eq!(AnalysisProgress { elapsed, percent: None }.to_string(), "已用 75 秒");
+    }
+}
@@ -0,0 +1,197 @@
+//! ReplayGain 模块 (ReplayGain Module)
+//!
+//! 根据 ebur128 测得的综合响度计算 ReplayGain 2.0 增益：
+//!
+//! - **音轨增益**: `-18 LUFS − 综合响度`，见 [`ReplayGain::from_integrated_loudness`]
+//! - **专辑增益**: 同一目录下的音轨视为一张专辑，先用组合公式估算专辑的综合响度，
+//!   再按音轨增益的规则换算，见 [`album_replaygain`]
+//!
+//! ## 专辑响度的组合公式
+//!
+//! ```text
+//! L_album = 10 · log10( (1/n) · Σ 10^(L_i / 10) )
+//! ```
+//!
+//! 即各音轨综合响度的能量平均。EBU R128 的严格做法是把所有音轨的 400 ms 测量块
+//! 合在一起重新门限，这里既没有测量块也没有时长，相当于假设各音轨等长，
+//! 因此结果是**近似值**，输出中会明确标注。
+//!
+//! ## 异常值
+//!
+//! - 综合响度不高于 [`SILENCE_LUFS`]（ebur128 的绝对门限）或为 `-inf` 时视为静音，
+//!   不给出增益
+//! - 增益超出 ±[`MAX_REPLAYGAIN_DB`] 时限幅并标记，避免几乎静音的文件得到
+//!   几十 dB 的放大
+
+use std::collections::BTreeMap;
+use std::fmt;
+
+use crate::album::album_key;
+
+/// ReplayGain 2.0 的参考响度（LUFS）
+pub const REPLAYGAIN_REFERENCE_LUFS: f64 = -18.0;
+
+/// 静音判定线（LUFS），与 ebur128 的绝对门限相同
+pub const SILENCE_LUFS: f64 = -70.0;
+
+/// 增益的合理上限（dB），超出时限幅
+pub const MAX_REPLAYGAIN_DB: f64 = 24.0;
+
+/// 结果文件中 ReplayGain 列的列名，与 [`replaygain_fields`] 的顺序一致
+pub const REPLAYGAIN_COLUMNS: [&str; 2] =
+    ["ReplayGain 音轨增益 (dB)", "ReplayGain 专辑增益 (dB, 按目录能量平均的近似值)"];
+
+/// 缺少综合响度时 ReplayGain 列的值
+pub const MISSING_REPLAYGAIN: &str = "n/a";
+
+/// ReplayGain 增益 (ReplayGain)
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum ReplayGain {
+    /// 正常的增益（dB，保留两位小数）
+    Gain(f64),
+    /// 超出 ±[`MAX_REPLAYGAIN_DB`] 后被限幅的增益
+    Clamped(f64),
+    /// 静音文件，没有有意义的增益
+    Silent,
+}
+
+impl ReplayGain {
+    /// 根据综合响度计算增益 (From Integrated Loudness)
+    ///
+    /// # 参数
+    /// - `integrated_lufs` - 综合响度（LUFS）
+    ///
+    /// # 示例
+    /// ```
+    /// use lra_calculator_rust::replaygain::ReplayGain;
+    ///
+    /// assert_eq!(ReplayGain::from_integrated_loudness(-14.126), ReplayGain::Gain(-3.87));
+    /// assert_eq!(ReplayGain::from_integrated_loudness(-60.0), ReplayGain::Clamped(24.0));
+    /// assert_eq!(ReplayGain::from_integrated_loudness(-70.0), ReplayGain::Silent);
+    /// ```
+    pub fn from_integrated_loudness(integrated_lufs: f64) -> Self {
+        if !integrated_lufs.is_finite() || integrated_lufs <= SILENCE_LUFS {
+            return Self::Silent;
+        }
+        let gain = round_to_hundredths(REPLAYGAIN_REFERENCE_LUFS - integrated_lufs);
+        if gain.abs() > MAX_REPLAYGAIN_DB {
+            Self::Clamped(gain.clamp(-MAX_REPLAYGAIN_DB, MAX_REPLAYGAIN_DB))
+        } else {
+            Self::Gain(gain)
+        }
+    }
+
+    /// 增益值（dB）；静音时为 `None`
+    pub fn gain_db(&self) -> Option<f64> {
+        match self {
+            Self::Gain(gain) | Self::Clamped(gain) => Some(*gain),
+            Self::Silent => None,
+        }
+    }
+}
+
+impl fmt::Display for ReplayGain {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Gain(gain) => write!(f, "{:+.2}", gain),
+            Self::Clamped(gain) => write!(f, "{:+.2} (已限幅)", gain),
+            Self::Silent => write!(f, "静音"),
+        }
+    }
+}
+
+/// 四舍五入到两位小数
+fn round_to_hundredths(value: f64) -> f64 {
+    (value * 100.0).round() / 100.0
+}
+
+/// 按能量平均组合多条音轨的综合响度 (Combine Integrated Loudness)
+///
+/// 静音音轨（`-inf`）的能量为零，自然不影响结果。
+///
+/// # 返回值
+/// - `Some(f64)` - 组合后的综合响度（LUFS），全部静音时为 `-inf`
+/// - `None` - 没有音轨
+pub fn combine_integrated_loudness(track_lufs: &[f64]) -> Option<f64> {
+    if track_lufs.is_empty() {
+        return None;
+    }
+    let mean_energy =
+        track_lufs.iter().map(|lufs| 10f64.powf(lufs / 10.0)).sum::<f64>() / track_lufs.len() as f64;
+    Some(10.0 * mean_energy.log10())
+}
+
+/// 按目录计算专辑增益 (Album ReplayGain)
+///
+/// # 参数
+/// - `tracks` - (显示路径, 综合响度)；没有综合响度的音轨不应传入
+///
+/// # 返回值
+/// - 专辑（见 [`crate::album::album_key`]）到专辑增益的映射
+pub fn album_replaygain<'a, I>(tracks: I) -> BTreeMap<&'a str, ReplayGain>
+where
+    I: IntoIterator<Item = (&'a str, f64)>,
+{
+    let mut albums: BTreeMap<&str, Vec<f64>> = BTreeMap::new();
+    for (path, lufs) in tracks {
+        albums.entry(album_key(path)).or_default().push(lufs);
+    }
+    albums
+        .into_iter()
+        .filter_map(|(album, lufs)| {
+            let combined = combine_integrated_loudness(&lufs)?;
+            Some((album, ReplayGain::from_integrated_loudness(combined)))
+        })
+        .collect()
+}
+
+/// 渲染结果文件中的 ReplayGain 列（对应 [`REPLAYGAIN_COLUMNS`]）
+///
+/// 没有综合响度的文件写 [`MISSING_REPLAYGAIN`]。
+pub fn replaygain_fields(track: Option<ReplayGain>, album: Option<ReplayGain>) -> [String; 2] {
+    let render = |gain: Option<ReplayGain>| gain.map_or_else(|| MISSING_REPLAYGAIN.to_string(), |gain| gain.to_string());
+    [render(track), render(album)]
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// 测试音轨增益的计算、舍入和异常值处理
+    #[test]
+    fn test_track_gain() {
+        assert_eq!(ReplayGain::from_integrated_loudness(-18.0), ReplayGain::Gain(0.0));
+        assert_eq!(ReplayGain::from_integrated_loudness(-23.456), ReplayGain::Gain(5.46));
+        assert_eq!(ReplayGain::from_integrated_loudness(-5.0), ReplayGain::Gain(-13.0));
+        assert_eq!(ReplayGain::from_integrated_loudness(-42.0), ReplayGain::Gain(24.0));
+        assert_eq!(ReplayGain::from_integrated_loudness(-50.0), ReplayGain::Clamped(24.0));
+        assert_eq!(ReplayGain::from_integrated_loudness(f64::NEG_INFINITY), ReplayGain::Silent);
+        assert_eq!(ReplayGain::from_integrated_loudness(f64::NAN), ReplayGain::Silent);
+        assert_eq!(ReplayGain::Silent.gain_db(), None);
+
+        assert_eq!(ReplayGain::Gain(-3.5).to_string(), "-3.50");
+        assert_eq!(ReplayGain::Clamped(24.0).to_string(), "+24.00 (已限幅)");
+    }
+
+    /// 测试按目录组合专辑响度
+    #[test]
+    fn test_album_gain() {
+        // 相同响度的音轨组合后不变；能量平均偏向较响的音轨
+        assert_eq!(combine_integrated_loudness(&[-20.0, -20.0]), Some(-20.0));
+        let combined = combine_integrated_loudness(&[-10.0, -20.0]).unwrap();
+        assert!((combined - -12.596).abs() < 0.001);
+        assert_eq!(combine_integrated_loudness(&[]), None);
+
+        let albums = album_replaygain([
+            ("A/01.flac", -10.0),
+            ("A/02.flac", -20.0),
+            ("B/01.flac", f64::NEG_INFINITY),
+            ("single.flac", -18.0),
+        ]);
+        assert_eq!(albums["A"], ReplayGain::Gain(-5.4));
+        assert_eq!(albums["B"], ReplayGain::Silent);
+        assert_eq!(albums[""], ReplayGain::Gain(0.0));
+
+        assert_eq!(replaygain_fields(Some(ReplayGain::Gain(-2.0)), None), ["-2.00", "n/a"]);
+    }
+}
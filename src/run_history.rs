@@ -0,0 +1,211 @@
+//! 运行历史模块 (Run History Module)
+//!
+//! `--run-history <文件>` 时每次分析结束后向历史文件追加一条记录（JSON Lines，每行一个
+//! [`RunRecord`]），`history <文件>` 子命令把这些记录按时间列成表格，
+//! 用来观察音乐库的响度分布多年来的变化。
+//!
+//! ## 容错策略
+//!
+//! - 追加时对文件加排他锁，同时结束的多个实例不会写出交错的行
+//! - 上次写入中断留下的半行不会与新记录粘在一起：追加前先补上换行符
+//! - 读取时跳过无法解析的行并报告行号，其余记录照常使用
+//! - 写入失败只由调用方警告，绝不影响本次运行的结果
+//!
+//! 未启用 `serde` 特性时无法读写历史文件，两个函数都返回错误。
+
+use std::io;
+use std::path::Path;
+use std::time::{SystemTime, UNIX_EPOCH};
+
+#[cfg(feature = "serde")]
+use std::fs::{File, OpenOptions};
+#[cfg(feature = "serde")]
+use std::io::{BufRead, BufReader, Read, Seek, SeekFrom, Write};
+
+#[cfg(feature = "serde")]
+use fs2::FileExt;
+
+use crate::processor::{LraDistribution, ProcessingStats};
+use crate::run_id::RunId;
+
+/// 一次运行的记录 (Run Record)
+#[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct RunRecord {
+    /// 运行结束的时间（Unix 秒）
+    pub timestamp: u64,
+    /// 扫描根目录
+    pub root: String,
+    /// 结果文件中的音轨数量（增量更新时包括沿用的旧结果）
+    pub files: usize,
+    /// 本次分析失败的文件数量
+    pub failed: usize,
+    /// 平均 LRA（LU）；没有成功的音轨时为 `None`
+    pub mean_lra: Option<f64>,
+    /// LRA 中位数（LU）
+    pub median_lra: Option<f64>,
+    /// LRA 的总体标准差（LU）
+    pub std_dev_lra: Option<f64>,
+    /// 运行 ID（见 [`crate::run_id`]）；旧版本写入的记录没有此字段
+    #[cfg_attr(feature = "serde", serde(default, skip_serializing_if = "Option::is_none"))]
+    pub run_id: Option<RunId>,
+}
+
+impl RunRecord {
+    /// 由本次运行的统计生成记录
+    ///
+    /// # 参数
+    /// - `root` - 扫描根目录
+    /// - `stats` - 本次运行的处理统计（失败数量和运行 ID）
+    /// - `distribution` - 结果文件中全部音轨的分布统计
+    /// - `finished_at` - 运行结束的时间
+    pub fn new(
+        root: &Path,
+        stats: &ProcessingStats,
+        distribution: Option<&LraDistribution>,
+        finished_at: SystemTime,
+    ) -> Self {
+        Self {
+            timestamp: finished_at.duration_since(UNIX_EPOCH).map_or(0, |elapsed| elapsed.as_secs()),
+            root: root.display().to_string(),
+            files: distribution.map_or(0, |distribution| distribution.count),
+            failed: stats.failed,
+            mean_lra: distribution.map(|distribution| distribution.mean),
+            median_lra: distribution.map(|distribution| distribution.median),
+            std_dev_lra: distribution.map(|distribution| distribution.std_dev),
+            run_id: stats.run_id.clone(),
+        }
+    }
+}
+
+/// 读取到的运行历史 (Run History)
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct RunHistory {
+    /// 能解析的记录，保持文件中的顺序
+    pub records: Vec<RunRecord>,
+    /// 无法解析的行的行号（从 1 开始）
+    pub invalid_lines: Vec<usize>,
+}
+
+/// 向历史文件追加一条记录 (Append Run Record)
+///
+/// 文件不存在时创建。写入期间持有排他锁；文件末尾缺少换行符（上次写入中断）时先补上。
+///
+/// # 参数
+/// - `path` - 历史文件路径
+/// - `record` - 要追加的记录
+///
+/// # 返回值
+/// - `Ok(())` - 追加成功
+/// - `Err(io::Error)` - 文件无法打开、加锁或写入
+#[cfg(feature = "serde")]
+pub fn append_run_record(path: &Path, record: &RunRecord) -> io::Result<()> {
+    let mut line = serde_json::to_string(record).map_err(io::Error::other)?;
+    line.push('\n');
+
+    let mut file = OpenOptions::new().read(true).append(true).create(true).open(path)?;
+    file.lock_exclusive()?;
+    let result = (|| {
+        if !ends_with_newline(&mut file)? {
+            line.insert(0, '\n');
+        }
+        file.write_all(line.as_bytes())?;
+        file.flush()
+    })();
+    let _ = FileExt::unlock(&file);
+    result
+}
+
+/// 向历史文件追加一条记录（未启用 `serde` 特性时总是失败）
+#[cfg(not(feature = "serde"))]
+pub fn append_run_record(_path: &Path, _record: &RunRecord) -> io::Result<()> {
+    Err(io::Error::other("运行历史需要启用 serde 特性重新编译"))
+}
+
+/// 文件为空或以换行符结尾
+#[cfg(feature = "serde")]
+fn ends_with_newline(file: &mut File) -> io::Result<bool> {
+    if file.metadata()?.len() == 0 {
+        return Ok(true);
+    }
+    let mut last = [0u8; 1];
+    file.seek(SeekFrom::End(-1))?;
+    file.read_exact(&mut last)?;
+    Ok(last[0] == b'\n')
+}
+
+/// 读取历史文件 (Read Run History)
+///
+/// 空行被忽略，无法解析的行记入 [`RunHistory::invalid_lines`]，不会导致读取失败。
+///
+/// # 参数
+/// - `path` - 历史文件路径
+///
+/// # 返回值
+/// - `Ok(RunHistory)` - 读取到的记录
+/// - `Err(io::Error)` - 文件无法打开或读取
+#[cfg(feature = "serde")]
+pub fn read_run_history(path: &Path) -> io::Result<RunHistory> {
+    let mut history = RunHistory::default();
+    for (index, line) in BufReader::new(File::open(path)?).lines().enumerate() {
+        let line = line?;
+        if line.trim().is_empty() {
+            continue;
+        }
+        match serde_json::from_str(&line) {
+            Ok(record) => history.records.push(record),
+            Err(_) => history.invalid_lines.push(index + 1),
+        }
+    }
+    Ok(history)
+}
+
+/// 读取历史文件（未启用 `serde` 特性时总是失败）
+#[cfg(not(feature = "serde"))]
+pub fn read_run_history(_path: &Path) -> io::Result<RunHistory> {
+    Err(io::Error::other("运行历史需要启用 serde 特性重新编译"))
+}
+
+#[cfg(all(test, feature = "serde"))]
+mod tests {
+    use super::*;
+    use std::fs;
+    use std::time::Duration;
+    use tempfile::TempDir;
+
+    fn record(timestamp: u64, mean: f64) -> RunRecord {
+        let distribution = LraDistribution::from_values(&[mean - 1.0, mean + 1.0]).unwrap();
+        let stats = ProcessingStats { run_id: RunId::parse(&format!("run-{}", timestamp)), ..ProcessingStats::new(2, 1, Vec::new()) };
+        RunRecord::new(Path::new("/music"), &stats, Some(&distribution), UNIX_EPOCH + Duration::from_secs(timestamp))
+    }
+
+    /// 测试追加和读取，以及损坏行和中断的半行的处理
+    #[test]
+    fn test_append_and_read_run_history() {
+        let temp_dir = TempDir::new().expect("无法创建临时目录");
+        let path = temp_dir.path().join("lra_history.jsonl");
+
+        append_run_record(&path, &record(1_700_000_000, 10.0)).unwrap();
+        let history = read_run_history(&path).unwrap();
+        assert_eq!(history.records, vec![record(1_700_000_000, 10.0)]);
+        assert_eq!(history.records[0].files, 2);
+        assert_eq!(history.records[0].failed, 1);
+        assert_eq!(history.records[0].std_dev_lra, Some(1.0));
+        assert_eq!(history.records[0].run_id.as_ref().map(RunId::as_str), Some("run-1700000000"));
+
+        // 上次写入中断留下的半行不会吞掉下一条记录
+        let mut content = fs::read_to_string(&path).unwrap();
+        content.push_str("{\"timestamp\":17");
+        fs::write(&path, content).unwrap();
+        append_run_record(&path, &record(1_800_000_000, 12.0)).unwrap();
+
+        let history = read_run_history(&path).unwrap();
+        assert_eq!(history.records, vec![record(1_700_000_000, 10.0), record(1_800_000_000, 12.0)]);
+        assert_eq!(history.invalid_lines, vec![2]);
+
+        // 旧版本写入的记录没有运行 ID
+        fs::write(&path, "{\"timestamp\":1,\"root\":\"/m\",\"files\":0,\"failed\":0,\"mean_lra\":null,\"median_lra\":null,\"std_dev_lra\":null}\n").unwrap();
+        assert_eq!(read_run_history(&path).unwrap().records[0].run_id, None);
+        assert!(read_run_history(&temp_dir.path().join("missing.jsonl")).is_err());
+    }
+}
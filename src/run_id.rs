@@ -0,0 +1,154 @@
+//! 运行 ID 模块 (Run ID Module)
+//!
+//! 每次运行在启动时生成一个简短的 ID，形如 `20240601-153012-a3f9`：UTC 时间戳加上
+//! 4 位十六进制随机后缀。多次运行的产物放在同一个目录时，靠它对应起来：
+//!
+//! - 结果文件的元数据行 `# run: <ID>`（见 [`crate::utils::RUN_METADATA_PREFIX`]）
+//! - 失败记录文件的第一行
+//! - 统计 JSON 和运行历史记录中的 `run_id` 字段
+//! - 每个调试输出文件的文件名（见 [`crate::audio::debug_dump_path`]）
+//!
+//! 库使用者从 [`crate::plan::ProcessingPlan::run_id`] 得到同一个 ID。
+
+use std::collections::hash_map::RandomState;
+use std::fmt;
+use std::hash::{BuildHasher, Hasher};
+use std::time::{SystemTime, UNIX_EPOCH};
+
+use crate::clock::{Clock, SystemClock};
+
+/// 运行 ID (Run ID)
+///
+/// 只包含 ASCII 字母、数字、`-` 和 `_`，可以直接用在文件名中。
+///
+/// # 示例
+/// ```
+/// use std::time::{Duration, UNIX_EPOCH};
+/// use lra_calculator_rust::run_id::RunId;
+///
+/// let id = RunId::from_parts(UNIX_EPOCH + Duration::from_secs(1_717_255_812), 0xa3f9);
+/// assert_eq!(id.as_str(), "20240601-153012-a3f9");
+/// assert_eq!(RunId::parse("20240601-153012-a3f9"), Some(id));
+/// assert_eq!(RunId::parse("../etc"), None);
+/// ```
+#[derive(Debug, Clone, PartialEq, Eq, Hash, PartialOrd, Ord)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[cfg_attr(feature = "serde", serde(transparent))]
+pub struct RunId(String);
+
+impl RunId {
+    /// 生成新的运行 ID (Generate)
+    ///
+    /// 时间戳取当前的 UTC 时间；后缀来自进程级随机种子和纳秒时间，同一秒内启动的运行也不会重复。
+    pub fn generate() -> Self {
+        Self::generate_with(&SystemClock)
+    }
+
+    /// 按指定时钟生成运行 ID (Generate with Clock)
+    ///
+    /// 与 [`generate`](Self::generate) 相同，但时间戳取自 `clock`；
+    /// 固定时钟（[`Clock::is_fixed`]）下后缀为 `0000`，生成的 ID 可以复现。
+    ///
+    /// # 示例
+    /// ```
+    /// use lra_calculator_rust::clock::FixedClock;
+    /// use lra_calculator_rust::run_id::RunId;
+    ///
+    /// let id = RunId::generate_with(&FixedClock::from_unix_secs(1_717_255_812));
+    /// assert_eq!(id.as_str(), "20240601-153012-0000");
+    /// ```
+    pub fn generate_with(clock: &dyn Clock) -> Self {
+        let now = clock.now();
+        if clock.is_fixed() {
+            return Self::from_parts(now, 0);
+        }
+        let mut hasher = RandomState::new().build_hasher();
+        hasher.write_u128(now.duration_since(UNIX_EPOCH).map_or(0, |elapsed| elapsed.as_nanos()));
+        hasher.write_u32(std::process::id());
+        Self::from_parts(now, hasher.finish() as u16)
+    }
+
+    /// 由时间和后缀构造 (From Parts)
+    ///
+    /// # 参数
+    /// - `time` - 运行开始的时间，按 UTC 格式化；早于 Unix 纪元时按纪元处理
+    /// - `suffix` - 随机后缀
+    pub fn from_parts(time: SystemTime, suffix: u16) -> Self {
+        let seconds = time.duration_since(UNIX_EPOCH).map_or(0, |elapsed| elapsed.as_secs());
+        let (year, month, day) = civil_from_days((seconds / 86_400) as i64);
+        let second_of_day = seconds % 86_400;
+        Self(format!(
+            "{:04}{:02}{:02}-{:02}{:02}{:02}-{:04x}",
+            year,
+            month,
+            day,
+            second_of_day / 3600,
+            second_of_day % 3600 / 60,
+            second_of_day % 60,
+            suffix
+        ))
+    }
+
+    /// 解析已有的运行 ID (Parse)
+    ///
+    /// 接受任何只包含 ASCII 字母、数字、`-` 和 `_` 的非空字符串（最多 64 个字符），
+    /// 因此其他版本生成的 ID 也能读取；包含路径分隔符等字符时返回 `None`。
+    pub fn parse(value: &str) -> Option<Self> {
+        let value = value.trim();
+        let valid = !value.is_empty()
+            && value.len() <= 64
+            && value.chars().all(|c| c.is_ascii_alphanumeric() || c == '-' || c == '_');
+        valid.then(|| Self(value.to_string()))
+    }
+
+    /// ID 文本
+    pub fn as_str(&self) -> &str {
+        &self.0
+    }
+}
+
+impl fmt::Display for RunId {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(&self.0)
+    }
+}
+
+/// 由 Unix 纪元以来的天数计算公历日期（Howard Hinnant 的 `civil_from_days` 算法）
+fn civil_from_days(days: i64) -> (i64, u32, u32) {
+    let z = days + 719_468;
+    let era = z.div_euclid(146_097);
+    let day_of_era = z.rem_euclid(146_097);
+    let year_of_era = (day_of_era - day_of_era / 1460 + day_of_era / 36_524 - day_of_era / 146_096) / 365;
+    let day_of_year = day_of_era - (365 * year_of_era + year_of_era / 4 - year_of_era / 100);
+    let mp = (5 * day_of_year + 2) / 153;
+    let day = (day_of_year - (153 * mp + 2) / 5 + 1) as u32;
+    let month = if mp < 10 { mp + 3 } else { mp - 9 } as u32;
+    let year = year_of_era + era * 400 + i64::from(month <= 2);
+    (year, month, day)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::time::Duration;
+
+    /// 测试时间戳格式，包括闰日和年末
+    #[test]
+    fn test_from_parts() {
+        let at = |seconds: u64| UNIX_EPOCH + Duration::from_secs(seconds);
+        assert_eq!(RunId::from_parts(at(0), 0).as_str(), "19700101-000000-0000");
+        assert_eq!(RunId::from_parts(at(951_782_400), 0xffff).as_str(), "20000229-000000-ffff");
+        assert_eq!(RunId::from_parts(at(1_735_689_599), 0x1).as_str(), "20241231-235959-0001");
+    }
+
+    /// 测试生成的 ID 格式正确且可以解析回来
+    #[test]
+    fn test_generate_and_parse() {
+        let id = RunId::generate();
+        assert_eq!(id.as_str().len(), "20240601-153012-a3f9".len());
+        assert_eq!(RunId::parse(&format!(" {} ", id)), Some(id));
+        for invalid in ["", "a/b", "a b", "..", "运行"] {
+            assert_eq!(RunId::parse(invalid), None, "{:?} 应该无效", invalid);
+        }
+    }
+}
@@ -0,0 +1,95 @@
+//! 扫描统计模块 (Scan Statistics Module)
+//!
+//! 找到的音频文件按扩展名统计的数量、文件总数和总大小。命令行程序在开始分析之前输出
+//! 文件格式统计，并写入 `--stats-json`；图形界面和报告生成器使用同一个 [`ScanStatistics`]，
+//! 按 [`ScanStatistics::by_count`] 的顺序显示，与命令行程序一致。
+//!
+//! 构造统计不读取任何文件：扩展名来自路径，总大小使用扫描时已经累计的
+//! [`ScanReport::total_bytes`](crate::audio::ScanReport::total_bytes)（见 [`ScanStatistics::with_total_bytes`]）。
+
+use std::cmp::Reverse;
+use std::collections::BTreeMap;
+use std::path::PathBuf;
+
+use crate::audio::extract_file_extension;
+
+/// 扫描统计 (Scan Statistics)
+///
+/// # 示例
+/// ```
+/// use lra_calculator_rust::scan_stats::ScanStatistics;
+///
+/// let files = vec![
+///     ("/m/a.flac".into(), "a.flac".to_string()),
+///     ("/m/b.FLAC".into(), "b.FLAC".to_string()),
+///     ("/m/c.mp3".into(), "c.mp3".to_string()),
+/// ];
+/// let stats = ScanStatistics::from_files(&files).with_total_bytes(4096);
+/// assert_eq!(stats.total_files, 3);
+/// assert_eq!(stats.total_bytes, Some(4096));
+/// assert_eq!(stats.by_count(), vec![("flac", 2), ("mp3", 1)]);
+/// ```
+#[derive(Debug, Clone, Default, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct ScanStatistics {
+    /// 文件总数
+    pub total_files: usize,
+    /// 文件总大小（字节），取自扫描结果；未知时为 `None`
+    pub total_bytes: Option<u64>,
+    /// 按小写扩展名统计的文件数量；没有扩展名的文件（`--probe-unknown` 按内容识别）为空字符串
+    pub extension_counts: BTreeMap<String, usize>,
+}
+
+impl ScanStatistics {
+    /// 统计扫描到的文件 (From Files)
+    ///
+    /// 只按路径统计，不读取元数据；总大小为 `None`，需要时用 [`ScanStatistics::with_total_bytes`] 补上。
+    ///
+    /// # 参数
+    /// - `files` - (完整路径, 显示路径)，与 [`ScanReport::files`](crate::audio::ScanReport::files) 相同
+    pub fn from_files(files: &[(PathBuf, String)]) -> Self {
+        let mut stats = Self { total_files: files.len(), ..Self::default() };
+        for (path, _) in files {
+            *stats.extension_counts.entry(extract_file_extension(path).unwrap_or_default()).or_insert(0) += 1;
+        }
+        stats
+    }
+
+    /// 设置文件总大小 (With Total Bytes)
+    ///
+    /// 通常为扫描时累计的 [`ScanReport::total_bytes`](crate::audio::ScanReport::total_bytes)。
+    pub fn with_total_bytes(mut self, total_bytes: u64) -> Self {
+        self.total_bytes = Some(total_bytes);
+        self
+    }
+
+    /// 按数量从多到少排列的 (扩展名, 文件数量) (By Count)
+    ///
+    /// 数量相同时按扩展名排列，输出顺序稳定。
+    pub fn by_count(&self) -> Vec<(&str, usize)> {
+        let mut counts: Vec<_> = self.extension_counts.iter().map(|(extension, count)| (extension.as_str(), *count)).collect();
+        counts.sort_by_key(|(_, count)| Reverse(*count));
+        counts
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// 测试统计扩展名时不读取文件（文件不存在也照常统计），总大小取自调用方
+    #[test]
+    fn test_from_files() {
+        let files: Vec<(PathBuf, String)> = ["a.flac", "b.MP3", "c.mp3", "track", "missing.wav"]
+            .into_iter()
+            .map(|name| (PathBuf::from("/no/such/dir").join(name), name.to_string()))
+            .collect();
+
+        let stats = ScanStatistics::from_files(&files);
+        assert_eq!(stats.total_files, 5);
+        assert_eq!(stats.total_bytes, None);
+        assert_eq!(stats.by_count(), vec![("mp3", 2), ("", 1), ("flac", 1), ("wav", 1)]);
+        assert_eq!(stats.with_total_bytes(65).total_bytes, Some(65));
+        assert_eq!(ScanStatistics::from_files(&[]), ScanStatistics::default());
+    }
+}
@@ -0,0 +1,240 @@
+//! 文件选择模块 (File Selection Module)
+//!
+//! 面对超大的音乐库，先分析一小部分文件快速了解 LRA 分布：
+//!
+//! - `--limit N`: 只处理最先发现的 N 个文件
+//! - `--sample N [--seed S]`: 从发现的文件中均匀随机抽取 N 个，相同的种子总是抽到相同的文件
+//!
+//! 选择发生在扫描之后、分析之前，见 [`FileSelection::apply`]。
+//! `--shuffle` 打乱处理顺序时使用同样的洗牌算法，见 [`shuffle`]。
+//! 随机数使用内置的 SplitMix64，结果只取决于种子和候选数量，不随平台或依赖版本变化。
+
+use std::fmt;
+
+/// 文件选择方式 (File Selection)
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum FileSelection {
+    /// 只保留前 N 个
+    Limit(usize),
+    /// 用给定种子均匀随机抽取 N 个
+    Sample {
+        /// 抽取数量
+        count: usize,
+        /// 随机种子
+        seed: u64,
+    },
+}
+
+impl FileSelection {
+    /// 从候选列表中选择文件 (Apply Selection)
+    ///
+    /// 抽样结果保持候选列表中的相对顺序。候选数量不超过选择数量时原样返回。
+    ///
+    /// # 参数
+    /// - `candidates` - 需要分析的全部文件（不含复用缓存结果的文件）
+    ///
+    /// # 返回值
+    /// - 选中的文件
+    ///
+    /// # 示例
+    /// ```
+    /// use lra_calculator_rust::selection::FileSelection;
+    ///
+    /// let files: Vec<u32> = (0..100).collect();
+    /// assert_eq!(FileSelection::Limit(3).apply(files.clone()), vec![0, 1, 2]);
+    ///
+    /// let sample = FileSelection::Sample { count: 5, seed: 42 };
+    /// assert_eq!(sample.apply(files.clone()), sample.apply(files));
+    /// ```
+    pub fn apply<T>(&self, mut candidates: Vec<T>) -> Vec<T> {
+        match *self {
+            FileSelection::Limit(count) => {
+                candidates.truncate(count);
+                candidates
+            }
+            FileSelection::Sample { count, seed } => {
+                if candidates.len() <= count {
+                    return candidates;
+                }
+                let mut selected = vec![false; candidates.len()];
+                for index in sample_indices(candidates.len(), count, seed) {
+                    selected[index] = true;
+                }
+                candidates.into_iter().zip(selected).filter(|(_, keep)| *keep).map(|(item, _)| item).collect()
+            }
+        }
+    }
+
+    /// 选择的文件数量上限
+    pub fn count(&self) -> usize {
+        match *self {
+            FileSelection::Limit(count) | FileSelection::Sample { count, .. } => count,
+        }
+    }
+}
+
+impl fmt::Display for FileSelection {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            FileSelection::Limit(count) => write!(f, "--limit {}", count),
+            FileSelection::Sample { count, seed } => write!(f, "--sample {} --seed {}", count, seed),
+        }
+    }
+}
+
+/// 一次选择的结果 (Selection Report)
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct SelectionReport {
+    /// 使用的选择方式
+    pub selection: FileSelection,
+    /// 候选文件数量，即需要分析（缓存中没有可复用结果）的文件数量
+    pub candidates: usize,
+    /// 实际选中的文件数量
+    pub selected: usize,
+}
+
+impl fmt::Display for SelectionReport {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self.selection {
+            FileSelection::Limit(_) => write!(
+                f,
+                "限量运行: 只分析了最先发现的 {} 个文件（共 {} 个候选文件）",
+                self.selected, self.candidates
+            ),
+            FileSelection::Sample { seed, .. } => write!(
+                f,
+                "抽样运行: 从 {} 个候选文件中随机抽取了 {} 个（用 --seed {} 可重现）",
+                self.candidates, self.selected, seed
+            ),
+        }
+    }
+}
+
+/// 用给定种子原地打乱顺序 (Shuffle)
+///
+/// Fisher-Yates 洗牌：每种排列出现的概率相同，相同的种子和长度总是得到相同的排列。
+///
+/// # 参数
+/// - `items` - 要打乱的元素
+/// - `seed` - 随机种子
+///
+/// # 示例
+/// ```
+/// use lra_calculator_rust::selection::shuffle;
+///
+/// let mut a: Vec<u32> = (0..10).collect();
+/// let mut b = a.clone();
+/// shuffle(&mut a, 7);
+/// shuffle(&mut b, 7);
+/// assert_eq!(a, b);
+/// ```
+pub fn shuffle<T>(items: &mut [T], seed: u64) {
+    partial_shuffle(items, items.len(), seed);
+}
+
+/// 只确定前 `count` 个位置的 Fisher-Yates 洗牌
+fn partial_shuffle<T>(items: &mut [T], count: usize, seed: u64) {
+    let mut rng = SplitMix64(seed);
+    let len = items.len();
+    for i in 0..count.min(len) {
+        let j = i + rng.below((len - i) as u64) as usize;
+        items.swap(i, j);
+    }
+}
+
+/// 从 `0..len` 中不重复地抽取 `count` 个下标
+fn sample_indices(len: usize, count: usize, seed: u64) -> Vec<usize> {
+    let mut indices: Vec<usize> = (0..len).collect();
+    partial_shuffle(&mut indices, count, seed);
+    indices.truncate(count);
+    indices
+}
+
+/// SplitMix64 伪随机数生成器
+struct SplitMix64(u64);
+
+impl SplitMix64 {
+    fn next_u64(&mut self) -> u64 {
+        self.0 = self.0.wrapping_add(0x9e37_79b9_7f4a_7c15);
+        let mut z = self.0;
+        z = (z ^ (z >> 30)).wrapping_mul(0xbf58_476d_1ce4_e5b9);
+        z = (z ^ (z >> 27)).wrapping_mul(0x94d0_49bb_1331_11eb);
+        z ^ (z >> 31)
+    }
+
+    /// `0..bound` 中的均匀随机数（拒绝采样，避免取模偏差）
+    fn below(&mut self, bound: u64) -> u64 {
+        let zone = u64::MAX - u64::MAX % bound;
+        loop {
+            let value = self.next_u64();
+            if value < zone {
+                return value % bound;
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// 测试相同种子的抽样结果相同、不同种子不同，且没有重复
+    #[test]
+    fn test_sample_is_deterministic() {
+        let candidates: Vec<usize> = (0..1000).collect();
+        let sample = |seed| FileSelection::Sample { count: 50, seed }.apply(candidates.clone());
+
+        let first = sample(7);
+        assert_eq!(first, sample(7));
+        assert_ne!(first, sample(8));
+        assert_eq!(first.len(), 50);
+        assert!(first.windows(2).all(|pair| pair[0] < pair[1]), "抽样结果应保持原顺序且不重复");
+
+        // 固定种子的结果不随实现细节变化，日志中记录的种子才能重现同一批文件
+        assert_eq!(FileSelection::Sample { count: 3, seed: 1 }.apply((0..10).collect::<Vec<_>>()), vec![1, 5, 8]);
+    }
+
+    /// 测试洗牌结果是原序列的排列，且固定种子时确定
+    #[test]
+    fn test_shuffle_is_deterministic_permutation() {
+        let original: Vec<usize> = (0..200).collect();
+        let shuffled = |seed| {
+            let mut items = original.clone();
+            shuffle(&mut items, seed);
+            items
+        };
+
+        let first = shuffled(99);
+        assert_eq!(first, shuffled(99));
+        assert_ne!(first, shuffled(100));
+        assert_ne!(first, original);
+        let mut sorted = first.clone();
+        sorted.sort_unstable();
+        assert_eq!(sorted, original);
+
+        let mut empty: Vec<usize> = Vec::new();
+        shuffle(&mut empty, 1);
+        assert!(empty.is_empty());
+    }
+
+    /// 测试抽样的均匀性和边界情况
+    #[test]
+    fn test_sample_distribution_and_bounds() {
+        // 每个候选被选中的次数应接近 次数 × count / len
+        let mut hits = [0usize; 10];
+        for seed in 0..2000 {
+            let selection = FileSelection::Sample { count: 3, seed };
+            for index in selection.apply((0..10).collect::<Vec<usize>>()) {
+                hits[index] += 1;
+            }
+        }
+        assert!(hits.iter().all(|&count| (500..700).contains(&count)), "分布不均匀: {:?}", hits);
+
+        assert_eq!(FileSelection::Sample { count: 5, seed: 0 }.apply(vec![1, 2]), vec![1, 2]);
+        assert!(FileSelection::Sample { count: 0, seed: 0 }.apply(vec![1, 2]).is_empty());
+        assert_eq!(FileSelection::Limit(5).apply(vec![1, 2]), vec![1, 2]);
+        assert_eq!(FileSelection::Limit(1).apply(vec![1, 2]), vec![1]);
+    }
+}
@@ -0,0 +1,143 @@
+//! Shell 命令行渲染模块 (Shell Command Line Module)
+//!
+//! 程序自己启动外部进程时直接传参（不经过 shell），但有两处需要给人看、让人复制运行的命令行：
+//! `--verbose` 打印的 FFmpeg 命令（见 [`crate::audio::render_command_line`]）和
+//! `--inspect-script` 生成的诊断脚本（见 [`crate::inspect`]）。两者共用这里的引号规则，
+//! 保证路径中的空格、引号、`$` 和非 ASCII 字符原样传给程序。
+
+use std::borrow::Cow;
+use std::ffi::OsStr;
+
+/// 命令行语法 (Shell Syntax)
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ShellSyntax {
+    /// POSIX shell（sh、bash、zsh）
+    Posix,
+    /// PowerShell（Windows PowerShell 5.1 和 PowerShell 7）
+    PowerShell,
+}
+
+impl ShellSyntax {
+    /// 当前平台常用的 shell：Windows 上为 PowerShell，其他平台为 POSIX shell
+    pub fn native() -> Self {
+        if cfg!(windows) {
+            ShellSyntax::PowerShell
+        } else {
+            ShellSyntax::Posix
+        }
+    }
+
+    /// 脚本文件扩展名（不含点）
+    pub fn script_extension(self) -> &'static str {
+        match self {
+            ShellSyntax::Posix => "sh",
+            ShellSyntax::PowerShell => "ps1",
+        }
+    }
+
+    /// 给单个参数加引号 (Quote Argument)
+    ///
+    /// 只含安全字符的参数原样输出，其他参数（包括空字符串）用单引号包裹：
+    /// - POSIX: 安全字符为字母、数字和 `-_./=:,+@%`；内部的单引号写成 `'\''`
+    /// - PowerShell: 安全字符为字母、数字和 `-_./=:\`；内部的单引号写成两个。
+    ///   PowerShell 把弯引号 `‘’‚‛` 也当作单引号，同样需要重复
+    ///
+    /// # 示例
+    /// ```
+    /// use lra_calculator_rust::shell::ShellSyntax;
+    ///
+    /// assert_eq!(ShellSyntax::Posix.quote("My Song's.flac"), r"'My Song'\''s.flac'");
+    /// assert_eq!(ShellSyntax::PowerShell.quote("My Song's.flac"), "'My Song''s.flac'");
+    /// ```
+    pub fn quote(self, arg: &str) -> Cow<'_, str> {
+        let safe_punctuation = match self {
+            ShellSyntax::Posix => "-_./=:,+@%",
+            ShellSyntax::PowerShell => "-_./=:\\",
+        };
+        if !arg.is_empty() && arg.chars().all(|c| c.is_ascii_alphanumeric() || safe_punctuation.contains(c)) {
+            return Cow::Borrowed(arg);
+        }
+
+        let mut quoted = String::with_capacity(arg.len() + 2);
+        quoted.push('\'');
+        for c in arg.chars() {
+            match (self, c) {
+                (ShellSyntax::Posix, '\'') => quoted.push_str(r"'\''"),
+                (ShellSyntax::PowerShell, '\'' | '\u{2018}' | '\u{2019}' | '\u{201A}' | '\u{201B}') => {
+                    quoted.push(c);
+                    quoted.push(c);
+                }
+                _ => quoted.push(c),
+            }
+        }
+        quoted.push('\'');
+        Cow::Owned(quoted)
+    }
+
+    /// 渲染可复制运行的命令行 (Render Command Line)
+    ///
+    /// PowerShell 中加了引号的程序名是字符串而不是命令，此时在前面加调用运算符 `&`。
+    ///
+    /// # 参数
+    /// - `program` - 程序名或路径
+    /// - `args` - 参数列表；不是有效 UTF-8 的部分按 `to_string_lossy` 替换
+    ///
+    /// # 返回值
+    /// - 可以直接粘贴到对应 shell 中执行的命令行
+    pub fn render_command_line<I, S>(self, program: &str, args: I) -> String
+    where
+        I: IntoIterator<Item = S>,
+        S: AsRef<OsStr>,
+    {
+        let program = self.quote(program);
+        let mut line = match (self, &program) {
+            (ShellSyntax::PowerShell, Cow::Owned(quoted)) => format!("& {}", quoted),
+            _ => program.into_owned(),
+        };
+        for arg in args {
+            line.push(' ');
+            line.push_str(&self.quote(&arg.as_ref().to_string_lossy()));
+        }
+        line
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// 测试 PowerShell 的引号规则
+    #[test]
+    fn test_powershell_quote() {
+        let shell = ShellSyntax::PowerShell;
+        assert_eq!(shell.quote(r"C:\Music\a.flac"), r"C:\Music\a.flac");
+        assert_eq!(shell.quote("My Song.flac"), "'My Song.flac'");
+        assert_eq!(shell.quote("it's"), "'it''s'");
+        assert_eq!(shell.quote("it\u{2019}s"), "'it\u{2019}\u{2019}s'");
+        assert_eq!(shell.quote("$env:HOME `x` \"q\""), "'$env:HOME `x` \"q\"'");
+        assert_eq!(shell.quote("专辑/01 晴天.flac"), "'专辑/01 晴天.flac'");
+        // 逗号和 @ 在 PowerShell 中有特殊含义
+        assert_eq!(shell.quote("a,b"), "'a,b'");
+        assert_eq!(shell.quote("@x"), "'@x'");
+        assert_eq!(shell.quote(""), "''");
+
+        assert_eq!(
+            shell.render_command_line("ffprobe", ["-hide_banner", "D:\\专辑\\01.flac"]),
+            "ffprobe -hide_banner 'D:\\专辑\\01.flac'"
+        );
+        assert_eq!(
+            shell.render_command_line(r"C:\Program Files\ffmpeg\bin\ffmpeg.exe", ["-i", "a.flac"]),
+            r"& 'C:\Program Files\ffmpeg\bin\ffmpeg.exe' -i a.flac"
+        );
+    }
+
+    /// 测试 POSIX 的引号规则（完整的执行验证见 audio 模块的测试）
+    #[test]
+    fn test_posix_quote() {
+        let shell = ShellSyntax::Posix;
+        assert_eq!(shell.quote("/music/a.flac"), "/music/a.flac");
+        assert_eq!(shell.quote("it\u{2019}s"), "'it\u{2019}s'");
+        assert_eq!(shell.quote(r"C:\Music"), r"'C:\Music'");
+        assert_eq!(shell.render_command_line("/opt/my ffmpeg", ["-i"]), "'/opt/my ffmpeg' -i");
+    }
+}
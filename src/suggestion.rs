@@ -0,0 +1,231 @@
+//! 错误建议模块 (Suggestion Module)
+//!
+//! 错误信息只陈述发生了什么，怎样解决由 [`Suggestion`] 单独表示，通过
+//! [`AppError::suggestions`](crate::error::AppError::suggestions) 和
+//! [`ProcessFileError::suggestions`](crate::error::ProcessFileError::suggestions) 取得。
+//! 命令行程序把建议输出为错误信息下方的列表（见 [`render_suggestions`]），
+//! 图形界面可以按变体显示为按钮或链接，不需要解析错误文本。
+//!
+//! 每条建议可以按 [`Language`] 输出；`Display` 使用 [`Language::Chinese`]。
+
+use std::fmt;
+
+/// 建议的输出语言 (Language)
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum Language {
+    /// 简体中文（命令行程序使用）
+    #[default]
+    Chinese,
+    /// 英文
+    English,
+}
+
+/// 解决错误的建议 (Suggestion)
+///
+/// 序列化时使用稳定的 snake_case 名称（如 `"install_ffmpeg"`）。
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[cfg_attr(feature = "serde", serde(rename_all = "snake_case"))]
+pub enum Suggestion {
+    /// 安装 FFmpeg 并添加到 PATH（附各平台的安装命令）
+    InstallFfmpeg,
+    /// FFmpeg 能找到但无法运行：重新安装
+    ReinstallFfmpeg,
+    /// 当前 FFmpeg 构建缺少 ebur128 滤波器：换用完整构建
+    InstallFullFfmpegBuild,
+    /// 检查路径的拼写和分隔符
+    CheckPathSpelling,
+    /// 确认文件夹没有被删除或移动
+    CheckPathMoved,
+    /// 选择文件夹而不是文件
+    SelectFolder,
+    /// 检查权限设置，或以管理员身份运行
+    CheckPermissions,
+    /// 确保文件夹没有被其他程序占用
+    CloseOtherPrograms,
+    /// 使用 `--output` 把结果文件写到其他位置
+    UseOutputFlag,
+    /// 分析整个文件夹时去掉 `file` 子命令
+    DropFileSubcommand,
+    /// 使用 `--add-ext` 把扩展名加入支持的格式
+    AddExtension,
+    /// 使用更新版本的程序
+    UpgradeProgram,
+    /// 确认音频文件格式受支持且没有损坏
+    CheckAudioFile,
+    /// 确认音频至少有几秒钟长
+    CheckAudioDuration,
+    /// 确认 FFmpeg 版本兼容
+    CheckFfmpegVersion,
+}
+
+impl Suggestion {
+    /// 按语言输出建议 (Render)
+    ///
+    /// 第一行是建议本身；[`Suggestion::InstallFfmpeg`] 之后每行一个平台的安装方法。
+    ///
+    /// # 示例
+    /// ```
+    /// use lra_calculator_rust::suggestion::{Language, Suggestion};
+    ///
+    /// assert_eq!(Suggestion::UseOutputFlag.render(Language::Chinese), "使用 --output <路径> 将结果文件写到其他可写位置");
+    /// assert!(Suggestion::InstallFfmpeg.render(Language::English).contains("brew install ffmpeg"));
+    /// ```
+    pub fn render(self, language: Language) -> String {
+        std::iter::once(self.text(language)).chain(self.details(language).iter().copied()).collect::<Vec<_>>().join("\n")
+    }
+
+    /// 建议本身（第一行）
+    fn text(self, language: Language) -> &'static str {
+        use Language::{Chinese, English};
+        match (self, language) {
+            (Suggestion::InstallFfmpeg, Chinese) => "安装 FFmpeg 并添加到 PATH 环境变量中",
+            (Suggestion::InstallFfmpeg, English) => "Install FFmpeg and add it to PATH",
+            (Suggestion::ReinstallFfmpeg, Chinese) => {
+                "FFmpeg 文件可能损坏或不完整、缺少系统依赖库或权限不足，请尝试重新安装 FFmpeg"
+            }
+            (Suggestion::ReinstallFfmpeg, English) => {
+                "The FFmpeg binary may be damaged, missing system libraries or lack permissions; try reinstalling FFmpeg"
+            }
+            (Suggestion::InstallFullFfmpegBuild, Chinese) => "安装包含完整滤波器的 FFmpeg 构建",
+            (Suggestion::InstallFullFfmpegBuild, English) => "Install an FFmpeg build that includes the full filter set",
+            (Suggestion::CheckPathSpelling, Chinese) => "检查路径拼写是否正确，是否使用了正确的路径分隔符",
+            (Suggestion::CheckPathSpelling, English) => "Check the spelling of the path and the path separators",
+            (Suggestion::CheckPathMoved, Chinese) => "确认文件夹没有被删除或移动",
+            (Suggestion::CheckPathMoved, English) => "Make sure the folder has not been deleted or moved",
+            (Suggestion::SelectFolder, Chinese) => "请确保选择的是文件夹而不是文件",
+            (Suggestion::SelectFolder, English) => "Select a folder rather than a file",
+            (Suggestion::CheckPermissions, Chinese) => "检查文件夹权限设置，或以管理员身份运行程序",
+            (Suggestion::CheckPermissions, English) => "Check the folder permissions or run the program as administrator",
+            (Suggestion::CloseOtherPrograms, Chinese) => "确保文件夹未被其他程序占用",
+            (Suggestion::CloseOtherPrograms, English) => "Make sure no other program is holding the folder",
+            (Suggestion::UseOutputFlag, Chinese) => "使用 --output <路径> 将结果文件写到其他可写位置",
+            (Suggestion::UseOutputFlag, English) => "Use --output <path> to write the results file to a writable location",
+            (Suggestion::DropFileSubcommand, Chinese) => "分析整个文件夹请去掉子命令 'file'",
+            (Suggestion::DropFileSubcommand, English) => "Drop the 'file' subcommand to analyze the whole folder",
+            (Suggestion::AddExtension, Chinese) => "使用 --add-ext <扩展名> 把扩展名加入支持的格式",
+            (Suggestion::AddExtension, English) => "Use --add-ext <extension> to add the extension to the supported formats",
+            (Suggestion::UpgradeProgram, Chinese) => "请使用更新版本的程序",
+            (Suggestion::UpgradeProgram, English) => "Use a newer version of the program",
+            (Suggestion::CheckAudioFile, Chinese) => "确认音频文件格式受支持且没有损坏",
+            (Suggestion::CheckAudioFile, English) => "Make sure the audio format is supported and the file is not damaged",
+            (Suggestion::CheckAudioDuration, Chinese) => "确认音频时长足够（需要至少几秒钟）",
+            (Suggestion::CheckAudioDuration, English) => "Make sure the audio is long enough (at least a few seconds)",
+            (Suggestion::CheckFfmpegVersion, Chinese) => "确认 FFmpeg 版本兼容（4.0 或更高）",
+            (Suggestion::CheckFfmpegVersion, English) => "Make sure the FFmpeg version is compatible (4.0 or newer)",
+        }
+    }
+
+    /// 建议的后续行
+    fn details(self, language: Language) -> &'static [&'static str] {
+        match (self, language) {
+            (Suggestion::InstallFfmpeg, Language::Chinese) => &[
+                "macOS: brew install ffmpeg",
+                "Ubuntu/Debian: sudo apt install ffmpeg",
+                "Windows: choco install ffmpeg 或从官网下载",
+                "其他系统: 请访问 https://ffmpeg.org/download.html",
+            ],
+            (Suggestion::InstallFfmpeg, Language::English) => &[
+                "macOS: brew install ffmpeg",
+                "Ubuntu/Debian: sudo apt install ffmpeg",
+                "Windows: choco install ffmpeg or download it from the website",
+                "Other systems: see https://ffmpeg.org/download.html",
+            ],
+            _ => &[],
+        }
+    }
+}
+
+impl fmt::Display for Suggestion {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(&self.render(Language::Chinese))
+    }
+}
+
+/// 输出建议列表 (Render Suggestions)
+///
+/// 命令行程序在错误信息之后输出的列表：标题行之后每条建议一个 `•`，
+/// 建议的后续行（如各平台的安装方法）再缩进一级。
+///
+/// # 参数
+/// - `suggestions` - 建议，按顺序输出
+/// - `language` - 输出语言
+///
+/// # 返回值
+/// - 多行文本；没有建议时为 `None`
+///
+/// # 示例
+/// ```
+/// use lra_calculator_rust::suggestion::{render_suggestions, Language, Suggestion};
+///
+/// let text = render_suggestions(&[Suggestion::CheckPathSpelling, Suggestion::CheckPathMoved], Language::Chinese).unwrap();
+/// assert_eq!(text, "💡 建议:\n   • 检查路径拼写是否正确，是否使用了正确的路径分隔符\n   • 确认文件夹没有被删除或移动");
+/// assert_eq!(render_suggestions(&[], Language::Chinese), None);
+/// ```
+pub fn render_suggestions(suggestions: &[Suggestion], language: Language) -> Option<String> {
+    if suggestions.is_empty() {
+        return None;
+    }
+    let title = match language {
+        Language::Chinese => human_format!("💡 建议:"),
+        Language::English => human_format!("💡 Suggestions:"),
+    };
+    let mut lines = vec![title];
+    for suggestion in suggestions {
+        lines.push(human_format!("   • {}", suggestion.text(language)));
+        lines.extend(suggestion.details(language).iter().map(|detail| format!("     - {}", detail)));
+    }
+    Some(lines.join("\n"))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const ALL: [Suggestion; 15] = [
+        Suggestion::InstallFfmpeg,
+        Suggestion::ReinstallFfmpeg,
+        Suggestion::InstallFullFfmpegBuild,
+        Suggestion::CheckPathSpelling,
+        Suggestion::CheckPathMoved,
+        Suggestion::SelectFolder,
+        Suggestion::CheckPermissions,
+        Suggestion::CloseOtherPrograms,
+        Suggestion::UseOutputFlag,
+        Suggestion::DropFileSubcommand,
+        Suggestion::AddExtension,
+        Suggestion::UpgradeProgram,
+        Suggestion::CheckAudioFile,
+        Suggestion::CheckAudioDuration,
+        Suggestion::CheckFfmpegVersion,
+    ];
+
+    /// 测试每条建议在两种语言下都有不同的非空文本
+    #[test]
+    fn test_every_suggestion_renders_in_both_languages() {
+        for suggestion in ALL {
+            let chinese = suggestion.render(Language::Chinese);
+            let english = suggestion.render(Language::English);
+            assert!(!chinese.is_empty() && !english.is_empty(), "{:?}", suggestion);
+            assert_ne!(chinese, english, "{:?}", suggestion);
+            assert_eq!(suggestion.to_string(), chinese);
+        }
+    }
+
+    /// 测试多行建议的后续行缩进为子项
+    #[test]
+    fn test_render_install_suggestion() {
+        let text = render_suggestions(&[Suggestion::InstallFfmpeg], Language::Chinese).unwrap();
+        assert_eq!(
+            text.lines().collect::<Vec<_>>(),
+            [
+                "💡 建议:",
+                "   • 安装 FFmpeg 并添加到 PATH 环境变量中",
+                "     - macOS: brew install ffmpeg",
+                "     - Ubuntu/Debian: sudo apt install ffmpeg",
+                "     - Windows: choco install ffmpeg 或从官网下载",
+                "     - 其他系统: 请访问 https://ffmpeg.org/download.html",
+            ]
+        );
+    }
+}
@@ -0,0 +1,289 @@
+//! 表格渲染模块 (Table Rendering Module)
+//!
+//! 把 (显示路径, LRA) 条目渲染成列对齐的控制台表格，供 `stats --top` 等命令使用
+//! （`history` 子命令等其他表格使用通用的 [`render_table`]）：
+//!
+//! - 宽度按显示宽度计算（`unicode-width`），中日韩字符占两列，不会错位
+//! - 路径过长时截掉中间部分（`…`，`--ascii` 时为 `...`），尽量保留文件名
+//! - LRA 列右对齐
+//!
+//! [`render_results_table`] 是纯函数，终端宽度由调用方传入；
+//! [`terminal_width`] 在 stdout 不是终端时返回 [`FALLBACK_WIDTH`]。
+
+use std::io::{self, IsTerminal};
+
+use unicode_width::{UnicodeWidthChar, UnicodeWidthStr};
+
+use crate::console::{output_charset, Glyph};
+use crate::file_key::FileKey;
+use crate::precision::LraValue;
+
+/// 无法检测终端宽度时使用的宽度
+pub const FALLBACK_WIDTH: usize = 100;
+
+/// 路径列的最小宽度，终端再窄也不会更小
+const MIN_PATH_WIDTH: usize = 12;
+
+/// 列之间的间隔
+const COLUMN_GAP: &str = "  ";
+
+/// 检测终端宽度 (Terminal Width)
+///
+/// # 返回值
+/// - stdout 是终端时返回终端的列数，否则返回 [`FALLBACK_WIDTH`]
+pub fn terminal_width() -> usize {
+    if !io::stdout().is_terminal() {
+        return FALLBACK_WIDTH;
+    }
+    terminal_size::terminal_size()
+        .map(|(terminal_size::Width(width), _)| width as usize)
+        .filter(|width| *width > 0)
+        .unwrap_or(FALLBACK_WIDTH)
+}
+
+/// 字符串的显示宽度（中日韩字符占两列）
+pub fn display_width(text: &str) -> usize {
+    text.width()
+}
+
+/// 从开头截取不超过 `max_width` 列的前缀
+fn take_prefix(text: &str, max_width: usize) -> &str {
+    let mut width = 0;
+    for (index, ch) in text.char_indices() {
+        width += ch.width().unwrap_or(0);
+        if width > max_width {
+            return &text[..index];
+        }
+    }
+    text
+}
+
+/// 从末尾截取不超过 `max_width` 列的后缀
+fn take_suffix(text: &str, max_width: usize) -> &str {
+    let mut width = 0;
+    for (index, ch) in text.char_indices().rev() {
+        width += ch.width().unwrap_or(0);
+        if width > max_width {
+            return &text[index + ch.len_utf8()..];
+        }
+    }
+    text
+}
+
+/// 截掉中间部分，使显示宽度不超过 `max_width` (Truncate in the Middle)
+///
+/// 路径中有目录时优先完整保留 `/文件名`，只截断目录部分；
+/// 文件名本身也放不下时，只保留文件名的开头和结尾（包括扩展名）。
+///
+/// # 示例
+/// ```
+/// use lra_calculator_rust::table::truncate_middle;
+///
+/// assert_eq!(truncate_middle("Artist/Album/01 Track.flac", 20), "Artis…/01 Track.flac");
+/// assert_eq!(truncate_middle("short.flac", 20), "short.flac");
+/// ```
+pub fn truncate_middle(text: &str, max_width: usize) -> String {
+    if display_width(text) <= max_width {
+        return text.to_string();
+    }
+    let ellipsis = Glyph::Ellipsis.as_str(output_charset());
+    let budget = max_width.saturating_sub(display_width(ellipsis));
+
+    let file_name = match text.rfind('/') {
+        Some(separator) => {
+            let tail = &text[separator..];
+            if let Some(head_width) = budget.checked_sub(display_width(tail)) {
+                return format!("{}{}{}", take_prefix(&text[..separator], head_width), ellipsis, tail);
+            }
+            &text[separator + 1..]
+        }
+        None => text,
+    };
+    if display_width(file_name) <= max_width {
+        return file_name.to_string();
+    }
+
+    let head_width = budget / 2;
+    let tail_width = budget - head_width;
+    format!("{}{}{}", take_prefix(file_name, head_width), ellipsis, take_suffix(file_name, tail_width))
+}
+
+/// 用空格补齐到指定显示宽度（左对齐）
+fn pad_right(text: &str, width: usize) -> String {
+    format!("{}{}", text, " ".repeat(width.saturating_sub(display_width(text))))
+}
+
+/// 用空格补齐到指定显示宽度（右对齐）
+fn pad_left(text: &str, width: usize) -> String {
+    format!("{}{}", " ".repeat(width.saturating_sub(display_width(text))), text)
+}
+
+/// 渲染结果表格 (Render Results Table)
+///
+/// 第一行为表头，第二行为分隔线，之后每个条目一行，顺序与输入相同。
+/// 路径列的宽度取最长路径和可用宽度中的较小值，过长的路径见 [`truncate_middle`]。
+/// 除非终端窄到连最小路径列都放不下，每行的显示宽度都不超过 `width`。
+///
+/// # 参数
+/// - `entries` - (显示路径, LRA) 条目
+/// - `width` - 可用的终端宽度（列数）
+/// - `precision` - LRA 值保留的小数位数
+///
+/// # 返回值
+/// - 表格的各行，不含换行符
+///
+/// # 示例
+/// ```
+/// use lra_calculator_rust::table::render_results_table;
+///
+/// let lines = render_results_table(&[("a.flac".into(), 12.5), ("b.flac".into(), 3.0)], 80, 1);
+/// assert_eq!(lines[2], "a.flac      12.5");
+/// assert_eq!(lines[3], "b.flac       3.0");
+/// ```
+pub fn render_results_table(entries: &[(FileKey, f64)], width: usize, precision: u8) -> Vec<String> {
+    const PATH_HEADER: &str = "文件";
+    const LRA_HEADER: &str = "LRA (LU)";
+
+    let values: Vec<String> =
+        entries.iter().map(|(_, lra)| LraValue(*lra).with_precision(precision).to_string()).collect();
+    let lra_width = values
+        .iter()
+        .map(|value| display_width(value))
+        .chain([display_width(LRA_HEADER)])
+        .max()
+        .unwrap_or(0);
+
+    let available = width.saturating_sub(lra_width + COLUMN_GAP.len()).max(MIN_PATH_WIDTH);
+    let path_width = entries
+        .iter()
+        .map(|(path, _)| display_width(path))
+        .chain([display_width(PATH_HEADER)])
+        .max()
+        .unwrap_or(0)
+        .min(available);
+
+    let mut lines = Vec::with_capacity(entries.len() + 2);
+    lines.push(format!(
+        "{}{}{}",
+        pad_right(PATH_HEADER, path_width),
+        COLUMN_GAP,
+        pad_left(LRA_HEADER, lra_width)
+    ));
+    lines.push("-".repeat(path_width + COLUMN_GAP.len() + lra_width));
+    for ((path, _), value) in entries.iter().zip(&values) {
+        lines.push(format!(
+            "{}{}{}",
+            pad_right(&truncate_middle(path, path_width), path_width),
+            COLUMN_GAP,
+            pad_left(value, lra_width)
+        ));
+    }
+    lines
+}
+
+/// 列的对齐方式
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Align {
+    /// 左对齐（文本）
+    Left,
+    /// 右对齐（数值）
+    Right,
+}
+
+/// 渲染通用表格 (Render Table)
+///
+/// 每列的宽度取表头和该列所有单元格中最宽的一个，不截断内容。
+/// 最后一列左对齐时不补尾随空格。
+///
+/// # 参数
+/// - `headers` - 表头，同时决定列数
+/// - `alignments` - 各列的对齐方式，与 `headers` 一一对应
+/// - `rows` - 各行的单元格，多出的单元格被忽略，缺少的视为空
+///
+/// # 返回值
+/// - 表格的各行（表头、分隔线、数据行），不含换行符
+///
+/// # 示例
+/// ```
+/// use lra_calculator_rust::table::{render_table, Align};
+///
+/// let rows = vec![vec!["2024".to_string(), "9.5".to_string()]];
+/// let lines = render_table(&["年份", "平均 LRA"], &[Align::Left, Align::Right], &rows);
+/// assert_eq!(lines, vec!["年份  平均 LRA", "--------------", "2024       9.5"]);
+/// ```
+pub fn render_table(headers: &[&str], alignments: &[Align], rows: &[Vec<String>]) -> Vec<String> {
+    let cell = |row: &[String], column: usize| row.get(column).map_or("", String::as_str).to_string();
+    let widths: Vec<usize> = (0..headers.len())
+        .map(|column| {
+            rows.iter()
+                .map(|row| display_width(&cell(row, column)))
+                .chain([display_width(headers[column])])
+                .max()
+                .unwrap_or(0)
+        })
+        .collect();
+
+    let render_row = |cells: Vec<String>| {
+        let last = cells.len().saturating_sub(1);
+        let padded: Vec<String> = cells
+            .iter()
+            .enumerate()
+            .map(|(column, text)| match alignments.get(column) {
+                Some(Align::Right) => pad_left(text, widths[column]),
+                _ if column == last => text.clone(),
+                _ => pad_right(text, widths[column]),
+            })
+            .collect();
+        padded.join(COLUMN_GAP)
+    };
+
+    let mut lines = Vec::with_capacity(rows.len() + 2);
+    lines.push(render_row(headers.iter().map(|header| header.to_string()).collect()));
+    lines.push("-".repeat(widths.iter().sum::<usize>() + COLUMN_GAP.len() * widths.len().saturating_sub(1)));
+    for row in rows {
+        lines.push(render_row((0..headers.len()).map(|column| cell(row, column)).collect()));
+    }
+    lines
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// 测试中间截断保留文件名并正确计算中日韩字符宽度
+    #[test]
+    fn test_truncate_middle() {
+        assert_eq!(display_width("周杰伦/晴天.flac"), 16);
+        assert_eq!(truncate_middle("周杰伦/七里香/晴天.flac", 16), "周杰…/晴天.flac");
+        assert!(display_width(&truncate_middle("周杰伦/七里香/晴天.flac", 15)) <= 15);
+
+        // 文件名本身放不下时保留开头和扩展名
+        let truncated = truncate_middle("dir/a very long file name.flac", 15);
+        assert_eq!(truncated, "a very …me.flac");
+        assert_eq!(truncate_middle("no directory at all.flac", 11), "no di….flac");
+        assert_eq!(truncate_middle("x", 1), "x");
+    }
+
+    /// 测试表格的列对齐和宽度限制
+    #[test]
+    fn test_render_results_table() {
+        let entries: Vec<(FileKey, f64)> = vec![
+            ("周杰伦/七里香/晴天.flac".into(), 8.3),
+            ("Pink Floyd/The Wall/Comfortably Numb.flac".into(), 12.0),
+            ("a.mp3".into(), 15.0),
+        ];
+
+        let lines = render_results_table(&entries, 40, 1);
+        assert_eq!(lines.len(), 5);
+        assert!(lines.iter().all(|line| display_width(line) == 40), "{:?}", lines);
+        assert!(lines[0].starts_with("文件") && lines[0].ends_with("LRA (LU)"));
+        assert!(lines[2].starts_with("周杰伦/七里香/晴天.flac ") && lines[2].ends_with("     8.3"));
+        assert!(lines[3].starts_with("Pink Fl…/Comfortably Numb.flac"));
+        assert!(lines[4].ends_with("15.0"));
+
+        // 宽度足够时路径列只取最长路径的宽度
+        let wide = render_results_table(&entries, 200, 1);
+        assert_eq!(display_width(&wide[0]), display_width(&entries[1].0) + 2 + 8);
+        assert_eq!(render_results_table(&[], 80, 1).len(), 2);
+    }
+}
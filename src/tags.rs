@@ -0,0 +1,135 @@
+//! 标签模块 (Tags Module)
+//!
+//! `--with-tags` 为每个成功分析的文件读取艺术家、专辑和标题标签，
+//! 作为附加列写入结果文件，报告中可以看到 "Pink Floyd — Time" 而不只是文件路径。
+//!
+//! 标签由 ffprobe 读取（见 [`crate::probe::run_ffprobe`]）。`--probe-unknown`
+//! 探测时已经调用过 ffprobe 的文件直接复用当次读到的标签，不会再启动第二个进程。
+
+use std::collections::HashMap;
+use std::path::PathBuf;
+use std::sync::atomic::{AtomicUsize, Ordering};
+
+use rayon::prelude::*;
+
+use crate::file_key::FileKey;
+use crate::probe::run_ffprobe;
+
+/// 读取的标签键（ffprobe 按不区分大小写的方式匹配）
+pub const TAG_KEYS: [&str; 3] = ["artist", "album", "title"];
+
+/// 结果文件中标签列的列名，与 [`TrackTags::fields`] 的顺序一致
+pub const TAG_COLUMNS: [&str; 3] = ["艺术家", "专辑", "标题"];
+
+/// 音轨标签 (Track Tags)
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct TrackTags {
+    /// 艺术家
+    pub artist: Option<String>,
+    /// 专辑
+    pub album: Option<String>,
+    /// 标题
+    pub title: Option<String>,
+}
+
+impl TrackTags {
+    /// 记录一个标签；键名不区分大小写，已有值或空值时忽略
+    pub fn set(&mut self, key: &str, value: &str) {
+        let slot = match key.to_ascii_lowercase().as_str() {
+            "artist" => &mut self.artist,
+            "album" => &mut self.album,
+            "title" => &mut self.title,
+            _ => return,
+        };
+        let value = value.trim();
+        if slot.is_none() && !value.is_empty() {
+            *slot = Some(value.to_string());
+        }
+    }
+
+    /// 用 `fallback` 补齐缺少的标签
+    pub fn or(self, fallback: TrackTags) -> TrackTags {
+        TrackTags {
+            artist: self.artist.or(fallback.artist),
+            album: self.album.or(fallback.album),
+            title: self.title.or(fallback.title),
+        }
+    }
+
+    /// 结果文件中的列值：艺术家、专辑、标题
+    ///
+    /// 缺少的标签为空字符串；制表符和换行符替换为空格，保证不会破坏列结构。
+    ///
+    /// # 示例
+    /// ```
+    /// use lra_calculator_rust::tags::TrackTags;
+    ///
+    /// let mut tags = TrackTags::default();
+    /// tags.set("ARTIST", "Pink Floyd");
+    /// tags.set("title", "Time\tLive");
+    /// assert_eq!(tags.fields(), ["Pink Floyd", "", "Time Live"]);
+    /// ```
+    pub fn fields(&self) -> [String; 3] {
+        let render = |tag: &Option<String>| {
+            tag.as_deref()
+                .unwrap_or("")
+                .chars()
+                .map(|c| if c.is_control() { ' ' } else { c })
+                .collect()
+        };
+        [render(&self.artist), render(&self.album), render(&self.title)]
+    }
+}
+
+/// 并行读取标签 (Read Tags)
+///
+/// ffprobe 无法执行的文件记为没有标签。
+///
+/// # 参数
+/// - `files` - 要读取的文件：(完整路径, 显示路径)
+/// - `on_progress` - 每读取完一个文件调用一次，参数为 (已完成数, 总数)
+///
+/// # 返回值
+/// - 显示路径到标签的映射
+pub fn read_tags<F>(files: Vec<(PathBuf, FileKey)>, on_progress: F) -> HashMap<FileKey, TrackTags>
+where
+    F: Fn(usize, usize) + Sync,
+{
+    let total = files.len();
+    let done = AtomicUsize::new(0);
+    files
+        .into_par_iter()
+        .map(|(full_path, display_path)| {
+            let tags = run_ffprobe(&full_path).map(|report| report.tags).unwrap_or_default();
+            on_progress(done.fetch_add(1, Ordering::SeqCst) + 1, total);
+            (display_path, tags)
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// 测试标签的记录、补齐和列值渲染
+    #[test]
+    fn test_track_tags() {
+        let mut tags = TrackTags::default();
+        tags.set("Artist", "  Pink Floyd ");
+        tags.set("artist", "Ignored");
+        tags.set("album", "");
+        tags.set("comment", "not a column");
+        assert_eq!(tags.artist.as_deref(), Some("Pink Floyd"));
+        assert_eq!(tags.album, None);
+
+        let mut fallback = TrackTags::default();
+        fallback.set("album", "The Dark Side of the Moon");
+        fallback.set("artist", "Other");
+        let merged = tags.or(fallback);
+        assert_eq!(merged.fields(), ["Pink Floyd", "The Dark Side of the Moon", ""]);
+
+        let mut multiline = TrackTags::default();
+        multiline.set("title", "Line 1\nLine 2");
+        assert_eq!(multiline.fields()[2], "Line 1 Line 2");
+    }
+}
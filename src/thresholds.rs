@@ -0,0 +1,249 @@
+//! 阈值检查模块 (Threshold Checks Module)
+//!
+//! 在 CI 中用于门禁：处理完成后检查每个成功结果的 LRA 和综合响度是否在允许范围内，
+//! 任何违规都会让程序以独立的退出码失败（见 [`crate::error::AppError::ThresholdViolated`]）。
+//!
+//! 检查逻辑是纯函数 [`evaluate_thresholds`]，不涉及文件或输出，便于测试。
+
+use std::fmt;
+
+use crate::file_key::FileKey;
+
+/// LRA 阈值配置 (LRA Thresholds)
+///
+/// 未设置的阈值不参与检查。
+#[derive(Debug, Clone, Copy, Default, PartialEq)]
+pub struct LraThresholds {
+    /// LRA 上限（`--fail-if-lra-above`），大于该值视为违规
+    pub max_lra: Option<f64>,
+    /// LRA 下限（`--fail-if-lra-below`），小于该值视为违规
+    pub min_lra: Option<f64>,
+    /// 综合响度允许的范围（`--fail-if-lufs-outside`），范围之外视为违规；综合响度未知的文件不检查
+    pub lufs_range: Option<LufsRange>,
+}
+
+impl LraThresholds {
+    /// 是否没有设置任何阈值
+    pub fn is_empty(&self) -> bool {
+        self.max_lra.is_none() && self.min_lra.is_none() && self.lufs_range.is_none()
+    }
+}
+
+/// 综合响度范围 (LUFS Range)，对应 `--fail-if-lufs-outside A:B`
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct LufsRange {
+    /// 下限（LUFS）
+    pub min: f64,
+    /// 上限（LUFS）
+    pub max: f64,
+}
+
+impl LufsRange {
+    /// 解析 `A:B` 形式的范围 (Parse)
+    ///
+    /// # 返回值
+    /// - `Some(LufsRange)` - 两端都是有限数且 A 不大于 B
+    ///
+    /// # 示例
+    /// ```
+    /// use lra_calculator_rust::thresholds::LufsRange;
+    ///
+    /// assert_eq!(LufsRange::parse("-24:-14"), Some(LufsRange { min: -24.0, max: -14.0 }));
+    /// assert_eq!(LufsRange::parse(" -16 : -16 "), Some(LufsRange { min: -16.0, max: -16.0 }));
+    /// assert_eq!(LufsRange::parse("-14:-24"), None);
+    /// assert_eq!(LufsRange::parse("-24-14"), None);
+    /// ```
+    pub fn parse(text: &str) -> Option<Self> {
+        let (min, max) = text.split_once(':')?;
+        let range = Self { min: min.trim().parse().ok()?, max: max.trim().parse().ok()? };
+        (range.min.is_finite() && range.max.is_finite() && range.min <= range.max).then_some(range)
+    }
+
+    /// 是否在范围内（包含两端）
+    pub fn contains(&self, lufs: f64) -> bool {
+        (self.min..=self.max).contains(&lufs)
+    }
+}
+
+impl fmt::Display for LufsRange {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}:{}", self.min, self.max)
+    }
+}
+
+/// 被违反的规则 (Threshold Rule)
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum ThresholdRule {
+    /// LRA 高于上限
+    LraAbove(f64),
+    /// LRA 低于下限
+    LraBelow(f64),
+    /// 综合响度在范围之外
+    LufsOutside(LufsRange),
+}
+
+impl fmt::Display for ThresholdRule {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ThresholdRule::LraAbove(limit) => write!(f, "--fail-if-lra-above {}", limit),
+            ThresholdRule::LraBelow(limit) => write!(f, "--fail-if-lra-below {}", limit),
+            ThresholdRule::LufsOutside(range) => write!(f, "--fail-if-lufs-outside {}", range),
+        }
+    }
+}
+
+/// 阈值违规记录 (Threshold Violation)
+#[derive(Debug, Clone, PartialEq)]
+pub struct ThresholdViolation {
+    /// 文件显示路径
+    pub path: FileKey,
+    /// 文件的 LRA 值
+    pub lra: f64,
+    /// 文件的综合响度（LUFS），未知时为 `None`
+    pub integrated_lufs: Option<f64>,
+    /// 被违反的规则
+    pub rule: ThresholdRule,
+}
+
+impl fmt::Display for ThresholdViolation {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match (self.rule, self.integrated_lufs) {
+            (ThresholdRule::LufsOutside(_), Some(lufs)) => {
+                write!(f, "文件 '{}': 综合响度 {:.1} LUFS 违反规则 {}", self.path, lufs, self.rule)
+            }
+            _ => write!(f, "文件 '{}': LRA {:.1} LU 违反规则 {}", self.path, self.lra, self.rule),
+        }
+    }
+}
+
+/// 检查结果是否满足阈值 (Evaluate Thresholds)
+///
+/// 按输入顺序返回所有违规记录；同一文件可能同时违反多条规则。
+/// 综合响度未知（如增量更新时沿用的旧条目）的文件不检查综合响度范围。
+///
+/// # 参数
+/// - `results` - 成功处理的结果：(显示路径, LRA 值, 综合响度)
+/// - `thresholds` - 阈值配置
+///
+/// # 返回值
+/// - 违规记录列表，为空表示全部通过
+///
+/// # 示例
+/// ```
+/// use lra_calculator_rust::thresholds::{evaluate_thresholds, LraThresholds, LufsRange};
+///
+/// let results = vec![("a.flac".into(), 18.0, Some(-16.0)), ("b.flac".into(), 9.0, Some(-9.5))];
+/// let thresholds = LraThresholds { max_lra: Some(15.0), ..LraThresholds::default() };
+/// let violations = evaluate_thresholds(&results, &thresholds);
+/// assert_eq!(violations.len(), 1);
+/// assert_eq!(violations[0].path, "a.flac");
+///
+/// let thresholds = LraThresholds { lufs_range: LufsRange::parse("-24:-14"), ..LraThresholds::default() };
+/// assert_eq!(evaluate_thresholds(&results, &thresholds)[0].path, "b.flac");
+/// ```
+pub fn evaluate_thresholds(
+    results: &[(FileKey, f64, Option<f64>)],
+    thresholds: &LraThresholds,
+) -> Vec<ThresholdViolation> {
+    let mut violations = Vec::new();
+
+    for (path, lra, integrated_lufs) in results {
+        let mut violate = |rule| {
+            violations.push(ThresholdViolation { path: path.clone(), lra: *lra, integrated_lufs: *integrated_lufs, rule });
+        };
+
+        if let Some(max) = thresholds.max_lra {
+            if *lra > max {
+                violate(ThresholdRule::LraAbove(max));
+            }
+        }
+        if let Some(min) = thresholds.min_lra {
+            if *lra < min {
+                violate(ThresholdRule::LraBelow(min));
+            }
+        }
+        if let (Some(range), Some(lufs)) = (thresholds.lufs_range, integrated_lufs) {
+            if !range.contains(*lufs) {
+                violate(ThresholdRule::LufsOutside(range));
+            }
+        }
+    }
+
+    violations
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn results() -> Vec<(FileKey, f64, Option<f64>)> {
+        vec![
+            ("loud.mp3".into(), 3.0, Some(-8.0)),
+            ("ok.flac".into(), 9.5, Some(-16.0)),
+            ("dynamic.wav".into(), 21.0, Some(-23.0)),
+        ]
+    }
+
+    /// 测试未设置阈值时全部通过
+    #[test]
+    fn test_no_thresholds() {
+        let thresholds = LraThresholds::default();
+        assert!(thresholds.is_empty());
+        assert!(evaluate_thresholds(&results(), &thresholds).is_empty());
+    }
+
+    /// 测试上下限同时检查
+    #[test]
+    fn test_above_and_below() {
+        let thresholds = LraThresholds { max_lra: Some(15.0), min_lra: Some(5.0), lufs_range: None };
+        let violations = evaluate_thresholds(&results(), &thresholds);
+
+        assert_eq!(
+            violations,
+            vec![
+                ThresholdViolation {
+                    path: "loud.mp3".into(),
+                    lra: 3.0,
+                    integrated_lufs: Some(-8.0),
+                    rule: ThresholdRule::LraBelow(5.0),
+                },
+                ThresholdViolation {
+                    path: "dynamic.wav".into(),
+                    lra: 21.0,
+                    integrated_lufs: Some(-23.0),
+                    rule: ThresholdRule::LraAbove(15.0),
+                },
+            ]
+        );
+        assert!(violations[1].to_string().contains("--fail-if-lra-above 15"));
+    }
+
+    /// 测试边界值不算违规
+    #[test]
+    fn test_boundaries_are_inclusive() {
+        let thresholds = LraThresholds { max_lra: Some(21.0), min_lra: Some(3.0), lufs_range: LufsRange::parse("-23:-8") };
+        assert!(evaluate_thresholds(&results(), &thresholds).is_empty());
+    }
+
+    /// 测试综合响度范围：范围之外的文件违规，可以与 LRA 规则同时违反；综合响度未知的文件不检查
+    #[test]
+    fn test_lufs_outside() {
+        let range = LufsRange { min: -20.0, max: -14.0 };
+        let thresholds = LraThresholds { max_lra: Some(15.0), lufs_range: Some(range), ..LraThresholds::default() };
+        let mut results = results();
+        results.push(("unknown.ogg".into(), 8.0, None));
+
+        let violations = evaluate_thresholds(&results, &thresholds);
+        let summary: Vec<_> = violations.iter().map(|violation| (violation.path.as_str(), violation.rule)).collect();
+        assert_eq!(
+            summary,
+            vec![
+                ("loud.mp3", ThresholdRule::LufsOutside(range)),
+                ("dynamic.wav", ThresholdRule::LraAbove(15.0)),
+                ("dynamic.wav", ThresholdRule::LufsOutside(range)),
+            ]
+        );
+        assert_eq!(violations[0].to_string(), "文件 'loud.mp3': 综合响度 -8.0 LUFS 违反规则 --fail-if-lufs-outside -20:-14");
+        assert_eq!(violations[1].to_string(), "文件 'dynamic.wav': LRA 21.0 LU 违反规则 --fail-if-lra-above 15");
+    }
+}
@@ -6,13 +6,13 @@
 //! ## 核心功能
 //!
 //! ### 用户交互 (User Interaction)
-//! - 路径输入和验证：安全地获取用户输入的文件夹路径
+//! - 路径输入和验证：安全地获取用户输入的文件夹路径（交互式输入见 `prompt`，需要 `cli` 特性）
 //! - 输入验证：确保路径存在、可访问且为目录
 //! - 错误处理：提供友好的错误信息和重试机制
 //!
 //! ### 文件操作 (File Operations)
 //! - 结果文件排序：按 LRA 值对结果进行排序
-//! - 文件格式处理：解析和格式化结果文件
+//! - 文件格式处理：解析和格式化结果文件（可选 UTF-8 BOM 和 CRLF 换行，见 [`TextFormat`]）
 //! - 错误恢复：处理文件操作中的各种异常情况
 //!
 //! ### 数据处理 (Data Processing)
@@ -27,117 +27,241 @@
 //! - **可测试性**: 函数设计便于单元测试
 //! - **性能优化**: 使用高效的算法和数据结构
 
-use std::fs::File;
+use std::collections::HashMap;
+use std::ffi::OsString;
+use std::fmt;
+use std::fs::{self, File};
 use std::io::{self, BufRead, BufReader, BufWriter, Write};
 use std::path::{Path, PathBuf};
+use std::sync::mpsc;
+use std::thread;
+use std::time::Duration;
+
+use rayon::prelude::*;
+
+use crate::audio::{extract_file_extension, is_supported_audio_format};
+use crate::error::{AppError, PathErrorKind, ResultLineParseError, ResultsVerificationError};
+use crate::file_key::FileKey;
+use crate::precision::{decimal_places, is_possible_lra, LraValue, DEFAULT_LRA_PRECISION};
+use crate::processor::{LraDistribution, ProcessingStats};
+use crate::run_id::RunId;
+
+#[cfg(feature = "cli")]
+pub mod history;
+pub mod lock;
+#[cfg(feature = "cli")]
+pub mod prompt;
+pub mod results_set;
+pub mod stream;
+pub mod template;
+
+#[cfg(feature = "cli")]
+pub use prompt::{get_folder_path_from_user, get_folder_path_with_history, get_folder_path_with_options, PromptOptions};
+pub use results_set::{MergePolicy, ResultsSet};
+
+/// 解析命令行传入的文件夹路径 (Resolve Folder Argument)
+///
+/// 对命令行参数中的文件夹执行与交互提示相同的验证和规范化。
+///
+/// # 参数
+/// - `path` - 命令行传入的路径（`~` 和环境变量已展开）
+///
+/// # 返回值
+/// - `Ok(PathBuf)` - 规范化后的绝对路径
+/// - `Err(AppError::Path)` - 路径无效
+pub fn resolve_folder_argument(path: &Path) -> Result<PathBuf, AppError> {
+    validate_folder_path(path)?;
+    canonicalize_path(path).map_err(|message| AppError::Path { kind: PathErrorKind::Invalid, message })
+}
 
-use crate::error::AppError;
-
-/// 从用户输入获取要处理的文件夹路径 (Get Folder Path from User Input)
+/// 规范化拖放输入的路径 (Normalize Drag-and-Dropped Path)
+///
+/// 在 macOS 和 Windows 上把文件夹拖到终端时，路径会被加上引号
+/// （`"C:\Users\me\My Music"`）或对空格进行反斜杠转义（`/Users/me/My\ Music`）。
+/// 这个函数在构造 `PathBuf` 之前去掉这些修饰：
+///
+/// - 去除首尾空白字符
+/// - 去除成对的外层单引号或双引号
+/// - 在 Unix 上将 `\ `、`\(` 等反斜杠转义还原为原字符
+///   （Windows 上反斜杠是路径分隔符，不做处理）
+///
+/// 路径内部的引号字符（如 `Rock'n'Roll`）会被保留。
+///
+/// # 参数
+/// - `input` - 用户输入的原始字符串
 ///
-/// 这是程序与用户交互的核心函数，负责安全地获取用户输入的文件夹路径。
-/// 它实现了一个健壮的输入循环，包含完整的验证和错误处理机制。
+/// # 返回值
+/// - 规范化后的路径字符串
+pub fn normalize_dropped_path(input: &str) -> String {
+    normalize_dropped_path_with(input, cfg!(unix))
+}
+
+/// 规范化拖放路径的实现，可指定是否还原反斜杠转义
+fn normalize_dropped_path_with(input: &str, unescape_backslashes: bool) -> String {
+    let trimmed = input.trim();
+
+    // 去除成对的外层引号；引号内的内容按字面处理，不再还原转义
+    for quote in ['"', '\''] {
+        if trimmed.len() >= 2 && trimmed.starts_with(quote) && trimmed.ends_with(quote) {
+            return trimmed[1..trimmed.len() - 1].to_string();
+        }
+    }
+
+    if !unescape_backslashes {
+        return trimmed.to_string();
+    }
+
+    let mut normalized = String::with_capacity(trimmed.len());
+    let mut chars = trimmed.chars().peekable();
+    while let Some(c) = chars.next() {
+        if c == '\\' {
+            if let Some(&next) = chars.peek() {
+                if !next.is_alphanumeric() && next != '/' {
+                    normalized.push(next);
+                    chars.next();
+                    continue;
+                }
+            }
+        }
+        normalized.push(c);
+    }
+    normalized
+}
+
+/// 展开路径中的 `~` 和环境变量 (Expand `~` and Environment Variables)
 ///
-/// ## 交互流程
+/// 用户在提示符中输入的路径不会经过 shell 处理，因此 `~/Music` 或
+/// `$HOME/Music` 会被当作字面路径。这个函数在验证之前完成展开：
 ///
-/// ### 输入提示
-/// - 显示清晰的中文提示信息
-/// - 说明期望的输入格式和要求
-/// - 提供示例路径格式
+/// - 开头的 `~` 展开为当前用户主目录（`HOME`，Windows 上为 `USERPROFILE`）
+/// - 开头的 `~user` 展开为主目录同级的 `user` 目录
+/// - `$VAR` 和 `${VAR}` 展开为对应环境变量的值；`%VAR%` 只在 Windows 上展开
 ///
-/// ### 输入验证
-/// 1. **非空检查**: 确保用户输入不为空
-/// 2. **路径存在性**: 验证路径在文件系统中存在
-/// 3. **目录检查**: 确认路径指向目录而非文件
-/// 4. **权限验证**: 检查程序是否有读取权限
-/// 5. **路径规范化**: 转换为绝对路径，解析符号链接
+/// `$` 和 `%` 都是合法的文件名字符，所以字面路径已经存在时原样返回，
+/// 未设置的变量引用也保留为字面文本，由之后的路径验证报告路径不存在。
 ///
-/// ### 错误处理
-/// - 对每种错误提供具体的中文说明
-/// - 给出可能的解决方案和建议
-/// - 允许用户重新输入而不退出程序
+/// # 参数
+/// - `input` - 用户输入的路径字符串
 ///
-/// ## 安全考虑
+/// # 返回值
+/// 展开后的路径
+pub fn expand_path(input: &str) -> PathBuf {
+    if Path::new(input).exists() {
+        return PathBuf::from(input);
+    }
+    PathBuf::from(expand_path_with(input, |name| std::env::var(name).ok()))
+}
+
+/// 使用指定的变量查找函数展开路径 (Expand Path with Lookup)
 ///
-/// ### 路径安全
-/// - 使用 `PathBuf::from()` 安全地构造路径
-/// - 通过 `canonicalize()` 解析符号链接，防止路径遍历攻击
-/// - 验证最终路径的有效性
+/// [`expand_path`] 的纯函数版本，变量查找通过参数注入，便于测试。
+/// `%VAR%` 是否展开取决于当前平台。
 ///
-/// ### 输入安全
-/// - 使用 `trim()` 移除前后空白字符
-/// - 处理各种异常输入情况
-/// - 防止无限循环（通过合理的错误处理）
+/// # 参数
+/// - `input` - 要展开的路径字符串
+/// - `lookup` - 根据变量名返回变量值的函数
 ///
 /// # 返回值
-/// - `Ok(PathBuf)` - 经过验证和规范化的有效文件夹路径
-/// - `Err(Box<dyn std::error::Error>)` - 不可恢复的 I/O 错误（如标准输入不可用）
-///
-/// # 错误处理
-/// - 可恢复错误（如路径不存在）会提示用户重新输入
-/// - 不可恢复错误（如 I/O 失败）会返回错误并终止函数
-///
-/// # 使用示例
-/// ```rust
-/// match get_folder_path_from_user() {
-///     Ok(path) => println!("选择的路径: {}", path.display()),
-///     Err(e) => eprintln!("获取路径失败: {}", e),
-/// }
-/// ```
-pub fn get_folder_path_from_user() -> Result<PathBuf, Box<dyn std::error::Error>> {
-    // 显示友好的欢迎信息和使用提示
-    println!("\n📁 请选择要处理的音频文件夹");
-    println!("💡 提示: 程序将递归扫描该文件夹及其所有子文件夹中的音频文件");
-    println!("📝 支持的格式: WAV, MP3, FLAC, AAC, OGG, Opus, WMA, AIFF, ALAC");
-    println!();
-
-    loop {
-        // 显示输入提示
-        print!("请输入文件夹路径: ");
-        io::stdout().flush()?;  // 确保提示信息立即显示
-
-        // 读取用户输入
-        let mut input = String::new();
-        io::stdin().read_line(&mut input)?;
-        let path_str = input.trim();
-
-        // 检查输入是否为空
-        if path_str.is_empty() {
-            eprintln!("❌ 错误: 路径不能为空，请重新输入。");
-            continue;
-        }
-
-        // 处理特殊输入
-        if path_str == "quit" || path_str == "exit" || path_str == "q" {
-            return Err("用户取消操作".into());
-        }
-
-        // 构造路径对象
-        let path = PathBuf::from(path_str);
-
-        // 验证路径的有效性
-        match validate_folder_path(&path) {
-            Ok(()) => {
-                // 路径验证成功，尝试规范化
-                match canonicalize_path(&path) {
-                    Ok(canonical_path) => {
-                        println!("✅ 路径验证成功: {}", canonical_path.display());
-                        return Ok(canonical_path);
-                    }
-                    Err(e) => {
-                        eprintln!("❌ 路径规范化失败: {}", e);
-                        eprintln!("💡 建议: 请检查路径格式是否正确，或尝试使用绝对路径");
+/// 展开后的路径字符串；找不到值的引用保持原样
+pub fn expand_path_with<F>(input: &str, lookup: F) -> String
+where
+    F: Fn(&str) -> Option<String>,
+{
+    expand_path_for(input, lookup, cfg!(windows))
+}
+
+/// 展开路径，`percent_vars` 决定是否识别 Windows 风格的 `%VAR%`
+fn expand_path_for<F>(input: &str, lookup: F, percent_vars: bool) -> String
+where
+    F: Fn(&str) -> Option<String>,
+{
+    // 1. 展开开头的 ~ 或 ~user；找不到主目录时保持原样
+    let mut rest = input;
+    let mut expanded = String::with_capacity(input.len());
+    if let Some(after_tilde) = input.strip_prefix('~') {
+        let user_end = after_tilde
+            .find(['/', '\\'])
+            .unwrap_or(after_tilde.len());
+        let user = &after_tilde[..user_end];
+
+        if let Some(home_dir) = lookup("HOME").or_else(|| lookup("USERPROFILE")) {
+            if user.is_empty() {
+                expanded.push_str(&home_dir);
+            } else {
+                // ~user: 取当前主目录的上级目录，再拼接用户名
+                let parent = Path::new(&home_dir)
+                    .parent()
+                    .map(|p| p.to_string_lossy().into_owned())
+                    .unwrap_or_default();
+                expanded.push_str(&parent);
+                expanded.push(std::path::MAIN_SEPARATOR);
+                expanded.push_str(user);
+            }
+            rest = &after_tilde[user_end..];
+        }
+    }
+
+    // 2. 展开 $VAR、${VAR} 和（Windows 上的）%VAR%
+    let is_name_char = |c: char| c.is_ascii_alphanumeric() || c == '_';
+    let mut chars = rest.char_indices().peekable();
+    while let Some((index, c)) = chars.next() {
+        match c {
+            '$' => {
+                let after = &rest[index + 1..];
+                if let Some(braced) = after.strip_prefix('{') {
+                    if let Some(close) = braced.find('}') {
+                        let name = &braced[..close];
+                        match lookup(name) {
+                            Some(value) => expanded.push_str(&value),
+                            None => expanded.push_str(&rest[index..index + close + 3]),
+                        }
+                        // 跳过 "{name}"
+                        for _ in 0..name.chars().count() + 2 {
+                            chars.next();
+                        }
                         continue;
                     }
                 }
+                let name_len = after.find(|c: char| !is_name_char(c)).unwrap_or(after.len());
+                if name_len == 0 {
+                    expanded.push('$');
+                } else {
+                    let name = &after[..name_len];
+                    match lookup(name) {
+                        Some(value) => expanded.push_str(&value),
+                        None => {
+                            expanded.push('$');
+                            expanded.push_str(name);
+                        }
+                    }
+                    for _ in 0..name_len {
+                        chars.next();
+                    }
+                }
             }
-            Err(e) => {
-                eprintln!("❌ 路径验证失败: {}", e);
-                eprintln!("💡 提示: 输入 'q' 或 'quit' 退出程序");
-                continue;
+            '%' if percent_vars => {
+                let after = &rest[index + 1..];
+                match after.find('%') {
+                    Some(close) if close > 0 && after[..close].chars().all(is_name_char) => {
+                        match lookup(&after[..close]) {
+                            Some(value) => {
+                                expanded.push_str(&value);
+                                for _ in 0..close + 1 {
+                                    chars.next();
+                                }
+                            }
+                            // 未设置时只输出开头的 %，结尾的 % 可能是下一个引用的开头
+                            None => expanded.push('%'),
+                        }
+                    }
+                    _ => expanded.push('%'),
+                }
             }
+            _ => expanded.push(c),
         }
     }
+
+    expanded
 }
 
 /// 规范化路径 (Canonicalize Path)
@@ -188,7 +312,7 @@ fn canonicalize_path(path: &Path) -> Result<PathBuf, String> {
 ///
 /// ## 错误分类
 ///
-/// 不同类型的错误会返回不同的 `AppError::Path` 变体，包含：
+/// 不同类型的错误会返回附带不同建议（见 [`AppError::suggestions`]）的 `AppError::Path`，包含：
 /// - 路径不存在：可能是拼写错误或路径已被删除
 /// - 不是目录：用户可能误选了文件而非文件夹
 /// - 权限不足：需要管理员权限或文件夹被保护
@@ -207,23 +331,18 @@ fn canonicalize_path(path: &Path) -> Result<PathBuf, String> {
 pub fn validate_folder_path(path: &Path) -> Result<(), AppError> {
     // 检查路径是否存在
     if !path.exists() {
-        return Err(AppError::Path(format!(
-            "路径 '{}' 不存在。\n\
-             请检查：\n\
-             1. 路径拼写是否正确\n\
-             2. 文件夹是否已被删除或移动\n\
-             3. 是否使用了正确的路径分隔符",
-            path.display()
-        )));
+        return Err(AppError::Path {
+            kind: PathErrorKind::NotFound,
+            message: format!("路径 '{}' 不存在", path.display()),
+        });
     }
 
     // 检查是否为目录
     if !path.is_dir() {
-        return Err(AppError::Path(format!(
-            "路径 '{}' 不是一个目录。\n\
-             请确保选择的是文件夹而不是文件。",
-            path.display()
-        )));
+        return Err(AppError::Path {
+            kind: PathErrorKind::NotADirectory,
+            message: format!("路径 '{}' 不是一个目录", path.display()),
+        });
     }
 
     // 检查读取权限
@@ -233,20 +352,460 @@ pub fn validate_folder_path(path: &Path) -> Result<(), AppError> {
             Ok(())
         }
         Err(e) => {
-            Err(AppError::Path(format!(
-                "无法访问目录 '{}'。\n\
-                 错误详情: {}\n\
-                 可能的解决方案：\n\
-                 1. 检查文件夹权限设置\n\
-                 2. 以管理员身份运行程序\n\
-                 3. 确保文件夹未被其他程序占用",
-                path.display(),
-                e
-            )))
+            Err(AppError::Path {
+                kind: PathErrorKind::Unreadable,
+                message: format!("无法访问目录 '{}': {}", path.display(), e),
+            })
         }
     }
 }
 
+/// 验证单个音频文件路径 (Validate Audio File Path)
+///
+/// `file` 子命令直接分析用户给出的文件，与 [`validate_folder_path`] 相反，
+/// 要求路径是一个存在、可读、扩展名在全局格式注册表中的文件。
+///
+/// # 参数
+/// - `path` - 要验证的文件路径
+///
+/// # 返回值
+/// - `Ok(())` - 可以直接分析
+/// - `Err(AppError::Path)` - 路径不存在、是目录、扩展名不受支持或无法读取
+///
+/// # 示例
+/// ```
+/// use std::path::Path;
+/// use lra_calculator_rust::utils::validate_audio_file_path;
+///
+/// assert!(validate_audio_file_path(Path::new("/no/such/song.flac")).is_err());
+/// assert!(validate_audio_file_path(Path::new(".")).is_err());
+/// ```
+pub fn validate_audio_file_path(path: &Path) -> Result<(), AppError> {
+    if !path.exists() {
+        return Err(AppError::Path {
+            kind: PathErrorKind::NotFound,
+            message: format!("文件 '{}' 不存在", path.display()),
+        });
+    }
+    if path.is_dir() {
+        return Err(AppError::Path {
+            kind: PathErrorKind::IsADirectory,
+            message: format!("'{}' 是一个目录", path.display()),
+        });
+    }
+    if !extract_file_extension(path).is_some_and(|extension| is_supported_audio_format(&extension)) {
+        return Err(AppError::Path {
+            kind: PathErrorKind::UnsupportedFormat,
+            message: format!("文件 '{}' 不是支持的音频格式", path.display()),
+        });
+    }
+    File::open(path).map(drop).map_err(|e| AppError::Path {
+        kind: PathErrorKind::Unreadable,
+        message: format!("无法读取文件 '{}': {}", path.display(), e),
+    })
+}
+
+/// 结果文件中每行的估算平均字节数
+///
+/// 用于在运行前粗略估算结果文件大小。中文路径和较深的目录结构
+/// 会让行变长，因此取一个偏保守的值。
+pub const ESTIMATED_RESULT_LINE_BYTES: u64 = 128;
+
+/// 结果文件的表头行
+pub const RESULTS_HEADER_LINE: &str = "文件路径 (相对) - LRA 数值 (LU)";
+
+/// 结果文件的格式版本 (Results File Format Version)
+///
+/// - 版本 1：没有格式标记，表头之后直接是批注和数据行
+/// - 版本 2：表头之后的第 1 行是格式标记 `# format: 2`（见 [`FORMAT_METADATA_PREFIX`]）
+///
+/// 读取时两个版本都解析为 [`ResultsFileContents`]（见 [`ResultsFile`]），写入时总是使用最新版本。
+pub const RESULTS_FORMAT_VERSION: u32 = 2;
+
+/// 结果文件中记录格式版本的元数据行前缀
+pub const FORMAT_METADATA_PREFIX: &str = "# format: ";
+
+/// 结果文件中记录运行 ID 的元数据行前缀（见 [`RunId`]）
+pub const RUN_METADATA_PREFIX: &str = "# run: ";
+
+/// 运行 ID 元数据行 (Run Metadata Line)
+///
+/// `run_id` 为 `None` 时没有元数据行。
+pub fn run_metadata_line(run_id: Option<&RunId>) -> Option<String> {
+    run_id.map(|id| format!("{}{}", RUN_METADATA_PREFIX, id))
+}
+
+/// 从运行 ID 元数据行还原运行 ID；不是运行 ID 元数据行时返回 `None`
+fn run_id_from_metadata_line(line: &str) -> Option<RunId> {
+    RunId::parse(line.trim().strip_prefix(RUN_METADATA_PREFIX.trim_end())?)
+}
+
+/// 结果文件中记录 FFmpeg 可执行文件位置的元数据行前缀（见 [`crate::environment::resolve_program`]）
+pub const FFMPEG_METADATA_PREFIX: &str = "# ffmpeg: ";
+
+/// FFmpeg 位置元数据行 (FFmpeg Metadata Line)
+///
+/// `ffmpeg_path` 为 `None` 时没有元数据行。
+pub fn ffmpeg_metadata_line(ffmpeg_path: Option<&Path>) -> Option<String> {
+    ffmpeg_path.map(|path| format!("{}{}", FFMPEG_METADATA_PREFIX, path.display()))
+}
+
+/// 从 FFmpeg 位置元数据行还原路径；不是 FFmpeg 位置元数据行时返回 `None`
+fn ffmpeg_path_from_metadata_line(line: &str) -> Option<PathBuf> {
+    let path = line.trim_end().strip_prefix(FFMPEG_METADATA_PREFIX)?;
+    (!path.is_empty()).then(|| PathBuf::from(path))
+}
+
+/// 写入结果文件的表头和格式标记 (Write Results Header)
+///
+/// 所有写结果文件的地方都通过这个函数写表头，保证格式标记紧跟在表头之后，
+/// 运行 ID 元数据行（如果有）紧跟在格式标记之后，FFmpeg 位置元数据行（如果有）紧跟在运行 ID 之后。
+///
+/// # 参数
+/// - `writer` - 输出
+/// - `format` - 换行格式（BOM 由 [`TextFormat::create_file`] 写入）
+/// - `header_line` - 表头行（包括附加列名）
+/// - `run_id` - 写出这个文件的运行
+/// - `ffmpeg_path` - 这次运行使用的 FFmpeg 可执行文件
+pub fn write_results_header(
+    writer: &mut impl Write,
+    format: TextFormat,
+    header_line: &str,
+    run_id: Option<&RunId>,
+    ffmpeg_path: Option<&Path>,
+) -> io::Result<()> {
+    format.write_line(writer, header_line)?;
+    format.write_line(writer, format_args!("{}{}", FORMAT_METADATA_PREFIX, RESULTS_FORMAT_VERSION))?;
+    for line in run_metadata_line(run_id).into_iter().chain(ffmpeg_metadata_line(ffmpeg_path)) {
+        format.write_line(writer, line)?;
+    }
+    Ok(())
+}
+
+/// UTF-8 字节顺序标记
+pub const UTF8_BOM: &str = "\u{feff}";
+
+/// 文本文件的写入格式 (Text Format)
+///
+/// Windows 上的 Excel 只有在文件以 UTF-8 BOM 开头时才按 UTF-8 打开，
+/// 记事本等旧工具需要 CRLF 换行。默认值在 Windows 上两者都启用，其他平台都不启用。
+/// 读取结果文件时两种格式都能识别，排序和重写时保持原文件的格式。
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct TextFormat {
+    /// 文件以 UTF-8 BOM 开头（`--bom`）
+    pub bom: bool,
+    /// 使用 CRLF 换行（`--crlf`）
+    pub crlf: bool,
+}
+
+impl Default for TextFormat {
+    fn default() -> Self {
+        Self::PLATFORM_DEFAULT
+    }
+}
+
+impl TextFormat {
+    /// 当前平台的默认格式：Windows 上启用 BOM 和 CRLF
+    const PLATFORM_DEFAULT: Self = Self { bom: cfg!(windows), crlf: cfg!(windows) };
+
+    /// 换行符
+    pub fn line_ending(&self) -> &'static str {
+        if self.crlf { "\r\n" } else { "\n" }
+    }
+
+    /// 根据文件的第 1 行（包括换行符）识别格式
+    ///
+    /// # 示例
+    /// ```
+    /// use lra_calculator_rust::utils::TextFormat;
+    ///
+    /// assert_eq!(TextFormat::detect("\u{feff}表头\r\n"), TextFormat { bom: true, crlf: true });
+    /// assert_eq!(TextFormat::detect("表头\n"), TextFormat { bom: false, crlf: false });
+    /// ```
+    pub fn detect(first_line: &str) -> Self {
+        Self { bom: first_line.starts_with(UTF8_BOM), crlf: first_line.ends_with("\r\n") }
+    }
+
+    /// 创建文件，需要时先写入 BOM
+    pub fn create_file(&self, path: &Path) -> io::Result<BufWriter<File>> {
+        let mut writer = BufWriter::new(File::create(path)?);
+        if self.bom {
+            writer.write_all(UTF8_BOM.as_bytes())?;
+        }
+        Ok(writer)
+    }
+
+    /// 写入一行，使用该格式的换行符
+    pub fn write_line(&self, writer: &mut impl Write, line: impl fmt::Display) -> io::Result<()> {
+        write!(writer, "{}{}", line, self.line_ending())
+    }
+}
+
+/// 汇总页脚行的前缀
+///
+/// 页脚是结果文件末尾的注释行（`--summary-footer`）。与普通的 `#` 批注不同，
+/// 页脚在排序时保持在数据之后，合并多个结果文件时被丢弃（合并后的数据已经不同）。
+pub const FOOTER_PREFIX: &str = "## ";
+
+/// 生成结果文件的汇总页脚 (Results Footer Lines)
+///
+/// # 参数
+/// - `stats` - 本次运行的处理统计（失败数）
+/// - `distribution` - 结果文件中音轨条目的 LRA 分布；没有条目时为 `None`
+/// - `elapsed` - 本次运行的用时
+///
+/// # 返回值
+/// - 以 [`FOOTER_PREFIX`] 开头的页脚行
+///
+/// # 示例
+/// ```
+/// use std::time::Duration;
+/// use lra_calculator_rust::processor::{LraDistribution, ProcessingStats};
+/// use lra_calculator_rust::utils::results_footer_lines;
+///
+/// let distribution = LraDistribution::from_values(&[6.0, 9.0, 18.0]).unwrap();
+/// let stats = ProcessingStats::new(3, 1, Vec::new());
+/// let lines = results_footer_lines(&stats, Some(&distribution), Duration::from_secs(2525));
+/// assert_eq!(lines[0], "## 文件数: 3  平均 LRA: 11.0 LU  失败: 1  用时: 42m 05s");
+/// ```
+pub fn results_footer_lines(
+    stats: &ProcessingStats,
+    distribution: Option<&LraDistribution>,
+    elapsed: Duration,
+) -> Vec<String> {
+    let Some(distribution) = distribution else {
+        return vec![format!(
+            "{}文件数: 0  失败: {}  用时: {}",
+            FOOTER_PREFIX,
+            stats.failed,
+            format_elapsed(elapsed)
+        )];
+    };
+    let percent = |count: usize| count as f64 * 100.0 / distribution.count as f64;
+    vec![
+        format!(
+            "{}文件数: {}  平均 LRA: {:.1} LU  失败: {}  用时: {}",
+            FOOTER_PREFIX,
+            distribution.count,
+            distribution.mean,
+            stats.failed,
+            format_elapsed(elapsed)
+        ),
+        format!(
+            "{}中位数: {:.1} LU  范围: {:.1}-{:.1} LU  >15 LU: {} ({:.1}%)  8-15 LU: {} ({:.1}%)  <8 LU: {} ({:.1}%)",
+            FOOTER_PREFIX,
+            distribution.median,
+            distribution.min,
+            distribution.max,
+            distribution.high_count,
+            percent(distribution.high_count),
+            distribution.medium_count,
+            percent(distribution.medium_count),
+            distribution.low_count,
+            percent(distribution.low_count)
+        ),
+    ]
+}
+
+/// 把用时格式化为 `1h 02m`、`42m 05s` 或 `7s`
+fn format_elapsed(elapsed: Duration) -> String {
+    let seconds = elapsed.as_secs();
+    match (seconds / 3600, seconds / 60 % 60, seconds % 60) {
+        (0, 0, s) => format!("{}s", s),
+        (0, m, s) => format!("{}m {:02}s", m, s),
+        (h, m, _) => format!("{}h {:02}m", h, m),
+    }
+}
+
+/// 改用并行排序的条目数阈值，低于该值时线程调度的开销大于收益
+pub const PARALLEL_SORT_THRESHOLD: usize = 50_000;
+
+/// 读取结果文件时每块交给解析线程的行数
+pub const PARSE_CHUNK_LINES: usize = 8192;
+
+/// 获取结果文件对应的失败记录文件路径
+///
+/// 失败记录文件与结果文件位于同一目录，例如 `lra_results.txt`
+/// 对应 `lra_results_failures.txt`。
+///
+/// # 示例
+/// ```
+/// use std::path::Path;
+/// use lra_calculator_rust::utils::failures_path_for;
+///
+/// let path = failures_path_for(Path::new("/music/lra_results.txt"));
+/// assert_eq!(path, Path::new("/music/lra_results_failures.txt"));
+/// ```
+pub fn failures_path_for(results_file_path: &Path) -> PathBuf {
+    let stem = results_file_path
+        .file_stem()
+        .map(|stem| stem.to_string_lossy().into_owned())
+        .unwrap_or_else(|| "lra_results".to_string());
+    results_file_path.with_file_name(format!("{}_failures.txt", stem))
+}
+
+/// 获取结果文件对应的未处理文件列表路径
+///
+/// `--max-duration` 到期时，没有开始分析的文件按显示路径逐行写入该文件，
+/// 例如 `lra_results.txt` 对应 `lra_results_remaining.txt`。
+///
+/// # 示例
+/// ```
+/// use std::path::Path;
+/// use lra_calculator_rust::utils::remaining_path_for;
+///
+/// let path = remaining_path_for(Path::new("/music/lra_results.txt"));
+/// assert_eq!(path, Path::new("/music/lra_results_remaining.txt"));
+/// ```
+pub fn remaining_path_for(results_file_path: &Path) -> PathBuf {
+    let stem = results_file_path
+        .file_stem()
+        .map(|stem| stem.to_string_lossy().into_owned())
+        .unwrap_or_else(|| "lra_results".to_string());
+    results_file_path.with_file_name(format!("{}_remaining.txt", stem))
+}
+
+/// 获取原子写入时使用的临时文件路径
+///
+/// 临时文件与目标文件位于同一目录，重命名不会跨越文件系统。
+///
+/// # 示例
+/// ```
+/// use std::path::Path;
+/// use lra_calculator_rust::utils::temp_path_for;
+///
+/// let path = temp_path_for(Path::new("/music/lra_results.txt"));
+/// assert_eq!(path, Path::new("/music/lra_results.txt.tmp"));
+/// ```
+pub fn temp_path_for(path: &Path) -> PathBuf {
+    let mut name = OsString::from(path.as_os_str());
+    name.push(".tmp");
+    PathBuf::from(name)
+}
+
+/// 原子写入的文件 (Atomic File)
+///
+/// 内容先写入 [`temp_path_for`] 给出的临时文件，[`AtomicFile::commit`] 时同步到磁盘
+/// 再重命名为目标文件。提交之前出错（包括校验失败）时临时文件在 drop 时删除，
+/// 目标文件保持上一次的完整内容，不会留下写了一半的结果。
+pub struct AtomicFile {
+    /// 目标文件路径
+    target: PathBuf,
+    /// 临时文件路径
+    temp_path: PathBuf,
+    /// 临时文件的写入器，提交后为 `None`
+    writer: Option<BufWriter<File>>,
+}
+
+impl AtomicFile {
+    /// 创建临时文件，需要时先写入 BOM
+    pub fn create(target: &Path, format: TextFormat) -> io::Result<Self> {
+        let temp_path = temp_path_for(target);
+        let writer = format.create_file(&temp_path)?;
+        Ok(Self { target: target.to_path_buf(), temp_path, writer: Some(writer) })
+    }
+
+    /// 临时文件的写入器
+    pub fn writer(&mut self) -> &mut BufWriter<File> {
+        self.writer.as_mut().expect("AtomicFile 已提交")
+    }
+
+    /// 临时文件路径，提交之前可以从这里读回已写入的内容
+    pub fn temp_path(&self) -> &Path {
+        &self.temp_path
+    }
+
+    /// 把已写入的内容刷新并同步到磁盘
+    pub fn sync(&mut self) -> io::Result<()> {
+        let writer = self.writer();
+        writer.flush()?;
+        writer.get_ref().sync_all()
+    }
+
+    /// 同步临时文件并重命名为目标文件
+    pub fn commit(mut self) -> io::Result<()> {
+        self.sync()?;
+        // 先关闭文件：Windows 上无法重命名仍然打开的文件
+        drop(self.writer.take());
+        let result = fs::rename(&self.temp_path, &self.target);
+        if result.is_err() {
+            let _ = fs::remove_file(&self.temp_path);
+        }
+        result
+    }
+}
+
+impl Drop for AtomicFile {
+    fn drop(&mut self) {
+        if self.writer.take().is_some() {
+            let _ = fs::remove_file(&self.temp_path);
+        }
+    }
+}
+
+/// 验证结果文件所在目录可写 (Validate Output Location Is Writable)
+///
+/// 在长时间处理开始之前，尝试在输出目录中创建并立即删除一个临时文件，
+/// 避免在运行结束时才发现目录只读（例如扫描根目录位于只读挂载点）。
+///
+/// # 参数
+/// - `results_file_path` - 计划写入的结果文件路径
+///
+/// # 返回值
+/// - `Ok(())` - 输出目录可写
+/// - `Err(AppError::Path)` - 输出目录不可写，附带使用 `--output` 的建议
+pub fn validate_output_writable(results_file_path: &Path) -> Result<(), AppError> {
+    let output_dir = results_file_path
+        .parent()
+        .filter(|dir| !dir.as_os_str().is_empty())
+        .unwrap_or_else(|| Path::new("."));
+    let probe_path = output_dir.join(format!(".lra_write_test_{}", std::process::id()));
+
+    match File::create(&probe_path) {
+        Ok(_) => {
+            // 探测文件只用于验证权限，删除失败不影响后续处理
+            let _ = std::fs::remove_file(&probe_path);
+            Ok(())
+        }
+        Err(e) => Err(AppError::Path {
+            kind: PathErrorKind::NotWritable,
+            message: format!("无法在目录 '{}' 中写入结果文件: {}", output_dir.display(), e),
+        }),
+    }
+}
+
+/// 检查输出目录的剩余空间 (Check Free Space for Results)
+///
+/// 根据待处理文件数量粗略估算结果文件大小，
+/// 如果剩余空间不足估算值的两倍，返回警告信息。
+///
+/// # 参数
+/// - `results_file_path` - 计划写入的结果文件路径
+/// - `entry_count` - 待处理的文件数量
+///
+/// # 返回值
+/// - `Some(String)` - 空间紧张时的警告信息
+/// - `None` - 空间充足，或无法获取剩余空间
+pub fn check_free_space_for_results(results_file_path: &Path, entry_count: usize) -> Option<String> {
+    let output_dir = results_file_path
+        .parent()
+        .filter(|dir| !dir.as_os_str().is_empty())
+        .unwrap_or_else(|| Path::new("."));
+    let available = fs2::available_space(output_dir).ok()?;
+    let estimated = (entry_count as u64 + 1) * ESTIMATED_RESULT_LINE_BYTES;
+
+    if available < estimated.saturating_mul(2) {
+        Some(format!(
+            "输出目录 '{}' 剩余空间仅 {} 字节，结果文件预计需要约 {} 字节",
+            output_dir.display(),
+            available,
+            estimated
+        ))
+    } else {
+        None
+    }
+}
+
 /// 对 LRA 结果文件进行排序 (Sort LRA Results File)
 ///
 /// 这个函数负责读取、解析、排序和重写 LRA 结果文件。
@@ -266,10 +825,11 @@ pub fn validate_folder_path(path: &Path) -> Result<(), AppError> {
 /// - 处理相同 LRA 值的情况（按文件路径排序）
 ///
 /// ### 3. 文件重写
-/// - 创建新的结果文件（覆盖原文件）
+/// - 写入同一目录下的临时文件（见 [`AtomicFile`]）
 /// - 写入头部行
 /// - 按排序顺序写入所有数据行
-/// - 确保文件完整性和格式一致性
+/// - 重新读取临时文件，校验条目与写入的一致（见 [`verify_results_file`]）
+/// - 校验通过后重命名覆盖原文件；任何一步失败时原文件保持不变
 ///
 /// ## 错误处理策略
 ///
@@ -284,111 +844,749 @@ pub fn validate_folder_path(path: &Path) -> Result<(), AppError> {
 /// - 文件编码问题
 ///
 /// ### 恢复机制
-/// - 解析错误的行会被跳过并记录警告
+/// - 解析错误的行原样保留，并记录在返回的 [`SortReport::invalid_lines`] 中
 /// - 部分数据损坏不会导致整个排序失败
 /// - 提供详细的错误信息用于问题诊断
 ///
 /// # 参数
-/// - `results_file_path` - 结果文件的路径引用
-/// - `header_line` - 文件头部说明行（用于重写文件时保持格式）
+/// - `results_file_path` - 结果文件的路径引用
+/// - `header_line` - 文件头部说明行（用于重写文件时保持格式）
+///
+/// # 返回值
+/// - `Ok(SortReport)` - 排序成功完成，文件已更新；报告由调用方决定如何显示
+/// - `Err(io::Error)` - 文件读取或写入过程中发生错误（无法解析的行会原样保留）
+///
+/// # 性能特性
+/// - 时间复杂度: O(n log n)，其中 n 是文件行数
+/// - 空间复杂度: O(n)，需要将所有数据加载到内存中
+/// - 读取时解析与 I/O 重叠；条目数超过 [`PARALLEL_SORT_THRESHOLD`] 时并行排序
+pub fn sort_lra_results_file(
+    results_file_path: &Path,
+    header_line: &str,
+) -> io::Result<SortReport> {
+    sort_lra_results_file_with_verification(results_file_path, header_line, true)
+}
+
+/// 排序结果文件，可选择跳过写入后的校验 (Sort with Optional Verification)
+///
+/// 与 [`sort_lra_results_file`] 相同；`verify` 为 `false` 时不重新读取写入的文件
+/// （对应 `--no-verify`），写入仍然是原子的。
+///
+/// # 返回值
+/// - `Err(io::Error)` - 读写失败；校验失败时错误类型为 `InvalidData`，
+///   内部错误是 [`ResultsVerificationError`]
+pub fn sort_lra_results_file_with_verification(
+    results_file_path: &Path,
+    header_line: &str,
+    verify: bool,
+) -> io::Result<SortReport> {
+    sort_results_file(results_file_path, header_line, None, DEFAULT_LRA_PRECISION, verify)
+}
+
+/// 按指定方式排序结果文件 (Sort Results File)
+///
+/// 与 [`sort_lra_results_file_with_verification`] 相同，排序方式由 `order` 决定。
+/// `order` 为 `None` 时沿用文件中元数据行记录的排序方式，没有记录时按 LRA 值从高到低；
+/// 不是默认排序时在表头之后写入元数据行（见 [`SortOrder::metadata_line`]）。
+/// LRA 值至少保留 `precision` 位小数，文件原有的位数更多时保留原有位数。
+///
+/// # 返回值
+/// - `Err(io::Error)` - 读写失败；校验失败时错误类型为 `InvalidData`，
+///   内部错误是 [`ResultsVerificationError`]
+pub fn sort_results_file(
+    results_file_path: &Path,
+    header_line: &str,
+    order: Option<SortOrder>,
+    precision: u8,
+    verify: bool,
+) -> io::Result<SortReport> {
+    // 读取和解析文件内容
+    let ResultsFile { version, mut contents } = ResultsFile::read_with_header(results_file_path, header_line)?;
+    let has_entries = !contents.entries.is_empty();
+    let report = SortReport {
+        upgraded_from: (version < RESULTS_FORMAT_VERSION && has_entries).then_some(version),
+        added_header: contents.header_lines == 0 && has_entries,
+        removed_headers: contents.header_lines.saturating_sub(1),
+        invalid_lines: std::mem::take(&mut contents.invalid_lines),
+        ..SortReport::default()
+    };
+
+    // 原表头中的附加列名、批注行、页脚和文件原有的小数位数（即使它是用更高的 --precision 写出的）都保留
+    let set = ResultsSet::from_contents(contents).with_header_line(header_line).with_precision(precision);
+
+    // 写入排序后的结果，批注行紧跟在表头之后，附加列随条目一起移动，页脚保持在数据之后，
+    // BOM 和换行符与原文件相同
+    let order = order.or(set.order()).unwrap_or_default();
+    let sorted = set.sort(order);
+    sorted.write_with_verification(results_file_path, sorted.format(), verify)?;
+
+    Ok(SortReport { entries: sorted.len(), ..report })
+}
+
+/// 排序报告 (Sort Report)
+///
+/// [`sort_results_file`] 等函数在重写结果文件时发现并处理的情况。库不打印它们，由调用方决定如何显示。
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct SortReport {
+    /// 写回的条目数量；为 0 时写回的文件只有表头
+    pub entries: usize,
+    /// 原文件的格式版本比 [`RESULTS_FORMAT_VERSION`] 旧时为原版本，写回时已升级
+    pub upgraded_from: Option<u32>,
+    /// 原文件缺少表头，写回时已补上
+    pub added_header: bool,
+    /// 移除的重复表头行数量
+    pub removed_headers: usize,
+    /// 无法解析、原样保留的行
+    pub invalid_lines: Vec<InvalidResultLine>,
+}
+
+/// 读取和解析结果文件 (Read and Parse Results File)
+///
+/// 从结果文件中读取所有数据行，解析文件路径和 LRA 值。
+/// 这个函数处理各种解析错误，确保部分数据损坏不会导致整个过程失败。
+///
+/// 与 [`RESULTS_HEADER_LINE`] 完全相同的行视为表头并跳过，不要求出现在第 1 行，
+/// 也不要求只出现一次：缺少表头的文件从第 1 行开始就是数据，
+/// 拼接多个结果文件产生的重复表头同样被跳过。
+///
+/// 不是数据的非空行不会被丢弃，而是按原顺序收集到 [`ResultsFileContents::annotations`]，
+/// 重写文件时放回表头之后。以 `#` 开头且不能解析为数据的行是注释；
+/// 其他无法解析的行还会记录在 [`ResultsFileContents::invalid_lines`] 中，提示可能是损坏的数据。
+///
+/// 以 [`FOOTER_PREFIX`] 开头的汇总页脚行收集到 [`ResultsFileContents::footer`]，
+/// 不会被当作批注移到表头之后。
+///
+/// 文件开头的 UTF-8 BOM 会被去掉，CRLF 换行与 LF 一样处理；
+/// 识别出的格式保存在 [`ResultsFileContents::format`] 中。
+///
+/// 第 1 行的表头在 [`RESULTS_HEADER_LINE`] 之后还有以制表符分隔的列名时（例如 ReplayGain 列），
+/// 数据行末尾相同数量的制表符分隔字段是附加列，保存在 [`ResultsFileContents::extra_columns`] 中，
+/// 不参与 LRA 值的解析。
+///
+/// 读取线程每次读取 [`PARSE_CHUNK_LINES`] 行交给当前线程，当前线程并行解析上一块的同时
+/// 读取线程继续读取下一块，使解析与磁盘 I/O 重叠。条目和无法解析的行的顺序与文件中的行顺序一致。
+///
+/// # 参数
+/// - `file_path` - 结果文件路径
+///
+/// # 返回值
+/// - `Ok(ResultsFileContents)` - 成功解析的条目和保留的批注行
+/// - `Err(io::Error)` - 文件读取错误
+///
+/// 需要知道文件格式版本时使用 [`ResultsFile::read`]。
+pub fn read_and_parse_results_file(file_path: &Path) -> io::Result<ResultsFileContents> {
+    ResultsFile::read(file_path).map(ResultsFile::into_contents)
+}
+
+/// 校验写入的结果文件 (Verify Results File)
+///
+/// 重新读取并解析结果文件，检查条目数量与写入的相同，并逐条比较路径和
+/// 按写入精度（`precision` 位小数）格式化的 LRA 值。解析本身就要遍历整个文件，逐条比较几乎没有额外开销，
+/// 相对于分析音频的耗时可以忽略。无法解析的行在写入前读取时已经报告过，这里忽略。
+///
+/// # 参数
+/// - `file_path` - 要校验的文件（通常是 [`AtomicFile::temp_path`]）
+/// - `header_line` - 写入时使用的表头行
+/// - `written` - 写入的条目，顺序与文件中相同
+/// - `precision` - 写入时使用的小数位数
+///
+/// # 返回值
+/// - `Ok(())` - 读回的内容与写入的一致
+/// - `Err(io::Error)` - 读取失败；内容不一致时错误类型为 `InvalidData`，
+///   内部错误是 [`ResultsVerificationError`]
+pub fn verify_results_file(
+    file_path: &Path,
+    header_line: &str,
+    written: &[(FileKey, f64)],
+    precision: u8,
+) -> io::Result<()> {
+    let read_back = ResultsFile::read_with_header(file_path, header_line)?.contents.entries;
+    if read_back.len() != written.len() {
+        return Err(io::Error::new(
+            io::ErrorKind::InvalidData,
+            ResultsVerificationError::CountMismatch { written: written.len(), read_back: read_back.len() },
+        ));
+    }
+
+    let format_entry = |(path, lra): &(FileKey, f64)| format!("{} - {}", path, LraValue(*lra).with_precision(precision));
+    for (index, (expected, actual)) in written.iter().zip(&read_back).enumerate() {
+        let (expected, actual) = (format_entry(expected), format_entry(actual));
+        if expected != actual {
+            return Err(io::Error::new(
+                io::ErrorKind::InvalidData,
+                ResultsVerificationError::EntryMismatch { index: index + 1, written: expected, read_back: actual },
+            ));
+        }
+    }
+    Ok(())
+}
+
+/// 结果文件 (Results File)
+///
+/// 读取任意格式版本（见 [`RESULTS_FORMAT_VERSION`]）的结果文件，解析为相同的内存表示。
+/// 排序、合并、基线比较和增量更新都通过它读取结果文件。
+///
+/// 版本 1 的文件没有格式标记；版本 2 的格式标记与排序元数据一样由写入方重新生成，
+/// 不会出现在 [`ResultsFileContents::annotations`] 中。
+/// 比本程序支持的版本更新的文件无法安全地重写，读取时返回错误。
+///
+/// # 示例
+/// ```
+/// use lra_calculator_rust::utils::{ResultsFile, RESULTS_FORMAT_VERSION, RESULTS_HEADER_LINE};
+///
+/// let path = std::env::temp_dir().join("lra_results_file_doc.txt");
+/// std::fs::write(&path, format!("{}\n# format: 2\na.flac - 7.5\n", RESULTS_HEADER_LINE)).unwrap();
+/// let file = ResultsFile::read(&path).unwrap();
+/// assert_eq!(file.version, RESULTS_FORMAT_VERSION);
+/// assert_eq!(file.contents.entries.len(), 1);
+/// assert!(file.contents.annotations.is_empty());
+/// # std::fs::remove_file(&path).unwrap();
+/// ```
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct ResultsFile {
+    /// 文件的格式版本：没有格式标记时为 1
+    pub version: u32,
+    /// 解析出的内容
+    pub contents: ResultsFileContents,
+}
+
+impl ResultsFile {
+    /// 读取结果文件 (Read)
+    ///
+    /// # 返回值
+    /// - `Ok(ResultsFile)` - 文件内容和格式版本
+    /// - `Err(io::Error)` - 读取失败；格式版本比 [`RESULTS_FORMAT_VERSION`] 新时错误类型为 `InvalidData`
+    pub fn read(file_path: &Path) -> io::Result<Self> {
+        Self::read_with_header(file_path, RESULTS_HEADER_LINE)
+    }
+
+    /// 取出文件内容
+    pub fn into_contents(self) -> ResultsFileContents {
+        self.contents
+    }
+
+    /// 读取结果文件，与 `header_line` 或 [`RESULTS_HEADER_LINE`] 相同的行都视为表头
+    fn read_with_header(file_path: &Path, header_line: &str) -> io::Result<Self> {
+        let mut contents = read_results_file_lines(file_path, header_line)?;
+
+        // 格式标记、运行 ID、FFmpeg 位置和排序元数据由写入方重新生成，不作为批注保留
+        let mut version = None;
+        let mut unsupported = None;
+        contents.annotations.retain(|line| {
+            if let Some(found) = format_version_from_metadata_line(line) {
+                match found {
+                    1..=RESULTS_FORMAT_VERSION => {
+                        version.get_or_insert(found);
+                    }
+                    _ => {
+                        unsupported.get_or_insert(found);
+                    }
+                }
+                return false;
+            }
+            if let Some(run_id) = run_id_from_metadata_line(line) {
+                contents.run_id.get_or_insert(run_id);
+                return false;
+            }
+            if let Some(path) = ffmpeg_path_from_metadata_line(line) {
+                contents.ffmpeg_path.get_or_insert(path);
+                return false;
+            }
+            match SortOrder::from_metadata_line(line) {
+                Some(order) => {
+                    contents.sort_order.get_or_insert(order);
+                    false
+                }
+                None => true,
+            }
+        });
+        if let Some(found) = unsupported {
+            return Err(io::Error::new(
+                io::ErrorKind::InvalidData,
+                format!(
+                    "结果文件 {} 的格式版本 {} 不受支持（本程序支持 1 到 {}），请使用更新版本的程序",
+                    file_path.display(),
+                    found,
+                    RESULTS_FORMAT_VERSION
+                ),
+            ));
+        }
+        Ok(Self { version: version.unwrap_or(1), contents })
+    }
+}
+
+/// 从格式标记行还原格式版本；不是格式标记时返回 `None`
+fn format_version_from_metadata_line(line: &str) -> Option<u32> {
+    line.trim().strip_prefix(FORMAT_METADATA_PREFIX)?.trim().parse().ok()
+}
+
+/// 逐行读取并分类结果文件，不处理元数据行（见 [`ResultsFile::read_with_header`]）
+fn read_results_file_lines(file_path: &Path, header_line: &str) -> io::Result<ResultsFileContents> {
+    let header_line = header_line.trim();
+    let mut reader = BufReader::new(File::open(file_path)?);
+    let mut contents = ResultsFileContents::default();
+
+    // 先读第 1 行：附加列的数量由表头决定，解析数据行之前必须知道
+    let mut first_chunk = LineChunk::new(1);
+    if reader.read_line(&mut first_chunk.text)? > 0 {
+        first_chunk.line_count = 1;
+        contents.format = TextFormat::detect(&first_chunk.text);
+        if contents.format.bom {
+            first_chunk.text.drain(..UTF8_BOM.len());
+        }
+    }
+    contents.extra_header_columns = extra_header_columns(first_chunk.text.trim())
+        .filter(|columns| !columns.is_empty())
+        .map(str::to_string);
+    let extra_column_count = contents.extra_header_columns.as_deref().map_or(0, |columns| columns.matches('\t').count());
+
+    let (sender, receiver) = mpsc::sync_channel::<io::Result<LineChunk>>(2);
+    thread::scope(|scope| {
+        scope.spawn(move || {
+            let mut chunk = first_chunk;
+            loop {
+                match reader.read_line(&mut chunk.text) {
+                    Ok(0) => break,
+                    Ok(_) => chunk.line_count += 1,
+                    Err(e) => {
+                        let _ = sender.send(Err(e));
+                        return;
+                    }
+                }
+                if chunk.line_count == PARSE_CHUNK_LINES {
+                    let next = LineChunk::new(chunk.first_line_number + chunk.line_count);
+                    if sender.send(Ok(std::mem::replace(&mut chunk, next))).is_err() {
+                        return;
+                    }
+                }
+            }
+            if chunk.line_count > 0 {
+                let _ = sender.send(Ok(chunk));
+            }
+        });
+
+        for chunk in receiver {
+            let chunk = chunk?;
+            let parsed: Vec<_> = chunk
+                .text
+                .par_lines()
+                .map(|line| classify_result_line(line, header_line, extra_column_count))
+                .collect();
+
+            for (offset, line) in parsed.into_iter().enumerate() {
+                match line {
+                    ResultLine::Header => contents.header_lines += 1,
+                    ResultLine::Entry(entry, precision, extra) => {
+                        contents.precision = contents.precision.max(precision);
+                        if let Some(extra) = extra {
+                            contents.extra_columns.insert(entry.0.clone(), extra.to_string());
+                        }
+                        contents.entries.push(entry);
+                    }
+                    ResultLine::Comment(text) => contents.annotations.push(text.to_string()),
+                    ResultLine::Footer(text) => contents.footer.push(text.to_string()),
+                    ResultLine::Invalid(text, error) => {
+                        contents.invalid_lines.push(InvalidResultLine {
+                            line_number: chunk.first_line_number + offset,
+                            text: text.to_string(),
+                            error,
+                        });
+                        contents.annotations.push(text.to_string());
+                    }
+                    // 跳过空行
+                    ResultLine::Blank => {}
+                }
+            }
+        }
+        Ok::<(), io::Error>(())
+    })?;
+
+    Ok(contents)
+}
+
+/// 结果文件内容 (Results File Contents)
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct ResultsFileContents {
+    /// 数据条目：(显示路径, LRA 值)，保持文件中的顺序
+    pub entries: Vec<(FileKey, f64)>,
+    /// 不是数据的非空行（注释、手工批注、无法解析的行），保持文件中的顺序
+    pub annotations: Vec<String>,
+    /// 文件中表头行的数量：0 表示缺少表头，大于 1 表示有重复的表头
+    pub header_lines: usize,
+    /// 第 1 行表头在 [`RESULTS_HEADER_LINE`] 之后的附加列名（以制表符开头）；没有附加列时为 `None`
+    pub extra_header_columns: Option<String>,
+    /// 各条目的附加列（以制表符开头，与 `extra_header_columns` 对应）
+    pub extra_columns: HashMap<FileKey, String>,
+    /// 文件的 BOM 和换行格式；空文件为当前平台的默认格式
+    pub format: TextFormat,
+    /// 数据之后的汇总页脚行（以 [`FOOTER_PREFIX`] 开头），保持文件中的顺序
+    pub footer: Vec<String>,
+    /// 数据行中 LRA 值的最大小数位数；没有数据时为 0
+    pub precision: u8,
+    /// 元数据行（[`SORT_METADATA_PREFIX`]）记录的排序方式；没有元数据时为 `None`，
+    /// 元数据行不会出现在 `annotations` 中
+    pub sort_order: Option<SortOrder>,
+    /// 元数据行（[`RUN_METADATA_PREFIX`]）记录的写出这个文件的运行；没有元数据时为 `None`，
+    /// 元数据行不会出现在 `annotations` 中
+    pub run_id: Option<RunId>,
+    /// 元数据行（[`FFMPEG_METADATA_PREFIX`]）记录的写出这个文件时使用的 FFmpeg；没有元数据时为 `None`，
+    /// 元数据行不会出现在 `annotations` 中
+    pub ffmpeg_path: Option<PathBuf>,
+    /// 无法解析的行（不含 `#` 注释），保持文件中的顺序；这些行同时原样保留在 `annotations` 中
+    pub invalid_lines: Vec<InvalidResultLine>,
+}
+
+/// 结果文件中无法解析的行 (Invalid Result Line)
+#[derive(Debug, Clone, PartialEq)]
+pub struct InvalidResultLine {
+    /// 行号，从 1 开始
+    pub line_number: usize,
+    /// 行的原文（不含换行符）
+    pub text: String,
+    /// 解析错误
+    pub error: ResultLineParseError,
+}
+
+impl fmt::Display for InvalidResultLine {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self.error {
+            // 格式错误本身不含行内容，附上原文便于定位
+            ResultLineParseError::MissingSeparator => {
+                write!(f, "第 {} 行: {}: '{}'", self.line_number, self.error, self.text)
+            }
+            _ => write!(f, "第 {} 行: {}", self.line_number, self.error),
+        }
+    }
+}
+
+/// 结果文件附加列 (Extra Column Table)
+///
+/// 收集写入结果文件的附加列（ReplayGain、标签等）。每组列有列名和缺值时的默认值；
+/// 增量更新时，本次没有重新计算的值按列名从旧结果文件中沿用。
+///
+/// # 示例
+/// ```
+/// use lra_calculator_rust::utils::ExtraColumnTable;
+///
+/// let mut table = ExtraColumnTable::default();
+/// table.add_columns(&["增益"], "n/a");
+/// table.set(&"a.flac".into(), &["增益"], ["-3.80".to_string()]);
+/// let rendered = table.render(["a.flac".into(), "b.flac".into()].iter());
+/// assert_eq!(rendered["a.flac"], "\t-3.80");
+/// assert_eq!(rendered["b.flac"], "\tn/a");
+/// ```
+#[derive(Debug, Clone, Default)]
+pub struct ExtraColumnTable {
+    /// (列名, 默认值)
+    columns: Vec<(&'static str, &'static str)>,
+    /// 各条目已知的列值，与 `columns` 一一对应
+    values: HashMap<FileKey, Vec<Option<String>>>,
+}
+
+impl ExtraColumnTable {
+    /// 追加一组列
+    pub fn add_columns(&mut self, names: &[&'static str], default: &'static str) {
+        self.columns.extend(names.iter().map(|name| (*name, default)));
+    }
+
+    /// 是否没有任何附加列
+    pub fn is_empty(&self) -> bool {
+        self.columns.is_empty()
+    }
+
+    /// 包含附加列名的表头行
+    pub fn header_line(&self) -> String {
+        let mut header = RESULTS_HEADER_LINE.to_string();
+        for (name, _) in &self.columns {
+            header.push('\t');
+            header.push_str(name);
+        }
+        header
+    }
+
+    /// 设置条目的一组列值，覆盖已有的值；表中不存在的列被忽略
+    pub fn set<I>(&mut self, path: &FileKey, names: &[&str], values: I)
+    where
+        I: IntoIterator<Item = String>,
+    {
+        for (name, value) in names.iter().zip(values) {
+            self.set_value(path, name, value, true);
+        }
+    }
+
+    /// 按列名沿用旧结果文件中的值，不覆盖已经设置的值
+    ///
+    /// # 参数
+    /// - `contents` - 旧结果文件的内容（见 [`ResultsFileContents::extra_header_columns`]）
+    pub fn adopt(&mut self, contents: &ResultsFileContents) {
+        let Some(header_columns) = &contents.extra_header_columns else {
+            return;
+        };
+        let names: Vec<&str> = header_columns.split('\t').skip(1).collect();
+        for (path, row) in &contents.extra_columns {
+            for (name, value) in names.iter().zip(row.split('\t').skip(1)) {
+                self.set_value(path, name, value.to_string(), false);
+            }
+        }
+    }
+
+    fn set_value(&mut self, path: &FileKey, name: &str, value: String, overwrite: bool) {
+        let Some(index) = self.columns.iter().position(|(column, _)| *column == name) else {
+            return;
+        };
+        let row = self.values.entry(path.clone()).or_default();
+        // 设置值之后仍可以追加新的列
+        row.resize(self.columns.len(), None);
+        if overwrite || row[index].is_none() {
+            row[index] = Some(value);
+        }
+    }
+
+    /// 渲染各条目的附加列（以制表符开头），缺少的值使用该列的默认值
+    ///
+    /// 没有附加列时返回空映射。
+    pub fn render<'a>(&self, paths: impl IntoIterator<Item = &'a FileKey>) -> HashMap<FileKey, String> {
+        if self.is_empty() {
+            return HashMap::new();
+        }
+        paths
+            .into_iter()
+            .map(|path| {
+                let row = self.values.get(path);
+                let mut rendered = String::new();
+                for (index, (_, default)) in self.columns.iter().enumerate() {
+                    rendered.push('\t');
+                    let value = row.and_then(|row| row.get(index)?.as_deref()).unwrap_or(default);
+                    rendered.push_str(value);
+                }
+                (path.clone(), rendered)
+            })
+            .collect()
+    }
+}
+
+/// 结果文件中一行的分类
+enum ResultLine<'a> {
+    /// 空行
+    Blank,
+    /// 表头行
+    Header,
+    /// 数据行、LRA 值的小数位数及其附加列（以制表符开头）
+    Entry((FileKey, f64), u8, Option<&'a str>),
+    /// 以 `#` 开头的注释行
+    Comment(&'a str),
+    /// 以 [`FOOTER_PREFIX`] 开头的页脚行
+    Footer(&'a str),
+    /// 无法解析的行及原因
+    Invalid(&'a str, ResultLineParseError),
+}
+
+/// 对结果文件中的一行分类
+///
+/// 表头必须整行匹配，路径中包含表头文字的数据行不受影响。
+/// 能解析为数据的行总是数据，因此 `#1 Hits/song.flac - 5.0` 这类以 `#` 开头的路径不会被当作注释。
+///
+/// 有 `extra_column_count` 个附加列时，先从行尾切下这些字段再解析；
+/// 切下后无法解析的行（例如没有附加列的旧数据行）按整行解析。
+fn classify_result_line<'a>(line: &'a str, header_line: &str, extra_column_count: usize) -> ResultLine<'a> {
+    let trimmed = line.trim();
+    if trimmed.is_empty() {
+        return ResultLine::Blank;
+    }
+    if trimmed == header_line || trimmed == RESULTS_HEADER_LINE {
+        return ResultLine::Header;
+    }
+    if let Some((data, extra)) = split_extra_columns(line, extra_column_count) {
+        if let Ok(entry) = parse_result_line(data) {
+            return ResultLine::Entry(entry, lra_decimal_places(data), Some(extra));
+        }
+    }
+    match parse_result_line(line) {
+        Ok(entry) => ResultLine::Entry(entry, lra_decimal_places(line), None),
+        Err(_) if extra_header_columns(trimmed).is_some() => ResultLine::Header,
+        Err(_) if trimmed.starts_with(FOOTER_PREFIX) => ResultLine::Footer(line),
+        Err(_) if trimmed.starts_with('#') => ResultLine::Comment(line),
+        Err(e) => ResultLine::Invalid(line, e),
+    }
+}
+
+/// 已解析的数据行中 LRA 值的小数位数
+fn lra_decimal_places(line: &str) -> u8 {
+    line.rsplit_once(" - ").map_or(0, |(_, lra)| decimal_places(lra))
+}
+
+/// 表头行在 [`RESULTS_HEADER_LINE`] 之后的附加列名
+///
+/// 能解析为数据的行不是表头，即使它以表头文字开头。
+///
+/// # 返回值
+/// - `Some("")` - 标准表头，没有附加列
+/// - `Some("\t列名…")` - 带附加列的表头
+/// - `None` - 不是表头
+fn extra_header_columns(line: &str) -> Option<&str> {
+    line.strip_prefix(RESULTS_HEADER_LINE)
+        .filter(|columns| columns.is_empty() || columns.starts_with('\t'))
+        .filter(|columns| columns.is_empty() || parse_result_line(line).is_err())
+}
+
+/// 从数据行末尾切下 `count` 个以制表符分隔的附加列
+///
+/// # 返回值
+/// - `Some((数据部分, 附加列))` - 附加列以制表符开头
+/// - `None` - 没有附加列，或行中的制表符不足
+fn split_extra_columns(line: &str, count: usize) -> Option<(&str, &str)> {
+    if count == 0 {
+        return None;
+    }
+    let line = line.trim_end_matches(['\r', '\n']);
+    let split_at = line.rmatch_indices('\t').nth(count - 1)?.0;
+    Some(line.split_at(split_at))
+}
+
+/// 读取线程交给解析线程的一块连续行
+///
+/// 整块内容放在同一个字符串中，避免为每一行单独分配。
+struct LineChunk {
+    /// 第一行在文件中的行号（从 1 开始）
+    first_line_number: usize,
+    /// 块中的行数
+    line_count: usize,
+    /// 原始文本（保留换行符）
+    text: String,
+}
+
+impl LineChunk {
+    fn new(first_line_number: usize) -> Self {
+        Self { first_line_number, line_count: 0, text: String::new() }
+    }
+}
+
+/// 合并多个结果文件 (Merge Results Files)
+///
+/// 读取多个结果文件，合并所有条目后按 LRA 值排序写入输出文件。
+/// 同一路径在多个输入中出现时保留最后一个文件中的值，
+/// 便于用新一次运行的结果覆盖旧结果。各输入的批注行按顺序保留，重复的只保留一次；
+/// 各输入的汇总页脚描述的是合并前的数据，不会写入输出。
+/// 输入带有附加列时，输出使用最后一个带附加列的输入的列名，条目的附加列同样以后出现的为准。
+///
+/// # 参数
+/// - `inputs` - 要合并的结果文件列表
+/// - `output` - 输出文件路径（可以是输入文件之一）
+/// - `header_line` - 输出文件的表头行
+/// - `format` - 输出文件的 BOM 和换行格式
+/// - `precision` - LRA 值至少保留的小数位数；输入中的位数更多时保留原有位数
+/// - `verify` - 写入后重新读取输出文件校验（见 [`verify_results_file`]）
+///
+/// # 返回值
+/// - `Ok(MergeReport)` - 合并后的条目数量和各输入中无法解析的行
+/// - `Err(AppError::Io)` - 读取、写入或校验失败；失败时输出文件保持原样
+pub fn merge_results_files(
+    inputs: &[PathBuf],
+    output: &Path,
+    header_line: &str,
+    format: TextFormat,
+    precision: u8,
+    verify: bool,
+) -> Result<MergeReport, AppError> {
+    let mut merged = ResultsSet::default().with_header_line(header_line).with_precision(precision);
+    let mut invalid_lines = Vec::new();
+    for input in inputs {
+        let mut contents = ResultsFile::read(input)?.contents;
+        invalid_lines.extend(contents.invalid_lines.drain(..).map(|line| (input.clone(), line)));
+        merged = merged.merge(ResultsSet::from_contents(contents), MergePolicy::PreferOther);
+    }
+    // 合并的结果来自多次运行，不记录运行 ID 和 FFmpeg 位置
+    let sorted = merged.sort_in_recorded_order().with_run_id(None).with_ffmpeg_path(None);
+    sorted.write_with_verification(output, format, verify)?;
+    Ok(MergeReport { entries: sorted.len(), invalid_lines })
+}
+
+/// 合并报告 (Merge Report)
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct MergeReport {
+    /// 合并后的条目数量
+    pub entries: usize,
+    /// 各输入文件中无法解析、原样保留的行
+    pub invalid_lines: Vec<(PathBuf, InvalidResultLine)>,
+}
+
+/// 向结果文件追加新条目 (Extend Results File)
+///
+/// 只添加文件中还没有的路径，已有条目的值保持不变；合并后按 LRA 值重新排序写回。
+/// 文件不存在时创建。
+/// 表头（包括附加列名）、批注行和文件格式保持原样，新条目的附加列留空；
+/// 页脚描述的是追加前的数据，不会写回。用于 `--update-baseline`。
+///
+/// # 参数
+/// - `path` - 已有的结果文件
+/// - `header_line` - 识别表头使用的表头行（不含附加列名）
+/// - `new_entries` - 要追加的条目
+/// - `precision` - LRA 值至少保留的小数位数；文件原有的位数更多时保留原有位数
+/// - `verify` - 写入后重新读取校验（见 [`verify_results_file`]）
 ///
 /// # 返回值
-/// - `Ok(())` - 排序成功完成，文件已更新
-/// - `Err(Box<dyn std::error::Error>)` - 文件操作或解析过程中发生错误
-///
-/// # 性能特性
-/// - 时间复杂度: O(n log n)，其中 n 是文件行数
-/// - 空间复杂度: O(n)，需要将所有数据加载到内存中
-/// - 对于大文件（>10万行），可能需要考虑流式排序
-pub fn sort_lra_results_file(
-    results_file_path: &Path,
+/// - `Ok(usize)` - 实际追加的条目数量
+/// - `Err(io::Error)` - 读取、写入或校验失败；失败时文件保持原样
+pub fn extend_results_file(
+    path: &Path,
     header_line: &str,
-) -> Result<(), Box<dyn std::error::Error>> {
-    println!("\n📊 正在排序结果文件: {}", results_file_path.display());
-
-    // 读取和解析文件内容
-    let entries = read_and_parse_results_file(results_file_path)?;
-
-    // 检查是否有有效数据需要排序
-    if entries.is_empty() {
-        println!("📝 结果文件为空或没有有效数据，创建仅包含表头的文件。");
-        write_results_file(results_file_path, header_line, &[])?;
-        return Ok(());
+    new_entries: &[(FileKey, f64)],
+    precision: u8,
+    verify: bool,
+) -> io::Result<usize> {
+    let existing = match ResultsSet::read_with_header(path, header_line) {
+        Ok(set) => set,
+        Err(e) if e.kind() == io::ErrorKind::NotFound => ResultsSet::default().with_header_line(header_line),
+        Err(e) => return Err(e),
+    };
+    let existing_count = existing.len();
+    let extended = existing.merge(ResultsSet::new(new_entries.to_vec()), MergePolicy::KeepExisting);
+    let added = extended.len() - existing_count;
+    if added == 0 {
+        return Ok(0);
     }
 
-    // 对数据进行排序
-    let sorted_entries = sort_entries_by_lra(entries);
-
-    // 写入排序后的结果
-    write_results_file(results_file_path, header_line, &sorted_entries)?;
-
-    println!("✅ 排序完成，共处理 {} 个条目", sorted_entries.len());
-    Ok(())
+    let sorted = extended.sort_in_recorded_order().with_precision(precision);
+    sorted.write_with_verification(path, sorted.format(), verify)?;
+    Ok(added)
 }
 
-/// 读取和解析结果文件 (Read and Parse Results File)
+/// 按路径合并结果条目 (Merge Entries)
 ///
-/// 从结果文件中读取所有数据行，解析文件路径和 LRA 值。
-/// 这个函数处理各种解析错误，确保部分数据损坏不会导致整个过程失败。
+/// 同一路径出现多次时，后出现的值覆盖先出现的值，条目保持首次出现的位置。
+/// 路径可以是 `String`、`&str` 或 [`FileKey`]，结果中的每个路径只保存一份。
 ///
 /// # 参数
-/// - `file_path` - 结果文件路径
+/// - `entries` - 按新旧顺序排列的结果条目（旧的在前）
 ///
 /// # 返回值
-/// - `Ok(Vec<(String, f64)>)` - 成功解析的条目列表
-/// - `Err(...)` - 文件读取错误
-fn read_and_parse_results_file(
-    file_path: &Path
-) -> Result<Vec<(String, f64)>, Box<dyn std::error::Error>> {
-    let file = File::open(file_path)?;
-    let reader = BufReader::new(file);
-    let mut entries = Vec::new();
-    let mut lines_iter = reader.lines();
-    let mut line_number = 0;
-    let mut skipped_lines = 0;
-
-    // 跳过第一行（表头）
-    if let Some(first_line) = lines_iter.next() {
-        line_number += 1;
-        let _ = first_line?; // 检查是否有读取错误，但不使用内容
-    } else {
-        // 文件为空
-        return Ok(entries);
-    }
-
-    // 处理数据行
-    for line_result in lines_iter {
-        line_number += 1;
-        let line = line_result?;
-
-        // 跳过空行
-        if line.trim().is_empty() {
-            continue;
-        }
-
-        // 解析行内容
-        match parse_result_line(&line) {
-            Ok((path, lra)) => {
-                entries.push((path, lra));
-            }
-            Err(e) => {
-                eprintln!(
-                    "⚠️  排序时警告 (第 {} 行): {}",
-                    line_number, e
-                );
-                skipped_lines += 1;
+/// - 去重后的结果条目
+///
+/// # 示例
+/// ```
+/// use lra_calculator_rust::utils::merge_entries;
+///
+/// let old = vec![("a.flac", 5.0), ("b.flac", 6.0)];
+/// let fresh = vec![("a.flac", 9.0)];
+/// let merged = merge_entries(old.into_iter().chain(fresh));
+/// assert_eq!(merged, vec![("a.flac".into(), 9.0), ("b.flac".into(), 6.0)]);
+/// ```
+pub fn merge_entries<K: Into<FileKey>>(entries: impl IntoIterator<Item = (K, f64)>) -> Vec<(FileKey, f64)> {
+    let mut merged: Vec<(FileKey, f64)> = Vec::new();
+    let mut index_by_path: HashMap<FileKey, usize> = HashMap::new();
+
+    for (path, lra) in entries {
+        let path = path.into();
+        match index_by_path.get(&path) {
+            Some(&index) => merged[index].1 = lra,
+            None => {
+                index_by_path.insert(path.clone(), merged.len());
+                merged.push((path, lra));
             }
         }
     }
 
-    if skipped_lines > 0 {
-        println!(
-            "📋 解析完成: 成功 {} 行，跳过 {} 行无效数据",
-            entries.len(), skipped_lines
-        );
-    }
-
-    Ok(entries)
+    merged
 }
 
 /// 解析单行结果数据 (Parse Single Result Line)
@@ -399,98 +1597,239 @@ fn read_and_parse_results_file(
 /// - `line` - 要解析的行内容
 ///
 /// # 返回值
-/// - `Ok((String, f64))` - 解析成功的文件路径和 LRA 值
-/// - `Err(String)` - 解析失败的错误信息
-pub fn parse_result_line(line: &str) -> Result<(String, f64), String> {
-    match line.rsplit_once(" - ") {
-        Some((path_part, lra_str_part)) => {
-            let lra_str = lra_str_part.trim();
-            match lra_str.parse::<f64>() {
-                Ok(lra_value) => {
-                    // 验证 LRA 值的合理性
-                    if lra_value.is_finite() && lra_value >= 0.0 {
-                        Ok((path_part.to_string(), lra_value))
-                    } else {
-                        Err(format!(
-                            "LRA 值 '{}' 超出合理范围 (应为非负有限数)",
-                            lra_str
-                        ))
-                    }
-                }
-                Err(e) => Err(format!(
-                    "无法解析 LRA 值 '{}': {}",
-                    lra_str, e
-                ))
-            }
-        }
-        None => Err(format!(
-            "行格式不正确: '{}' (期望格式: '文件路径 - LRA值')",
-            line
-        ))
+/// - `Ok((FileKey, f64))` - 解析成功的文件路径和 LRA 值
+/// - `Err(ResultLineParseError)` - 解析失败的原因
+///
+/// # 示例
+/// ```
+/// use lra_calculator_rust::error::ResultLineParseError;
+/// use lra_calculator_rust::utils::parse_result_line;
+///
+/// assert!(parse_result_line("a.flac - 7.5").is_ok());
+/// assert_eq!(parse_result_line("a.flac"), Err(ResultLineParseError::MissingSeparator));
+/// assert_eq!(parse_result_line("a.flac - -1"), Err(ResultLineParseError::OutOfRange { value: -1.0 }));
+/// ```
+pub fn parse_result_line(line: &str) -> Result<(FileKey, f64), ResultLineParseError> {
+    let (path_part, lra_str_part) = line
+        .rsplit_once(" - ")
+        .ok_or(ResultLineParseError::MissingSeparator)?;
+
+    let lra_str = lra_str_part.trim();
+    let lra_value = lra_str
+        .parse::<f64>()
+        .map_err(|source| ResultLineParseError::InvalidNumber { raw: lra_str.to_string(), source })?;
+
+    // 只拒绝不可能出现的值；超出合理范围的值也是有效结果（见 crate::precision）
+    if is_possible_lra(lra_value) {
+        Ok((FileKey::from(path_part), lra_value))
+    } else {
+        Err(ResultLineParseError::OutOfRange { value: lra_value })
     }
 }
 
 /// 对条目按 LRA 值排序 (Sort Entries by LRA Value)
 ///
 /// 使用稳定排序算法按 LRA 值降序排列，LRA 值相同时按文件路径排序。
+/// 条目数达到 [`PARALLEL_SORT_THRESHOLD`] 时改用 rayon 的并行稳定排序，
+/// 两种方式使用同一个比较函数，排序结果完全相同。
 ///
 /// # 参数
 /// - `mut entries` - 要排序的条目列表
 ///
 /// # 返回值
 /// - 排序后的条目列表
-pub fn sort_entries_by_lra(mut entries: Vec<(String, f64)>) -> Vec<(String, f64)> {
-    entries.sort_by(|a, b| {
-        // 首先按 LRA 值降序排序
-        match b.1.total_cmp(&a.1) {
-            std::cmp::Ordering::Equal => {
-                // LRA 值相同时，按文件路径升序排序
-                a.0.cmp(&b.0)
+pub fn sort_entries_by_lra(entries: Vec<(FileKey, f64)>) -> Vec<(FileKey, f64)> {
+    sort_entries(entries, SortOrder::LraDescending)
+}
+
+/// 按指定方式对条目排序 (Sort Entries)
+///
+/// 与 [`sort_entries_by_lra`] 相同，排序键由 `order` 决定；键相同时都按文件路径升序排列。
+///
+/// # 示例
+/// ```
+/// use lra_calculator_rust::utils::{sort_entries, SortOrder};
+///
+/// let entries = vec![("a.flac".into(), 9.5), ("b.flac".into(), 4.0), ("c.flac".into(), 12.0)];
+/// let sorted = sort_entries(entries, SortOrder::DeviationFrom(9.0));
+/// let paths: Vec<&str> = sorted.iter().map(|(path, _)| path.as_str()).collect();
+/// assert_eq!(paths, ["b.flac", "c.flac", "a.flac"]);
+/// ```
+pub fn sort_entries(mut entries: Vec<(FileKey, f64)>, order: SortOrder) -> Vec<(FileKey, f64)> {
+    let compare = |a: &(FileKey, f64), b: &(FileKey, f64)| order.compare(a, b);
+    if entries.len() >= PARALLEL_SORT_THRESHOLD {
+        entries.par_sort_by(compare);
+    } else {
+        entries.sort_by(compare);
+    }
+    entries
+}
+
+/// 结果条目的排序规则
+fn compare_entries(a: &(FileKey, f64), b: &(FileKey, f64)) -> std::cmp::Ordering {
+    // 首先按 LRA 值降序排序
+    match b.1.total_cmp(&a.1) {
+        std::cmp::Ordering::Equal => {
+            // LRA 值相同时，按文件路径升序排序
+            a.0.cmp(&b.0)
+        }
+        other => other,
+    }
+}
+
+/// 结果文件中记录排序方式的元数据行前缀
+///
+/// 不是默认排序时，表头之后写一行 `# 排序: deviation:9 (...)`，之后读到文件的人
+/// 和 `sort` 子命令都知道条目的顺序是什么意思。
+pub const SORT_METADATA_PREFIX: &str = "# 排序: ";
+
+/// 结果排序方式 (Sort Order)
+#[derive(Debug, Clone, Copy, Default, PartialEq)]
+pub enum SortOrder {
+    /// 按 LRA 值从高到低（默认）
+    #[default]
+    LraDescending,
+    /// 按与目标 LRA 的偏差 `|lra - target|` 从大到小，高于和低于目标同等对待（`--sort deviation:9.0`）
+    DeviationFrom(f64),
+}
+
+impl SortOrder {
+    /// 比较两个条目；排序键相同时按路径升序
+    pub fn compare(self, a: &(FileKey, f64), b: &(FileKey, f64)) -> std::cmp::Ordering {
+        match self {
+            SortOrder::LraDescending => compare_entries(a, b),
+            SortOrder::DeviationFrom(target) => {
+                let deviation = |lra: f64| (lra - target).abs();
+                deviation(b.1).total_cmp(&deviation(a.1)).then_with(|| a.0.cmp(&b.0))
             }
-            other => other,
         }
-    });
-    entries
+    }
+
+    /// 解析 `--sort` 的取值：`lra` 或 `deviation:<目标 LU>`
+    ///
+    /// # 示例
+    /// ```
+    /// use lra_calculator_rust::utils::SortOrder;
+    ///
+    /// assert_eq!(SortOrder::parse("deviation:9.0"), Some(SortOrder::DeviationFrom(9.0)));
+    /// assert_eq!(SortOrder::parse("lra"), Some(SortOrder::LraDescending));
+    /// assert_eq!(SortOrder::parse("deviation:abc"), None);
+    /// ```
+    pub fn parse(text: &str) -> Option<Self> {
+        match text.trim().split_once(':') {
+            None if text.trim() == "lra" => Some(SortOrder::LraDescending),
+            Some(("deviation", target)) => {
+                let target: f64 = target.trim().parse().ok()?;
+                target.is_finite().then_some(SortOrder::DeviationFrom(target))
+            }
+            _ => None,
+        }
+    }
+
+    /// 人类可读的描述，如"LRA 值从高到低"
+    pub fn description(self) -> String {
+        match self {
+            SortOrder::LraDescending => "LRA 值从高到低".to_string(),
+            SortOrder::DeviationFrom(target) => format!("与目标 LRA {} LU 的偏差从大到小", target),
+        }
+    }
+
+    /// 写入结果文件的元数据行；默认排序不需要元数据，返回 `None`
+    pub fn metadata_line(self) -> Option<String> {
+        match self {
+            SortOrder::LraDescending => None,
+            order => Some(format!("{}{} ({})", SORT_METADATA_PREFIX, order, order.description())),
+        }
+    }
+
+    /// 从元数据行还原排序方式
+    fn from_metadata_line(line: &str) -> Option<Self> {
+        let value = line.trim().strip_prefix(SORT_METADATA_PREFIX)?;
+        Self::parse(value.split_whitespace().next()?)
+    }
+}
+
+impl fmt::Display for SortOrder {
+    /// 与 [`SortOrder::parse`] 接受的格式相同
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            SortOrder::LraDescending => write!(f, "lra"),
+            SortOrder::DeviationFrom(target) => write!(f, "deviation:{}", target),
+        }
+    }
 }
 
 /// 写入结果文件 (Write Results File)
 ///
-/// 将排序后的结果写入文件，包含表头和所有数据行。
+/// 将排序后的结果写入文件，依次包含表头、格式标记、批注行、所有数据行和页脚行。
 ///
 /// # 参数
 /// - `file_path` - 输出文件路径
-/// - `header_line` - 表头行内容
+/// - `format` - BOM 和换行格式
+/// - `header_line` - 表头行内容（包括附加列名）
+/// - `annotations` - 原样写回的批注行
 /// - `entries` - 要写入的数据条目
+/// - `extra_columns` - 各条目的附加列，追加在 LRA 值之后
+/// - `footer` - 写在数据之后的页脚行
+/// - `precision` - LRA 值保留的小数位数
+/// - `verify` - 提交之前重新读取临时文件校验（见 [`verify_results_file`]）
 ///
 /// # 返回值
 /// - `Ok(())` - 写入成功
-/// - `Err(...)` - 写入失败
+/// - `Err(io::Error)` - 写入或校验失败，目标文件保持原样
+#[allow(clippy::too_many_arguments)]
 fn write_results_file(
     file_path: &Path,
+    format: TextFormat,
     header_line: &str,
-    entries: &[(String, f64)]
-) -> Result<(), Box<dyn std::error::Error>> {
-    let mut writer = BufWriter::new(File::create(file_path)?);
-
-    // 写入表头
-    writeln!(writer, "{}", header_line)?;
+    annotations: &[String],
+    entries: &[(FileKey, f64)],
+    extra_columns: &HashMap<FileKey, String>,
+    footer: &[String],
+    precision: u8,
+    verify: bool,
+) -> io::Result<()> {
+    let mut file = AtomicFile::create(file_path, format)?;
+    let writer = file.writer();
+
+    // 写入表头和格式标记；运行 ID 等元数据行由调用方放在批注行的最前面
+    write_results_header(writer, format, header_line, None, None)?;
+
+    // 写入批注行
+    for annotation in annotations {
+        format.write_line(writer, annotation)?;
+    }
 
     // 写入数据行
     for (path_str, lra) in entries {
-        writeln!(writer, "{} - {:.1}", path_str, lra)?;
+        let extra = extra_columns.get(path_str).map_or("", String::as_str);
+        format.write_line(writer, format_args!("{} - {}{}", path_str, LraValue(*lra).with_precision(precision), extra))?;
     }
 
-    // 确保数据写入磁盘
-    writer.flush()?;
-    Ok(())
+    // 写入页脚行
+    for line in footer {
+        format.write_line(writer, line)?;
+    }
+
+    // 确保数据写入磁盘，校验通过后才替换目标文件
+    file.sync()?;
+    if verify {
+        verify_results_file(file.temp_path(), header_line, entries, precision)?;
+    }
+    file.commit()
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::clock::FixedClock;
+    use crate::suggestion::Suggestion;
     use std::fs;
     use tempfile::TempDir;
 
+    /// 写入方在表头之后生成的格式标记
+    const FORMAT_LINE: &str = "# format: 2";
+
     /// 测试文件夹路径验证功能
     #[test]
     fn test_validate_folder_path() {
@@ -500,24 +1839,200 @@ mod tests {
 
         // 测试不存在的路径
         let non_existent = Path::new("/this/path/should/not/exist/12345");
-        let result = validate_folder_path(non_existent);
-        assert!(result.is_err());
-
-        if let Err(AppError::Path(msg)) = result {
-            assert!(msg.contains("不存在"));
+        let error = validate_folder_path(non_existent).unwrap_err();
+        if let AppError::Path { message, .. } = &error {
+            assert!(message.contains("不存在"));
         } else {
             panic!("期望得到 AppError::Path 错误");
         }
+        assert_eq!(error.suggestions(), vec![Suggestion::CheckPathSpelling, Suggestion::CheckPathMoved]);
 
         // 测试文件而非目录（使用 Cargo.toml）
         let file_path = Path::new("Cargo.toml");
         if file_path.exists() {
-            let result = validate_folder_path(file_path);
-            assert!(result.is_err());
-
-            if let Err(AppError::Path(msg)) = result {
-                assert!(msg.contains("不是一个目录"));
+            let error = validate_folder_path(file_path).unwrap_err();
+            if let AppError::Path { message, .. } = &error {
+                assert!(message.contains("不是一个目录"));
             }
+            assert_eq!(error.suggestions(), vec![Suggestion::SelectFolder]);
+        }
+    }
+
+    /// 测试单个音频文件路径验证：目录、不支持的格式和不存在的文件都被拒绝
+    #[test]
+    fn test_validate_audio_file_path() {
+        let temp_dir = TempDir::new().expect("无法创建临时目录");
+        let song = temp_dir.path().join("Song.FLAC");
+        let notes = temp_dir.path().join("notes.txt");
+        File::create(&song).expect("无法创建测试文件");
+        File::create(&notes).expect("无法创建测试文件");
+
+        assert!(validate_audio_file_path(&song).is_ok());
+        let path_error = |path: &Path| match validate_audio_file_path(path) {
+            Err(AppError::Path { kind, message }) => (kind, message),
+            other => panic!("期望得到 AppError::Path 错误，得到 {:?}", other),
+        };
+        let (kind, message) = path_error(&notes);
+        assert_eq!(kind, PathErrorKind::UnsupportedFormat);
+        assert!(message.contains("不是支持的音频格式"));
+        let (kind, message) = path_error(temp_dir.path());
+        assert_eq!(kind, PathErrorKind::IsADirectory);
+        assert!(message.contains("是一个目录"));
+        let (kind, message) = path_error(&temp_dir.path().join("missing.mp3"));
+        assert_eq!(kind, PathErrorKind::NotFound);
+        assert!(message.contains("不存在"));
+        assert_eq!(validate_audio_file_path(temp_dir.path()).unwrap_err().suggestions(), vec![Suggestion::DropFileSubcommand]);
+    }
+
+    /// 测试命令行文件夹参数的解析
+    #[test]
+    fn test_resolve_folder_argument() {
+        let temp_dir = TempDir::new().expect("无法创建临时目录");
+        assert!(resolve_folder_argument(temp_dir.path()).is_ok());
+        let error = resolve_folder_argument(Path::new("/this/path/should/not/exist/12345")).unwrap_err();
+        assert!(matches!(&error, AppError::Path { .. }));
+    }
+
+    /// 测试合并结果文件时去重并排序
+    #[test]
+    fn test_merge_results_files() {
+        let temp_dir = TempDir::new().expect("无法创建临时目录");
+        let first = temp_dir.path().join("a.txt");
+        let second = temp_dir.path().join("b.txt");
+        let output = temp_dir.path().join("merged.txt");
+
+        fs::write(&first, format!("{}\n# 旧库\nx.flac - 3.0\ny.flac - 9.0\n", RESULTS_HEADER_LINE)).unwrap();
+        fs::write(&second, format!("{}\n# 旧库\n# 新库\nx.flac - 12.5\nz.mp3 - 1.0\n", RESULTS_HEADER_LINE)).unwrap();
+
+        let plain = TextFormat { bom: false, crlf: false };
+        let report = merge_results_files(&[first.clone(), second.clone()], &output, RESULTS_HEADER_LINE, plain, 1, true)
+            .expect("合并失败");
+        assert_eq!(report.entries, 3);
+        assert!(report.invalid_lines.is_empty());
+
+        let content = fs::read_to_string(&output).unwrap();
+        let lines: Vec<&str> = content.lines().collect();
+        assert_eq!(
+            lines,
+            vec![RESULTS_HEADER_LINE, FORMAT_LINE, "# 旧库", "# 新库", "x.flac - 12.5", "y.flac - 9.0", "z.mp3 - 1.0"]
+        );
+
+        // 指定更多位数时所有条目都按该精度写出
+        merge_results_files(&[first, second], &output, RESULTS_HEADER_LINE, plain, 2, true).expect("合并失败");
+        let content = fs::read_to_string(&output).unwrap();
+        assert!(content.ends_with("x.flac - 12.50\ny.flac - 9.00\nz.mp3 - 1.00\n"));
+
+        // 输入文件不存在时返回 I/O 错误
+        let missing = merge_results_files(
+            &[temp_dir.path().join("missing.txt")],
+            &output,
+            RESULTS_HEADER_LINE,
+            plain,
+            1,
+            true,
+        );
+        assert!(matches!(missing, Err(AppError::Io(_))));
+    }
+
+    /// 测试拖放路径的规范化
+    #[test]
+    fn test_normalize_dropped_path() {
+        // 带空格的引号路径
+        assert_eq!(
+            normalize_dropped_path_with("\"C:\\Users\\me\\My Music\"", false),
+            "C:\\Users\\me\\My Music"
+        );
+        assert_eq!(
+            normalize_dropped_path_with("'/Users/me/My Music' ", true),
+            "/Users/me/My Music"
+        );
+
+        // 首尾空白（包括粘贴时带入的换行）
+        assert_eq!(normalize_dropped_path_with("  /music/lib \n", true), "/music/lib");
+
+        // Unix 上的反斜杠转义
+        assert_eq!(
+            normalize_dropped_path_with("/Users/me/My\\ Music\\ \\(2024\\)", true),
+            "/Users/me/My Music (2024)"
+        );
+
+        // Windows 上不还原反斜杠
+        assert_eq!(
+            normalize_dropped_path_with("C:\\My\\ Music", false),
+            "C:\\My\\ Music"
+        );
+
+        // 路径中本身包含的引号应被保留
+        assert_eq!(
+            normalize_dropped_path_with("/music/Rock'n'Roll", true),
+            "/music/Rock'n'Roll"
+        );
+        assert_eq!(normalize_dropped_path_with("'/music/it's'", true), "/music/it's");
+
+        // 不成对的引号不被去除
+        assert_eq!(normalize_dropped_path_with("\"/music/lib", true), "\"/music/lib");
+        assert_eq!(normalize_dropped_path_with("\"", true), "\"");
+    }
+
+    /// 测试 ~ 和环境变量的展开
+    #[test]
+    fn test_expand_path_with() {
+        let lookup = |name: &str| match name {
+            "HOME" => Some("/home/alice".to_string()),
+            "MUSIC" => Some("/srv/music".to_string()),
+            "USERPROFILE" => Some("C:\\Users\\alice".to_string()),
+            _ => None,
+        };
+
+        // 开头的 ~ 和 ~user
+        assert_eq!(expand_path_with("~", lookup), "/home/alice");
+        assert_eq!(expand_path_with("~/Music", lookup), "/home/alice/Music");
+        assert_eq!(
+            expand_path_with("~bob/Music", lookup),
+            format!("/home{}bob/Music", std::path::MAIN_SEPARATOR)
+        );
+
+        // 中间的 ~ 保持原样
+        assert_eq!(expand_path_with("/a/~b", lookup), "/a/~b");
+
+        // $VAR 和 ${VAR}
+        assert_eq!(expand_path_with("$HOME/Music", lookup), "/home/alice/Music");
+        assert_eq!(expand_path_with("${MUSIC}/jazz", lookup), "/srv/music/jazz");
+        assert_eq!(expand_path_with("$MUSIC-old", lookup), "/srv/music-old");
+
+        // Windows 风格 %VAR% 只在 Windows 上展开
+        assert_eq!(
+            expand_path_for("%USERPROFILE%\\Music", lookup, true),
+            "C:\\Users\\alice\\Music"
+        );
+        assert_eq!(
+            expand_path_for("%USERPROFILE%\\Music", lookup, false),
+            "%USERPROFILE%\\Music"
+        );
+
+        // 不构成变量引用的 $ 和 % 保持原样
+        assert_eq!(expand_path_with("/music/$", lookup), "/music/$");
+        assert_eq!(expand_path_with("/music/100% live", lookup), "/music/100% live");
+
+        // 未设置的变量和找不到主目录的 ~ 保留为字面文本
+        assert_eq!(expand_path_with("$NOPE/Music", lookup), "$NOPE/Music");
+        assert_eq!(expand_path_with("/tmp/$ecret", lookup), "/tmp/$ecret");
+        assert_eq!(expand_path_with("${NOPE}/Music", lookup), "${NOPE}/Music");
+        assert_eq!(expand_path_for("/tmp/50%off%sale", lookup, true), "/tmp/50%off%sale");
+        assert_eq!(expand_path_for("%NOPE%%MUSIC%", lookup, true), "%NOPE%/srv/music");
+        assert_eq!(expand_path_with("~/Music", |_| None), "~/Music");
+    }
+
+    /// 测试字面路径已经存在时不做展开
+    #[test]
+    fn test_expand_path_keeps_existing_literal_path() {
+        let temp_dir = TempDir::new().unwrap();
+        for name in ["50%off%sale", "$ecret", "${HOME}"] {
+            let literal = temp_dir.path().join(name);
+            fs::create_dir(&literal).unwrap();
+            let literal_str = literal.to_str().unwrap();
+            assert_eq!(expand_path(literal_str), literal);
+            assert!(validate_folder_path(&expand_path(literal_str)).is_ok());
         }
     }
 
@@ -558,36 +2073,42 @@ mod tests {
         // 测试格式错误的行
         let invalid_line = "invalid format";
         let result = parse_result_line(invalid_line);
-        assert!(result.is_err());
-        assert!(result.unwrap_err().contains("行格式不正确"));
+        assert_eq!(result, Err(ResultLineParseError::MissingSeparator));
+        assert!(result.unwrap_err().to_string().contains("行格式不正确"));
 
         // 测试无效的 LRA 值
         let invalid_lra = "music/song.mp3 - not_a_number";
         let result = parse_result_line(invalid_lra);
-        assert!(result.is_err());
-        assert!(result.unwrap_err().contains("无法解析 LRA 值"));
+        match result {
+            Err(ref error @ ResultLineParseError::InvalidNumber { ref raw, .. }) => {
+                assert_eq!(raw, "not_a_number");
+                assert!(std::error::Error::source(error).is_some());
+                assert!(error.to_string().contains("无法解析 LRA 值 'not_a_number'"));
+            }
+            other => panic!("期望得到 InvalidNumber，实际为 {:?}", other),
+        }
 
         // 测试负数 LRA 值（应该被拒绝）
         let negative_lra = "music/song.mp3 - -5.0";
         let result = parse_result_line(negative_lra);
-        assert!(result.is_err());
-        assert!(result.unwrap_err().contains("超出合理范围"));
+        assert_eq!(result, Err(ResultLineParseError::OutOfRange { value: -5.0 }));
+        assert!(result.unwrap_err().to_string().contains("超出合理范围"));
 
         // 测试无穷大值
         let infinite_lra = "music/song.mp3 - inf";
         let result = parse_result_line(infinite_lra);
-        assert!(result.is_err());
+        assert_eq!(result, Err(ResultLineParseError::OutOfRange { value: f64::INFINITY }));
     }
 
     /// 测试条目排序功能
     #[test]
     fn test_sort_entries_by_lra() {
         let entries = vec![
-            ("file1.mp3".to_string(), 8.5),
-            ("file2.wav".to_string(), 15.2),
-            ("file3.flac".to_string(), 12.1),
-            ("file4.m4a".to_string(), 15.2), // 相同的 LRA 值
-            ("file5.ogg".to_string(), 5.3),
+            ("file1.mp3".into(), 8.5),
+            ("file2.wav".into(), 15.2),
+            ("file3.flac".into(), 12.1),
+            ("file4.m4a".into(), 15.2), // 相同的 LRA 值
+            ("file5.ogg".into(), 5.3),
         ];
 
         let sorted = sort_entries_by_lra(entries);
@@ -603,6 +2124,43 @@ mod tests {
         assert!(sorted[0].0 < sorted[1].0); // file2.wav < file4.m4a
     }
 
+    /// 测试超过阈值时的并行排序与顺序排序结果完全相同
+    #[test]
+    fn test_parallel_sort_matches_sequential() {
+        // 只有 25 种 LRA 值，大量条目需要按路径决定先后
+        let entries: Vec<(FileKey, f64)> = (0..PARALLEL_SORT_THRESHOLD + 1000)
+            .map(|i| (format!("file_{:06}.flac", (i * 7919) % 100_000).into(), (i % 25) as f64))
+            .collect();
+
+        let mut expected = entries.clone();
+        expected.sort_by(compare_entries);
+        assert_eq!(sort_entries_by_lra(entries), expected);
+    }
+
+    /// 测试按偏差排序：高于和低于目标同样距离的值按路径排列
+    #[test]
+    fn test_sort_entries_by_deviation() {
+        let entries = vec![
+            ("d_above.flac".into(), 10.5),
+            ("c_exact.flac".into(), 9.0),
+            ("b_below.flac".into(), 7.5),
+            ("z_far_below.flac".into(), 3.0),
+            ("a_above.flac".into(), 10.5),
+        ];
+        let sorted = sort_entries(entries, SortOrder::DeviationFrom(9.0));
+        let paths: Vec<&str> = sorted.iter().map(|(path, _)| path.as_str()).collect();
+        assert_eq!(paths, ["z_far_below.flac", "a_above.flac", "b_below.flac", "d_above.flac", "c_exact.flac"]);
+
+        let order = SortOrder::DeviationFrom(9.5);
+        assert_eq!(order.to_string(), "deviation:9.5");
+        let line = order.metadata_line().unwrap();
+        assert_eq!(line, "# 排序: deviation:9.5 (与目标 LRA 9.5 LU 的偏差从大到小)");
+        assert_eq!(SortOrder::from_metadata_line(&line), Some(order));
+        assert_eq!(SortOrder::LraDescending.metadata_line(), None);
+        assert_eq!(SortOrder::parse("deviation"), None);
+        assert_eq!(SortOrder::parse("deviation:inf"), None);
+    }
+
     /// 测试结果文件写入功能
     #[test]
     fn test_write_results_file() {
@@ -611,24 +2169,73 @@ mod tests {
 
         let header = "文件路径 (相对) - LRA 数值 (LU)";
         let entries = vec![
-            ("file1.mp3".to_string(), 12.5),
-            ("file2.wav".to_string(), 8.3),
-            ("file3.flac".to_string(), 15.7),
+            ("file1.mp3".into(), 12.5),
+            ("file2.wav".into(), 8.3),
+            ("file3.flac".into(), 15.7),
         ];
 
         // 写入文件
-        let result = write_results_file(&test_file, header, &entries);
+        let format = TextFormat { bom: false, crlf: false };
+        let result = write_results_file(&test_file, format, header, &[], &entries, &HashMap::new(), &[], 1, true);
         assert!(result.is_ok());
 
         // 验证文件内容
         let content = fs::read_to_string(&test_file).expect("无法读取文件");
         let lines: Vec<&str> = content.lines().collect();
 
-        assert_eq!(lines.len(), 4); // 表头 + 3 个数据行
+        assert_eq!(lines.len(), 5); // 表头 + 格式标记 + 3 个数据行
         assert_eq!(lines[0], header);
-        assert_eq!(lines[1], "file1.mp3 - 12.5");
-        assert_eq!(lines[2], "file2.wav - 8.3");
-        assert_eq!(lines[3], "file3.flac - 15.7");
+        assert_eq!(lines[1], FORMAT_LINE);
+        assert_eq!(lines[2], "file1.mp3 - 12.5");
+        assert_eq!(lines[3], "file2.wav - 8.3");
+        assert_eq!(lines[4], "file3.flac - 15.7");
+    }
+
+    /// 测试校验失败时保留原文件且不留下临时文件
+    #[test]
+    fn test_write_results_file_verification() {
+        let temp_dir = TempDir::new().expect("无法创建临时目录");
+        let test_file = temp_dir.path().join("lra_results.txt");
+        let previous = format!("{}\nold.flac - 5.0\n", RESULTS_HEADER_LINE);
+        fs::write(&test_file, &previous).unwrap();
+
+        // 路径中的换行使读回的条目比写入的多
+        let plain = TextFormat { bom: false, crlf: false };
+        let entries = vec![("a.flac - 1.0\nb.flac".into(), 2.0)];
+        let error = write_results_file(&test_file, plain, RESULTS_HEADER_LINE, &[], &entries, &HashMap::new(), &[], 1, true)
+            .unwrap_err();
+        assert_eq!(error.kind(), io::ErrorKind::InvalidData);
+        let inner = error.get_ref().and_then(|e| e.downcast_ref::<ResultsVerificationError>());
+        assert_eq!(inner, Some(&ResultsVerificationError::CountMismatch { written: 1, read_back: 2 }));
+        assert_eq!(fs::read_to_string(&test_file).unwrap(), previous);
+        assert!(!temp_path_for(&test_file).exists());
+
+        // 逐条比较路径和一位小数的 LRA 值
+        let good = vec![("a.flac".into(), 12.5), ("b.flac".into(), 8.3)];
+        write_results_file(&test_file, plain, RESULTS_HEADER_LINE, &[], &good, &HashMap::new(), &[], 1, true).unwrap();
+        assert!(verify_results_file(&test_file, RESULTS_HEADER_LINE, &[("a.flac".into(), 12.54), ("b.flac".into(), 8.3)], 1).is_ok());
+        let error = verify_results_file(&test_file, RESULTS_HEADER_LINE, &[("a.flac".into(), 12.5), ("b.flac".into(), 9.0)], 1)
+            .unwrap_err();
+        assert!(error.to_string().contains("第 2 个条目写入 'b.flac - 9.0'，读回 'b.flac - 8.3'"), "{}", error);
+        // 精度更高时一位小数的读回值不再相同
+        assert!(verify_results_file(&test_file, RESULTS_HEADER_LINE, &[("a.flac".into(), 12.54), ("b.flac".into(), 8.3)], 2).is_err());
+
+        // 不校验时照常写入
+        write_results_file(&test_file, plain, RESULTS_HEADER_LINE, &[], &entries, &HashMap::new(), &[], 1, false).unwrap();
+        assert_eq!(read_and_parse_results_file(&test_file).unwrap().entries.len(), 2);
+    }
+
+    /// 测试排序保留文件原有的小数位数，超出合理范围的值照常作为数据
+    #[test]
+    fn test_sort_preserves_precision() {
+        let temp_dir = TempDir::new().expect("无法创建临时目录");
+        let test_file = temp_dir.path().join("lra_results.txt");
+        fs::write(&test_file, format!("{}\na.flac - 7.25\nb.flac - 250.0\nc.flac - 7.3\n", RESULTS_HEADER_LINE)).unwrap();
+
+        assert_eq!(read_and_parse_results_file(&test_file).unwrap().precision, 2);
+        sort_lra_results_file(&test_file, RESULTS_HEADER_LINE).unwrap();
+        let lines: Vec<String> = fs::read_to_string(&test_file).unwrap().lines().map(str::to_string).collect();
+        assert_eq!(lines[2..], ["b.flac - 250.00", "c.flac - 7.30", "a.flac - 7.25"]);
     }
 
     /// 测试读取和解析结果文件功能
@@ -653,14 +2260,89 @@ file5.ogg - 9.1"#;
         let result = read_and_parse_results_file(&test_file);
         assert!(result.is_ok());
 
-        let entries = result.unwrap();
+        let contents = result.unwrap();
+        let entries = &contents.entries;
         assert_eq!(entries.len(), 4); // 应该成功解析 4 个有效条目
 
         // 验证解析的条目
-        assert_eq!(entries[0], ("file1.mp3".to_string(), 12.5));
-        assert_eq!(entries[1], ("file2.wav".to_string(), 8.3));
-        assert_eq!(entries[2], ("file3.flac".to_string(), 15.7));
-        assert_eq!(entries[3], ("file5.ogg".to_string(), 9.1));
+        assert_eq!(entries[0], ("file1.mp3".into(), 12.5));
+        assert_eq!(entries[1], ("file2.wav".into(), 8.3));
+        assert_eq!(entries[2], ("file3.flac".into(), 15.7));
+        assert_eq!(entries[3], ("file5.ogg".into(), 9.1));
+
+        // 无法解析的行原样保留
+        assert_eq!(contents.annotations, vec!["invalid line format", "file4.m4a - not_a_number"]);
+    }
+
+    /// 测试跨越多个读取块的大文件：条目顺序与文件一致，无效行被保留
+    #[test]
+    fn test_read_results_file_across_chunks() {
+        let temp_dir = TempDir::new().expect("无法创建临时目录");
+        let test_file = temp_dir.path().join("test_results.txt");
+
+        let line_count = PARSE_CHUNK_LINES * 2 + 10;
+        let mut content = String::from(RESULTS_HEADER_LINE);
+        for i in 0..line_count {
+            if i == PARSE_CHUNK_LINES {
+                content.push_str("\ninvalid line format");
+            } else {
+                content.push_str(&format!("\nfile_{}.mp3 - {}.0", i, i % 20));
+            }
+        }
+        fs::write(&test_file, content).expect("无法写入测试文件");
+
+        let contents = read_and_parse_results_file(&test_file).unwrap();
+        assert_eq!(contents.annotations, vec!["invalid line format"]);
+        let entries = contents.entries;
+        assert_eq!(entries.len(), line_count - 1);
+        assert_eq!(entries[0], ("file_0.mp3".into(), 0.0));
+        assert_eq!(entries[PARSE_CHUNK_LINES], (format!("file_{}.mp3", PARSE_CHUNK_LINES + 1).into(), ((PARSE_CHUNK_LINES + 1) % 20) as f64));
+        assert_eq!(entries.last().unwrap().0, format!("file_{}.mp3", line_count - 1));
+    }
+
+    proptest::proptest! {
+        /// 测试任意路径和非负有限 LRA 值写入后能原样读回（数值保留一位小数）
+        #[test]
+        fn prop_results_file_round_trip(
+            entries in proptest::collection::vec(
+                (
+                    proptest::prop_oneof![
+                        r"[^\r\n]{0,40}",
+                        // 集中生成分隔符、空白、# 和表头文字等容易出错的片段
+                        r"( - | |-|#|\t|文件路径 \(相对\)|LRA 数值 \(LU\)|[a-z0-9.]){0,12}",
+                    ],
+                    proptest::num::f64::POSITIVE
+                        | proptest::num::f64::NORMAL
+                        | proptest::num::f64::SUBNORMAL
+                        | proptest::num::f64::ZERO,
+                ),
+                0..20,
+            )
+        ) {
+            let temp_dir = TempDir::new().expect("无法创建临时目录");
+            let test_file = temp_dir.path().join("lra_results.txt");
+            let entries: Vec<(FileKey, f64)> =
+                entries.into_iter().map(|(path, lra)| (FileKey::from(path), lra)).collect();
+
+            let format = TextFormat::default();
+            write_results_file(&test_file, format, RESULTS_HEADER_LINE, &[], &entries, &HashMap::new(), &[], 1, true).unwrap();
+            let contents = read_and_parse_results_file(&test_file).unwrap();
+
+            proptest::prop_assert!(contents.annotations.is_empty(), "{:?}", contents.annotations);
+            proptest::prop_assert_eq!(contents.entries.len(), entries.len());
+            for ((path, lra), (read_path, read_lra)) in entries.iter().zip(&contents.entries) {
+                proptest::prop_assert_eq!(read_path, path);
+                let expected: f64 = format!("{:.1}", lra).parse().unwrap();
+                proptest::prop_assert_eq!(*read_lra, expected);
+            }
+        }
+
+        /// 测试任意输入都不会让行解析发生 panic
+        #[test]
+        fn prop_parse_result_line_never_panics(line in proptest::prelude::any::<String>()) {
+            let _ = parse_result_line(&line);
+            let _ = classify_result_line(&line, RESULTS_HEADER_LINE, 0);
+        }
     }
 
     /// 测试完整的结果文件排序功能
@@ -688,13 +2370,278 @@ file5.ogg - 5.3"#;
         let sorted_content = fs::read_to_string(&results_file).expect("无法读取排序后的文件");
         let lines: Vec<&str> = sorted_content.lines().collect();
 
-        assert_eq!(lines.len(), 6); // 表头 + 5 个数据行
+        assert_eq!(lines.len(), 7); // 表头 + 格式标记 + 5 个数据行
         assert_eq!(lines[0], header_line);
-        assert!(lines[1].contains("file4.m4a - 20.0"));
-        assert!(lines[2].contains("file2.wav - 15.2"));
-        assert!(lines[3].contains("file3.flac - 12.1"));
-        assert!(lines[4].contains("file1.mp3 - 8.5"));
-        assert!(lines[5].contains("file5.ogg - 5.3"));
+        assert_eq!(lines[1], FORMAT_LINE);
+        assert!(lines[2].contains("file4.m4a - 20.0"));
+        assert!(lines[3].contains("file2.wav - 15.2"));
+        assert!(lines[4].contains("file3.flac - 12.1"));
+        assert!(lines[5].contains("file1.mp3 - 8.5"));
+        assert!(lines[6].contains("file5.ogg - 5.3"));
+    }
+
+    /// 测试注释和批注行在排序后保留，且重复排序结果不变
+    #[test]
+    fn test_sort_preserves_annotations_idempotently() {
+        let temp_dir = TempDir::new().expect("无法创建临时目录");
+        let results_file = temp_dir.path().join("annotated_results.txt");
+
+        let content = format!(
+            "{}\n# 来源: 黑胶转录\nquiet.flac - 4.0\n# 以下为 2024 年补录\nloud.flac - 18.5\n待复查: live.wav\n",
+            RESULTS_HEADER_LINE
+        );
+        fs::write(&results_file, content).expect("无法写入测试文件");
+
+        assert!(matches!(classify_result_line("  # 注释", RESULTS_HEADER_LINE, 0), ResultLine::Comment(_)));
+        assert!(matches!(classify_result_line("待复查: live.wav", RESULTS_HEADER_LINE, 0), ResultLine::Invalid(..)));
+        // 以 # 开头的路径仍然是数据
+        assert!(matches!(classify_result_line("#1 Hits/song.flac - 5.0", RESULTS_HEADER_LINE, 0), ResultLine::Entry(_, 1, None)));
+
+        // 无法解析的行不打印，而是连同行号记录在报告中
+        let report = sort_lra_results_file(&results_file, RESULTS_HEADER_LINE).unwrap();
+        assert_eq!(report.entries, 2);
+        assert_eq!(report.invalid_lines.len(), 1);
+        assert_eq!(report.invalid_lines[0].line_number, 6);
+        assert_eq!(report.invalid_lines[0].text, "待复查: live.wav");
+        assert_eq!(report.invalid_lines[0].error, ResultLineParseError::MissingSeparator);
+        assert_eq!(
+            report.invalid_lines[0].to_string(),
+            format!("第 6 行: {}: '待复查: live.wav'", ResultLineParseError::MissingSeparator)
+        );
+        let first_pass = fs::read_to_string(&results_file).unwrap();
+        assert_eq!(
+            first_pass.lines().collect::<Vec<_>>(),
+            vec![
+                RESULTS_HEADER_LINE,
+                FORMAT_LINE,
+                "# 来源: 黑胶转录",
+                "# 以下为 2024 年补录",
+                "待复查: live.wav",
+                "loud.flac - 18.5",
+                "quiet.flac - 4.0",
+            ]
+        );
+
+        sort_lra_results_file(&results_file, RESULTS_HEADER_LINE).unwrap();
+        assert_eq!(fs::read_to_string(&results_file).unwrap(), first_pass);
+
+        // 只有批注没有数据时也保留批注
+        fs::write(&results_file, format!("{}\n# 空库\n", RESULTS_HEADER_LINE)).unwrap();
+        sort_lra_results_file(&results_file, RESULTS_HEADER_LINE).unwrap();
+        assert_eq!(fs::read_to_string(&results_file).unwrap(), format!("{}\n{}\n# 空库\n", RESULTS_HEADER_LINE, FORMAT_LINE));
+    }
+
+    /// 测试汇总页脚在两次排序后仍保持在数据之后，合并时被丢弃
+    #[test]
+    fn test_sort_preserves_footer() {
+        let temp_dir = TempDir::new().expect("无法创建临时目录");
+        let results_file = temp_dir.path().join("lra_results.txt");
+        let distribution = LraDistribution::from_values(&[3.0, 12.0]).unwrap();
+        let stats = ProcessingStats::new(2, 1, Vec::new());
+        let footer = results_footer_lines(&stats, Some(&distribution), Duration::from_secs(7));
+        assert_eq!(footer[0], "## 文件数: 2  平均 LRA: 7.5 LU  失败: 1  用时: 7s");
+        fs::write(
+            &results_file,
+            format!("{}\n# 批注\nlow.flac - 3.0\nhigh.flac - 12.0\n{}\n", RESULTS_HEADER_LINE, footer.join("\n")),
+        )
+        .unwrap();
+
+        let contents = read_and_parse_results_file(&results_file).unwrap();
+        assert_eq!(contents.footer, footer);
+        assert_eq!(contents.annotations, vec!["# 批注"]);
+        assert_eq!(contents.entries.len(), 2);
+
+        let expected =
+            format!("{}\n{}\n# 批注\nhigh.flac - 12.0\nlow.flac - 3.0\n{}\n", RESULTS_HEADER_LINE, FORMAT_LINE, footer.join("\n"));
+        sort_lra_results_file(&results_file, RESULTS_HEADER_LINE).unwrap();
+        assert_eq!(fs::read_to_string(&results_file).unwrap(), expected);
+        sort_lra_results_file(&results_file, RESULTS_HEADER_LINE).unwrap();
+        assert_eq!(fs::read_to_string(&results_file).unwrap(), expected);
+
+        let output = temp_dir.path().join("merged.txt");
+        let plain = TextFormat { bom: false, crlf: false };
+        merge_results_files(&[results_file], &output, RESULTS_HEADER_LINE, plain, 1, true).unwrap();
+        assert!(read_and_parse_results_file(&output).unwrap().footer.is_empty());
+
+        assert_eq!(format_elapsed(Duration::from_secs(3725)), "1h 02m");
+        assert!(results_footer_lines(&ProcessingStats::default(), None, Duration::ZERO)[0].starts_with("## 文件数: 0"));
+    }
+
+    /// 测试带 BOM 和 CRLF 换行的结果文件能被解析，排序后保持原格式
+    #[test]
+    fn test_sort_preserves_bom_and_crlf() {
+        let temp_dir = TempDir::new().expect("无法创建临时目录");
+        let results_file = temp_dir.path().join("lra_results.txt");
+        let format = TextFormat { bom: true, crlf: true };
+        let mut writer = format.create_file(&results_file).unwrap();
+        for line in [RESULTS_HEADER_LINE, "# 批注", "低.flac - 3.0", "高.flac - 12.0"] {
+            format.write_line(&mut writer, line).unwrap();
+        }
+        writer.flush().unwrap();
+
+        let contents = read_and_parse_results_file(&results_file).unwrap();
+        assert_eq!(contents.header_lines, 1);
+        assert_eq!(contents.annotations, vec!["# 批注"]);
+        assert_eq!(contents.entries, vec![("低.flac".into(), 3.0), ("高.flac".into(), 12.0)]);
+        assert_eq!(contents.format, format);
+
+        sort_lra_results_file(&results_file, RESULTS_HEADER_LINE).unwrap();
+        let sorted = fs::read_to_string(&results_file).unwrap();
+        assert_eq!(
+            sorted,
+            format!("{}{}\r\n{}\r\n# 批注\r\n高.flac - 12.0\r\n低.flac - 3.0\r\n", UTF8_BOM, RESULTS_HEADER_LINE, FORMAT_LINE)
+        );
+
+        // 只有 CRLF 没有 BOM 的文件同样保持原格式
+        fs::write(&results_file, format!("{}\r\na.flac - 1.0\r\nb.flac - 2.0\r\n", RESULTS_HEADER_LINE)).unwrap();
+        sort_lra_results_file(&results_file, RESULTS_HEADER_LINE).unwrap();
+        assert_eq!(
+            fs::read_to_string(&results_file).unwrap(),
+            format!("{}\r\n{}\r\nb.flac - 2.0\r\na.flac - 1.0\r\n", RESULTS_HEADER_LINE, FORMAT_LINE)
+        );
+    }
+
+    /// 测试缺少表头、重复表头以及路径中包含表头文字的文件
+    #[test]
+    fn test_sort_detects_header_lines() {
+        let temp_dir = TempDir::new().expect("无法创建临时目录");
+        let results_file = temp_dir.path().join("lra_results.txt");
+        let sort_lines = |content: String| {
+            fs::write(&results_file, content).unwrap();
+            sort_lra_results_file(&results_file, RESULTS_HEADER_LINE).unwrap();
+            fs::read_to_string(&results_file).unwrap().lines().map(String::from).collect::<Vec<_>>()
+        };
+
+        // 缺少表头：第 1 行是数据，不能被吞掉
+        fs::write(&results_file, "first.flac - 3.0\nsecond.flac - 9.0\n").unwrap();
+        let headerless = read_and_parse_results_file(&results_file).unwrap();
+        assert_eq!(headerless.header_lines, 0);
+        assert_eq!(headerless.entries.len(), 2);
+        assert!(sort_lra_results_file(&results_file, RESULTS_HEADER_LINE).unwrap().added_header);
+        assert_eq!(
+            sort_lines("first.flac - 3.0\nsecond.flac - 9.0\n".to_string()),
+            vec![RESULTS_HEADER_LINE, FORMAT_LINE, "second.flac - 9.0", "first.flac - 3.0"]
+        );
+
+        // 两个结果文件直接拼接：中间的表头被丢弃，输出只有一个表头
+        let doubled = format!(
+            "{h}\na.flac - 5.0\n{h}\nb.flac - 7.0\n",
+            h = RESULTS_HEADER_LINE
+        );
+        fs::write(&results_file, &doubled).unwrap();
+        let contents = read_and_parse_results_file(&results_file).unwrap();
+        assert_eq!(contents.header_lines, 2);
+        assert!(contents.annotations.is_empty());
+        let report = sort_lra_results_file(&results_file, RESULTS_HEADER_LINE).unwrap();
+        assert_eq!((report.removed_headers, report.added_header), (1, false));
+        assert_eq!(sort_lines(doubled), vec![RESULTS_HEADER_LINE, FORMAT_LINE, "b.flac - 7.0", "a.flac - 5.0"]);
+
+        // 路径中包含表头文字的行仍然是数据
+        let tricky_path = format!("{}/live.flac - 12.0", RESULTS_HEADER_LINE);
+        assert_eq!(
+            sort_lines(format!("{}\n{}\nz.flac - 1.0\n", RESULTS_HEADER_LINE, tricky_path)),
+            vec![RESULTS_HEADER_LINE.to_string(), FORMAT_LINE.to_string(), tricky_path, "z.flac - 1.0".to_string()]
+        );
+
+        // 以 # 开头的首行是注释，不是表头也不是数据
+        assert_eq!(
+            sort_lines("# 导出自旧版本\nx.flac - 2.0\n".to_string()),
+            vec![RESULTS_HEADER_LINE, FORMAT_LINE, "# 导出自旧版本", "x.flac - 2.0"]
+        );
+
+        // 调用方指定的自定义表头同样被识别，不会重复
+        fs::write(&results_file, "Path - LRA\nx.flac - 2.0\n").unwrap();
+        sort_lra_results_file(&results_file, "Path - LRA").unwrap();
+        assert_eq!(fs::read_to_string(&results_file).unwrap(), "Path - LRA\n# format: 2\nx.flac - 2.0\n");
+    }
+
+    /// 测试附加列表按列名沿用旧值，新值优先
+    #[test]
+    fn test_extra_column_table() {
+        let mut table = ExtraColumnTable::default();
+        table.add_columns(&["增益", "专辑增益"], "n/a");
+        table.set(&"c.flac".into(), &["专辑增益"], ["+0.50".to_string()]);
+        table.add_columns(&["艺术家"], "");
+        assert_eq!(table.header_line(), format!("{}\t增益\t专辑增益\t艺术家", RESULTS_HEADER_LINE));
+
+        let old = ResultsFileContents {
+            extra_header_columns: Some("\t艺术家\t已删除的列\t增益".to_string()),
+            extra_columns: [
+                ("a.flac".into(), "\tOld Artist\tx\t+1.00".to_string()),
+                ("b.flac".into(), "\tB Artist\ty\t+2.00".to_string()),
+            ]
+            .into_iter()
+            .collect(),
+            ..Default::default()
+        };
+        table.set(&"a.flac".into(), &["艺术家"], ["New Artist".to_string()]);
+        table.adopt(&old);
+
+        let paths: Vec<FileKey> = vec!["a.flac".into(), "b.flac".into(), "c.flac".into()];
+        let rendered = table.render(&paths);
+        assert_eq!(rendered["a.flac"], "\t+1.00\tn/a\tNew Artist");
+        assert_eq!(rendered["b.flac"], "\t+2.00\tn/a\tB Artist");
+        assert_eq!(rendered["c.flac"], "\tn/a\t+0.50\t");
+
+        assert!(ExtraColumnTable::default().render(&paths).is_empty());
+    }
+
+    /// 测试附加列随条目一起排序、合并，且不影响 LRA 值的解析
+    #[test]
+    fn test_extra_columns() {
+        let temp_dir = TempDir::new().expect("无法创建临时目录");
+        let results_file = temp_dir.path().join("replaygain_results.txt");
+        let header = format!("{}\t增益\t专辑", RESULTS_HEADER_LINE);
+
+        // 附加列中的 " - " 和路径中的制表符都不影响解析；没有附加列的旧行按整行解析
+        let content = format!(
+            "{}\nquiet.flac - 4.0\t+2.00\tLive - 2\nodd\tname.flac - 18.5\t-3.80\tn/a\nold.flac - 9.0\n",
+            header
+        );
+        fs::write(&results_file, content).expect("无法写入测试文件");
+
+        let contents = read_and_parse_results_file(&results_file).unwrap();
+        assert_eq!(contents.header_lines, 1);
+        assert_eq!(contents.extra_header_columns.as_deref(), Some("\t增益\t专辑"));
+        assert_eq!(
+            contents.entries,
+            vec![("quiet.flac".into(), 4.0), ("odd\tname.flac".into(), 18.5), ("old.flac".into(), 9.0)]
+        );
+        assert_eq!(contents.extra_columns["quiet.flac"], "\t+2.00\tLive - 2");
+        assert!(!contents.extra_columns.contains_key("old.flac"));
+
+        sort_lra_results_file(&results_file, RESULTS_HEADER_LINE).unwrap();
+        let sorted = fs::read_to_string(&results_file).unwrap();
+        assert_eq!(
+            sorted.lines().collect::<Vec<_>>(),
+            vec![
+                header.as_str(),
+                FORMAT_LINE,
+                "odd\tname.flac - 18.5\t-3.80\tn/a",
+                "old.flac - 9.0",
+                "quiet.flac - 4.0\t+2.00\tLive - 2",
+            ]
+        );
+
+        // 合并时使用带附加列的表头，同一路径以后出现的附加列为准
+        let plain = temp_dir.path().join("plain.txt");
+        let newer = temp_dir.path().join("newer.txt");
+        let output = temp_dir.path().join("merged.txt");
+        fs::write(&plain, format!("{}\nquiet.flac - 5.0\n", RESULTS_HEADER_LINE)).unwrap();
+        fs::write(&newer, format!("{}\nold.flac - 9.5\t+1.00\t+1.50\n", header)).unwrap();
+        let format = TextFormat { bom: false, crlf: false };
+        merge_results_files(&[results_file, plain, newer], &output, RESULTS_HEADER_LINE, format, 1, true).unwrap();
+        let merged = fs::read_to_string(&output).unwrap();
+        assert_eq!(
+            merged.lines().collect::<Vec<_>>(),
+            vec![
+                header.as_str(),
+                FORMAT_LINE,
+                "odd\tname.flac - 18.5\t-3.80\tn/a",
+                "old.flac - 9.5\t+1.00\t+1.50",
+                "quiet.flac - 5.0\t+2.00\tLive - 2",
+            ]
+        );
     }
 
     /// 测试空结果文件的排序
@@ -711,9 +2658,124 @@ file5.ogg - 5.3"#;
         let result = sort_lra_results_file(&results_file, header_line);
         assert!(result.is_ok());
 
-        // 验证文件内容保持不变
+        // 验证文件只有表头和格式标记
         let content = fs::read_to_string(&results_file).expect("无法读取文件");
-        assert_eq!(content.trim(), header_line);
+        assert_eq!(content.lines().collect::<Vec<_>>(), [header_line, FORMAT_LINE]);
+    }
+
+    /// 测试读取上一版程序写出的版本 1 文件，排序后升级到最新版本且内容不变
+    #[test]
+    fn test_read_version_1_results_file() {
+        let temp_dir = TempDir::new().expect("无法创建临时目录");
+        let results_file = temp_dir.path().join("lra_results.txt");
+        // 由没有格式标记的旧版本用 --replaygain --summary-footer --sort deviation:9 生成
+        fs::write(&results_file, include_str!("../tests/fixtures/results_v1.txt")).unwrap();
+
+        let v1 = ResultsFile::read(&results_file).unwrap();
+        assert_eq!(v1.version, 1);
+        assert_eq!(v1.contents.entries.len(), 3);
+        assert_eq!(v1.contents.sort_order, Some(SortOrder::DeviationFrom(9.0)));
+        assert_eq!(v1.contents.extra_columns["song.wav"], "\t-3.80\t-3.80");
+        assert_eq!(v1.contents.footer.len(), 2);
+        assert!(v1.contents.annotations.is_empty());
+
+        let report = sort_lra_results_file(&results_file, RESULTS_HEADER_LINE).unwrap();
+        assert_eq!(report.upgraded_from, Some(1));
+        let v2 = ResultsFile::read(&results_file).unwrap();
+        assert_eq!(v2.version, RESULTS_FORMAT_VERSION);
+        assert_eq!(v2.contents, v1.contents);
+        assert_eq!(fs::read_to_string(&results_file).unwrap().lines().nth(1), Some(FORMAT_LINE));
+    }
+
+    /// 测试比支持的版本更新的文件返回错误，无法识别的格式标记按普通注释保留
+    #[test]
+    fn test_read_unsupported_format_version() {
+        let temp_dir = TempDir::new().expect("无法创建临时目录");
+        let results_file = temp_dir.path().join("lra_results.txt");
+        fs::write(&results_file, format!("{}\n# format: 3\na.flac - 1.0\n", RESULTS_HEADER_LINE)).unwrap();
+        let error = ResultsFile::read(&results_file).unwrap_err();
+        assert_eq!(error.kind(), io::ErrorKind::InvalidData);
+        assert!(error.to_string().contains("格式版本 3"), "{}", error);
+        // 排序失败时文件保持原样
+        assert!(sort_lra_results_file(&results_file, RESULTS_HEADER_LINE).is_err());
+        assert!(fs::read_to_string(&results_file).unwrap().contains("# format: 3"));
+
+        fs::write(&results_file, format!("{}\n# format: 草稿\na.flac - 1.0\n", RESULTS_HEADER_LINE)).unwrap();
+        let file = ResultsFile::read(&results_file).unwrap();
+        assert_eq!(file.version, 1);
+        assert_eq!(file.contents.annotations, vec!["# format: 草稿"]);
+    }
+
+    /// 测试运行 ID 和 FFmpeg 位置元数据行：读取时不作为批注，排序和追加后紧跟在格式标记之后保留
+    #[test]
+    fn test_run_id_metadata() {
+        let temp_dir = TempDir::new().expect("无法创建临时目录");
+        let results_file = temp_dir.path().join("lra_results.txt");
+        let run_id = RunId::parse("20240601-153012-a3f9").unwrap();
+        let ffmpeg = Path::new("/opt/ffmpeg 7/bin/ffmpeg");
+        let mut header = Vec::new();
+        let format = TextFormat { bom: false, crlf: false };
+        write_results_header(&mut header, format, RESULTS_HEADER_LINE, Some(&run_id), Some(ffmpeg)).unwrap();
+        header.extend_from_slice("# 批注\na.flac - 1.0\nb.flac - 9.0\n".as_bytes());
+        fs::write(&results_file, header).unwrap();
+
+        let contents = ResultsFile::read(&results_file).unwrap().contents;
+        assert_eq!(contents.run_id.as_ref(), Some(&run_id));
+        assert_eq!(contents.ffmpeg_path.as_deref(), Some(ffmpeg));
+        assert_eq!(contents.annotations, vec!["# 批注"]);
+
+        sort_lra_results_file(&results_file, RESULTS_HEADER_LINE).unwrap();
+        extend_results_file(&results_file, RESULTS_HEADER_LINE, &[("c.flac".into(), 5.0)], 1, true).unwrap();
+        let content = fs::read_to_string(&results_file).unwrap();
+        let lines: Vec<&str> = content.lines().collect();
+        assert_eq!(
+            lines[..5],
+            [RESULTS_HEADER_LINE, FORMAT_LINE, "# run: 20240601-153012-a3f9", "# ffmpeg: /opt/ffmpeg 7/bin/ffmpeg", "# 批注"]
+        );
+        assert_eq!(lines[5..], ["b.flac - 9.0", "c.flac - 5.0", "a.flac - 1.0"]);
+    }
+
+    /// 测试固定时钟下结果文件的元数据头部与黄金文件一致
+    #[test]
+    fn test_golden_results_header() {
+        let run_id = RunId::generate_with(&FixedClock::from_unix_secs(1_717_255_812));
+        let mut header = Vec::new();
+        let format = TextFormat { bom: false, crlf: false };
+        write_results_header(&mut header, format, RESULTS_HEADER_LINE, Some(&run_id), None).unwrap();
+        assert_eq!(String::from_utf8(header).unwrap(), include_str!("../tests/fixtures/golden_results_header.txt"));
+    }
+
+    /// 测试输出目录可写性验证
+    #[test]
+    fn test_validate_output_writable() {
+        let temp_dir = TempDir::new().expect("无法创建临时目录");
+        let results_file = temp_dir.path().join("lra_results.txt");
+
+        assert!(validate_output_writable(&results_file).is_ok());
+
+        // 探测文件应该已被删除
+        let leftovers: Vec<_> = fs::read_dir(temp_dir.path()).unwrap().collect();
+        assert!(leftovers.is_empty());
+
+        // 不存在的目录无法写入
+        let missing = temp_dir.path().join("missing").join("lra_results.txt");
+        let error = validate_output_writable(&missing).unwrap_err();
+        assert!(matches!(&error, AppError::Path { .. }));
+        assert_eq!(error.suggestions(), vec![Suggestion::UseOutputFlag]);
+    }
+
+    /// 测试剩余空间检查
+    #[test]
+    fn test_check_free_space_for_results() {
+        let temp_dir = TempDir::new().expect("无法创建临时目录");
+        let results_file = temp_dir.path().join("lra_results.txt");
+
+        // 少量条目不应触发警告
+        assert!(check_free_space_for_results(&results_file, 10).is_none());
+
+        // 天文数字的条目数一定会触发警告
+        let warning = check_free_space_for_results(&results_file, usize::MAX / 256);
+        assert!(warning.is_some());
     }
 
     /// 测试不存在文件的排序处理
@@ -0,0 +1,406 @@
+//! 最近使用路径记录 (Recent Path History)
+//!
+//! 保存最近几次成功验证的文件夹路径，让交互提示可以直接复用，
+//! 避免每次启动都重新输入相同的路径。同一目录下还保存最近几次运行的分析速度
+//! （[`ThroughputHistory`]），用于开始分析前估算用时（见 [`crate::estimate`]）。
+//!
+//! ## 存储格式
+//!
+//! 历史文件是纯文本，每行一个路径，最近使用的在最前面。
+//! 速度记录同样是纯文本，每行一次运行：`<文件数> <字节数> <秒数>`，最近的在最前面。
+//! 文件位于用户的缓存目录下（见 [`default_history_file`]、[`default_throughput_file`]）。
+//!
+//! ## 容错策略
+//!
+//! 历史记录只是便利功能：文件缺失、损坏或无法写入时都会被静默忽略，
+//! 绝不会影响主流程。
+
+use std::fs;
+use std::io;
+use std::path::{Path, PathBuf};
+use std::time::Duration;
+
+use crate::estimate::Throughput;
+
+/// 最多保存的历史路径数量
+pub const MAX_HISTORY_ENTRIES: usize = 5;
+
+/// 最多保存的运行速度记录数量
+pub const MAX_THROUGHPUT_ENTRIES: usize = 10;
+
+/// 历史文件名
+const HISTORY_FILE_NAME: &str = "recent_folders.txt";
+
+/// 速度记录文件名
+const THROUGHPUT_FILE_NAME: &str = "throughput.txt";
+
+/// 最近使用路径记录 (Path History)
+///
+/// 可以通过 [`PathHistory::disabled`] 创建一个不读不写的空记录，
+/// 用于 `--no-history` 模式。
+#[derive(Debug, Clone, Default)]
+pub struct PathHistory {
+    /// 历史文件路径；为 `None` 时不会写入磁盘
+    file: Option<PathBuf>,
+    /// 历史路径，最近使用的在最前面
+    entries: Vec<PathBuf>,
+}
+
+impl PathHistory {
+    /// 创建一个禁用的历史记录（不读取也不写入）
+    pub fn disabled() -> Self {
+        Self::default()
+    }
+
+    /// 从指定文件加载历史记录
+    ///
+    /// 文件不存在或无法解析时返回空记录，后续的 [`PathHistory::save`]
+    /// 仍会写入该文件。
+    ///
+    /// # 参数
+    /// - `file` - 历史文件路径
+    pub fn load(file: &Path) -> Self {
+        let entries = fs::read_to_string(file)
+            .map(|content| parse_history(&content))
+            .unwrap_or_default();
+
+        Self {
+            file: Some(file.to_path_buf()),
+            entries,
+        }
+    }
+
+    /// 从默认位置加载历史记录
+    ///
+    /// 无法确定缓存目录时返回禁用的记录。
+    pub fn load_default() -> Self {
+        match default_history_file() {
+            Some(file) => Self::load(&file),
+            None => Self::disabled(),
+        }
+    }
+
+    /// 获取所有历史路径（最近使用的在最前面）
+    pub fn entries(&self) -> &[PathBuf] {
+        &self.entries
+    }
+
+    /// 获取最近一次使用的路径
+    pub fn most_recent(&self) -> Option<&Path> {
+        self.entries.first().map(PathBuf::as_path)
+    }
+
+    /// 记录一次成功使用的路径
+    ///
+    /// 路径会被移动到最前面，重复的旧记录会被删除，
+    /// 超出 [`MAX_HISTORY_ENTRIES`] 的旧记录会被丢弃。
+    pub fn record(&mut self, path: &Path) {
+        self.entries.retain(|existing| existing != path);
+        self.entries.insert(0, path.to_path_buf());
+        self.entries.truncate(MAX_HISTORY_ENTRIES);
+    }
+
+    /// 将历史记录写入磁盘
+    ///
+    /// 禁用的记录不会写入任何内容。
+    ///
+    /// # 返回值
+    /// - `Ok(())` - 写入成功或记录已禁用
+    /// - `Err(io::Error)` - 写入失败（调用方通常可以忽略）
+    pub fn save(&self) -> io::Result<()> {
+        let Some(file) = &self.file else {
+            return Ok(());
+        };
+
+        if let Some(parent) = file.parent() {
+            fs::create_dir_all(parent)?;
+        }
+
+        let content: String = self
+            .entries
+            .iter()
+            .map(|entry| format!("{}\n", entry.display()))
+            .collect();
+        fs::write(file, content)
+    }
+}
+
+/// 单次运行的分析量和用时
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+struct RunSample {
+    /// 分析的文件数
+    files: u64,
+    /// 分析的总字节数
+    bytes: u64,
+    /// 分析用时（毫秒）
+    millis: u64,
+}
+
+/// 运行速度记录 (Throughput History)
+///
+/// 与 [`PathHistory`] 相同：文件缺失、损坏或无法写入时都被静默忽略，
+/// [`ThroughputHistory::disabled`] 用于 `--no-history` 模式。
+#[derive(Debug, Clone, Default)]
+pub struct ThroughputHistory {
+    /// 记录文件路径；为 `None` 时不会写入磁盘
+    file: Option<PathBuf>,
+    /// 最近几次运行，最近的在最前面
+    samples: Vec<RunSample>,
+}
+
+impl ThroughputHistory {
+    /// 创建一个禁用的速度记录（不读取也不写入）
+    pub fn disabled() -> Self {
+        Self::default()
+    }
+
+    /// 从指定文件加载速度记录；文件不存在或无法解析的行被忽略
+    ///
+    /// # 参数
+    /// - `file` - 记录文件路径
+    pub fn load(file: &Path) -> Self {
+        let samples = fs::read_to_string(file)
+            .map(|content| parse_throughput(&content))
+            .unwrap_or_default();
+        Self { file: Some(file.to_path_buf()), samples }
+    }
+
+    /// 从默认位置加载速度记录；无法确定缓存目录时返回禁用的记录
+    pub fn load_default() -> Self {
+        match default_throughput_file() {
+            Some(file) => Self::load(&file),
+            None => Self::disabled(),
+        }
+    }
+
+    /// 最近几次运行的平均速度（总量除以总用时，长的运行权重更大）
+    ///
+    /// # 返回值
+    /// - `Some(Throughput)` - 至少有一次有效的记录
+    /// - `None` - 没有记录
+    pub fn average(&self) -> Option<Throughput> {
+        // 记录来自磁盘上的文件，数值可能任意大，求和时饱和而不是溢出
+        let total = |field: fn(&RunSample) -> u64| self.samples.iter().map(field).fold(0u64, u64::saturating_add);
+        let elapsed = Duration::from_millis(total(|sample| sample.millis));
+        let files = usize::try_from(total(|sample| sample.files)).unwrap_or(usize::MAX);
+        Throughput::from_run(files, total(|sample| sample.bytes), elapsed)
+    }
+
+    /// 记录一次运行；没有分析任何文件或用时为零时忽略
+    ///
+    /// 超出 [`MAX_THROUGHPUT_ENTRIES`] 的旧记录会被丢弃。
+    pub fn record(&mut self, files: usize, bytes: u64, elapsed: Duration) {
+        let millis = u64::try_from(elapsed.as_millis()).unwrap_or(u64::MAX);
+        if files == 0 || millis == 0 {
+            return;
+        }
+        self.samples.insert(0, RunSample { files: files as u64, bytes, millis });
+        self.samples.truncate(MAX_THROUGHPUT_ENTRIES);
+    }
+
+    /// 将速度记录写入磁盘；禁用的记录不会写入任何内容
+    ///
+    /// # 返回值
+    /// - `Ok(())` - 写入成功或记录已禁用
+    /// - `Err(io::Error)` - 写入失败（调用方通常可以忽略）
+    pub fn save(&self) -> io::Result<()> {
+        let Some(file) = &self.file else {
+            return Ok(());
+        };
+        if let Some(parent) = file.parent() {
+            fs::create_dir_all(parent)?;
+        }
+        let content: String = self
+            .samples
+            .iter()
+            .map(|sample| format!("{} {} {:.3}\n", sample.files, sample.bytes, sample.millis as f64 / 1000.0))
+            .collect();
+        fs::write(file, content)
+    }
+}
+
+/// 解析速度记录文件内容，忽略格式不对的行
+fn parse_throughput(content: &str) -> Vec<RunSample> {
+    content
+        .lines()
+        .filter_map(|line| {
+            let mut fields = line.split_whitespace();
+            let files = fields.next()?.parse().ok()?;
+            let bytes = fields.next()?.parse().ok()?;
+            let seconds: f64 = fields.next()?.parse().ok()?;
+            let millis = Duration::try_from_secs_f64(seconds).ok()?.as_millis();
+            (fields.next().is_none() && files > 0 && millis > 0)
+                .then(|| RunSample { files, bytes, millis: u64::try_from(millis).unwrap_or(u64::MAX) })
+        })
+        .take(MAX_THROUGHPUT_ENTRIES)
+        .collect()
+}
+
+/// 解析历史文件内容
+///
+/// 忽略空行和包含控制字符的行（文件损坏的迹象），并去除重复项。
+fn parse_history(content: &str) -> Vec<PathBuf> {
+    let mut entries: Vec<PathBuf> = Vec::new();
+
+    for line in content.lines() {
+        let line = line.trim();
+        if line.is_empty() || line.chars().any(char::is_control) {
+            continue;
+        }
+
+        let path = PathBuf::from(line);
+        if !entries.contains(&path) {
+            entries.push(path);
+        }
+        if entries.len() == MAX_HISTORY_ENTRIES {
+            break;
+        }
+    }
+
+    entries
+}
+
+/// 获取默认的历史文件路径 (Default History File)
+///
+/// - Linux 等: `$XDG_CACHE_HOME/lra-calculator/` 或 `~/.cache/lra-calculator/`
+/// - macOS: `~/Library/Caches/lra-calculator/`
+/// - Windows: `%LOCALAPPDATA%\lra-calculator\`
+///
+/// # 返回值
+/// - `Some(PathBuf)` - 历史文件路径
+/// - `None` - 无法确定缓存目录
+pub fn default_history_file() -> Option<PathBuf> {
+    Some(cache_dir()?.join(HISTORY_FILE_NAME))
+}
+
+/// 获取默认的速度记录文件路径，与 [`default_history_file`] 位于同一目录
+pub fn default_throughput_file() -> Option<PathBuf> {
+    Some(cache_dir()?.join(THROUGHPUT_FILE_NAME))
+}
+
+/// 本程序在用户缓存目录下的子目录
+fn cache_dir() -> Option<PathBuf> {
+    let env_dir = |name: &str| {
+        std::env::var_os(name)
+            .filter(|value| !value.is_empty())
+            .map(PathBuf::from)
+    };
+
+    let cache_dir = if cfg!(windows) {
+        env_dir("LOCALAPPDATA")
+    } else if cfg!(target_os = "macos") {
+        env_dir("HOME").map(|home| home.join("Library").join("Caches"))
+    } else {
+        env_dir("XDG_CACHE_HOME").or_else(|| env_dir("HOME").map(|home| home.join(".cache")))
+    }?;
+
+    Some(cache_dir.join("lra-calculator"))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempfile::TempDir;
+
+    /// 测试记录、去重和截断
+    #[test]
+    fn test_record_moves_to_front_and_truncates() {
+        let mut history = PathHistory::disabled();
+        for i in 0..7 {
+            history.record(Path::new(&format!("/music/{}", i)));
+        }
+        assert_eq!(history.entries().len(), MAX_HISTORY_ENTRIES);
+        assert_eq!(history.most_recent(), Some(Path::new("/music/6")));
+
+        // 再次使用旧路径时移到最前面，且不产生重复
+        history.record(Path::new("/music/4"));
+        assert_eq!(history.most_recent(), Some(Path::new("/music/4")));
+        assert_eq!(history.entries().len(), MAX_HISTORY_ENTRIES);
+        assert_eq!(
+            history.entries().iter().filter(|p| p.as_path() == Path::new("/music/4")).count(),
+            1
+        );
+    }
+
+    /// 测试保存和重新加载
+    #[test]
+    fn test_save_and_load_round_trip() {
+        let temp_dir = TempDir::new().expect("无法创建临时目录");
+        let file = temp_dir.path().join("nested").join("recent.txt");
+
+        let mut history = PathHistory::load(&file);
+        assert!(history.entries().is_empty());
+
+        history.record(Path::new("/music/a"));
+        history.record(Path::new("/music/b with space"));
+        history.save().expect("保存历史记录失败");
+
+        let reloaded = PathHistory::load(&file);
+        assert_eq!(
+            reloaded.entries(),
+            &[PathBuf::from("/music/b with space"), PathBuf::from("/music/a")]
+        );
+    }
+
+    /// 测试损坏的历史文件被静默忽略
+    #[test]
+    fn test_corrupt_history_is_ignored() {
+        let temp_dir = TempDir::new().expect("无法创建临时目录");
+        let file = temp_dir.path().join("recent.txt");
+
+        // 非 UTF-8 内容
+        fs::write(&file, [0xff, 0xfe, 0x00, 0x41]).unwrap();
+        assert!(PathHistory::load(&file).entries().is_empty());
+
+        // 混入空行、控制字符和重复项
+        fs::write(&file, "/music/a\n\n/bad\u{0}line\n/music/a\n/music/b\n").unwrap();
+        assert_eq!(
+            PathHistory::load(&file).entries(),
+            &[PathBuf::from("/music/a"), PathBuf::from("/music/b")]
+        );
+    }
+
+    /// 测试速度记录：平均值按总量计算，保存后重新加载，损坏的行被忽略
+    #[test]
+    fn test_throughput_history() {
+        let temp_dir = TempDir::new().expect("无法创建临时目录");
+        let file = temp_dir.path().join("throughput.txt");
+
+        let mut history = ThroughputHistory::load(&file);
+        assert_eq!(history.average(), None);
+        history.record(0, 100, Duration::from_secs(10));
+        history.record(10, 0, Duration::ZERO);
+        assert_eq!(history.average(), None);
+
+        history.record(10, 100 * 1024 * 1024, Duration::from_secs(60));
+        history.record(20, 0, Duration::from_secs(60));
+        let average = history.average().unwrap();
+        assert_eq!(average.files_per_minute, 15.0);
+        assert_eq!(average.mb_per_minute, 50.0);
+        history.save().expect("保存速度记录失败");
+        assert_eq!(ThroughputHistory::load(&file).average(), Some(average));
+
+        fs::write(&file, "12 1000 60.000\nbroken line\n3 x 1\n5 10 0\n1 2 3 4\n12 1000 60\n").unwrap();
+        assert_eq!(ThroughputHistory::load(&file).samples.len(), 2);
+
+        // 极大的数值求和时不会溢出
+        fs::write(&file, "1 18446744073709551615 60\n18446744073709551615 18446744073709551615 60\n").unwrap();
+        assert!(ThroughputHistory::load(&file).average().is_some());
+
+        let mut many = ThroughputHistory::disabled();
+        for _ in 0..MAX_THROUGHPUT_ENTRIES + 3 {
+            many.record(1, 1, Duration::from_secs(1));
+        }
+        assert_eq!(many.samples.len(), MAX_THROUGHPUT_ENTRIES);
+        assert!(many.save().is_ok());
+    }
+
+    /// 测试禁用的历史记录不写入磁盘
+    #[test]
+    fn test_disabled_history_does_not_write() {
+        let mut history = PathHistory::disabled();
+        history.record(Path::new("/music/a"));
+        assert!(history.save().is_ok());
+        assert_eq!(history.most_recent(), Some(Path::new("/music/a")));
+    }
+}
@@ -0,0 +1,245 @@
+//! 结果文件锁 (Results File Lock)
+//!
+//! 防止两个同时运行的进程向同一个结果文件交替写入导致内容损坏。
+//!
+//! ## 锁文件格式
+//!
+//! 锁文件位于结果文件旁边，名为 `<结果文件名>.lock`，内容为持有者的 PID。
+//! PID 只用于错误提示，是否被占用由操作系统的排他文件锁（`fs2`）决定。
+//!
+//! ## 过期锁
+//!
+//! 进程崩溃时锁文件会残留，但操作系统在进程退出时会释放它持有的文件锁。
+//! 因此锁文件存在但没有被锁住时，无论内容是否为空、是否刚刚修改，
+//! 都可以直接接管：加锁本身是原子的，两个进程同时接管时只有一个能成功。
+
+use std::ffi::OsString;
+use std::fs::{self, File, OpenOptions};
+use std::io::{self, Write};
+use std::path::{Path, PathBuf};
+
+use fs2::FileExt;
+
+use crate::error::AppError;
+
+/// 结果文件锁 (Results Lock)
+///
+/// 持有期间锁文件存在并被排他锁定；离开作用域时删除锁文件并释放锁。
+#[derive(Debug)]
+pub struct ResultsLock {
+    /// 锁文件路径
+    path: PathBuf,
+    /// 持有排他锁的锁文件句柄；关闭句柄即释放锁
+    file: Option<File>,
+}
+
+impl ResultsLock {
+    /// 获取结果文件的锁 (Acquire Lock)
+    ///
+    /// # 参数
+    /// - `results_file` - 要保护的结果文件路径
+    /// - `force` - 即使锁被其他存活进程持有也强制接管（`--force`）
+    ///
+    /// # 返回值
+    /// - `Ok(ResultsLock)` - 获取成功
+    /// - `Err(AppError::Configuration)` - 其他进程正在写入同一结果文件
+    /// - `Err(AppError::Io)` - 无法创建锁文件
+    pub fn acquire(results_file: &Path, force: bool) -> Result<Self, AppError> {
+        let path = lock_path_for(results_file);
+
+        loop {
+            let file = OpenOptions::new().read(true).write(true).create(true).truncate(false).open(&path)?;
+            match file.try_lock_exclusive() {
+                Ok(()) => {
+                    // 加锁前锁文件可能已被上一个持有者删除，此时锁住的是已经不在该路径下的文件，需要重试
+                    if !is_same_file(&file, &path) {
+                        continue;
+                    }
+                    write_pid(&file)?;
+                    return Ok(Self { path, file: Some(file) });
+                }
+                Err(e) if e.kind() == fs2::lock_contended_error().kind() => {}
+                Err(e) => return Err(AppError::Io(e)),
+            }
+
+            if !force {
+                let holder = match read_lock_pid(&path) {
+                    Some(pid) => format!("另一个进程 (PID {})", pid),
+                    None => "另一个进程".to_string(),
+                };
+                return Err(AppError::Configuration(format!(
+                    "{} 正在写入结果文件 {}。\n\
+                     如果确认没有其他实例在运行，请删除锁文件 {} 或使用 --force 参数。",
+                    holder,
+                    results_file.display(),
+                    path.display()
+                )));
+            }
+
+            // 强制接管：删除被锁住的锁文件，重新创建
+            drop(file);
+            match fs::remove_file(&path) {
+                Ok(()) => {}
+                Err(e) if e.kind() == io::ErrorKind::NotFound => {}
+                Err(e) => return Err(AppError::Io(e)),
+            }
+        }
+    }
+
+    /// 锁文件路径
+    pub fn path(&self) -> &Path {
+        &self.path
+    }
+
+    /// 释放锁
+    ///
+    /// 与直接丢弃等价，用于在代码中明确表达释放时机。
+    pub fn release(self) {}
+}
+
+impl Drop for ResultsLock {
+    /// Unix 上先删除再解锁：同时打开了旧文件的进程加锁后会发现文件已不在原路径下并重试。
+    /// Windows 上打开中的文件不能删除，因此先关闭句柄
+    fn drop(&mut self) {
+        if cfg!(unix) {
+            let _ = fs::remove_file(&self.path);
+            drop(self.file.take());
+        } else {
+            drop(self.file.take());
+            let _ = fs::remove_file(&self.path);
+        }
+    }
+}
+
+/// 获取结果文件对应的锁文件路径
+///
+/// # 示例
+/// ```
+/// use std::path::Path;
+/// use lra_calculator_rust::utils::lock::lock_path_for;
+///
+/// let lock = lock_path_for(Path::new("/music/lra_results.txt"));
+/// assert_eq!(lock, Path::new("/music/lra_results.txt.lock"));
+/// ```
+pub fn lock_path_for(results_file: &Path) -> PathBuf {
+    let mut name = OsString::from(results_file.as_os_str());
+    name.push(".lock");
+    PathBuf::from(name)
+}
+
+/// 把当前 PID 写入已经锁定的锁文件
+fn write_pid(mut file: &File) -> io::Result<()> {
+    file.set_len(0)?;
+    writeln!(file, "{}", std::process::id())?;
+    file.flush()
+}
+
+/// 读取锁文件中的 PID，无法读取或解析时返回 `None`
+fn read_lock_pid(path: &Path) -> Option<u32> {
+    fs::read_to_string(path).ok()?.trim().parse().ok()
+}
+
+/// 打开的文件是否仍是 `path` 指向的文件
+#[cfg(unix)]
+fn is_same_file(file: &File, path: &Path) -> bool {
+    use std::os::unix::fs::MetadataExt;
+
+    match (file.metadata(), fs::metadata(path)) {
+        (Ok(opened), Ok(current)) => opened.dev() == current.dev() && opened.ino() == current.ino(),
+        _ => false,
+    }
+}
+
+/// 打开的文件是否仍是 `path` 指向的文件
+///
+/// Windows 上打开中的文件不能被删除，只需确认路径仍然存在。
+#[cfg(not(unix))]
+fn is_same_file(_file: &File, path: &Path) -> bool {
+    path.exists()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempfile::TempDir;
+
+    /// 测试获取和释放锁
+    #[test]
+    fn test_acquire_and_release() {
+        let temp_dir = TempDir::new().expect("无法创建临时目录");
+        let results_file = temp_dir.path().join("lra_results.txt");
+
+        let lock = ResultsLock::acquire(&results_file, false).expect("获取锁失败");
+        let lock_path = lock.path().to_path_buf();
+        assert_eq!(lock_path, temp_dir.path().join("lra_results.txt.lock"));
+        assert_eq!(read_lock_pid(&lock_path), Some(std::process::id()));
+
+        lock.release();
+        assert!(!lock_path.exists());
+    }
+
+    /// 测试被其他持有者锁住的锁会拒绝获取，--force 可以接管
+    #[test]
+    fn test_held_lock_is_refused() {
+        let temp_dir = TempDir::new().expect("无法创建临时目录");
+        let results_file = temp_dir.path().join("lra_results.txt");
+
+        // 另一个持有者：锁文件被排他锁定，但 PID 还没写入（刚刚创建的空文件）
+        let holder = File::create(lock_path_for(&results_file)).unwrap();
+        holder.try_lock_exclusive().unwrap();
+
+        match ResultsLock::acquire(&results_file, false) {
+            Err(AppError::Configuration(msg)) => assert!(msg.contains("--force")),
+            other => panic!("期望得到 AppError::Configuration 错误, 实际为 {:?}", other),
+        }
+        assert!(lock_path_for(&results_file).exists());
+
+        // 已经写入 PID 的锁同样被拒绝，错误信息中包含 PID
+        let other_file = temp_dir.path().join("other.txt");
+        let first = ResultsLock::acquire(&other_file, false).unwrap();
+        match ResultsLock::acquire(&other_file, false) {
+            Err(AppError::Configuration(msg)) => assert!(msg.contains(&std::process::id().to_string())),
+            other => panic!("期望得到 AppError::Configuration 错误, 实际为 {:?}", other),
+        }
+        drop(first);
+
+        let forced = ResultsLock::acquire(&results_file, true).expect("强制获取锁失败");
+        assert_eq!(read_lock_pid(forced.path()), Some(std::process::id()));
+    }
+
+    /// 测试没有被锁住的残留锁文件（进程已退出、内容为空或损坏）会被接管
+    #[test]
+    fn test_stale_lock_is_replaced() {
+        let temp_dir = TempDir::new().expect("无法创建临时目录");
+        let results_file = temp_dir.path().join("lra_results.txt");
+        let lock_path = lock_path_for(&results_file);
+
+        for content in ["4294967295\n", "", "not a pid"] {
+            fs::write(&lock_path, content).unwrap();
+            let lock = ResultsLock::acquire(&results_file, false).expect("残留的锁应被接管");
+            assert_eq!(read_lock_pid(lock.path()), Some(std::process::id()));
+        }
+    }
+
+    /// 测试多个线程同时获取同一个锁时只有一个成功
+    #[test]
+    fn test_concurrent_acquire_has_single_winner() {
+        let temp_dir = TempDir::new().expect("无法创建临时目录");
+        let results_file = temp_dir.path().join("lra_results.txt");
+        fs::write(lock_path_for(&results_file), "").unwrap();
+
+        let barrier = std::sync::Barrier::new(8);
+        let locks: Vec<ResultsLock> = std::thread::scope(|scope| {
+            let handles: Vec<_> = (0..8)
+                .map(|_| {
+                    scope.spawn(|| {
+                        barrier.wait();
+                        ResultsLock::acquire(&results_file, false).ok()
+                    })
+                })
+                .collect();
+            handles.into_iter().filter_map(|handle| handle.join().unwrap()).collect()
+        });
+        assert_eq!(locks.len(), 1);
+    }
+}
@@ -0,0 +1,419 @@
+//! 交互式路径输入 (Interactive Folder Prompt)
+//!
+//! 没有在命令行中指定文件夹时，程序从标准输入交互式读取要处理的文件夹，
+//! 并提供最近使用的路径（见 [`super::history`]）。只在启用 `cli` 特性时编译。
+//!
+//! 交互逻辑在 [`prompt_folder_path`] 中，输入流和输出流都通过参数注入，
+//! 测试和图形界面包装器可以用脚本化的输入驱动，并检查写出的提示。
+//!
+//! 无效输入最多允许 [`PromptOptions::max_attempts`] 次，之后返回错误，避免包装程序不断
+//! 输入空行时无限循环；与上一次相同的无效输入只输出一行提示，不重复完整的错误信息。
+
+use std::io::{self, BufRead, Write};
+use std::path::{Path, PathBuf};
+
+use super::history::PathHistory;
+use super::{canonicalize_path, expand_path, normalize_dropped_path, validate_folder_path};
+use crate::console::{HumanWriter, Verbatim};
+use crate::error::AppError;
+use crate::suggestion::{render_suggestions, Language};
+
+/// 默认允许的无效输入次数
+pub const DEFAULT_MAX_PROMPT_ATTEMPTS: usize = 10;
+
+/// 交互式输入选项 (Prompt Options)
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct PromptOptions {
+    /// 允许的无效输入次数，达到后返回 `AppError::Configuration`（至少为 1）
+    pub max_attempts: usize,
+}
+
+impl Default for PromptOptions {
+    fn default() -> Self {
+        Self { max_attempts: DEFAULT_MAX_PROMPT_ATTEMPTS }
+    }
+}
+
+/// 从用户输入获取要处理的文件夹路径 (Get Folder Path from User Input)
+///
+/// 这是程序与用户交互的核心函数，负责安全地获取用户输入的文件夹路径。
+/// 它实现了一个健壮的输入循环，包含完整的验证和错误处理机制。
+///
+/// ## 交互流程
+///
+/// ### 输入提示
+/// - 显示清晰的中文提示信息
+/// - 说明期望的输入格式和要求
+/// - 提供示例路径格式
+///
+/// ### 输入验证
+/// 1. **非空检查**: 确保用户输入不为空
+/// 2. **路径存在性**: 验证路径在文件系统中存在
+/// 3. **目录检查**: 确认路径指向目录而非文件
+/// 4. **权限验证**: 检查程序是否有读取权限
+/// 5. **路径规范化**: 转换为绝对路径，解析符号链接
+///
+/// ### 错误处理
+/// - 对每种错误提供具体的中文说明
+/// - 给出可能的解决方案和建议
+/// - 允许用户重新输入而不退出程序
+///
+/// ## 安全考虑
+///
+/// ### 路径安全
+/// - 使用 `PathBuf::from()` 安全地构造路径
+/// - 通过 `canonicalize()` 解析符号链接，防止路径遍历攻击
+/// - 验证最终路径的有效性
+///
+/// ### 输入安全
+/// - 使用 `trim()` 移除前后空白字符
+/// - 处理各种异常输入情况
+/// - 防止无限循环：无效输入达到 [`DEFAULT_MAX_PROMPT_ATTEMPTS`] 次后返回错误
+///
+/// # 返回值
+/// - `Ok(PathBuf)` - 经过验证和规范化的有效文件夹路径
+/// - `Err(AppError::Io)` - 不可恢复的 I/O 错误（如标准输入不可用）
+/// - `Err(AppError::Interrupted)` - 用户输入 `q`/`quit`/`exit` 取消操作
+/// - `Err(AppError::Configuration)` - 标准输入已关闭，或无效输入达到 [`DEFAULT_MAX_PROMPT_ATTEMPTS`] 次
+///
+/// # 错误处理
+/// - 可恢复错误（如路径不存在）会提示用户重新输入
+/// - 不可恢复错误（如 I/O 失败）会返回错误并终止函数
+///
+/// # 使用示例
+/// ```rust,no_run
+/// use lra_calculator_rust::utils::get_folder_path_from_user;
+///
+/// match get_folder_path_from_user() {
+///     Ok(path) => println!("选择的路径: {}", path.display()),
+///     Err(e) => eprintln!("获取路径失败: {}", e),
+/// }
+/// ```
+pub fn get_folder_path_from_user() -> Result<PathBuf, AppError> {
+    get_folder_path_with_history(&mut PathHistory::disabled())
+}
+
+/// 从用户输入获取文件夹路径，并提供最近使用的路径 (Get Folder Path with History)
+///
+/// 与 [`get_folder_path_from_user`] 相同，但会列出最近使用的文件夹：
+/// 直接按回车复用最近一次的路径，输入编号选择列表中的路径。
+/// 验证成功的路径会被记录到历史中并保存（保存失败会被忽略）。
+///
+/// # 参数
+/// - `history` - 最近使用路径记录（`--no-history` 时为禁用的记录）
+///
+/// # 返回值
+/// - 与 [`get_folder_path_from_user`] 相同
+pub fn get_folder_path_with_history(history: &mut PathHistory) -> Result<PathBuf, AppError> {
+    get_folder_path_with_options(history, &PromptOptions::default())
+}
+
+/// 按选项从用户输入获取文件夹路径 (Get Folder Path with Options)
+///
+/// 与 [`get_folder_path_with_history`] 相同，无效输入的次数上限由 `options` 指定。
+///
+/// # 参数
+/// - `history` - 最近使用路径记录
+/// - `options` - 交互式输入选项
+///
+/// # 返回值
+/// - 与 [`get_folder_path_from_user`] 相同；无效输入达到 `options.max_attempts` 次时返回 `AppError::Configuration`
+pub fn get_folder_path_with_options(history: &mut PathHistory, options: &PromptOptions) -> Result<PathBuf, AppError> {
+    prompt_folder_path(io::stdin().lock(), HumanWriter, history, options)
+}
+
+/// 从指定输入流读取文件夹路径 (Prompt Folder Path from Reader)
+///
+/// 交互提示的实现：提示、错误信息和验证结果都写入 `output`。
+/// [`get_folder_path_with_options`] 使用标准输入和人类可读输出（[`HumanWriter`]）。
+/// 读取到 EOF（例如从 GUI 包装器或 CI 中启动、标准输入已关闭）时立即返回错误，
+/// 而不是把 EOF 当作空输入无限重试。
+///
+/// # 参数
+/// - `input` - 输入流，每行一次输入；路径两端的引号和空白会被去掉
+/// - `output` - 提示和错误信息的输出流
+/// - `history` - 最近使用路径记录
+/// - `options` - 交互式输入选项
+///
+/// # 返回值
+/// - 与 [`get_folder_path_with_options`] 相同；EOF 时返回 `AppError::Configuration`，
+///   写入 `output` 失败时返回 `AppError::Io`
+///
+/// # 示例
+/// ```
+/// use lra_calculator_rust::error::AppError;
+/// use lra_calculator_rust::utils::history::PathHistory;
+/// use lra_calculator_rust::utils::prompt::{prompt_folder_path, PromptOptions};
+///
+/// let mut output = Vec::new();
+/// let result = prompt_folder_path(&b"\nq\n"[..], &mut output, &mut PathHistory::disabled(), &PromptOptions::default());
+/// assert!(matches!(result, Err(AppError::Interrupted { .. })));
+/// assert!(String::from_utf8(output).unwrap().contains("路径不能为空"));
+/// ```
+pub fn prompt_folder_path<R: BufRead, W: Write>(
+    mut input: R,
+    mut output: W,
+    history: &mut PathHistory,
+    options: &PromptOptions,
+) -> Result<PathBuf, AppError> {
+    // 显示友好的欢迎信息和使用提示
+    writeln!(output, "\n📁 请选择要处理的音频文件夹")?;
+    writeln!(output, "💡 提示: 程序将递归扫描该文件夹及其所有子文件夹中的音频文件")?;
+    writeln!(output, "📝 支持的格式: WAV, MP3, FLAC, AAC, OGG, Opus, WMA, AIFF, ALAC")?;
+    writeln!(output)?;
+
+    if !history.entries().is_empty() {
+        writeln!(output, "🕘 最近使用的文件夹:")?;
+        for (index, entry) in history.entries().iter().enumerate() {
+            writeln!(output, "   {}. {}", index + 1, Verbatim(entry.display()))?;
+        }
+        writeln!(output, "💡 直接按回车使用 [1]，或输入编号选择")?;
+        writeln!(output)?;
+    }
+
+    let mut invalid_attempts = 0;
+    let mut last_invalid_input: Option<String> = None;
+    loop {
+        // 显示输入提示
+        match history.most_recent() {
+            Some(recent) => write!(output, "请输入文件夹路径 [{}]: ", Verbatim(recent.display()))?,
+            None => write!(output, "请输入文件夹路径: ")?,
+        }
+        output.flush()?;  // 确保提示信息立即显示
+
+        // 读取用户输入；读取到 0 字节表示 EOF，非交互环境下不能继续重试
+        let mut line = String::new();
+        if input.read_line(&mut line)? == 0 {
+            writeln!(output)?;
+            return Err(AppError::Configuration(format!(
+                "标准输入已关闭，无法交互式读取文件夹路径。\n\
+                 请将路径作为命令行参数传入，例如: {} /path/to/music",
+                env!("CARGO_PKG_NAME")
+            )));
+        }
+        let normalized_input = normalize_dropped_path(&line);
+
+        // 回车或编号选择历史路径
+        let history_choice = resolve_history_choice(&normalized_input, history);
+        let path_str = match &history_choice {
+            Some(chosen) => chosen.to_str().unwrap_or(normalized_input.as_str()),
+            None => normalized_input.as_str(),
+        };
+
+        // 处理特殊输入
+        if path_str == "quit" || path_str == "exit" || path_str == "q" {
+            return Err(AppError::Interrupted { completed: 0, total: 0 });
+        }
+
+        let message = match check_folder_input(path_str, history_choice.as_deref()) {
+            Ok(canonical_path) => {
+                writeln!(output, "✅ 路径验证成功: {}", Verbatim(canonical_path.display()))?;
+                history.record(&canonical_path);
+                // 历史记录只是便利功能，保存失败不影响主流程
+                let _ = history.save();
+                return Ok(canonical_path);
+            }
+            Err(message) => message,
+        };
+
+        // 与上一次相同的无效输入（例如不断输入的空行）只提示一行
+        if last_invalid_input.as_deref() == Some(normalized_input.as_str()) {
+            writeln!(output, "❌ 与上一次的输入相同，仍然无效（输入 'q' 退出）")?;
+        } else {
+            writeln!(output, "{}", Verbatim(message))?;
+        }
+        last_invalid_input = Some(normalized_input);
+
+        invalid_attempts += 1;
+        if invalid_attempts >= options.max_attempts.max(1) {
+            return Err(AppError::Configuration(format!(
+                "无效输入已达 {} 次，停止交互式读取文件夹路径。\n\
+                 请将路径作为命令行参数传入，例如: {} /path/to/music",
+                invalid_attempts,
+                env!("CARGO_PKG_NAME")
+            )));
+        }
+    }
+}
+
+/// 检查输入的文件夹路径 (Check Folder Input)
+///
+/// # 参数
+/// - `path_str` - 输入的路径（选择历史路径时为该路径）
+/// - `history_choice` - 选择的历史路径，已经是展开后的绝对路径
+///
+/// # 返回值
+/// - `Ok(PathBuf)` - 验证并规范化后的路径
+/// - `Err(String)` - 向用户显示的错误信息（可能有多行），已按当前字符集格式化
+fn check_folder_input(path_str: &str, history_choice: Option<&Path>) -> Result<PathBuf, String> {
+    // 检查输入是否为空
+    if path_str.is_empty() {
+        return Err(human_format!("❌ 错误: 路径不能为空，请重新输入。"));
+    }
+
+    // 展开 ~ 和环境变量，然后构造路径对象
+    let path = match history_choice {
+        Some(chosen) => chosen.to_path_buf(),
+        None => expand_path(path_str),
+    };
+
+    // 验证路径的有效性
+    if let Err(e) = validate_folder_path(&path) {
+        let mut message = human_format!("❌ 路径验证失败: {}", e);
+        if let Some(suggestions) = render_suggestions(&e.suggestions(), Language::Chinese) {
+            message.push('\n');
+            message.push_str(&suggestions);
+        }
+        message.push_str(&human_format!("\n💡 提示: 输入 'q' 或 'quit' 退出程序"));
+        return Err(message);
+    }
+
+    // 路径验证成功，尝试规范化
+    canonicalize_path(&path)
+        .map_err(|e| human_format!("❌ 路径规范化失败: {}\n💡 建议: 请检查路径格式是否正确，或尝试使用绝对路径", e))
+}
+
+/// 根据输入选择历史路径 (Resolve History Choice)
+///
+/// # 参数
+/// - `input` - 规范化后的用户输入
+/// - `history` - 最近使用路径记录
+///
+/// # 返回值
+/// - `Some(PathBuf)` - 空输入对应最近一次的路径，编号对应列表中的路径
+/// - `None` - 输入不是历史选择，应按普通路径处理
+fn resolve_history_choice(input: &str, history: &PathHistory) -> Option<PathBuf> {
+    if input.is_empty() {
+        return history.most_recent().map(Path::to_path_buf);
+    }
+
+    let index: usize = input.parse().ok()?;
+    index
+        .checked_sub(1)
+        .and_then(|i| history.entries().get(i))
+        .cloned()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempfile::TempDir;
+
+    /// 用脚本化的输入运行交互提示，返回结果和写出的全部输出
+    fn run_prompt(input: &str, history: &mut PathHistory) -> (Result<PathBuf, AppError>, String) {
+        let mut output = Vec::new();
+        let result = prompt_folder_path(input.as_bytes(), &mut output, history, &PromptOptions::default());
+        (result, String::from_utf8(output).expect("输出应为 UTF-8"))
+    }
+
+    /// 测试标准输入为空（EOF）时立即返回错误，而不是无限重试
+    #[test]
+    fn test_prompt_returns_error_on_eof() {
+        let mut empty_stdin = io::Cursor::new(Vec::<u8>::new());
+        let result = prompt_folder_path(&mut empty_stdin, io::sink(), &mut PathHistory::disabled(), &PromptOptions::default());
+
+        match result {
+            Err(AppError::Configuration(msg)) => assert!(msg.contains("命令行参数")),
+            other => panic!("期望得到 AppError::Configuration 错误, 实际为 {:?}", other),
+        }
+
+        // 先输入无效路径再遇到 EOF，同样应该返回错误
+        let mut invalid_then_eof = io::Cursor::new(b"/this/path/should/not/exist/12345\n".to_vec());
+        let result = prompt_folder_path(&mut invalid_then_eof, io::sink(), &mut PathHistory::disabled(), &PromptOptions::default());
+        assert!(matches!(result, Err(AppError::Configuration(_))));
+    }
+
+    /// 测试无效输入达到上限后返回错误，不再读取后续输入
+    #[test]
+    fn test_prompt_stops_after_max_attempts() {
+        // 不断输入的空行
+        let mut newlines = io::Cursor::new(vec![b'\n'; 1000]);
+        let result = prompt_folder_path(&mut newlines, io::sink(), &mut PathHistory::disabled(), &PromptOptions::default());
+        match result {
+            Err(AppError::Configuration(msg)) => assert!(msg.contains("无效输入已达 10 次")),
+            other => panic!("期望得到 AppError::Configuration 错误, 实际为 {:?}", other),
+        }
+        assert_eq!(newlines.position(), DEFAULT_MAX_PROMPT_ATTEMPTS as u64);
+
+        // 不同的无效输入同样计数，有效输入之前达到上限
+        let temp_dir = TempDir::new().expect("无法创建临时目录");
+        let mut input = io::Cursor::new(format!("\n/missing/a\n/missing/b\n{}\n", temp_dir.path().display()).into_bytes());
+        let result = prompt_folder_path(&mut input, io::sink(), &mut PathHistory::disabled(), &PromptOptions { max_attempts: 3 });
+        assert!(matches!(result, Err(AppError::Configuration(_))));
+    }
+
+    /// 测试从输入流读取有效路径：空行提示后重新读取，路径两端的引号和空白被去掉
+    #[test]
+    fn test_prompt_reads_valid_path() {
+        let temp_dir = TempDir::new().expect("无法创建临时目录");
+        let mut history = PathHistory::disabled();
+
+        let (result, output) = run_prompt(&format!("\n  '{}'  \n", temp_dir.path().display()), &mut history);
+        let path = result.expect("应该读取到有效路径");
+        assert_eq!(path, temp_dir.path().canonicalize().unwrap());
+        assert_eq!(history.most_recent(), Some(path.as_path()));
+
+        assert!(output.starts_with("\n📁 请选择要处理的音频文件夹\n"));
+        assert!(!output.contains("最近使用的文件夹"));
+        assert_eq!(output.matches("请输入文件夹路径: ").count(), 2);
+        assert!(output.contains("请输入文件夹路径: ❌ 错误: 路径不能为空，请重新输入。\n"));
+        assert!(output.ends_with(&format!("✅ 路径验证成功: {}\n", path.display())));
+    }
+
+    /// 测试空行、无效路径、重复的无效路径之后输入 quit：每种错误只完整输出一次
+    #[test]
+    fn test_prompt_script_until_quit() {
+        let (result, output) = run_prompt("\n/missing/lra\n/missing/lra\nquit\nnot read\n", &mut PathHistory::disabled());
+        assert!(matches!(result, Err(AppError::Interrupted { completed: 0, total: 0 })));
+
+        assert_eq!(output.matches("请输入文件夹路径: ").count(), 4);
+        assert_eq!(output.matches("路径不能为空").count(), 1);
+        assert_eq!(output.matches("❌ 路径验证失败: ").count(), 1);
+        assert!(output.contains("💡 建议:\n   • 检查路径拼写"));
+        assert!(output.contains("请输入文件夹路径: ❌ 与上一次的输入相同，仍然无效（输入 'q' 退出）\n"));
+        assert!(output.ends_with("请输入文件夹路径: "));
+
+        for keyword in ["q", "exit", "\"quit\""] {
+            let (result, _) = run_prompt(&format!("{}\n", keyword), &mut PathHistory::disabled());
+            assert!(matches!(result, Err(AppError::Interrupted { .. })), "{}", keyword);
+        }
+    }
+
+    /// 测试列出最近使用的文件夹，直接按回车选择最近一次的路径
+    #[test]
+    fn test_prompt_with_history() {
+        let temp_dir = TempDir::new().expect("无法创建临时目录");
+        let recent = temp_dir.path().canonicalize().unwrap();
+        let mut history = PathHistory::disabled();
+        history.record(Path::new("/music/old"));
+        history.record(&recent);
+
+        let (result, output) = run_prompt("\n", &mut history);
+        assert_eq!(result.unwrap(), recent);
+        assert!(output.contains(&format!("🕘 最近使用的文件夹:\n   1. {}\n   2. /music/old\n", recent.display())));
+        assert!(output.contains(&format!("请输入文件夹路径 [{}]: ✅", recent.display())));
+    }
+
+    /// 测试历史路径选择
+    #[test]
+    fn test_resolve_history_choice() {
+        let empty = PathHistory::disabled();
+        assert_eq!(resolve_history_choice("", &empty), None);
+        assert_eq!(resolve_history_choice("1", &empty), None);
+
+        let mut history = PathHistory::disabled();
+        history.record(Path::new("/music/old"));
+        history.record(Path::new("/music/new"));
+
+        // 回车复用最近一次的路径
+        assert_eq!(resolve_history_choice("", &history), Some(PathBuf::from("/music/new")));
+
+        // 编号选择
+        assert_eq!(resolve_history_choice("2", &history), Some(PathBuf::from("/music/old")));
+
+        // 超出范围的编号和普通路径不视为历史选择
+        assert_eq!(resolve_history_choice("0", &history), None);
+        assert_eq!(resolve_history_choice("3", &history), None);
+        assert_eq!(resolve_history_choice("/music/other", &history), None);
+    }
+}
@@ -0,0 +1,498 @@
+//! 结果集 (Results Set)
+//!
+//! "读取结果文件、筛选、排序、写回"是 `sort`、`merge`、`stats` 子命令和 `--update-baseline`
+//! 共同的流程。[`ResultsSet`] 把条目和写回时需要保留的内容（批注行、附加列、页脚、运行 ID、
+//! 小数位数）放在一起，各步骤都是接收并返回 `self` 的纯函数，可以链式组合：
+//!
+//! ```
+//! use lra_calculator_rust::utils::{ResultsSet, SortOrder};
+//!
+//! let set = ResultsSet::new(vec![("a.flac".into(), 4.0), ("b.flac".into(), 15.5), ("c.flac".into(), 9.0)]);
+//! let loudest = set.filter_lra(Some(5.0), None).sort(SortOrder::LraDescending).top_n(1);
+//! assert_eq!(loudest.entries(), [("b.flac".into(), 15.5)]);
+//! ```
+//!
+//! 写回时格式与 [`super::sort_results_file`] 等函数写出的完全相同。
+
+use std::collections::{BTreeMap, HashMap, HashSet};
+use std::io;
+use std::path::{Path, PathBuf};
+
+use crate::album::album_key;
+use crate::file_key::FileKey;
+use crate::precision::DEFAULT_LRA_PRECISION;
+use crate::run_id::RunId;
+
+use super::{
+    ffmpeg_metadata_line, merge_entries, run_metadata_line, sort_entries, write_results_file, ResultsFile, ResultsFileContents, SortOrder,
+    TextFormat, RESULTS_HEADER_LINE,
+};
+
+/// 合并策略 (Merge Policy)
+///
+/// 两个结果集中出现同一路径时保留哪一个值。
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum MergePolicy {
+    /// 以后合并进来的为准（默认）：新一次运行的结果覆盖旧结果（`merge` 子命令）
+    #[default]
+    PreferOther,
+    /// 保留已有的值，只加入新路径（`--update-baseline`）
+    KeepExisting,
+}
+
+/// 结果集 (Results Set)
+///
+/// 结果条目 (显示路径, LRA 值) 以及写回结果文件时需要保留的内容。
+/// 见模块文档。
+#[derive(Debug, Clone, PartialEq)]
+pub struct ResultsSet {
+    /// 数据条目，保持当前顺序
+    entries: Vec<(FileKey, f64)>,
+    /// 表头行（不含附加列名）
+    header_line: String,
+    /// 附加列名（以制表符开头）
+    extra_header_columns: Option<String>,
+    /// 各条目的附加列
+    extra_columns: HashMap<FileKey, String>,
+    /// 写回时放在表头之后的批注行
+    annotations: Vec<String>,
+    /// 数据之后的汇总页脚；筛选、截取和合并后不再描述这些条目，随之丢弃
+    footer: Vec<String>,
+    /// 读取时的 BOM 和换行格式
+    format: TextFormat,
+    /// 读取时 LRA 值的最大小数位数
+    precision: u8,
+    /// 写回时至少保留的小数位数（`--precision`）
+    write_precision: u8,
+    /// 当前条目的排序方式；未排序时为 `None`
+    order: Option<SortOrder>,
+    /// 写出这些结果的运行
+    run_id: Option<RunId>,
+    /// 写出这些结果时使用的 FFmpeg
+    ffmpeg_path: Option<PathBuf>,
+}
+
+impl ResultsSet {
+    /// 由条目创建 (New)
+    ///
+    /// 使用默认表头和当前平台的换行格式，没有批注和附加列。
+    pub fn new(entries: Vec<(FileKey, f64)>) -> Self {
+        Self::from_contents(ResultsFileContents { entries, ..ResultsFileContents::default() })
+    }
+
+    /// 由解析出的结果文件内容创建 (From Contents)
+    pub fn from_contents(contents: ResultsFileContents) -> Self {
+        Self {
+            entries: contents.entries,
+            header_line: RESULTS_HEADER_LINE.to_string(),
+            extra_header_columns: contents.extra_header_columns,
+            extra_columns: contents.extra_columns,
+            annotations: contents.annotations,
+            footer: contents.footer,
+            format: contents.format,
+            precision: contents.precision,
+            write_precision: DEFAULT_LRA_PRECISION,
+            order: contents.sort_order,
+            run_id: contents.run_id,
+            ffmpeg_path: contents.ffmpeg_path,
+        }
+    }
+
+    /// 读取结果文件 (Read)
+    ///
+    /// # 返回值
+    /// - `Err(io::Error)` - 读取失败，见 [`ResultsFile::read`]
+    pub fn read(path: &Path) -> io::Result<Self> {
+        Self::read_with_header(path, RESULTS_HEADER_LINE)
+    }
+
+    /// 读取使用自定义表头的结果文件；写回时使用同一个表头
+    pub fn read_with_header(path: &Path, header_line: &str) -> io::Result<Self> {
+        let contents = ResultsFile::read_with_header(path, header_line)?.contents;
+        Ok(Self::from_contents(contents).with_header_line(header_line))
+    }
+
+    /// 写回时使用的表头行（不含附加列名）
+    pub fn with_header_line(mut self, header_line: &str) -> Self {
+        self.header_line = header_line.to_string();
+        self
+    }
+
+    /// 写回时至少保留的小数位数；读取时的位数更多时保留原有位数，默认为 [`DEFAULT_LRA_PRECISION`]
+    pub fn with_precision(mut self, precision: u8) -> Self {
+        self.write_precision = precision;
+        self
+    }
+
+    /// 写回时记录的运行 ID；`None` 时不写运行 ID 元数据行
+    pub fn with_run_id(mut self, run_id: Option<RunId>) -> Self {
+        self.run_id = run_id;
+        self
+    }
+
+    /// 写回时记录的 FFmpeg 位置；`None` 时不写 FFmpeg 位置元数据行
+    pub fn with_ffmpeg_path(mut self, ffmpeg_path: Option<PathBuf>) -> Self {
+        self.ffmpeg_path = ffmpeg_path;
+        self
+    }
+
+    /// 条目，保持当前顺序
+    pub fn entries(&self) -> &[(FileKey, f64)] {
+        &self.entries
+    }
+
+    /// 取出条目
+    pub fn into_entries(self) -> Vec<(FileKey, f64)> {
+        self.entries
+    }
+
+    /// 条目数量
+    pub fn len(&self) -> usize {
+        self.entries.len()
+    }
+
+    /// 是否没有条目
+    pub fn is_empty(&self) -> bool {
+        self.entries.is_empty()
+    }
+
+    /// 读取时的 BOM 和换行格式，原样写回时使用
+    pub fn format(&self) -> TextFormat {
+        self.format
+    }
+
+    /// 当前条目的排序方式；未排序时为 `None`
+    pub fn order(&self) -> Option<SortOrder> {
+        self.order
+    }
+
+    /// 排序 (Sort)
+    ///
+    /// 与 [`sort_entries`] 相同；写回时不是默认排序的会记录排序元数据行。
+    ///
+    /// # 示例
+    /// ```
+    /// use lra_calculator_rust::utils::{ResultsSet, SortOrder};
+    ///
+    /// let set = ResultsSet::new(vec![("a.flac".into(), 9.5), ("b.flac".into(), 4.0)]);
+    /// let sorted = set.sort(SortOrder::DeviationFrom(9.0));
+    /// assert_eq!(sorted.entries()[0].0, "b.flac");
+    /// ```
+    pub fn sort(mut self, order: SortOrder) -> Self {
+        self.entries = sort_entries(self.entries, order);
+        self.order = Some(order);
+        self
+    }
+
+    /// 按记录的排序方式排序：读取时文件中元数据行记录的方式，没有记录时按 LRA 值从高到低
+    pub fn sort_in_recorded_order(self) -> Self {
+        let order = self.order.unwrap_or_default();
+        self.sort(order)
+    }
+
+    /// 按 LRA 值筛选 (Filter by LRA)
+    ///
+    /// 保留 LRA 值在 `[min, max]` 之间的条目（两端都包含），`None` 表示该端不限。
+    pub fn filter_lra(mut self, min: Option<f64>, max: Option<f64>) -> Self {
+        self.entries.retain(|(_, lra)| min.is_none_or(|min| *lra >= min) && max.is_none_or(|max| *lra <= max));
+        self.without_footer()
+    }
+
+    /// 截取前 N 个条目 (Top N)
+    ///
+    /// 按当前顺序截取，通常先 [`sort`](Self::sort)。
+    pub fn top_n(mut self, n: usize) -> Self {
+        self.entries.truncate(n);
+        self.without_footer()
+    }
+
+    /// 按目录分组 (Group by Directory)
+    ///
+    /// 目录与 [`crate::album::album_key`] 相同（扫描根目录下的文件为空字符串），
+    /// 每组保持当前顺序以及批注等写回时需要的内容。
+    ///
+    /// # 示例
+    /// ```
+    /// use lra_calculator_rust::utils::ResultsSet;
+    ///
+    /// let set = ResultsSet::new(vec![("A/1.flac".into(), 5.0), ("top.flac".into(), 6.0), ("A/2.flac".into(), 7.0)]);
+    /// let groups = set.group_by_dir();
+    /// let directories: Vec<&str> = groups.keys().map(String::as_str).collect();
+    /// assert_eq!(directories, ["", "A"]);
+    /// assert_eq!(groups["A"].len(), 2);
+    /// ```
+    pub fn group_by_dir(&self) -> BTreeMap<String, ResultsSet> {
+        let mut groups: BTreeMap<String, Vec<(FileKey, f64)>> = BTreeMap::new();
+        for (path, lra) in &self.entries {
+            groups.entry(album_key(path).to_string()).or_default().push((path.clone(), *lra));
+        }
+        groups
+            .into_iter()
+            .map(|(directory, entries)| {
+                let extra_columns = entries
+                    .iter()
+                    .filter_map(|(path, _)| Some((path.clone(), self.extra_columns.get(path)?.clone())))
+                    .collect();
+                let group = Self { entries, extra_columns, footer: Vec::new(), ..self.clone_metadata() };
+                (directory, group)
+            })
+            .collect()
+    }
+
+    /// 合并另一个结果集 (Merge)
+    ///
+    /// 同一路径的值按 `policy` 取舍，条目保持首次出现的位置，合并后未排序。
+    /// 批注行按顺序合并、去掉重复；附加列名以后合并进来的为准；小数位数取两者中较多的；
+    /// 排序方式以已有的为准；运行 ID 只在另一个结果集没有或与之相同时保留。页脚不再保留。
+    ///
+    /// # 示例
+    /// ```
+    /// use lra_calculator_rust::utils::{MergePolicy, ResultsSet};
+    ///
+    /// let old = ResultsSet::new(vec![("a.flac".into(), 5.0), ("b.flac".into(), 6.0)]);
+    /// let fresh = ResultsSet::new(vec![("a.flac".into(), 9.0), ("c.flac".into(), 1.0)]);
+    /// let merged = old.clone().merge(fresh.clone(), MergePolicy::PreferOther);
+    /// assert_eq!(merged.entries(), [("a.flac".into(), 9.0), ("b.flac".into(), 6.0), ("c.flac".into(), 1.0)]);
+    /// let kept = old.merge(fresh, MergePolicy::KeepExisting);
+    /// assert_eq!(kept.entries()[0], ("a.flac".into(), 5.0));
+    /// ```
+    pub fn merge(mut self, other: ResultsSet, policy: MergePolicy) -> Self {
+        match policy {
+            MergePolicy::PreferOther => {
+                self.entries = merge_entries(self.entries.into_iter().chain(other.entries));
+                self.extra_columns.extend(other.extra_columns);
+            }
+            MergePolicy::KeepExisting => {
+                let mut known: HashSet<FileKey> = self.entries.iter().map(|(path, _)| path.clone()).collect();
+                for (path, lra) in other.entries {
+                    if known.insert(path.clone()) {
+                        if let Some(extra) = other.extra_columns.get(&path) {
+                            self.extra_columns.insert(path.clone(), extra.clone());
+                        }
+                        self.entries.push((path, lra));
+                    }
+                }
+            }
+        }
+        for annotation in other.annotations {
+            if !self.annotations.contains(&annotation) {
+                self.annotations.push(annotation);
+            }
+        }
+        if other.extra_header_columns.is_some() {
+            self.extra_header_columns = other.extra_header_columns;
+        }
+        self.precision = self.precision.max(other.precision);
+        self.order = self.order.or(other.order);
+        if other.run_id.is_some() && other.run_id != self.run_id {
+            self.run_id = None;
+        }
+        if other.ffmpeg_path.is_some() && other.ffmpeg_path != self.ffmpeg_path {
+            self.ffmpeg_path = None;
+        }
+        self.without_footer()
+    }
+
+    /// 写入结果文件 (Write)
+    ///
+    /// 原子地写入 `path`（先写临时文件，校验通过后替换），写入后重新读取校验（见 [`super::verify_results_file`]）。
+    ///
+    /// # 参数
+    /// - `path` - 输出文件
+    /// - `format` - BOM 和换行格式；原样写回时使用 [`format`](Self::format)
+    ///
+    /// # 返回值
+    /// - `Err(io::Error)` - 写入或校验失败；失败时目标文件保持原样
+    pub fn write(&self, path: &Path, format: TextFormat) -> io::Result<()> {
+        self.write_with_verification(path, format, true)
+    }
+
+    /// 写入结果文件，可选择跳过写入后的校验（对应 `--no-verify`）
+    pub fn write_with_verification(&self, path: &Path, format: TextFormat, verify: bool) -> io::Result<()> {
+        let header_line = format!("{}{}", self.header_line, self.extra_header_columns.as_deref().unwrap_or(""));
+        // 运行 ID、FFmpeg 位置和排序方式写在批注之前
+        let annotations: Vec<String> = run_metadata_line(self.run_id.as_ref())
+            .into_iter()
+            .chain(ffmpeg_metadata_line(self.ffmpeg_path.as_deref()))
+            .chain(self.order.and_then(SortOrder::metadata_line))
+            .chain(self.annotations.iter().cloned())
+            .collect();
+        write_results_file(
+            path,
+            format,
+            &header_line,
+            &annotations,
+            &self.entries,
+            &self.extra_columns,
+            &self.footer,
+            self.precision.max(self.write_precision),
+            verify,
+        )
+    }
+
+    /// 丢弃页脚：条目变化后页脚不再描述这些数据
+    fn without_footer(mut self) -> Self {
+        self.footer.clear();
+        self
+    }
+
+    /// 复制条目以外的内容
+    fn clone_metadata(&self) -> Self {
+        Self {
+            entries: Vec::new(),
+            header_line: self.header_line.clone(),
+            extra_header_columns: self.extra_header_columns.clone(),
+            extra_columns: HashMap::new(),
+            annotations: self.annotations.clone(),
+            footer: self.footer.clone(),
+            format: self.format,
+            precision: self.precision,
+            write_precision: self.write_precision,
+            order: self.order,
+            run_id: self.run_id.clone(),
+            ffmpeg_path: self.ffmpeg_path.clone(),
+        }
+    }
+}
+
+impl Default for ResultsSet {
+    fn default() -> Self {
+        Self::new(Vec::new())
+    }
+}
+
+impl From<Vec<(FileKey, f64)>> for ResultsSet {
+    fn from(entries: Vec<(FileKey, f64)>) -> Self {
+        Self::new(entries)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::clock::FixedClock;
+    use tempfile::TempDir;
+
+    fn set(entries: &[(&str, f64)]) -> ResultsSet {
+        ResultsSet::new(entries.iter().map(|(path, lra)| (FileKey::from(*path), *lra)).collect())
+    }
+
+    fn paths(set: &ResultsSet) -> Vec<&str> {
+        set.entries().iter().map(|(path, _)| path.as_str()).collect()
+    }
+
+    /// 测试排序及排序方式的记录
+    #[test]
+    fn test_sort() {
+        let sorted = set(&[("b.flac", 5.0), ("a.flac", 5.0), ("c.flac", 12.0)]).sort(SortOrder::LraDescending);
+        assert_eq!(paths(&sorted), ["c.flac", "a.flac", "b.flac"]);
+        assert_eq!(sorted.order(), Some(SortOrder::LraDescending));
+
+        let by_deviation = sorted.sort(SortOrder::DeviationFrom(11.0));
+        assert_eq!(paths(&by_deviation), ["a.flac", "b.flac", "c.flac"]);
+        assert_eq!(by_deviation.order(), Some(SortOrder::DeviationFrom(11.0)));
+    }
+
+    /// 测试按 LRA 值筛选：两端都包含，`None` 不限
+    #[test]
+    fn test_filter_lra() {
+        let all = set(&[("a.flac", 4.0), ("b.flac", 8.0), ("c.flac", 12.0)]);
+        assert_eq!(paths(&all.clone().filter_lra(Some(4.0), Some(8.0))), ["a.flac", "b.flac"]);
+        assert_eq!(paths(&all.clone().filter_lra(Some(8.5), None)), ["c.flac"]);
+        assert_eq!(paths(&all.clone().filter_lra(None, Some(3.0))), Vec::<&str>::new());
+        assert_eq!(all.clone().filter_lra(None, None), all);
+    }
+
+    /// 测试截取前 N 个条目
+    #[test]
+    fn test_top_n() {
+        let sorted = set(&[("a.flac", 4.0), ("b.flac", 8.0), ("c.flac", 12.0)]).sort(SortOrder::LraDescending);
+        assert_eq!(paths(&sorted.clone().top_n(2)), ["c.flac", "b.flac"]);
+        assert_eq!(sorted.clone().top_n(10).len(), 3);
+        assert!(sorted.top_n(0).is_empty());
+    }
+
+    /// 测试按目录分组：每组保持原顺序和附加列
+    #[test]
+    fn test_group_by_dir() {
+        let mut all = set(&[("A/2.flac", 7.0), ("B/1.flac", 3.0), ("A/1.flac", 5.0), ("top.flac", 6.0)]);
+        all.extra_columns.insert("A/1.flac".into(), "\tx".to_string());
+        all.footer.push("# 汇总".to_string());
+
+        let groups = all.group_by_dir();
+        assert_eq!(groups.keys().map(String::as_str).collect::<Vec<_>>(), ["", "A", "B"]);
+        assert_eq!(paths(&groups["A"]), ["A/2.flac", "A/1.flac"]);
+        assert_eq!(groups["A"].extra_columns.len(), 1);
+        assert!(groups["B"].extra_columns.is_empty());
+        assert!(groups.values().all(|group| group.footer.is_empty()));
+    }
+
+    /// 测试合并：两种策略、批注去重、小数位数和运行 ID 的取舍
+    #[test]
+    fn test_merge() {
+        let run = RunId::generate_with(&FixedClock::from_unix_secs(1_717_255_812));
+        let other_run = RunId::generate_with(&FixedClock::from_unix_secs(1_717_255_999));
+        let mut old = set(&[("a.flac", 5.0), ("b.flac", 6.0)]).with_run_id(Some(run.clone()));
+        old.annotations.push("# 批注".to_string());
+        old.footer.push("# 汇总".to_string());
+        let mut fresh = set(&[("a.flac", 9.25), ("c.flac", 1.0)]).with_run_id(Some(run.clone()));
+        fresh.annotations = vec!["# 批注".to_string(), "# 新批注".to_string()];
+        fresh.precision = 2;
+
+        let merged = old.clone().merge(fresh.clone(), MergePolicy::PreferOther);
+        assert_eq!(merged.entries(), set(&[("a.flac", 9.25), ("b.flac", 6.0), ("c.flac", 1.0)]).entries());
+        assert_eq!(merged.annotations, ["# 批注", "# 新批注"]);
+        assert_eq!(merged.precision, 2);
+        assert_eq!(merged.run_id, Some(run.clone()));
+        assert!(merged.footer.is_empty());
+
+        let kept = old.clone().merge(fresh.clone(), MergePolicy::KeepExisting);
+        assert_eq!(kept.entries(), set(&[("a.flac", 5.0), ("b.flac", 6.0), ("c.flac", 1.0)]).entries());
+
+        let mixed = old.merge(fresh.with_run_id(Some(other_run)), MergePolicy::PreferOther);
+        assert_eq!(mixed.run_id, None);
+    }
+
+    /// 测试写入后再读取得到相同的结果集
+    #[test]
+    fn test_write_round_trip() {
+        let temp_dir = TempDir::new().expect("无法创建临时目录");
+        let path = temp_dir.path().join("results.txt");
+        let run = RunId::generate_with(&FixedClock::from_unix_secs(1_717_255_812));
+        let original = set(&[("a.flac", 4.5), ("b.flac", 12.0), ("c.flac", 8.0)])
+            .with_run_id(Some(run.clone()))
+            .with_ffmpeg_path(Some(PathBuf::from("/usr/bin/ffmpeg")))
+            .sort(SortOrder::DeviationFrom(9.0));
+        original.write(&path, TextFormat::default()).expect("写入失败");
+
+        let read = ResultsSet::read(&path).expect("读取失败");
+        assert_eq!(read.entries(), original.entries());
+        assert_eq!(read.order(), Some(SortOrder::DeviationFrom(9.0)));
+        assert_eq!(read.run_id, Some(run));
+        assert_eq!(read.ffmpeg_path, Some(PathBuf::from("/usr/bin/ffmpeg")));
+        assert!(read.annotations.is_empty(), "元数据行不应作为批注重复写入: {:?}", read.annotations);
+    }
+
+    /// 测试写回的小数位数取读取时的位数和 with_precision 中较大的一个
+    #[test]
+    fn test_write_precision() {
+        let temp_dir = TempDir::new().expect("无法创建临时目录");
+        let path = temp_dir.path().join("results.txt");
+        let plain = TextFormat { bom: false, crlf: false };
+        let data_lines = |path: &Path| -> Vec<String> {
+            std::fs::read_to_string(path).unwrap().lines().skip(2).map(str::to_string).collect()
+        };
+
+        set(&[("a.flac", 7.26)]).write(&path, plain).unwrap();
+        assert_eq!(data_lines(&path), ["a.flac - 7.3"]);
+        set(&[("a.flac", 7.26)]).with_precision(0).write(&path, plain).unwrap();
+        assert_eq!(data_lines(&path), ["a.flac - 7"]);
+
+        let mut precise = set(&[("a.flac", 7.26)]);
+        precise.precision = 2;
+        precise.clone().write(&path, plain).unwrap();
+        assert_eq!(data_lines(&path), ["a.flac - 7.26"]);
+        precise.with_precision(3).write(&path, plain).unwrap();
+        assert_eq!(data_lines(&path), ["a.flac - 7.260"]);
+    }
+}
@@ -0,0 +1,437 @@
+//! 流式结果写入 (Streaming Results Writer)
+//!
+//! 结果文件在处理结束后才一次写出，分析几十万个文件时中途崩溃或被杀掉会丢失全部结果。
+//! `--stream-results` 在处理期间把每个完成的文件立即追加到
+//! [`partial_path_for`] 给出的部分结果文件，格式与结果文件相同（未排序）。
+//!
+//! ## 刷新策略
+//!
+//! 每一行都刷新会让大量小文件的运行变慢，完全不刷新则进程被杀时缓冲区中的内容全部丢失。
+//! [`StreamingConfig`] 在两者之间折中：每 [`StreamingConfig::flush_every`] 个条目或每隔
+//! [`StreamingConfig::flush_interval`]（以先到者为准）刷新一次，进程被杀时最多丢失最近
+//! `flush_every` 个条目。写入器被丢弃时（包括时间预算到期后正常返回和 panic 展开）
+//! 总会执行最后一次刷新；丢弃时的刷新错误无处报告，需要知道结果的调用方在结束时先调用
+//! [`StreamingResultsWriter::flush`]。
+//!
+//! 刷新只把缓冲区交给操作系统，不调用 `fsync`：目标是进程异常退出时不丢结果，
+//! 而不是在断电时保证落盘。
+//!
+//! ## 保持排序（`--stream-sorted`）
+//!
+//! 追加写入的部分结果文件是按完成顺序排列的，运行结束后还要对完整的结果文件再排序一遍，
+//! 几十万行时这一步本身就很慢。[`StreamingConfig::sorted`] 时完成的结果保存在按
+//! (LRA 降序, 路径升序) 排列的 `BTreeSet` 中（每个条目只是共享的路径和一个 `f64`），
+//! 部分结果文件定期由它整体重写（先写临时文件再重命名），磁盘上的文件始终是排好序的完整文件。
+//!
+//! 每次重写的代价与条目数成正比，因此重写频率随文件增大而退避：新增条目达到
+//! `max(flush_every, 已有条目数 / 4)` 时重写，总的写入量是条目数的常数倍；按时间触发的间隔
+//! 每多 [`SORTED_INTERVAL_STEP`] 个条目增加一个 `flush_interval`。进程被杀时最多丢失最近
+//! 一批（约四分之一）条目。
+
+use std::collections::BTreeSet;
+use std::cmp::Ordering;
+use std::fs::File;
+use std::io::{self, BufWriter, Write};
+use std::path::{Path, PathBuf};
+use std::sync::{Mutex, MutexGuard};
+use std::time::{Duration, Instant};
+
+use super::{write_results_header, AtomicFile, TextFormat};
+use crate::file_key::FileKey;
+use crate::precision::{LraValue, DEFAULT_LRA_PRECISION};
+
+/// 默认每写入多少个条目刷新一次
+pub const DEFAULT_FLUSH_EVERY: usize = 50;
+
+/// 默认的最长刷新间隔
+pub const DEFAULT_FLUSH_INTERVAL: Duration = Duration::from_secs(10);
+
+/// 保持排序时，新增条目达到已有条目数的 1/4 才重写
+const SORTED_BATCH_DIVISOR: usize = 4;
+
+/// 保持排序时，每多这么多条目，按时间触发的重写间隔增加一个 `flush_interval`
+pub const SORTED_INTERVAL_STEP: usize = 100_000;
+
+/// 获取结果文件对应的部分结果文件路径
+///
+/// 例如 `lra_results.txt` 对应 `lra_results_partial.txt`。
+///
+/// # 示例
+/// ```
+/// use std::path::Path;
+/// use lra_calculator_rust::utils::stream::partial_path_for;
+///
+/// let path = partial_path_for(Path::new("/music/lra_results.txt"));
+/// assert_eq!(path, Path::new("/music/lra_results_partial.txt"));
+/// ```
+pub fn partial_path_for(results_file_path: &Path) -> PathBuf {
+    let stem = results_file_path
+        .file_stem()
+        .map(|stem| stem.to_string_lossy().into_owned())
+        .unwrap_or_else(|| "lra_results".to_string());
+    results_file_path.with_file_name(format!("{}_partial.txt", stem))
+}
+
+/// 流式写入的刷新配置 (Streaming Config)
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct StreamingConfig {
+    /// 每写入多少个条目刷新一次（`--flush-every`），至少为 1
+    pub flush_every: usize,
+    /// 距离上次刷新超过该时间后，下一个条目写入时刷新（`--flush-interval`）
+    pub flush_interval: Duration,
+    /// 文件的 BOM 和换行符格式
+    pub format: TextFormat,
+    /// 在内存中保持排序并定期重写整个文件（`--stream-sorted`），而不是追加
+    pub sorted: bool,
+    /// LRA 值保留的小数位数（`--precision`）
+    pub precision: u8,
+}
+
+impl Default for StreamingConfig {
+    fn default() -> Self {
+        Self {
+            flush_every: DEFAULT_FLUSH_EVERY,
+            flush_interval: DEFAULT_FLUSH_INTERVAL,
+            format: TextFormat::default(),
+            sorted: false,
+            precision: DEFAULT_LRA_PRECISION,
+        }
+    }
+}
+
+/// 按结果文件的排列顺序比较的条目：与 [`super::sort_entries_by_lra`] 相同，LRA 降序，路径升序
+#[derive(Debug, Clone)]
+struct SortedEntry(FileKey, f64);
+
+impl Ord for SortedEntry {
+    fn cmp(&self, other: &Self) -> Ordering {
+        other.1.total_cmp(&self.1).then_with(|| self.0.cmp(&other.0))
+    }
+}
+
+impl PartialOrd for SortedEntry {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+impl PartialEq for SortedEntry {
+    fn eq(&self, other: &Self) -> bool {
+        self.cmp(other) == Ordering::Equal
+    }
+}
+
+impl Eq for SortedEntry {}
+
+/// 条目的存放方式
+#[derive(Debug)]
+enum Sink {
+    /// 追加到打开的文件
+    Append(BufWriter<File>),
+    /// 保存在内存中，刷新时整体重写文件
+    Sorted {
+        entries: BTreeSet<SortedEntry>,
+        path: PathBuf,
+        header_line: String,
+        /// 重写次数（用于测试退避）
+        rewrites: usize,
+    },
+}
+
+/// 写入器的可变状态
+#[derive(Debug)]
+struct StreamState {
+    sink: Sink,
+    format: TextFormat,
+    /// LRA 值保留的小数位数
+    precision: u8,
+    /// 上次刷新之后写入的条目数量
+    pending: usize,
+    /// 上次刷新的时间
+    last_flush: Instant,
+    /// 已写入的条目总数
+    written: usize,
+}
+
+impl StreamState {
+    /// 写入一个条目（保持排序时只放入内存）
+    fn push(&mut self, path: &FileKey, lra: f64) -> io::Result<()> {
+        match &mut self.sink {
+            Sink::Append(writer) => {
+                let lra = LraValue(lra).with_precision(self.precision);
+                self.format.write_line(writer, format_args!("{} - {}", path, lra))?
+            }
+            Sink::Sorted { entries, .. } => {
+                entries.insert(SortedEntry(path.clone(), lra));
+            }
+        }
+        self.pending += 1;
+        self.written += 1;
+        Ok(())
+    }
+
+    /// 按配置判断是否应该刷新
+    fn flush_due(&self, config: &StreamingConfig) -> bool {
+        match &self.sink {
+            Sink::Append(_) => {
+                self.pending >= config.flush_every.max(1) || self.last_flush.elapsed() >= config.flush_interval
+            }
+            Sink::Sorted { entries, .. } => {
+                let batch = config.flush_every.max(1).max(entries.len() / SORTED_BATCH_DIVISOR);
+                let steps = 1 + entries.len() / SORTED_INTERVAL_STEP;
+                self.pending >= batch || self.last_flush.elapsed() >= config.flush_interval * steps as u32
+            }
+        }
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        match &mut self.sink {
+            Sink::Append(writer) => writer.flush()?,
+            Sink::Sorted { .. } if self.pending == 0 => {}
+            Sink::Sorted { entries, path, header_line, rewrites } => {
+                let mut file = AtomicFile::create(path, self.format)?;
+                let writer = file.writer();
+                write_results_header(writer, self.format, header_line, None, None)?;
+                for SortedEntry(path, lra) in entries.iter() {
+                    let lra = LraValue(*lra).with_precision(self.precision);
+                    self.format.write_line(writer, format_args!("{} - {}", path, lra))?;
+                }
+                file.commit()?;
+                *rewrites += 1;
+            }
+        }
+        self.pending = 0;
+        self.last_flush = Instant::now();
+        Ok(())
+    }
+}
+
+/// 流式结果写入器 (Streaming Results Writer)
+///
+/// 可以在多个工作线程中共享（`&self` 写入），内部用 `Mutex<BufWriter>` 串行化；
+/// [`StreamingConfig::sorted`] 时改为在内存中保持排序并定期重写（见模块文档）。
+/// 丢弃时执行最后一次刷新。
+///
+/// # 示例
+/// ```
+/// use lra_calculator_rust::utils::stream::{StreamingConfig, StreamingResultsWriter};
+///
+/// let path = std::env::temp_dir().join("lra_stream_doc_partial.txt");
+/// let writer = StreamingResultsWriter::create(&path, "表头", StreamingConfig::default()).unwrap();
+/// writer.append(&"a.flac".into(), 7.3).unwrap();
+/// drop(writer);
+/// assert!(std::fs::read_to_string(&path).unwrap().ends_with("a.flac - 7.3\n"));
+/// # std::fs::remove_file(&path).unwrap();
+/// ```
+#[derive(Debug)]
+pub struct StreamingResultsWriter {
+    state: Mutex<StreamState>,
+    config: StreamingConfig,
+}
+
+impl StreamingResultsWriter {
+    /// 创建（或覆盖）部分结果文件并写入表头和格式标记 (Create)
+    ///
+    /// # 参数
+    /// - `path` - 部分结果文件路径，通常由 [`partial_path_for`] 得到
+    /// - `header_line` - 表头行
+    /// - `config` - 刷新配置
+    ///
+    /// # 返回值
+    /// - `Ok(Self)` - 表头已经刷新到文件
+    /// - `Err(io::Error)` - 无法创建或写入文件
+    pub fn create(path: &Path, header_line: &str, config: StreamingConfig) -> io::Result<Self> {
+        let mut writer = config.format.create_file(path)?;
+        write_results_header(&mut writer, config.format, header_line, None, None)?;
+        writer.flush()?;
+        let sink = if config.sorted {
+            drop(writer);
+            Sink::Sorted {
+                entries: BTreeSet::new(),
+                path: path.to_path_buf(),
+                header_line: header_line.to_string(),
+                rewrites: 0,
+            }
+        } else {
+            Sink::Append(writer)
+        };
+        let state = StreamState {
+            sink,
+            format: config.format,
+            precision: config.precision,
+            pending: 0,
+            last_flush: Instant::now(),
+            written: 0,
+        };
+        Ok(Self { state: Mutex::new(state), config })
+    }
+
+    /// 追加一个结果条目 (Append)
+    ///
+    /// 达到 [`StreamingConfig::flush_every`] 个条目或超过 [`StreamingConfig::flush_interval`]
+    /// 时刷新。
+    ///
+    /// # 参数
+    /// - `path` - 显示路径
+    /// - `lra` - LRA 值
+    pub fn append(&self, path: &FileKey, lra: f64) -> io::Result<()> {
+        let mut state = self.lock();
+        state.push(path, lra)?;
+        if state.flush_due(&self.config) {
+            state.flush()?;
+        }
+        Ok(())
+    }
+
+    /// 立即刷新缓冲区 (Flush)
+    pub fn flush(&self) -> io::Result<()> {
+        self.lock().flush()
+    }
+
+    /// 已写入的条目数量
+    pub fn written(&self) -> usize {
+        self.lock().written
+    }
+
+    /// 获取状态锁；其他线程写入时 panic 不影响继续写入和最后的刷新
+    fn lock(&self) -> MutexGuard<'_, StreamState> {
+        self.state.lock().unwrap_or_else(|poisoned| poisoned.into_inner())
+    }
+}
+
+impl Drop for StreamingResultsWriter {
+    /// 最后一次刷新；与 `BufWriter` 一样忽略错误
+    fn drop(&mut self) {
+        let state = self.state.get_mut().unwrap_or_else(|poisoned| poisoned.into_inner());
+        let _ = state.flush();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::sync::Arc;
+    use std::thread;
+    use tempfile::TempDir;
+
+    /// 部分结果文件中的条目数量（不含表头和格式标记）
+    fn entry_count(path: &Path) -> usize {
+        std::fs::read_to_string(path).unwrap().lines().skip(2).count()
+    }
+
+    fn config(flush_every: usize, flush_interval: Duration) -> StreamingConfig {
+        StreamingConfig {
+            flush_every,
+            flush_interval,
+            format: TextFormat { bom: false, crlf: false },
+            sorted: false,
+            precision: DEFAULT_LRA_PRECISION,
+        }
+    }
+
+    /// 测试进程中途被杀（写入器没有被丢弃）时最多丢失 flush_every 个条目
+    #[test]
+    fn test_killed_run_loses_at_most_flush_every_entries() {
+        let temp_dir = TempDir::new().expect("无法创建临时目录");
+        for (written, flush_every) in [(0, 10), (9, 10), (10, 10), (137, 10), (137, 1)] {
+            let path = temp_dir.path().join(format!("{}_{}_partial.txt", written, flush_every));
+            let writer = StreamingResultsWriter::create(&path, "表头", config(flush_every, Duration::from_secs(3600))).unwrap();
+            for i in 0..written {
+                writer.append(&format!("dir/{}.flac", i).into(), 7.3).unwrap();
+            }
+            // 模拟被杀：不运行 Drop，缓冲区中的内容丢失
+            std::mem::forget(writer);
+
+            let on_disk = entry_count(&path);
+            assert!(on_disk <= written);
+            assert!(written - on_disk < flush_every, "写入 {} 个、每 {} 个刷新，磁盘上只有 {} 个", written, flush_every, on_disk);
+        }
+    }
+
+    /// 测试超过刷新间隔后下一个条目写入时刷新
+    #[test]
+    fn test_flush_interval() {
+        let temp_dir = TempDir::new().expect("无法创建临时目录");
+        let path = temp_dir.path().join("lra_results_partial.txt");
+        let writer = StreamingResultsWriter::create(&path, "表头", config(1000, Duration::ZERO)).unwrap();
+        writer.append(&"a.flac".into(), 7.3).unwrap();
+        writer.append(&"b.flac".into(), 8.1).unwrap();
+        assert_eq!(entry_count(&path), 2);
+        assert_eq!(writer.written(), 2);
+        std::mem::forget(writer);
+    }
+
+    /// 测试工作线程 panic 后丢弃写入器时仍然刷新全部条目
+    #[test]
+    fn test_final_flush_after_panic() {
+        let temp_dir = TempDir::new().expect("无法创建临时目录");
+        let path = temp_dir.path().join("lra_results_partial.txt");
+        let writer = Arc::new(
+            StreamingResultsWriter::create(&path, "表头", config(1000, Duration::from_secs(3600))).unwrap(),
+        );
+
+        let worker = Arc::clone(&writer);
+        let result = thread::spawn(move || {
+            worker.append(&"a.flac".into(), 7.3).unwrap();
+            // 持有锁时 panic，使互斥锁中毒
+            let _state = worker.lock();
+            panic!("模拟工作线程崩溃");
+        })
+        .join();
+        assert!(result.is_err());
+
+        writer.append(&"b.flac".into(), 8.1).unwrap();
+        assert_eq!(entry_count(&path), 0, "未达到刷新条件时条目仍在缓冲区");
+        drop(writer);
+        assert_eq!(std::fs::read_to_string(&path).unwrap(), "表头\n# format: 2\na.flac - 7.3\nb.flac - 8.1\n");
+    }
+
+    /// 测试保持排序时磁盘上的文件始终有序，重写频率随条目数退避
+    #[test]
+    fn test_sorted_stream() {
+        let temp_dir = TempDir::new().expect("无法创建临时目录");
+        let path = temp_dir.path().join("lra_results_partial.txt");
+        let sorted = StreamingConfig { sorted: true, ..config(1, Duration::from_secs(3600)) };
+        let writer = StreamingResultsWriter::create(&path, "表头", sorted).unwrap();
+        assert_eq!(std::fs::read_to_string(&path).unwrap(), "表头\n# format: 2\n");
+
+        let total = 2000;
+        for i in 0..total {
+            writer.append(&format!("{:04}.flac", i).into(), (i * 7 % 25) as f64).unwrap();
+            if i % 97 == 0 {
+                let contents = std::fs::read_to_string(&path).unwrap();
+                let lines: Vec<&str> = contents.lines().skip(2).collect();
+                let lras: Vec<f64> = lines.iter().map(|line| line.rsplit(" - ").next().unwrap().parse().unwrap()).collect();
+                assert!(lras.windows(2).all(|pair| pair[0] >= pair[1]), "磁盘上的文件应始终按 LRA 降序排列");
+                // 最多丢失最近一批（约四分之一）条目
+                assert!(lines.len() * 4 + 4 >= (i + 1) * 3, "写入 {} 个，磁盘上只有 {} 个", i + 1, lines.len());
+            }
+        }
+        let rewrites = match &writer.lock().sink {
+            Sink::Sorted { rewrites, .. } => *rewrites,
+            Sink::Append(_) => unreachable!(),
+        };
+        assert!(rewrites < 40, "重写 {} 次，应随条目数退避", rewrites);
+
+        drop(writer);
+        let contents = std::fs::read_to_string(&path).unwrap();
+        let lines: Vec<&str> = contents.lines().collect();
+        assert_eq!(lines.len(), total + 2);
+        assert_eq!(&lines[2..4], ["0007.flac - 24.0", "0032.flac - 24.0"]);
+        assert!(!crate::utils::temp_path_for(&path).exists());
+    }
+
+    /// 测试追加和保持排序两种方式都按配置的精度写出 LRA 值
+    #[test]
+    fn test_stream_precision() {
+        let temp_dir = TempDir::new().expect("无法创建临时目录");
+        for sorted in [false, true] {
+            let path = temp_dir.path().join(format!("{}_partial.txt", sorted));
+            let config = StreamingConfig { sorted, precision: 2, ..config(1, Duration::from_secs(3600)) };
+            let writer = StreamingResultsWriter::create(&path, "表头", config).unwrap();
+            writer.append(&"a.flac".into(), 7.3).unwrap();
+            drop(writer);
+            assert_eq!(std::fs::read_to_string(&path).unwrap(), "表头\n# format: 2\na.flac - 7.30\n");
+        }
+    }
+}
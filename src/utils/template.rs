@@ -0,0 +1,218 @@
+//! 结果文件名模板 (Results File Name Template)
+//!
+//! `--output-name` 用模板为每次运行生成结果文件名，例如 `lra_results_{date}.txt`
+//! 或 `lra_{root}.{format}`。展开后的文件名放在扫描根目录下。
+//!
+//! ## 占位符
+//!
+//! - `{date}` - 运行日期，`2024-06-01`
+//! - `{time}` - 运行时间，`153012`（不含冒号，Windows 文件名中不能出现冒号）
+//! - `{root}` - 扫描根目录的名称
+//! - `{format}` - 结果文件格式的扩展名，目前总是 [`RESULTS_FORMAT_EXTENSION`]
+//!
+//! ## 路径安全
+//!
+//! 模板本身只能是文件名，不能包含路径分隔符；`{root}` 的值中的分隔符和控制字符替换为 `_`。
+//! 展开结果为空、`.` 或 `..` 时报错，因此展开后的文件名不会离开输出目录。
+
+use crate::error::AppError;
+
+/// 结果文件格式的扩展名（`{format}` 的值）
+pub const RESULTS_FORMAT_EXTENSION: &str = "txt";
+
+/// 文件名中不允许出现的字符：路径分隔符和 Windows 的驱动器、流分隔符
+const FORBIDDEN_CHARS: [char; 3] = ['/', '\\', ':'];
+
+/// 模板中的一段
+#[derive(Debug, Clone, PartialEq, Eq)]
+enum Segment {
+    /// 原样保留的文字
+    Literal(String),
+    /// `{date}`
+    Date,
+    /// `{time}`
+    Time,
+    /// `{root}`
+    Root,
+    /// `{format}`
+    Format,
+}
+
+/// 展开模板时使用的值 (File Name Values)
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct FileNameValues<'a> {
+    /// `{date}` 的值
+    pub date: &'a str,
+    /// `{time}` 的值
+    pub time: &'a str,
+    /// `{root}` 的值（未经处理的目录名）
+    pub root: &'a str,
+    /// `{format}` 的值
+    pub format: &'a str,
+}
+
+/// 结果文件名模板 (File Name Template)
+///
+/// # 示例
+/// ```
+/// use lra_calculator_rust::utils::template::{FileNameTemplate, FileNameValues};
+///
+/// let template = FileNameTemplate::parse("lra_{root}_{date}.{format}").unwrap();
+/// let values = FileNameValues { date: "2024-06-01", time: "153012", root: "Jazz", format: "txt" };
+/// assert_eq!(template.expand(&values).unwrap(), "lra_Jazz_2024-06-01.txt");
+///
+/// assert!(FileNameTemplate::parse("../lra_{date}.txt").is_err());
+/// assert!(FileNameTemplate::parse("lra_{year}.txt").is_err());
+/// ```
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct FileNameTemplate {
+    /// 原始模板文字
+    source: String,
+    segments: Vec<Segment>,
+}
+
+impl FileNameTemplate {
+    /// 解析模板 (Parse)
+    ///
+    /// # 返回值
+    /// - `Ok(FileNameTemplate)` - 解析成功
+    /// - `Err(AppError::Configuration)` - 模板为空、包含路径分隔符、括号不匹配或有未知的占位符
+    pub fn parse(template: &str) -> Result<Self, AppError> {
+        let invalid = |reason: String| AppError::Configuration(format!("结果文件名模板 '{}' 无效: {}", template, reason));
+        if template.trim().is_empty() {
+            return Err(invalid("模板为空".to_string()));
+        }
+        if let Some(c) = template.chars().find(|c| FORBIDDEN_CHARS.contains(c) || c.is_control()) {
+            return Err(invalid(format!("只能是文件名，不能包含 '{}'", c.escape_default())));
+        }
+
+        let mut segments = Vec::new();
+        let mut rest = template;
+        while !rest.is_empty() {
+            let next = rest.find(['{', '}']).unwrap_or(rest.len());
+            if next > 0 {
+                segments.push(Segment::Literal(rest[..next].to_string()));
+                rest = &rest[next..];
+                continue;
+            }
+            if rest.starts_with('}') {
+                return Err(invalid("多余的 '}'".to_string()));
+            }
+            let end = rest.find('}').ok_or_else(|| invalid("缺少 '}'".to_string()))?;
+            segments.push(match &rest[1..end] {
+                "date" => Segment::Date,
+                "time" => Segment::Time,
+                "root" => Segment::Root,
+                "format" => Segment::Format,
+                name => {
+                    return Err(invalid(format!(
+                        "未知的占位符 {{{}}}（可用: {{date}}、{{time}}、{{root}}、{{format}}）",
+                        name
+                    )))
+                }
+            });
+            rest = &rest[end + 1..];
+        }
+
+        let parsed = Self { source: template.to_string(), segments };
+        // 只有文字时在解析阶段就能发现 `..` 这类名称
+        if parsed.segments.iter().all(|segment| matches!(segment, Segment::Literal(_))) {
+            validate_file_name(template).map_err(invalid)?;
+        }
+        Ok(parsed)
+    }
+
+    /// 展开模板 (Expand)
+    ///
+    /// `values.root` 中的路径分隔符和控制字符替换为 `_`，其他值原样使用。
+    ///
+    /// # 返回值
+    /// - `Ok(String)` - 不含路径分隔符的文件名
+    /// - `Err(AppError::Configuration)` - 展开结果为空、`.`、`..` 或仍含有路径分隔符
+    pub fn expand(&self, values: &FileNameValues) -> Result<String, AppError> {
+        let root = sanitize_component(values.root);
+        let mut name = String::new();
+        for segment in &self.segments {
+            name.push_str(match segment {
+                Segment::Literal(text) => text,
+                Segment::Date => values.date,
+                Segment::Time => values.time,
+                Segment::Root => &root,
+                Segment::Format => values.format,
+            });
+        }
+        validate_file_name(&name).map_err(|reason| {
+            AppError::Configuration(format!("结果文件名模板 '{}' 展开为 '{}'，{}", self.source, name, reason))
+        })?;
+        Ok(name)
+    }
+}
+
+/// 把目录名中的路径分隔符和控制字符替换为 `_`
+fn sanitize_component(value: &str) -> String {
+    value.chars().map(|c| if FORBIDDEN_CHARS.contains(&c) || c.is_control() { '_' } else { c }).collect()
+}
+
+/// 检查展开后的名称是单个普通文件名
+fn validate_file_name(name: &str) -> Result<(), String> {
+    if name.trim().is_empty() {
+        return Err("文件名为空".to_string());
+    }
+    if matches!(name, "." | "..") {
+        return Err("不是普通的文件名".to_string());
+    }
+    if name.chars().any(|c| FORBIDDEN_CHARS.contains(&c) || c.is_control()) {
+        return Err("包含路径分隔符".to_string());
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const VALUES: FileNameValues<'static> =
+        FileNameValues { date: "2024-06-01", time: "153012", root: "Music", format: RESULTS_FORMAT_EXTENSION };
+
+    fn expand(template: &str, values: &FileNameValues) -> Result<String, AppError> {
+        FileNameTemplate::parse(template)?.expand(values)
+    }
+
+    /// 测试每个占位符的展开和重复出现的占位符
+    #[test]
+    fn test_expand_placeholders() {
+        assert_eq!(expand("lra_results_{date}.txt", &VALUES).unwrap(), "lra_results_2024-06-01.txt");
+        assert_eq!(expand("lra_{time}.txt", &VALUES).unwrap(), "lra_153012.txt");
+        assert_eq!(expand("lra_{root}.csv", &VALUES).unwrap(), "lra_Music.csv");
+        assert_eq!(expand("lra_results.{format}", &VALUES).unwrap(), "lra_results.txt");
+        assert_eq!(expand("{root}-{date}-{root}", &VALUES).unwrap(), "Music-2024-06-01-Music");
+        assert_eq!(expand("固定名称.txt", &VALUES).unwrap(), "固定名称.txt");
+    }
+
+    /// 测试模板语法错误在解析时报告
+    #[test]
+    fn test_parse_errors() {
+        for template in ["", "  ", "lra_{year}.txt", "lra_{date.txt", "lra_}.txt", "lra_{}.txt", "{{date}}"] {
+            assert!(FileNameTemplate::parse(template).is_err(), "{:?} 应该无效", template);
+        }
+    }
+
+    /// 测试模板和目录名都不能让文件名离开输出目录
+    #[test]
+    fn test_path_injection() {
+        for template in ["../lra.txt", "sub/lra.txt", r"..\lra.txt", "C:lra.txt", "/etc/passwd", "..", ".", "a\nb"] {
+            assert!(FileNameTemplate::parse(template).is_err(), "{:?} 应该无效", template);
+        }
+
+        // 目录名中的分隔符被替换，不会产生新的路径层级
+        let sneaky = FileNameValues { root: "../../etc", ..VALUES };
+        assert_eq!(expand("lra_{root}.txt", &sneaky).unwrap(), "lra_.._.._etc.txt");
+        let windows = FileNameValues { root: r"C:\Windows", ..VALUES };
+        assert_eq!(expand("{root}.txt", &windows).unwrap(), "C__Windows.txt");
+
+        // 只有占位符时展开结果也不能是 `..` 或空
+        assert!(expand("{root}", &FileNameValues { root: "..", ..VALUES }).is_err());
+        assert!(expand("{root}", &FileNameValues { root: "", ..VALUES }).is_err());
+        assert!(expand("{root}.", &FileNameValues { root: ".", ..VALUES }).is_err());
+    }
+}
@@ -0,0 +1,198 @@
+//! 同名音轨比较模块 (Track Versions Module)
+//!
+//! 同一张专辑常有多个母带版本（原版 CD 与重制版）。按规范化后的文件名把不同目录中的
+//! 同名音轨归为一组，比较各版本的 LRA：组内差异（最大值减最小值）大的通常是响度战争中
+//! 被压缩过的重制版。`compare-versions` 子命令对已有结果文件做这项比较，
+//! `--compare-versions` 在分析结束后对本次结果做同样的比较。
+//!
+//! 文件名的规范化规则见 [`NameNormalization`]。
+
+use std::collections::BTreeMap;
+use std::sync::LazyLock;
+
+use regex::Regex;
+
+use crate::album::is_album_entry;
+use crate::file_key::FileKey;
+
+/// 默认只报告 LRA 差异不小于该值（LU）的组
+pub const DEFAULT_MIN_SPREAD: f64 = 1.0;
+
+/// 文件名开头的音轨编号，如 `01 - `、`01. `、`1-01 `、`03_`
+static TRACK_NUMBER: LazyLock<Regex> =
+    LazyLock::new(|| Regex::new(r"^(?:\d{1,2}[-.])?\d{1,3}\s*(?:[-–._)]\s*|\s+)").expect("音轨编号正则表达式无效"));
+
+/// 文件名规范化规则 (Name Normalization)
+///
+/// 总是只取路径的最后一段并去掉扩展名，连续的空白合并为一个空格。
+/// 默认同时去掉开头的音轨编号并忽略大小写。
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct NameNormalization {
+    /// 去掉开头的音轨编号（`01 - Song` 与 `Song` 视为同名）
+    pub strip_track_numbers: bool,
+    /// 忽略大小写
+    pub case_insensitive: bool,
+}
+
+impl Default for NameNormalization {
+    fn default() -> Self {
+        Self { strip_track_numbers: true, case_insensitive: true }
+    }
+}
+
+impl NameNormalization {
+    /// 规范化显示路径中的文件名 (Normalize)
+    ///
+    /// # 示例
+    /// ```
+    /// use lra_calculator_rust::versions::NameNormalization;
+    ///
+    /// let rules = NameNormalization::default();
+    /// assert_eq!(rules.normalize("Remaster/01 - Time.FLAC"), "time");
+    /// assert_eq!(rules.normalize("CD/1-01 Time.flac"), "time");
+    ///
+    /// let keep_numbers = NameNormalization { strip_track_numbers: false, ..rules };
+    /// assert_eq!(keep_numbers.normalize("CD/01 - Time.flac"), "01 - time");
+    /// ```
+    pub fn normalize(&self, display_path: &str) -> String {
+        let file_name = display_path.rsplit(['/', '\\']).next().unwrap_or(display_path);
+        let stem = match file_name.rsplit_once('.') {
+            Some((stem, extension))
+                if !stem.is_empty() && !extension.is_empty() && extension.chars().all(|c| c.is_ascii_alphanumeric()) =>
+            {
+                stem
+            }
+            _ => file_name,
+        };
+        let mut name = stem.trim();
+        if self.strip_track_numbers {
+            // 只剩编号时保留原名（例如 `01.flac`）
+            if let Some(number) = TRACK_NUMBER.find(name).filter(|number| !name[number.end()..].trim().is_empty()) {
+                name = &name[number.end()..];
+            }
+        }
+        let name = name.split_whitespace().collect::<Vec<_>>().join(" ");
+        if self.case_insensitive {
+            name.to_lowercase()
+        } else {
+            name
+        }
+    }
+}
+
+/// 一组同名音轨 (Version Group)
+#[derive(Debug, Clone, PartialEq)]
+pub struct VersionGroup {
+    /// 规范化后的文件名
+    pub name: String,
+    /// 各版本的显示路径和 LRA，按 LRA 从高到低排列，相同时按路径排列
+    pub members: Vec<(FileKey, f64)>,
+}
+
+impl VersionGroup {
+    /// 组内 LRA 差异：最大值减最小值（LU）
+    pub fn spread(&self) -> f64 {
+        match (self.members.first(), self.members.last()) {
+            (Some((_, max)), Some((_, min))) => max - min,
+            _ => 0.0,
+        }
+    }
+}
+
+/// 比较同名音轨的 LRA (Compare Versions)
+///
+/// 专辑条目（`--album-lra`）不参与比较。
+///
+/// # 参数
+/// - `entries` - 结果条目 (显示路径, LRA)
+/// - `normalization` - 文件名规范化规则
+///
+/// # 返回值
+/// - 成员多于一个的组，按差异从大到小排序，差异相同时按名称排序
+///
+/// # 示例
+/// ```
+/// use lra_calculator_rust::versions::{compare_versions, NameNormalization};
+///
+/// let entries = [
+///     ("CD/01 - Time.flac".into(), 12.0),
+///     ("Remaster/01 Time.flac".into(), 6.5),
+///     ("CD/02 - Money.flac".into(), 9.0),
+/// ];
+/// let groups = compare_versions(&entries, &NameNormalization::default());
+/// assert_eq!(groups.len(), 1);
+/// assert_eq!((groups[0].name.as_str(), groups[0].spread()), ("time", 5.5));
+/// ```
+pub fn compare_versions(entries: &[(FileKey, f64)], normalization: &NameNormalization) -> Vec<VersionGroup> {
+    let mut groups: BTreeMap<String, Vec<(FileKey, f64)>> = BTreeMap::new();
+    for (path, lra) in entries.iter().filter(|(path, _)| !is_album_entry(path)) {
+        groups.entry(normalization.normalize(path)).or_default().push((path.clone(), *lra));
+    }
+
+    let mut versions: Vec<VersionGroup> = groups
+        .into_iter()
+        .filter(|(_, members)| members.len() > 1)
+        .map(|(name, mut members)| {
+            members.sort_by(|a, b| b.1.total_cmp(&a.1).then_with(|| a.0.cmp(&b.0)));
+            VersionGroup { name, members }
+        })
+        .collect();
+    // BTreeMap 已按名称排序，稳定排序保证差异相同时仍按名称排列
+    versions.sort_by(|a, b| b.spread().total_cmp(&a.spread()));
+    versions
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// 测试各种音轨编号写法的去除，以及可以关闭的规则
+    #[test]
+    fn test_normalize() {
+        let rules = NameNormalization::default();
+        for path in [
+            "A/01 - Wish You Were Here.flac",
+            "B/01. Wish You Were Here.mp3",
+            "C/1-01 Wish You Were Here.wav",
+            "D/01_Wish You Were Here.flac",
+            "E/01 Wish  You Were Here.m4a",
+            "F/wish you were here.FLAC",
+            r"G\03) Wish You Were Here.ogg",
+        ] {
+            assert_eq!(rules.normalize(path), "wish you were here", "{}", path);
+        }
+        // 编号之后没有分隔符、或只有编号时不去除
+        assert_eq!(rules.normalize("1979.flac"), "1979");
+        assert_eq!(rules.normalize("X/01.flac"), "01");
+        assert_eq!(rules.normalize("Mr. Brightside"), "mr. brightside");
+
+        let exact = NameNormalization { strip_track_numbers: false, case_insensitive: false };
+        assert_eq!(exact.normalize("A/01 - Time.flac"), "01 - Time");
+    }
+
+    /// 测试分组、排序和专辑条目的排除
+    #[test]
+    fn test_compare_versions() {
+        let entries: Vec<(FileKey, f64)> = vec![
+            ("Original/01 - Speak to Me.flac".into(), 11.0),
+            ("Remaster/01 - Speak To Me.flac".into(), 10.5),
+            ("Original/02 - Breathe.flac".into(), 9.0),
+            ("Remaster/02 - Breathe.flac".into(), 4.0),
+            ("Deluxe/Breathe.mp3".into(), 6.0),
+            ("Original/03 - On the Run.flac".into(), 8.0),
+            ("ALBUM:Original".into(), 10.0),
+            ("ALBUM:Remaster".into(), 5.0),
+        ];
+        let groups = compare_versions(&entries, &NameNormalization::default());
+        assert_eq!(groups.len(), 2);
+        assert_eq!(groups[0].name, "breathe");
+        assert_eq!(groups[0].spread(), 5.0);
+        let paths: Vec<&str> = groups[0].members.iter().map(|(path, _)| path.as_str()).collect();
+        assert_eq!(paths, ["Original/02 - Breathe.flac", "Deluxe/Breathe.mp3", "Remaster/02 - Breathe.flac"]);
+        assert_eq!((groups[1].name.as_str(), groups[1].spread()), ("speak to me", 0.5));
+
+        // 区分大小写时 Speak to Me 与 Speak To Me 不再同组
+        let case_sensitive = NameNormalization { case_insensitive: false, ..NameNormalization::default() };
+        assert_eq!(compare_versions(&entries, &case_sensitive).len(), 1);
+    }
+}
@@ -0,0 +1,152 @@
+//! FFmpeg 警告模块 (FFmpeg Warnings Module)
+//!
+//! 容器有小问题的文件（码率估算时长、MP3 帧损坏等）在输出正常的 ebur128 汇总之前，会先在 stderr 中
+//! 输出警告。分析结果照常可用，但这些警告往往说明文件值得重新抓取，因此记录在
+//! [`FileMeasurement::warnings`](crate::processor::FileMeasurement::warnings) 中，而不是丢弃。
+//!
+//! 只有包含 [`KNOWN_WARNINGS`] 中某一条的行才算警告，未知的 stderr 输出不会被误判。
+//! 输入信息和元数据行（`Input #0, ...` 以及缩进的行）即使包含这些文字也不算，
+//! 因为文件名和标签内容是任意文本。
+
+use std::sync::LazyLock;
+
+use regex::Regex;
+
+/// 识别为警告的 FFmpeg 输出，按行内包含的文字匹配（区分大小写）
+///
+/// 新增条目时在 `tests/fixtures` 中附上实际的输出，并在测试中覆盖。
+pub const KNOWN_WARNINGS: &[&str] = &[
+    // 容器没有时长信息（常见于没有 Xing/VBRI 头的 VBR MP3）
+    "Estimating duration from bitrate",
+    // MP3 帧数据损坏或截断
+    "invalid new backstep",
+    "overread, skip",
+    "big_values too big",
+    "Header missing",
+    "bytes of junk at",
+    // 解码失败后跳过了一部分数据
+    "Error while decoding stream",
+    "Invalid data found when processing input",
+    "Packet corrupt",
+    "partial file",
+    // FLAC 帧损坏
+    "invalid sync code",
+    "frame CRC mismatch",
+    "decode_frame() failed",
+    // AAC 声道配置与数据不符
+    "is not allocated",
+    // 时间戳损坏
+    "non monotonically increasing dts",
+    "Could not update timestamps",
+];
+
+/// 每个文件最多保留的不同警告数量
+pub const MAX_WARNINGS_PER_FILE: usize = 20;
+
+/// 组件前缀中的对象地址，如 `[mp3float @ 0x5632a1e7f100]` 中的 ` @ 0x5632a1e7f100`
+static ADDRESS_PATTERN: LazyLock<Regex> =
+    LazyLock::new(|| Regex::new(r" @ 0x[0-9a-fA-F]+\]").expect("地址正则表达式无效"));
+
+/// 从 FFmpeg 输出中提取警告 (Extract FFmpeg Warnings)
+///
+/// 去掉组件前缀中的对象地址，相同的警告合并为一条并注明次数，按首次出现的顺序排列；
+/// 超过 [`MAX_WARNINGS_PER_FILE`] 种时最后一条说明省略的数量。
+///
+/// # 参数
+/// - `ffmpeg_output` - FFmpeg 的 stderr 输出
+///
+/// # 返回值
+/// - 警告列表，没有警告时为空
+///
+/// # 示例
+/// ```
+/// use lra_calculator_rust::warnings::extract_ffmpeg_warnings;
+///
+/// let output = "[mp3float @ 0x55d0] invalid new backstep -1\n\
+///               [mp3float @ 0x55d0] invalid new backstep -1\n\
+///               [Parsed_ebur128_0 @ 0x55e0] Summary:\n";
+/// assert_eq!(extract_ffmpeg_warnings(output), vec!["[mp3float] invalid new backstep -1（2 次）"]);
+/// ```
+pub fn extract_ffmpeg_warnings(ffmpeg_output: &str) -> Vec<String> {
+    let mut counted: Vec<(String, usize)> = Vec::new();
+    for line in ffmpeg_output.lines() {
+        if !is_warning_line(line) {
+            continue;
+        }
+        let message = ADDRESS_PATTERN.replace_all(line.trim_end(), "]");
+        match counted.iter_mut().find(|(existing, _)| *existing == message) {
+            Some((_, count)) => *count += 1,
+            None => counted.push((message.into_owned(), 1)),
+        }
+    }
+
+    let omitted = counted.len().saturating_sub(MAX_WARNINGS_PER_FILE);
+    let mut warnings: Vec<String> = counted
+        .into_iter()
+        .take(MAX_WARNINGS_PER_FILE)
+        .map(|(message, count)| match count {
+            1 => message,
+            count => format!("{}（{} 次）", message, count),
+        })
+        .collect();
+    if omitted > 0 {
+        warnings.push(format!("另有 {} 种警告未列出", omitted));
+    }
+    warnings
+}
+
+/// 该行是否为已知的警告：不是输入信息或元数据行，且包含 [`KNOWN_WARNINGS`] 中的一条
+fn is_warning_line(line: &str) -> bool {
+    let descriptive = line.starts_with(char::is_whitespace) || line.starts_with("Input #") || line.starts_with("Output #");
+    !descriptive && KNOWN_WARNINGS.iter().any(|known| line.contains(known))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// 测试从实际输出中提取警告，文件名和标签中的相同文字不算
+    #[test]
+    fn test_extract_from_captured_output() {
+        let output = include_str!("../tests/fixtures/ebur128_with_warnings.txt");
+        assert_eq!(
+            extract_ffmpeg_warnings(output),
+            vec![
+                "[mp3] Skipping 417 bytes of junk at 2260.",
+                "[mp3] Estimating duration from bitrate, this may be inaccurate",
+                "[mp3float] invalid new backstep -1（3 次）",
+                "[mp3float] overread, skip -5 enddists: -3 -3",
+            ]
+        );
+    }
+
+    /// 测试没有警告的正常输出和未知的 stderr 输出不会被识别为警告
+    #[test]
+    fn test_unknown_output_is_not_a_warning() {
+        for output in [
+            include_str!("../tests/fixtures/ebur128_ffmpeg4.txt"),
+            include_str!("../tests/fixtures/ebur128_ffmpeg5.txt"),
+            include_str!("../tests/fixtures/ebur128_ffmpeg6.txt"),
+            include_str!("../tests/fixtures/ebur128_ffmpeg7.txt"),
+            include_str!("../tests/fixtures/ebur128_no_range_bounds.txt"),
+            include_str!("../tests/fixtures/ebur128_truncated.txt"),
+            "[aist#0:0/mp3 @ 0x5632a1e6d840] Guessed Channel Layout: stereo\n",
+            "[mp3 @ 0x55d0] Discarding ID3 tags because more suitable tags were found.\n",
+            "something unexpected happened\n",
+        ] {
+            assert!(extract_ffmpeg_warnings(output).is_empty(), "不应识别为警告: {}", output);
+        }
+    }
+
+    /// 测试不同的警告超过上限时只保留前面的并说明省略的数量
+    #[test]
+    fn test_warning_cap() {
+        let output: String = (0..MAX_WARNINGS_PER_FILE + 3)
+            .map(|frame| format!("[flac @ 0x7f00] invalid sync code at frame {}\n", frame))
+            .collect();
+        let warnings = extract_ffmpeg_warnings(&output);
+        assert_eq!(warnings.len(), MAX_WARNINGS_PER_FILE + 1);
+        assert_eq!(warnings[0], "[flac] invalid sync code at frame 0");
+        assert_eq!(warnings[MAX_WARNINGS_PER_FILE], "另有 3 种警告未列出");
+    }
+}
@@ -4,14 +4,16 @@
 //! 这些测试模拟真实的使用场景，确保各个模块协同工作正常。
 
 use std::fs::{self, File};
-use std::path::Path;
+use std::path::{Path, PathBuf};
 use tempfile::TempDir;
 
 // 导入被测试的模块
 // 注意：Rust 中连字符会被转换为下划线
-use lra_calculator_rust::audio::{scan_audio_files, calculate_lra_direct, check_ffmpeg_availability};
-use lra_calculator_rust::processor::{process_files_parallel, analyze_results};
-use lra_calculator_rust::utils::{validate_folder_path, sort_lra_results_file};
+use lra_calculator_rust::audio::{
+    scan_audio_files, check_ffmpeg_availability, self_test, calculate_lra_with_options, CalculationOptions,
+};
+use lra_calculator_rust::processor::{process_files_parallel, analyze_results, FileMeasurement, FileOutcome};
+use lra_calculator_rust::utils::{validate_folder_path, sort_lra_results_file, FORMAT_METADATA_PREFIX, RESULTS_FORMAT_VERSION};
 use lra_calculator_rust::error::{AppError, ProcessFileError};
 
 /// 测试 FFmpeg 环境检查功能
@@ -21,8 +23,9 @@ use lra_calculator_rust::error::{AppError, ProcessFileError};
 #[test]
 fn test_ffmpeg_availability() {
     match check_ffmpeg_availability() {
-        Ok(()) => {
-            println!("✅ FFmpeg 可用，可以进行后续测试");
+        Ok(ffmpeg_path) => {
+            assert!(ffmpeg_path.is_absolute(), "PATH 中的 FFmpeg 应该解析为绝对路径: {}", ffmpeg_path.display());
+            println!("✅ FFmpeg 可用 ({})，可以进行后续测试", ffmpeg_path.display());
         }
         Err(e) => {
             panic!("❌ FFmpeg 不可用，无法进行测试: {}", e);
@@ -30,6 +33,18 @@ fn test_ffmpeg_availability() {
     }
 }
 
+/// 测试安装自检
+///
+/// 生成测试音频并完整分析一遍，验证能得到有限的 LRA 值。
+#[test]
+#[ignore] // 默认忽略，需要支持 lavfi 的真实 FFmpeg
+fn test_self_test_pipeline() {
+    match self_test() {
+        Ok(lra) => assert!(lra.is_finite()),
+        Err(e) => panic!("❌ 自检失败: {}", e),
+    }
+}
+
 /// 测试文件夹路径验证功能
 /// 
 /// 验证路径验证函数能够正确处理各种路径情况。
@@ -133,13 +148,13 @@ fn test_file_exclusion() {
 fn test_result_analysis() {
     // 创建模拟的处理结果
     let mock_results = vec![
-        Ok(("file1.mp3".to_string(), 12.5)),
-        Ok(("file2.wav".to_string(), 8.3)),
+        Ok(FileMeasurement::new("file1.mp3", 12.5)),
+        Ok(FileMeasurement::new("file2.wav", 8.3)),
         Err(ProcessFileError::ffmpeg_error(
             "file3.flac".to_string(),
             "模拟的 FFmpeg 错误".to_string()
         )),
-        Ok(("file4.m4a".to_string(), 15.7)),
+        Ok(FileMeasurement::new("file4.m4a", 15.7)),
         Err(ProcessFileError::lra_parsing_error(
             "file5.mp3".to_string(),
             "模拟的解析错误".to_string()
@@ -152,16 +167,17 @@ fn test_result_analysis() {
     // 验证统计信息
     assert_eq!(stats.successful, 3);
     assert_eq!(stats.failed, 2);
-    assert_eq!(stats.error_messages.len(), 2);
+    assert_eq!(stats.errors.len(), 2);
     
     // 验证成功结果
     assert_eq!(successful_results.len(), 3);
-    assert_eq!(successful_results[0].0, "file1.mp3");
-    assert_eq!(successful_results[0].1, 12.5);
+    assert_eq!(successful_results[0].path, "file1.mp3");
+    assert_eq!(successful_results[0].lra, 12.5);
     
     // 验证错误信息包含预期内容
-    assert!(stats.error_messages.iter().any(|msg| msg.contains("file3.flac")));
-    assert!(stats.error_messages.iter().any(|msg| msg.contains("file5.mp3")));
+    let error_messages = stats.error_messages();
+    assert!(error_messages.iter().any(|msg| msg.contains("file3.flac")));
+    assert!(error_messages.iter().any(|msg| msg.contains("file5.mp3")));
 }
 
 /// 测试结果文件排序功能
@@ -191,13 +207,14 @@ file5.ogg - 5.3"#;
     let lines: Vec<&str> = sorted_content.lines().collect();
     
     // 验证排序结果（应该按 LRA 值降序排列）
-    assert_eq!(lines.len(), 6); // 包括表头
+    assert_eq!(lines.len(), 7); // 包括表头和格式标记
     assert_eq!(lines[0], header_line);
-    assert!(lines[1].contains("file4.m4a - 20.0"));
-    assert!(lines[2].contains("file2.wav - 15.2"));
-    assert!(lines[3].contains("file3.flac - 12.1"));
-    assert!(lines[4].contains("file1.mp3 - 8.5"));
-    assert!(lines[5].contains("file5.ogg - 5.3"));
+    assert_eq!(lines[1], format!("{}{}", FORMAT_METADATA_PREFIX, RESULTS_FORMAT_VERSION));
+    assert!(lines[2].contains("file4.m4a - 20.0"));
+    assert!(lines[3].contains("file2.wav - 15.2"));
+    assert!(lines[4].contains("file3.flac - 12.1"));
+    assert!(lines[5].contains("file1.mp3 - 8.5"));
+    assert!(lines[6].contains("file5.ogg - 5.3"));
 }
 
 /// 测试空结果文件的处理
@@ -216,9 +233,10 @@ fn test_empty_result_file_handling() {
     let result = sort_lra_results_file(&results_file, header_line);
     assert!(result.is_ok());
     
-    // 验证文件内容保持不变
+    // 验证文件只有表头和格式标记
     let content = fs::read_to_string(&results_file).expect("无法读取文件");
-    assert_eq!(content.trim(), header_line);
+    assert_eq!(content.lines().next(), Some(header_line));
+    assert_eq!(content.lines().count(), 2);
 }
 
 /// 测试错误处理的健壮性
@@ -231,19 +249,19 @@ fn test_error_handling_robustness() {
     let validation_result = validate_folder_path(non_existent);
     assert!(validation_result.is_err());
     
-    if let Err(AppError::Path(msg)) = validation_result {
-        assert!(msg.contains("不存在"));
+    if let Err(AppError::Path { message, .. }) = validation_result {
+        assert!(message.contains("不存在"));
     } else {
         panic!("期望得到 AppError::Path 错误");
     }
     
     // 测试空文件列表的并行处理
-    let empty_files = vec![];
+    let empty_files: Vec<(PathBuf, String)> = vec![];
     let empty_results = process_files_parallel(empty_files);
     assert!(empty_results.is_empty());
     
     // 测试空结果的分析
-    let (empty_stats, empty_successful) = analyze_results(vec![]);
+    let (empty_stats, empty_successful) = analyze_results(Vec::<FileOutcome>::new());
     assert_eq!(empty_stats.successful, 0);
     assert_eq!(empty_stats.failed, 0);
     assert!(empty_successful.is_empty());
@@ -277,3 +295,314 @@ fn test_performance_with_many_files() {
     // 验证扫描时间在合理范围内（应该在几毫秒内完成）
     assert!(scan_duration.as_millis() < 1000, "文件扫描耗时过长");
 }
+
+/// 测试带大幅封面的文件
+///
+/// 生成一段带 3000x3000 封面的 FLAC，分别用默认命令（只分析第一条音频流）
+/// 和 `keep_all_streams` 分析：两者的 LRA 应当一致，并打印耗时用于比较。
+/// 需要本机安装支持 lavfi 的真实 FFmpeg。
+#[test]
+#[ignore] // 默认忽略，需要真实的 FFmpeg
+fn test_cover_art_is_skipped() {
+    let temp_dir = TempDir::new().expect("无法创建临时目录");
+    let cover = temp_dir.path().join("cover.png");
+    let tagged = temp_dir.path().join("tagged.flac");
+
+    let run = |args: &[&str]| {
+        let status = std::process::Command::new("ffmpeg")
+            .args(["-hide_banner", "-loglevel", "error", "-y"])
+            .args(args)
+            .status()
+            .expect("无法执行 ffmpeg");
+        assert!(status.success(), "ffmpeg 执行失败: {:?}", args);
+    };
+    run(&["-f", "lavfi", "-i", "testsrc=size=3000x3000", "-frames:v", "1", cover.to_str().unwrap()]);
+    run(&[
+        "-f", "lavfi", "-i", "sine=frequency=440:duration=30", "-i", cover.to_str().unwrap(),
+        "-map", "0:a", "-map", "1:v", "-c:a", "flac", "-c:v", "png",
+        "-disposition:v", "attached_pic", tagged.to_str().unwrap(),
+    ]);
+
+    let measure = |options: &CalculationOptions| {
+        let start = std::time::Instant::now();
+        let lra = calculate_lra_with_options(&tagged, "tagged.flac", options).expect("分析失败");
+        (lra, start.elapsed())
+    };
+    let (skipped_lra, skipped_time) = measure(&CalculationOptions::default());
+    let (all_lra, all_time) = measure(&CalculationOptions { keep_all_streams: true, ..Default::default() });
+
+    println!("只分析音频流: {:?}，自动选择流: {:?}", skipped_time, all_time);
+    assert_eq!(skipped_lra, all_lra);
+}
+
+/// 模拟 FFmpeg 的脚本：`-version` 和 `-filters` 输出环境检查需要的内容，
+/// 分析时忽略输入，立即输出一份固定的 ebur128 汇总
+#[cfg(all(feature = "cli", unix))]
+const FAKE_FFMPEG_SCRIPT: &str = r#"#!/bin/sh
+case "$*" in
+  *-version*) echo 'ffmpeg version 6.0-fake'; exit 0 ;;
+  *-filters*) echo ' ... ebur128           A->N       EBU R128 scanner.'; exit 0 ;;
+esac
+printf '%s\n' \
+  '[Parsed_ebur128_0 @ 0x1] Summary:' \
+  '' \
+  '  Integrated loudness:' \
+  '    I:         -14.2 LUFS' \
+  '    Threshold: -24.5 LUFS' \
+  '' \
+  '  Loudness range:' \
+  '    LRA:         7.3 LU' \
+  '    Threshold: -34.6 LUFS' \
+  '    LRA low:   -19.8 LUFS' \
+  '    LRA high:  -12.5 LUFS' >&2
+"#;
+
+/// 放在临时目录中的模拟 FFmpeg
+///
+/// 命令行测试不依赖系统中安装的 FFmpeg：[`FakeFfmpeg::cli_command`] 创建的命令
+/// 把临时目录加到子进程 `PATH` 的最前面（与 `benches/lra_benchmark.rs` 的调度开销基准相同），
+/// 不修改测试进程自己的环境变量。
+#[cfg(all(feature = "cli", unix))]
+struct FakeFfmpeg {
+    bin_dir: TempDir,
+}
+
+#[cfg(all(feature = "cli", unix))]
+impl FakeFfmpeg {
+    fn new() -> Self {
+        use std::os::unix::fs::PermissionsExt;
+
+        let bin_dir = TempDir::new().expect("无法创建临时目录");
+        let script = bin_dir.path().join("ffmpeg");
+        fs::write(&script, FAKE_FFMPEG_SCRIPT).expect("无法写入模拟 FFmpeg");
+        fs::set_permissions(&script, fs::Permissions::from_mode(0o755)).expect("无法设置执行权限");
+        Self { bin_dir }
+    }
+
+    /// 运行本程序的命令，`PATH` 中优先找到模拟的 FFmpeg
+    fn cli_command(&self) -> std::process::Command {
+        let original_path = std::env::var_os("PATH").unwrap_or_default();
+        let search_path = std::env::join_paths(
+            std::iter::once(self.bin_dir.path().to_path_buf()).chain(std::env::split_paths(&original_path)),
+        )
+        .expect("无法构建 PATH");
+        let mut command = std::process::Command::new(env!("CARGO_BIN_EXE_LRA-Calculator-Rust"));
+        command.env("PATH", search_path);
+        command
+    }
+}
+
+/// 写入一个非空的占位音频文件；模拟的 FFmpeg 不读取内容
+#[cfg(all(feature = "cli", unix))]
+fn write_dummy_audio(path: &Path) {
+    fs::write(path, [0u8; 1024]).expect("无法创建测试文件");
+}
+
+/// 测试固定时间下的命令行输出
+///
+/// 以 `LRA_FAKE_TIME` 固定时间、`TZ=UTC` 运行真实程序，结果文件的元数据头部应当与
+/// `tests/fixtures/golden_results_header.txt` 逐字一致；输出不是终端，不显示横幅，只以结果文件位置结尾。
+/// 同一时间和运行 ID 的完成横幅应当与 `golden_footer.txt` 一致。分析由 [`FakeFfmpeg`] 完成。
+#[cfg(all(feature = "cli", unix))]
+#[test]
+fn test_golden_cli_output() {
+    use chrono::{TimeZone, Utc};
+    use lra_calculator_rust::banner::{completion_banner, lra_advice};
+    use lra_calculator_rust::console::{Charset, ColorMode};
+    use lra_calculator_rust::run_id::RunId;
+
+    let temp_dir = TempDir::new().expect("无法创建临时目录");
+    let ffmpeg = FakeFfmpeg::new();
+    write_dummy_audio(&temp_dir.path().join("tone.wav"));
+
+    let output = ffmpeg.cli_command()
+        .arg(temp_dir.path())
+        .args(["--no-cache", "--no-history", "--unicode"])
+        .env("LRA_FAKE_TIME", "1717255812")
+        .env("TZ", "UTC")
+        .env("NO_COLOR", "1")
+        .output()
+        .expect("无法运行程序");
+    assert!(output.status.success(), "程序运行失败: {}", String::from_utf8_lossy(&output.stderr));
+
+    let results = fs::read_to_string(temp_dir.path().join("lra_results.txt")).expect("无法读取结果文件");
+    let header: String = results.lines().take(3).map(|line| format!("{}\n", line)).collect();
+    assert_eq!(header, include_str!("fixtures/golden_results_header.txt"));
+
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    let root = temp_dir.path().canonicalize().expect("无法解析临时目录");
+    let stdout = stdout.replace(&*root.to_string_lossy(), "<ROOT>");
+    assert!(!stdout.contains("🎵 ===") && !stdout.contains("🎉 ==="), "输出不是终端时不应显示横幅");
+    assert!(stdout.ends_with("\n📄 结果文件位置: <ROOT>/lra_results.txt\n"));
+
+    let run_id = RunId::parse("20240601-153012-0000").unwrap();
+    let finished = Utc.timestamp_opt(1717255812, 0).unwrap();
+    let footer = completion_banner(
+        "📄 结果文件位置: <ROOT>/lra_results.txt",
+        "LRA 值从高到低",
+        &run_id,
+        finished,
+        &lra_advice(None, ColorMode::Plain, Charset::Unicode),
+        ColorMode::Plain,
+        Charset::Unicode,
+    );
+    assert_eq!(format!("{}\n", footer.trim_start()), include_str!("fixtures/golden_footer.txt"));
+}
+
+/// 测试生成的文件不会被之后的运行当作音频
+///
+/// 两次运行使用按时间命名、扩展名为 `.wav` 的结果文件，调试输出目录也放在扫描根目录下；
+/// 第二次运行应当通过生成文件清单跳过第一次的结果文件，处理的文件数与第一次相同。
+#[cfg(all(feature = "cli", unix))]
+#[test]
+fn test_generated_artifacts_are_not_rescanned() {
+    let temp_dir = TempDir::new().expect("无法创建临时目录");
+    let ffmpeg = FakeFfmpeg::new();
+    write_dummy_audio(&temp_dir.path().join("tone.wav"));
+
+    let entry_counts: Vec<usize> = ["1717255812", "1717255900"]
+        .into_iter()
+        .map(|fake_time| {
+            let output = ffmpeg.cli_command()
+                .arg(temp_dir.path())
+                .args(["--output-name", "mix_{time}.wav", "--no-cache", "--no-history", "--debug-dump"])
+                .arg(temp_dir.path().join("dump"))
+                .env("LRA_FAKE_TIME", fake_time)
+                .env("TZ", "UTC")
+                .output()
+                .expect("无法运行程序");
+            assert!(output.status.success(), "程序运行失败: {}", String::from_utf8_lossy(&output.stderr));
+            let stdout = String::from_utf8_lossy(&output.stdout);
+            let results_path = stdout
+                .lines()
+                .find_map(|line| line.strip_prefix("📄 结果文件位置: "))
+                .expect("没有结果文件位置");
+            let results = fs::read_to_string(results_path.trim()).expect("无法读取结果文件");
+            // 跳过表头、元数据行和页脚
+            results.lines().skip(1).filter(|line| !line.starts_with('#') && !line.trim().is_empty()).count()
+        })
+        .collect();
+    assert_eq!(entry_counts[0], 1);
+    assert_eq!(entry_counts[1], entry_counts[0], "第二次运行扫描到了第一次生成的文件");
+}
+
+/// 测试以错误退出的运行同样记录生成文件：阈值违规时结果文件和失败记录都写入清单
+#[cfg(all(feature = "cli", unix))]
+#[test]
+fn test_artifacts_recorded_on_failed_run() {
+    use lra_calculator_rust::exclude::ARTIFACT_MANIFEST_FILE_NAME;
+
+    let temp_dir = TempDir::new().expect("无法创建临时目录");
+    let ffmpeg = FakeFfmpeg::new();
+    write_dummy_audio(&temp_dir.path().join("tone.wav"));
+
+    let output = ffmpeg.cli_command()
+        .arg(temp_dir.path())
+        .args(["--output-name", "mix.wav", "--no-cache", "--no-history", "--fail-if-lra-below", "100"])
+        .output()
+        .expect("无法运行程序");
+    assert!(!output.status.success(), "阈值违规时应当以错误退出");
+
+    let manifest = fs::read_to_string(temp_dir.path().join(ARTIFACT_MANIFEST_FILE_NAME)).expect("没有生成文件清单");
+    let recorded: Vec<&str> = manifest.lines().filter(|line| !line.starts_with('#')).collect();
+    assert!(recorded.contains(&"mix.wav"), "清单: {}", manifest);
+    assert!(recorded.contains(&"mix_failures.txt"), "清单: {}", manifest);
+    assert!(!recorded.iter().any(|line| line.ends_with(".lock")), "锁文件不应记录: {}", manifest);
+}
+
+/// 运行程序分析 `folder`，返回退出码和 stdout
+#[cfg(all(feature = "cli", unix))]
+fn run_cli_on(folder: &Path) -> (Option<i32>, String) {
+    let output = FakeFfmpeg::new()
+        .cli_command()
+        .arg(folder)
+        .args(["--no-cache", "--no-history"])
+        .env("NO_COLOR", "1")
+        .output()
+        .expect("无法运行程序");
+    (output.status.code(), String::from_utf8_lossy(&output.stdout).into_owned())
+}
+
+/// 测试空目录：不是失败，输出成功信息，以"目录为空"的退出码退出，仍然创建只有表头的结果文件
+#[cfg(all(feature = "cli", unix))]
+#[test]
+fn test_empty_directory_is_nothing_to_do() {
+    let temp_dir = TempDir::new().expect("无法创建临时目录");
+    let (code, stdout) = run_cli_on(temp_dir.path());
+
+    assert_eq!(code, Some(7));
+    assert!(stdout.contains("✅") && stdout.contains("没有需要处理的音频文件: 目录为空"), "输出: {}", stdout);
+    assert!(!stdout.contains("❌"));
+    let results = fs::read_to_string(temp_dir.path().join("lra_results.txt")).expect("应该创建结果文件");
+    assert!(results.starts_with("文件路径 (相对)"));
+
+    // 再次运行时目录中只有上次生成的文件：不再报告"目录为空"，退出码不变
+    let (code, stdout) = run_cli_on(temp_dir.path());
+    assert_eq!(code, Some(7));
+    assert!(stdout.contains("目录中只有本程序生成的") && stdout.contains("已忽略"), "输出: {}", stdout);
+    assert!(!stdout.contains("目录为空"));
+}
+
+/// 测试名称中含有字面 `%…%` 和 `$` 的文件夹能被正常分析，不会被当作环境变量引用
+#[cfg(all(feature = "cli", unix))]
+#[test]
+fn test_literal_percent_and_dollar_folders_are_analyzed() {
+    let temp_dir = TempDir::new().expect("无法创建临时目录");
+    for name in ["50%off%sale", "$ecret"] {
+        let folder = temp_dir.path().join(name);
+        fs::create_dir(&folder).expect("无法创建测试目录");
+        let (code, stdout) = run_cli_on(&folder);
+
+        assert_eq!(code, Some(7), "文件夹 '{}' 的输出: {}", name, stdout);
+        assert!(!stdout.contains("环境变量"), "输出: {}", stdout);
+        assert!(folder.join("lra_results.txt").exists());
+    }
+}
+
+/// 测试只有不支持的文件的目录：与空目录的提示和退出码不同
+#[cfg(all(feature = "cli", unix))]
+#[test]
+fn test_unsupported_files_only_is_nothing_to_do() {
+    let temp_dir = TempDir::new().expect("无法创建临时目录");
+    File::create(temp_dir.path().join("notes.txt")).expect("无法创建测试文件");
+    File::create(temp_dir.path().join("cover.jpg")).expect("无法创建测试文件");
+    let (code, stdout) = run_cli_on(temp_dir.path());
+
+    assert_eq!(code, Some(9));
+    assert!(stdout.contains("目录中有 2 个文件，但都不是支持的音频格式"), "输出: {}", stdout);
+    assert!(!stdout.contains("目录为空"));
+}
+
+/// 测试增量更新拒绝 `--replaygain`：专辑增益无法只用变化的文件重新计算，
+/// 程序以配置错误退出，不改动已有的结果文件
+#[cfg(all(feature = "cli", unix))]
+#[test]
+fn test_incremental_run_rejects_replaygain() {
+    let temp_dir = TempDir::new().expect("无法创建临时目录");
+    let ffmpeg = FakeFfmpeg::new();
+    write_dummy_audio(&temp_dir.path().join("a.wav"));
+    write_dummy_audio(&temp_dir.path().join("b.wav"));
+
+    let run = |extra: &[&str]| {
+        ffmpeg
+            .cli_command()
+            .arg(temp_dir.path())
+            .args(["--no-cache", "--no-history", "--replaygain"])
+            .args(extra)
+            .env("NO_COLOR", "1")
+            .output()
+            .expect("无法运行程序")
+    };
+    let full = run(&[]);
+    assert!(full.status.success(), "程序运行失败: {}", String::from_utf8_lossy(&full.stderr));
+    let results_path = temp_dir.path().join("lra_results.txt");
+    let before = fs::read_to_string(&results_path).expect("无法读取结果文件");
+    assert!(before.contains("ReplayGain 专辑增益"));
+
+    write_dummy_audio(&temp_dir.path().join("c.wav"));
+    let incremental = run(&["--newer-than-results"]);
+    assert_eq!(incremental.status.code(), Some(2));
+    let stderr = String::from_utf8_lossy(&incremental.stderr);
+    assert!(stderr.contains("--replaygain 不能与 --newer-than"), "输出: {}", stderr);
+    assert_eq!(fs::read_to_string(&results_path).expect("无法读取结果文件"), before);
+}